@@ -15,9 +15,14 @@
  * limitations under the License.
  */
 
+use std::sync::Arc;
+
 use rocketmq_common::common::broker::broker_config::BrokerConfig;
 use rocketmq_common::common::server::config::ServerConfig;
 use rocketmq_store::config::message_store_config::MessageStoreConfig;
+use rocketmq_store::plugin::message_store_plugin::MessageStorePlugin;
+use rocketmq_store::plugin::message_store_plugin::MessageStorePluginContext;
+use rocketmq_store::plugin::message_store_plugin::MessageStorePluginRegistry;
 use tracing::error;
 
 use crate::broker_runtime::BrokerRuntime;
@@ -48,6 +53,7 @@ pub struct Builder {
     broker_config: BrokerConfig,
     message_store_config: MessageStoreConfig,
     server_config: ServerConfig,
+    message_store_plugin_registry: MessageStorePluginRegistry,
 }
 
 impl Builder {
@@ -56,6 +62,7 @@ impl Builder {
             broker_config: Default::default(),
             message_store_config: MessageStoreConfig::default(),
             server_config: Default::default(),
+            message_store_plugin_registry: MessageStorePluginRegistry::new(),
         }
     }
 
@@ -74,14 +81,24 @@ impl Builder {
         self
     }
 
+    /// Makes the message store plugin built by `factory` available under `name`, the plugins
+    /// named in `messageStorePlugIn` wrap the message store.
+    pub fn register_message_store_plugin<F>(mut self, name: impl Into<String>, factory: F) -> Self
+    where
+        F: Fn(&MessageStorePluginContext) -> Arc<dyn MessageStorePlugin> + Send + Sync + 'static,
+    {
+        self.message_store_plugin_registry.register(name, factory);
+        self
+    }
+
     pub fn build(self) -> BrokerBootstrap {
-        BrokerBootstrap {
-            broker_runtime: BrokerRuntime::new(
-                self.broker_config,
-                self.message_store_config,
-                self.server_config,
-            ),
-        }
+        let mut broker_runtime = BrokerRuntime::new(
+            self.broker_config,
+            self.message_store_config,
+            self.server_config,
+        );
+        broker_runtime.set_message_store_plugin_registry(self.message_store_plugin_registry);
+        BrokerBootstrap { broker_runtime }
     }
 }
 
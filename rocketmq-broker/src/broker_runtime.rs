@@ -27,6 +27,7 @@ use rocketmq_common::common::config_manager::ConfigManager;
 use rocketmq_common::common::constant::PermName;
 use rocketmq_common::common::server::config::ServerConfig;
 use rocketmq_common::common::statistics::state_getter::StateGetter;
+use rocketmq_common::utils::network_util::NetworkUtil;
 use rocketmq_common::ArcRefCellWrapper;
 use rocketmq_common::TimeUtils::get_current_millis;
 use rocketmq_common::UtilAll::compute_next_morning_time_millis;
@@ -39,12 +40,16 @@ use rocketmq_remoting::remoting_server::server::RocketMQServer;
 use rocketmq_remoting::runtime::config::client_config::TokioClientConfig;
 use rocketmq_runtime::RocketMQRuntime;
 use rocketmq_store::base::store_enum::StoreType;
+use rocketmq_store::config::broker_role::BrokerRole;
 use rocketmq_store::config::message_store_config::MessageStoreConfig;
 use rocketmq_store::log_file::MessageStore;
 use rocketmq_store::message_store::default_message_store::DefaultMessageStore;
+use rocketmq_store::plugin::message_store_plugin::MessageStorePluginContext;
+use rocketmq_store::plugin::message_store_plugin::MessageStorePluginRegistry;
 use rocketmq_store::stats::broker_stats::BrokerStats;
 use rocketmq_store::stats::broker_stats_manager::BrokerStatsManager;
 use rocketmq_store::timer::timer_message_store::TimerMessageStore;
+use tracing::error;
 use tracing::info;
 use tracing::warn;
 
@@ -53,15 +58,23 @@ use crate::client::default_consumer_ids_change_listener::DefaultConsumerIdsChang
 use crate::client::manager::consumer_manager::ConsumerManager;
 use crate::client::manager::producer_manager::ProducerManager;
 use crate::client::rebalance::rebalance_lock_manager::RebalanceLockManager;
+use crate::coldctr::cold_data_cg_ctr_service::ColdDataCgCtrService;
+use crate::filter::commit_log_dispatcher_calc_bit_map::CommitLogDispatcherCalcBitMap;
 use crate::filter::manager::consumer_filter_manager::ConsumerFilterManager;
 use crate::hook::batch_check_before_put_message::BatchCheckBeforePutMessageHook;
 use crate::hook::check_before_put_message::CheckBeforePutMessageHook;
+use crate::hook::handle_schedule_message::HandleScheduleMessageHook;
 use crate::long_polling::long_polling_service::pull_request_hold_service::PullRequestHoldService;
 use crate::long_polling::notify_message_arriving_listener::NotifyMessageArrivingListener;
+use crate::metadata_bootstrap;
+use crate::mqtrace::commercial_stats_hook::CommercialConsumeMessageHook;
+use crate::mqtrace::commercial_stats_hook::CommercialSendMessageHook;
+use crate::mqtrace::consume_message_hook::ConsumeMessageHook;
 use crate::offset::manager::broadcast_offset_manager::BroadcastOffsetManager;
 use crate::offset::manager::consumer_offset_manager::ConsumerOffsetManager;
 use crate::offset::manager::consumer_order_info_manager::ConsumerOrderInfoManager;
 use crate::out_api::broker_outer_api::BrokerOuterAPI;
+use crate::out_api::registered_topic_configs::RegisteredTopicConfigs;
 use crate::processor::admin_broker_processor::AdminBrokerProcessor;
 use crate::processor::client_manage_processor::ClientManageProcessor;
 use crate::processor::consumer_manage_processor::ConsumerManageProcessor;
@@ -73,6 +86,7 @@ use crate::processor::reply_message_processor::ReplyMessageProcessor;
 use crate::processor::send_message_processor::SendMessageProcessor;
 use crate::processor::BrokerRequestProcessor;
 use crate::schedule::schedule_message_service::ScheduleMessageService;
+use crate::slave::slave_synchronize::SlaveSynchronize;
 use crate::subscription::manager::subscription_group_manager::SubscriptionGroupManager;
 use crate::topic::manager::topic_config_manager::TopicConfigManager;
 use crate::topic::manager::topic_queue_mapping_manager::TopicQueueMappingManager;
@@ -89,6 +103,7 @@ pub(crate) struct BrokerRuntime {
     subscription_group_manager: Arc<SubscriptionGroupManager<DefaultMessageStore>>,
     consumer_filter_manager: Arc<ConsumerFilterManager>,
     consumer_order_info_manager: Arc<ConsumerOrderInfoManager>,
+    cold_data_cg_ctr_service: Arc<ColdDataCgCtrService>,
     #[cfg(feature = "local_file_store")]
     message_store: Option<DefaultMessageStore>,
     #[cfg(feature = "local_file_store")]
@@ -98,6 +113,7 @@ pub(crate) struct BrokerRuntime {
     timer_message_store: Option<TimerMessageStore>,
 
     broker_out_api: Arc<BrokerOuterAPI>,
+    registered_topic_configs: Arc<RegisteredTopicConfigs>,
 
     broker_runtime: Option<RocketMQRuntime>,
     producer_manager: Arc<ProducerManager>,
@@ -114,6 +130,8 @@ pub(crate) struct BrokerRuntime {
     #[cfg(feature = "local_file_store")]
     pull_request_hold_service: Option<PullRequestHoldService<DefaultMessageStore>>,
     rebalance_lock_manager: Arc<RebalanceLockManager>,
+    slave_synchronize: Option<SlaveSynchronize>,
+    message_store_plugin_registry: MessageStorePluginRegistry,
 }
 
 impl Clone for BrokerRuntime {
@@ -126,13 +144,15 @@ impl Clone for BrokerRuntime {
             topic_queue_mapping_manager: self.topic_queue_mapping_manager.clone(),
             consumer_offset_manager: self.consumer_offset_manager.clone(),
             subscription_group_manager: self.subscription_group_manager.clone(),
-            consumer_filter_manager: Arc::new(Default::default()),
+            consumer_filter_manager: self.consumer_filter_manager.clone(),
             consumer_order_info_manager: Arc::new(Default::default()),
+            cold_data_cg_ctr_service: self.cold_data_cg_ctr_service.clone(),
             message_store: self.message_store.clone(),
             broker_stats: self.broker_stats.clone(),
             schedule_message_service: self.schedule_message_service.clone(),
             timer_message_store: self.timer_message_store.clone(),
             broker_out_api: self.broker_out_api.clone(),
+            registered_topic_configs: self.registered_topic_configs.clone(),
             broker_runtime: None,
             producer_manager: self.producer_manager.clone(),
             consumer_manager: self.consumer_manager.clone(),
@@ -147,6 +167,8 @@ impl Clone for BrokerRuntime {
             is_isolated: self.is_isolated.clone(),
             pull_request_hold_service: self.pull_request_hold_service.clone(),
             rebalance_lock_manager: self.rebalance_lock_manager.clone(),
+            slave_synchronize: self.slave_synchronize.clone(),
+            message_store_plugin_registry: self.message_store_plugin_registry.clone(),
         }
     }
 }
@@ -163,6 +185,8 @@ impl BrokerRuntime {
             Arc::new(BrokerOuterAPI::new(Arc::new(TokioClientConfig::default())));
         let server_config = Arc::new(server_config);
         let message_store_config = Arc::new(message_store_config);
+        let schedule_message_service =
+            ScheduleMessageService::new(broker_config.clone(), &message_store_config);
         let topic_queue_mapping_manager =
             Arc::new(TopicQueueMappingManager::new(broker_config.clone()));
         let broker_runtime_inner = Arc::new(BrokerRuntimeInner {
@@ -176,8 +200,12 @@ impl BrokerRuntime {
             TopicConfigManager::new(broker_config.clone(), broker_runtime_inner);
         let mut stats_manager = BrokerStatsManager::new(broker_config.clone());
         let producer_manager = Arc::new(ProducerManager::new());
+        let consumer_filter_manager = Arc::new(ConsumerFilterManager::new(broker_config.clone()));
         let consumer_manager = Arc::new(ConsumerManager::new_with_broker_stats(
-            Box::new(DefaultConsumerIdsChangeListener {}),
+            Box::new(DefaultConsumerIdsChangeListener::new(
+                broker_config.clone(),
+                consumer_filter_manager.clone(),
+            )),
             broker_config.clone(),
         ));
         stats_manager.set_producer_state_getter(Arc::new(ProducerStateGetter {
@@ -190,6 +218,10 @@ impl BrokerRuntime {
         }));
         let broker_stats_manager = Arc::new(stats_manager);
         consumer_manager.set_broker_stats_manager(Some(Arc::downgrade(&broker_stats_manager)));
+        let cold_data_cg_ctr_service = Arc::new(ColdDataCgCtrService::new(
+            broker_config.clone(),
+            message_store_config.clone(),
+        ));
         Self {
             broker_config: broker_config.clone(),
             message_store_config,
@@ -201,13 +233,15 @@ impl BrokerRuntime {
                 broker_config.clone(),
                 None,
             )),
-            consumer_filter_manager: Arc::new(Default::default()),
+            consumer_filter_manager,
             consumer_order_info_manager: Arc::new(Default::default()),
+            cold_data_cg_ctr_service,
             message_store: None,
             broker_stats: None,
-            schedule_message_service: Default::default(),
+            schedule_message_service,
             timer_message_store: None,
             broker_out_api: broker_outer_api,
+            registered_topic_configs: Arc::new(RegisteredTopicConfigs::default()),
             broker_runtime: Some(runtime),
             producer_manager,
             consumer_manager,
@@ -222,9 +256,18 @@ impl BrokerRuntime {
             is_isolated: Arc::new(AtomicBool::new(false)),
             pull_request_hold_service: None,
             rebalance_lock_manager: Arc::new(Default::default()),
+            slave_synchronize: None,
+            message_store_plugin_registry: MessageStorePluginRegistry::default(),
         }
     }
 
+    pub(crate) fn set_message_store_plugin_registry(
+        &mut self,
+        message_store_plugin_registry: MessageStorePluginRegistry,
+    ) {
+        self.message_store_plugin_registry = message_store_plugin_registry;
+    }
+
     pub(crate) fn broker_config(&self) -> &BrokerConfig {
         &self.broker_config
     }
@@ -235,6 +278,7 @@ impl BrokerRuntime {
 
     pub fn shutdown(&mut self) {
         self.broker_out_api.shutdown();
+        self.schedule_message_service.stop();
         if let Some(message_store) = &mut self.message_store {
             message_store.shutdown()
         }
@@ -310,6 +354,24 @@ impl BrokerRuntime {
                 Some(self.broker_stats_manager.clone()),
                 false,
             );
+            let plugin_context = MessageStorePluginContext {
+                broker_config: self.broker_config.clone(),
+                message_store_config: self.message_store_config.clone(),
+            };
+            match self.message_store_plugin_registry.build_chain(
+                self.broker_config.message_store_plug_in.as_str(),
+                &plugin_context,
+            ) {
+                Ok(plugins) => message_store.set_plugins(plugins),
+                Err(e) => {
+                    error!("Build message store plugins failed: {}", e);
+                    return false;
+                }
+            }
+            message_store.add_first_dispatcher(Box::new(CommitLogDispatcherCalcBitMap::new(
+                self.broker_config.clone(),
+                self.consumer_filter_manager.clone(),
+            )));
             if self.message_store_config.is_timer_wheel_enable() {
                 let time_message_store = TimerMessageStore::new(Some(message_store.clone()));
                 message_store.set_timer_message_store(Arc::new(time_message_store));
@@ -345,6 +407,7 @@ impl BrokerRuntime {
             result &= self.timer_message_store.as_mut().unwrap().load();
         }
         result &= self.schedule_message_service.load();
+        result &= self.provision_metadata();
 
         if result {
             self.initialize_remoting_server();
@@ -358,6 +421,22 @@ impl BrokerRuntime {
         result
     }
 
+    /// Applies the declarative topic and subscription group bootstrap files, if any, before the
+    /// broker starts serving requests.
+    fn provision_metadata(&self) -> bool {
+        match metadata_bootstrap::provision(
+            &self.broker_config,
+            &self.topic_config_manager,
+            &self.subscription_group_manager,
+        ) {
+            Ok(_) => true,
+            Err(e) => {
+                error!("Provision metadata failed: {}", e);
+                false
+            }
+        }
+    }
+
     pub fn register_message_store_hook(&mut self) {
         if let Some(ref mut message_store) = self.message_store {
             message_store.set_put_message_hook(Box::new(CheckBeforePutMessageHook::new(
@@ -367,6 +446,12 @@ impl BrokerRuntime {
             message_store.set_put_message_hook(Box::new(BatchCheckBeforePutMessageHook::new(
                 self.topic_config_manager.topic_config_table(),
             )));
+            message_store.set_put_message_hook(Box::new(HandleScheduleMessageHook::new(
+                self.timer_message_store.clone(),
+                self.schedule_message_service.clone(),
+                self.message_store_config.clone(),
+                self.topic_config_manager.topic_config_table(),
+            )));
         }
     }
 
@@ -377,10 +462,22 @@ impl BrokerRuntime {
 
     fn initialize_resources(&mut self) {
         self.topic_queue_mapping_clean_service = Some(Arc::new(TopicQueueMappingCleanService));
+        self.slave_synchronize = Some(SlaveSynchronize::new(
+            NetworkUtil::host_port(
+                &self.broker_config.broker_ip1,
+                self.server_config.listen_port,
+            ),
+            self.broker_out_api.clone(),
+            self.topic_config_manager.clone(),
+            self.topic_queue_mapping_manager.clone(),
+            self.consumer_offset_manager.clone(),
+            self.subscription_group_manager.clone(),
+            self.schedule_message_service.clone(),
+        ));
     }
 
     fn init_processor(&mut self) -> BrokerRequestProcessor<DefaultMessageStore> {
-        let send_message_processor = SendMessageProcessor::<DefaultMessageStore>::new(
+        let mut send_message_processor = SendMessageProcessor::<DefaultMessageStore>::new(
             self.topic_queue_mapping_manager.clone(),
             self.subscription_group_manager.clone(),
             self.topic_config_manager.clone(),
@@ -389,6 +486,15 @@ impl BrokerRuntime {
             self.rebalance_lock_manager.clone(),
             self.broker_stats_manager.clone(),
         );
+        let mut consume_message_hooks: Vec<Box<dyn ConsumeMessageHook>> = Vec::new();
+        if self.broker_config.commercial_stats_enable {
+            send_message_processor.register_send_message_hook(Box::new(
+                CommercialSendMessageHook::new(self.broker_stats_manager.clone()),
+            ));
+            consume_message_hooks.push(Box::new(CommercialConsumeMessageHook::new(
+                self.broker_stats_manager.clone(),
+            )));
+        }
         let reply_message_processor = ReplyMessageProcessor::new(
             self.topic_queue_mapping_manager.clone(),
             self.subscription_group_manager.clone(),
@@ -408,7 +514,7 @@ impl BrokerRuntime {
                 self.broadcast_offset_manager.clone(),
                 self.broker_stats_manager.clone(),
                 self.broker_config.clone(),
-                Arc::new(Default::default()),
+                Arc::new(consume_message_hooks),
             )) as Box<dyn PullMessageResultHandler>);
         let message_store = Arc::new(self.message_store.as_ref().unwrap().clone());
         let pull_message_processor = PullMessageProcessor::new(
@@ -422,6 +528,7 @@ impl BrokerRuntime {
             Arc::new(self.consumer_offset_manager.clone()),
             Arc::new(BroadcastOffsetManager::default()),
             message_store.clone(),
+            self.cold_data_cg_ctr_service.clone(),
             self.broker_out_api.clone(),
         );
 
@@ -464,11 +571,13 @@ impl BrokerRuntime {
             self.message_store_config.clone(),
             self.topic_config_manager.clone(),
             self.consumer_offset_manager.clone(),
+            self.subscription_group_manager.clone(),
             self.topic_queue_mapping_manager.clone(),
             self.message_store.as_ref().unwrap().clone(),
             self.schedule_message_service.clone(),
             self.broker_stats.clone(),
             self.consumer_manager.clone(),
+            self.consumer_filter_manager.clone(),
             self.broker_out_api.clone(),
         );
 
@@ -539,6 +648,26 @@ impl BrokerRuntime {
                 }
             });
 
+        if self.message_store_config.broker_role == BrokerRole::Slave {
+            let slave_synchronize = self.slave_synchronize.clone().unwrap();
+            self.broker_runtime
+                .as_ref()
+                .unwrap()
+                .get_handle()
+                .spawn(async move {
+                    info!("Slave synchronize Start scheduled task");
+                    tokio::time::sleep(Duration::from_secs(3)).await;
+                    loop {
+                        let current_execution_time = tokio::time::Instant::now();
+                        slave_synchronize.sync_all().await;
+                        let next_execution_time = current_execution_time + Duration::from_secs(10);
+                        let delay = next_execution_time
+                            .saturating_duration_since(tokio::time::Instant::now());
+                        tokio::time::sleep(delay).await;
+                    }
+                });
+        }
+
         let consumer_filter_manager = self.consumer_filter_manager.clone();
         let consumer_order_info_manager = self.consumer_order_info_manager.clone();
         self.broker_runtime
@@ -561,6 +690,24 @@ impl BrokerRuntime {
                 }
             });
 
+        let consumer_manager = self.consumer_manager.clone();
+        self.broker_runtime
+            .as_ref()
+            .unwrap()
+            .get_handle()
+            .spawn(async move {
+                info!("Client housekeeping Start scheduled task");
+                tokio::time::sleep(Duration::from_secs(10)).await;
+                loop {
+                    let current_execution_time = tokio::time::Instant::now();
+                    consumer_manager.scan_not_active_channel();
+                    let next_execution_time = current_execution_time + Duration::from_secs(10);
+                    let delay =
+                        next_execution_time.saturating_duration_since(tokio::time::Instant::now());
+                    tokio::time::sleep(delay).await;
+                }
+            });
+
         let mut runtime = self.clone();
         self.broker_runtime
             .as_ref()
@@ -644,6 +791,8 @@ impl BrokerRuntime {
             .unwrap()
             .start()
             .expect("Message store start error");
+        self.schedule_message_service
+            .start(self.message_store.clone().unwrap());
 
         let server = RocketMQServer::new(self.server_config.clone());
         //start nomarl broker remoting_server
@@ -809,6 +958,49 @@ impl BrokerRuntime {
             );
         }
 
+        // Only register the topic configs changed since the last registration, an unchanged
+        // data version sends none and keeps the broker alive on the name servers
+        if self.broker_config.enable_incremental_registration {
+            let data_version = self.topic_config_manager.data_version().as_ref().clone();
+            if let Some(changed_topic_config_table) = self
+                .registered_topic_configs
+                .changed_since(&topic_config_table, &data_version)
+            {
+                let topic_queue_mapping_info_map = changed_topic_config_table
+                    .keys()
+                    .filter_map(|topic| {
+                        self.topic_queue_mapping_manager
+                            .get_topic_queue_mapping(topic)
+                            .map(|detail| {
+                                (
+                                    topic.clone(),
+                                    TopicQueueMappingDetail::clone_as_mapping_info(&detail),
+                                )
+                            })
+                    })
+                    .collect();
+                let topic_config_wrapper = TopicConfigAndMappingSerializeWrapper {
+                    topic_queue_mapping_info_map,
+                    topic_config_serialize_wrapper: TopicConfigSerializeWrapper {
+                        topic_config_table: changed_topic_config_table,
+                        data_version: data_version.clone(),
+                    },
+                    ..Default::default()
+                };
+                if self
+                    .do_register_broker_all(check_order_config, oneway, topic_config_wrapper, true)
+                    .await
+                {
+                    self.registered_topic_configs
+                        .record(topic_config_table, &data_version);
+                } else {
+                    self.registered_topic_configs.clear();
+                }
+                return;
+            }
+        }
+        let registered_topic_config_table = topic_config_table.clone();
+
         // Handle split registration logic
         if self.broker_config.enable_split_registration
             && topic_config_table.len() as i32 >= self.broker_config.split_registration_size
@@ -816,7 +1008,7 @@ impl BrokerRuntime {
             let topic_config_wrapper = self
                 .topic_config_manager
                 .build_serialize_wrapper(topic_config_table.clone());
-            self.do_register_broker_all(check_order_config, oneway, topic_config_wrapper)
+            self.do_register_broker_all(check_order_config, oneway, topic_config_wrapper, false)
                 .await;
             topic_config_table.clear();
         }
@@ -861,8 +1053,19 @@ impl BrokerRuntime {
                 self.broker_config.is_in_broker_container,
             )
         {
-            self.do_register_broker_all(check_order_config, oneway, topic_config_wrapper)
-                .await;
+            let data_version = topic_config_wrapper
+                .topic_config_serialize_wrapper
+                .data_version
+                .clone();
+            if self
+                .do_register_broker_all(check_order_config, oneway, topic_config_wrapper, false)
+                .await
+            {
+                self.registered_topic_configs
+                    .record(registered_topic_config_table, &data_version);
+            } else {
+                self.registered_topic_configs.clear();
+            }
         }
     }
 
@@ -877,24 +1080,28 @@ impl BrokerRuntime {
         unimplemented!()
     }
 
+    /// Returns whether every name server acknowledged the registration.
     async fn do_register_broker_all(
         &mut self,
         _check_order_config: bool,
         oneway: bool,
         topic_config_wrapper: TopicConfigAndMappingSerializeWrapper,
-    ) {
+        incremental: bool,
+    ) -> bool {
         let cluster_name = self
             .broker_config
             .broker_identity
             .broker_cluster_name
             .clone();
         let broker_name = self.broker_config.broker_identity.broker_name.clone();
-        let broker_addr = format!(
-            "{}:{}",
-            self.broker_config.broker_ip1, self.server_config.listen_port
+        let broker_addr = NetworkUtil::host_port(
+            &self.broker_config.broker_ip1,
+            self.server_config.listen_port,
         );
         let broker_id = self.broker_config.broker_identity.broker_id;
-        self.broker_out_api
+        let name_server_count = self.broker_out_api.name_server_count();
+        let register_broker_results = self
+            .broker_out_api
             .register_broker_all(
                 cluster_name,
                 broker_addr.clone(),
@@ -906,11 +1113,20 @@ impl BrokerRuntime {
                 oneway,
                 10000,
                 false,
-                false,
+                self.broker_config.compressed_register,
+                incremental,
                 None,
                 Default::default(),
             )
             .await;
+        if let Some(result) = register_broker_results.first() {
+            if let Some(slave_synchronize) = self.slave_synchronize.as_ref() {
+                slave_synchronize.set_master_addr(
+                    Some(result.master_addr.clone()).filter(|addr| !addr.is_empty()),
+                );
+            }
+        }
+        !oneway && name_server_count > 0 && register_broker_results.len() == name_server_count
     }
 }
 
@@ -990,7 +1206,7 @@ impl BrokerRuntimeInner {
             }
         }
         serialize_wrapper.topic_queue_mapping_info_map = topic_queue_mapping_info_map;
-        self.do_register_broker_all(true, false, serialize_wrapper)
+        self.do_register_broker_all(true, false, serialize_wrapper, true)
             .await;
     }
 
@@ -999,6 +1215,7 @@ impl BrokerRuntimeInner {
         _check_order_config: bool,
         oneway: bool,
         topic_config_wrapper: TopicConfigAndMappingSerializeWrapper,
+        incremental: bool,
     ) {
         let cluster_name = self
             .broker_config
@@ -1006,9 +1223,9 @@ impl BrokerRuntimeInner {
             .broker_cluster_name
             .clone();
         let broker_name = self.broker_config.broker_identity.broker_name.clone();
-        let broker_addr = format!(
-            "{}:{}",
-            self.broker_config.broker_ip1, self.server_config.listen_port
+        let broker_addr = NetworkUtil::host_port(
+            &self.broker_config.broker_ip1,
+            self.server_config.listen_port,
         );
         let broker_id = self.broker_config.broker_identity.broker_id;
         self.broker_out_api
@@ -1023,7 +1240,8 @@ impl BrokerRuntimeInner {
                 oneway,
                 10000,
                 false,
-                false,
+                self.broker_config.compressed_register,
+                incremental,
                 None,
                 Default::default(),
             )
@@ -26,6 +26,7 @@ use rocketmq_remoting::net::channel::Channel;
 use rocketmq_remoting::protocol::heartbeat::consume_type::ConsumeType;
 use rocketmq_remoting::protocol::heartbeat::message_model::MessageModel;
 use rocketmq_remoting::protocol::heartbeat::subscription_data::SubscriptionData;
+use rocketmq_remoting::protocol::DataVersion;
 use tracing::error;
 use tracing::info;
 use tracing::warn;
@@ -41,6 +42,7 @@ pub struct ConsumerGroupInfo {
     message_model: Arc<RwLock<MessageModel>>,
     consume_from_where: Arc<RwLock<ConsumeFromWhere>>,
     last_update_timestamp: Arc<Mutex<u64>>,
+    subscription_data_version: Arc<RwLock<DataVersion>>,
 }
 
 impl ConsumerGroupInfo {
@@ -58,6 +60,7 @@ impl ConsumerGroupInfo {
             message_model: Arc::new(RwLock::new(message_model)),
             consume_from_where: Arc::new(RwLock::new(consume_from_where)),
             last_update_timestamp: Arc::new(Mutex::new(get_current_millis())),
+            subscription_data_version: Arc::new(RwLock::new(DataVersion::new())),
         }
     }
 
@@ -70,6 +73,7 @@ impl ConsumerGroupInfo {
             message_model: Arc::new(RwLock::new(MessageModel::Clustering)),
             consume_from_where: Arc::new(RwLock::new(ConsumeFromWhere::ConsumeFromLastOffset)),
             last_update_timestamp: Arc::new(Mutex::new(get_current_millis())),
+            subscription_data_version: Arc::new(RwLock::new(DataVersion::new())),
         }
     }
 
@@ -195,6 +199,7 @@ impl ConsumerGroupInfo {
 
     pub fn update_subscription(&self, sub_list: &HashSet<SubscriptionData>) -> bool {
         let mut updated = false;
+        let mut version_changed = false;
         let mut topic_set: HashSet<String> = HashSet::new();
 
         let mut subscription_table = self.subscription_table.write();
@@ -208,6 +213,7 @@ impl ConsumerGroupInfo {
                         );
                     }
                     subscription_table.insert(sub.topic.clone(), sub.clone());
+                    version_changed = true;
                 }
             } else {
                 subscription_table.insert(sub.topic.clone(), sub.clone());
@@ -234,10 +240,22 @@ impl ConsumerGroupInfo {
         });
 
         *self.last_update_timestamp.lock() = self.get_last_update_timestamp();
+        if updated || version_changed {
+            self.subscription_data_version.write().next_version();
+        }
 
         updated
     }
 
+    /// Version of the subscription table, changed whenever a subscription is added, replaced by
+    /// a newer one or removed. Clients compare it across heartbeats to notice changes made by
+    /// other members of the group.
+    pub fn get_subscription_data_version(&self) -> DataVersion {
+        let mut data_version = DataVersion::new();
+        data_version.assign_new_one(&self.subscription_data_version.read());
+        data_version
+    }
+
     pub fn get_subscribe_topics(&self) -> HashSet<String> {
         let subscription_table = self.subscription_table.read();
         subscription_table.keys().cloned().collect()
@@ -403,4 +421,39 @@ mod tests {
 
         assert!(consumer_group_info.update_subscription(&sub_list));
     }
+
+    #[test]
+    fn consumer_group_info_subscription_data_version_follows_changes() {
+        let consumer_group_info = ConsumerGroupInfo::with_group_name("test_group".to_string());
+        let initial = consumer_group_info.get_subscription_data_version();
+
+        let mut sub_list = HashSet::new();
+        sub_list.insert(SubscriptionData {
+            topic: "topic".to_string(),
+            sub_string: "*".to_string(),
+            sub_version: 1,
+            ..Default::default()
+        });
+        consumer_group_info.update_subscription(&sub_list);
+        let added = consumer_group_info.get_subscription_data_version();
+        assert_eq!(added.get_counter(), initial.get_counter() + 1);
+
+        consumer_group_info.update_subscription(&sub_list);
+        assert_eq!(consumer_group_info.get_subscription_data_version(), added);
+
+        let mut newer_list = HashSet::new();
+        newer_list.insert(SubscriptionData {
+            topic: "topic".to_string(),
+            sub_string: "TagA".to_string(),
+            sub_version: 2,
+            ..Default::default()
+        });
+        consumer_group_info.update_subscription(&newer_list);
+        assert_eq!(
+            consumer_group_info
+                .get_subscription_data_version()
+                .get_counter(),
+            added.get_counter() + 1
+        );
+    }
 }
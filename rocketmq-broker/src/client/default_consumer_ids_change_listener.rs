@@ -15,15 +15,75 @@
  * limitations under the License.
  */
 use std::any::Any;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use rocketmq_common::common::broker::broker_config::BrokerConfig;
+use rocketmq_remoting::net::channel::Channel;
+use rocketmq_remoting::protocol::heartbeat::subscription_data::SubscriptionData;
 
 use crate::client::consumer_group_event::ConsumerGroupEvent;
 use crate::client::consumer_ids_change_listener::ConsumerIdsChangeListener;
+use crate::client::net::broker_to_client::Broker2Client;
+use crate::filter::manager::consumer_filter_manager::ConsumerFilterManager;
+
+pub struct DefaultConsumerIdsChangeListener {
+    broker_config: Arc<BrokerConfig>,
+    broker_to_client: Broker2Client,
+    consumer_filter_manager: Arc<ConsumerFilterManager>,
+}
 
-#[derive(Default)]
-pub struct DefaultConsumerIdsChangeListener {}
+impl DefaultConsumerIdsChangeListener {
+    pub fn new(
+        broker_config: Arc<BrokerConfig>,
+        consumer_filter_manager: Arc<ConsumerFilterManager>,
+    ) -> Self {
+        Self {
+            broker_config,
+            broker_to_client: Broker2Client,
+            consumer_filter_manager,
+        }
+    }
+}
 
 impl ConsumerIdsChangeListener for DefaultConsumerIdsChangeListener {
-    fn handle(&self, _event: ConsumerGroupEvent, _group: &str, _args: &[&dyn Any]) {}
+    fn handle(&self, event: ConsumerGroupEvent, group: &str, args: &[&dyn Any]) {
+        if let ConsumerGroupEvent::Register = event {
+            if let Some(subscriptions) = args
+                .first()
+                .and_then(|arg| arg.downcast_ref::<HashSet<SubscriptionData>>())
+            {
+                self.consumer_filter_manager
+                    .register_subscriptions(group, subscriptions);
+            }
+            return;
+        }
+        if let ConsumerGroupEvent::Unregister = event {
+            self.consumer_filter_manager.unregister(group);
+            return;
+        }
+        if let ConsumerGroupEvent::Change = event {
+            if !self.broker_config.notify_consumer_ids_changed_enable {
+                return;
+            }
+            let Some(channels) = args
+                .first()
+                .and_then(|arg| arg.downcast_ref::<Vec<Channel>>())
+            else {
+                return;
+            };
+            for channel in channels {
+                let mut channel = channel.clone();
+                let mut broker_to_client = self.broker_to_client.clone();
+                let group = group.to_string();
+                tokio::spawn(async move {
+                    broker_to_client
+                        .notify_consumer_ids_changed(&mut channel, group.as_str())
+                        .await;
+                });
+            }
+        }
+    }
 
     fn shutdown(&self) {
         todo!()
@@ -16,4 +16,5 @@
  */
 
 pub(crate) mod consumer_manager;
+pub(crate) mod consumer_offline_history;
 pub(crate) mod producer_manager;
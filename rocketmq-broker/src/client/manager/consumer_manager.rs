@@ -23,15 +23,21 @@ use std::sync::Weak;
 use parking_lot::RwLock;
 use rocketmq_common::common::broker::broker_config::BrokerConfig;
 use rocketmq_common::common::consumer::consume_from_where::ConsumeFromWhere;
+use rocketmq_common::TimeUtils::get_current_millis;
+use rocketmq_remoting::protocol::body::get_consumer_listby_group_response_body::OfflineConsumerInfo;
+use rocketmq_remoting::protocol::body::get_consumer_listby_group_response_body::OfflineReason;
 use rocketmq_remoting::protocol::heartbeat::consume_type::ConsumeType;
 use rocketmq_remoting::protocol::heartbeat::message_model::MessageModel;
 use rocketmq_remoting::protocol::heartbeat::subscription_data::SubscriptionData;
 use rocketmq_store::stats::broker_stats_manager::BrokerStatsManager;
+use tracing::info;
+use tracing::warn;
 
 use crate::client::client_channel_info::ClientChannelInfo;
 use crate::client::consumer_group_event::ConsumerGroupEvent;
 use crate::client::consumer_group_info::ConsumerGroupInfo;
 use crate::client::consumer_ids_change_listener::ConsumerIdsChangeListener;
+use crate::client::manager::consumer_offline_history::ConsumerOfflineHistory;
 
 pub struct ConsumerManager {
     consumer_table: Arc<RwLock<HashMap<String, ConsumerGroupInfo>>>,
@@ -41,6 +47,7 @@ pub struct ConsumerManager {
     broker_stats_manager: Arc<RwLock<Option<Weak<BrokerStatsManager>>>>,
     channel_expired_timeout: u64,
     subscription_expired_timeout: u64,
+    offline_history: ConsumerOfflineHistory,
 }
 
 impl ConsumerManager {
@@ -56,6 +63,7 @@ impl ConsumerManager {
             broker_stats_manager: Arc::new(Default::default()),
             channel_expired_timeout: expired_timeout,
             subscription_expired_timeout: expired_timeout,
+            offline_history: ConsumerOfflineHistory::new(1024, 1000 * 60 * 60),
         }
     }
 
@@ -71,6 +79,10 @@ impl ConsumerManager {
             broker_stats_manager: Arc::new(Default::default()),
             channel_expired_timeout: broker_config.channel_expired_timeout,
             subscription_expired_timeout: broker_config.subscription_expired_timeout,
+            offline_history: ConsumerOfflineHistory::new(
+                broker_config.consumer_offline_history_size,
+                broker_config.consumer_offline_history_retention_millis,
+            ),
         }
     }
 }
@@ -109,6 +121,15 @@ impl ConsumerManager {
         self.get_consumer_group_info_internal(group, false)
     }
 
+    pub fn find_channel(&self, group: &str, client_id: &str) -> Option<ClientChannelInfo> {
+        self.consumer_table
+            .read()
+            .get(group)
+            .and_then(|consumer_group_info| {
+                consumer_group_info.find_channel_by_client_id(client_id)
+            })
+    }
+
     pub fn get_consumer_group_info_internal(
         &self,
         group: &str,
@@ -177,6 +198,29 @@ impl ConsumerManager {
         )
     }
 
+    /// Refreshes the channel of a consumer whose heartbeat left its subscriptions out as they did
+    /// not change, keeping the registered subscriptions of the group.
+    pub fn register_consumer_without_sub(
+        &self,
+        group: &str,
+        client_channel_info: ClientChannelInfo,
+        consume_type: ConsumeType,
+        message_model: MessageModel,
+        consume_from_where: ConsumeFromWhere,
+        is_notify_consumer_ids_changed_enable: bool,
+    ) -> bool {
+        self.register_consumer_ext(
+            group,
+            client_channel_info,
+            consume_type,
+            message_model,
+            consume_from_where,
+            HashSet::new(),
+            is_notify_consumer_ids_changed_enable,
+            false,
+        )
+    }
+
     fn register_consumer_ext(
         &self,
         group: &str,
@@ -231,6 +275,119 @@ impl ConsumerManager {
         r1 || r2
     }
 
+    pub fn unregister_consumer(
+        &self,
+        group: &str,
+        client_channel_info: &ClientChannelInfo,
+        is_notify_consumer_ids_changed_enable: bool,
+    ) {
+        let mut write_guard = self.consumer_table.write();
+        let Some(consumer_group_info) = write_guard.get(group).cloned() else {
+            return;
+        };
+        let last_update_timestamp = consumer_group_info
+            .find_channel_by_channel(client_channel_info.channel())
+            .map(|info| info.last_update_timestamp());
+        let removed = consumer_group_info.unregister_channel(client_channel_info);
+        if let Some(last_update_timestamp) = last_update_timestamp {
+            self.record_offline(
+                group,
+                client_channel_info,
+                last_update_timestamp,
+                OfflineReason::Unregister,
+            );
+            self.call_consumer_ids_change_listener(
+                ConsumerGroupEvent::ClientUnregister,
+                group,
+                &[client_channel_info as &dyn Any],
+            );
+        }
+        if consumer_group_info
+            .get_channel_info_table()
+            .read()
+            .is_empty()
+            && write_guard.remove(group).is_some()
+        {
+            info!(
+                "unregister consumer ok, no any connection, and remove consumer group, {}",
+                group
+            );
+            self.call_consumer_ids_change_listener(ConsumerGroupEvent::Unregister, group, &[]);
+        }
+        if removed && is_notify_consumer_ids_changed_enable {
+            let all_channel = consumer_group_info.get_all_channels();
+            self.call_consumer_ids_change_listener(
+                ConsumerGroupEvent::Change,
+                group,
+                &[&all_channel as &dyn Any],
+            );
+        }
+    }
+
+    /// Drops the consumers that sent no heartbeat within the channel expired timeout, and the
+    /// groups left without consumers.
+    pub fn scan_not_active_channel(&self) {
+        let now = get_current_millis();
+        let mut write_guard = self.consumer_table.write();
+        write_guard.retain(|group, consumer_group_info| {
+            let channel_info_table = consumer_group_info.get_channel_info_table();
+            channel_info_table.write().retain(|channel, info| {
+                let diff = now.saturating_sub(info.last_update_timestamp());
+                if diff <= self.channel_expired_timeout {
+                    return true;
+                }
+                warn!(
+                    "SCAN: remove expired channel from ConsumerManager consumerTable. channel={}, \
+                     consumerGroup={}",
+                    channel.remote_address(),
+                    group
+                );
+                self.record_offline(
+                    group,
+                    info,
+                    info.last_update_timestamp(),
+                    OfflineReason::Expired,
+                );
+                false
+            });
+            let is_empty = channel_info_table.read().is_empty();
+            if is_empty {
+                warn!(
+                    "SCAN: remove expired channel from ConsumerManager consumerTable, all clear, \
+                     consumerGroup={}",
+                    group
+                );
+                self.call_consumer_ids_change_listener(ConsumerGroupEvent::Unregister, group, &[]);
+            }
+            !is_empty
+        });
+    }
+
+    /// Consumers of `group` that went offline recently, newest first.
+    pub fn get_offline_consumers(&self, group: &str) -> Vec<OfflineConsumerInfo> {
+        self.offline_history.get(group)
+    }
+
+    fn record_offline(
+        &self,
+        group: &str,
+        client_channel_info: &ClientChannelInfo,
+        last_update_timestamp: u64,
+        offline_reason: OfflineReason,
+    ) {
+        self.offline_history.record(
+            group,
+            OfflineConsumerInfo {
+                client_id: client_channel_info.client_id().clone(),
+                client_addr: client_channel_info.channel().remote_address().to_string(),
+                version: client_channel_info.version(),
+                last_update_timestamp,
+                offline_timestamp: get_current_millis(),
+                offline_reason,
+            },
+        );
+    }
+
     pub fn call_consumer_ids_change_listener(
         &self,
         event: ConsumerGroupEvent,
@@ -252,3 +409,152 @@ impl ConsumerManager {
         groups
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use rocketmq_common::ArcRefCellWrapper;
+    use rocketmq_remoting::connection::Connection;
+    use rocketmq_remoting::net::channel::Channel;
+    use rocketmq_remoting::protocol::LanguageCode;
+
+    use super::*;
+
+    #[derive(Clone, Default)]
+    struct RecordingListener {
+        events: Arc<parking_lot::Mutex<Vec<String>>>,
+    }
+
+    impl ConsumerIdsChangeListener for RecordingListener {
+        fn handle(&self, event: ConsumerGroupEvent, group: &str, _args: &[&dyn Any]) {
+            self.events.lock().push(format!("{:?} {}", event, group));
+        }
+
+        fn shutdown(&self) {}
+    }
+
+    async fn new_client_channel_info(client_id: &str) -> ClientChannelInfo {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let stream = tokio::net::TcpStream::connect(address).await.unwrap();
+        let channel = Channel::new(
+            stream.local_addr().unwrap(),
+            address,
+            Connection::new(stream),
+            ArcRefCellWrapper::new(HashMap::new()),
+        );
+        ClientChannelInfo::new(channel, client_id.to_string(), LanguageCode::RUST, 1)
+    }
+
+    fn register(consumer_manager: &ConsumerManager, client_channel_info: ClientChannelInfo) {
+        consumer_manager.register_consumer(
+            "GroupTest",
+            client_channel_info,
+            ConsumeType::ConsumePassively,
+            MessageModel::Clustering,
+            ConsumeFromWhere::ConsumeFromLastOffset,
+            HashSet::new(),
+            false,
+        );
+    }
+
+    #[tokio::test]
+    async fn unregister_consumer_records_the_offline_consumer() {
+        let listener = RecordingListener::default();
+        let consumer_manager = ConsumerManager::new(Box::new(listener.clone()), 120_000);
+        let c1 = new_client_channel_info("c1").await;
+        let c2 = new_client_channel_info("c2").await;
+        register(&consumer_manager, c1.clone());
+        register(&consumer_manager, c2.clone());
+        listener.events.lock().clear();
+
+        consumer_manager.unregister_consumer("GroupTest", &c1, false);
+        assert!(consumer_manager.find_channel("GroupTest", "c1").is_none());
+        assert!(consumer_manager.find_channel("GroupTest", "c2").is_some());
+        let offline = consumer_manager.get_offline_consumers("GroupTest");
+        assert_eq!(offline.len(), 1);
+        assert_eq!(offline[0].client_id, "c1");
+        assert_eq!(
+            offline[0].client_addr,
+            c1.channel().remote_address().to_string()
+        );
+        assert_eq!(offline[0].last_update_timestamp, c1.last_update_timestamp());
+        assert_eq!(offline[0].offline_reason, OfflineReason::Unregister);
+
+        // unknown channels leave no record
+        consumer_manager.unregister_consumer("GroupTest", &c1, false);
+        assert_eq!(consumer_manager.get_offline_consumers("GroupTest").len(), 1);
+
+        consumer_manager.unregister_consumer("GroupTest", &c2, false);
+        assert!(consumer_manager
+            .get_consumer_group_info("GroupTest")
+            .is_none());
+        let ids = consumer_manager
+            .get_offline_consumers("GroupTest")
+            .into_iter()
+            .map(|info| info.client_id)
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec!["c2", "c1"]);
+        assert_eq!(
+            *listener.events.lock(),
+            vec![
+                "ClientUnregister GroupTest",
+                "ClientUnregister GroupTest",
+                "Unregister GroupTest"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn scan_not_active_channel_expires_silent_consumers() {
+        let listener = RecordingListener::default();
+        let consumer_manager = ConsumerManager::new(Box::new(listener.clone()), 120_000);
+        let mut silent = new_client_channel_info("silent").await;
+        silent.set_last_update_timestamp(get_current_millis() - 300_000);
+        register(&consumer_manager, silent.clone());
+        register(&consumer_manager, new_client_channel_info("alive").await);
+        listener.events.lock().clear();
+
+        consumer_manager.scan_not_active_channel();
+        assert!(consumer_manager
+            .find_channel("GroupTest", "silent")
+            .is_none());
+        assert!(consumer_manager
+            .find_channel("GroupTest", "alive")
+            .is_some());
+        let offline = consumer_manager.get_offline_consumers("GroupTest");
+        assert_eq!(offline.len(), 1);
+        assert_eq!(offline[0].client_id, "silent");
+        assert_eq!(
+            offline[0].last_update_timestamp,
+            silent.last_update_timestamp()
+        );
+        assert_eq!(offline[0].offline_reason, OfflineReason::Expired);
+        assert!(listener.events.lock().is_empty());
+
+        // the last consumer expires, the group goes away
+        let mut expired = new_client_channel_info("expired").await;
+        expired.set_last_update_timestamp(get_current_millis() - 300_000);
+        consumer_manager.register_consumer(
+            "GroupExpired",
+            expired,
+            ConsumeType::ConsumePassively,
+            MessageModel::Clustering,
+            ConsumeFromWhere::ConsumeFromLastOffset,
+            HashSet::new(),
+            false,
+        );
+        listener.events.lock().clear();
+        consumer_manager.scan_not_active_channel();
+        assert!(consumer_manager
+            .get_consumer_group_info("GroupExpired")
+            .is_none());
+        assert!(consumer_manager
+            .get_consumer_group_info("GroupTest")
+            .is_some());
+        assert_eq!(
+            consumer_manager.get_offline_consumers("GroupExpired")[0].offline_reason,
+            OfflineReason::Expired
+        );
+        assert_eq!(*listener.events.lock(), vec!["Unregister GroupExpired"]);
+    }
+}
@@ -0,0 +1,113 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::collections::VecDeque;
+
+use parking_lot::Mutex;
+use rocketmq_common::TimeUtils::get_current_millis;
+use rocketmq_remoting::protocol::body::get_consumer_listby_group_response_body::OfflineConsumerInfo;
+
+/// Ring of the consumers that went offline, kept to tell why a group has no online member.
+///
+/// Holds at most `capacity` records, the oldest is dropped first, and records older than
+/// `retention_millis` are pruned whenever the ring is read. A client has a single record per
+/// group, the one of its latest departure.
+pub struct ConsumerOfflineHistory {
+    capacity: usize,
+    retention_millis: u64,
+    ring: Mutex<VecDeque<(String, OfflineConsumerInfo)>>,
+}
+
+impl ConsumerOfflineHistory {
+    pub fn new(capacity: usize, retention_millis: u64) -> Self {
+        Self {
+            capacity,
+            retention_millis,
+            ring: Mutex::new(VecDeque::with_capacity(capacity)),
+        }
+    }
+
+    pub fn record(&self, group: &str, info: OfflineConsumerInfo) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut ring = self.ring.lock();
+        ring.retain(|(g, i)| g != group || i.client_id != info.client_id);
+        if ring.len() >= self.capacity {
+            ring.pop_front();
+        }
+        ring.push_back((group.to_string(), info));
+    }
+
+    /// Offline consumers of `group`, newest first.
+    pub fn get(&self, group: &str) -> Vec<OfflineConsumerInfo> {
+        let now = get_current_millis();
+        let mut ring = self.ring.lock();
+        ring.retain(|(_, info)| {
+            now.saturating_sub(info.offline_timestamp) <= self.retention_millis
+        });
+        ring.iter()
+            .rev()
+            .filter(|(g, _)| g == group)
+            .map(|(_, info)| info.clone())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn offline(client_id: &str, offline_timestamp: u64) -> OfflineConsumerInfo {
+        OfflineConsumerInfo {
+            client_id: client_id.to_string(),
+            offline_timestamp,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn ring_keeps_latest_records_per_group() {
+        let now = get_current_millis();
+        let history = ConsumerOfflineHistory::new(3, 60_000);
+        history.record("g1", offline("c1", now));
+        history.record("g2", offline("c2", now));
+        history.record("g1", offline("c3", now));
+        history.record("g1", offline("c1", now + 1));
+        let ids: Vec<String> = history
+            .get("g1")
+            .into_iter()
+            .map(|info| info.client_id)
+            .collect();
+        assert_eq!(ids, vec!["c1", "c3"]);
+
+        history.record("g1", offline("c4", now));
+        history.record("g1", offline("c5", now));
+        assert!(history.get("g2").is_empty());
+        assert_eq!(history.get("g1").len(), 3);
+    }
+
+    #[test]
+    fn expired_records_are_pruned() {
+        let now = get_current_millis();
+        let history = ConsumerOfflineHistory::new(8, 1_000);
+        history.record("g", offline("old", now - 5_000));
+        history.record("g", offline("new", now));
+        let infos = history.get("g");
+        assert_eq!(infos.len(), 1);
+        assert_eq!(infos[0].client_id, "new");
+    }
+}
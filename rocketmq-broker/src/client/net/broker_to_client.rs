@@ -14,12 +14,27 @@
  * See the License for the specific language governing permissions and
  * limitations under the License.
  */
+use std::collections::HashMap;
+
+use rocketmq_common::common::mq_version::RocketMqVersion;
+use rocketmq_remoting::code::request_code::RequestCode;
+use rocketmq_remoting::code::response_code::ResponseCode;
 use rocketmq_remoting::net::channel::Channel;
+use rocketmq_remoting::protocol::body::get_consumer_status_body::GetConsumerStatusBody;
+use rocketmq_remoting::protocol::header::get_consumer_status_request_header::GetConsumerStatusRequestHeader;
+use rocketmq_remoting::protocol::header::notify_consumer_ids_changed_request_header::NotifyConsumerIdsChangedRequestHeader;
 use rocketmq_remoting::protocol::remoting_command::RemotingCommand;
+use rocketmq_remoting::protocol::RemotingDeserializable;
+use rocketmq_remoting::protocol::RemotingSerializable;
+use tracing::error;
+use tracing::warn;
 
+use crate::client::consumer_group_info::ConsumerGroupInfo;
 use crate::error::BrokerError::BrokerClientError;
 use crate::BrokerResult;
 
+const CALL_CLIENT_TIMEOUT_MILLIS: u64 = 10_000;
+
 #[derive(Default, Clone)]
 pub struct Broker2Client;
 
@@ -35,4 +50,135 @@ impl Broker2Client {
             Err(e) => Err(BrokerClientError(e)),
         }
     }
+
+    /// Tells a consumer that the client list of its group changed so that it rebalances at once
+    /// instead of waiting for the next rebalance round.
+    pub async fn notify_consumer_ids_changed(
+        &mut self,
+        channel: &mut Channel,
+        consumer_group: &str,
+    ) {
+        let request_header = NotifyConsumerIdsChangedRequestHeader {
+            consumer_group: consumer_group.to_string(),
+        };
+        let request = RemotingCommand::create_request_command(
+            RequestCode::NotifyConsumerIdsChanged,
+            request_header,
+        );
+        if let Err(e) = channel.send_one_way(request).await {
+            error!(
+                "notifyConsumerIdsChanged exception. group={}, error={}",
+                consumer_group, e
+            );
+        }
+    }
+
+    /// Asks every client of `group` (or only `origin_client_id` when given) for its consume
+    /// progress on `topic` and aggregates the answers by client id.
+    pub async fn get_consume_status(
+        &mut self,
+        consumer_group_info: Option<ConsumerGroupInfo>,
+        topic: &str,
+        group: &str,
+        origin_client_id: Option<&str>,
+    ) -> RemotingCommand {
+        let origin_client_id = origin_client_id.filter(|client_id| !client_id.trim().is_empty());
+        let client_channel_infos = match consumer_group_info {
+            Some(consumer_group_info) => consumer_group_info
+                .get_channel_info_table()
+                .read()
+                .values()
+                .cloned()
+                .collect::<Vec<_>>(),
+            None => Vec::new(),
+        };
+        if client_channel_infos.is_empty() {
+            return RemotingCommand::create_response_command_with_code_remark(
+                ResponseCode::SystemError,
+                format!("No Any Consumer online in the consumer group: [{}]", group),
+            );
+        }
+
+        let request_header = GetConsumerStatusRequestHeader {
+            topic: topic.to_string(),
+            group: group.to_string(),
+            client_addr: None,
+        };
+        let mut consumer_status_table = HashMap::new();
+        for client_channel_info in client_channel_infos {
+            let version = client_channel_info.version();
+            let client_id = client_channel_info.client_id();
+            if version < i32::from(RocketMqVersion::V307Snapshot) {
+                return RemotingCommand::create_response_command_with_code_remark(
+                    ResponseCode::SystemError,
+                    format!(
+                        "the client does not support this feature. version={}",
+                        version
+                    ),
+                );
+            }
+            if origin_client_id.is_some_and(|origin| origin != client_id) {
+                continue;
+            }
+            let request = RemotingCommand::create_request_command(
+                RequestCode::GetConsumerStatusFromClient,
+                request_header.clone(),
+            );
+            let mut channel = client_channel_info.channel().clone();
+            match self
+                .call_client(&mut channel, request, CALL_CLIENT_TIMEOUT_MILLIS)
+                .await
+            {
+                Ok(response) => {
+                    if ResponseCode::from(response.code()) == ResponseCode::Success {
+                        if let Some(body) = response.body() {
+                            match GetConsumerStatusBody::decode(body.as_ref()) {
+                                Ok(body) => {
+                                    consumer_status_table
+                                        .insert(client_id.clone(), body.message_queue_table);
+                                }
+                                Err(e) => warn!(
+                                    "decode consumer status of client {} failed: {}",
+                                    client_id, e
+                                ),
+                            }
+                        }
+                    }
+                }
+                Err(e) => error!(
+                    "get consumer status from client {} failed, group={}, topic={}, error={}",
+                    client_id, group, topic, e
+                ),
+            }
+            if origin_client_id.is_some() {
+                break;
+            }
+        }
+        let body = GetConsumerStatusBody {
+            consumer_table: consumer_status_table,
+            ..Default::default()
+        };
+        RemotingCommand::create_response_command().set_body(Some(body.encode()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn get_consume_status_without_online_consumer() {
+        let mut broker_to_client = Broker2Client;
+        let response = broker_to_client
+            .get_consume_status(None, "test_topic", "test_group", None)
+            .await;
+        assert_eq!(
+            ResponseCode::from(response.code()),
+            ResponseCode::SystemError
+        );
+        assert_eq!(
+            response.remark().map(String::as_str),
+            Some("No Any Consumer online in the consumer group: [test_group]")
+        );
+    }
 }
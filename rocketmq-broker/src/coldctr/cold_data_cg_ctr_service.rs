@@ -15,11 +15,193 @@
  * limitations under the License.
  */
 
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use rocketmq_common::common::broker::broker_config::BrokerConfig;
+use rocketmq_common::common::mix_all::is_sys_consumer_group_for_no_cold_read_limit;
+use rocketmq_common::TimeUtils::get_current_millis;
+use rocketmq_store::config::message_store_config::MessageStoreConfig;
+use tracing::info;
+
+/// Length of the window cold reads are accumulated over, thresholds are bytes per window.
+const COLD_ACC_WINDOW_MILLS: u64 = 1000;
+
+/// Cold data read by one consumer group, or by all of them, in the current window.
+#[derive(Debug, Clone, Copy, Default)]
+struct AccAndTimeStamp {
+    cold_acc: i64,
+    window_start_mills: u64,
+}
+
+impl AccAndTimeStamp {
+    fn acc(&mut self, cold_data_to_acc: i64, now: u64) {
+        if now.saturating_sub(self.window_start_mills) >= COLD_ACC_WINDOW_MILLS {
+            self.cold_acc = 0;
+            self.window_start_mills = now;
+        }
+        self.cold_acc += cold_data_to_acc;
+    }
+
+    fn current(&self, now: u64) -> i64 {
+        if now.saturating_sub(self.window_start_mills) >= COLD_ACC_WINDOW_MILLS {
+            0
+        } else {
+            self.cold_acc
+        }
+    }
+}
+
+/// Throttles consumer groups that read too much data missing the page cache, so that one group
+/// replaying history does not degrade the latency of groups reading hot data.
 #[derive(Default)]
-pub struct ColdDataCgCtrService;
+pub struct ColdDataCgCtrService {
+    broker_config: Arc<BrokerConfig>,
+    message_store_config: Arc<MessageStoreConfig>,
+    runtime_table: parking_lot::Mutex<HashMap<String /* group */, AccAndTimeStamp>>,
+    global_acc: parking_lot::Mutex<AccAndTimeStamp>,
+    cg_cold_threshold_config: parking_lot::RwLock<HashMap<String /* group */, i64>>,
+}
 
 impl ColdDataCgCtrService {
-    pub fn is_cg_need_cold_data_flow_ctr(&self, _consumer_group: &str) -> bool {
-        false
+    pub fn new(
+        broker_config: Arc<BrokerConfig>,
+        message_store_config: Arc<MessageStoreConfig>,
+    ) -> Self {
+        Self {
+            broker_config,
+            message_store_config,
+            ..Default::default()
+        }
+    }
+
+    /// Records `cold_data_to_acc` bytes of cold data read by `consumer_group`.
+    pub fn cold_acc(&self, consumer_group: &str, cold_data_to_acc: i64) {
+        if cold_data_to_acc <= 0 {
+            return;
+        }
+        let now = get_current_millis();
+        self.runtime_table
+            .lock()
+            .entry(consumer_group.to_string())
+            .or_default()
+            .acc(cold_data_to_acc, now);
+        self.global_acc.lock().acc(cold_data_to_acc, now);
+    }
+
+    pub fn is_cg_need_cold_data_flow_ctr(&self, consumer_group: &str) -> bool {
+        if !self.message_store_config.cold_data_flow_control_enable
+            || is_sys_consumer_group_for_no_cold_read_limit(consumer_group)
+        {
+            return false;
+        }
+        let now = get_current_millis();
+        let cold_acc = match self.runtime_table.lock().get(consumer_group) {
+            None => return false,
+            Some(acc) => acc.current(now),
+        };
+        if cold_acc <= 0 {
+            return false;
+        }
+        cold_acc >= self.cg_cold_read_threshold(consumer_group)
+            || self.global_acc.lock().current(now) >= self.broker_config.global_cold_read_threshold
+    }
+
+    /// Overrides `BrokerConfig::cg_cold_read_threshold` for `consumer_group`.
+    pub fn add_or_update_group_config(&self, consumer_group: &str, threshold: i64) {
+        info!(
+            "add or update cold data flow control config, group: {}, threshold: {}",
+            consumer_group, threshold
+        );
+        self.cg_cold_threshold_config
+            .write()
+            .insert(consumer_group.to_string(), threshold);
+    }
+
+    pub fn remove_group_config(&self, consumer_group: &str) {
+        info!(
+            "remove cold data flow control config, group: {}",
+            consumer_group
+        );
+        self.cg_cold_threshold_config.write().remove(consumer_group);
+    }
+
+    fn cg_cold_read_threshold(&self, consumer_group: &str) -> i64 {
+        self.cg_cold_threshold_config
+            .read()
+            .get(consumer_group)
+            .copied()
+            .unwrap_or(self.broker_config.cg_cold_read_threshold)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn service(
+        cg_cold_read_threshold: i64,
+        global_cold_read_threshold: i64,
+    ) -> ColdDataCgCtrService {
+        let broker_config = BrokerConfig {
+            cg_cold_read_threshold,
+            global_cold_read_threshold,
+            ..Default::default()
+        };
+        let message_store_config = MessageStoreConfig {
+            cold_data_flow_control_enable: true,
+            ..Default::default()
+        };
+        ColdDataCgCtrService::new(Arc::new(broker_config), Arc::new(message_store_config))
+    }
+
+    #[test]
+    fn acc_resets_once_the_window_elapsed() {
+        let mut acc = AccAndTimeStamp::default();
+        acc.acc(10, 5000);
+        acc.acc(10, 5500);
+        assert_eq!(acc.current(5999), 20);
+        assert_eq!(acc.current(6000), 0);
+        acc.acc(5, 6000);
+        assert_eq!(acc.current(6000), 5);
+    }
+
+    #[test]
+    fn group_over_threshold_needs_flow_ctr() {
+        let service = service(100, i64::MAX);
+        assert!(!service.is_cg_need_cold_data_flow_ctr("group_a"));
+        service.cold_acc("group_a", 99);
+        assert!(!service.is_cg_need_cold_data_flow_ctr("group_a"));
+        service.cold_acc("group_a", 1);
+        assert!(service.is_cg_need_cold_data_flow_ctr("group_a"));
+        assert!(!service.is_cg_need_cold_data_flow_ctr("group_b"));
+
+        service.add_or_update_group_config("group_a", 1000);
+        assert!(!service.is_cg_need_cold_data_flow_ctr("group_a"));
+        service.remove_group_config("group_a");
+        assert!(service.is_cg_need_cold_data_flow_ctr("group_a"));
+    }
+
+    #[test]
+    fn global_threshold_throttles_cold_readers() {
+        let service = service(i64::MAX, 100);
+        service.cold_acc("group_a", 60);
+        service.cold_acc("group_b", 60);
+        assert!(service.is_cg_need_cold_data_flow_ctr("group_a"));
+        assert!(service.is_cg_need_cold_data_flow_ctr("group_b"));
+        assert!(!service.is_cg_need_cold_data_flow_ctr("group_c"));
+    }
+
+    #[test]
+    fn disabled_flow_ctr_never_throttles() {
+        let service = ColdDataCgCtrService::new(
+            Arc::new(BrokerConfig {
+                cg_cold_read_threshold: 1,
+                ..Default::default()
+            }),
+            Arc::new(MessageStoreConfig::default()),
+        );
+        service.cold_acc("group_a", 100);
+        assert!(!service.is_cg_need_cold_data_flow_ctr("group_a"));
     }
 }
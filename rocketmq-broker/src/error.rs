@@ -14,10 +14,34 @@
  * See the License for the specific language governing permissions and
  * limitations under the License.
  */
+use rocketmq_remoting::code::response_code::ResponseCode;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
 pub enum BrokerError {
     #[error("broker client error: {0}")]
     BrokerClientError(#[from] rocketmq_remoting::error::Error),
+
+    #[error("CODE: {0} DESC: {1} BROKER: {2}")]
+    Response(i32, String, String),
+
+    #[error("metadata bootstrap error: {0}")]
+    IllegalMetadataBootstrap(String),
+}
+
+impl BrokerError {
+    /// The response code the remote broker or name server answered with, `None` for local
+    /// errors and unknown codes.
+    pub fn response_code(&self) -> Option<ResponseCode> {
+        let code = match self {
+            BrokerError::Response(code, _, _) => *code,
+            BrokerError::BrokerClientError(rocketmq_remoting::error::Error::RpcException(
+                code,
+                _,
+            )) => *code,
+            _ => return None,
+        };
+        let response_code = ResponseCode::from(code);
+        (response_code as i32 == code).then_some(response_code)
+    }
 }
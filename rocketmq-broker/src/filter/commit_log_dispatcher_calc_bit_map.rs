@@ -0,0 +1,101 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::sync::Arc;
+
+use bytes::Bytes;
+use rocketmq_common::common::broker::broker_config::BrokerConfig;
+use rocketmq_store::base::commit_log_dispatcher::CommitLogDispatcher;
+use rocketmq_store::base::dispatch_request::DispatchRequest;
+use tracing::error;
+
+use crate::filter::manager::consumer_filter_manager::ConsumerFilterManager;
+use crate::filter::message_evaluation_context::MessageEvaluationContext;
+
+/// Evaluates the SQL92 subscriptions of the topic of each dispatched message and sets the bloom
+/// filter bits of the matching ones in the bit map of the request, the consume queue stores it
+/// in its ext so that pulls can skip the messages that can not match.
+pub(crate) struct CommitLogDispatcherCalcBitMap {
+    broker_config: Arc<BrokerConfig>,
+    consumer_filter_manager: Arc<ConsumerFilterManager>,
+}
+
+impl CommitLogDispatcherCalcBitMap {
+    pub fn new(
+        broker_config: Arc<BrokerConfig>,
+        consumer_filter_manager: Arc<ConsumerFilterManager>,
+    ) -> Self {
+        Self {
+            broker_config,
+            consumer_filter_manager,
+        }
+    }
+}
+
+impl CommitLogDispatcher for CommitLogDispatcherCalcBitMap {
+    fn dispatch(&self, dispatch_request: &mut DispatchRequest) {
+        if !self.broker_config.enable_calc_filter_bit_map {
+            return;
+        }
+        let Some(bloom_filter) = self.consumer_filter_manager.get_bloom_filter() else {
+            return;
+        };
+        let filter_datas = self
+            .consumer_filter_manager
+            .get_by_topic(dispatch_request.topic.as_str());
+        if filter_datas.is_empty() {
+            return;
+        }
+        let empty_properties = Default::default();
+        let context = MessageEvaluationContext::new(
+            dispatch_request
+                .properties_map
+                .as_ref()
+                .unwrap_or(&empty_properties),
+        );
+        let mut filter_bit_map = vec![0u8; bloom_filter.m() as usize / 8];
+        for filter_data in &filter_datas {
+            let (Some(compiled_expression), Some(bloom_filter_data)) = (
+                filter_data.compiled_expression(),
+                filter_data.bloom_filter_data(),
+            ) else {
+                error!(
+                    "[BUG] Consumer in filter manager has no compiled expression or bloom filter \
+                     data, topic={}, group={}",
+                    filter_data.topic(),
+                    filter_data.consumer_group()
+                );
+                continue;
+            };
+            match compiled_expression.evaluate(&context) {
+                Ok(value) if value.downcast_ref::<bool>().copied().unwrap_or(false) => {
+                    bloom_filter.hash_to(bloom_filter_data, &mut filter_bit_map);
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    error!(
+                        "Calc filter bit map error, topic={}, group={}, expression={:?}, {}",
+                        filter_data.topic(),
+                        filter_data.consumer_group(),
+                        filter_data.expression(),
+                        e
+                    );
+                }
+            }
+        }
+        dispatch_request.bit_map = Some(Bytes::from(filter_bit_map));
+    }
+}
@@ -53,6 +53,10 @@ impl ConsumerFilterData {
         self.expression_type.as_ref()
     }
 
+    pub fn compiled_expression(&self) -> Option<&Arc<Box<dyn Expression + Send + Sync + 'static>>> {
+        self.compiled_expression.as_ref()
+    }
+
     pub fn born_time(&self) -> u64 {
         self.born_time
     }
@@ -85,6 +89,13 @@ impl ConsumerFilterData {
         self.expression_type = expression_type;
     }
 
+    pub fn set_compiled_expression(
+        &mut self,
+        compiled_expression: Option<Arc<Box<dyn Expression + Send + Sync + 'static>>>,
+    ) {
+        self.compiled_expression = compiled_expression;
+    }
+
     pub fn set_born_time(&mut self, born_time: u64) {
         self.born_time = born_time;
     }
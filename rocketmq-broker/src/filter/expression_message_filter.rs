@@ -16,14 +16,18 @@
  */
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Instant;
 
 use rocketmq_common::common::filter::expression_type::ExpressionType;
+use rocketmq_common::MessageDecoder;
 use rocketmq_remoting::protocol::heartbeat::subscription_data::SubscriptionData;
 use rocketmq_store::consume_queue::consume_queue_ext::CqExtUnit;
 use rocketmq_store::filter::MessageFilter;
+use tracing::warn;
 
 use crate::filter::consumer_filter_data::ConsumerFilterData;
 use crate::filter::manager::consumer_filter_manager::ConsumerFilterManager;
+use crate::filter::message_evaluation_context::MessageEvaluationContext;
 
 pub struct ExpressionMessageFilter {
     subscription_data: Option<SubscriptionData>,
@@ -80,7 +84,32 @@ impl MessageFilter for ExpressionMessageFilter {
                 .code_set
                 .contains(&(tags_code.unwrap() as i32))
         } else {
-            unimplemented!("SQL92 expression type is not supported yet.")
+            // A miss of the bloom filter bits computed at dispatch is certain, anything else is
+            // decided against the message properties once read from the commit log.
+            let Some(consumer_filter_data) = self.consumer_filter_data.as_ref() else {
+                return true;
+            };
+            let (Some(cq_ext_unit), Some(bloom_filter_data), Some(bloom_filter)) = (
+                cq_ext_unit,
+                consumer_filter_data.bloom_filter_data(),
+                self.consumer_filter_manager.get_bloom_filter(),
+            ) else {
+                return true;
+            };
+            // messages stored before the subscription have no bit for it
+            if !self.bloom_data_valid
+                || cq_ext_unit.msg_store_time() <= consumer_filter_data.born_time() as i64
+            {
+                return true;
+            }
+            match cq_ext_unit.filter_bit_map() {
+                Some(filter_bit_map)
+                    if filter_bit_map.len() * 8 == bloom_filter_data.bit_num() as usize =>
+                {
+                    bloom_filter.is_hit(bloom_filter_data, filter_bit_map)
+                }
+                _ => true,
+            }
         }
     }
 
@@ -106,6 +135,40 @@ impl MessageFilter for ExpressionMessageFilter {
         if real_filter_data.expression().is_none() || real_filter_data.expression_type().is_none() {
             return true;
         }
-        unimplemented!("SQL92 expression type is not supported yet.")
+        let Some(compiled_expression) = real_filter_data.compiled_expression() else {
+            return true;
+        };
+
+        let decoded_properties;
+        let properties = match (properties, msg_buffer) {
+            (Some(properties), _) => properties,
+            (None, Some(msg_buffer)) => {
+                decoded_properties =
+                    MessageDecoder::decode_properties(msg_buffer).unwrap_or_default();
+                &decoded_properties
+            }
+            (None, None) => return true,
+        };
+
+        let context = MessageEvaluationContext::new(properties);
+        let start = Instant::now();
+        let matched = match compiled_expression.evaluate(&context) {
+            Ok(value) => Some(value.downcast_ref::<bool>().copied().unwrap_or(false)),
+            Err(e) => {
+                warn!(
+                    "Message Filter error, {:?}, {:?}, {}",
+                    real_filter_data.expression(),
+                    real_filter_data.expression_type(),
+                    e
+                );
+                None
+            }
+        };
+        self.consumer_filter_manager.evaluation_stats().record(
+            real_filter_data.expression_type().unwrap(),
+            start.elapsed(),
+            matched,
+        );
+        matched.unwrap_or(false)
     }
 }
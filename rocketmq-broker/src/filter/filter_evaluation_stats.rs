@@ -0,0 +1,136 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::collections::HashMap;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+
+use parking_lot::RwLock;
+
+/// Evaluations of the filter expressions of one type.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FilterEvaluationSnapshot {
+    pub evaluations: u64,
+    pub matched: u64,
+    pub errors: u64,
+    pub total_cost_nanos: u64,
+    pub max_cost_nanos: u64,
+}
+
+impl FilterEvaluationSnapshot {
+    pub fn avg_cost_nanos(&self) -> u64 {
+        self.total_cost_nanos
+            .checked_div(self.evaluations)
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Default)]
+struct Counters {
+    evaluations: AtomicU64,
+    matched: AtomicU64,
+    errors: AtomicU64,
+    total_cost_nanos: AtomicU64,
+    max_cost_nanos: AtomicU64,
+}
+
+/// Count and cost of the filter expressions evaluated against messages, per expression type.
+#[derive(Default)]
+pub(crate) struct FilterEvaluationStats {
+    table: RwLock<HashMap<String, Arc<Counters>>>,
+}
+
+impl FilterEvaluationStats {
+    /// Records one evaluation; `matched` is `None` when the expression failed.
+    pub fn record(&self, expression_type: &str, cost: Duration, matched: Option<bool>) {
+        let counters = self.table.read().get(expression_type).cloned();
+        let counters = match counters {
+            Some(counters) => counters,
+            None => self
+                .table
+                .write()
+                .entry(expression_type.to_string())
+                .or_default()
+                .clone(),
+        };
+        let cost_nanos = cost.as_nanos() as u64;
+        counters.evaluations.fetch_add(1, Ordering::Relaxed);
+        counters
+            .total_cost_nanos
+            .fetch_add(cost_nanos, Ordering::Relaxed);
+        counters
+            .max_cost_nanos
+            .fetch_max(cost_nanos, Ordering::Relaxed);
+        match matched {
+            Some(true) => {
+                counters.matched.fetch_add(1, Ordering::Relaxed);
+            }
+            Some(false) => {}
+            None => {
+                counters.errors.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    pub fn snapshot(&self) -> HashMap<String, FilterEvaluationSnapshot> {
+        self.table
+            .read()
+            .iter()
+            .map(|(expression_type, counters)| {
+                (
+                    expression_type.clone(),
+                    FilterEvaluationSnapshot {
+                        evaluations: counters.evaluations.load(Ordering::Relaxed),
+                        matched: counters.matched.load(Ordering::Relaxed),
+                        errors: counters.errors.load(Ordering::Relaxed),
+                        total_cost_nanos: counters.total_cost_nanos.load(Ordering::Relaxed),
+                        max_cost_nanos: counters.max_cost_nanos.load(Ordering::Relaxed),
+                    },
+                )
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record_accumulates_per_expression_type() {
+        let stats = FilterEvaluationStats::default();
+        stats.record("SQL92", Duration::from_nanos(100), Some(true));
+        stats.record("SQL92", Duration::from_nanos(300), Some(false));
+        stats.record("SQL92", Duration::from_nanos(200), None);
+        stats.record("CUSTOM", Duration::from_nanos(50), Some(true));
+
+        let snapshot = stats.snapshot();
+        assert_eq!(
+            snapshot["SQL92"],
+            FilterEvaluationSnapshot {
+                evaluations: 3,
+                matched: 1,
+                errors: 1,
+                total_cost_nanos: 600,
+                max_cost_nanos: 300,
+            }
+        );
+        assert_eq!(snapshot["SQL92"].avg_cost_nanos(), 200);
+        assert_eq!(snapshot["CUSTOM"].evaluations, 1);
+    }
+}
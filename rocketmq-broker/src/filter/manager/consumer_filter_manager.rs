@@ -15,6 +15,7 @@
  * limitations under the License.
  */
 
+use std::collections::HashSet;
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -22,10 +23,17 @@ use rocketmq_common::common::broker::broker_config::BrokerConfig;
 use rocketmq_common::common::config_manager::ConfigManager;
 use rocketmq_common::common::filter::expression_type::ExpressionType;
 use rocketmq_common::TimeUtils::get_current_millis;
+use rocketmq_filter::expression::Expression;
+use rocketmq_filter::filter_factory::FilterFactory;
 use rocketmq_filter::utils::bloom_filter::BloomFilter;
+use rocketmq_remoting::protocol::heartbeat::subscription_data::SubscriptionData;
+use tracing::error;
+use tracing::info;
+use tracing::warn;
 
 use crate::broker_path_config_helper::get_consumer_filter_path;
 use crate::filter::consumer_filter_data::ConsumerFilterData;
+use crate::filter::filter_evaluation_stats::FilterEvaluationStats;
 use crate::filter::manager::consumer_filter_wrapper::ConsumerFilterWrapper;
 
 const MS_24_HOUR: u64 = Duration::from_hours(24).as_millis() as u64;
@@ -35,6 +43,7 @@ pub(crate) struct ConsumerFilterManager {
     broker_config: Arc<BrokerConfig>,
     consumer_filter_wrapper: Arc<parking_lot::RwLock<ConsumerFilterWrapper>>,
     bloom_filter: Option<BloomFilter>,
+    evaluation_stats: Arc<FilterEvaluationStats>,
 }
 
 impl ConsumerFilterManager {
@@ -52,38 +61,72 @@ impl ConsumerFilterManager {
             broker_config,
             consumer_filter_wrapper,
             bloom_filter: Some(bloom_filter),
+            evaluation_stats: Arc::new(FilterEvaluationStats::default()),
         }
     }
 }
 
-//Fully implemented will be removed
-#[allow(unused_variables)]
 impl ConfigManager for ConsumerFilterManager {
-    fn decode0(&mut self, key: &[u8], body: &[u8]) {
-        todo!()
-    }
-
-    fn stop(&mut self) -> bool {
-        todo!()
-    }
-
     fn config_file_path(&self) -> String {
         get_consumer_filter_path(self.broker_config.store_path_root_dir.as_str())
     }
 
-    fn encode(&mut self) -> String {
-        todo!()
-    }
-
+    /// Drops the filter data of groups dead for a day before encoding the rest.
     fn encode_pretty(&self, pretty_format: bool) -> String {
-        "".to_string()
+        self.clean_expired(get_current_millis());
+        let wrapper = self.consumer_filter_wrapper.read();
+        let json = if pretty_format {
+            serde_json::to_string_pretty(&*wrapper)
+        } else {
+            serde_json::to_string(&*wrapper)
+        };
+        json.unwrap_or_default()
     }
 
-    fn decode(&self, json_string: &str) {}
+    /// Compiles the persisted expressions again, filter data whose expression no longer
+    /// compiles is dropped and bloom filter data generated for another bloom filter is
+    /// generated again.
+    fn decode(&self, json_string: &str) {
+        if json_string.is_empty() {
+            return;
+        }
+        let wrapper = match serde_json::from_str::<ConsumerFilterWrapper>(json_string) {
+            Ok(wrapper) => wrapper,
+            Err(e) => {
+                error!("decode consumer filter data failed: {}", e);
+                return;
+            }
+        };
+        let mut consumer_filter_wrapper = self.consumer_filter_wrapper.write();
+        for mut filter_data in wrapper.into_filter_data() {
+            let Some(compiled_expression) = Self::compile(
+                filter_data.topic(),
+                filter_data.consumer_group(),
+                filter_data.expression().map(String::as_str),
+                filter_data.expression_type().map(String::as_str),
+            ) else {
+                continue;
+            };
+            filter_data.set_compiled_expression(Some(compiled_expression));
+            if let Some(bloom_filter) = self.bloom_filter.as_ref() {
+                if !bloom_filter.is_valid(filter_data.bloom_filter_data()) {
+                    filter_data.set_bloom_filter_data(Some(
+                        bloom_filter.generate(
+                            format!("{}#{}", filter_data.consumer_group(), filter_data.topic())
+                                .as_str(),
+                        ),
+                    ));
+                }
+            }
+            consumer_filter_wrapper.put(filter_data);
+        }
+    }
 }
 
-#[allow(unused_variables)]
 impl ConsumerFilterManager {
+    /// Compiles the expression of a subscription with the filter type registered in
+    /// [`FilterFactory`] under `type_`. `None` for TAG subscriptions and expressions that do
+    /// not compile.
     pub fn build(
         topic: &str,
         consumer_group: &str,
@@ -104,32 +147,306 @@ impl ConsumerFilterManager {
         consumer_filter_data.set_expression_type(type_.map(|s| s.to_string()));
         consumer_filter_data.set_client_version(client_version);
 
-        /*        let filter_factory = FilterFactory;
-                match filter_factory.get(&type_).compile(&expression) {
-                    Ok(compiled_expression) => {
-                        consumer_filter_data.set_compiled_expression(compiled_expression);
-                    }
-                    Err(e) => {
-                        eprintln!(
-                            "parse error: expr={}, topic={}, group={}, error={}",
-                            expression, topic, consumer_group, e
-                        );
-                        return None;
-                    }
-                }
-        */
+        consumer_filter_data.set_compiled_expression(Some(Self::compile(
+            topic,
+            consumer_group,
+            expression,
+            type_,
+        )?));
         Some(consumer_filter_data)
     }
 
+    fn compile(
+        topic: &str,
+        consumer_group: &str,
+        expression: Option<&str>,
+        type_: Option<&str>,
+    ) -> Option<Arc<Box<dyn Expression + Send + Sync + 'static>>> {
+        let type_ = type_.unwrap_or_default();
+        let Some(filter_spi) = FilterFactory::instance().get(type_) else {
+            warn!(
+                "filter type {} is not registered, topic={}, group={}",
+                type_, topic, consumer_group
+            );
+            return None;
+        };
+        match filter_spi.compile(expression.unwrap_or_default()) {
+            Ok(compiled_expression) => Some(Arc::new(compiled_expression)),
+            Err(e) => {
+                error!(
+                    "parse error: expr={:?}, topic={}, group={}, error={}",
+                    expression, topic, consumer_group, e
+                );
+                None
+            }
+        }
+    }
+
+    /// Filter data of the subscription of `consumer_group` to `topic`. The compiled expression
+    /// is cached and only rebuilt when the expression or its type changes; data from a client
+    /// version older than the cached one is ignored. Registering revives the filter data of a
+    /// dead group.
+    pub fn register(
+        &self,
+        topic: &str,
+        consumer_group: &str,
+        expression: &str,
+        type_: &str,
+        client_version: u64,
+    ) -> Option<ConsumerFilterData> {
+        if ExpressionType::is_tag_type(Some(type_)) {
+            return None;
+        }
+        let same_subscription = |filter_data: &ConsumerFilterData| {
+            filter_data.expression().map(String::as_str) == Some(expression)
+                && filter_data.expression_type().map(String::as_str) == Some(type_)
+        };
+        if let Some(filter_data) = self
+            .consumer_filter_wrapper
+            .read()
+            .get(topic, consumer_group)
+        {
+            if filter_data.dead_time() == 0
+                && (filter_data.client_version() > client_version
+                    || (filter_data.client_version() == client_version
+                        && same_subscription(filter_data)))
+            {
+                return Some(filter_data.clone());
+            }
+        }
+
+        let mut consumer_filter_wrapper = self.consumer_filter_wrapper.write();
+        let filter_data = match consumer_filter_wrapper.get(topic, consumer_group) {
+            Some(filter_data) if filter_data.client_version() >= client_version => {
+                let mut filter_data = filter_data.clone();
+                if filter_data.dead_time() == 0 {
+                    return Some(filter_data);
+                }
+                filter_data.set_dead_time(0);
+                filter_data
+            }
+            Some(filter_data) if same_subscription(filter_data) => {
+                let mut filter_data = filter_data.clone();
+                filter_data.set_client_version(client_version);
+                filter_data.set_dead_time(0);
+                filter_data
+            }
+            _ => {
+                let mut filter_data = Self::build(
+                    topic,
+                    consumer_group,
+                    Some(expression),
+                    Some(type_),
+                    client_version,
+                )?;
+                if let Some(bloom_filter) = self.bloom_filter.as_ref() {
+                    filter_data.set_bloom_filter_data(Some(
+                        bloom_filter.generate(format!("{}#{}", consumer_group, topic).as_str()),
+                    ));
+                }
+                filter_data
+            }
+        };
+        consumer_filter_wrapper.put(filter_data.clone());
+        Some(filter_data)
+    }
+
+    /// Registers the filter data of the subscriptions of `consumer_group` using a filter type.
+    /// The filter data of the topics the group no longer subscribes to with one is marked dead.
+    pub fn register_subscriptions(
+        &self,
+        consumer_group: &str,
+        subscriptions: &HashSet<SubscriptionData>,
+    ) {
+        let subscribed = subscriptions
+            .iter()
+            .filter(|subscription_data| {
+                !ExpressionType::is_tag_type(Some(subscription_data.expression_type.as_str()))
+            })
+            .map(|subscription_data| subscription_data.topic.as_str())
+            .collect::<HashSet<_>>();
+        let now = get_current_millis();
+        for filter_data in self
+            .consumer_filter_wrapper
+            .write()
+            .iter_mut_by_group(consumer_group)
+        {
+            if filter_data.dead_time() == 0 && !subscribed.contains(filter_data.topic()) {
+                filter_data.set_dead_time(now);
+            }
+        }
+        for subscription_data in subscriptions {
+            if ExpressionType::is_tag_type(Some(subscription_data.expression_type.as_str())) {
+                continue;
+            }
+            self.register(
+                subscription_data.topic.as_str(),
+                consumer_group,
+                subscription_data.sub_string.as_str(),
+                subscription_data.expression_type.as_str(),
+                subscription_data.sub_version as u64,
+            );
+        }
+    }
+
+    /// Marks the filter data of every subscription of `consumer_group` dead, it is dropped a
+    /// day later unless the group registers again.
+    pub fn unregister(&self, consumer_group: &str) {
+        let now = get_current_millis();
+        for filter_data in self
+            .consumer_filter_wrapper
+            .write()
+            .iter_mut_by_group(consumer_group)
+        {
+            if filter_data.dead_time() == 0 {
+                filter_data.set_dead_time(now);
+            }
+        }
+    }
+
+    /// Drops the filter data dead for a day at `now`. Returns how many were dropped.
+    pub fn clean_expired(&self, now: u64) -> usize {
+        let removed = self.consumer_filter_wrapper.write().retain(|filter_data| {
+            filter_data.dead_time() == 0 || now.saturating_sub(filter_data.dead_time()) < MS_24_HOUR
+        });
+        if removed > 0 {
+            info!("removed {} expired consumer filter data", removed);
+        }
+        removed
+    }
+
     pub fn get_consumer_filter_data(
         &self,
         topic: &str,
         consumer_group: &str,
     ) -> Option<ConsumerFilterData> {
-        None
+        self.consumer_filter_wrapper
+            .read()
+            .get(topic, consumer_group)
+            .cloned()
+    }
+
+    /// Filter data of every subscription to `topic` using a filter type.
+    pub fn get_by_topic(&self, topic: &str) -> Vec<ConsumerFilterData> {
+        self.consumer_filter_wrapper.read().get_by_topic(topic)
     }
 
     pub fn get_bloom_filter(&self) -> Option<&BloomFilter> {
         self.bloom_filter.as_ref()
     }
+
+    pub fn evaluation_stats(&self) -> &FilterEvaluationStats {
+        &self.evaluation_stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::any::Any;
+    use std::error::Error;
+
+    use rocketmq_filter::error::FilterError;
+    use rocketmq_filter::expression::evaluation_context::EvaluationContext;
+    use rocketmq_filter::expression::Expression;
+    use rocketmq_filter::filter_spi::FilterSpi;
+
+    use super::*;
+
+    struct PropertyExistsExpression(String);
+
+    impl Expression for PropertyExistsExpression {
+        fn evaluate(
+            &self,
+            context: &dyn EvaluationContext,
+        ) -> Result<Box<dyn Any>, Box<dyn Error>> {
+            Ok(Box::new(context.get(self.0.as_str()).is_some()))
+        }
+    }
+
+    struct PropertyExistsFilter;
+
+    impl FilterSpi for PropertyExistsFilter {
+        fn compile(&self, expr: &str) -> Result<Box<dyn Expression + Send + Sync>, FilterError> {
+            Ok(Box::new(PropertyExistsExpression(expr.to_string())))
+        }
+
+        fn of_type(&self) -> &str {
+            "PROPERTY_EXISTS"
+        }
+    }
+
+    #[test]
+    fn register_reuses_compiled_expression_until_it_changes() {
+        let _ = FilterFactory::instance().register(Arc::new(PropertyExistsFilter));
+        let manager = ConsumerFilterManager::default();
+        assert!(manager.register("T", "g", "a", "UNKNOWN_TYPE", 1).is_none());
+
+        let first = manager
+            .register("T", "g", "a", "PROPERTY_EXISTS", 1)
+            .unwrap();
+        let same = manager
+            .register("T", "g", "a", "PROPERTY_EXISTS", 2)
+            .unwrap();
+        assert!(Arc::ptr_eq(
+            first.compiled_expression().unwrap(),
+            same.compiled_expression().unwrap()
+        ));
+        assert_eq!(same.client_version(), 2);
+
+        let stale = manager
+            .register("T", "g", "b", "PROPERTY_EXISTS", 1)
+            .unwrap();
+        assert_eq!(stale.expression().map(String::as_str), Some("a"));
+
+        let changed = manager
+            .register("T", "g", "b", "PROPERTY_EXISTS", 3)
+            .unwrap();
+        assert!(!Arc::ptr_eq(
+            first.compiled_expression().unwrap(),
+            changed.compiled_expression().unwrap()
+        ));
+        assert_eq!(
+            manager
+                .get_consumer_filter_data("T", "g")
+                .unwrap()
+                .expression()
+                .map(String::as_str),
+            Some("b")
+        );
+    }
+
+    #[test]
+    fn dead_groups_expire_and_filter_data_survives_a_restart() {
+        let _ = FilterFactory::instance().register(Arc::new(PropertyExistsFilter));
+        let manager = ConsumerFilterManager::default();
+        manager.register("T", "g", "a", "PROPERTY_EXISTS", 1);
+        manager.register("T2", "g", "b", "PROPERTY_EXISTS", 1);
+
+        let restarted = ConsumerFilterManager::default();
+        restarted.decode(manager.encode_pretty(false).as_str());
+        let decoded = restarted.get_consumer_filter_data("T", "g").unwrap();
+        assert!(decoded.compiled_expression().is_some());
+        assert_eq!(decoded.expression().map(String::as_str), Some("a"));
+
+        manager.unregister("g");
+        let dead_time = manager
+            .get_consumer_filter_data("T", "g")
+            .unwrap()
+            .dead_time();
+        assert!(dead_time > 0);
+        assert_eq!(manager.clean_expired(dead_time + 1000), 0);
+
+        // registering again revives the filter data
+        manager.register("T", "g", "a", "PROPERTY_EXISTS", 1);
+        assert_eq!(
+            manager
+                .get_consumer_filter_data("T", "g")
+                .unwrap()
+                .dead_time(),
+            0
+        );
+        assert_eq!(manager.clean_expired(dead_time + MS_24_HOUR), 1);
+        assert!(manager.get_consumer_filter_data("T2", "g").is_none());
+        assert!(manager.get_consumer_filter_data("T", "g").is_some());
+    }
 }
@@ -33,3 +33,60 @@ pub struct FilterDataMapByTopic {
     filter_data_map: HashMap<String /* consumer group */, ConsumerFilterData>,
     topic: String,
 }
+
+impl ConsumerFilterWrapper {
+    pub fn get(&self, topic: &str, consumer_group: &str) -> Option<&ConsumerFilterData> {
+        self.filter_data_by_topic
+            .get(topic)?
+            .filter_data_map
+            .get(consumer_group)
+    }
+
+    pub fn get_by_topic(&self, topic: &str) -> Vec<ConsumerFilterData> {
+        self.filter_data_by_topic
+            .get(topic)
+            .map(|by_topic| by_topic.filter_data_map.values().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    pub fn put(&mut self, filter_data: ConsumerFilterData) {
+        self.filter_data_by_topic
+            .entry(filter_data.topic().to_string())
+            .or_insert_with(|| FilterDataMapByTopic {
+                filter_data_map: HashMap::new(),
+                topic: filter_data.topic().to_string(),
+            })
+            .filter_data_map
+            .insert(filter_data.consumer_group().to_string(), filter_data);
+    }
+
+    /// Filter data of every subscription of `consumer_group`.
+    pub fn iter_mut_by_group<'a>(
+        &'a mut self,
+        consumer_group: &'a str,
+    ) -> impl Iterator<Item = &'a mut ConsumerFilterData> + 'a {
+        self.filter_data_by_topic
+            .values_mut()
+            .filter_map(move |by_topic| by_topic.filter_data_map.get_mut(consumer_group))
+    }
+
+    /// Keeps the filter data `f` returns true for, dropping the topics left without any.
+    pub fn retain(&mut self, mut f: impl FnMut(&ConsumerFilterData) -> bool) -> usize {
+        let mut removed = 0;
+        self.filter_data_by_topic.retain(|_, by_topic| {
+            let before = by_topic.filter_data_map.len();
+            by_topic
+                .filter_data_map
+                .retain(|_, filter_data| f(filter_data));
+            removed += before - by_topic.filter_data_map.len();
+            !by_topic.filter_data_map.is_empty()
+        });
+        removed
+    }
+
+    pub fn into_filter_data(self) -> impl Iterator<Item = ConsumerFilterData> {
+        self.filter_data_by_topic
+            .into_values()
+            .flat_map(|by_topic| by_topic.filter_data_map.into_values())
+    }
+}
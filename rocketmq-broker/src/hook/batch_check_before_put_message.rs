@@ -18,7 +18,7 @@ use std::collections::HashMap;
 use std::sync::Arc;
 
 use rocketmq_common::common::config::TopicConfig;
-use rocketmq_common::common::message::message_ext::MessageExt;
+use rocketmq_common::common::message::message_ext_broker_inner::MessageExtBrokerInner;
 use rocketmq_store::base::message_result::PutMessageResult;
 use rocketmq_store::hook::put_message_hook::PutMessageHook;
 
@@ -39,7 +39,10 @@ impl PutMessageHook for BatchCheckBeforePutMessageHook {
         "batchCheckBeforePutMessage".to_string()
     }
 
-    fn execute_before_put_message(&self, msg: &MessageExt) -> Option<PutMessageResult> {
-        HookUtils::check_inner_batch(&self.topic_config_table, msg)
+    fn execute_before_put_message(
+        &self,
+        msg: &mut MessageExtBrokerInner,
+    ) -> Option<PutMessageResult> {
+        HookUtils::check_inner_batch(&self.topic_config_table, &msg.message_ext_inner)
     }
 }
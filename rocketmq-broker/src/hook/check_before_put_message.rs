@@ -16,7 +16,7 @@
  */
 use std::sync::Arc;
 
-use rocketmq_common::common::message::message_ext::MessageExt;
+use rocketmq_common::common::message::message_ext_broker_inner::MessageExtBrokerInner;
 use rocketmq_store::base::message_result::PutMessageResult;
 use rocketmq_store::config::message_store_config::MessageStoreConfig;
 use rocketmq_store::hook::put_message_hook::PutMessageHook;
@@ -43,7 +43,14 @@ impl<MS: MessageStore> PutMessageHook for CheckBeforePutMessageHook<MS> {
         "checkBeforePutMessage".to_string()
     }
 
-    fn execute_before_put_message(&self, msg: &MessageExt) -> Option<PutMessageResult> {
-        HookUtils::check_before_put_message(&self.message_store, &self.message_store_config, msg)
+    fn execute_before_put_message(
+        &self,
+        msg: &mut MessageExtBrokerInner,
+    ) -> Option<PutMessageResult> {
+        HookUtils::check_before_put_message(
+            &self.message_store,
+            &self.message_store_config,
+            &msg.message_ext_inner,
+        )
     }
 }
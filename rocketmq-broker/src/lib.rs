@@ -37,11 +37,14 @@ pub(crate) mod error;
 pub(crate) mod filter;
 pub(crate) mod hook;
 pub(crate) mod long_polling;
+pub(crate) mod metadata_bootstrap;
 pub(crate) mod mqtrace;
 pub(crate) mod offset;
 pub(crate) mod out_api;
+pub(crate) mod pagecache;
 pub(crate) mod processor;
 pub(crate) mod schedule;
+pub(crate) mod slave;
 pub(crate) mod subscription;
 pub(crate) mod topic;
 pub(crate) mod util;
@@ -18,6 +18,7 @@
 use std::collections::HashMap;
 use std::sync::Arc;
 
+use bytes::Bytes;
 use rocketmq_common::common::broker::broker_config::BrokerConfig;
 use rocketmq_common::TimeUtils::get_current_millis;
 use rocketmq_store::consume_queue::consume_queue_ext::CqExtUnit;
@@ -123,7 +124,10 @@ where
                 "check hold request, topic: {}, queue_id: {}",
                 topic, queue_id
             );*/
-            let max_offset = self.message_store.get_max_offset_in_queue(topic, queue_id);
+            let max_offset = self
+                .message_store
+                .get_max_offset_in_queue(topic, queue_id)
+                .value();
             self.notify_message_arriving(topic, queue_id, max_offset);
         }
     }
@@ -139,7 +143,7 @@ where
         max_offset: i64,
         tags_code: Option<i64>,
         msg_store_time: i64,
-        filter_bit_map: Option<Vec<u8>>,
+        filter_bit_map: Option<Bytes>,
         properties: Option<&HashMap<String, String>>,
     ) {
         let key = build_key(topic, queue_id);
@@ -155,7 +159,10 @@ where
                 for request in request_list {
                     let mut newest_offset = max_offset;
                     if newest_offset <= request.pull_from_this_offset() {
-                        newest_offset = self.message_store.get_max_offset_in_queue(topic, queue_id);
+                        newest_offset = self
+                            .message_store
+                            .get_max_offset_in_queue(topic, queue_id)
+                            .value();
                     }
 
                     if newest_offset > request.pull_from_this_offset() {
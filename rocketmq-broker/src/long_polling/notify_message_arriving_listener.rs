@@ -16,6 +16,7 @@
  */
 use std::collections::HashMap;
 
+use bytes::Bytes;
 use rocketmq_store::base::message_arriving_listener::MessageArrivingListener;
 use rocketmq_store::log_file::MessageStore;
 
@@ -48,7 +49,7 @@ where
         logic_offset: i64,
         tags_code: Option<i64>,
         msg_store_time: i64,
-        filter_bit_map: Option<Vec<u8>>,
+        filter_bit_map: Option<Bytes>,
         properties: Option<&HashMap<String, String>>,
     ) {
         self.pull_request_hold_service.notify_message_arriving_ext(
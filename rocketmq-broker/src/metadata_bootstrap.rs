@@ -0,0 +1,380 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Declarative provisioning of topics and subscription groups.
+//!
+//! The broker reads `topicBootstrapFile` (`topics.yaml`) and `subscriptionGroupBootstrapFile`
+//! (`groups.yaml`) at start, after the persisted metadata is loaded and before any request is
+//! served, and creates or updates every declared entry:
+//!
+//! ```yaml
+//! topics:
+//!   - name: TopicTest
+//!     readQueueNums: 8
+//!     writeQueueNums: 8
+//!     perm: 6
+//!     attributes:
+//!       message.type: NORMAL
+//! ```
+//!
+//! ```yaml
+//! groups:
+//!   - name: please_rename_unique_group_name
+//!     consumeBroadcastEnable: false
+//!     retryMaxTimes: 3
+//! ```
+//!
+//! Fields left out keep their current value, or the default for entries which do not exist yet,
+//! and entries which are not declared are never touched, so applying the same files again is a
+//! no-op.
+
+use std::collections::HashMap;
+
+use config::Config;
+use config::FileFormat;
+use rocketmq_common::common::attribute::AttributeTrait;
+use rocketmq_common::common::broker::broker_config::BrokerConfig;
+use rocketmq_common::common::config::TopicConfig;
+use rocketmq_common::common::constant::PermName;
+use rocketmq_common::common::topic::TopicValidator;
+use rocketmq_common::TopicAttributes::ALL;
+use rocketmq_remoting::protocol::subscription::subscription_group_config::SubscriptionGroupConfig;
+use rocketmq_store::message_store::default_message_store::DefaultMessageStore;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use tracing::info;
+
+use crate::error::BrokerError;
+use crate::subscription::manager::subscription_group_manager::SubscriptionGroupManager;
+use crate::subscription::manager::subscription_group_manager::CHARACTER_MAX_LENGTH;
+use crate::topic::manager::topic_config_manager::TopicConfigManager;
+use crate::BrokerResult;
+
+#[derive(Debug, Default, Deserialize)]
+pub(crate) struct TopicsBootstrap {
+    #[serde(default)]
+    pub topics: Vec<TopicDeclaration>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct TopicDeclaration {
+    pub name: String,
+    pub read_queue_nums: Option<u32>,
+    pub write_queue_nums: Option<u32>,
+    pub perm: Option<u32>,
+    pub order: Option<bool>,
+    #[serde(default)]
+    pub attributes: HashMap<String, String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub(crate) struct GroupsBootstrap {
+    #[serde(default)]
+    pub groups: Vec<GroupDeclaration>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct GroupDeclaration {
+    pub name: String,
+    pub consume_enable: Option<bool>,
+    pub consume_from_min_enable: Option<bool>,
+    pub consume_broadcast_enable: Option<bool>,
+    pub consume_message_orderly: Option<bool>,
+    pub retry_queue_nums: Option<i32>,
+    pub retry_max_times: Option<i32>,
+    pub broker_id: Option<u64>,
+    pub which_broker_when_consume_slowly: Option<u64>,
+    pub notify_consumer_ids_changed_enable: Option<bool>,
+    pub consume_timeout_minute: Option<i32>,
+    #[serde(default)]
+    pub attributes: HashMap<String, String>,
+}
+
+/// Applies the bootstrap files configured in `broker_config`, returns the number of topics and
+/// groups created or updated. Nothing is applied when a file is malformed or declares an
+/// invalid entry.
+pub(crate) fn provision(
+    broker_config: &BrokerConfig,
+    topic_config_manager: &TopicConfigManager,
+    subscription_group_manager: &SubscriptionGroupManager<DefaultMessageStore>,
+) -> BrokerResult<usize> {
+    let mut topic_configs = Vec::new();
+    if let Some(path) = broker_config.topic_bootstrap_file.as_deref() {
+        let bootstrap = load::<TopicsBootstrap>(path)?;
+        for declaration in &bootstrap.topics {
+            let current = topic_config_manager.select_topic_config(declaration.name.as_str());
+            topic_configs.push(plan_topic_config(
+                broker_config,
+                declaration,
+                current.as_ref(),
+            )?);
+        }
+    }
+    let mut group_configs = Vec::new();
+    if let Some(path) = broker_config.subscription_group_bootstrap_file.as_deref() {
+        let bootstrap = load::<GroupsBootstrap>(path)?;
+        for declaration in &bootstrap.groups {
+            let current = subscription_group_manager
+                .find_subscription_group_config_inner(declaration.name.as_str());
+            group_configs.push(plan_subscription_group_config(
+                declaration,
+                current.as_ref(),
+            )?);
+        }
+    }
+
+    let declared = topic_configs.len() + group_configs.len();
+    let mut changed = 0;
+    for topic_config in topic_configs {
+        if topic_config_manager.upsert_topic_config(topic_config) {
+            changed += 1;
+        }
+    }
+    for group_config in group_configs {
+        if subscription_group_manager.upsert_subscription_group_config(group_config) {
+            changed += 1;
+        }
+    }
+    if declared > 0 {
+        info!(
+            "metadata bootstrap applied, declared: {}, changed: {}",
+            declared, changed
+        );
+    }
+    Ok(changed)
+}
+
+fn load<T: DeserializeOwned>(path: &str) -> BrokerResult<T> {
+    Config::builder()
+        .add_source(config::File::new(path, FileFormat::Yaml))
+        .build()
+        .and_then(|config| config.try_deserialize::<T>())
+        .map_err(|e| BrokerError::IllegalMetadataBootstrap(format!("load {} failed: {}", path, e)))
+}
+
+/// Returns the topic config `declaration` asks for on top of the `current` one.
+pub(crate) fn plan_topic_config(
+    broker_config: &BrokerConfig,
+    declaration: &TopicDeclaration,
+    current: Option<&TopicConfig>,
+) -> BrokerResult<TopicConfig> {
+    let topic = declaration.name.as_str();
+    let result = TopicValidator::validate_topic(topic);
+    if !result.valid() {
+        return Err(BrokerError::IllegalMetadataBootstrap(format!(
+            "topic [{}]: {}",
+            topic,
+            result.remark()
+        )));
+    }
+    if broker_config.validate_system_topic_when_update_topic
+        && TopicValidator::is_system_topic(topic)
+    {
+        return Err(BrokerError::IllegalMetadataBootstrap(format!(
+            "topic [{}] is conflict with system topic",
+            topic
+        )));
+    }
+
+    let mut topic_config = current.cloned().unwrap_or_else(|| TopicConfig::new(topic));
+    if let Some(read_queue_nums) = declaration.read_queue_nums {
+        topic_config.read_queue_nums = read_queue_nums;
+    }
+    if let Some(write_queue_nums) = declaration.write_queue_nums {
+        topic_config.write_queue_nums = write_queue_nums;
+    }
+    if let Some(perm) = declaration.perm {
+        if !PermName::is_valid(perm) {
+            return Err(BrokerError::IllegalMetadataBootstrap(format!(
+                "topic [{}]: invalid perm {}",
+                topic, perm
+            )));
+        }
+        topic_config.perm = perm;
+    }
+    if let Some(order) = declaration.order {
+        topic_config.order = order;
+    }
+    for (key, value) in &declaration.attributes {
+        let Some(attribute) = ALL.get(key) else {
+            return Err(BrokerError::IllegalMetadataBootstrap(format!(
+                "topic [{}]: unsupported attribute {}",
+                topic, key
+            )));
+        };
+        if !attribute.get_universe().contains(value) {
+            return Err(BrokerError::IllegalMetadataBootstrap(format!(
+                "topic [{}]: value {} of attribute {} is not one of {:?}",
+                topic,
+                value,
+                key,
+                attribute.get_universe()
+            )));
+        }
+        let previous = topic_config.attributes.insert(key.clone(), value.clone());
+        if current.is_some() && !attribute.changeable() && previous.as_ref() != Some(value) {
+            return Err(BrokerError::IllegalMetadataBootstrap(format!(
+                "topic [{}]: attribute {} can not be changed",
+                topic, key
+            )));
+        }
+    }
+    Ok(topic_config)
+}
+
+/// Returns the subscription group config `declaration` asks for on top of the `current` one.
+pub(crate) fn plan_subscription_group_config(
+    declaration: &GroupDeclaration,
+    current: Option<&SubscriptionGroupConfig>,
+) -> BrokerResult<SubscriptionGroupConfig> {
+    let group = declaration.name.as_str();
+    if group.is_empty()
+        || group.len() > CHARACTER_MAX_LENGTH
+        || TopicValidator::is_topic_or_group_illegal(group)
+    {
+        return Err(BrokerError::IllegalMetadataBootstrap(format!(
+            "group [{}] is illegal",
+            group
+        )));
+    }
+
+    let mut config = current
+        .cloned()
+        .unwrap_or_else(|| SubscriptionGroupConfig::new(group));
+    if let Some(value) = declaration.consume_enable {
+        config.set_consume_enable(value);
+    }
+    if let Some(value) = declaration.consume_from_min_enable {
+        config.set_consume_from_min_enable(value);
+    }
+    if let Some(value) = declaration.consume_broadcast_enable {
+        config.set_consume_broadcast_enable(value);
+    }
+    if let Some(value) = declaration.consume_message_orderly {
+        config.set_consume_message_orderly(value);
+    }
+    if let Some(value) = declaration.retry_queue_nums {
+        config.set_retry_queue_nums(value);
+    }
+    if let Some(value) = declaration.retry_max_times {
+        config.set_retry_max_times(value);
+    }
+    if let Some(value) = declaration.broker_id {
+        config.set_broker_id(value);
+    }
+    if let Some(value) = declaration.which_broker_when_consume_slowly {
+        config.set_which_broker_when_consume_slowly(value);
+    }
+    if let Some(value) = declaration.notify_consumer_ids_changed_enable {
+        config.set_notify_consumer_ids_changed_enable(value);
+    }
+    if let Some(value) = declaration.consume_timeout_minute {
+        config.set_consume_timeout_minute(value);
+    }
+    if !declaration.attributes.is_empty() {
+        let mut attributes = config.attributes().clone();
+        attributes.extend(declaration.attributes.clone());
+        config.set_attributes(attributes);
+    }
+    Ok(config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse<T: DeserializeOwned>(yaml: &str) -> T {
+        Config::builder()
+            .add_source(config::File::from_str(yaml, FileFormat::Yaml))
+            .build()
+            .unwrap()
+            .try_deserialize::<T>()
+            .unwrap()
+    }
+
+    #[test]
+    fn plan_topic_config_merges_declaration_over_current() {
+        let bootstrap = parse::<TopicsBootstrap>(
+            "topics:\n  - name: TopicTest\n    writeQueueNums: 4\n    attributes:\n      \
+             message.type: FIFO\n",
+        );
+        let broker_config = BrokerConfig::default();
+        let declaration = &bootstrap.topics[0];
+
+        let created = plan_topic_config(&broker_config, declaration, None).unwrap();
+        assert_eq!(created.topic_name.as_deref(), Some("TopicTest"));
+        assert_eq!(created.write_queue_nums, 4);
+        assert_eq!(
+            created.read_queue_nums,
+            TopicConfig::default().read_queue_nums
+        );
+        assert_eq!(created.attributes.get("message.type").unwrap(), "FIFO");
+
+        let again = plan_topic_config(&broker_config, declaration, Some(&created)).unwrap();
+        assert_eq!(again, created);
+    }
+
+    #[test]
+    fn plan_topic_config_rejects_invalid_declarations() {
+        let broker_config = BrokerConfig::default();
+        let unsupported = TopicDeclaration {
+            name: "TopicTest".to_string(),
+            attributes: HashMap::from([("unknown".to_string(), "x".to_string())]),
+            ..Default::default()
+        };
+        assert!(plan_topic_config(&broker_config, &unsupported, None).is_err());
+
+        let mut current = TopicConfig::new("TopicTest");
+        current
+            .attributes
+            .insert("queue.type".to_string(), "SimpleCQ".to_string());
+        let unchangeable = TopicDeclaration {
+            name: "TopicTest".to_string(),
+            attributes: HashMap::from([("queue.type".to_string(), "BatchCQ".to_string())]),
+            ..Default::default()
+        };
+        assert!(plan_topic_config(&broker_config, &unchangeable, Some(&current)).is_err());
+
+        let system = TopicDeclaration {
+            name: TopicValidator::RMQ_SYS_SCHEDULE_TOPIC.to_string(),
+            ..Default::default()
+        };
+        assert!(plan_topic_config(&broker_config, &system, None).is_err());
+    }
+
+    #[test]
+    fn plan_subscription_group_config_keeps_undeclared_fields() {
+        let bootstrap = parse::<GroupsBootstrap>(
+            "groups:\n  - name: group_a\n    consumeBroadcastEnable: false\n    retryMaxTimes: 3\n",
+        );
+        let mut current = SubscriptionGroupConfig::new("group_a");
+        current.set_consume_timeout_minute(30);
+
+        let config = plan_subscription_group_config(&bootstrap.groups[0], Some(&current)).unwrap();
+        assert!(!config.consume_broadcast_enable());
+        assert_eq!(config.retry_max_times(), 3);
+        assert_eq!(config.consume_timeout_minute(), 30);
+
+        let illegal = GroupDeclaration {
+            name: "group a".to_string(),
+            ..Default::default()
+        };
+        assert!(plan_subscription_group_config(&illegal, None).is_err());
+    }
+}
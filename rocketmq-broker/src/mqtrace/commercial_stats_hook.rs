@@ -0,0 +1,96 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::sync::Arc;
+
+use rocketmq_store::stats::broker_stats_manager::BrokerStatsManager;
+
+use crate::mqtrace::consume_message_context::ConsumeMessageContext;
+use crate::mqtrace::consume_message_hook::ConsumeMessageHook;
+use crate::mqtrace::send_message_context::SendMessageContext;
+use crate::mqtrace::send_message_hook::SendMessageHook;
+
+/// Records the billed messages and bytes of every handled send into the commercial stats of
+/// the [`BrokerStatsManager`], per owner, topic and producer group.
+pub struct CommercialSendMessageHook {
+    broker_stats_manager: Arc<BrokerStatsManager>,
+}
+
+impl CommercialSendMessageHook {
+    pub fn new(broker_stats_manager: Arc<BrokerStatsManager>) -> Self {
+        Self {
+            broker_stats_manager,
+        }
+    }
+}
+
+impl SendMessageHook for CommercialSendMessageHook {
+    fn hook_name(&self) -> &str {
+        "CommercialSendMessageHook"
+    }
+
+    fn send_message_before(&self, _context: &SendMessageContext) {}
+
+    fn send_message_after(&self, context: &SendMessageContext) {
+        if context.commercial_send_times <= 0 {
+            return;
+        }
+        self.broker_stats_manager.inc_commercial_value(
+            context.commercial_owner.as_str(),
+            context.topic.as_str(),
+            context.producer_group.as_str(),
+            context.commercial_send_stats,
+            context.commercial_send_times,
+            context.commercial_send_size,
+        );
+    }
+}
+
+/// Records the billed messages and bytes of every handled pull into the commercial stats of
+/// the [`BrokerStatsManager`], per owner, topic and consumer group.
+pub struct CommercialConsumeMessageHook {
+    broker_stats_manager: Arc<BrokerStatsManager>,
+}
+
+impl CommercialConsumeMessageHook {
+    pub fn new(broker_stats_manager: Arc<BrokerStatsManager>) -> Self {
+        Self {
+            broker_stats_manager,
+        }
+    }
+}
+
+impl ConsumeMessageHook for CommercialConsumeMessageHook {
+    fn hook_name(&self) -> &str {
+        "CommercialConsumeMessageHook"
+    }
+
+    fn consume_message_before(&self, context: &mut ConsumeMessageContext) {
+        if context.commercial_rcv_times <= 0 {
+            return;
+        }
+        self.broker_stats_manager.inc_commercial_value(
+            context.commercial_owner.as_deref().unwrap_or_default(),
+            context.topic.as_str(),
+            context.consumer_group.as_str(),
+            context.commercial_rcv_stats,
+            context.commercial_rcv_times,
+            context.commercial_rcv_size,
+        );
+    }
+
+    fn consume_message_after(&self, _context: &mut ConsumeMessageContext) {}
+}
@@ -202,6 +202,23 @@ impl ConsumerOffsetManager {
         }
         topics
     }
+
+    /// Copies the offsets of `src_group` on `topic` to `dest_group`, replacing the ones it had.
+    /// Returns whether `src_group` had offsets to copy.
+    pub fn clone_offset(&self, src_group: &str, dest_group: &str, topic: &str) -> bool {
+        let mut offset_table = self.consumer_offset_wrapper.offset_table.write();
+        let Some(offsets) = offset_table
+            .get(format!("{}{}{}", topic, TOPIC_GROUP_SEPARATOR, src_group).as_str())
+            .cloned()
+        else {
+            return false;
+        };
+        offset_table.insert(
+            format!("{}{}{}", topic, TOPIC_GROUP_SEPARATOR, dest_group),
+            offsets,
+        );
+        true
+    }
 }
 
 impl ConfigManager for ConsumerOffsetManager {
@@ -268,6 +285,30 @@ impl ConsumerOffsetManager {
     }
 }
 
+impl ConsumerOffsetManager {
+    /// Merges the offsets pulled from the master, encoded as `consumerOffset.json`, into the local
+    /// offset table and persists it.
+    pub fn sync_offset_table(&self, json_string: &str) -> bool {
+        let wrapper = match SerdeJsonUtils::from_json_str::<ConsumerOffsetWrapper>(json_string) {
+            Ok(wrapper) => wrapper,
+            Err(e) => {
+                warn!("decode consumer offset from master failed: {}", e);
+                return false;
+            }
+        };
+        self.consumer_offset_wrapper
+            .offset_table
+            .write()
+            .extend(wrapper.offset_table.read().clone());
+        self.consumer_offset_wrapper
+            .data_version
+            .mut_from_ref()
+            .assign_new_one(wrapper.data_version.as_ref());
+        self.persist();
+        true
+    }
+}
+
 #[derive(Default, Clone)]
 struct ConsumerOffsetWrapper {
     data_version: ArcRefCellWrapper<DataVersion>,
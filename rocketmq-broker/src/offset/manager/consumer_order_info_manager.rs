@@ -16,17 +16,24 @@
  */
 
 use std::collections::HashMap;
+use std::collections::HashSet;
 use std::ops::Deref;
 use std::sync::Arc;
 
 use rocketmq_common::common::broker::broker_config::BrokerConfig;
 use rocketmq_common::common::config_manager::ConfigManager;
+use rocketmq_common::TimeUtils::get_current_millis;
 use serde::Deserialize;
 use serde::Serialize;
+use tracing::warn;
 
 use crate::broker_path_config_helper::get_consumer_order_info_path;
+use crate::offset::manager::consumer_offset_manager::TOPIC_GROUP_SEPARATOR;
 use crate::offset::manager::consumer_order_info_lock_manager::ConsumerOrderInfoLockManager;
 
+/// Tracks, per queue of an orderly POP consumer group, the offsets of the batch last popped and
+/// which of them have been acked, so the next batch of a queue is only delivered once the
+/// previous one is acked or became visible again.
 #[derive(Default)]
 pub(crate) struct ConsumerOrderInfoManager {
     pub(crate) broker_config: Arc<BrokerConfig>,
@@ -34,6 +41,158 @@ pub(crate) struct ConsumerOrderInfoManager {
     pub(crate) consumer_order_info_lock_manager: Option<ConsumerOrderInfoLockManager>,
 }
 
+impl ConsumerOrderInfoManager {
+    /// Records the batch popped from `queue_id` at `pop_time`. The consumed counts of the
+    /// offsets already delivered by an earlier attempt are carried over.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn update(
+        &self,
+        attempt_id: &str,
+        topic: &str,
+        group: &str,
+        queue_id: i32,
+        pop_time: u64,
+        invisible_time: u64,
+        msg_queue_offset_list: &[u64],
+    ) {
+        let mut wrapper = self.consumer_order_info_wrapper.lock();
+        let queues = wrapper.table.entry(build_key(topic, group)).or_default();
+        let mut order_info = OrderInfo::new(
+            attempt_id,
+            pop_time,
+            invisible_time,
+            msg_queue_offset_list,
+            get_current_millis(),
+        );
+        if let Some(pre_order_info) = queues.get(&queue_id) {
+            order_info.merge_offset_consumed_count(pre_order_info);
+        }
+        queues.insert(queue_id, order_info);
+    }
+
+    /// Whether delivering from `queue_id` must wait because a batch popped by another attempt
+    /// is neither acked nor visible again.
+    pub(crate) fn check_block(
+        &self,
+        attempt_id: &str,
+        topic: &str,
+        group: &str,
+        queue_id: i32,
+        invisible_time: u64,
+    ) -> bool {
+        self.consumer_order_info_wrapper
+            .lock()
+            .table
+            .get(&build_key(topic, group))
+            .and_then(|queues| queues.get(&queue_id))
+            .is_some_and(|order_info| {
+                order_info.need_block(attempt_id, invisible_time, get_current_millis())
+            })
+    }
+
+    /// Acks `queue_offset` of the batch popped at `pop_time` and returns the offset to commit:
+    /// the first offset not acked yet, or the one after the batch when all are acked. Returns
+    /// -1 when the offset is not part of the batch and -2 when the batch was popped again
+    /// since.
+    pub(crate) fn commit_and_next(
+        &self,
+        topic: &str,
+        group: &str,
+        queue_id: i32,
+        queue_offset: u64,
+        pop_time: u64,
+    ) -> i64 {
+        let mut wrapper = self.consumer_order_info_wrapper.lock();
+        let Some(order_info) = wrapper
+            .table
+            .get_mut(&build_key(topic, group))
+            .and_then(|queues| queues.get_mut(&queue_id))
+        else {
+            warn!(
+                "no order info of topic {}, group {}, queue {} to commit offset {}",
+                topic, group, queue_id, queue_offset
+            );
+            return queue_offset as i64 + 1;
+        };
+        if order_info.offset_list.is_empty() {
+            return -1;
+        }
+        if order_info.pop_time != pop_time {
+            warn!(
+                "popTime {} of topic {}, group {}, queue {} does not match the last pop {}",
+                pop_time, topic, group, queue_id, order_info.pop_time
+            );
+            return -2;
+        }
+        let Some(index) =
+            (0..order_info.offset_list.len()).find(|i| order_info.queue_offset(*i) == queue_offset)
+        else {
+            return -1;
+        };
+        if index < u64::BITS as usize {
+            order_info.commit_offset_bit |= 1 << index;
+        }
+        order_info.next_offset()
+    }
+
+    /// Postpones the redelivery of `queue_offset` popped at `pop_time` to `next_visible_time`,
+    /// for a changed invisible time.
+    pub(crate) fn update_next_visible_time(
+        &self,
+        topic: &str,
+        group: &str,
+        queue_id: i32,
+        queue_offset: u64,
+        pop_time: u64,
+        next_visible_time: u64,
+    ) {
+        let mut wrapper = self.consumer_order_info_wrapper.lock();
+        let Some(order_info) = wrapper
+            .table
+            .get_mut(&build_key(topic, group))
+            .and_then(|queues| queues.get_mut(&queue_id))
+        else {
+            return;
+        };
+        if order_info.pop_time != pop_time {
+            return;
+        }
+        order_info
+            .offset_next_visible_time
+            .insert(queue_offset, next_visible_time);
+    }
+
+    /// How many times `queue_offset` of `queue_id` has been delivered before the last pop.
+    pub(crate) fn consumed_count(
+        &self,
+        topic: &str,
+        group: &str,
+        queue_id: i32,
+        queue_offset: u64,
+    ) -> i32 {
+        self.consumer_order_info_wrapper
+            .lock()
+            .table
+            .get(&build_key(topic, group))
+            .and_then(|queues| queues.get(&queue_id))
+            .and_then(|order_info| order_info.offset_consumed_count.get(&queue_offset))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Drops the order info of `topic@group`, e.g. after the topic or group was deleted.
+    pub(crate) fn remove(&self, topic: &str, group: &str) {
+        self.consumer_order_info_wrapper
+            .lock()
+            .table
+            .remove(&build_key(topic, group));
+    }
+}
+
+fn build_key(topic: &str, group: &str) -> String {
+    format!("{}{}{}", topic, TOPIC_GROUP_SEPARATOR, group)
+}
+
 //Fully implemented will be removed
 #[allow(unused_variables)]
 impl ConfigManager for ConsumerOrderInfoManager {
@@ -42,7 +201,7 @@ impl ConfigManager for ConsumerOrderInfoManager {
     }
 
     fn stop(&mut self) -> bool {
-        todo!()
+        true
     }
 
     fn config_file_path(&self) -> String {
@@ -50,11 +209,17 @@ impl ConfigManager for ConsumerOrderInfoManager {
     }
 
     fn encode(&mut self) -> String {
-        todo!()
+        self.encode_pretty(false)
     }
 
     fn encode_pretty(&self, pretty_format: bool) -> String {
-        "".to_string()
+        let wrapper = self.consumer_order_info_wrapper.lock();
+        let json = if pretty_format {
+            serde_json::to_string_pretty(wrapper.deref())
+        } else {
+            serde_json::to_string(wrapper.deref())
+        };
+        json.unwrap_or_default()
     }
 
     fn decode(&self, json_string: &str) {
@@ -89,7 +254,8 @@ pub(crate) struct OrderInfo {
     pop_time: u64,
     #[serde(rename = "i")]
     invisible_time: Option<u64>,
-    #[serde(rename = "0")]
+    /// The first offset of the batch followed by the distance of the others to it.
+    #[serde(rename = "o")]
     offset_list: Vec<u64>,
     #[serde(rename = "ot")]
     offset_next_visible_time: HashMap<u64, u64>,
@@ -102,3 +268,168 @@ pub(crate) struct OrderInfo {
     #[serde(rename = "a")]
     attempt_id: String,
 }
+
+impl OrderInfo {
+    fn new(
+        attempt_id: &str,
+        pop_time: u64,
+        invisible_time: u64,
+        queue_offsets: &[u64],
+        last_consume_timestamp: u64,
+    ) -> Self {
+        let offset_list = match queue_offsets.first() {
+            Some(first) => std::iter::once(*first)
+                .chain(queue_offsets[1..].iter().map(|offset| offset - first))
+                .collect(),
+            None => Vec::new(),
+        };
+        OrderInfo {
+            pop_time,
+            invisible_time: Some(invisible_time),
+            offset_list,
+            last_consume_timestamp,
+            attempt_id: attempt_id.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn queue_offset(&self, index: usize) -> u64 {
+        if index == 0 {
+            self.offset_list[0]
+        } else {
+            self.offset_list[0] + self.offset_list[index]
+        }
+    }
+
+    fn is_not_ack(&self, index: usize) -> bool {
+        index < u64::BITS as usize && self.commit_offset_bit & (1 << index) == 0
+    }
+
+    fn need_block(&self, attempt_id: &str, current_invisible_time: u64, now: u64) -> bool {
+        if self.offset_list.is_empty() || self.attempt_id == attempt_id {
+            return false;
+        }
+        let invisible_time = self
+            .invisible_time
+            .filter(|invisible_time| *invisible_time > 0)
+            .unwrap_or(current_invisible_time);
+        (0..self.offset_list.len())
+            .filter(|i| self.is_not_ack(*i))
+            .any(|i| {
+                let next_visible_time = self
+                    .offset_next_visible_time
+                    .get(&self.queue_offset(i))
+                    .copied()
+                    .unwrap_or(self.pop_time + invisible_time);
+                now < next_visible_time
+            })
+    }
+
+    fn next_offset(&self) -> i64 {
+        if self.offset_list.is_empty() {
+            return -2;
+        }
+        match (0..self.offset_list.len()).find(|i| self.is_not_ack(*i)) {
+            Some(index) => self.queue_offset(index) as i64,
+            None => self.queue_offset(self.offset_list.len() - 1) as i64 + 1,
+        }
+    }
+
+    fn merge_offset_consumed_count(&mut self, pre: &OrderInfo) {
+        if pre.attempt_id == self.attempt_id {
+            self.offset_consumed_count = pre.offset_consumed_count.clone();
+            return;
+        }
+        let pre_queue_offsets = (0..pre.offset_list.len())
+            .map(|i| pre.queue_offset(i))
+            .collect::<HashSet<_>>();
+        self.offset_consumed_count = (0..self.offset_list.len())
+            .map(|i| self.queue_offset(i))
+            .filter(|queue_offset| pre_queue_offsets.contains(queue_offset))
+            .map(|queue_offset| {
+                let count = pre
+                    .offset_consumed_count
+                    .get(&queue_offset)
+                    .map_or(1, |count| count + 1);
+                (queue_offset, count)
+            })
+            .collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blocks_until_batch_acked() {
+        let manager = ConsumerOrderInfoManager::default();
+        let now = get_current_millis();
+        manager.update("a1", "TopicTest", "group", 0, now, 60_000, &[10, 11, 12]);
+
+        assert!(manager.check_block("a2", "TopicTest", "group", 0, 60_000));
+        assert!(!manager.check_block("a1", "TopicTest", "group", 0, 60_000));
+        assert!(!manager.check_block("a2", "TopicTest", "group", 1, 60_000));
+
+        assert_eq!(
+            manager.commit_and_next("TopicTest", "group", 0, 11, now),
+            10
+        );
+        assert_eq!(
+            manager.commit_and_next("TopicTest", "group", 0, 10, now),
+            12
+        );
+        assert_eq!(
+            manager.commit_and_next("TopicTest", "group", 0, 13, now),
+            -1
+        );
+        assert_eq!(
+            manager.commit_and_next("TopicTest", "group", 0, 12, now + 1),
+            -2
+        );
+        assert!(manager.check_block("a2", "TopicTest", "group", 0, 60_000));
+        assert_eq!(
+            manager.commit_and_next("TopicTest", "group", 0, 12, now),
+            13
+        );
+        assert!(!manager.check_block("a2", "TopicTest", "group", 0, 60_000));
+    }
+
+    #[test]
+    fn unblocks_once_invisible_time_expired() {
+        let manager = ConsumerOrderInfoManager::default();
+        let now = get_current_millis();
+        manager.update("a1", "TopicTest", "group", 0, now - 2_000, 1_000, &[10, 11]);
+        assert!(!manager.check_block("a2", "TopicTest", "group", 0, 1_000));
+
+        manager.update_next_visible_time("TopicTest", "group", 0, 11, now - 2_000, now + 60_000);
+        assert!(manager.check_block("a2", "TopicTest", "group", 0, 1_000));
+    }
+
+    #[test]
+    fn counts_redelivered_offsets() {
+        let manager = ConsumerOrderInfoManager::default();
+        let now = get_current_millis();
+        manager.update("a1", "TopicTest", "group", 0, now, 1_000, &[10, 11]);
+        manager.update("a2", "TopicTest", "group", 0, now, 1_000, &[11, 12]);
+        manager.update("a3", "TopicTest", "group", 0, now, 1_000, &[11, 12]);
+
+        assert_eq!(manager.consumed_count("TopicTest", "group", 0, 10), 0);
+        assert_eq!(manager.consumed_count("TopicTest", "group", 0, 11), 2);
+        assert_eq!(manager.consumed_count("TopicTest", "group", 0, 12), 1);
+    }
+
+    #[test]
+    fn encode_decode_round_trip() {
+        let manager = ConsumerOrderInfoManager::default();
+        manager.update("a1", "TopicTest", "group", 3, 100, 1_000, &[10, 12]);
+        let json = manager.encode_pretty(false);
+
+        let decoded = ConsumerOrderInfoManager::default();
+        decoded.decode(json.as_str());
+        assert_eq!(
+            decoded.commit_and_next("TopicTest", "group", 3, 12, 100),
+            10
+        );
+    }
+}
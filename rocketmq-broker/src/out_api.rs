@@ -16,3 +16,4 @@
  */
 
 pub(crate) mod broker_outer_api;
+pub(crate) mod registered_topic_configs;
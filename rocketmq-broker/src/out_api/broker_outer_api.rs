@@ -16,10 +16,13 @@
  */
 use std::sync::Arc;
 
+use bytes::Bytes;
 use dns_lookup::lookup_host;
 use rocketmq_common::common::broker::broker_config::BrokerIdentity;
 use rocketmq_common::common::config::TopicConfig;
+use rocketmq_common::common::mix_all;
 use rocketmq_common::utils::crc32_utils;
+use rocketmq_common::utils::network_util::NetworkUtil;
 use rocketmq_common::utils::serde_json_utils::SerdeJsonUtils;
 use rocketmq_remoting::clients::rocketmq_default_impl::RocketmqDefaultClient;
 use rocketmq_remoting::clients::RemotingClient;
@@ -46,6 +49,9 @@ use tracing::debug;
 use tracing::error;
 use tracing::info;
 
+use crate::error::BrokerError;
+use crate::BrokerResult;
+
 #[derive(Clone)]
 pub struct BrokerOuterAPI {
     remoting_client: RocketmqDefaultClient,
@@ -128,6 +134,11 @@ impl BrokerOuterAPI {
             .await;
     }
 
+    /// Number of name servers a registration is sent to.
+    pub fn name_server_count(&self) -> usize {
+        self.remoting_client.get_available_name_srv_list().len()
+    }
+
     pub async fn register_broker_all(
         &self,
         cluster_name: String,
@@ -141,6 +152,7 @@ impl BrokerOuterAPI {
         timeout_mills: u64,
         enable_acting_master: bool,
         compressed: bool,
+        incremental: bool,
         heartbeat_timeout_millis: Option<i64>,
         _broker_identity: BrokerIdentity,
     ) -> Vec<RegisterBrokerResult> {
@@ -154,9 +166,10 @@ impl BrokerOuterAPI {
                 cluster_name,
                 ha_server_addr,
                 enable_acting_master: Some(enable_acting_master),
-                compressed: false,
+                compressed,
                 heartbeat_timeout_millis,
                 body_crc32: 0,
+                incremental,
             };
 
             //build request body
@@ -219,9 +232,15 @@ impl BrokerOuterAPI {
             request_header,
             body
         );
-        let request =
+        let mut request =
             RemotingCommand::create_request_command(RequestCode::RegisterBroker, request_header)
                 .set_body(Some(body.clone()));
+        // The name servers publish the zone in the routes so clients can stay in their zone.
+        if let Ok(zone_name) = std::env::var(mix_all::ROCKETMQ_ZONE_ENV) {
+            if !zone_name.is_empty() {
+                request.add_ext_field(mix_all::ZONE_NAME, zone_name);
+            }
+        }
         if oneway {
             self.remoting_client
                 .invoke_oneway(namesrv_addr, request, timeout_mills)
@@ -293,6 +312,67 @@ impl BrokerOuterAPI {
         }
     }
 
+    pub async fn get_all_topic_config(
+        &self,
+        addr: &str,
+    ) -> BrokerResult<TopicConfigAndMappingSerializeWrapper> {
+        let body = self
+            .invoke_for_body(addr, RequestCode::GetAllTopicConfig)
+            .await?;
+        SerdeJsonUtils::decode::<TopicConfigAndMappingSerializeWrapper>(body.as_ref()).map_err(
+            |e| {
+                BrokerError::Response(
+                    ResponseCode::SystemError as i32,
+                    e.to_string(),
+                    addr.to_string(),
+                )
+            },
+        )
+    }
+
+    /// Returns the consumer offsets of the broker at `addr`, encoded as the `consumerOffset.json`
+    /// config file.
+    pub async fn get_all_consumer_offset(&self, addr: &str) -> BrokerResult<String> {
+        let body = self
+            .invoke_for_body(addr, RequestCode::GetAllConsumerOffset)
+            .await?;
+        Ok(String::from_utf8_lossy(body.as_ref()).into_owned())
+    }
+
+    /// Returns the delay offsets of the broker at `addr`, encoded as the `delayOffset.json`
+    /// config file.
+    pub async fn get_all_delay_offset(&self, addr: &str) -> BrokerResult<String> {
+        let body = self
+            .invoke_for_body(addr, RequestCode::GetAllDelayOffset)
+            .await?;
+        Ok(String::from_utf8_lossy(body.as_ref()).into_owned())
+    }
+
+    /// Returns the subscription groups of the broker at `addr`, encoded as the
+    /// `subscriptionGroup.json` config file.
+    pub async fn get_all_subscription_group_config(&self, addr: &str) -> BrokerResult<String> {
+        let body = self
+            .invoke_for_body(addr, RequestCode::GetAllSubscriptionGroupConfig)
+            .await?;
+        Ok(String::from_utf8_lossy(body.as_ref()).into_owned())
+    }
+
+    async fn invoke_for_body(&self, addr: &str, request_code: RequestCode) -> BrokerResult<Bytes> {
+        let request = RemotingCommand::create_remoting_command(request_code);
+        let response = self
+            .remoting_client
+            .invoke_async(Some(addr.to_string()), request, 3000)
+            .await?;
+        match ResponseCode::from(response.code()) {
+            ResponseCode::Success => Ok(response.body().clone().unwrap_or_default()),
+            _ => Err(BrokerError::Response(
+                response.code(),
+                response.remark().cloned().unwrap_or_default(),
+                addr.to_string(),
+            )),
+        }
+    }
+
     pub fn shutdown(&self) {}
 
     pub fn refresh_metadata(&self) {}
@@ -306,27 +386,24 @@ fn dns_lookup_address_by_domain(domain: &str) -> Vec<String> {
     let mut address_list = Vec::new();
     // Ensure logging is initialized
 
-    match domain.find(':') {
-        Some(index) => {
-            let (domain_str, port_str) = domain.split_at(index);
-            match lookup_host(domain_str) {
-                Ok(addresses) => {
-                    for address in addresses {
-                        address_list.push(format!("{}{}", address, port_str));
-                    }
-                    info!(
-                        "DNS lookup address by domain success, domain={}, result={:?}",
-                        domain, address_list
-                    );
-                }
-                Err(e) => {
-                    error!(
-                        "DNS lookup address by domain error, domain={}, error={}",
-                        domain, e
-                    );
+    match NetworkUtil::split_host_port(domain) {
+        Some((domain_str, port)) => match lookup_host(domain_str) {
+            Ok(addresses) => {
+                for address in addresses {
+                    address_list.push(NetworkUtil::host_port(&address.to_string(), port));
                 }
+                info!(
+                    "DNS lookup address by domain success, domain={}, result={:?}",
+                    domain, address_list
+                );
             }
-        }
+            Err(e) => {
+                error!(
+                    "DNS lookup address by domain error, domain={}, error={}",
+                    domain, e
+                );
+            }
+        },
         None => {
             error!("Invalid domain format, missing port: {}", domain);
         }
@@ -0,0 +1,126 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::collections::HashMap;
+
+use parking_lot::Mutex;
+use rocketmq_common::common::config::TopicConfig;
+use rocketmq_remoting::protocol::DataVersion;
+
+/// Topic configs last registered to every name server, so the next registration only sends
+/// what changed since.
+#[derive(Default)]
+pub(crate) struct RegisteredTopicConfigs {
+    registered: Mutex<Option<(HashMap<String, TopicConfig>, DataVersion)>>,
+}
+
+impl RegisteredTopicConfigs {
+    /// Topic configs added or changed since the last registration, empty when the data version
+    /// is the registered one. `None` when a full registration is needed: nothing was
+    /// registered yet, or topics were deleted, which only a full registration removes from
+    /// the name servers.
+    pub fn changed_since(
+        &self,
+        topic_config_table: &HashMap<String, TopicConfig>,
+        data_version: &DataVersion,
+    ) -> Option<HashMap<String, TopicConfig>> {
+        let registered = self.registered.lock();
+        let (registered_table, registered_version) = registered.as_ref()?;
+        if registered_table
+            .keys()
+            .any(|topic| !topic_config_table.contains_key(topic))
+        {
+            return None;
+        }
+        if registered_version == data_version {
+            return Some(HashMap::new());
+        }
+        Some(
+            topic_config_table
+                .iter()
+                .filter(|(topic, topic_config)| registered_table.get(*topic) != Some(*topic_config))
+                .map(|(topic, topic_config)| (topic.clone(), topic_config.clone()))
+                .collect(),
+        )
+    }
+
+    pub fn record(
+        &self,
+        topic_config_table: HashMap<String, TopicConfig>,
+        data_version: &DataVersion,
+    ) {
+        // A cloned data version shares its counter with the live one, keep a detached copy
+        let mut registered_version = DataVersion::default();
+        registered_version.assign_new_one(data_version);
+        *self.registered.lock() = Some((topic_config_table, registered_version));
+    }
+
+    /// Forgets the registered topic configs, the next registration is a full one.
+    pub fn clear(&self) {
+        self.registered.lock().take();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(topics: &[(&str, u32)]) -> HashMap<String, TopicConfig> {
+        topics
+            .iter()
+            .map(|(topic, queue_nums)| {
+                let mut topic_config = TopicConfig::new(*topic);
+                topic_config.read_queue_nums = *queue_nums;
+                topic_config.write_queue_nums = *queue_nums;
+                (topic.to_string(), topic_config)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn only_changed_topics_are_registered_again() {
+        let registered = RegisteredTopicConfigs::default();
+        let mut data_version = DataVersion::default();
+        assert!(registered
+            .changed_since(&table(&[("TopicA", 4)]), &data_version)
+            .is_none());
+
+        registered.record(table(&[("TopicA", 4), ("TopicB", 4)]), &data_version);
+        assert!(registered
+            .changed_since(&table(&[("TopicA", 4), ("TopicB", 4)]), &data_version)
+            .unwrap()
+            .is_empty());
+
+        data_version.next_version();
+        let changed = registered
+            .changed_since(
+                &table(&[("TopicA", 4), ("TopicB", 8), ("TopicC", 4)]),
+                &data_version,
+            )
+            .unwrap();
+        let mut topics = changed.keys().cloned().collect::<Vec<_>>();
+        topics.sort();
+        assert_eq!(topics, vec!["TopicB", "TopicC"]);
+
+        assert!(registered
+            .changed_since(&table(&[("TopicA", 4)]), &data_version)
+            .is_none());
+        registered.clear();
+        assert!(registered
+            .changed_since(&table(&[("TopicA", 4), ("TopicB", 4)]), &data_version)
+            .is_none());
+    }
+}
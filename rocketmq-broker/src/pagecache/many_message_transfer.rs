@@ -0,0 +1,45 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use bytes::BufMut;
+use bytes::BytesMut;
+use rocketmq_remoting::protocol::file_region::FileRegion;
+use rocketmq_store::base::get_message_result::GetMessageResult;
+
+/// Pulled messages sent from the mapped commit log files, without the heap copy of
+/// `transferMsgByHeap`. It holds the mapped files until the response is written.
+pub(crate) struct ManyMessageTransfer {
+    get_message_result: GetMessageResult,
+}
+
+impl ManyMessageTransfer {
+    pub fn new(get_message_result: GetMessageResult) -> Self {
+        Self { get_message_result }
+    }
+}
+
+impl FileRegion for ManyMessageTransfer {
+    fn count(&self) -> usize {
+        self.get_message_result.buffer_total_size() as usize
+    }
+
+    fn transfer_to(&self, dst: &mut BytesMut) {
+        dst.reserve(self.count());
+        for msg in self.get_message_result.message_mapped_list() {
+            dst.put(msg.get_buffer());
+        }
+    }
+}
@@ -29,19 +29,24 @@ use rocketmq_store::stats::broker_stats::BrokerStats;
 use tracing::warn;
 
 use crate::client::manager::consumer_manager::ConsumerManager;
+use crate::client::net::broker_to_client::Broker2Client;
+use crate::filter::manager::consumer_filter_manager::ConsumerFilterManager;
 use crate::offset::manager::consumer_offset_manager::ConsumerOffsetManager;
 use crate::out_api::broker_outer_api::BrokerOuterAPI;
 use crate::processor::admin_broker_processor::broker_config_request_handler::BrokerConfigRequestHandler;
 use crate::processor::admin_broker_processor::consumer_request_handler::ConsumerRequestHandler;
+use crate::processor::admin_broker_processor::message_request_handler::MessageRequestHandler;
 use crate::processor::admin_broker_processor::offset_request_handler::OffsetRequestHandler;
 use crate::processor::admin_broker_processor::topic_request_handler::TopicRequestHandler;
 use crate::processor::pop_inflight_message_counter::PopInflightMessageCounter;
 use crate::schedule::schedule_message_service::ScheduleMessageService;
+use crate::subscription::manager::subscription_group_manager::SubscriptionGroupManager;
 use crate::topic::manager::topic_config_manager::TopicConfigManager;
 use crate::topic::manager::topic_queue_mapping_manager::TopicQueueMappingManager;
 
 mod broker_config_request_handler;
 mod consumer_request_handler;
+mod message_request_handler;
 mod offset_request_handler;
 mod topic_request_handler;
 
@@ -51,6 +56,7 @@ pub struct AdminBrokerProcessor {
     broker_config_request_handler: BrokerConfigRequestHandler,
     consumer_request_handler: ConsumerRequestHandler,
     offset_request_handler: OffsetRequestHandler,
+    message_request_handler: MessageRequestHandler,
 }
 
 impl AdminBrokerProcessor {
@@ -60,11 +66,13 @@ impl AdminBrokerProcessor {
         message_store_config: Arc<MessageStoreConfig>,
         topic_config_manager: TopicConfigManager,
         consumer_offset_manager: ConsumerOffsetManager,
+        subscription_group_manager: Arc<SubscriptionGroupManager<DefaultMessageStore>>,
         topic_queue_mapping_manager: Arc<TopicQueueMappingManager>,
         default_message_store: DefaultMessageStore,
         schedule_message_service: ScheduleMessageService,
         broker_stats: Option<Arc<BrokerStats<DefaultMessageStore>>>,
         consume_manager: Arc<ConsumerManager>,
+        consumer_filter_manager: Arc<ConsumerFilterManager>,
         broker_out_api: Arc<BrokerOuterAPI>,
     ) -> Self {
         let inner = Inner {
@@ -73,23 +81,28 @@ impl AdminBrokerProcessor {
             message_store_config,
             topic_config_manager,
             consumer_offset_manager,
+            subscription_group_manager,
             topic_queue_mapping_manager,
             default_message_store,
             pop_inflight_message_counter: Arc::new(PopInflightMessageCounter),
             schedule_message_service,
             broker_stats,
             consume_manager,
+            consumer_filter_manager,
             broker_out_api,
+            broker_to_client: Broker2Client,
         };
         let topic_request_handler = TopicRequestHandler::new(inner.clone());
         let broker_config_request_handler = BrokerConfigRequestHandler::new(inner.clone());
         let consumer_request_handler = ConsumerRequestHandler::new(inner.clone());
         let offset_request_handler = OffsetRequestHandler::new(inner.clone());
+        let message_request_handler = MessageRequestHandler::new(inner.clone());
         AdminBrokerProcessor {
             topic_request_handler,
             broker_config_request_handler,
             consumer_request_handler,
             offset_request_handler,
+            message_request_handler,
         }
     }
 }
@@ -148,6 +161,21 @@ impl AdminBrokerProcessor {
                     .get_consumer_connection_list(channel, ctx, request_code, request)
                     .await
             }
+            RequestCode::InvokeBrokerToGetConsumerStatus => {
+                self.consumer_request_handler
+                    .get_consumer_status(channel, ctx, request_code, request)
+                    .await
+            }
+            RequestCode::GetConsumerRunningInfo => {
+                self.consumer_request_handler
+                    .get_consumer_running_info(channel, ctx, request_code, request)
+                    .await
+            }
+            RequestCode::ConsumeMessageDirectly => {
+                self.consumer_request_handler
+                    .consume_message_directly(channel, ctx, request_code, request)
+                    .await
+            }
             RequestCode::GetTopicConfig => {
                 self.topic_request_handler
                     .get_topic_config(channel, ctx, request_code, request)
@@ -178,6 +206,81 @@ impl AdminBrokerProcessor {
                     .get_min_offset(channel, ctx, request_code, request)
                     .await
             }
+            RequestCode::GetEarliestMsgStoreTime => {
+                self.offset_request_handler
+                    .get_earliest_msg_storetime(channel, ctx, request_code, request)
+                    .await
+            }
+            RequestCode::SearchOffsetByTimestamp => {
+                self.offset_request_handler
+                    .search_offset_by_timestamp(channel, ctx, request_code, request)
+                    .await
+            }
+            RequestCode::GetAllConsumerOffset => {
+                self.offset_request_handler
+                    .get_all_consumer_offset(channel, ctx, request_code, request)
+                    .await
+            }
+            RequestCode::GetAllDelayOffset => {
+                self.offset_request_handler
+                    .get_all_delay_offset(channel, ctx, request_code, request)
+                    .await
+            }
+            RequestCode::CloneGroupOffset => {
+                self.offset_request_handler
+                    .clone_group_offset(channel, ctx, request_code, request)
+                    .await
+            }
+            RequestCode::QueryConsumeTimeSpan => {
+                self.offset_request_handler
+                    .query_consume_time_span(channel, ctx, request_code, request)
+                    .await
+            }
+            RequestCode::UpdateAndCreateSubscriptionGroup => {
+                self.consumer_request_handler
+                    .update_and_create_subscription_group(channel, ctx, request_code, request)
+                    .await
+            }
+            RequestCode::GetAllSubscriptionGroupConfig => {
+                self.consumer_request_handler
+                    .get_all_subscription_group(channel, ctx, request_code, request)
+                    .await
+            }
+            RequestCode::GetConsumerOfflineHistory => {
+                self.consumer_request_handler
+                    .get_consumer_offline_history(channel, ctx, request_code, request)
+                    .await
+            }
+            RequestCode::ResendCommitLogMessages => {
+                self.message_request_handler
+                    .resend_commit_log_messages(channel, ctx, request_code, request)
+                    .await
+            }
+            RequestCode::QueryDlqMessages => {
+                self.message_request_handler
+                    .query_dlq_messages(channel, ctx, request_code, request)
+                    .await
+            }
+            RequestCode::ResendDlqMessages => {
+                self.message_request_handler
+                    .resend_dlq_messages(channel, ctx, request_code, request)
+                    .await
+            }
+            RequestCode::QueryMessageTrace => {
+                self.message_request_handler
+                    .query_message_trace(channel, ctx, request_code, request)
+                    .await
+            }
+            RequestCode::GetCommercialStats => {
+                self.broker_config_request_handler
+                    .get_commercial_stats(channel, ctx, request_code, request)
+                    .await
+            }
+            RequestCode::GetPullSourceStats => {
+                self.broker_config_request_handler
+                    .get_pull_source_stats(channel, ctx, request_code, request)
+                    .await
+            }
 
             _ => Some(get_unknown_cmd_response(request_code)),
         }
@@ -203,11 +306,14 @@ struct Inner {
     message_store_config: Arc<MessageStoreConfig>,
     topic_config_manager: TopicConfigManager,
     consumer_offset_manager: ConsumerOffsetManager,
+    subscription_group_manager: Arc<SubscriptionGroupManager<DefaultMessageStore>>,
     topic_queue_mapping_manager: Arc<TopicQueueMappingManager>,
     default_message_store: DefaultMessageStore,
     pop_inflight_message_counter: Arc<PopInflightMessageCounter>,
     schedule_message_service: ScheduleMessageService,
     broker_stats: Option<Arc<BrokerStats<DefaultMessageStore>>>,
     consume_manager: Arc<ConsumerManager>,
+    consumer_filter_manager: Arc<ConsumerFilterManager>,
     broker_out_api: Arc<BrokerOuterAPI>,
+    broker_to_client: Broker2Client,
 }
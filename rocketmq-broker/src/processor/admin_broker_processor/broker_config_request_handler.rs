@@ -22,7 +22,12 @@ use rocketmq_common::common::mix_all;
 use rocketmq_common::common::mq_version::RocketMqVersion;
 use rocketmq_remoting::code::request_code::RequestCode;
 use rocketmq_remoting::net::channel::Channel;
+use rocketmq_remoting::protocol::body::commercial_stats_body::CommercialStatsBody;
+use rocketmq_remoting::protocol::body::commercial_stats_body::CommercialStatsItem;
 use rocketmq_remoting::protocol::body::kv_table::KVTable;
+use rocketmq_remoting::protocol::body::pull_source_stats_body::PullSourceStatsBody;
+use rocketmq_remoting::protocol::body::pull_source_stats_body::PullSourceStatsItem;
+use rocketmq_remoting::protocol::header::get_commercial_stats_request_header::GetCommercialStatsRequestHeader;
 use rocketmq_remoting::protocol::remoting_command::RemotingCommand;
 use rocketmq_remoting::runtime::connection_handler_context::ConnectionHandlerContext;
 use rocketmq_store::log_file::MessageStore;
@@ -101,6 +106,77 @@ impl BrokerConfigRequestHandler {
         Some(response)
     }
 
+    pub async fn get_commercial_stats(
+        &mut self,
+        _channel: Channel,
+        _ctx: ConnectionHandlerContext,
+        _request_code: RequestCode,
+        request: RemotingCommand,
+    ) -> Option<RemotingCommand> {
+        let begin_timestamp = request
+            .decode_command_custom_header::<GetCommercialStatsRequestHeader>()
+            .and_then(|request_header| request_header.begin_timestamp)
+            .unwrap_or_default()
+            .max(0) as u64;
+        let items = self
+            .inner
+            .default_message_store
+            .get_broker_stats_manager()
+            .map(|broker_stats_manager| {
+                broker_stats_manager
+                    .commercial_stats()
+                    .export(begin_timestamp)
+                    .into_iter()
+                    .map(|bucket| CommercialStatsItem {
+                        minute_timestamp: bucket.minute_timestamp,
+                        owner: bucket.owner,
+                        topic: bucket.topic,
+                        group: bucket.group,
+                        stats_type: bucket.stats_type,
+                        times: bucket.times,
+                        size: bucket.size,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        let body = CommercialStatsBody { items };
+        Some(
+            RemotingCommand::create_response_command()
+                .set_body(Some(Bytes::from(serde_json::to_string(&body).unwrap()))),
+        )
+    }
+
+    pub async fn get_pull_source_stats(
+        &mut self,
+        _channel: Channel,
+        _ctx: ConnectionHandlerContext,
+        _request_code: RequestCode,
+        _request: RemotingCommand,
+    ) -> Option<RemotingCommand> {
+        let items = self
+            .inner
+            .default_message_store
+            .get_broker_stats_manager()
+            .map(|broker_stats_manager| {
+                broker_stats_manager
+                    .pull_source_stats()
+                    .export()
+                    .into_iter()
+                    .map(|item| PullSourceStatsItem {
+                        group: item.group,
+                        pulls: item.pulls,
+                        slave_suggested_pulls: item.slave_suggested_pulls,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        let body = PullSourceStatsBody { items };
+        Some(
+            RemotingCommand::create_response_command()
+                .set_body(Some(Bytes::from(serde_json::to_string(&body).unwrap()))),
+        )
+    }
+
     fn prepare_runtime_info(&self) -> HashMap<String, String> {
         let mut runtime_info = self.inner.default_message_store.get_runtime_info();
         self.inner
@@ -171,6 +247,13 @@ impl BrokerConfigRequestHandler {
                 .dispatch_behind_bytes()
                 .to_string(),
         );
+        runtime_info.insert(
+            "flushBehindBytes".to_string(),
+            self.inner
+                .default_message_store
+                .flush_behind_bytes()
+                .to_string(),
+        );
         runtime_info.insert(
             "pageCacheLockTimeMills".to_string(),
             self.inner
@@ -285,6 +368,24 @@ impl BrokerConfigRequestHandler {
                 }
             }
         }
+        for (expression_type, stats) in self
+            .inner
+            .consumer_filter_manager
+            .evaluation_stats()
+            .snapshot()
+        {
+            runtime_info.insert(
+                format!("filterEvaluateStats_{}", expression_type),
+                format!(
+                    "evaluations: {}, matched: {}, errors: {}, avgCostNanos: {}, maxCostNanos: {}",
+                    stats.evaluations,
+                    stats.matched,
+                    stats.errors,
+                    stats.avg_cost_nanos(),
+                    stats.max_cost_nanos
+                ),
+            );
+        }
         runtime_info
     }
     fn is_special_service_running(&self) -> bool {
@@ -15,15 +15,26 @@
  * limitations under the License.
  */
 
+use rocketmq_common::common::config_manager::ConfigManager;
+use rocketmq_common::common::mq_version::RocketMqVersion;
+use rocketmq_common::MessageUtils;
 use rocketmq_remoting::code::request_code::RequestCode;
 use rocketmq_remoting::code::response_code::ResponseCode;
 use rocketmq_remoting::net::channel::Channel;
 use rocketmq_remoting::protocol::body::connection::Connection;
 use rocketmq_remoting::protocol::body::consumer_connection::ConsumerConnection;
+use rocketmq_remoting::protocol::body::get_consumer_listby_group_response_body::GetConsumerListByGroupResponseBody;
+use rocketmq_remoting::protocol::header::consume_message_directly_result_request_header::ConsumeMessageDirectlyResultRequestHeader;
 use rocketmq_remoting::protocol::header::get_consumer_connection_list_request_header::GetConsumerConnectionListRequestHeader;
+use rocketmq_remoting::protocol::header::get_consumer_listby_group_request_header::GetConsumerListByGroupRequestHeader;
+use rocketmq_remoting::protocol::header::get_consumer_running_info_request_header::GetConsumerRunningInfoRequestHeader;
+use rocketmq_remoting::protocol::header::get_consumer_status_request_header::GetConsumerStatusRequestHeader;
 use rocketmq_remoting::protocol::remoting_command::RemotingCommand;
+use rocketmq_remoting::protocol::subscription::subscription_group_config::SubscriptionGroupConfig;
 use rocketmq_remoting::protocol::RemotingSerializable;
 use rocketmq_remoting::runtime::connection_handler_context::ConnectionHandlerContext;
+use rocketmq_store::log_file::MessageStore;
+use tracing::info;
 
 use crate::processor::admin_broker_processor::Inner;
 
@@ -88,4 +99,239 @@ impl ConsumerRequestHandler {
             ),
         }
     }
+
+    /// Online consumers of a group along with the ones that went offline recently.
+    pub async fn get_consumer_offline_history(
+        &mut self,
+        _channel: Channel,
+        _ctx: ConnectionHandlerContext,
+        _request_code: RequestCode,
+        request: RemotingCommand,
+    ) -> Option<RemotingCommand> {
+        let request_header = request
+            .decode_command_custom_header::<GetConsumerListByGroupRequestHeader>()
+            .unwrap();
+        let consumer_id_list = self
+            .inner
+            .consume_manager
+            .get_consumer_group_info(request_header.consumer_group.as_str())
+            .map(|info| info.get_all_client_ids())
+            .unwrap_or_default();
+        let body = GetConsumerListByGroupResponseBody {
+            consumer_id_list,
+            offline_consumer_list: self
+                .inner
+                .consume_manager
+                .get_offline_consumers(request_header.consumer_group.as_str()),
+        };
+        Some(RemotingCommand::create_response_command().set_body(Some(body.encode())))
+    }
+
+    pub async fn get_consumer_status(
+        &mut self,
+        channel: Channel,
+        _ctx: ConnectionHandlerContext,
+        _request_code: RequestCode,
+        request: RemotingCommand,
+    ) -> Option<RemotingCommand> {
+        let request_header = request
+            .decode_command_custom_header::<GetConsumerStatusRequestHeader>()
+            .unwrap();
+        info!(
+            "[get-consumer-status] get consumer status by {}. topic={}, group={}",
+            channel.remote_address(),
+            request_header.topic,
+            request_header.group
+        );
+        let consumer_group_info = self
+            .inner
+            .consume_manager
+            .get_consumer_group_info(request_header.group.as_str());
+        Some(
+            self.inner
+                .broker_to_client
+                .get_consume_status(
+                    consumer_group_info,
+                    request_header.topic.as_str(),
+                    request_header.group.as_str(),
+                    request_header.client_addr.as_deref(),
+                )
+                .await,
+        )
+    }
+
+    pub async fn get_consumer_running_info(
+        &mut self,
+        _channel: Channel,
+        _ctx: ConnectionHandlerContext,
+        _request_code: RequestCode,
+        request: RemotingCommand,
+    ) -> Option<RemotingCommand> {
+        let request_header = request
+            .decode_command_custom_header::<GetConsumerRunningInfoRequestHeader>()
+            .unwrap();
+        Some(
+            self.call_consumer(
+                RequestCode::GetConsumerRunningInfo,
+                request,
+                request_header.consumer_group.as_str(),
+                request_header.client_id.as_str(),
+            )
+            .await,
+        )
+    }
+
+    pub async fn consume_message_directly(
+        &mut self,
+        _channel: Channel,
+        _ctx: ConnectionHandlerContext,
+        _request_code: RequestCode,
+        mut request: RemotingCommand,
+    ) -> Option<RemotingCommand> {
+        let request_header = request
+            .decode_command_custom_header::<ConsumeMessageDirectlyResultRequestHeader>()
+            .unwrap();
+        request.add_ext_field("brokerName", self.inner.broker_config.broker_name.as_str());
+        let offset = request_header
+            .msg_id
+            .as_deref()
+            .and_then(MessageUtils::parse_message_id)
+            .map(|(_, offset)| offset);
+        if let Some(offset) = offset {
+            if let Some(result) = self
+                .inner
+                .default_message_store
+                .select_one_message_by_offset(offset)
+                .await
+            {
+                request.set_body_mut_ref(result.get_bytes());
+            }
+        }
+        Some(
+            self.call_consumer(
+                RequestCode::ConsumeMessageDirectly,
+                request,
+                request_header.consumer_group.as_str(),
+                request_header.client_id.as_deref().unwrap_or_default(),
+            )
+            .await,
+        )
+    }
+
+    /// Relays `request` to the client `client_id` of `consumer_group` and returns its answer.
+    async fn call_consumer(
+        &mut self,
+        request_code: RequestCode,
+        request: RemotingCommand,
+        consumer_group: &str,
+        client_id: &str,
+    ) -> RemotingCommand {
+        let client_channel_info = match self
+            .inner
+            .consume_manager
+            .find_channel(consumer_group, client_id)
+        {
+            Some(client_channel_info) => client_channel_info,
+            None => {
+                return RemotingCommand::create_response_command_with_code_remark(
+                    ResponseCode::SystemError,
+                    format!(
+                        "The Consumer <{}> <{}> not online",
+                        consumer_group, client_id
+                    ),
+                );
+            }
+        };
+        if client_channel_info.version() < i32::from(RocketMqVersion::V318Snapshot) {
+            return RemotingCommand::create_response_command_with_code_remark(
+                ResponseCode::SystemError,
+                format!(
+                    "The Consumer <{}> Version <{}> too low to finish, please upgrade it to \
+                     V3_1_8_SNAPSHOT",
+                    client_id,
+                    client_channel_info.version()
+                ),
+            );
+        }
+
+        let new_request = RemotingCommand::create_remoting_command(request_code)
+            .set_ext_fields(request.ext_fields().cloned().unwrap_or_default())
+            .set_body(request.body().clone());
+        let mut channel = client_channel_info.channel().clone();
+        match self
+            .inner
+            .broker_to_client
+            .call_client(&mut channel, new_request, 10_000)
+            .await
+        {
+            Ok(response) => response,
+            Err(e) => RemotingCommand::create_response_command_with_code_remark(
+                ResponseCode::SystemError,
+                format!(
+                    "invoke consumer <{}> <{}> Exception: {}",
+                    consumer_group, client_id, e
+                ),
+            ),
+        }
+    }
+
+    /// Creates or replaces the subscription group in the body, retry policy included.
+    pub async fn update_and_create_subscription_group(
+        &mut self,
+        _channel: Channel,
+        _ctx: ConnectionHandlerContext,
+        _request_code: RequestCode,
+        request: RemotingCommand,
+    ) -> Option<RemotingCommand> {
+        let subscription_group_config = match request
+            .body()
+            .as_ref()
+            .map(|body| serde_json::from_slice::<SubscriptionGroupConfig>(body))
+        {
+            Some(Ok(config)) => config,
+            Some(Err(e)) => {
+                return Some(
+                    RemotingCommand::create_response_command_with_code(ResponseCode::SystemError)
+                        .set_remark(Some(format!("decode subscription group failed, {}", e))),
+                );
+            }
+            None => {
+                return Some(
+                    RemotingCommand::create_response_command_with_code(ResponseCode::SystemError)
+                        .set_remark(Some("the subscription group is missing".to_string())),
+                );
+            }
+        };
+        if let Err(remark) = subscription_group_config.group_retry_policy().validate() {
+            return Some(
+                RemotingCommand::create_response_command_with_code(ResponseCode::SystemError)
+                    .set_remark(Some(remark)),
+            );
+        }
+        info!(
+            "update and create subscription group, {:?}",
+            subscription_group_config
+        );
+        self.inner
+            .subscription_group_manager
+            .upsert_subscription_group_config(subscription_group_config);
+        Some(RemotingCommand::create_response_command())
+    }
+
+    pub async fn get_all_subscription_group(
+        &mut self,
+        _channel: Channel,
+        _ctx: ConnectionHandlerContext,
+        _request_code: RequestCode,
+        _request: RemotingCommand,
+    ) -> Option<RemotingCommand> {
+        let content = self.inner.subscription_group_manager.encode_pretty(false);
+        if content.is_empty() {
+            return Some(
+                RemotingCommand::create_response_command_with_code(ResponseCode::SystemError)
+                    .set_remark(Some("No subscription group in this broker".to_string())),
+            );
+        }
+        Some(RemotingCommand::create_response_command().set_body(Some(content)))
+    }
 }
@@ -0,0 +1,795 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use bytes::Buf;
+use rocketmq_common::common::boundary_type::BoundaryType;
+use rocketmq_common::common::config::TopicConfig;
+use rocketmq_common::common::message::message_ext::MessageExt;
+use rocketmq_common::common::message::message_ext_broker_inner::MessageExtBrokerInner;
+use rocketmq_common::common::message::MessageConst;
+use rocketmq_common::common::message::MessageTrait;
+use rocketmq_common::common::message::MessageVersion;
+use rocketmq_common::common::mix_all;
+use rocketmq_common::common::topic::TopicValidator;
+use rocketmq_common::MessageDecoder;
+use rocketmq_common::MessageDecoder::message_properties_to_string;
+use rocketmq_common::MessageDecoder::BLANK_MAGIC_CODE;
+use rocketmq_remoting::code::request_code::RequestCode;
+use rocketmq_remoting::code::response_code::ResponseCode;
+use rocketmq_remoting::net::channel::Channel;
+use rocketmq_remoting::protocol::body::dlq_messages_body::DlqMessage;
+use rocketmq_remoting::protocol::body::dlq_messages_body::DlqMessagesBody;
+use rocketmq_remoting::protocol::body::query_message_trace_body::MessageTraceRecord;
+use rocketmq_remoting::protocol::body::query_message_trace_body::QueryMessageTraceBody;
+use rocketmq_remoting::protocol::body::resend_commit_log_messages_response_body::ResendCommitLogMessagesResponseBody;
+use rocketmq_remoting::protocol::body::resend_dlq_messages_response_body::ResendDlqMessagesResponseBody;
+use rocketmq_remoting::protocol::header::query_dlq_messages_request_header::QueryDlqMessagesRequestHeader;
+use rocketmq_remoting::protocol::header::query_message_trace_request_header::QueryMessageTraceRequestHeader;
+use rocketmq_remoting::protocol::header::resend_commit_log_messages_request_header::ResendCommitLogMessagesRequestHeader;
+use rocketmq_remoting::protocol::header::resend_dlq_messages_request_header::ResendDlqMessagesRequestHeader;
+use rocketmq_remoting::protocol::remoting_command::RemotingCommand;
+use rocketmq_remoting::protocol::RemotingSerializable;
+use rocketmq_remoting::runtime::connection_handler_context::ConnectionHandlerContext;
+use rocketmq_store::log_file::MessageStore;
+use tracing::info;
+use tracing::warn;
+
+use crate::processor::admin_broker_processor::Inner;
+
+const DEFAULT_MAX_RESEND_COUNT: i32 = 10_000;
+const MAX_SCAN_BYTES_PER_REQUEST: i64 = 256 * 1024 * 1024;
+const DEFAULT_DLQ_PAGE_SIZE: i32 = 32;
+const MAX_DLQ_PAGE_SIZE: i32 = 1_000;
+const MAX_DLQ_PAGE_BYTES: i32 = 16 * 1024 * 1024;
+const DEFAULT_TRACE_RECORD_COUNT: i32 = 64;
+const MAX_TRACE_RECORD_COUNT: i32 = 1_000;
+const TRACE_SCAN_BATCH_SIZE: i32 = 32;
+
+#[derive(Clone)]
+pub(super) struct MessageRequestHandler {
+    inner: Inner,
+}
+
+impl MessageRequestHandler {
+    pub fn new(inner: Inner) -> Self {
+        Self { inner }
+    }
+}
+
+impl MessageRequestHandler {
+    /// Scans the requested commit log range and puts the matching messages again, so their
+    /// consume queue entries are rebuilt in the target topic. Used to recover after a consume
+    /// queue got corrupted, or to backfill a topic from the messages still in the commit log.
+    pub async fn resend_commit_log_messages(
+        &mut self,
+        channel: Channel,
+        _ctx: ConnectionHandlerContext,
+        _request_code: RequestCode,
+        request: RemotingCommand,
+    ) -> Option<RemotingCommand> {
+        let Some(request_header) =
+            request.decode_command_custom_header::<ResendCommitLogMessagesRequestHeader>()
+        else {
+            return Some(RemotingCommand::create_response_command_with_code_remark(
+                ResponseCode::SystemError,
+                "decode ResendCommitLogMessagesRequestHeader failed",
+            ));
+        };
+        info!(
+            "[resend-commit-log] {:?} requested by {}",
+            request_header,
+            channel.remote_address()
+        );
+        Some(self.resend_commit_log_range(&request_header).await)
+    }
+
+    async fn resend_commit_log_range(
+        &mut self,
+        request_header: &ResendCommitLogMessagesRequestHeader,
+    ) -> RemotingCommand {
+        let response = RemotingCommand::create_response_command();
+        let topic = request_header.topic.as_str();
+        let target_topic = request_header
+            .target_topic
+            .as_deref()
+            .filter(|target_topic| !target_topic.is_empty())
+            .unwrap_or(topic);
+        let Some(topic_config) = self
+            .inner
+            .topic_config_manager
+            .select_topic_config(target_topic)
+        else {
+            return response
+                .set_code(ResponseCode::TopicNotExist)
+                .set_remark(Some(format!("target topic {} not exist", target_topic)));
+        };
+
+        let store = &self.inner.default_message_store;
+        let end_offset = request_header
+            .end_offset
+            .map_or(store.get_max_phy_offset().value(), |end| {
+                end.min(store.get_max_phy_offset().value())
+            });
+        let mut offset = request_header
+            .begin_offset
+            .map_or(store.get_min_phy_offset().value(), |begin| {
+                begin.max(store.get_min_phy_offset().value())
+            });
+        let begin_timestamp = request_header.begin_timestamp.unwrap_or(0);
+        let end_timestamp = request_header.end_timestamp.unwrap_or(i64::MAX);
+        let max_count = request_header
+            .max_count
+            .filter(|max_count| *max_count > 0)
+            .unwrap_or(DEFAULT_MAX_RESEND_COUNT) as u64;
+        let scan_limit = offset.saturating_add(MAX_SCAN_BYTES_PER_REQUEST);
+        info!(
+            "[resend-commit-log] resend messages of topic {} in [{}, {}) to {}",
+            topic, offset, end_offset, target_topic
+        );
+
+        let mut body = ResendCommitLogMessagesResponseBody {
+            end_offset,
+            ..Default::default()
+        };
+        while offset < end_offset && offset < scan_limit && body.resent + body.failed < max_count {
+            let Some((size, magic_code)) = self.read_size_and_magic_code(offset).await else {
+                offset = self.next_file_offset(offset);
+                continue;
+            };
+            if size <= 0 || MessageVersion::value_of_magic_code(magic_code).is_err() {
+                if magic_code != BLANK_MAGIC_CODE {
+                    warn!(
+                        "[resend-commit-log] no message at commit log offset {}, skip to the next \
+                         file",
+                        offset
+                    );
+                }
+                offset = self.next_file_offset(offset);
+                continue;
+            }
+            let msg = self
+                .inner
+                .default_message_store
+                .look_message_by_offset_with_size(offset, size);
+            offset += size as i64;
+            body.scanned += 1;
+            let Some(msg) = msg.filter(|msg| {
+                msg.get_topic() == topic
+                    && (begin_timestamp..=end_timestamp).contains(&msg.store_timestamp)
+            }) else {
+                continue;
+            };
+            body.matched += 1;
+            let put_message_result = self
+                .inner
+                .default_message_store
+                .put_message(build_resend_message(msg, &topic_config))
+                .await;
+            if put_message_result.is_ok() {
+                body.resent += 1;
+            } else {
+                body.failed += 1;
+                warn!(
+                    "[resend-commit-log] put message failed: {:?}",
+                    put_message_result.put_message_status()
+                );
+            }
+        }
+        body.next_offset = offset.min(end_offset);
+        info!("[resend-commit-log] finished: {:?}", body);
+        response.set_body(Some(body.encode()))
+    }
+
+    /// Lists a page of the dead letter queue of a consumer group, with the original topic and
+    /// the reconsume times of each message so poison messages can be told apart before they
+    /// are resent.
+    pub async fn query_dlq_messages(
+        &mut self,
+        _channel: Channel,
+        _ctx: ConnectionHandlerContext,
+        _request_code: RequestCode,
+        request: RemotingCommand,
+    ) -> Option<RemotingCommand> {
+        let response = RemotingCommand::create_response_command();
+        let request_header =
+            request.decode_command_custom_header::<QueryDlqMessagesRequestHeader>()?;
+        let dlq_topic = mix_all::get_dlq_topic(request_header.consumer_group.as_str());
+        let queue_id = request_header.queue_id.unwrap_or(0);
+        if let Err(remark) = self.check_dlq_queue(dlq_topic.as_str(), queue_id) {
+            return Some(
+                response
+                    .set_code(ResponseCode::TopicNotExist)
+                    .set_remark(Some(remark)),
+            );
+        }
+
+        let store = &self.inner.default_message_store;
+        let min_offset = store
+            .get_min_offset_in_queue(dlq_topic.as_str(), queue_id)
+            .value();
+        let max_offset = store
+            .get_max_offset_in_queue(dlq_topic.as_str(), queue_id)
+            .value();
+        let max_count = request_header
+            .max_count
+            .filter(|max_count| *max_count > 0)
+            .unwrap_or(DEFAULT_DLQ_PAGE_SIZE)
+            .min(MAX_DLQ_PAGE_SIZE);
+        let mut body = DlqMessagesBody {
+            next_offset: request_header.offset.unwrap_or(min_offset).max(min_offset),
+            min_offset,
+            max_offset,
+            ..Default::default()
+        };
+        if body.next_offset < max_offset {
+            if let Some(result) = store
+                .get_message(
+                    request_header.consumer_group.as_str(),
+                    dlq_topic.as_str(),
+                    queue_id,
+                    body.next_offset,
+                    max_count,
+                    MAX_DLQ_PAGE_BYTES,
+                    None,
+                )
+                .await
+            {
+                body.messages = result
+                    .message_mapped_list()
+                    .iter()
+                    .filter_map(|mapped_buffer| {
+                        MessageDecoder::decode(
+                            &mut mapped_buffer.get_bytes()?,
+                            true,
+                            false,
+                            false,
+                            false,
+                            false,
+                        )
+                    })
+                    .map(dlq_message)
+                    .collect();
+                body.next_offset = result.next_begin_offset().max(body.next_offset);
+            }
+        }
+        Some(response.set_body(Some(body.encode())))
+    }
+
+    /// Sends the selected dead letter queue messages back to the topics they were originally
+    /// sent to, with their reconsume times reset, once the cause of the failures is fixed.
+    pub async fn resend_dlq_messages(
+        &mut self,
+        channel: Channel,
+        _ctx: ConnectionHandlerContext,
+        _request_code: RequestCode,
+        request: RemotingCommand,
+    ) -> Option<RemotingCommand> {
+        let response = RemotingCommand::create_response_command();
+        let request_header =
+            request.decode_command_custom_header::<ResendDlqMessagesRequestHeader>()?;
+        let dlq_topic = mix_all::get_dlq_topic(request_header.consumer_group.as_str());
+        let queue_id = request_header.queue_id.unwrap_or(0);
+        if let Err(remark) = self.check_dlq_queue(dlq_topic.as_str(), queue_id) {
+            return Some(
+                response
+                    .set_code(ResponseCode::TopicNotExist)
+                    .set_remark(Some(remark)),
+            );
+        }
+        info!(
+            "[resend-dlq] resend messages {} of {} to their original topics, requested by {}",
+            request_header.offsets,
+            dlq_topic,
+            channel.remote_address()
+        );
+
+        let mut body = ResendDlqMessagesResponseBody::default();
+        for offset in request_header.offsets() {
+            let msg = self
+                .inner
+                .default_message_store
+                .get_message(
+                    request_header.consumer_group.as_str(),
+                    dlq_topic.as_str(),
+                    queue_id,
+                    offset,
+                    1,
+                    MAX_DLQ_PAGE_BYTES,
+                    None,
+                )
+                .await
+                .and_then(|result| {
+                    let mut bytes = result.message_mapped_list().first()?.get_bytes()?;
+                    MessageDecoder::decode(&mut bytes, true, false, false, false, false)
+                })
+                .filter(|msg| msg.queue_offset == offset);
+            let Some((msg, topic_config)) = msg.and_then(|msg| {
+                let original_topic = msg.get_property(MessageConst::PROPERTY_RETRY_TOPIC)?;
+                let topic_config = self
+                    .inner
+                    .topic_config_manager
+                    .select_topic_config(original_topic.as_str())?;
+                Some((msg, topic_config))
+            }) else {
+                body.not_found.push(offset);
+                continue;
+            };
+            let put_message_result = self
+                .inner
+                .default_message_store
+                .put_message(build_dlq_resend_message(msg, &topic_config))
+                .await;
+            if put_message_result.is_ok() {
+                body.resent.push(offset);
+            } else {
+                body.failed.push(offset);
+                warn!(
+                    "[resend-dlq] put message at offset {} of {} failed: {:?}",
+                    offset,
+                    dlq_topic,
+                    put_message_result.put_message_status()
+                );
+            }
+        }
+        info!("[resend-dlq] finished: {:?}", body);
+        Some(response.set_body(Some(body.encode())))
+    }
+
+    /// Reads the trace topic over the requested time window and returns the trace messages
+    /// indexed by the requested message id or key, so the trace of a message can be looked up
+    /// without an external trace system.
+    pub async fn query_message_trace(
+        &mut self,
+        _channel: Channel,
+        _ctx: ConnectionHandlerContext,
+        _request_code: RequestCode,
+        request: RemotingCommand,
+    ) -> Option<RemotingCommand> {
+        let response = RemotingCommand::create_response_command();
+        let request_header =
+            request.decode_command_custom_header::<QueryMessageTraceRequestHeader>()?;
+        let trace_topic = request_header
+            .trace_topic
+            .as_deref()
+            .unwrap_or(TopicValidator::RMQ_SYS_TRACE_TOPIC);
+        let Some(topic_config) = self
+            .inner
+            .topic_config_manager
+            .select_topic_config(trace_topic)
+        else {
+            return Some(
+                response
+                    .set_code(ResponseCode::TopicNotExist)
+                    .set_remark(Some(format!("trace topic {} not exist", trace_topic))),
+            );
+        };
+        let begin_timestamp = request_header.begin_timestamp.unwrap_or(0);
+        let end_timestamp = request_header.end_timestamp.unwrap_or(i64::MAX);
+        let max_count = request_header
+            .max_count
+            .filter(|max_count| *max_count > 0)
+            .unwrap_or(DEFAULT_TRACE_RECORD_COUNT)
+            .min(MAX_TRACE_RECORD_COUNT) as usize;
+
+        let store = &self.inner.default_message_store;
+        let mut body = QueryMessageTraceBody::default();
+        let mut scanned_bytes = 0i64;
+        'queues: for queue_id in 0..topic_config.read_queue_nums as i32 {
+            let max_offset = store.get_max_offset_in_queue(trace_topic, queue_id).value();
+            let mut offset = store
+                .get_offset_in_queue_by_time(
+                    trace_topic,
+                    queue_id,
+                    begin_timestamp,
+                    BoundaryType::Lower,
+                )
+                .max(store.get_min_offset_in_queue(trace_topic, queue_id))
+                .value();
+            while offset < max_offset {
+                if scanned_bytes >= MAX_SCAN_BYTES_PER_REQUEST {
+                    body.truncated = true;
+                    break 'queues;
+                }
+                let Some(result) = store
+                    .get_message(
+                        mix_all::TOOLS_CONSUMER_GROUP,
+                        trace_topic,
+                        queue_id,
+                        offset,
+                        TRACE_SCAN_BATCH_SIZE,
+                        MAX_DLQ_PAGE_BYTES,
+                        None,
+                    )
+                    .await
+                else {
+                    break;
+                };
+                if result.next_begin_offset() <= offset {
+                    break;
+                }
+                offset = result.next_begin_offset();
+                scanned_bytes += result.buffer_total_size() as i64;
+                for msg in result
+                    .message_mapped_list()
+                    .iter()
+                    .filter_map(|mapped_buffer| {
+                        MessageDecoder::decode(
+                            &mut mapped_buffer.get_bytes()?,
+                            true,
+                            true,
+                            false,
+                            false,
+                            false,
+                        )
+                    })
+                {
+                    if msg.store_timestamp > end_timestamp {
+                        continue 'queues;
+                    }
+                    if msg.store_timestamp < begin_timestamp
+                        || !msg.get_keys().is_some_and(|keys| {
+                            keys.split(' ').any(|key| key == request_header.key)
+                        })
+                    {
+                        continue;
+                    }
+                    if body.records.len() >= max_count {
+                        body.truncated = true;
+                        break 'queues;
+                    }
+                    body.records.push(message_trace_record(msg));
+                }
+            }
+        }
+        body.records.sort_by_key(|record| record.store_timestamp);
+        Some(response.set_body(Some(body.encode())))
+    }
+
+    fn check_dlq_queue(&self, dlq_topic: &str, queue_id: i32) -> Result<(), String> {
+        let Some(topic_config) = self
+            .inner
+            .topic_config_manager
+            .select_topic_config(dlq_topic)
+        else {
+            return Err(format!("dead letter queue topic {} not exist", dlq_topic));
+        };
+        if queue_id < 0 || queue_id >= topic_config.read_queue_nums as i32 {
+            return Err(format!(
+                "queueId[{}] is illegal, topic:[{}] topicConfig.readQueueNums:[{}]",
+                queue_id, dlq_topic, topic_config.read_queue_nums
+            ));
+        }
+        Ok(())
+    }
+
+    async fn read_size_and_magic_code(&self, offset: i64) -> Option<(i32, i32)> {
+        let result = self
+            .inner
+            .default_message_store
+            .select_one_message_by_offset_with_size(offset, 8)
+            .await?;
+        let mut buffer = result.get_buffer();
+        Some((buffer.get_i32(), buffer.get_i32()))
+    }
+
+    /// Start of the commit log file following the one `offset` is in.
+    fn next_file_offset(&self, offset: i64) -> i64 {
+        let file_size = self.inner.message_store_config.mapped_file_size_commit_log as i64;
+        offset + file_size - offset % file_size
+    }
+}
+
+fn dlq_message(msg: MessageExt) -> DlqMessage {
+    DlqMessage {
+        queue_offset: msg.queue_offset,
+        origin_msg_id: msg.get_property(MessageConst::PROPERTY_ORIGIN_MESSAGE_ID),
+        original_topic: msg.get_property(MessageConst::PROPERTY_RETRY_TOPIC),
+        reconsume_times: msg.reconsume_times,
+        store_timestamp: msg.store_timestamp,
+        born_timestamp: msg.born_timestamp,
+        tags: msg.get_tags(),
+        keys: msg.get_keys(),
+        body_size: msg.get_body().map_or(0, |body| body.len() as i32),
+        msg_id: msg.msg_id,
+    }
+}
+
+fn message_trace_record(msg: MessageExt) -> MessageTraceRecord {
+    MessageTraceRecord {
+        store_timestamp: msg.store_timestamp,
+        born_host: msg.born_host.to_string(),
+        trace_data: msg
+            .get_body()
+            .map(|body| String::from_utf8_lossy(body).into_owned())
+            .unwrap_or_default(),
+        msg_id: msg.msg_id,
+    }
+}
+
+/// Turns a dead letter queue message back into a fresh message of its original topic, the
+/// retry bookkeeping the broker added on the way to the dead letter queue is dropped.
+fn build_dlq_resend_message(
+    mut msg: MessageExt,
+    topic_config: &TopicConfig,
+) -> MessageExtBrokerInner {
+    msg.reconsume_times = 0;
+    msg.clear_property(MessageConst::PROPERTY_RETRY_TOPIC);
+    msg.clear_property(MessageConst::PROPERTY_DELAY_TIME_LEVEL);
+    msg.clear_property(MessageConst::PROPERTY_RECONSUME_TIME);
+    build_resend_message(msg, topic_config)
+}
+
+fn build_resend_message(msg: MessageExt, topic_config: &TopicConfig) -> MessageExtBrokerInner {
+    let origin_message_id = msg.msg_id.clone();
+    let queue_id = msg.queue_id;
+    let mut msg_inner = MessageExtBrokerInner {
+        message_ext_inner: msg,
+        ..Default::default()
+    };
+    msg_inner.set_topic(topic_config.topic_name.clone().unwrap_or_default().as_str());
+    msg_inner.message_ext_inner.queue_id = queue_id % topic_config.write_queue_nums.max(1) as i32;
+    msg_inner
+        .message_ext_inner
+        .message
+        .properties
+        .entry(MessageConst::PROPERTY_ORIGIN_MESSAGE_ID.to_string())
+        .or_insert(origin_message_id);
+    msg_inner.tags_code = MessageExtBrokerInner::tags_string2tags_code(
+        &topic_config.topic_filter_type,
+        msg_inner.get_tags().unwrap_or_default().as_str(),
+    );
+    msg_inner.properties_string =
+        message_properties_to_string(&msg_inner.message_ext_inner.message.properties);
+    msg_inner
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    use bytes::Bytes;
+    use rocketmq_common::common::broker::broker_config::BrokerConfig;
+    use rocketmq_common::common::server::config::ServerConfig;
+    use rocketmq_remoting::protocol::RemotingDeserializable;
+    use rocketmq_remoting::runtime::config::client_config::TokioClientConfig;
+    use rocketmq_store::config::flush_disk_type::FlushDiskType;
+    use rocketmq_store::config::message_store_config::MessageStoreConfig;
+    use rocketmq_store::message_store::default_message_store::DefaultMessageStore;
+
+    use super::*;
+    use crate::broker_runtime::BrokerRuntimeInner;
+    use crate::client::default_consumer_ids_change_listener::DefaultConsumerIdsChangeListener;
+    use crate::client::manager::consumer_manager::ConsumerManager;
+    use crate::client::net::broker_to_client::Broker2Client;
+    use crate::filter::manager::consumer_filter_manager::ConsumerFilterManager;
+    use crate::offset::manager::consumer_offset_manager::ConsumerOffsetManager;
+    use crate::out_api::broker_outer_api::BrokerOuterAPI;
+    use crate::processor::pop_inflight_message_counter::PopInflightMessageCounter;
+    use crate::subscription::manager::subscription_group_manager::SubscriptionGroupManager;
+    use crate::topic::manager::topic_config_manager::TopicConfigManager;
+    use crate::topic::manager::topic_queue_mapping_manager::TopicQueueMappingManager;
+
+    async fn new_handler(store_path_root_dir: &str) -> MessageRequestHandler {
+        let broker_config = Arc::new(BrokerConfig {
+            store_path_root_dir: store_path_root_dir.to_string(),
+            ..Default::default()
+        });
+        let message_store_config = Arc::new(MessageStoreConfig {
+            store_path_root_dir: store_path_root_dir.to_string(),
+            flush_disk_type: FlushDiskType::AsyncFlush,
+            ..Default::default()
+        });
+        let server_config = Arc::new(ServerConfig::default());
+        let broker_out_api = Arc::new(BrokerOuterAPI::new(Arc::new(TokioClientConfig::default())));
+        let topic_queue_mapping_manager =
+            Arc::new(TopicQueueMappingManager::new(broker_config.clone()));
+        let topic_config_manager = TopicConfigManager::new(
+            broker_config.clone(),
+            Arc::new(BrokerRuntimeInner {
+                broker_out_api: broker_out_api.clone(),
+                broker_config: broker_config.clone(),
+                message_store_config: message_store_config.clone(),
+                server_config: server_config.clone(),
+                topic_queue_mapping_manager: topic_queue_mapping_manager.clone(),
+            }),
+        );
+        let mut default_message_store = DefaultMessageStore::new(
+            message_store_config.clone(),
+            broker_config.clone(),
+            Arc::new(parking_lot::Mutex::new(HashMap::new())),
+            None,
+            false,
+        );
+        assert!(default_message_store.load().await);
+        let consumer_filter_manager = Arc::new(ConsumerFilterManager::new(broker_config.clone()));
+        MessageRequestHandler::new(Inner {
+            broker_config: broker_config.clone(),
+            server_config,
+            message_store_config,
+            topic_config_manager,
+            consumer_offset_manager: ConsumerOffsetManager::new(broker_config.clone(), None),
+            subscription_group_manager: Arc::new(SubscriptionGroupManager::new(
+                broker_config.clone(),
+                None,
+            )),
+            topic_queue_mapping_manager,
+            default_message_store,
+            pop_inflight_message_counter: Arc::new(PopInflightMessageCounter),
+            schedule_message_service: Default::default(),
+            broker_stats: None,
+            consume_manager: Arc::new(ConsumerManager::new_with_broker_stats(
+                Box::new(DefaultConsumerIdsChangeListener::new(
+                    broker_config.clone(),
+                    consumer_filter_manager.clone(),
+                )),
+                broker_config,
+            )),
+            consumer_filter_manager,
+            broker_out_api,
+            broker_to_client: Broker2Client,
+        })
+    }
+
+    fn message(topic: &str, tags: &str) -> MessageExtBrokerInner {
+        let mut msg = MessageExtBrokerInner::default();
+        msg.set_topic(topic);
+        msg.message_ext_inner.message.set_tags(tags.to_string());
+        msg.message_ext_inner.message.body = Some(Bytes::from_static(b"body"));
+        msg.properties_string =
+            message_properties_to_string(&msg.message_ext_inner.message.properties);
+        msg
+    }
+
+    #[test]
+    fn resend_commit_log_range_puts_the_messages_of_the_topic_again() {
+        // the handler owns runtimes which must not be dropped inside of one
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let handler = runtime.block_on(async {
+            let mut handler = new_handler(temp_dir.path().to_str().unwrap()).await;
+            let store = &mut handler.inner.default_message_store;
+            for msg in [
+                message("TopicTest", "TagA"),
+                message("OtherTopic", "TagA"),
+                message("TopicTest", "TagB"),
+            ] {
+                assert!(store.put_message(msg).await.is_ok());
+            }
+            let end_offset = store.get_max_phy_offset().value();
+            handler
+                .inner
+                .topic_config_manager
+                .put_topic_config(TopicConfig::with_queues("TopicBackfill", 4, 4));
+
+            let request_header = ResendCommitLogMessagesRequestHeader {
+                topic: "TopicTest".to_string(),
+                target_topic: Some("TopicBackfill".to_string()),
+                ..Default::default()
+            };
+            let response = handler.resend_commit_log_range(&request_header).await;
+            assert_eq!(ResponseCode::from(response.code()), ResponseCode::Success);
+            let body =
+                ResendCommitLogMessagesResponseBody::decode(response.body().as_ref().unwrap())
+                    .unwrap();
+            assert_eq!(
+                body,
+                ResendCommitLogMessagesResponseBody {
+                    scanned: 3,
+                    matched: 2,
+                    resent: 2,
+                    failed: 0,
+                    next_offset: end_offset,
+                    end_offset,
+                }
+            );
+            // the resent messages follow the scanned range in the commit log
+            let resent = handler
+                .inner
+                .default_message_store
+                .look_message_by_offset(end_offset)
+                .unwrap();
+            assert_eq!(resent.get_topic(), "TopicBackfill");
+            assert_eq!(resent.get_tags().as_deref(), Some("TagA"));
+            assert!(resent
+                .get_property(MessageConst::PROPERTY_ORIGIN_MESSAGE_ID)
+                .is_some());
+
+            let request_header = ResendCommitLogMessagesRequestHeader {
+                topic: "TopicTest".to_string(),
+                target_topic: Some("NotExist".to_string()),
+                ..Default::default()
+            };
+            let response = handler.resend_commit_log_range(&request_header).await;
+            assert_eq!(
+                ResponseCode::from(response.code()),
+                ResponseCode::TopicNotExist
+            );
+            handler
+        });
+        drop(runtime);
+        drop(handler);
+    }
+
+    #[test]
+    fn resend_message_keeps_the_origin_message_id() {
+        let mut msg = MessageExt {
+            msg_id: "MSG_ID".to_string(),
+            queue_id: 6,
+            ..Default::default()
+        };
+        msg.set_topic("TopicTest");
+        msg.set_tags("TagA");
+
+        let msg_inner = build_resend_message(
+            msg.clone(),
+            &TopicConfig::with_queues("TopicBackfill", 4, 4),
+        );
+        assert_eq!(msg_inner.get_topic(), "TopicBackfill");
+        assert_eq!(msg_inner.message_ext_inner.queue_id, 2);
+        assert_eq!(
+            msg_inner
+                .get_property(MessageConst::PROPERTY_ORIGIN_MESSAGE_ID)
+                .as_deref(),
+            Some("MSG_ID")
+        );
+        assert!(msg_inner.properties_string.contains("MSG_ID"));
+
+        // a message resent before keeps pointing at the first one
+        msg.put_property(MessageConst::PROPERTY_ORIGIN_MESSAGE_ID, "FIRST_ID");
+        let msg_inner = build_resend_message(msg, &TopicConfig::with_queues("TopicBackfill", 4, 4));
+        assert_eq!(
+            msg_inner
+                .get_property(MessageConst::PROPERTY_ORIGIN_MESSAGE_ID)
+                .as_deref(),
+            Some("FIRST_ID")
+        );
+    }
+
+    #[test]
+    fn dlq_resend_message_goes_back_to_the_original_topic() {
+        let mut msg = MessageExt {
+            msg_id: "DLQ_MSG_ID".to_string(),
+            queue_id: 0,
+            reconsume_times: 17,
+            ..Default::default()
+        };
+        msg.set_topic("%DLQ%group");
+        msg.put_property(MessageConst::PROPERTY_RETRY_TOPIC, "TopicTest");
+        msg.put_property(MessageConst::PROPERTY_DELAY_TIME_LEVEL, "-1");
+        msg.put_property(MessageConst::PROPERTY_ORIGIN_MESSAGE_ID, "ORIGIN_ID");
+        assert_eq!(
+            dlq_message(msg.clone()).original_topic.as_deref(),
+            Some("TopicTest")
+        );
+
+        let msg_inner = build_dlq_resend_message(msg, &TopicConfig::with_queues("TopicTest", 4, 4));
+        assert_eq!(msg_inner.get_topic(), "TopicTest");
+        assert_eq!(msg_inner.message_ext_inner.reconsume_times, 0);
+        assert!(msg_inner
+            .get_property(MessageConst::PROPERTY_RETRY_TOPIC)
+            .is_none());
+        assert!(msg_inner
+            .get_property(MessageConst::PROPERTY_DELAY_TIME_LEVEL)
+            .is_none());
+        assert_eq!(
+            msg_inner
+                .get_property(MessageConst::PROPERTY_ORIGIN_MESSAGE_ID)
+                .as_deref(),
+            Some("ORIGIN_ID")
+        );
+    }
+}
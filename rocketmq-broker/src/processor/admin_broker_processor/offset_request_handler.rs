@@ -14,21 +14,40 @@
  * See the License for the specific language governing permissions and
  * limitations under the License.
  */
+use std::collections::HashSet;
+
+use rocketmq_common::common::boundary_type::BoundaryType;
+use rocketmq_common::common::config::TopicConfig;
+use rocketmq_common::common::config_manager::ConfigManager;
+use rocketmq_common::common::message::message_queue::MessageQueue;
+use rocketmq_common::common::mix_all::is_lmq;
+use rocketmq_common::utils::file_utils;
+use rocketmq_common::TimeUtils::get_current_millis;
 use rocketmq_remoting::code::request_code::RequestCode;
 use rocketmq_remoting::code::response_code::ResponseCode;
 use rocketmq_remoting::net::channel::Channel;
+use rocketmq_remoting::protocol::body::query_consume_time_span_body::QueryConsumeTimeSpanBody;
+use rocketmq_remoting::protocol::body::query_consume_time_span_body::QueueTimeSpan;
+use rocketmq_remoting::protocol::header::clone_group_offset_request_header::CloneGroupOffsetRequestHeader;
+use rocketmq_remoting::protocol::header::get_earliest_msg_storetime_request_header::GetEarliestMsgStoretimeRequestHeader;
+use rocketmq_remoting::protocol::header::get_earliest_msg_storetime_response_header::GetEarliestMsgStoretimeResponseHeader;
 use rocketmq_remoting::protocol::header::get_max_offset_request_header::GetMaxOffsetRequestHeader;
 use rocketmq_remoting::protocol::header::get_max_offset_response_header::GetMaxOffsetResponseHeader;
 use rocketmq_remoting::protocol::header::get_min_offset_request_header::GetMinOffsetRequestHeader;
 use rocketmq_remoting::protocol::header::get_min_offset_response_header::GetMinOffsetResponseHeader;
 use rocketmq_remoting::protocol::header::message_operation_header::TopicRequestHeaderTrait;
+use rocketmq_remoting::protocol::header::query_consume_time_span_request_header::QueryConsumeTimeSpanRequestHeader;
+use rocketmq_remoting::protocol::header::search_offset_request_header::SearchOffsetRequestHeader;
+use rocketmq_remoting::protocol::header::search_offset_response_header::SearchOffsetResponseHeader;
 use rocketmq_remoting::protocol::remoting_command::RemotingCommand;
 use rocketmq_remoting::protocol::static_topic::topic_queue_mapping_context::TopicQueueMappingContext;
 use rocketmq_remoting::protocol::static_topic::topic_queue_mapping_utils::TopicQueueMappingUtils;
+use rocketmq_remoting::protocol::RemotingSerializable;
 use rocketmq_remoting::rpc::rpc_client::RpcClient;
 use rocketmq_remoting::rpc::rpc_request::RpcRequest;
 use rocketmq_remoting::runtime::connection_handler_context::ConnectionHandlerContext;
 use rocketmq_store::log_file::MessageStore;
+use tracing::warn;
 
 use crate::processor::admin_broker_processor::Inner;
 
@@ -58,17 +77,22 @@ impl OffsetRequestHandler {
             .build_topic_queue_mapping_context(&request_header, false);
         let topic = request_header.topic.clone();
         let queue_id = request_header.queue_id;
+        let committed = request_header.committed;
         let rewrite_result = self
             .rewrite_request_for_static_topic(request_header, mapping_context)
             .await;
         if rewrite_result.is_some() {
             return rewrite_result;
         }
+        if let Some(response) = self.check_queue_range(topic.as_str(), queue_id) {
+            return Some(response);
+        }
 
         let offset = self
             .inner
             .default_message_store
-            .get_max_offset_in_queue(topic.as_str(), queue_id);
+            .get_max_offset_in_queue_committed(topic.as_str(), queue_id, committed)
+            .value();
         let response_header = GetMaxOffsetResponseHeader { offset };
         Some(RemotingCommand::create_response_command_with_header(
             response_header,
@@ -96,16 +120,64 @@ impl OffsetRequestHandler {
         if rewrite_result.is_some() {
             return rewrite_result;
         }
+        if let Some(response) = self.check_queue_range(topic.as_str(), queue_id) {
+            return Some(response);
+        }
 
         let offset = self
             .inner
             .default_message_store
-            .get_min_offset_in_queue(topic.as_str(), queue_id);
+            .get_min_offset_in_queue(topic.as_str(), queue_id)
+            .value();
         let response_header = GetMinOffsetResponseHeader { offset };
         Some(RemotingCommand::create_response_command_with_header(
             response_header,
         ))
     }
+
+    pub async fn get_earliest_msg_storetime(
+        &mut self,
+        _channel: Channel,
+        _ctx: ConnectionHandlerContext,
+        _request_code: RequestCode,
+        request: RemotingCommand,
+    ) -> Option<RemotingCommand> {
+        let request_header =
+            request.decode_command_custom_header::<GetEarliestMsgStoretimeRequestHeader>()?;
+        if let Some(response) =
+            self.check_queue_range(request_header.topic.as_str(), request_header.queue_id)
+        {
+            return Some(response);
+        }
+
+        let timestamp = self
+            .inner
+            .default_message_store
+            .get_earliest_message_time_in_queue(
+                request_header.topic.as_str(),
+                request_header.queue_id,
+            );
+        Some(RemotingCommand::create_response_command_with_header(
+            GetEarliestMsgStoretimeResponseHeader { timestamp },
+        ))
+    }
+
+    /// Rejects queue ids the topic does not have, answering with the offsets of a queue which
+    /// does not exist would send admin tools and offset resets to a wrong position. LMQ topics
+    /// have no topic config and are not checked.
+    fn check_queue_range(&self, topic: &str, queue_id: i32) -> Option<RemotingCommand> {
+        if is_lmq(Some(topic)) {
+            return None;
+        }
+        check_queue_range(
+            self.inner
+                .topic_config_manager
+                .select_topic_config(topic)
+                .as_ref(),
+            topic,
+            queue_id,
+        )
+    }
     /*
     async fn handle_get_min_offset(
         &mut self,
@@ -145,6 +217,7 @@ impl OffsetRequestHandler {
             self.inner
                 .default_message_store
                 .get_min_offset_in_queue(mapping_context.topic.as_str(), max_item.queue_id)
+                .value()
         } else {
             let rpc_request =
                 RpcRequest::new(RequestCode::GetMinOffset.to_i32(), request_header, None);
@@ -213,7 +286,12 @@ impl OffsetRequestHandler {
         {
             self.inner
                 .default_message_store
-                .get_max_offset_in_queue(mapping_context.topic.as_str(), max_item.queue_id)
+                .get_max_offset_in_queue_committed(
+                    mapping_context.topic.as_str(),
+                    max_item.queue_id,
+                    request_header.committed,
+                )
+                .value()
         } else {
             let rpc_request = RpcRequest::new(
                 RequestCode::GetMaxOffset.to_i32(),
@@ -254,4 +332,381 @@ impl OffsetRequestHandler {
             },
         ))
     }
+
+    pub async fn search_offset_by_timestamp(
+        &mut self,
+        _channel: Channel,
+        _ctx: ConnectionHandlerContext,
+        _request_code: RequestCode,
+        request: RemotingCommand,
+    ) -> Option<RemotingCommand> {
+        let request_header = request.decode_command_custom_header::<SearchOffsetRequestHeader>()?;
+        let mapping_context = self
+            .inner
+            .topic_queue_mapping_manager
+            .build_topic_queue_mapping_context(&request_header, false);
+        if mapping_context.mapping_detail.is_some() {
+            return self
+                .handle_search_offset_for_static_topic(request_header, mapping_context)
+                .await;
+        }
+        if let Some(response) =
+            self.check_queue_range(request_header.topic.as_str(), request_header.queue_id)
+        {
+            return Some(response);
+        }
+
+        let offset = self
+            .inner
+            .default_message_store
+            .get_offset_in_queue_by_time(
+                request_header.topic.as_str(),
+                request_header.queue_id,
+                request_header.timestamp,
+                request_header.boundary_type.unwrap_or(BoundaryType::Lower),
+            )
+            .value();
+        Some(RemotingCommand::create_response_command_with_header(
+            SearchOffsetResponseHeader { offset },
+        ))
+    }
+
+    /// Searches the mapping items from the newest one backwards, a timestamp earlier than the
+    /// first message of an item belongs to an older item.
+    async fn handle_search_offset_for_static_topic(
+        &mut self,
+        request_header: SearchOffsetRequestHeader,
+        mapping_context: TopicQueueMappingContext,
+    ) -> Option<RemotingCommand> {
+        let mapping_detail = mapping_context.mapping_detail.as_ref()?;
+        if !mapping_context.is_leader() {
+            return Some(
+                RemotingCommand::create_response_command_with_code(ResponseCode::NotLeaderForQueue)
+                    .set_remark(Some(format!(
+                        "{}-{:?} does not exit in request process of current broker {:?}",
+                        mapping_context.topic,
+                        mapping_context.global_id,
+                        mapping_detail.topic_queue_mapping_info.bname
+                    ))),
+            );
+        }
+
+        let boundary_type = request_header.boundary_type.unwrap_or(BoundaryType::Lower);
+        let mut mapping_items = mapping_context.mapping_item_list.clone();
+        mapping_items.sort_by_key(|item| item.logic_offset);
+        for (index, item) in mapping_items.iter().enumerate().rev() {
+            if item.logic_offset < 0 {
+                continue;
+            }
+            let physical_offset = if item.bname == mapping_detail.topic_queue_mapping_info.bname {
+                self.inner
+                    .default_message_store
+                    .get_offset_in_queue_by_time(
+                        mapping_context.topic.as_str(),
+                        item.queue_id,
+                        request_header.timestamp,
+                        boundary_type,
+                    )
+                    .value()
+            } else {
+                let mut item_request_header = request_header.clone();
+                item_request_header.set_broker_name(item.bname.clone()?);
+                item_request_header.set_lo(Some(false));
+                item_request_header.queue_id = item.queue_id;
+                let rpc_request = RpcRequest::new(
+                    RequestCode::SearchOffsetByTimestamp.to_i32(),
+                    item_request_header,
+                    None,
+                );
+                let rpc_response = self
+                    .inner
+                    .broker_out_api
+                    .rpc_client()
+                    .invoke(rpc_request, self.inner.broker_config.forward_timeout)
+                    .await;
+                match rpc_response {
+                    Err(e) => {
+                        return Some(
+                            RemotingCommand::create_response_command_with_code(
+                                ResponseCode::SystemError,
+                            )
+                            .set_remark(Some(format!("{}", e))),
+                        );
+                    }
+                    Ok(response) => match response.get_header::<SearchOffsetResponseHeader>() {
+                        None => {
+                            return Some(
+                                RemotingCommand::create_response_command_with_code(
+                                    ResponseCode::SystemError,
+                                )
+                                .set_remark(Some("Rpc response header is None".to_string())),
+                            );
+                        }
+                        Some(offset_response_header) => offset_response_header.offset,
+                    },
+                }
+            };
+            if index > 0 && physical_offset <= item.start_offset {
+                continue;
+            }
+            return Some(RemotingCommand::create_response_command_with_header(
+                SearchOffsetResponseHeader {
+                    offset: item.compute_static_queue_offset_loosely(physical_offset),
+                },
+            ));
+        }
+        Some(
+            RemotingCommand::create_response_command_with_code(ResponseCode::SystemError)
+                .set_remark(Some(format!(
+                    "no mapping item of {}-{:?} to search",
+                    mapping_context.topic, mapping_context.global_id
+                ))),
+        )
+    }
+
+    pub async fn get_all_consumer_offset(
+        &mut self,
+        _channel: Channel,
+        _ctx: ConnectionHandlerContext,
+        _request_code: RequestCode,
+        _request: RemotingCommand,
+    ) -> Option<RemotingCommand> {
+        let content = self.inner.consumer_offset_manager.encode_pretty(false);
+        if content.is_empty() {
+            return Some(
+                RemotingCommand::create_response_command_with_code(ResponseCode::SystemError)
+                    .set_remark(Some("No consumer offset in this broker".to_string())),
+            );
+        }
+        Some(RemotingCommand::create_response_command().set_body(Some(content)))
+    }
+
+    pub async fn get_all_delay_offset(
+        &mut self,
+        _channel: Channel,
+        _ctx: ConnectionHandlerContext,
+        _request_code: RequestCode,
+        _request: RemotingCommand,
+    ) -> Option<RemotingCommand> {
+        let file_name = self.inner.schedule_message_service.config_file_path();
+        let content = match file_utils::file_to_string(file_name.as_str()) {
+            Ok(content) => content,
+            Err(e) => {
+                warn!("read delay offset file {} failed: {}", file_name, e);
+                String::new()
+            }
+        };
+        if content.is_empty() {
+            return Some(
+                RemotingCommand::create_response_command_with_code(ResponseCode::SystemError)
+                    .set_remark(Some("No delay offset in this broker".to_string())),
+            );
+        }
+        Some(RemotingCommand::create_response_command().set_body(Some(content)))
+    }
+
+    pub async fn clone_group_offset(
+        &mut self,
+        _channel: Channel,
+        _ctx: ConnectionHandlerContext,
+        _request_code: RequestCode,
+        request: RemotingCommand,
+    ) -> Option<RemotingCommand> {
+        let request_header =
+            request.decode_command_custom_header::<CloneGroupOffsetRequestHeader>()?;
+        let src_group = request_header.src_group.as_str();
+        let topics = match request_header.topic.as_ref() {
+            Some(topic) if !topic.is_empty() => HashSet::from([topic.clone()]),
+            _ => self
+                .inner
+                .consumer_offset_manager
+                .which_topic_by_consumer(src_group),
+        };
+        for topic in topics {
+            if self
+                .inner
+                .topic_config_manager
+                .select_topic_config(topic.as_str())
+                .is_none()
+            {
+                warn!("[cloneGroupOffset], topic config not exist, {}", topic);
+                continue;
+            }
+            if !request_header.offline {
+                // online consumers of the source group no longer subscribe to the topic
+                let unsubscribed = self
+                    .inner
+                    .consume_manager
+                    .get_consumer_group_info(src_group)
+                    .is_some_and(|group_info| {
+                        !group_info.get_subscribe_topics().is_empty()
+                            && group_info.find_subscription_data(topic.as_str()).is_none()
+                    });
+                if unsubscribed {
+                    warn!(
+                        "[cloneGroupOffset], the consumer group[{}], topic[{}] not exist",
+                        src_group, topic
+                    );
+                    continue;
+                }
+            }
+            self.inner.consumer_offset_manager.clone_offset(
+                src_group,
+                request_header.dest_group.as_str(),
+                topic.as_str(),
+            );
+        }
+        Some(RemotingCommand::create_response_command())
+    }
+
+    pub async fn query_consume_time_span(
+        &mut self,
+        _channel: Channel,
+        _ctx: ConnectionHandlerContext,
+        _request_code: RequestCode,
+        request: RemotingCommand,
+    ) -> Option<RemotingCommand> {
+        let request_header =
+            request.decode_command_custom_header::<QueryConsumeTimeSpanRequestHeader>()?;
+        let topic = request_header.topic.as_str();
+        let Some(topic_config) = self.inner.topic_config_manager.select_topic_config(topic) else {
+            return Some(
+                RemotingCommand::create_response_command_with_code(ResponseCode::TopicNotExist)
+                    .set_remark(Some(format!("topic[{}] not exist", topic))),
+            );
+        };
+        let message_store = &self.inner.default_message_store;
+        let now = get_current_millis() as i64;
+        let consume_time_span_set = (0..topic_config.write_queue_nums as i32)
+            .map(|queue_id| {
+                queue_time_span(
+                    MessageQueue::from_parts(
+                        topic,
+                        self.inner.broker_config.broker_name.as_str(),
+                        queue_id,
+                    ),
+                    message_store.get_earliest_message_time_in_queue(topic, queue_id),
+                    message_store
+                        .get_max_offset_in_queue(topic, queue_id)
+                        .value(),
+                    self.inner.consumer_offset_manager.query_offset(
+                        request_header.group.as_str(),
+                        topic,
+                        queue_id,
+                    ),
+                    |offset| message_store.get_message_store_timestamp(topic, queue_id, offset),
+                    now,
+                )
+            })
+            .collect();
+        Some(
+            RemotingCommand::create_response_command().set_body(Some(
+                QueryConsumeTimeSpanBody {
+                    consume_time_span_set,
+                }
+                .to_json(),
+            )),
+        )
+    }
+}
+
+/// Time span of `mq` for a group which committed `consumer_offset`, `store_time` looks up the
+/// store time of the message at a consume queue offset.
+fn queue_time_span(
+    mq: MessageQueue,
+    min_time_stamp: i64,
+    max_offset: i64,
+    consumer_offset: i64,
+    store_time: impl Fn(i64) -> i64,
+    now: i64,
+) -> QueueTimeSpan {
+    let consume_time_stamp = if consumer_offset > 0 {
+        store_time(consumer_offset - 1)
+    } else {
+        min_time_stamp
+    };
+    let delay_time = if consumer_offset < max_offset {
+        now - store_time(consumer_offset.max(0))
+    } else {
+        0
+    };
+    QueueTimeSpan {
+        message_queue: mq,
+        min_time_stamp,
+        max_time_stamp: store_time(max_offset - 1),
+        consume_time_stamp,
+        delay_time,
+    }
+}
+
+fn check_queue_range(
+    topic_config: Option<&TopicConfig>,
+    topic: &str,
+    queue_id: i32,
+) -> Option<RemotingCommand> {
+    let Some(topic_config) = topic_config else {
+        return Some(
+            RemotingCommand::create_response_command_with_code(ResponseCode::TopicNotExist)
+                .set_remark(Some(format!("topic[{}] not exist", topic))),
+        );
+    };
+    let queue_nums = topic_config
+        .read_queue_nums
+        .max(topic_config.write_queue_nums) as i32;
+    if queue_id < 0 || queue_id >= queue_nums {
+        return Some(
+            RemotingCommand::create_response_command_with_code(ResponseCode::SystemError)
+                .set_remark(Some(format!(
+                    "queueId[{}] is illegal, topic:[{}] topicConfig.readQueueNums:[{}] \
+                     topicConfig.writeQueueNums:[{}]",
+                    queue_id, topic, topic_config.read_queue_nums, topic_config.write_queue_nums
+                ))),
+        );
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn queue_time_span_follows_the_consumer_offset() {
+        // message at offset n was stored at 1000 + n
+        let store_time = |offset: i64| 1000 + offset;
+        let span = queue_time_span(MessageQueue::default(), 1000, 10, 4, store_time, 2000);
+        assert_eq!(span.max_time_stamp, 1009);
+        assert_eq!(span.consume_time_stamp, 1003);
+        assert_eq!(span.delay_time, 996);
+
+        let never_consumed =
+            queue_time_span(MessageQueue::default(), 1000, 10, -1, store_time, 2000);
+        assert_eq!(never_consumed.consume_time_stamp, 1000);
+        assert_eq!(never_consumed.delay_time, 1000);
+
+        let caught_up = queue_time_span(MessageQueue::default(), 1000, 10, 10, store_time, 2000);
+        assert_eq!(caught_up.consume_time_stamp, 1009);
+        assert_eq!(caught_up.delay_time, 0);
+    }
+
+    #[test]
+    fn check_queue_range_rejects_unknown_topics_and_queues() {
+        let mut topic_config = TopicConfig::new("TopicTest");
+        topic_config.read_queue_nums = 4;
+        topic_config.write_queue_nums = 8;
+
+        assert!(check_queue_range(Some(&topic_config), "TopicTest", 0).is_none());
+        assert!(check_queue_range(Some(&topic_config), "TopicTest", 7).is_none());
+        assert_eq!(
+            check_queue_range(Some(&topic_config), "TopicTest", 8)
+                .unwrap()
+                .code(),
+            ResponseCode::SystemError as i32
+        );
+        assert!(check_queue_range(Some(&topic_config), "TopicTest", -1).is_some());
+        assert_eq!(
+            check_queue_range(None, "TopicTest", 0).unwrap().code(),
+            ResponseCode::TopicNotExist as i32
+        );
+    }
 }
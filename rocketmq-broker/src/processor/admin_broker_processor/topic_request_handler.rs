@@ -24,8 +24,10 @@ use rocketmq_common::common::attribute::topic_message_type::TopicMessageType;
 use rocketmq_common::common::config::TopicConfig;
 use rocketmq_common::common::key_builder::KeyBuilder;
 use rocketmq_common::common::message::message_queue::MessageQueue;
+use rocketmq_common::common::offset::QueueOffset;
 use rocketmq_common::common::topic::TopicValidator;
 use rocketmq_common::common::TopicFilterType;
+use rocketmq_common::utils::network_util::NetworkUtil;
 use rocketmq_remoting::code::request_code::RequestCode;
 use rocketmq_remoting::code::response_code::ResponseCode;
 use rocketmq_remoting::net::channel::Channel;
@@ -136,15 +138,16 @@ impl TopicRequestHandler {
             order: request_header.order,
             attributes,
         };
-        if topic_config.get_topic_message_type() == TopicMessageType::Mixed
-            && !self.inner.broker_config.enable_mixed_message_type
-        {
-            return Some(
-                response
-                    .set_code(ResponseCode::SystemError)
-                    .set_remark(Some("MIXED message type is not supported.".to_string())),
-            );
-        }
+        let altered_topic_config = match self.altered_topic_config(&topic_config) {
+            Ok(value) => value,
+            Err(err) => {
+                return Some(
+                    response
+                        .set_code(ResponseCode::SystemError)
+                        .set_remark(Some(err)),
+                );
+            }
+        };
 
         let topic_config_origin = self
             .inner
@@ -153,18 +156,39 @@ impl TopicRequestHandler {
             .lock()
             .get(topic)
             .cloned();
-        if topic_config_origin.is_some() && topic_config == topic_config_origin.unwrap() {
-            info!(
-                "Broker receive request to update or create topic={}, but topicConfig has  no \
-                 changes , so idempotent, caller address={}",
-                topic,
-                channel.remote_address(),
-            );
-            return Some(response.set_code(ResponseCode::Success));
+        if let Some(topic_config_origin) = topic_config_origin {
+            if altered_topic_config == topic_config_origin {
+                info!(
+                    "Broker receive request to update or create topic={}, but topicConfig has  no \
+                     changes , so idempotent, caller address={}",
+                    topic,
+                    channel.remote_address(),
+                );
+                return Some(response.set_code(ResponseCode::Success));
+            }
+            if let Err(remark) = self.check_queue_nums_shrink(
+                &topic_config_origin,
+                &topic_config,
+                request_header.force.unwrap_or(false),
+            ) {
+                return Some(
+                    response
+                        .set_code(ResponseCode::SystemError)
+                        .set_remark(Some(remark)),
+                );
+            }
         }
-        self.inner
+        if let Err(err) = self
+            .inner
             .topic_config_manager
-            .update_topic_config(&mut topic_config);
+            .update_topic_config(&mut topic_config)
+        {
+            return Some(
+                response
+                    .set_code(ResponseCode::SystemError)
+                    .set_remark(Some(err)),
+            );
+        }
 
         if self.inner.broker_config.enable_single_topic_register {
             self.inner
@@ -209,6 +233,11 @@ impl TopicRequestHandler {
             topic_names,
             channel.remote_address()
         );
+        let force = request
+            .get_ext_fields()
+            .and_then(|fields| fields.get(CreateTopicRequestHeader::FORCE))
+            .and_then(|force| force.parse().ok())
+            .unwrap_or(false);
         let response = RemotingCommand::create_response_command();
         for topic_config in request_body.topic_config_list.iter() {
             let topic = topic_config.topic_name.as_ref().unwrap().as_str();
@@ -235,15 +264,16 @@ impl TopicRequestHandler {
                         ))),
                 );
             }
-            if topic_config.get_topic_message_type() == TopicMessageType::Mixed
-                && !self.inner.broker_config.enable_mixed_message_type
-            {
-                return Some(
-                    response
-                        .set_code(ResponseCode::SystemError)
-                        .set_remark(Some("MIXED message type is not supported.".to_string())),
-                );
-            }
+            let altered_topic_config = match self.altered_topic_config(topic_config) {
+                Ok(value) => value,
+                Err(err) => {
+                    return Some(
+                        response
+                            .set_code(ResponseCode::SystemError)
+                            .set_remark(Some(err)),
+                    );
+                }
+            };
             let topic_config_origin = self
                 .inner
                 .topic_config_manager
@@ -251,21 +281,39 @@ impl TopicRequestHandler {
                 .lock()
                 .get(topic)
                 .cloned();
-            if topic_config_origin.is_some() && topic_config.clone() == topic_config_origin.unwrap()
-            {
-                info!(
-                    "Broker receive request to update or create topic={}, but topicConfig has  no \
-                     changes , so idempotent, caller address={}",
-                    topic,
-                    channel.remote_address(),
-                );
-                return Some(response.set_code(ResponseCode::Success));
+            if let Some(topic_config_origin) = topic_config_origin {
+                if altered_topic_config == topic_config_origin {
+                    info!(
+                        "Broker receive request to update or create topic={}, but topicConfig has \
+                         no changes , so idempotent, caller address={}",
+                        topic,
+                        channel.remote_address(),
+                    );
+                    return Some(response.set_code(ResponseCode::Success));
+                }
+                if let Err(remark) =
+                    self.check_queue_nums_shrink(&topic_config_origin, topic_config, force)
+                {
+                    return Some(
+                        response
+                            .set_code(ResponseCode::SystemError)
+                            .set_remark(Some(remark)),
+                    );
+                }
             }
         }
 
-        self.inner
+        if let Err(err) = self
+            .inner
             .topic_config_manager
-            .update_topic_config_list(request_body.topic_config_list.as_mut_slice());
+            .update_topic_config_list(request_body.topic_config_list.as_mut_slice())
+        {
+            return Some(
+                response
+                    .set_code(ResponseCode::SystemError)
+                    .set_remark(Some(err)),
+            );
+        }
         if self.inner.broker_config.enable_single_topic_register {
             for topic_config in request_body.topic_config_list.iter() {
                 self.inner
@@ -452,27 +500,25 @@ impl TopicRequestHandler {
             message_queue.set_broker_name(self.inner.broker_config.broker_name.clone());
             message_queue.set_queue_id(i as i32);
             let mut topic_offset = TopicOffset::new();
-            let min = std::cmp::max(
-                self.inner
-                    .default_message_store
-                    .get_min_offset_in_queue(topic, i as i32),
-                0,
-            );
-            let max = std::cmp::max(
-                self.inner
-                    .default_message_store
-                    .get_max_offset_in_queue(topic, i as i32),
-                0,
-            );
+            let min = self
+                .inner
+                .default_message_store
+                .get_min_offset_in_queue(topic, i as i32)
+                .max(QueueOffset::default());
+            let max = self
+                .inner
+                .default_message_store
+                .get_max_offset_in_queue(topic, i as i32)
+                .max(QueueOffset::default());
             let mut timestamp = 0;
-            if max > 0 {
+            if max > QueueOffset::default() {
                 timestamp = self
                     .inner
                     .default_message_store
-                    .get_message_store_timestamp(topic, i as i32, max - 1);
+                    .get_message_store_timestamp(topic, i as i32, max.value() - 1);
             }
-            topic_offset.set_min_offset(min);
-            topic_offset.set_max_offset(max);
+            topic_offset.set_min_offset(min.value());
+            topic_offset.set_max_offset(max.value());
             topic_offset.set_last_update_timestamp(timestamp);
             map.insert(message_queue, topic_offset);
         }
@@ -559,9 +605,9 @@ impl TopicRequestHandler {
             .inner
             .consumer_offset_manager
             .which_topic_by_consumer(request_header.get_group());
-        let broker_addr = format!(
-            "{}:{}",
-            self.inner.broker_config.broker_ip1, self.inner.server_config.listen_port
+        let broker_addr = NetworkUtil::host_port(
+            &self.inner.broker_config.broker_ip1,
+            self.inner.server_config.listen_port,
         );
         let topic_list = TopicList {
             topic_list: topics.into_iter().collect(),
@@ -571,6 +617,51 @@ impl TopicRequestHandler {
         Some(response)
     }
 
+    /// `topic_config` with its attribute modifications applied, rejects invalid modifications
+    /// and MIXED topics unless enabled.
+    fn altered_topic_config(&self, topic_config: &TopicConfig) -> Result<TopicConfig, String> {
+        let mut altered_topic_config = topic_config.clone();
+        altered_topic_config.attributes = self
+            .inner
+            .topic_config_manager
+            .alter_topic_attributes(topic_config)?;
+        if altered_topic_config.get_topic_message_type() == TopicMessageType::Mixed
+            && !self.inner.broker_config.enable_mixed_message_type
+        {
+            return Err("MIXED message type is not supported.".to_string());
+        }
+        Ok(altered_topic_config)
+    }
+
+    fn check_queue_nums_shrink(
+        &self,
+        topic_config_origin: &TopicConfig,
+        topic_config: &TopicConfig,
+        force: bool,
+    ) -> Result<(), String> {
+        let topic = topic_config.topic_name.as_deref().unwrap_or_default();
+        check_queue_nums_shrink(
+            topic_config_origin,
+            topic_config,
+            force,
+            &self
+                .inner
+                .consumer_offset_manager
+                .which_group_by_topic(topic),
+            |queue_id| {
+                self.inner
+                    .default_message_store
+                    .get_max_offset_in_queue(topic, queue_id)
+                    .value()
+            },
+            |group, queue_id| {
+                self.inner
+                    .consumer_offset_manager
+                    .query_offset(group, topic, queue_id)
+            },
+        )
+    }
+
     fn delete_topic_in_broker(&mut self, topic: &str) {
         self.inner.topic_config_manager.delete_topic_config(topic);
         self.inner.topic_queue_mapping_manager.delete(topic);
@@ -583,3 +674,136 @@ impl TopicRequestHandler {
         self.inner.default_message_store.delete_topics(vec![topic]);
     }
 }
+
+/// Queues dropped out of the read range can no longer be consumed, so unless `force` is set
+/// shrinking is refused while any of them still holds messages one of `groups` has not caught up
+/// with. `max_offset` and `consumer_offset` look up the queue and group offsets by queue id.
+fn check_queue_nums_shrink(
+    topic_config_origin: &TopicConfig,
+    topic_config: &TopicConfig,
+    force: bool,
+    groups: &HashSet<String>,
+    max_offset: impl Fn(i32) -> i64,
+    consumer_offset: impl Fn(&str, i32) -> i64,
+) -> Result<(), String> {
+    let topic = topic_config.topic_name.as_deref().unwrap_or_default();
+    let read_queue_nums = topic_config.read_queue_nums;
+    if force || read_queue_nums >= topic_config_origin.read_queue_nums {
+        return Ok(());
+    }
+    if topic_config.write_queue_nums > read_queue_nums {
+        return Err(format!(
+            "Shrink topic[{}] failed, writeQueueNums {} must not exceed readQueueNums {}",
+            topic, topic_config.write_queue_nums, read_queue_nums
+        ));
+    }
+    let max_queue_nums = topic_config_origin
+        .read_queue_nums
+        .max(topic_config_origin.write_queue_nums);
+    for queue_id in read_queue_nums..max_queue_nums {
+        let max_offset = max_offset(queue_id as i32);
+        if max_offset <= 0 {
+            continue;
+        }
+        for group in groups.iter() {
+            let consumer_offset = consumer_offset(group, queue_id as i32);
+            if consumer_offset < max_offset {
+                return Err(format!(
+                    "Shrink topic[{}] failed, queue {} still has {} messages unconsumed by group \
+                     {}",
+                    topic,
+                    queue_id,
+                    max_offset - consumer_offset.max(0),
+                    group
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn topic_config(queue_nums: u32) -> TopicConfig {
+        let mut topic_config = TopicConfig::new("TopicTest");
+        topic_config.read_queue_nums = queue_nums;
+        topic_config.write_queue_nums = queue_nums;
+        topic_config
+    }
+
+    #[test]
+    fn check_queue_nums_shrink_refuses_dropping_unconsumed_queues() {
+        let groups = HashSet::from(["GroupA".to_string()]);
+        // every queue holds 10 messages, the group consumed all but queue 2
+        let max_offset = |_queue_id: i32| 10;
+        let consumer_offset = |_group: &str, queue_id: i32| if queue_id == 2 { 4 } else { 10 };
+
+        let remark = check_queue_nums_shrink(
+            &topic_config(4),
+            &topic_config(2),
+            false,
+            &groups,
+            max_offset,
+            consumer_offset,
+        )
+        .unwrap_err();
+        assert!(remark.contains("queue 2 still has 6 messages unconsumed by group GroupA"));
+
+        assert!(check_queue_nums_shrink(
+            &topic_config(4),
+            &topic_config(3),
+            false,
+            &groups,
+            max_offset,
+            consumer_offset,
+        )
+        .is_ok());
+        assert!(check_queue_nums_shrink(
+            &topic_config(2),
+            &topic_config(4),
+            false,
+            &groups,
+            max_offset,
+            consumer_offset,
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn check_queue_nums_shrink_is_skipped_when_forced() {
+        let groups = HashSet::from(["GroupA".to_string()]);
+        let mut shrunk = topic_config(2);
+        shrunk.write_queue_nums = 4;
+
+        assert!(check_queue_nums_shrink(
+            &topic_config(4),
+            &shrunk,
+            false,
+            &groups,
+            |_| 10,
+            |_, _| 0,
+        )
+        .unwrap_err()
+        .contains("writeQueueNums 4 must not exceed readQueueNums 2"));
+        assert!(check_queue_nums_shrink(
+            &topic_config(4),
+            &shrunk,
+            true,
+            &groups,
+            |_| 10,
+            |_, _| 0
+        )
+        .is_ok());
+        assert!(check_queue_nums_shrink(
+            &topic_config(4),
+            &topic_config(2),
+            true,
+            &groups,
+            |_| 10,
+            |_, _| 0,
+        )
+        .is_ok());
+    }
+}
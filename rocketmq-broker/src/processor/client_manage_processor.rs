@@ -23,14 +23,17 @@ use rocketmq_common::common::constant::PermName;
 use rocketmq_common::common::mix_all;
 use rocketmq_common::common::mix_all::IS_SUB_CHANGE;
 use rocketmq_common::common::mix_all::IS_SUPPORT_HEART_BEAT_V2;
+use rocketmq_common::common::mix_all::SUBSCRIPTION_DATA_VERSION;
 use rocketmq_common::common::sys_flag::topic_sys_flag;
 use rocketmq_common::utils::serde_json_utils::SerdeJsonUtils;
 use rocketmq_remoting::code::request_code::RequestCode;
 use rocketmq_remoting::net::channel::Channel;
 use rocketmq_remoting::protocol::header::unregister_client_request_header::UnregisterClientRequestHeader;
 use rocketmq_remoting::protocol::heartbeat::consume_type::ConsumeType;
+use rocketmq_remoting::protocol::heartbeat::consumer_data::ConsumerData;
 use rocketmq_remoting::protocol::heartbeat::heartbeat_data::HeartbeatData;
 use rocketmq_remoting::protocol::remoting_command::RemotingCommand;
+use rocketmq_remoting::protocol::DataVersion;
 use rocketmq_remoting::runtime::connection_handler_context::ConnectionHandlerContext;
 use rocketmq_store::log_file::MessageStore;
 use tracing::info;
@@ -38,6 +41,7 @@ use tracing::info;
 use crate::client::client_channel_info::ClientChannelInfo;
 use crate::client::manager::consumer_manager::ConsumerManager;
 use crate::client::manager::producer_manager::ProducerManager;
+use crate::processor::heartbeat_inflight_table::HeartbeatInflightTable;
 use crate::subscription::manager::subscription_group_manager::SubscriptionGroupManager;
 use crate::topic::manager::topic_config_manager::TopicConfigManager;
 
@@ -46,6 +50,7 @@ pub struct ClientManageProcessor<MS> {
     consumer_group_heartbeat_table: Arc<
         parking_lot::RwLock<HashMap<String /* ConsumerGroup */, i32 /* HeartbeatFingerprint */>>,
     >,
+    heartbeat_inflight_table: Arc<HeartbeatInflightTable>,
     producer_manager: Arc<ProducerManager>,
     consumer_manager: Arc<ConsumerManager>,
     topic_config_manager: TopicConfigManager,
@@ -66,6 +71,7 @@ where
     ) -> Self {
         Self {
             consumer_group_heartbeat_table: Arc::new(parking_lot::RwLock::new(HashMap::new())),
+            heartbeat_inflight_table: Arc::new(HeartbeatInflightTable::default()),
             producer_manager,
             consumer_manager,
             topic_config_manager,
@@ -120,8 +126,16 @@ where
                 .unregister_producer(group, &client_channel_info, &ctx);
         }
 
-        if let Some(ref _group) = request_header.consumer_group {
-            unimplemented!()
+        if let Some(ref group) = request_header.consumer_group {
+            let is_notify_consumer_ids_changed_enable = self
+                .subscription_group_manager
+                .find_subscription_group_config(group)
+                .is_none_or(|config| config.notify_consumer_ids_changed_enable());
+            self.consumer_manager.unregister_consumer(
+                group,
+                &client_channel_info,
+                is_notify_consumer_ids_changed_enable,
+            );
         }
 
         Some(RemotingCommand::create_response_command())
@@ -148,66 +162,20 @@ where
         }
 
         //do consumer data handle
+        let mut subscription_data_versions = HashMap::new();
         for consumer_data in heartbeat_data.consumer_data_set.iter() {
             if self.broker_config.reject_pull_consumer_enable
                 && ConsumeType::ConsumeActively == consumer_data.consume_type
             {
                 continue;
             }
-            self.consumer_group_heartbeat_table.write().insert(
-                consumer_data.group_name.clone(),
+            self.register_consumer_data(
+                &channel,
+                consumer_data,
+                &client_channel_info,
                 heartbeat_data.heartbeat_fingerprint,
+                &mut subscription_data_versions,
             );
-            let mut has_order_topic_sub = false;
-            for subscription_data in consumer_data.subscription_data_set.iter() {
-                if self
-                    .topic_config_manager
-                    .is_order_topic(subscription_data.topic.as_str())
-                {
-                    has_order_topic_sub = true;
-                    break;
-                }
-            }
-            let subscription_group_config = self
-                .subscription_group_manager
-                .find_subscription_group_config(consumer_data.group_name.as_str());
-            if subscription_group_config.is_none() {
-                continue;
-            }
-            let subscription_group_config = subscription_group_config.unwrap();
-            let is_notify_consumer_ids_changed_enable =
-                subscription_group_config.notify_consumer_ids_changed_enable();
-            let topic_sys_flag = if consumer_data.unit_mode {
-                topic_sys_flag::build_sys_flag(false, true)
-            } else {
-                0
-            };
-            let new_topic = mix_all::get_retry_topic(consumer_data.group_name.as_str());
-            self.topic_config_manager
-                .create_topic_in_send_message_back_method(
-                    new_topic.as_str(),
-                    subscription_group_config.retry_queue_nums(),
-                    PermName::PERM_WRITE | PermName::PERM_READ,
-                    has_order_topic_sub,
-                    topic_sys_flag,
-                );
-            let changed = self.consumer_manager.register_consumer(
-                consumer_data.group_name.as_str(),
-                client_channel_info.clone(),
-                consumer_data.consume_type,
-                consumer_data.message_model,
-                consumer_data.consume_from_where,
-                consumer_data.subscription_data_set.clone(),
-                is_notify_consumer_ids_changed_enable,
-            );
-            if changed {
-                info!(
-                    "ClientManageProcessor: registerConsumer info changed, SDK address={}, \
-                     consumerData={:?}",
-                    channel.remote_address(),
-                    consumer_data
-                )
-            }
         }
         //do producer data handle
         for producer_data in heartbeat_data.producer_data_set.iter() {
@@ -217,27 +185,178 @@ where
         let mut response_command = RemotingCommand::create_response_command();
         response_command.add_ext_field(IS_SUPPORT_HEART_BEAT_V2.to_string(), true.to_string());
         response_command.add_ext_field(IS_SUB_CHANGE.to_string(), true.to_string());
+        if !subscription_data_versions.is_empty() {
+            response_command.add_ext_field(
+                SUBSCRIPTION_DATA_VERSION.to_string(),
+                SerdeJsonUtils::to_json(&subscription_data_versions).unwrap_or_default(),
+            );
+        }
         Some(response_command)
     }
 
+    /// Registers the consumer of `consumer_data` with its subscriptions and remembers
+    /// `heartbeat_fingerprint` as the fingerprint of the subscriptions of the group.
+    fn register_consumer_data(
+        &mut self,
+        channel: &Channel,
+        consumer_data: &ConsumerData,
+        client_channel_info: &ClientChannelInfo,
+        heartbeat_fingerprint: i32,
+        subscription_data_versions: &mut HashMap<String, DataVersion>,
+    ) {
+        self.consumer_group_heartbeat_table
+            .write()
+            .insert(consumer_data.group_name.clone(), heartbeat_fingerprint);
+        let mut has_order_topic_sub = false;
+        for subscription_data in consumer_data.subscription_data_set.iter() {
+            if self
+                .topic_config_manager
+                .is_order_topic(subscription_data.topic.as_str())
+            {
+                has_order_topic_sub = true;
+                break;
+            }
+        }
+        let Some(subscription_group_config) = self
+            .subscription_group_manager
+            .find_subscription_group_config(consumer_data.group_name.as_str())
+        else {
+            return;
+        };
+        let is_notify_consumer_ids_changed_enable =
+            subscription_group_config.notify_consumer_ids_changed_enable();
+        let topic_sys_flag = if consumer_data.unit_mode {
+            topic_sys_flag::build_sys_flag(false, true)
+        } else {
+            0
+        };
+        let new_topic = mix_all::get_retry_topic(consumer_data.group_name.as_str());
+        self.topic_config_manager
+            .create_topic_in_send_message_back_method(
+                new_topic.as_str(),
+                subscription_group_config.retry_queue_nums(),
+                PermName::PERM_WRITE | PermName::PERM_READ,
+                has_order_topic_sub,
+                topic_sys_flag,
+            );
+        let changed = self.consumer_manager.register_consumer(
+            consumer_data.group_name.as_str(),
+            client_channel_info.clone(),
+            consumer_data.consume_type,
+            consumer_data.message_model,
+            consumer_data.consume_from_where,
+            consumer_data.subscription_data_set.clone(),
+            is_notify_consumer_ids_changed_enable,
+        );
+        if changed {
+            info!(
+                "ClientManageProcessor: registerConsumer info changed, SDK address={}, \
+                 consumerData={:?}",
+                channel.remote_address(),
+                consumer_data
+            )
+        }
+        if let Some(consumer_group_info) = self
+            .consumer_manager
+            .get_consumer_group_info(consumer_data.group_name.as_str())
+        {
+            subscription_data_versions.insert(
+                consumer_data.group_name.clone(),
+                consumer_group_info.get_subscription_data_version(),
+            );
+        }
+    }
+
+    /// Handles a heartbeat of a client sending the fingerprint of its subscriptions. The client
+    /// leaves the subscriptions out while the fingerprint is unchanged, then only the channels
+    /// are refreshed, unless the broker does not know that fingerprint for the group, e.g. after
+    /// a restart, and answers with `IS_SUB_CHANGE` to get the subscriptions in the next
+    /// heartbeat. A heartbeat duplicating one of the same client still in flight is answered
+    /// right away.
     fn heart_beat_v2(
-        &self,
-        _channel: &Channel,
+        &mut self,
+        channel: &Channel,
         _ctx: &ConnectionHandlerContext,
         heartbeat_data: HeartbeatData,
         client_channel_info: ClientChannelInfo,
     ) -> Option<RemotingCommand> {
-        let is_sub_change = false;
+        let mut response_command = RemotingCommand::create_response_command();
+        response_command.add_ext_field(IS_SUPPORT_HEART_BEAT_V2.to_string(), true.to_string());
+        let Some(_inflight) = self.heartbeat_inflight_table.try_begin(
+            heartbeat_data.client_id.as_str(),
+            heartbeat_data.heartbeat_fingerprint,
+            heartbeat_data.is_without_sub,
+        ) else {
+            response_command.add_ext_field(IS_SUB_CHANGE.to_string(), false.to_string());
+            return Some(response_command);
+        };
+
         //handle consumer data
+        let mut is_sub_change = false;
+        let mut subscription_data_versions = HashMap::new();
+        for consumer_data in heartbeat_data.consumer_data_set.iter() {
+            if self.broker_config.reject_pull_consumer_enable
+                && ConsumeType::ConsumeActively == consumer_data.consume_type
+            {
+                continue;
+            }
+            if !heartbeat_data.is_without_sub {
+                self.register_consumer_data(
+                    channel,
+                    consumer_data,
+                    &client_channel_info,
+                    heartbeat_data.heartbeat_fingerprint,
+                    &mut subscription_data_versions,
+                );
+                continue;
+            }
+            let group = consumer_data.group_name.as_str();
+            let fingerprint = self
+                .consumer_group_heartbeat_table
+                .read()
+                .get(group)
+                .copied();
+            let consumer_group_info = self.consumer_manager.get_consumer_group_info(group);
+            if fingerprint != Some(heartbeat_data.heartbeat_fingerprint)
+                || consumer_group_info.is_none()
+            {
+                is_sub_change = true;
+                continue;
+            }
+            let Some(subscription_group_config) = self
+                .subscription_group_manager
+                .find_subscription_group_config(group)
+            else {
+                continue;
+            };
+            self.consumer_manager.register_consumer_without_sub(
+                group,
+                client_channel_info.clone(),
+                consumer_data.consume_type,
+                consumer_data.message_model,
+                consumer_data.consume_from_where,
+                subscription_group_config.notify_consumer_ids_changed_enable(),
+            );
+            if let Some(consumer_group_info) = consumer_group_info {
+                subscription_data_versions.insert(
+                    consumer_data.group_name.clone(),
+                    consumer_group_info.get_subscription_data_version(),
+                );
+            }
+        }
 
         //handle producer data
         for producer_data in heartbeat_data.producer_data_set.iter() {
             self.producer_manager
                 .register_producer(&producer_data.group_name, &client_channel_info);
         }
-        let mut response_command = RemotingCommand::create_response_command();
-        response_command.add_ext_field(IS_SUPPORT_HEART_BEAT_V2.to_string(), true.to_string());
         response_command.add_ext_field(IS_SUB_CHANGE.to_string(), is_sub_change.to_string());
+        if !subscription_data_versions.is_empty() {
+            response_command.add_ext_field(
+                SUBSCRIPTION_DATA_VERSION.to_string(),
+                SerdeJsonUtils::to_json(&subscription_data_versions).unwrap_or_default(),
+            );
+        }
         Some(response_command)
     }
 }
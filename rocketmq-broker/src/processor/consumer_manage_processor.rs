@@ -19,6 +19,8 @@ use std::sync::Arc;
 
 use bytes::Bytes;
 use rocketmq_common::common::broker::broker_config::BrokerConfig;
+use rocketmq_common::common::offset::QueueOffset;
+use rocketmq_common::utils::util_all;
 use rocketmq_remoting::code::request_code::RequestCode;
 use rocketmq_remoting::code::response_code::ResponseCode;
 use rocketmq_remoting::net::channel::Channel;
@@ -134,6 +136,7 @@ where
                 if !client_ids.is_empty() {
                     let body = GetConsumerListByGroupResponseBody {
                         consumer_id_list: client_ids,
+                        offline_consumer_list: vec![],
                     };
                     return Some(
                         response
@@ -149,14 +152,35 @@ where
                 }
             }
         }
-        Some(
-            response
-                .set_remark(Some(format!(
-                    "no consumer for this group, {}",
-                    request_header.consumer_group
-                )))
-                .set_code(ResponseCode::SystemError),
-        )
+        let offline_consumer_list = self
+            .consumer_manager
+            .get_offline_consumers(request_header.consumer_group.as_str());
+        let remark = match offline_consumer_list.first() {
+            Some(last_offline) => format!(
+                "no consumer for this group, {}, last consumer {} seen at {} went offline at {} \
+                 ({:?})",
+                request_header.consumer_group,
+                last_offline.client_id,
+                util_all::time_millis_to_human_string2(last_offline.last_update_timestamp as i64),
+                util_all::time_millis_to_human_string2(last_offline.offline_timestamp as i64),
+                last_offline.offline_reason
+            ),
+            None => format!(
+                "no consumer for this group, {}",
+                request_header.consumer_group
+            ),
+        };
+        let response = response
+            .set_remark(Some(remark))
+            .set_code(ResponseCode::SystemError);
+        if offline_consumer_list.is_empty() {
+            return Some(response);
+        }
+        let body = GetConsumerListByGroupResponseBody {
+            consumer_id_list: vec![],
+            offline_consumer_list,
+        };
+        Some(response.set_body(Some(Bytes::from(body.encode()))))
     }
 
     async fn update_consumer_offset(
@@ -281,7 +305,7 @@ where
                                 .to_string(),
                         ));
                 }
-            } else if min_offset <= 0
+            } else if min_offset <= QueueOffset::default()
                 && self.message_store.check_in_mem_by_consume_offset(
                     request_header.topic.as_str(),
                     request_header.queue_id,
@@ -57,6 +57,7 @@ use crate::mqtrace::consume_message_context::ConsumeMessageContext;
 use crate::mqtrace::consume_message_hook::ConsumeMessageHook;
 use crate::offset::manager::broadcast_offset_manager::BroadcastOffsetManager;
 use crate::offset::manager::consumer_offset_manager::ConsumerOffsetManager;
+use crate::pagecache::many_message_transfer::ManyMessageTransfer;
 use crate::processor::pull_message_processor::is_broadcast;
 use crate::processor::pull_message_processor::rewrite_response_for_static_topic;
 use crate::processor::pull_message_result_handler::PullMessageResultHandler;
@@ -147,6 +148,12 @@ impl PullMessageResultHandler for DefaultPullMessageResultHandler {
             let response_header = response
                 .read_custom_header_mut::<PullMessageResponseHeader>()
                 .unwrap();
+            self.broker_stats_manager.inc_group_pull_source(
+                request_header.consumer_group.as_str(),
+                response_header
+                    .suggest_which_broker_id
+                    .is_some_and(|broker_id| broker_id != MASTER_ID),
+            );
             let rewrite_result = rewrite_response_for_static_topic(
                 &request_header,
                 response_header,
@@ -201,7 +208,11 @@ impl PullMessageResultHandler for DefaultPullMessageResultHandler {
                     );
                     Some(response.set_body(body))
                 } else {
-                    None
+                    Some(
+                        response.set_body_region(Some(Arc::new(ManyMessageTransfer::new(
+                            get_message_result,
+                        )))),
+                    )
                 }
             }
             ResponseCode::PullNotFound => {
@@ -309,9 +320,7 @@ impl DefaultPullMessageResultHandler {
         let mut bytes_mut =
             BytesMut::with_capacity(get_message_result.buffer_total_size() as usize);
         for msg in get_message_result.message_mapped_list() {
-            let data = &msg.mapped_file.as_ref().unwrap().get_mapped_file()
-                [msg.start_offset as usize..(msg.start_offset + msg.size as u64) as usize];
-            bytes_mut.extend_from_slice(data);
+            bytes_mut.extend_from_slice(msg.get_buffer());
         }
         Some(bytes_mut.freeze())
     }
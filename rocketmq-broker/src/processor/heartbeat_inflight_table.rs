@@ -0,0 +1,106 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::collections::HashMap;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+
+/// V2 heartbeats being processed, by client id. A heartbeat a client resends while its previous
+/// one with the same fingerprint is still being processed, e.g. after timing out on the client
+/// side, would only repeat the same registration, so it is answered without processing it.
+#[derive(Default)]
+pub(crate) struct HeartbeatInflightTable {
+    table: Mutex<HashMap<String /* clientId */, (i32 /* fingerprint */, bool /* withoutSub */)>>,
+    coalesced: AtomicU64,
+}
+
+impl HeartbeatInflightTable {
+    /// Marks the heartbeat of `client_id` in flight until the returned guard drops. Returns
+    /// `None` when a heartbeat of the client with the same fingerprint is in flight and covers
+    /// this one: a heartbeat with subscriptions covers both kinds, one without subscriptions
+    /// covers only another one without.
+    pub fn try_begin(
+        self: &Arc<Self>,
+        client_id: &str,
+        fingerprint: i32,
+        without_sub: bool,
+    ) -> Option<HeartbeatInflightGuard> {
+        let mut table = self.table.lock();
+        if let Some((inflight_fingerprint, inflight_without_sub)) = table.get(client_id) {
+            if *inflight_fingerprint == fingerprint && (!*inflight_without_sub || without_sub) {
+                self.coalesced.fetch_add(1, Ordering::Relaxed);
+                return None;
+            }
+        }
+        table.insert(client_id.to_string(), (fingerprint, without_sub));
+        Some(HeartbeatInflightGuard {
+            table: self.clone(),
+            client_id: client_id.to_string(),
+            fingerprint,
+        })
+    }
+
+    /// Heartbeats answered without processing them so far.
+    pub fn coalesced(&self) -> u64 {
+        self.coalesced.load(Ordering::Relaxed)
+    }
+}
+
+/// Keeps a heartbeat in its [`HeartbeatInflightTable`] while it is processed.
+pub(crate) struct HeartbeatInflightGuard {
+    table: Arc<HeartbeatInflightTable>,
+    client_id: String,
+    fingerprint: i32,
+}
+
+impl Drop for HeartbeatInflightGuard {
+    fn drop(&mut self) {
+        let mut table = self.table.table.lock();
+        // a heartbeat with another fingerprint may have replaced this one meanwhile
+        if table
+            .get(&self.client_id)
+            .is_some_and(|(fingerprint, _)| *fingerprint == self.fingerprint)
+        {
+            table.remove(&self.client_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn duplicate_heartbeats_in_flight_are_coalesced() {
+        let table = Arc::new(HeartbeatInflightTable::default());
+        let without_sub = table.try_begin("client-a", 7, true).unwrap();
+        assert!(table.try_begin("client-a", 7, true).is_none());
+        assert!(table.try_begin("client-b", 7, true).is_some());
+
+        // subscriptions are not covered by a heartbeat without them
+        let full = table.try_begin("client-a", 7, false).unwrap();
+        assert!(table.try_begin("client-a", 7, true).is_none());
+        assert!(table.try_begin("client-a", 7, false).is_none());
+        assert_eq!(table.coalesced(), 3);
+
+        drop(without_sub);
+        drop(full);
+        assert!(table.try_begin("client-a", 7, false).is_some());
+    }
+}
@@ -67,6 +67,7 @@ use crate::processor::pull_message_result_handler::PullMessageResultHandler;
 use crate::subscription::manager::subscription_group_manager::SubscriptionGroupManager;
 use crate::topic::manager::topic_config_manager::TopicConfigManager;
 use crate::topic::manager::topic_queue_mapping_manager::TopicQueueMappingManager;
+use crate::topic::system_topic_guard::SystemTopicGuard;
 
 #[derive(Clone)]
 pub struct PullMessageProcessor<MS> {
@@ -82,6 +83,7 @@ pub struct PullMessageProcessor<MS> {
     message_store: Arc<MS>,
     cold_data_cg_ctr_service: Arc<ColdDataCgCtrService>,
     broker_outer_api: Arc<BrokerOuterAPI>,
+    system_topic_guard: Arc<SystemTopicGuard>,
     // write message to consume client runtime
     write_message_runtime: Arc<RocketMQRuntime>,
     // write message to consume client lock
@@ -100,11 +102,13 @@ impl<MS> PullMessageProcessor<MS> {
         consumer_offset_manager: Arc<ConsumerOffsetManager>,
         broadcast_offset_manager: Arc<BroadcastOffsetManager>,
         message_store: Arc<MS>,
+        cold_data_cg_ctr_service: Arc<ColdDataCgCtrService>,
         broker_outer_api: Arc<BrokerOuterAPI>,
     ) -> Self {
         let cpus = num_cpus::get();
         Self {
             pull_message_result_handler,
+            system_topic_guard: Arc::new(SystemTopicGuard::new(&broker_config)),
             broker_config,
             subscription_group_manager,
             topic_config_manager,
@@ -114,7 +118,7 @@ impl<MS> PullMessageProcessor<MS> {
             consumer_offset_manager,
             broadcast_offset_manager,
             message_store,
-            cold_data_cg_ctr_service: Arc::new(Default::default()),
+            cold_data_cg_ctr_service,
             broker_outer_api,
             write_message_runtime: Arc::new(RocketMQRuntime::new_multi(
                 cpus,
@@ -456,7 +460,11 @@ where
                     ))),
             );
         }
-        if !PermName::is_readable(topic_config.as_ref().unwrap().perm) {
+        if !PermName::is_readable(topic_config.as_ref().unwrap().perm)
+            || !self
+                .system_topic_guard
+                .is_allowed(request_header.topic.as_str(), channel.remote_address())
+        {
             response_header.forbidden_type = Some(ForbiddenType::TOPIC_FORBIDDEN);
             return Some(
                 response
@@ -535,11 +543,11 @@ where
             );
             let consumer_filter_data =
                 if !ExpressionType::is_tag_type(Some(subscription_data.expression_type.as_str())) {
-                    let consumer_filter_data = ConsumerFilterManager::build(
+                    let consumer_filter_data = self.consumer_filter_manager.register(
                         request_header.topic.as_str(),
                         request_header.consumer_group.as_str(),
-                        request_header.subscription.as_deref(),
-                        request_header.expression_type.as_deref(),
+                        subscription_data.sub_string.as_str(),
+                        subscription_data.expression_type.as_str(),
                         request_header.sub_version as u64,
                     );
                     if consumer_filter_data.is_none() {
@@ -709,14 +717,19 @@ where
             ))
         };
 
-        //ColdDataFlow not implement
-
-        cfg_if::cfg_if! {
-            if #[cfg(feature = "local_file_store")] {
-                if self.cold_data_cg_ctr_service.is_cg_need_cold_data_flow_ctr(request_header.consumer_group.as_str()) {
-                    unimplemented!("ColdDataFlow not implement")
-                }
-            }
+        if self
+            .cold_data_cg_ctr_service
+            .is_cg_need_cold_data_flow_ctr(request_header.consumer_group.as_str())
+        {
+            return Some(
+                response
+                    .set_code(ResponseCode::SystemBusy)
+                    .set_remark(Some(format!(
+                        "[COLD_DATA_FLOW_CTR] consumer group {} reads cold data too fast, try \
+                         again later",
+                        request_header.consumer_group
+                    ))),
+            );
         }
 
         let use_reset_offset_feature = self.broker_config.use_server_side_reset_offset;
@@ -730,10 +743,16 @@ where
             let mut get_message_result = GetMessageResult::new();
             get_message_result.set_status(Some(GetMessageStatus::OffsetReset));
             get_message_result.set_next_begin_offset(reset_offset.unwrap());
-            get_message_result
-                .set_min_offset(self.message_store.get_min_offset_in_queue(topic, queue_id));
-            get_message_result
-                .set_max_offset(self.message_store.get_max_offset_in_queue(topic, queue_id));
+            get_message_result.set_min_offset(
+                self.message_store
+                    .get_min_offset_in_queue(topic, queue_id)
+                    .value(),
+            );
+            get_message_result.set_max_offset(
+                self.message_store
+                    .get_max_offset_in_queue(topic, queue_id)
+                    .value(),
+            );
             get_message_result.set_suggest_pulling_from_slave(false);
             Some(get_message_result)
         } else {
@@ -750,7 +769,7 @@ where
                 get_message_result.set_next_begin_offset(broadcast_init_offset);
                 Some(get_message_result)
             } else {
-                let result = self
+                let mut result = self
                     .message_store
                     .get_message(
                         group,
@@ -769,6 +788,17 @@ where
                             .set_remark(Some("store getMessage return None".to_string())),
                     );
                 }
+                if let Some(get_message_result) = result.as_mut() {
+                    self.cold_data_cg_ctr_service
+                        .cold_acc(group, get_message_result.cold_data_sum());
+                    if is_lagging_behind(
+                        self.broker_config.consumer_lagging_behind_threshold,
+                        get_message_result.max_offset(),
+                        request_header.queue_offset,
+                    ) {
+                        get_message_result.set_suggest_pulling_from_slave(true);
+                    }
+                }
                 result
             }
         };
@@ -871,6 +901,14 @@ where
         });
     }
 }
+
+/// Whether a group pulling `queue_offset` is more than `threshold` messages behind
+/// `max_offset`, so deep history reads are served by a slave and keep the page cache of the
+/// master for the groups consuming recent messages.
+pub(crate) fn is_lagging_behind(threshold: i64, max_offset: i64, queue_offset: i64) -> bool {
+    threshold > 0 && max_offset - queue_offset > threshold
+}
+
 pub(crate) fn is_broadcast(
     proxy_pull_broadcast: bool,
     consumer_group_info: Option<&ConsumerGroupInfo>,
@@ -930,6 +968,13 @@ mod tests {
         );
     }
 
+    #[test]
+    fn lagging_behind_only_past_threshold() {
+        assert!(!is_lagging_behind(0, 1_000_000, 0));
+        assert!(!is_lagging_behind(100, 1_000, 900));
+        assert!(is_lagging_behind(100, 1_000, 899));
+    }
+
     #[test]
     fn returns_false_when_no_consumer_group_info_provided() {
         let result = is_broadcast(false, None);
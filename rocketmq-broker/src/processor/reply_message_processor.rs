@@ -23,6 +23,7 @@ use rocketmq_common::common::broker::broker_config::BrokerConfig;
 use rocketmq_common::common::message::message_ext_broker_inner::MessageExtBrokerInner;
 use rocketmq_common::common::message::MessageConst;
 use rocketmq_common::common::message::MessageTrait;
+use rocketmq_common::utils::network_util::NetworkUtil;
 use rocketmq_common::ArcRefCellWrapper;
 use rocketmq_common::MessageAccessor::MessageAccessor;
 use rocketmq_common::MessageDecoder;
@@ -51,6 +52,7 @@ use crate::processor::send_message_processor::Inner;
 use crate::subscription::manager::subscription_group_manager::SubscriptionGroupManager;
 use crate::topic::manager::topic_config_manager::TopicConfigManager;
 use crate::topic::manager::topic_queue_mapping_manager::TopicQueueMappingManager;
+use crate::topic::system_topic_guard::SystemTopicGuard;
 
 #[derive(Clone)]
 pub struct ReplyMessageProcessor<MS = DefaultMessageStore> {
@@ -69,11 +71,11 @@ impl<MS: MessageStore> ReplyMessageProcessor<MS> {
         broker_stats_manager: Arc<BrokerStatsManager>,
         producer_manager: Option<Arc<ProducerManager>>,
     ) -> Self {
-        let store_host = format!("{}:{}", broker_config.broker_ip1, broker_config.listen_port)
-            .parse::<SocketAddr>()
-            .unwrap();
+        let store_host =
+            NetworkUtil::string_to_socket_address(&broker_config.get_broker_addr()).unwrap();
         Self {
             inner: Inner {
+                system_topic_guard: Arc::new(SystemTopicGuard::new(&broker_config)),
                 broker_config,
                 topic_config_manager,
                 send_message_hook_vec: ArcRefCellWrapper::new(Vec::new()),
@@ -22,9 +22,12 @@ use std::time::Instant;
 
 use rand::Rng;
 use rocketmq_common::common::attribute::cleanup_policy::CleanupPolicy;
+use rocketmq_common::common::attribute::topic_message_type::TopicMessageType;
 use rocketmq_common::common::broker::broker_config::BrokerConfig;
+use rocketmq_common::common::config::TopicConfig;
 use rocketmq_common::common::constant::PermName;
 use rocketmq_common::common::key_builder::KeyBuilder;
+use rocketmq_common::common::message::message_accessor::MessageAccessor;
 use rocketmq_common::common::message::message_batch::MessageExtBatch;
 use rocketmq_common::common::message::message_client_id_setter::MessageClientIDSetter;
 use rocketmq_common::common::message::message_enum::MessageType;
@@ -35,12 +38,14 @@ use rocketmq_common::common::message::MessageTrait;
 use rocketmq_common::common::mix_all;
 use rocketmq_common::common::mix_all::RETRY_GROUP_TOPIC_PREFIX;
 use rocketmq_common::common::mq_version::RocketMqVersion;
+use rocketmq_common::common::offset::DelayLevel;
 use rocketmq_common::common::sys_flag::message_sys_flag::MessageSysFlag;
 use rocketmq_common::common::topic::TopicValidator;
 use rocketmq_common::common::FAQUrl;
 use rocketmq_common::common::TopicFilterType;
 use rocketmq_common::common::TopicSysFlag::build_sys_flag;
 use rocketmq_common::utils::message_utils;
+use rocketmq_common::utils::network_util::NetworkUtil;
 use rocketmq_common::utils::queue_type_utils::QueueTypeUtils;
 use rocketmq_common::utils::util_all;
 use rocketmq_common::ArcRefCellWrapper;
@@ -49,11 +54,13 @@ use rocketmq_common::MessageDecoder;
 use rocketmq_common::MessageDecoder::message_properties_to_string;
 use rocketmq_common::MessageDecoder::string_to_message_properties;
 use rocketmq_common::TimeUtils;
+use rocketmq_common::TopicAttributes::TOPIC_MESSAGE_TYPE_ATTRIBUTE;
 use rocketmq_remoting::code::request_code::RequestCode;
 use rocketmq_remoting::code::response_code::RemotingSysResponseCode;
 use rocketmq_remoting::code::response_code::ResponseCode;
 use rocketmq_remoting::code::response_code::ResponseCode::SystemError;
 use rocketmq_remoting::net::channel::Channel;
+use rocketmq_remoting::protocol::header::consumer_send_msg_back_request_header::ConsumerSendMsgBackRequestHeader;
 use rocketmq_remoting::protocol::header::message_operation_header::send_message_request_header::parse_request_header;
 use rocketmq_remoting::protocol::header::message_operation_header::send_message_request_header::SendMessageRequestHeader;
 use rocketmq_remoting::protocol::header::message_operation_header::send_message_response_header::SendMessageResponseHeader;
@@ -61,8 +68,10 @@ use rocketmq_remoting::protocol::header::message_operation_header::TopicRequestH
 use rocketmq_remoting::protocol::namespace_util::NamespaceUtil;
 use rocketmq_remoting::protocol::remoting_command::RemotingCommand;
 use rocketmq_remoting::protocol::static_topic::topic_queue_mapping_context::TopicQueueMappingContext;
+use rocketmq_remoting::protocol::subscription::group_retry_policy::GroupRetryPolicy;
 use rocketmq_remoting::runtime::connection_handler_context::ConnectionHandlerContext;
 use rocketmq_store::base::message_result::PutMessageResult;
+use rocketmq_store::base::message_status_enum::PutMessageStatus;
 use rocketmq_store::log_file::MessageStore;
 use rocketmq_store::stats::broker_stats_manager::BrokerStatsManager;
 use rocketmq_store::stats::stats_type::StatsType;
@@ -77,6 +86,7 @@ use crate::mqtrace::send_message_hook::SendMessageHook;
 use crate::subscription::manager::subscription_group_manager::SubscriptionGroupManager;
 use crate::topic::manager::topic_config_manager::TopicConfigManager;
 use crate::topic::manager::topic_queue_mapping_manager::TopicQueueMappingManager;
+use crate::topic::system_topic_guard::SystemTopicGuard;
 
 pub struct SendMessageProcessor<MS>
 where
@@ -98,7 +108,11 @@ impl<MS: Clone> Clone for SendMessageProcessor<MS> {
 // RequestProcessor implementation
 impl<MS: MessageStore + Send> SendMessageProcessor<MS> {
     pub fn has_send_message_hook(&self) -> bool {
-        self.inner.send_message_hook_vec.is_empty()
+        self.inner.has_send_message_hook()
+    }
+
+    pub fn register_send_message_hook(&mut self, hook: Box<dyn SendMessageHook>) {
+        self.inner.send_message_hook_vec.push(hook);
     }
 
     fn clear_reserved_properties(request_header: &mut SendMessageRequestHeader) {
@@ -117,9 +131,18 @@ impl<MS: MessageStore + Send> SendMessageProcessor<MS> {
         request_code: RequestCode,
         request: RemotingCommand,
     ) -> Option<RemotingCommand> {
+        if self.reject_request() {
+            return Some(
+                RemotingCommand::create_response_command_with_code(ResponseCode::SystemBusy)
+                    .set_remark(Some(
+                        "[REJECTREQUEST]system busy, start flow control for a while".to_string(),
+                    ))
+                    .set_opaque(request.opaque()),
+            );
+        }
         match request_code {
             RequestCode::ConsumerSendMsgBack => {
-                self.inner.consumer_send_msg_back(&channel, &ctx, &request)
+                self.consumer_send_msg_back(&channel, &ctx, &request).await
             }
             _ => {
                 let mut request_header = parse_request_header(&request, request_code)?;
@@ -177,6 +200,13 @@ impl<MS: MessageStore + Send> SendMessageProcessor<MS> {
 }
 
 impl<MS: MessageStore> SendMessageProcessor<MS> {
+    /// Self-protection of the store: requests are turned away while the page cache is locked for
+    /// too long or the transient store pool has no buffer left.
+    fn reject_request(&self) -> bool {
+        self.inner.message_store.is_os_page_cache_busy()
+            || self.inner.message_store.is_transient_store_pool_deficient()
+    }
+
     pub fn new(
         topic_queue_mapping_manager: Arc<TopicQueueMappingManager>,
         subscription_group_manager: Arc<SubscriptionGroupManager<MS>>,
@@ -186,12 +216,10 @@ impl<MS: MessageStore> SendMessageProcessor<MS> {
         rebalance_lock_manager: Arc<RebalanceLockManager>,
         broker_stats_manager: Arc<BrokerStatsManager>,
     ) -> Self {
-        let store_host = format!("{}:{}", broker_config.broker_ip1, broker_config.listen_port)
-            .parse::<SocketAddr>()
-            .unwrap();
+        let store_host =
+            NetworkUtil::string_to_socket_address(&broker_config.get_broker_addr()).unwrap();
         Self {
             inner: Inner {
-                broker_config,
                 topic_config_manager,
                 send_message_hook_vec: ArcRefCellWrapper::new(Vec::new()),
                 topic_queue_mapping_manager,
@@ -201,6 +229,8 @@ impl<MS: MessageStore> SendMessageProcessor<MS> {
                 broker_stats_manager,
                 producer_manager: None,
                 broker_to_client: Default::default(),
+                system_topic_guard: Arc::new(SystemTopicGuard::new(&broker_config)),
+                broker_config,
             },
             store_host,
         }
@@ -256,6 +286,16 @@ impl<MS: MessageStore> SendMessageProcessor<MS> {
                     ))),
             );
         }
+        if let Err(remark) = check_topic_message_type(
+            &topic_config,
+            &string_to_message_properties(request_header.properties.as_ref()),
+        ) {
+            return Some(
+                response
+                    .set_code(ResponseCode::MessageIllegal)
+                    .set_remark(Some(remark)),
+            );
+        }
         let mut message_ext = MessageExtBrokerInner::default();
         message_ext.message_ext_inner.message.topic = request_header.topic().to_string();
         message_ext.message_ext_inner.queue_id = queue_id.unwrap();
@@ -263,6 +303,24 @@ impl<MS: MessageStore> SendMessageProcessor<MS> {
         if TopicFilterType::MultiTag == topic_config.topic_filter_type {
             sys_flag |= MessageSysFlag::MULTI_TAGS_FLAG;
         }
+        // the producer compressed the encoded batch as a whole
+        let decompressed_body =
+            if MessageSysFlag::check(sys_flag, MessageSysFlag::COMPRESSED_FLAG) {
+                match request.body().as_ref().map(|body| {
+                    MessageSysFlag::get_compression_type(sys_flag).try_decompression(body)
+                }) {
+                    Some(Ok(body)) => Some(body),
+                    _ => {
+                        return Some(
+                            response
+                                .set_code(ResponseCode::MessageIllegal)
+                                .set_remark(Some("decompress the batch body failed".to_string())),
+                        );
+                    }
+                }
+            } else {
+                None
+            };
         message_ext.message_ext_inner.sys_flag = sys_flag;
         message_ext.message_ext_inner.message.flag = request_header.flag;
         message_ext
@@ -316,14 +374,16 @@ impl<MS: MessageStore> SendMessageProcessor<MS> {
                 .sys_flag =
                 sys_flag | MessageSysFlag::NEED_UNWRAP_FLAG | MessageSysFlag::INNER_BATCH_FLAG;
             batch_message.is_inner_batch = true;
-            let inner_num = MessageDecoder::count_inner_msg_num(
-                batch_message
-                    .message_ext_broker_inner
-                    .message_ext_inner
-                    .message
-                    .body
-                    .clone(),
-            );
+            // kept compressed, consumers decompress the batch before splitting it
+            let inner_num =
+                MessageDecoder::count_inner_msg_num(decompressed_body.clone().or_else(|| {
+                    batch_message
+                        .message_ext_broker_inner
+                        .message_ext_inner
+                        .message
+                        .body
+                        .clone()
+                }));
             batch_message
                 .message_ext_broker_inner
                 .message_ext_inner
@@ -342,6 +402,16 @@ impl<MS: MessageStore> SendMessageProcessor<MS> {
             response_header.set_batch_uniq_id(batch_uniq_id);
             is_inner_batch = true;
         }
+        if !is_inner_batch {
+            if let Some(body) = decompressed_body {
+                // the commit log splits the batch, its messages are stored uncompressed
+                let message_ext_inner =
+                    &mut batch_message.message_ext_broker_inner.message_ext_inner;
+                message_ext_inner.message.body = Some(body);
+                message_ext_inner.sys_flag =
+                    MessageSysFlag::clear_compression_flags(message_ext_inner.sys_flag);
+            }
+        }
         let start = Instant::now();
         let transaction_id = MessageClientIDSetter::get_uniq_id(
             &batch_message
@@ -477,21 +547,30 @@ impl<MS: MessageStore> SendMessageProcessor<MS> {
         message_ext.message_ext_inner.message.properties = ori_props;
         let cleanup_policy = CleanupPolicyUtils::get_delete_policy(Some(&topic_config));
 
-        if cleanup_policy == CleanupPolicy::COMPACTION {
-            if let Some(value) = message_ext
+        // compacted topics keep the last message of each key, a message without one is lost
+        if cleanup_policy == CleanupPolicy::COMPACTION
+            && message_ext
                 .message_ext_inner
                 .message
                 .properties
                 .get(MessageConst::PROPERTY_KEYS)
-            {
-                if value.trim().is_empty() {
-                    return Some(
-                        response
-                            .set_code(ResponseCode::MessageIllegal)
-                            .set_remark(Some("Required message key is missing".to_string())),
-                    );
-                }
-            }
+                .is_none_or(|keys| keys.trim().is_empty())
+        {
+            return Some(
+                response
+                    .set_code(ResponseCode::MessageIllegal)
+                    .set_remark(Some("Required message key is missing".to_string())),
+            );
+        }
+        if let Err(remark) = check_topic_message_type(
+            &topic_config,
+            &message_ext.message_ext_inner.message.properties,
+        ) {
+            return Some(
+                response
+                    .set_code(ResponseCode::MessageIllegal)
+                    .set_remark(Some(remark)),
+            );
         }
         message_ext.tags_code = MessageExtBrokerInner::tags_string2tags_code(
             &topic_config.topic_filter_type,
@@ -769,6 +848,8 @@ impl<MS: MessageStore> SendMessageProcessor<MS> {
                 send_message_context.account_owner_self = owner_self.unwrap_or_default();
                 send_message_context.send_msg_size = wrote_size;
                 send_message_context.send_msg_num = msg_num;
+                self.inner
+                    .execute_send_message_hook_after(None, send_message_context);
             }
             None
         } else {
@@ -793,6 +874,8 @@ impl<MS: MessageStore> SendMessageProcessor<MS> {
                 send_message_context.account_owner_self = owner_self.unwrap_or_default();
                 send_message_context.send_msg_size = wrote_size;
                 send_message_context.send_msg_num = msg_num;
+                self.inner
+                    .execute_send_message_hook_after(Some(&mut response), send_message_context);
             }
             Some(response.clone())
         }
@@ -833,6 +916,20 @@ impl<MS: MessageStore> SendMessageProcessor<MS> {
         response = response.set_code(-1);
         self.inner
             .msg_check(channel, ctx, request, request_header, &mut response);
+        if response.code() == -1 {
+            if let Some(properties) = request_header.properties.as_ref() {
+                if let Err(e) = self
+                    .inner
+                    .broker_config
+                    .message_properties_limits()
+                    .check_encoded(properties)
+                {
+                    response = response
+                        .set_code(ResponseCode::MessageIllegal)
+                        .set_remark(Some(e.to_string()));
+                }
+            }
+        }
         response
     }
 
@@ -926,6 +1023,285 @@ impl<MS: MessageStore> SendMessageProcessor<MS> {
         msg.sys_flag = sys_flag;
         true
     }
+
+    /// Handles `CONSUMER_SEND_MSG_BACK`: the consumer failed to consume the message stored at
+    /// `offset`, so it is written into the group's retry topic with an escalated delay level, or
+    /// into the group's DLQ once the retry budget is used up.
+    async fn consumer_send_msg_back(
+        &mut self,
+        _channel: &Channel,
+        _ctx: &ConnectionHandlerContext,
+        request: &RemotingCommand,
+    ) -> Option<RemotingCommand> {
+        let request_header =
+            request.decode_command_custom_header::<ConsumerSendMsgBackRequestHeader>()?;
+        let response = RemotingCommand::create_response_command();
+        let Some(subscription_group_config) = self
+            .inner
+            .subscription_group_manager
+            .find_subscription_group_config(request_header.group.as_str())
+        else {
+            return Some(
+                response
+                    .set_code(ResponseCode::SubscriptionGroupNotExist)
+                    .set_remark(Some(format!(
+                        "subscription group not exist, {} {}",
+                        request_header.group,
+                        FAQUrl::suggest_todo(FAQUrl::SUBSCRIPTION_GROUP_NOT_EXIST)
+                    ))),
+            );
+        };
+        if !PermName::is_writeable(self.inner.broker_config.broker_permission()) {
+            return Some(
+                response
+                    .set_code(ResponseCode::NoPermission)
+                    .set_remark(Some(format!(
+                        "the broker[{}] sending message is forbidden",
+                        self.inner.broker_config.broker_ip1
+                    ))),
+            );
+        }
+        if subscription_group_config.retry_queue_nums() <= 0 {
+            return Some(response.set_code(ResponseCode::Success));
+        }
+
+        let mut new_topic = mix_all::get_retry_topic(request_header.group.as_str());
+        let mut queue_id_int = self
+            .inner
+            .random_queue_id(subscription_group_config.retry_queue_nums() as u32)
+            as i32;
+        let topic_sys_flag = if request_header.unit_mode {
+            build_sys_flag(false, true)
+        } else {
+            0
+        };
+        let Some(mut topic_config) = self
+            .inner
+            .topic_config_manager
+            .create_topic_in_send_message_back_method(
+                new_topic.as_str(),
+                subscription_group_config.retry_queue_nums(),
+                PermName::PERM_WRITE | PermName::PERM_READ,
+                false,
+                topic_sys_flag,
+            )
+        else {
+            return Some(
+                response
+                    .set_code(ResponseCode::SystemError)
+                    .set_remark(Some(format!("topic[{}] not exist", new_topic))),
+            );
+        };
+        if !PermName::is_writeable(topic_config.perm) {
+            return Some(
+                response
+                    .set_code(ResponseCode::NoPermission)
+                    .set_remark(Some(format!(
+                        "the topic[{}] sending message is forbidden",
+                        new_topic
+                    ))),
+            );
+        }
+
+        let Some(mut msg_ext) = self
+            .inner
+            .message_store
+            .look_message_by_offset(request_header.offset)
+        else {
+            return Some(
+                response
+                    .set_code(ResponseCode::SystemError)
+                    .set_remark(Some(format!(
+                        "look message by offset failed, {}",
+                        request_header.offset
+                    ))),
+            );
+        };
+        if msg_ext
+            .get_property(MessageConst::PROPERTY_RETRY_TOPIC)
+            .is_none()
+        {
+            let topic = msg_ext.message.topic.clone();
+            msg_ext.put_property(MessageConst::PROPERTY_RETRY_TOPIC, topic.as_str());
+        }
+        msg_ext.message.set_wait_store_msg_ok(false);
+
+        let mut max_reconsume_times = subscription_group_config.retry_max_times();
+        if request.version() >= From::from(RocketMqVersion::V349) {
+            if let Some(times) = request_header.max_reconsume_times {
+                max_reconsume_times = times;
+            }
+        }
+        match escalate_delay_level(
+            DelayLevel::new(request_header.delay_level),
+            msg_ext.reconsume_times,
+            max_reconsume_times,
+        ) {
+            Some(delay_level) => match retry_policy_delay_millis(
+                subscription_group_config.group_retry_policy(),
+                DelayLevel::new(request_header.delay_level),
+                msg_ext.reconsume_times,
+            )
+            .filter(|_| {
+                self.inner
+                    .broker_config
+                    .timer_wheel_config
+                    .timer_wheel_enable
+            }) {
+                // The timer wheel delivers the retry after the delay the policy of the group
+                // gives, instead of the fixed delay of the level.
+                Some(delay_millis) => {
+                    for key in [
+                        MessageConst::PROPERTY_TIMER_OUT_MS,
+                        MessageConst::PROPERTY_TIMER_DELIVER_MS,
+                        MessageConst::PROPERTY_TIMER_DELAY_SEC,
+                    ] {
+                        msg_ext.message.clear_property(key);
+                    }
+                    msg_ext.put_property(
+                        MessageConst::PROPERTY_TIMER_DELAY_MS,
+                        delay_millis.to_string().as_str(),
+                    );
+                    msg_ext.message.set_delay_time_level(0);
+                }
+                None => msg_ext.message.set_delay_time_level(delay_level.into()),
+            },
+            None => {
+                new_topic = mix_all::get_dlq_topic(request_header.group.as_str());
+                queue_id_int = self.inner.random_queue_id(DLQ_NUMS_PER_GROUP) as i32;
+                // the tags code of the message follows the filter type of the DLQ topic
+                let Some(dlq_topic_config) = self
+                    .inner
+                    .topic_config_manager
+                    .create_topic_in_send_message_back_method(
+                        new_topic.as_str(),
+                        DLQ_NUMS_PER_GROUP as i32,
+                        PermName::PERM_WRITE | PermName::PERM_READ,
+                        false,
+                        0,
+                    )
+                else {
+                    return Some(
+                        response
+                            .set_code(ResponseCode::SystemError)
+                            .set_remark(Some(format!("topic[{}] not exist", new_topic))),
+                    );
+                };
+                topic_config = dlq_topic_config;
+                msg_ext.message.set_delay_time_level(0);
+            }
+        }
+
+        let mut msg_inner = MessageExtBrokerInner::default();
+        msg_inner.message_ext_inner.message.topic = new_topic.clone();
+        msg_inner
+            .message_ext_inner
+            .message
+            .body
+            .clone_from(&msg_ext.message.body);
+        msg_inner.message_ext_inner.message.flag = msg_ext.message.flag;
+        msg_inner
+            .message_ext_inner
+            .message
+            .set_properties(msg_ext.message.properties().clone());
+        msg_inner.tags_code = MessageExtBrokerInner::tags_string2tags_code(
+            &topic_config.topic_filter_type,
+            msg_ext.get_tags().unwrap_or_default().as_str(),
+        );
+        msg_inner.message_ext_inner.queue_id = queue_id_int;
+        msg_inner.message_ext_inner.sys_flag = msg_ext.sys_flag;
+        msg_inner.message_ext_inner.born_timestamp = msg_ext.born_timestamp;
+        msg_inner.message_ext_inner.born_host = msg_ext.born_host;
+        msg_inner.message_ext_inner.store_host = self.store_host;
+        msg_inner.message_ext_inner.reconsume_times = msg_ext.reconsume_times + 1;
+        let origin_msg_id = MessageAccessor::get_origin_message_id(&msg_ext)
+            .filter(|origin_msg_id| !origin_msg_id.is_empty())
+            .unwrap_or_else(|| msg_ext.msg_id.clone());
+        MessageAccessor::set_origin_message_id(&mut msg_inner, origin_msg_id.as_str());
+        msg_inner.properties_string =
+            message_properties_to_string(msg_inner.message_ext_inner.message.properties());
+
+        let put_message_result = self.inner.message_store.put_message(msg_inner).await;
+        match put_message_result.put_message_status() {
+            PutMessageStatus::PutOk => {
+                let back_topic = msg_ext
+                    .get_property(MessageConst::PROPERTY_RETRY_TOPIC)
+                    .unwrap_or_else(|| msg_ext.message.topic.clone());
+                self.inner
+                    .broker_stats_manager
+                    .inc_send_back_nums(request_header.group.as_str(), back_topic.as_str());
+                Some(response.set_code(ResponseCode::Success))
+            }
+            status => Some(
+                response
+                    .set_code(ResponseCode::SystemError)
+                    .set_remark(Some(status.to_string())),
+            ),
+        }
+    }
+}
+
+/// Returns the delay level a sent back message is retried with, or `None` when it has to go to
+/// the DLQ. A requested level of `0` escalates with the reconsume times, starting at level 3.
+fn escalate_delay_level(
+    delay_level: DelayLevel,
+    reconsume_times: i32,
+    max_reconsume_times: i32,
+) -> Option<DelayLevel> {
+    if reconsume_times >= max_reconsume_times || delay_level.value() < 0 {
+        None
+    } else if delay_level.is_delayed() {
+        Some(delay_level)
+    } else {
+        DelayLevel::new(3).checked_add(reconsume_times)
+    }
+}
+
+/// Returns the delay in milliseconds a sent back message is retried after when its group set a
+/// retry policy. Consumers asking for a delay level themselves keep it.
+fn retry_policy_delay_millis(
+    group_retry_policy: &GroupRetryPolicy,
+    delay_level: DelayLevel,
+    reconsume_times: i32,
+) -> Option<i64> {
+    if delay_level.is_delayed() {
+        return None;
+    }
+    group_retry_policy
+        .configured_retry_policy()
+        .map(|policy| policy.next_delay_duration(reconsume_times))
+}
+
+/// Checks a message with `properties` against the `message.type` its topic declares, e.g. a
+/// FIFO topic only takes messages with a sharding key. Topics declaring no type, UNSPECIFIED or
+/// MIXED take any message.
+fn check_topic_message_type(
+    topic_config: &TopicConfig,
+    properties: &HashMap<String, String>,
+) -> Result<(), String> {
+    let Some(declared) = topic_config
+        .attributes
+        .get(TOPIC_MESSAGE_TYPE_ATTRIBUTE.get_name())
+    else {
+        return Ok(());
+    };
+    let topic_message_type = TopicMessageType::from(declared.clone());
+    if matches!(
+        topic_message_type,
+        TopicMessageType::Unspecified | TopicMessageType::Mixed
+    ) {
+        return Ok(());
+    }
+    let message_type = TopicMessageType::parse_from_message_property(properties);
+    if message_type != topic_message_type {
+        return Err(format!(
+            "The message type {} does not match the message type {} of topic {}",
+            message_type,
+            topic_message_type,
+            topic_config.topic_name.as_deref().unwrap_or_default()
+        ));
+    }
+    Ok(())
 }
 
 const DLQ_NUMS_PER_GROUP: u32 = 1;
@@ -942,11 +1318,12 @@ pub(crate) struct Inner<MS> {
     pub(crate) broker_stats_manager: Arc<BrokerStatsManager>,
     pub(crate) producer_manager: Option<Arc<ProducerManager>>,
     pub(crate) broker_to_client: Broker2Client,
+    pub(crate) system_topic_guard: Arc<SystemTopicGuard>,
 }
 
 impl<MS> Inner<MS> {
     pub fn has_send_message_hook(&self) -> bool {
-        self.send_message_hook_vec.is_empty()
+        !self.send_message_hook_vec.is_empty()
     }
 
     pub(crate) fn execute_send_message_hook_before(&self, context: &SendMessageContext) {
@@ -977,15 +1354,6 @@ impl<MS> Inner<MS> {
         }
     }
 
-    pub(crate) fn consumer_send_msg_back(
-        &self,
-        _channel: &Channel,
-        _ctx: &ConnectionHandlerContext,
-        _request: &RemotingCommand,
-    ) -> Option<RemotingCommand> {
-        todo!()
-    }
-
     pub(crate) fn build_msg_context(
         &self,
         channel: &Channel,
@@ -1078,7 +1446,13 @@ impl<MS> Inner<MS> {
             return;
         }
 
-        if TopicValidator::is_not_allowed_send_topic(request_header.topic.as_str()) {
+        let remote_address = channel.remote_address();
+        if !self
+            .system_topic_guard
+            .is_allowed(request_header.topic.as_str(), remote_address)
+            || (TopicValidator::is_not_allowed_send_topic(request_header.topic.as_str())
+                && !self.system_topic_guard.is_admin(remote_address))
+        {
             response.with_code(ResponseCode::NoPermission);
             response.with_remark(Some(format!(
                 "Sending message to topic[{}] is forbidden.",
@@ -1183,3 +1557,81 @@ fn rewrite_response_for_static_topic(
     response_header.set_queue_offset(static_logic_offset);
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use rocketmq_remoting::protocol::subscription::exponential_retry_policy::ExponentialRetryPolicy;
+    use rocketmq_remoting::protocol::subscription::group_retry_policy_type::GroupRetryPolicyType;
+
+    use super::*;
+
+    #[test]
+    fn escalate_delay_level_starts_at_level_three() {
+        assert_eq!(
+            escalate_delay_level(DelayLevel::new(0), 0, 16),
+            Some(DelayLevel::new(3))
+        );
+        assert_eq!(
+            escalate_delay_level(DelayLevel::new(0), 5, 16),
+            Some(DelayLevel::new(8))
+        );
+        assert_eq!(
+            escalate_delay_level(DelayLevel::new(2), 5, 16),
+            Some(DelayLevel::new(2))
+        );
+    }
+
+    #[test]
+    fn check_topic_message_type_enforces_declared_types_only() {
+        let delayed = HashMap::from([(
+            MessageConst::PROPERTY_DELAY_TIME_LEVEL.to_string(),
+            "3".to_string(),
+        )]);
+        let normal = HashMap::new();
+        let mut topic_config = TopicConfig::new("TopicTest");
+        assert!(check_topic_message_type(&topic_config, &delayed).is_ok());
+
+        topic_config.attributes.insert(
+            TOPIC_MESSAGE_TYPE_ATTRIBUTE.get_name().to_string(),
+            "DELAY".to_string(),
+        );
+        assert!(check_topic_message_type(&topic_config, &delayed).is_ok());
+        assert!(check_topic_message_type(&topic_config, &normal).is_err());
+
+        topic_config.attributes.insert(
+            TOPIC_MESSAGE_TYPE_ATTRIBUTE.get_name().to_string(),
+            "MIXED".to_string(),
+        );
+        assert!(check_topic_message_type(&topic_config, &normal).is_ok());
+    }
+
+    #[test]
+    fn retry_policy_delay_millis_applies_configured_policies() {
+        let mut group_retry_policy = GroupRetryPolicy::default();
+        assert_eq!(
+            retry_policy_delay_millis(&group_retry_policy, DelayLevel::new(0), 0),
+            None
+        );
+        group_retry_policy.set_type_(GroupRetryPolicyType::Exponential);
+        group_retry_policy
+            .set_exponential_retry_policy(Some(ExponentialRetryPolicy::new(1_000, 60_000, 3)));
+        assert_eq!(
+            retry_policy_delay_millis(&group_retry_policy, DelayLevel::new(0), 2),
+            Some(9_000)
+        );
+        assert_eq!(
+            retry_policy_delay_millis(&group_retry_policy, DelayLevel::new(0), 10),
+            Some(60_000)
+        );
+        assert_eq!(
+            retry_policy_delay_millis(&group_retry_policy, DelayLevel::new(4), 2),
+            None
+        );
+    }
+
+    #[test]
+    fn escalate_delay_level_sends_to_dlq() {
+        assert_eq!(escalate_delay_level(DelayLevel::new(0), 16, 16), None);
+        assert_eq!(escalate_delay_level(DelayLevel::new(-1), 0, 16), None);
+    }
+}
@@ -28,6 +28,13 @@ pub struct DelayOffsetSerializeWrapper {
 }
 
 impl DelayOffsetSerializeWrapper {
+    pub fn new(offset_table: HashMap<i32, i64>, data_version: DataVersion) -> Self {
+        Self {
+            offset_table,
+            data_version,
+        }
+    }
+
     pub fn offset_table(&self) -> &HashMap<i32, i64> {
         &self.offset_table
     }
@@ -16,38 +16,351 @@
  */
 
 use std::collections::HashMap;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
+use std::time::Duration;
 
 use rocketmq_common::common::broker::broker_config::BrokerConfig;
 use rocketmq_common::common::config_manager::ConfigManager;
+use rocketmq_common::common::message::message_ext::MessageExt;
+use rocketmq_common::common::message::message_ext_broker_inner::MessageExtBrokerInner;
+use rocketmq_common::common::message::MessageConst;
+use rocketmq_common::common::message::MessageTrait;
+use rocketmq_common::common::mix_all;
+use rocketmq_common::common::topic::TopicValidator;
+use rocketmq_common::common::TopicFilterType;
+use rocketmq_common::MessageDecoder;
+use rocketmq_common::TimeUtils::get_current_millis;
+use rocketmq_remoting::protocol::DataVersion;
+use rocketmq_store::base::message_status_enum::GetMessageStatus;
+use rocketmq_store::base::message_status_enum::PutMessageStatus;
+use rocketmq_store::config::message_store_config::MessageStoreConfig;
+use rocketmq_store::log_file::MessageStore;
 use rocketmq_store::store_path_config_helper::get_delay_offset_store_path;
+use tokio::task::JoinHandle;
+use tracing::error;
+use tracing::info;
+use tracing::warn;
 
+use crate::schedule::delay_offset_serialize_wrapper::DelayOffsetSerializeWrapper;
+
+/// Delay levels used when `messageDelayLevel` is not configured.
+pub const DEFAULT_MESSAGE_DELAY_LEVEL: &str =
+    "1s 5s 10s 30s 1m 2m 3m 4m 5m 6m 7m 8m 9m 10m 20m 30m 1h 2h";
+
+/// Wait of a delivery task when its level has nothing to deliver.
+const DELAY_FOR_A_WHILE: Duration = Duration::from_millis(100);
+
+/// Wait of a delivery task after failing to read or put a message.
+const DELAY_FOR_A_PERIOD: Duration = Duration::from_millis(10_000);
+
+/// Interval of persisting the delay offsets when `flush_delay_offset_interval` is not set.
+const DEFAULT_FLUSH_DELAY_OFFSET_INTERVAL: u64 = 10_000;
+
+/// Messages read from a level in one round.
+const DELIVER_BATCH_SIZE: i32 = 32;
+
+/// Delay messages scheduled by each delay mode.
+#[derive(Default)]
+pub(crate) struct DelayModeStats {
+    level: AtomicU64,
+    timer_wheel: AtomicU64,
+    timer_wheel_fallback: AtomicU64,
+}
+
+impl DelayModeStats {
+    pub fn record_level(&self) {
+        self.level.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_timer_wheel(&self) {
+        self.timer_wheel.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// A message of a timer wheel topic scheduled by level as the timer wheel is disabled.
+    pub fn record_timer_wheel_fallback(&self) {
+        self.timer_wheel_fallback.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Moves the messages of `SCHEDULE_TOPIC_XXXX` back to their real topic once the delay of their
+/// level elapsed. Queue `level - 1` of the schedule topic holds the messages of a level, one task
+/// per level delivers them in order and the offset reached in each queue is persisted.
 #[derive(Default, Clone)]
 pub struct ScheduleMessageService {
     pub(crate) broker_config: Arc<BrokerConfig>,
+    pub(crate) delay_mode_stats: Arc<DelayModeStats>,
+    /// Delay in milliseconds of each level, level 1 first.
+    delay_level_table: Arc<Vec<u64>>,
+    /// Next offset to deliver in the schedule topic queue of each level.
+    offset_table: Arc<parking_lot::Mutex<HashMap<i32, i64>>>,
+    data_version: Arc<parking_lot::Mutex<DataVersion>>,
+    flush_delay_offset_interval: u64,
+    started: Arc<AtomicBool>,
+    tasks: Arc<parking_lot::Mutex<Vec<JoinHandle<()>>>>,
 }
 
 impl ScheduleMessageService {
+    pub fn new(
+        broker_config: Arc<BrokerConfig>,
+        message_store_config: &MessageStoreConfig,
+    ) -> Self {
+        Self {
+            broker_config,
+            delay_mode_stats: Arc::default(),
+            delay_level_table: Arc::new(Self::parse_delay_level(
+                &message_store_config.message_delay_level,
+            )),
+            flush_delay_offset_interval: match message_store_config.flush_delay_offset_interval {
+                0 => DEFAULT_FLUSH_DELAY_OFFSET_INTERVAL,
+                interval => interval as u64,
+            },
+            ..Self::default()
+        }
+    }
+
+    /// Starts a delivery task for each level and the periodic persisting of the delay offsets.
+    pub fn start<MS: MessageStore + Send + Sync + 'static>(&self, message_store: MS) {
+        if self.started.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        info!(
+            "Start schedule message service, {} delay levels",
+            self.get_max_delay_level()
+        );
+        let mut tasks = self.tasks.lock();
+        for level in 1..=self.get_max_delay_level() {
+            let service = self.clone();
+            let mut message_store = message_store.clone();
+            tasks.push(tokio::spawn(async move {
+                loop {
+                    let wait = service.deliver_level(&mut message_store, level).await;
+                    tokio::time::sleep(wait).await;
+                }
+            }));
+        }
+        let service = self.clone();
+        let interval = Duration::from_millis(self.flush_delay_offset_interval);
+        tasks.push(tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                service.persist();
+            }
+        }));
+    }
+
+    /// Delivers the messages of `level` whose delay elapsed, returns how long to wait before the
+    /// next round.
+    async fn deliver_level<MS: MessageStore>(
+        &self,
+        message_store: &mut MS,
+        level: i32,
+    ) -> Duration {
+        let queue_id = Self::delay_level2queue_id(level);
+        let offset = self.offset_table.lock().get(&level).copied().unwrap_or(0);
+        let Some(result) = message_store
+            .get_message(
+                mix_all::SCHEDULE_CONSUMER_GROUP,
+                TopicValidator::RMQ_SYS_SCHEDULE_TOPIC,
+                queue_id,
+                offset,
+                DELIVER_BATCH_SIZE,
+                i32::MAX,
+                None,
+            )
+            .await
+        else {
+            return DELAY_FOR_A_PERIOD;
+        };
+        if result.status() != Some(GetMessageStatus::Found) {
+            // e.g. the offset was behind the first message kept or beyond the last one
+            let next_offset = result.next_begin_offset();
+            if next_offset != offset {
+                warn!(
+                    "schedule queue of level {} moves from offset {} to {}, status {:?}",
+                    level,
+                    offset,
+                    next_offset,
+                    result.status()
+                );
+                self.update_offset(level, next_offset);
+            }
+            return DELAY_FOR_A_WHILE;
+        }
+
+        let delay = self.delay_level_table[(level - 1) as usize];
+        let mut next_offset = offset;
+        for buffer in result.message_mapped_list() {
+            let Some(msg_ext) = buffer.get_bytes().and_then(|mut bytes| {
+                MessageDecoder::decode(&mut bytes, true, false, false, false, false)
+            }) else {
+                next_offset += 1;
+                continue;
+            };
+            let deliver_timestamp = msg_ext.store_timestamp as u64 + delay;
+            let now = get_current_millis();
+            if deliver_timestamp > now {
+                self.update_offset(level, next_offset);
+                return Duration::from_millis(deliver_timestamp - now).min(DELAY_FOR_A_PERIOD);
+            }
+            match Self::message_time_up(&msg_ext) {
+                Some(msg_inner) => {
+                    let put_message_result = message_store.put_message(msg_inner).await;
+                    if put_message_result.put_message_status() != PutMessageStatus::PutOk {
+                        error!(
+                            "schedule message of level {} at offset {} not delivered, status {:?}",
+                            level,
+                            msg_ext.queue_offset,
+                            put_message_result.put_message_status()
+                        );
+                        self.update_offset(level, next_offset);
+                        return DELAY_FOR_A_PERIOD;
+                    }
+                }
+                None => error!(
+                    "schedule message of level {} at offset {} has no real topic, dropped",
+                    level, msg_ext.queue_offset
+                ),
+            }
+            next_offset = msg_ext.queue_offset + 1;
+        }
+        self.update_offset(level, result.next_begin_offset().max(next_offset));
+        DELAY_FOR_A_WHILE
+    }
+
+    /// The message to put on the real topic of `msg_ext`, `None` if it has none.
+    fn message_time_up(msg_ext: &MessageExt) -> Option<MessageExtBrokerInner> {
+        let real_topic = msg_ext.get_property(MessageConst::PROPERTY_REAL_TOPIC)?;
+        let real_queue_id = msg_ext
+            .get_property(MessageConst::PROPERTY_REAL_QUEUE_ID)
+            .and_then(|queue_id| queue_id.parse::<i32>().ok())?;
+
+        let mut msg_inner = MessageExtBrokerInner::default();
+        msg_inner.message_ext_inner.message.topic = real_topic;
+        msg_inner
+            .message_ext_inner
+            .message
+            .body
+            .clone_from(&msg_ext.message.body);
+        msg_inner.message_ext_inner.message.flag = msg_ext.message.flag;
+        msg_inner
+            .message_ext_inner
+            .message
+            .set_properties(msg_ext.message.properties().clone());
+        for key in [
+            MessageConst::PROPERTY_DELAY_TIME_LEVEL,
+            MessageConst::PROPERTY_REAL_TOPIC,
+            MessageConst::PROPERTY_REAL_QUEUE_ID,
+        ] {
+            msg_inner.message_ext_inner.message.clear_property(key);
+        }
+        msg_inner.tags_code = MessageExtBrokerInner::tags_string2tags_code(
+            &TopicFilterType::SingleTag,
+            msg_ext.get_tags().unwrap_or_default().as_str(),
+        );
+        msg_inner.message_ext_inner.queue_id = real_queue_id;
+        msg_inner.message_ext_inner.sys_flag = msg_ext.sys_flag;
+        msg_inner.message_ext_inner.born_timestamp = msg_ext.born_timestamp;
+        msg_inner.message_ext_inner.born_host = msg_ext.born_host;
+        msg_inner.message_ext_inner.store_host = msg_ext.store_host;
+        msg_inner.message_ext_inner.reconsume_times = msg_ext.reconsume_times;
+        msg_inner
+            .message_ext_inner
+            .message
+            .set_wait_store_msg_ok(false);
+        msg_inner.properties_string = MessageDecoder::message_properties_to_string(
+            msg_inner.message_ext_inner.message.properties(),
+        );
+        Some(msg_inner)
+    }
+
+    fn update_offset(&self, level: i32, offset: i64) {
+        let previous = self.offset_table.lock().insert(level, offset);
+        if previous != Some(offset) {
+            self.data_version.lock().next_version();
+        }
+    }
+
+    /// Next offset to deliver of `level`.
+    pub fn offset(&self, level: i32) -> i64 {
+        self.offset_table.lock().get(&level).copied().unwrap_or(0)
+    }
+
     pub fn delay_level2queue_id(delay_level: i32) -> i32 {
         delay_level - 1
     }
 
-    pub fn build_running_stats(&self, _stats: &mut HashMap<String, String>) {
-        //TODO
+    /// Delay in milliseconds of each level of `message_delay_level`, e.g. `"1s 5s 1m 2h"`, level
+    /// 1 first. Falls back to [`DEFAULT_MESSAGE_DELAY_LEVEL`] when empty or malformed.
+    pub fn parse_delay_level(message_delay_level: &str) -> Vec<u64> {
+        let parse = |levels: &str| -> Option<Vec<u64>> {
+            levels
+                .split_whitespace()
+                .map(|level| {
+                    let unit = match level.chars().last()? {
+                        's' => 1000,
+                        'm' => 60 * 1000,
+                        'h' => 60 * 60 * 1000,
+                        'd' => 24 * 60 * 60 * 1000,
+                        _ => return None,
+                    };
+                    level[..level.len() - 1]
+                        .parse::<u64>()
+                        .ok()
+                        .map(|num| num * unit)
+                })
+                .collect()
+        };
+        match parse(message_delay_level) {
+            Some(levels) if !levels.is_empty() => levels,
+            _ => parse(DEFAULT_MESSAGE_DELAY_LEVEL).unwrap_or_default(),
+        }
     }
 
+    pub fn delay_mode_stats(&self) -> &DelayModeStats {
+        &self.delay_mode_stats
+    }
+
+    pub fn build_running_stats(&self, stats: &mut HashMap<String, String>) {
+        let delay_mode_stats = &self.delay_mode_stats;
+        stats.insert(
+            "delayLevelMessageCount".to_string(),
+            delay_mode_stats.level.load(Ordering::Relaxed).to_string(),
+        );
+        stats.insert(
+            "delayTimerWheelMessageCount".to_string(),
+            delay_mode_stats
+                .timer_wheel
+                .load(Ordering::Relaxed)
+                .to_string(),
+        );
+        stats.insert(
+            "delayTimerWheelFallbackCount".to_string(),
+            delay_mode_stats
+                .timer_wheel_fallback
+                .load(Ordering::Relaxed)
+                .to_string(),
+        );
+    }
+
+    /// Highest delay level, 0 when no levels are configured.
     pub fn get_max_delay_level(&self) -> i32 {
-        0
+        self.delay_level_table.len() as i32
     }
 }
 
 impl ConfigManager for ScheduleMessageService {
-    fn decode0(&mut self, _key: &[u8], _body: &[u8]) {
-        todo!()
-    }
-
+    /// Stops the delivery tasks and persists the offsets they reached.
     fn stop(&mut self) -> bool {
-        todo!()
+        if self.started.swap(false, Ordering::SeqCst) {
+            for task in self.tasks.lock().drain(..) {
+                task.abort();
+            }
+            self.persist();
+        }
+        true
     }
 
     fn config_file_path(&self) -> String {
@@ -55,12 +368,133 @@ impl ConfigManager for ScheduleMessageService {
     }
 
     fn encode(&mut self) -> String {
-        todo!()
+        self.encode_pretty(false)
+    }
+
+    fn encode_pretty(&self, pretty_format: bool) -> String {
+        let wrapper = DelayOffsetSerializeWrapper::new(
+            self.offset_table.lock().clone(),
+            self.data_version.lock().clone(),
+        );
+        let json = if pretty_format {
+            serde_json::to_string_pretty(&wrapper)
+        } else {
+            serde_json::to_string(&wrapper)
+        };
+        json.unwrap_or_default()
     }
 
-    fn encode_pretty(&self, _pretty_format: bool) -> String {
-        todo!()
+    fn decode(&self, json_string: &str) {
+        if json_string.is_empty() {
+            return;
+        }
+        match serde_json::from_str::<DelayOffsetSerializeWrapper>(json_string) {
+            Ok(wrapper) => {
+                self.offset_table
+                    .lock()
+                    .extend(wrapper.offset_table().iter().map(|(k, v)| (*k, *v)));
+                self.data_version
+                    .lock()
+                    .assign_new_one(wrapper.data_version());
+            }
+            Err(e) => error!("decode delay offsets failed: {}", e),
+        }
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use rocketmq_store::config::flush_disk_type::FlushDiskType;
+    use rocketmq_store::message_store::default_message_store::DefaultMessageStore;
+
+    use super::*;
+    use crate::hook::handle_schedule_message::HandleScheduleMessageHook;
 
-    fn decode(&self, _json_string: &str) {}
+    #[test]
+    fn delay_level_message_is_delivered_to_its_real_topic() {
+        // the store owns runtimes which must not be dropped inside of one
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root_dir = temp_dir.path().to_str().unwrap().to_string();
+        let broker_config = Arc::new(BrokerConfig {
+            store_path_root_dir: root_dir.clone(),
+            ..Default::default()
+        });
+        let message_store_config = Arc::new(MessageStoreConfig {
+            store_path_root_dir: root_dir,
+            flush_disk_type: FlushDiskType::AsyncFlush,
+            message_delay_level: "1s 10s".to_string(),
+            ..Default::default()
+        });
+        let (store, mut service) = runtime.block_on(async {
+            let topic_config_table = Arc::new(parking_lot::Mutex::new(HashMap::new()));
+            let mut store = DefaultMessageStore::new(
+                message_store_config.clone(),
+                broker_config.clone(),
+                topic_config_table.clone(),
+                None,
+                false,
+            );
+            let service = ScheduleMessageService::new(broker_config, &message_store_config);
+            store.set_put_message_hook(Box::new(HandleScheduleMessageHook::new(
+                None,
+                service.clone(),
+                message_store_config.clone(),
+                topic_config_table,
+            )));
+            assert!(store.load().await);
+            store.start().unwrap();
+
+            let mut msg = MessageExtBrokerInner::default();
+            msg.set_topic("TopicTest");
+            msg.message_ext_inner.queue_id = 1;
+            msg.message_ext_inner.message.set_tags("TagA".to_string());
+            msg.message_ext_inner.message.set_delay_time_level(1);
+            msg.message_ext_inner.message.body = Some(Bytes::from_static(b"delayed"));
+            msg.properties_string = MessageDecoder::message_properties_to_string(
+                msg.message_ext_inner.message.properties(),
+            );
+            assert!(store.put_message(msg).await.is_ok());
+            service.start(store.clone());
+
+            // parked in the schedule topic until the delay of level 1 elapsed
+            let deadline = tokio::time::Instant::now() + Duration::from_secs(10);
+            while store.get_max_offset_in_queue("TopicTest", 1).value() == 0 {
+                assert!(tokio::time::Instant::now() < deadline, "not delivered");
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+            assert_eq!(
+                store
+                    .get_max_offset_in_queue(TopicValidator::RMQ_SYS_SCHEDULE_TOPIC, 0)
+                    .value(),
+                1
+            );
+
+            let result = store
+                .get_message("group", "TopicTest", 1, 0, 32, i32::MAX, None)
+                .await
+                .unwrap();
+            assert_eq!(result.status(), Some(GetMessageStatus::Found));
+            let mut bytes = result.message_mapped_list()[0].get_bytes().unwrap();
+            let delivered =
+                MessageDecoder::decode(&mut bytes, true, false, false, false, false).unwrap();
+            assert_eq!(delivered.message.body.as_deref(), Some(&b"delayed"[..]));
+            assert_eq!(delivered.get_tags().as_deref(), Some("TagA"));
+            assert_eq!(delivered.message.get_delay_time_level(), 0);
+            assert!(delivered
+                .get_property(MessageConst::PROPERTY_REAL_TOPIC)
+                .is_none());
+            (store, service)
+        });
+
+        assert!(service.stop());
+        assert_eq!(service.offset(1), 1);
+        let reloaded =
+            ScheduleMessageService::new(service.broker_config.clone(), &message_store_config);
+        assert!(reloaded.load());
+        assert_eq!(reloaded.offset(1), 1);
+        drop(runtime);
+        drop(store);
+    }
 }
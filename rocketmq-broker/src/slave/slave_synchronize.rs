@@ -0,0 +1,216 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::sync::Arc;
+
+use rocketmq_common::common::config_manager::ConfigManager;
+use rocketmq_common::utils::file_utils;
+use rocketmq_store::message_store::default_message_store::DefaultMessageStore;
+use tracing::error;
+use tracing::info;
+
+use crate::offset::manager::consumer_offset_manager::ConsumerOffsetManager;
+use crate::out_api::broker_outer_api::BrokerOuterAPI;
+use crate::schedule::schedule_message_service::ScheduleMessageService;
+use crate::subscription::manager::subscription_group_manager::SubscriptionGroupManager;
+use crate::topic::manager::topic_config_manager::TopicConfigManager;
+use crate::topic::manager::topic_queue_mapping_manager::TopicQueueMappingManager;
+
+/// Pulls the metadata of a slave broker from its master, so that a failover to the slave keeps
+/// topics, consumer offsets, delay offsets and subscription groups.
+#[derive(Clone)]
+pub(crate) struct SlaveSynchronize {
+    broker_addr: String,
+    broker_out_api: Arc<BrokerOuterAPI>,
+    topic_config_manager: TopicConfigManager,
+    topic_queue_mapping_manager: Arc<TopicQueueMappingManager>,
+    consumer_offset_manager: ConsumerOffsetManager,
+    subscription_group_manager: Arc<SubscriptionGroupManager<DefaultMessageStore>>,
+    schedule_message_service: ScheduleMessageService,
+    master_addr: Arc<parking_lot::RwLock<Option<String>>>,
+}
+
+impl SlaveSynchronize {
+    pub fn new(
+        broker_addr: String,
+        broker_out_api: Arc<BrokerOuterAPI>,
+        topic_config_manager: TopicConfigManager,
+        topic_queue_mapping_manager: Arc<TopicQueueMappingManager>,
+        consumer_offset_manager: ConsumerOffsetManager,
+        subscription_group_manager: Arc<SubscriptionGroupManager<DefaultMessageStore>>,
+        schedule_message_service: ScheduleMessageService,
+    ) -> Self {
+        Self {
+            broker_addr,
+            broker_out_api,
+            topic_config_manager,
+            topic_queue_mapping_manager,
+            consumer_offset_manager,
+            subscription_group_manager,
+            schedule_message_service,
+            master_addr: Arc::new(parking_lot::RwLock::new(None)),
+        }
+    }
+
+    pub fn master_addr(&self) -> Option<String> {
+        self.master_addr.read().clone()
+    }
+
+    pub fn set_master_addr(&self, master_addr: Option<String>) {
+        let mut current = self.master_addr.write();
+        if *current != master_addr {
+            info!(
+                "update master address from {:?} to {:?}",
+                *current, master_addr
+            );
+            *current = master_addr;
+        }
+    }
+
+    pub async fn sync_all(&self) {
+        self.sync_topic_config().await;
+        self.sync_consumer_offset().await;
+        self.sync_delay_offset().await;
+        self.sync_subscription_group_config().await;
+    }
+
+    /// The master to pull from, or `None` when it is unknown or is this broker itself.
+    fn effective_master_addr(&self) -> Option<String> {
+        self.master_addr()
+            .filter(|master_addr| *master_addr != self.broker_addr)
+    }
+
+    async fn sync_topic_config(&self) {
+        let Some(master_addr) = self.effective_master_addr() else {
+            return;
+        };
+        match self
+            .broker_out_api
+            .get_all_topic_config(master_addr.as_str())
+            .await
+        {
+            Ok(wrapper) => {
+                let topic_config_wrapper = wrapper.topic_config_serialize_wrapper();
+                if self.topic_config_manager.sync_topic_config_table(
+                    topic_config_wrapper.topic_config_table(),
+                    topic_config_wrapper.data_version(),
+                ) {
+                    info!("update slave topic config from master, {}", master_addr);
+                }
+                if self
+                    .topic_queue_mapping_manager
+                    .sync_topic_queue_mapping_table(
+                        wrapper.topic_queue_mapping_detail_map(),
+                        wrapper.mapping_data_version(),
+                    )
+                {
+                    info!(
+                        "update slave topic queue mapping from master, {}",
+                        master_addr
+                    );
+                }
+            }
+            Err(e) => {
+                error!(
+                    "sync topic config from master {} failed: {}",
+                    master_addr, e
+                );
+            }
+        }
+    }
+
+    async fn sync_consumer_offset(&self) {
+        let Some(master_addr) = self.effective_master_addr() else {
+            return;
+        };
+        match self
+            .broker_out_api
+            .get_all_consumer_offset(master_addr.as_str())
+            .await
+        {
+            Ok(content) => {
+                if self
+                    .consumer_offset_manager
+                    .sync_offset_table(content.as_str())
+                {
+                    info!("update slave consumer offset from master, {}", master_addr);
+                }
+            }
+            Err(e) => {
+                error!(
+                    "sync consumer offset from master {} failed: {}",
+                    master_addr, e
+                );
+            }
+        }
+    }
+
+    async fn sync_delay_offset(&self) {
+        let Some(master_addr) = self.effective_master_addr() else {
+            return;
+        };
+        match self
+            .broker_out_api
+            .get_all_delay_offset(master_addr.as_str())
+            .await
+        {
+            Ok(content) => {
+                let file_name = self.schedule_message_service.config_file_path();
+                if let Err(e) = file_utils::string_to_file(content.as_str(), file_name.as_str()) {
+                    error!("persist delay offset to {} failed: {}", file_name, e);
+                    return;
+                }
+                self.schedule_message_service.load();
+                info!("update slave delay offset from master, {}", master_addr);
+            }
+            Err(e) => {
+                error!(
+                    "sync delay offset from master {} failed: {}",
+                    master_addr, e
+                );
+            }
+        }
+    }
+
+    async fn sync_subscription_group_config(&self) {
+        let Some(master_addr) = self.effective_master_addr() else {
+            return;
+        };
+        match self
+            .broker_out_api
+            .get_all_subscription_group_config(master_addr.as_str())
+            .await
+        {
+            Ok(content) => {
+                if self
+                    .subscription_group_manager
+                    .sync_subscription_group_table(content.as_str())
+                {
+                    info!(
+                        "update slave subscription group from master, {}",
+                        master_addr
+                    );
+                }
+            }
+            Err(e) => {
+                error!(
+                    "sync subscription group from master {} failed: {}",
+                    master_addr, e
+                );
+            }
+        }
+    }
+}
@@ -29,6 +29,7 @@ use rocketmq_store::log_file::MessageStore;
 use serde::Deserialize;
 use serde::Serialize;
 use tracing::info;
+use tracing::warn;
 
 use crate::broker_path_config_helper::get_subscription_group_path;
 
@@ -97,6 +98,29 @@ impl<MS> ConfigManager for SubscriptionGroupManager<MS> {
     }
 }
 
+impl<MS> SubscriptionGroupManager<MS> {
+    /// Replaces the local subscription groups with the ones pulled from the master, encoded as
+    /// `subscriptionGroup.json`. Returns `false` when nothing was applied.
+    pub fn sync_subscription_group_table(&self, json_string: &str) -> bool {
+        let wrapper = match serde_json::from_str::<SubscriptionGroupWrapper>(json_string) {
+            Ok(wrapper) => wrapper,
+            Err(e) => {
+                warn!("decode subscription group from master failed: {}", e);
+                return false;
+            }
+        };
+        {
+            let mut local = self.subscription_group_wrapper.lock();
+            if local.data_version == wrapper.data_version {
+                return false;
+            }
+            *local = wrapper;
+        }
+        self.persist();
+        true
+    }
+}
+
 impl<MS> SubscriptionGroupManager<MS>
 where
     MS: MessageStore,
@@ -160,6 +184,52 @@ where
             .cloned()
     }
 
+    /// Stores `subscription_group_config` as is and persists the table, used by the metadata
+    /// bootstrap and the admin API. Returns `false` without touching anything when the group is
+    /// already configured exactly like that.
+    pub(crate) fn upsert_subscription_group_config(
+        &self,
+        subscription_group_config: SubscriptionGroupConfig,
+    ) -> bool {
+        let group = subscription_group_config.group_name().to_string();
+        {
+            let mut wrapper = self.subscription_group_wrapper.lock();
+            let unchanged = wrapper
+                .subscription_group_table
+                .get(group.as_str())
+                .is_some_and(|current| {
+                    serde_json::to_value(current).ok()
+                        == serde_json::to_value(&subscription_group_config).ok()
+                });
+            if unchanged {
+                return false;
+            }
+            match wrapper
+                .subscription_group_table
+                .insert(group, subscription_group_config.clone())
+            {
+                None => info!(
+                    "provision new subscription group, {:?}",
+                    subscription_group_config
+                ),
+                Some(old) => info!(
+                    "provision subscription group, old: {:?}, new: {:?}",
+                    old, subscription_group_config
+                ),
+            }
+            let state_machine_version = if let Some(ref store) = self.message_store {
+                store.get_state_machine_version()
+            } else {
+                0
+            };
+            wrapper
+                .data_version
+                .next_version_with(state_machine_version);
+        }
+        self.persist();
+        true
+    }
+
     pub fn get_forbidden(&self, group: &str, topic: &str, forbidden_index: i32) -> bool {
         let topic_forbidden = self.get_forbidden_internal(group, topic);
         let bit_forbidden = 1 << forbidden_index;
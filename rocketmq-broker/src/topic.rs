@@ -16,4 +16,5 @@
  */
 
 pub(crate) mod manager;
+pub(crate) mod system_topic_guard;
 pub(crate) mod topic_queue_mapping_clean_service;
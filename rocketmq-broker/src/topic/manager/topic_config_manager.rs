@@ -339,7 +339,11 @@ impl TopicConfigManager {
         if let Some(ref mut config) = self.get_topic_config(topic) {
             if is_order != config.order {
                 config.order = is_order;
-                self.update_topic_config(config);
+                // keeps the current attributes
+                config.attributes.clear();
+                if let Err(e) = self.update_topic_config(config) {
+                    warn!("update order of topic {} failed: {}", topic, e);
+                }
             }
             return Some(config.clone());
         }
@@ -393,10 +397,14 @@ impl TopicConfigManager {
         });
     }
 
-    pub fn update_topic_config_list(&mut self, topic_config_list: &mut [TopicConfig]) {
+    pub fn update_topic_config_list(
+        &mut self,
+        topic_config_list: &mut [TopicConfig],
+    ) -> Result<(), String> {
         for topic_config in topic_config_list {
-            self.update_topic_config(topic_config);
+            self.update_topic_config(topic_config)?;
         }
+        Ok(())
     }
 
     #[inline]
@@ -422,17 +430,26 @@ impl TopicConfigManager {
         }
     }
 
-    pub fn update_topic_config(&mut self, topic_config: &mut TopicConfig) {
-        let new_attributes = Self::request(topic_config);
-        let current_attributes = self.current(topic_config.topic_name.as_ref().unwrap().as_str());
-        let create = self
-            .topic_config_table
-            .lock()
-            .get(topic_config.topic_name.as_ref().unwrap().as_str())
-            .is_none();
-        let final_attributes =
-            alter_current_attributes(create, ALL.clone(), new_attributes, current_attributes);
-        topic_config.attributes = final_attributes;
+    /// Attributes `topic_config` ends up with once its `+key=value` and `-key` attribute
+    /// modifications are applied to the ones of the topic, see [`alter_current_attributes`].
+    pub fn alter_topic_attributes(
+        &self,
+        topic_config: &TopicConfig,
+    ) -> Result<HashMap<String, String>, String> {
+        let topic = topic_config.topic_name.as_deref().unwrap_or_default();
+        let create = self.topic_config_table.lock().get(topic).is_none();
+        alter_current_attributes(
+            create,
+            &ALL,
+            self.current(topic),
+            Self::request(topic_config),
+        )
+    }
+
+    /// Creates or updates the topic, its attributes are the modifications of the attributes of
+    /// the current topic config. Fails without touching anything when they are invalid.
+    pub fn update_topic_config(&mut self, topic_config: &mut TopicConfig) -> Result<(), String> {
+        topic_config.attributes = self.alter_topic_attributes(topic_config)?;
         match self.put_topic_config(topic_config.clone()) {
             None => {
                 info!("create new topic [{:?}]", topic_config)
@@ -455,6 +472,34 @@ impl TopicConfigManager {
             topic_config.topic_name.as_ref().unwrap().as_str(),
             topic_config.clone(),
         );
+        Ok(())
+    }
+
+    /// Stores `topic_config` as is and persists the table, used by the metadata bootstrap which
+    /// validates attributes itself. Returns `false` without touching anything when the topic is
+    /// already configured exactly like that.
+    pub(crate) fn upsert_topic_config(&self, topic_config: TopicConfig) -> bool {
+        let topic = topic_config.topic_name.clone().unwrap_or_default();
+        if self.get_topic_config(topic.as_str()).as_ref() == Some(&topic_config) {
+            return false;
+        }
+        match self.put_topic_config(topic_config.clone()) {
+            None => info!("provision new topic [{:?}]", topic_config),
+            Some(old) => info!(
+                "provision topic config, old:[{:?}] new:[{:?}]",
+                old, topic_config
+            ),
+        }
+        let state_machine_version = if let Some(message_store) = self.message_store.as_ref() {
+            message_store.get_state_machine_version()
+        } else {
+            0
+        };
+        self.data_version
+            .mut_from_ref()
+            .next_version_with(state_machine_version);
+        self.persist();
+        true
     }
 
     fn request(topic_config: &TopicConfig) -> HashMap<String, String> {
@@ -538,6 +583,32 @@ impl TopicConfigManager {
         self.data_version.clone()
     }
 
+    /// Replaces the local topic configs with the ones pulled from the master, dropping topics the
+    /// master no longer has. Returns `false` when the local table is already up to date.
+    pub fn sync_topic_config_table(
+        &self,
+        topic_config_table: &HashMap<String, TopicConfig>,
+        data_version: &DataVersion,
+    ) -> bool {
+        if self.data_version.as_ref() == data_version {
+            return false;
+        }
+        self.data_version
+            .mut_from_ref()
+            .assign_new_one(data_version);
+        {
+            let mut table = self.topic_config_table.lock();
+            table.retain(|topic, _| topic_config_table.contains_key(topic));
+            table.extend(
+                topic_config_table
+                    .iter()
+                    .map(|(topic, config)| (topic.clone(), config.clone())),
+            );
+        }
+        self.persist();
+        true
+    }
+
     #[inline]
     pub fn broker_runtime_inner(&self) -> &Arc<BrokerRuntimeInner> {
         &self.broker_runtime_inner
@@ -212,6 +212,30 @@ impl TopicQueueMappingManager {
             }
         }
     }
+
+    /// Replaces the local static topic mappings with the ones pulled from the master. Returns
+    /// `false` when the local table is already up to date.
+    pub fn sync_topic_queue_mapping_table(
+        &self,
+        topic_queue_mapping_table: &HashMap<String, TopicQueueMappingDetail>,
+        data_version: &DataVersion,
+    ) -> bool {
+        if *self.data_version.lock() == *data_version {
+            return false;
+        }
+        self.data_version.lock().assign_new_one(data_version);
+        {
+            let mut table = self.topic_queue_mapping_table.lock();
+            table.retain(|topic, _| topic_queue_mapping_table.contains_key(topic));
+            table.extend(
+                topic_queue_mapping_table
+                    .iter()
+                    .map(|(topic, detail)| (topic.clone(), detail.clone())),
+            );
+        }
+        self.persist();
+        true
+    }
 }
 
 //Fully implemented will be removed
@@ -305,4 +329,26 @@ mod tests {
 
         assert!(manager.get_topic_queue_mapping("existing_topic").is_none());
     }
+
+    #[test]
+    fn sync_topic_queue_mapping_table_replaces_stale_topics() {
+        let broker_config = Arc::new(BrokerConfig::default());
+        let manager = TopicQueueMappingManager::new(broker_config);
+        manager.topic_queue_mapping_table.lock().insert(
+            "stale_topic".to_string(),
+            TopicQueueMappingDetail::default(),
+        );
+        let mut master_table = HashMap::new();
+        master_table.insert(
+            "master_topic".to_string(),
+            TopicQueueMappingDetail::default(),
+        );
+        let mut master_version = DataVersion::new();
+        master_version.next_version();
+
+        assert!(manager.sync_topic_queue_mapping_table(&master_table, &master_version));
+        assert!(manager.get_topic_queue_mapping("stale_topic").is_none());
+        assert!(manager.get_topic_queue_mapping("master_topic").is_some());
+        assert!(!manager.sync_topic_queue_mapping_table(&master_table, &master_version));
+    }
 }
@@ -0,0 +1,105 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::collections::HashSet;
+use std::net::IpAddr;
+use std::net::SocketAddr;
+
+use rocketmq_common::common::broker::broker_config::BrokerConfig;
+use rocketmq_common::common::topic::TopicValidator;
+use tracing::warn;
+
+/// Keeps clients from sending to and pulling from the topics the broker uses internally, such
+/// as `SCHEDULE_TOPIC_XXXX` or `RMQ_SYS_TRANS_HALF_TOPIC`. Clients connecting from one of
+/// `system_topic_admin_addresses` may use every system topic, other clients only the trace
+/// topic and the ones in `system_topic_allowlist`.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct SystemTopicGuard {
+    enable: bool,
+    allowed_topics: HashSet<String>,
+    admin_hosts: HashSet<IpAddr>,
+}
+
+impl SystemTopicGuard {
+    pub fn new(broker_config: &BrokerConfig) -> Self {
+        let mut allowed_topics: HashSet<String> = split(&broker_config.system_topic_allowlist)
+            .map(ToString::to_string)
+            .collect();
+        allowed_topics.insert(broker_config.msg_trace_topic_name.clone());
+        let admin_hosts = split(&broker_config.system_topic_admin_addresses)
+            .filter_map(|host| match host.parse::<IpAddr>() {
+                Ok(host) => Some(host),
+                Err(_) => {
+                    warn!("ignore invalid system topic admin address {}", host);
+                    None
+                }
+            })
+            .collect();
+        Self {
+            enable: broker_config.system_topic_protection_enable,
+            allowed_topics,
+            admin_hosts,
+        }
+    }
+
+    /// Whether the client at `remote_address` may send to or pull from `topic`.
+    pub fn is_allowed(&self, topic: &str, remote_address: SocketAddr) -> bool {
+        !self.enable
+            || !TopicValidator::is_system_topic(topic)
+            || self.allowed_topics.contains(topic)
+            || self.is_admin(remote_address)
+    }
+
+    /// Whether `remote_address` is one of the admin clients.
+    pub fn is_admin(&self, remote_address: SocketAddr) -> bool {
+        self.admin_hosts.contains(&remote_address.ip())
+    }
+}
+
+fn split(list: &str) -> impl Iterator<Item = &str> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_admins_use_internal_system_topics() {
+        let broker_config = BrokerConfig {
+            system_topic_allowlist: "BenchmarkTest, rmq_sys_custom".to_string(),
+            system_topic_admin_addresses: "10.0.0.1,not-an-ip".to_string(),
+            system_topic_protection_enable: true,
+            ..Default::default()
+        };
+        let guard = SystemTopicGuard::new(&broker_config);
+        let client: SocketAddr = "10.0.0.2:5000".parse().unwrap();
+        let admin: SocketAddr = "10.0.0.1:5000".parse().unwrap();
+
+        assert!(!guard.is_allowed(TopicValidator::RMQ_SYS_SCHEDULE_TOPIC, client));
+        assert!(!guard.is_allowed(TopicValidator::RMQ_SYS_TRANS_HALF_TOPIC, client));
+        assert!(!guard.is_allowed("rmq_sys_wheel_timer", client));
+        assert!(guard.is_allowed(TopicValidator::RMQ_SYS_SCHEDULE_TOPIC, admin));
+        assert!(guard.is_allowed(TopicValidator::RMQ_SYS_TRACE_TOPIC, client));
+        assert!(guard.is_allowed("rmq_sys_custom", client));
+        assert!(guard.is_allowed("TopicTest", client));
+
+        let disabled = SystemTopicGuard::new(&BrokerConfig::default());
+        assert!(disabled.is_allowed(TopicValidator::RMQ_SYS_SCHEDULE_TOPIC, client));
+    }
+}
@@ -20,6 +20,7 @@ use std::sync::atomic::AtomicI64;
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
 
+use rocketmq_common::common::attribute::delay_mode::DelayMode;
 use rocketmq_common::common::config::TopicConfig;
 use rocketmq_common::common::message::message_ext::MessageExt;
 use rocketmq_common::common::message::message_ext_broker_inner::MessageExtBrokerInner;
@@ -164,37 +165,97 @@ impl HookUtils {
         None
     }
 
+    /// Routes delay messages to the timer wheel or to the schedule topic. Messages with a delay
+    /// level of a topic in [`DelayMode::TimerWheel`] go to the timer wheel, delayed by the
+    /// level's time, or stay on the level when the timer wheel is disabled.
     pub fn handle_schedule_message(
-        timer_message_store: &TimerMessageStore,
+        timer_message_store: Option<&TimerMessageStore>,
         schedule_message_service: &ScheduleMessageService,
         message_store_config: &Arc<MessageStoreConfig>,
+        delay_mode: DelayMode,
         msg: &mut MessageExtBrokerInner,
     ) -> Option<PutMessageResult> {
         let tran_type = MessageSysFlag::get_transaction_value(msg.sys_flag());
         if tran_type == MessageSysFlag::TRANSACTION_NOT_TYPE
             || tran_type == MessageSysFlag::TRANSACTION_COMMIT_TYPE
         {
-            if !Self::is_rolled_timer_message(msg) && Self::check_if_timer_message(msg) {
-                if !message_store_config.timer_wheel_enable {
-                    // wheel timer is not enabled, reject the message
-                    return Some(PutMessageResult::new_default(
-                        PutMessageStatus::WheelTimerNotEnable,
-                    ));
+            let delay_level = msg.message_ext_inner.message.get_delay_time_level();
+            if delay_level > 0
+                && delay_mode == DelayMode::TimerWheel
+                && !Self::is_rolled_timer_message(msg)
+            {
+                if timer_message_store.is_some() && message_store_config.timer_wheel_enable {
+                    Self::transform_level_to_timer_message(message_store_config, msg);
+                } else {
+                    schedule_message_service
+                        .delay_mode_stats()
+                        .record_timer_wheel_fallback();
                 }
-                if let Some(transform_res) =
-                    Self::transform_timer_message(timer_message_store, message_store_config, msg)
-                {
+            }
+            if !Self::is_rolled_timer_message(msg) && Self::check_if_timer_message(msg) {
+                let timer_message_store = match timer_message_store {
+                    Some(timer_message_store) if message_store_config.timer_wheel_enable => {
+                        timer_message_store
+                    }
+                    _ => {
+                        // wheel timer is not enabled, reject the message
+                        return Some(PutMessageResult::new_default(
+                            PutMessageStatus::WheelTimerNotEnable,
+                        ));
+                    }
+                };
+                // the delay level a message was sent with exempts it from the max delay
+                if let Some(transform_res) = Self::transform_timer_message(
+                    timer_message_store,
+                    message_store_config,
+                    delay_level,
+                    msg,
+                ) {
                     return Some(transform_res);
                 }
+                if timer_message_store::TIMER_TOPIC == msg.topic() {
+                    schedule_message_service
+                        .delay_mode_stats()
+                        .record_timer_wheel();
+                }
             }
-            // Delay Delivery
-            if msg.message_ext_inner.message.get_delay_time_level() > 0 {
+            // Delay Delivery, only when the schedule service has delay levels configured
+            if msg.message_ext_inner.message.get_delay_time_level() > 0
+                && schedule_message_service.get_max_delay_level() > 0
+            {
                 Self::transform_delay_level_message(schedule_message_service, msg);
+                schedule_message_service.delay_mode_stats().record_level();
             }
         }
         None
     }
 
+    /// Replaces the delay level of `msg` by a timer delay of the level's time, the last level
+    /// for levels beyond it.
+    fn transform_level_to_timer_message(
+        message_store_config: &MessageStoreConfig,
+        msg: &mut MessageExtBrokerInner,
+    ) {
+        let delay_levels =
+            ScheduleMessageService::parse_delay_level(&message_store_config.message_delay_level);
+        let delay_level = msg.message_ext_inner.message.get_delay_time_level() as usize;
+        let Some(delay_ms) = delay_levels
+            .get(delay_level - 1)
+            .or(delay_levels.last())
+            .copied()
+        else {
+            return;
+        };
+        msg.message_ext_inner
+            .message
+            .properties
+            .remove(MessageConst::PROPERTY_DELAY_TIME_LEVEL);
+        msg.message_ext_inner.message.properties.insert(
+            MessageConst::PROPERTY_TIMER_DELAY_MS.to_string(),
+            delay_ms.to_string(),
+        );
+    }
+
     fn is_rolled_timer_message(msg: &MessageExtBrokerInner) -> bool {
         timer_message_store::TIMER_TOPIC == msg.topic()
     }
@@ -257,9 +318,9 @@ impl HookUtils {
     fn transform_timer_message(
         timer_message_store: &TimerMessageStore,
         message_store_config: &Arc<MessageStoreConfig>,
+        delay_level: i32,
         msg: &mut MessageExtBrokerInner,
     ) -> Option<PutMessageResult> {
-        let delay_level = msg.message_ext_inner.message.get_delay_time_level();
         let deliver_ms = match msg.property(MessageConst::PROPERTY_TIMER_DELAY_SEC) {
             Some(delay_sec) => get_current_millis() + delay_sec.parse::<u64>().unwrap() * 1000,
             None => match msg.property(MessageConst::PROPERTY_TIMER_DELAY_MS) {
@@ -435,4 +496,156 @@ mod tests {
             PutMessageStatus::MessageIllegal
         );
     }
+
+    #[test]
+    fn handle_schedule_message_rejects_timer_message_when_timer_wheel_disabled() {
+        let mut msg = MessageExtBrokerInner::default();
+        msg.message_ext_inner.message.topic = "test_topic".to_string();
+        msg.message_ext_inner.message.properties.insert(
+            MessageConst::PROPERTY_TIMER_DELAY_SEC.to_string(),
+            "10".to_string(),
+        );
+
+        let result = HookUtils::handle_schedule_message(
+            None,
+            &ScheduleMessageService::default(),
+            &Arc::new(MessageStoreConfig::default()),
+            DelayMode::Level,
+            &mut msg,
+        );
+
+        assert_eq!(
+            result.unwrap().put_message_status(),
+            PutMessageStatus::WheelTimerNotEnable
+        );
+    }
+
+    #[test]
+    fn handle_schedule_message_keeps_topic_without_delay_levels() {
+        let mut msg = MessageExtBrokerInner::default();
+        msg.message_ext_inner.message.topic = "test_topic".to_string();
+        msg.message_ext_inner.message.set_delay_time_level(3);
+
+        let schedule_message_service = ScheduleMessageService::default();
+        let result = HookUtils::handle_schedule_message(
+            None,
+            &schedule_message_service,
+            &Arc::new(MessageStoreConfig::default()),
+            DelayMode::Level,
+            &mut msg,
+        );
+
+        assert!(result.is_none());
+        assert_eq!(msg.topic(), "test_topic");
+        let mut stats = HashMap::new();
+        schedule_message_service.build_running_stats(&mut stats);
+        assert_eq!(stats["delayLevelMessageCount"], "0");
+    }
+
+    #[test]
+    fn handle_schedule_message_moves_delay_levels_to_the_schedule_topic() {
+        let message_store_config = Arc::new(MessageStoreConfig {
+            message_delay_level: "1s 5s 10s".to_string(),
+            ..MessageStoreConfig::default()
+        });
+        let schedule_message_service =
+            ScheduleMessageService::new(Arc::default(), &message_store_config);
+        assert_eq!(schedule_message_service.get_max_delay_level(), 3);
+        let mut msg = MessageExtBrokerInner::default();
+        msg.message_ext_inner.message.topic = "test_topic".to_string();
+        msg.message_ext_inner.queue_id = 5;
+        msg.message_ext_inner.message.set_delay_time_level(3);
+
+        let result = HookUtils::handle_schedule_message(
+            None,
+            &schedule_message_service,
+            &message_store_config,
+            DelayMode::Level,
+            &mut msg,
+        );
+
+        assert!(result.is_none());
+        assert_eq!(msg.topic(), TopicValidator::RMQ_SYS_SCHEDULE_TOPIC);
+        assert_eq!(msg.message_ext_inner.queue_id, 2);
+        assert_eq!(msg.message_ext_inner.message.get_delay_time_level(), 3);
+        assert_eq!(
+            msg.property(MessageConst::PROPERTY_REAL_TOPIC).unwrap(),
+            "test_topic"
+        );
+        assert_eq!(
+            msg.property(MessageConst::PROPERTY_REAL_QUEUE_ID).unwrap(),
+            "5"
+        );
+
+        // levels beyond the last one are clamped to it
+        let mut beyond = MessageExtBrokerInner::default();
+        beyond.message_ext_inner.message.topic = "test_topic".to_string();
+        beyond.message_ext_inner.message.set_delay_time_level(18);
+        HookUtils::handle_schedule_message(
+            None,
+            &schedule_message_service,
+            &message_store_config,
+            DelayMode::Level,
+            &mut beyond,
+        );
+        assert_eq!(beyond.message_ext_inner.message.get_delay_time_level(), 3);
+        assert_eq!(beyond.message_ext_inner.queue_id, 2);
+
+        let mut stats = HashMap::new();
+        schedule_message_service.build_running_stats(&mut stats);
+        assert_eq!(stats["delayLevelMessageCount"], "2");
+    }
+
+    #[test]
+    fn handle_schedule_message_moves_levels_of_timer_wheel_topics_to_the_timer_wheel() {
+        let message_store_config = Arc::new(MessageStoreConfig {
+            timer_wheel_enable: true,
+            ..MessageStoreConfig::default()
+        });
+        let schedule_message_service =
+            ScheduleMessageService::new(Arc::default(), &message_store_config);
+        let mut msg = MessageExtBrokerInner::default();
+        msg.message_ext_inner.message.topic = "test_topic".to_string();
+        msg.message_ext_inner.message.set_delay_time_level(3);
+
+        let result = HookUtils::handle_schedule_message(
+            Some(&TimerMessageStore::new_empty()),
+            &schedule_message_service,
+            &message_store_config,
+            DelayMode::TimerWheel,
+            &mut msg,
+        );
+
+        assert!(result.is_none());
+        assert_eq!(msg.topic(), timer_message_store::TIMER_TOPIC);
+        assert_eq!(msg.message_ext_inner.message.get_delay_time_level(), 0);
+        assert_eq!(
+            msg.property(MessageConst::PROPERTY_REAL_TOPIC).unwrap(),
+            "test_topic"
+        );
+        assert_eq!(
+            msg.property(MessageConst::PROPERTY_TIMER_DELAY_MS).unwrap(),
+            "10000"
+        );
+
+        let mut fallback = MessageExtBrokerInner::default();
+        fallback.message_ext_inner.message.topic = "test_topic".to_string();
+        fallback.message_ext_inner.message.set_delay_time_level(3);
+        let result = HookUtils::handle_schedule_message(
+            None,
+            &schedule_message_service,
+            &message_store_config,
+            DelayMode::TimerWheel,
+            &mut fallback,
+        );
+        assert!(result.is_none());
+        assert_eq!(fallback.topic(), TopicValidator::RMQ_SYS_SCHEDULE_TOPIC);
+        assert_eq!(fallback.message_ext_inner.message.get_delay_time_level(), 3);
+
+        let mut stats = HashMap::new();
+        schedule_message_service.build_running_stats(&mut stats);
+        assert_eq!(stats["delayTimerWheelMessageCount"], "1");
+        assert_eq!(stats["delayTimerWheelFallbackCount"], "1");
+        assert_eq!(stats["delayLevelMessageCount"], "1");
+    }
 }
@@ -17,7 +17,22 @@
 use clap::Parser;
 use rocketmq_cli::command_line::Commands;
 use rocketmq_cli::command_line::RootCli;
+use rocketmq_cli::commercial_stats::print_commercial_stats;
+use rocketmq_cli::consumer_offsets::clone_group_offset;
+use rocketmq_cli::consumer_offsets::print_consume_time_span;
 use rocketmq_cli::content_show::print_content;
+use rocketmq_cli::dlq_messages::query_dlq_messages;
+use rocketmq_cli::dlq_messages::resend_dlq_messages;
+use rocketmq_cli::message_trace::print_message_trace;
+use rocketmq_cli::resend_messages::resend_messages;
+use rocketmq_cli::topic_list::print_topic_list;
+use rocketmq_client::trace::trace_view::TraceFilter;
+use rocketmq_remoting::protocol::header::clone_group_offset_request_header::CloneGroupOffsetRequestHeader;
+use rocketmq_remoting::protocol::header::query_consume_time_span_request_header::QueryConsumeTimeSpanRequestHeader;
+use rocketmq_remoting::protocol::header::query_dlq_messages_request_header::QueryDlqMessagesRequestHeader;
+use rocketmq_remoting::protocol::header::query_message_trace_request_header::QueryMessageTraceRequestHeader;
+use rocketmq_remoting::protocol::header::resend_commit_log_messages_request_header::ResendCommitLogMessagesRequestHeader;
+use rocketmq_remoting::protocol::header::resend_dlq_messages_request_header::ResendDlqMessagesRequestHeader;
 
 fn main() {
     let cli = RootCli::parse();
@@ -25,5 +40,126 @@ fn main() {
         Commands::ReadMessageLog { config, from, to } => {
             print_content(from, to, config);
         }
+        Commands::ResendMessages {
+            broker_addr,
+            topic,
+            target_topic,
+            begin_offset,
+            end_offset,
+            begin_timestamp,
+            end_timestamp,
+            max_count,
+            timeout_millis,
+        } => {
+            let request_header = ResendCommitLogMessagesRequestHeader {
+                topic,
+                target_topic,
+                begin_offset,
+                end_offset,
+                begin_timestamp,
+                end_timestamp,
+                max_count,
+            };
+            resend_messages(broker_addr, request_header, timeout_millis);
+        }
+        Commands::QueryDlqMessages {
+            broker_addr,
+            consumer_group,
+            queue_id,
+            offset,
+            max_count,
+            timeout_millis,
+        } => {
+            let request_header = QueryDlqMessagesRequestHeader {
+                consumer_group,
+                queue_id,
+                offset,
+                max_count,
+            };
+            query_dlq_messages(broker_addr, request_header, timeout_millis);
+        }
+        Commands::ResendDlqMessages {
+            broker_addr,
+            consumer_group,
+            queue_id,
+            offsets,
+            timeout_millis,
+        } => {
+            let request_header = ResendDlqMessagesRequestHeader {
+                consumer_group,
+                queue_id,
+                offsets: offsets
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(","),
+            };
+            resend_dlq_messages(broker_addr, request_header, timeout_millis);
+        }
+        Commands::TopicList {
+            namesrv_addr,
+            cluster,
+            timeout_millis,
+        } => {
+            print_topic_list(namesrv_addr, cluster, timeout_millis);
+        }
+        Commands::CommercialStats {
+            broker_addr,
+            begin_timestamp,
+            json,
+            timeout_millis,
+        } => {
+            print_commercial_stats(broker_addr, begin_timestamp, json, timeout_millis);
+        }
+        Commands::CloneGroupOffset {
+            broker_addr,
+            src_group,
+            dest_group,
+            topic,
+            offline,
+            timeout_millis,
+        } => {
+            let request_header = CloneGroupOffsetRequestHeader {
+                src_group,
+                dest_group,
+                topic,
+                offline,
+            };
+            clone_group_offset(broker_addr, request_header, timeout_millis);
+        }
+        Commands::ConsumeTimeSpan {
+            broker_addr,
+            topic,
+            group,
+            timeout_millis,
+        } => {
+            let request_header = QueryConsumeTimeSpanRequestHeader { topic, group };
+            print_consume_time_span(broker_addr, request_header, timeout_millis);
+        }
+        Commands::QueryMessageTrace {
+            broker_addrs,
+            msg_id,
+            key,
+            trace_topic,
+            begin_timestamp,
+            end_timestamp,
+            max_count,
+            timeout_millis,
+        } => {
+            let filter = match (msg_id, key) {
+                (Some(msg_id), _) => TraceFilter::MsgId(msg_id),
+                (None, key) => TraceFilter::Key(key.unwrap_or_default()),
+            };
+            let request_header = QueryMessageTraceRequestHeader {
+                key: match &filter {
+                    TraceFilter::MsgId(key) | TraceFilter::Key(key) => key.clone(),
+                },
+                trace_topic,
+                begin_timestamp,
+                end_timestamp,
+                max_count,
+            };
+            print_message_trace(broker_addrs, request_header, &filter, timeout_millis);
+        }
     }
 }
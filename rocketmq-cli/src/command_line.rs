@@ -81,4 +81,406 @@ pub enum Commands {
         )]
         to: Option<u32>,
     },
+
+    #[command(
+        arg_required_else_help = true,
+        author = "mxsm",
+        version = "0.2.0",
+        about = "resend the messages of a topic stored in a broker commit log range"
+    )]
+    ResendMessages {
+        #[arg(
+            short = 'b',
+            long,
+            value_name = "ADDR",
+            help = "broker address, e.g. 127.0.0.1:10911"
+        )]
+        broker_addr: String,
+
+        #[arg(
+            short = 't',
+            long,
+            value_name = "TOPIC",
+            help = "topic of the messages to resend"
+        )]
+        topic: String,
+
+        #[arg(
+            short = 'd',
+            long,
+            value_name = "TOPIC",
+            help = "topic to resend the messages to, defaults to the original topic"
+        )]
+        target_topic: Option<String>,
+
+        #[arg(
+            long,
+            value_name = "OFFSET",
+            help = "commit log offset of the first message to scan, defaults to the minimum offset"
+        )]
+        begin_offset: Option<i64>,
+
+        #[arg(
+            long,
+            value_name = "OFFSET",
+            help = "commit log offset to stop scanning at, defaults to the maximum offset"
+        )]
+        end_offset: Option<i64>,
+
+        #[arg(
+            long,
+            value_name = "MILLIS",
+            help = "only resend messages stored at or after this timestamp"
+        )]
+        begin_timestamp: Option<i64>,
+
+        #[arg(
+            long,
+            value_name = "MILLIS",
+            help = "only resend messages stored at or before this timestamp"
+        )]
+        end_timestamp: Option<i64>,
+
+        #[arg(
+            short = 'n',
+            long,
+            value_name = "COUNT",
+            help = "maximum number of messages the broker resends per request"
+        )]
+        max_count: Option<i32>,
+
+        #[arg(
+            long,
+            value_name = "MILLIS",
+            default_value_t = 30000,
+            help = "request timeout"
+        )]
+        timeout_millis: u64,
+    },
+
+    #[command(
+        arg_required_else_help = true,
+        author = "mxsm",
+        version = "0.2.0",
+        about = "list the messages in the dead letter queue of a consumer group"
+    )]
+    QueryDlqMessages {
+        #[arg(
+            short = 'b',
+            long,
+            value_name = "ADDR",
+            help = "broker address, e.g. 127.0.0.1:10911"
+        )]
+        broker_addr: String,
+
+        #[arg(short = 'g', long, value_name = "GROUP", help = "consumer group")]
+        consumer_group: String,
+
+        #[arg(
+            short = 'q',
+            long,
+            value_name = "QUEUE",
+            help = "dead letter queue id, defaults to 0"
+        )]
+        queue_id: Option<i32>,
+
+        #[arg(
+            short = 'o',
+            long,
+            value_name = "OFFSET",
+            help = "consume queue offset of the first message, defaults to the oldest message"
+        )]
+        offset: Option<i64>,
+
+        #[arg(
+            short = 'n',
+            long,
+            value_name = "COUNT",
+            help = "maximum number of messages to list"
+        )]
+        max_count: Option<i32>,
+
+        #[arg(
+            long,
+            value_name = "MILLIS",
+            default_value_t = 30000,
+            help = "request timeout"
+        )]
+        timeout_millis: u64,
+    },
+
+    #[command(
+        arg_required_else_help = true,
+        author = "mxsm",
+        version = "0.2.0",
+        about = "resend dead letter queue messages of a consumer group to their original topics"
+    )]
+    ResendDlqMessages {
+        #[arg(
+            short = 'b',
+            long,
+            value_name = "ADDR",
+            help = "broker address, e.g. 127.0.0.1:10911"
+        )]
+        broker_addr: String,
+
+        #[arg(short = 'g', long, value_name = "GROUP", help = "consumer group")]
+        consumer_group: String,
+
+        #[arg(
+            short = 'q',
+            long,
+            value_name = "QUEUE",
+            help = "dead letter queue id, defaults to 0"
+        )]
+        queue_id: Option<i32>,
+
+        #[arg(
+            short = 'o',
+            long,
+            value_name = "OFFSETS",
+            value_delimiter = ',',
+            required = true,
+            help = "comma separated consume queue offsets of the messages to resend"
+        )]
+        offsets: Vec<i64>,
+
+        #[arg(
+            long,
+            value_name = "MILLIS",
+            default_value_t = 30000,
+            help = "request timeout"
+        )]
+        timeout_millis: u64,
+    },
+
+    #[command(
+        arg_required_else_help = true,
+        author = "mxsm",
+        version = "0.2.0",
+        about = "list the topics known to a name server"
+    )]
+    TopicList {
+        #[arg(
+            short = 'n',
+            long,
+            value_name = "ADDR",
+            help = "name server address, e.g. 127.0.0.1:9876"
+        )]
+        namesrv_addr: String,
+
+        #[arg(
+            short = 'c',
+            long,
+            value_name = "CLUSTER",
+            help = "only list the topics of this cluster"
+        )]
+        cluster: Option<String>,
+
+        #[arg(
+            long,
+            value_name = "MILLIS",
+            default_value_t = 30000,
+            help = "request timeout"
+        )]
+        timeout_millis: u64,
+    },
+
+    #[command(
+        arg_required_else_help = true,
+        author = "mxsm",
+        version = "0.2.0",
+        about = "export the billed message counts and bytes of a broker per minute, owner, topic \
+                 and group"
+    )]
+    CommercialStats {
+        #[arg(
+            short = 'b',
+            long,
+            value_name = "ADDR",
+            help = "broker address, e.g. 127.0.0.1:10911"
+        )]
+        broker_addr: String,
+
+        #[arg(
+            short = 's',
+            long,
+            value_name = "MILLIS",
+            help = "only the minutes from this timestamp on, defaults to all kept minutes"
+        )]
+        begin_timestamp: Option<i64>,
+
+        #[arg(long, help = "print JSON instead of a table")]
+        json: bool,
+
+        #[arg(
+            long,
+            value_name = "MILLIS",
+            default_value_t = 30000,
+            help = "request timeout"
+        )]
+        timeout_millis: u64,
+    },
+
+    #[command(
+        arg_required_else_help = true,
+        author = "mxsm",
+        version = "0.2.0",
+        about = "copy the consumer offsets of a group to another group"
+    )]
+    CloneGroupOffset {
+        #[arg(
+            short = 'b',
+            long,
+            value_name = "ADDR",
+            help = "broker address, e.g. 127.0.0.1:10911"
+        )]
+        broker_addr: String,
+
+        #[arg(
+            short = 's',
+            long,
+            value_name = "GROUP",
+            help = "group to copy the offsets of"
+        )]
+        src_group: String,
+
+        #[arg(
+            short = 'd',
+            long,
+            value_name = "GROUP",
+            help = "group to copy the offsets to"
+        )]
+        dest_group: String,
+
+        #[arg(
+            short = 't',
+            long,
+            value_name = "TOPIC",
+            help = "only copy the offsets of this topic, defaults to every topic of the source \
+                    group"
+        )]
+        topic: Option<String>,
+
+        #[arg(
+            short = 'o',
+            long,
+            help = "also copy the topics the online consumers of the source group no longer \
+                    subscribe to"
+        )]
+        offline: bool,
+
+        #[arg(
+            long,
+            value_name = "MILLIS",
+            default_value_t = 30000,
+            help = "request timeout"
+        )]
+        timeout_millis: u64,
+    },
+
+    #[command(
+        arg_required_else_help = true,
+        author = "mxsm",
+        version = "0.2.0",
+        about = "show the oldest, newest and last consumed store time of each queue of a topic \
+                 for a consumer group"
+    )]
+    ConsumeTimeSpan {
+        #[arg(
+            short = 'b',
+            long,
+            value_name = "ADDR",
+            help = "broker address, e.g. 127.0.0.1:10911"
+        )]
+        broker_addr: String,
+
+        #[arg(short = 't', long, value_name = "TOPIC", help = "topic")]
+        topic: String,
+
+        #[arg(short = 'g', long, value_name = "GROUP", help = "consumer group")]
+        group: String,
+
+        #[arg(
+            long,
+            value_name = "MILLIS",
+            default_value_t = 30000,
+            help = "request timeout"
+        )]
+        timeout_millis: u64,
+    },
+
+    #[command(
+        arg_required_else_help = true,
+        author = "mxsm",
+        version = "0.2.0",
+        about = "show the send, transaction and consume timeline of a message from the trace topic"
+    )]
+    QueryMessageTrace {
+        #[arg(
+            short = 'b',
+            long,
+            value_name = "ADDRS",
+            value_delimiter = ',',
+            required = true,
+            help = "comma separated addresses of the brokers serving the trace topic"
+        )]
+        broker_addrs: Vec<String>,
+
+        #[arg(
+            short = 'i',
+            long,
+            value_name = "MSG_ID",
+            required_unless_present = "key",
+            conflicts_with = "key",
+            help = "id of the message to trace"
+        )]
+        msg_id: Option<String>,
+
+        #[arg(
+            short = 'k',
+            long,
+            value_name = "KEY",
+            help = "key of the messages to trace"
+        )]
+        key: Option<String>,
+
+        #[arg(
+            short = 't',
+            long,
+            value_name = "TOPIC",
+            help = "trace topic, defaults to RMQ_SYS_TRACE_TOPIC"
+        )]
+        trace_topic: Option<String>,
+
+        #[arg(
+            long,
+            value_name = "MILLIS",
+            help = "only read trace records stored at or after this timestamp"
+        )]
+        begin_timestamp: Option<i64>,
+
+        #[arg(
+            long,
+            value_name = "MILLIS",
+            help = "only read trace records stored at or before this timestamp"
+        )]
+        end_timestamp: Option<i64>,
+
+        #[arg(
+            short = 'n',
+            long,
+            value_name = "COUNT",
+            help = "maximum number of trace messages each broker returns"
+        )]
+        max_count: Option<i32>,
+
+        #[arg(
+            long,
+            value_name = "MILLIS",
+            default_value_t = 30000,
+            help = "request timeout"
+        )]
+        timeout_millis: u64,
+    },
 }
@@ -0,0 +1,76 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use rocketmq_remoting::code::request_code::RequestCode;
+use rocketmq_remoting::protocol::body::commercial_stats_body::CommercialStatsBody;
+use rocketmq_remoting::protocol::body::commercial_stats_body::CommercialStatsItem;
+use rocketmq_remoting::protocol::header::get_commercial_stats_request_header::GetCommercialStatsRequestHeader;
+use rocketmq_remoting::protocol::RemotingSerializable;
+use tabled::Table;
+use tabled::Tabled;
+
+use crate::dlq_messages::invoke;
+
+/// Prints the billed message counts and bytes per minute, owner, topic and group of the broker
+/// at `broker_addr`, as a table or as JSON for billing systems.
+pub fn print_commercial_stats(
+    broker_addr: String,
+    begin_timestamp: Option<i64>,
+    json: bool,
+    timeout_millis: u64,
+) {
+    let Some(body) = invoke::<CommercialStatsBody>(
+        broker_addr,
+        RequestCode::GetCommercialStats,
+        GetCommercialStatsRequestHeader { begin_timestamp },
+        timeout_millis,
+    ) else {
+        return;
+    };
+    if json {
+        println!("{}", body.to_json_pretty());
+    } else {
+        println!(
+            "{}",
+            Table::new(body.items.iter().map(CommercialStatsPrint::from))
+        );
+    }
+}
+
+#[derive(Tabled)]
+struct CommercialStatsPrint {
+    minute: u64,
+    owner: String,
+    topic: String,
+    group: String,
+    stats_type: String,
+    times: i64,
+    size: i64,
+}
+
+impl From<&CommercialStatsItem> for CommercialStatsPrint {
+    fn from(item: &CommercialStatsItem) -> Self {
+        Self {
+            minute: item.minute_timestamp,
+            owner: item.owner.clone(),
+            topic: item.topic.clone(),
+            group: item.group.clone(),
+            stats_type: item.stats_type.clone(),
+            times: item.times,
+            size: item.size,
+        }
+    }
+}
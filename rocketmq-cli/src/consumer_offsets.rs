@@ -0,0 +1,96 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use rocketmq_remoting::code::request_code::RequestCode;
+use rocketmq_remoting::protocol::body::query_consume_time_span_body::QueryConsumeTimeSpanBody;
+use rocketmq_remoting::protocol::body::query_consume_time_span_body::QueueTimeSpan;
+use rocketmq_remoting::protocol::header::clone_group_offset_request_header::CloneGroupOffsetRequestHeader;
+use rocketmq_remoting::protocol::header::query_consume_time_span_request_header::QueryConsumeTimeSpanRequestHeader;
+use tabled::Table;
+use tabled::Tabled;
+
+use crate::dlq_messages::invoke;
+use crate::dlq_messages::invoke_command;
+
+/// Copies the consumer offsets of one group to another at the broker at `broker_addr`, e.g.
+/// to start a new group where an existing one is.
+pub fn clone_group_offset(
+    broker_addr: String,
+    request_header: CloneGroupOffsetRequestHeader,
+    timeout_millis: u64,
+) {
+    let src_group = request_header.src_group.clone();
+    let dest_group = request_header.dest_group.clone();
+    if invoke_command(
+        broker_addr.clone(),
+        RequestCode::CloneGroupOffset,
+        request_header,
+        timeout_millis,
+    )
+    .is_some()
+    {
+        println!(
+            "cloned the offsets of group {} to group {} at broker {}",
+            src_group, dest_group, broker_addr
+        );
+    }
+}
+
+/// Prints, per queue of a topic, the store times of the oldest, newest and last consumed
+/// message of a consumer group and how long its next message has been waiting.
+pub fn print_consume_time_span(
+    broker_addr: String,
+    request_header: QueryConsumeTimeSpanRequestHeader,
+    timeout_millis: u64,
+) {
+    let Some(mut body) = invoke::<QueryConsumeTimeSpanBody>(
+        broker_addr,
+        RequestCode::QueryConsumeTimeSpan,
+        request_header,
+        timeout_millis,
+    ) else {
+        return;
+    };
+    body.consume_time_span_set
+        .sort_by(|a, b| a.message_queue.cmp(&b.message_queue));
+    println!(
+        "{}",
+        Table::new(body.consume_time_span_set.iter().map(TimeSpanPrint::from))
+    );
+}
+
+#[derive(Tabled)]
+struct TimeSpanPrint {
+    broker_name: String,
+    queue_id: i32,
+    min_time_stamp: i64,
+    max_time_stamp: i64,
+    consume_time_stamp: i64,
+    delay_millis: i64,
+}
+
+impl From<&QueueTimeSpan> for TimeSpanPrint {
+    fn from(span: &QueueTimeSpan) -> Self {
+        Self {
+            broker_name: span.message_queue.get_broker_name().to_string(),
+            queue_id: span.message_queue.get_queue_id(),
+            min_time_stamp: span.min_time_stamp,
+            max_time_stamp: span.max_time_stamp,
+            consume_time_stamp: span.consume_time_stamp,
+            delay_millis: span.delay_time,
+        }
+    }
+}
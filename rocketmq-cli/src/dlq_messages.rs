@@ -0,0 +1,184 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::sync::Arc;
+
+use rocketmq_remoting::clients::rocketmq_default_impl::RocketmqDefaultClient;
+use rocketmq_remoting::clients::RemotingClient;
+use rocketmq_remoting::code::request_code::RequestCode;
+use rocketmq_remoting::code::response_code::ResponseCode;
+use rocketmq_remoting::protocol::body::dlq_messages_body::DlqMessage;
+use rocketmq_remoting::protocol::body::dlq_messages_body::DlqMessagesBody;
+use rocketmq_remoting::protocol::body::resend_dlq_messages_response_body::ResendDlqMessagesResponseBody;
+use rocketmq_remoting::protocol::command_custom_header::CommandCustomHeader;
+use rocketmq_remoting::protocol::header::query_dlq_messages_request_header::QueryDlqMessagesRequestHeader;
+use rocketmq_remoting::protocol::header::resend_dlq_messages_request_header::ResendDlqMessagesRequestHeader;
+use rocketmq_remoting::protocol::remoting_command::RemotingCommand;
+use rocketmq_remoting::protocol::RemotingDeserializable;
+use rocketmq_remoting::request_processor::default_request_processor::DefaultRemotingRequestProcessor;
+use rocketmq_remoting::runtime::config::client_config::TokioClientConfig;
+use tabled::Table;
+use tabled::Tabled;
+
+/// Prints a page of the dead letter queue of a consumer group at the broker at `broker_addr`.
+pub fn query_dlq_messages(
+    broker_addr: String,
+    request_header: QueryDlqMessagesRequestHeader,
+    timeout_millis: u64,
+) {
+    let Some(body) = invoke::<DlqMessagesBody>(
+        broker_addr,
+        RequestCode::QueryDlqMessages,
+        request_header,
+        timeout_millis,
+    ) else {
+        return;
+    };
+    println!(
+        "{}",
+        Table::new(body.messages.iter().map(DlqMessagePrint::from))
+    );
+    println!(
+        "queue offsets [{}, {}), next page starts at offset {}{}",
+        body.min_offset,
+        body.max_offset,
+        body.next_offset,
+        if body.has_more() {
+            ""
+        } else {
+            ", no more messages"
+        }
+    );
+}
+
+/// Resends the selected dead letter queue messages of a consumer group to their original
+/// topics and prints which offsets were resent.
+pub fn resend_dlq_messages(
+    broker_addr: String,
+    request_header: ResendDlqMessagesRequestHeader,
+    timeout_millis: u64,
+) {
+    let Some(body) = invoke::<ResendDlqMessagesResponseBody>(
+        broker_addr,
+        RequestCode::ResendDlqMessages,
+        request_header,
+        timeout_millis,
+    ) else {
+        return;
+    };
+    let print = [
+        ("resent", body.resent),
+        ("not found", body.not_found),
+        ("failed", body.failed),
+    ]
+    .into_iter()
+    .map(|(result, offsets)| ResendDlqPrint {
+        result,
+        count: offsets.len(),
+        offsets: offsets
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(","),
+    });
+    println!("{}", Table::new(print));
+}
+
+pub(crate) fn invoke<T: RemotingDeserializable<Output = T>>(
+    broker_addr: String,
+    request_code: RequestCode,
+    request_header: impl CommandCustomHeader + Send + Sync + 'static,
+    timeout_millis: u64,
+) -> Option<T> {
+    let response = invoke_command(broker_addr, request_code, request_header, timeout_millis)?;
+    let body = response.get_body().and_then(|body| T::decode(body).ok());
+    if body.is_none() {
+        println!("broker answered an invalid response");
+    }
+    body
+}
+
+/// Sends the request to the broker and returns its response when it succeeded, printing why
+/// it did not otherwise.
+pub(crate) fn invoke_command(
+    broker_addr: String,
+    request_code: RequestCode,
+    request_header: impl CommandCustomHeader + Send + Sync + 'static,
+    timeout_millis: u64,
+) -> Option<RemotingCommand> {
+    let client = RocketmqDefaultClient::new(
+        Arc::new(TokioClientConfig::default()),
+        DefaultRemotingRequestProcessor,
+    );
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let request = RemotingCommand::create_request_command(request_code, request_header);
+    let response = match runtime.block_on(client.invoke_async(
+        Some(broker_addr.clone()),
+        request,
+        timeout_millis,
+    )) {
+        Ok(response) => response,
+        Err(e) => {
+            println!("request to {} failed: {}", broker_addr, e);
+            return None;
+        }
+    };
+    if response.code() != ResponseCode::Success as i32 {
+        println!(
+            "broker rejected the request, code: {}, remark: {}",
+            response.code(),
+            response.remark().cloned().unwrap_or_default()
+        );
+        return None;
+    }
+    Some(response)
+}
+
+#[derive(Tabled)]
+struct DlqMessagePrint {
+    offset: i64,
+    msg_id: String,
+    origin_msg_id: String,
+    original_topic: String,
+    reconsume_times: i32,
+    store_timestamp: i64,
+    tags: String,
+    keys: String,
+    body_size: i32,
+}
+
+impl From<&DlqMessage> for DlqMessagePrint {
+    fn from(msg: &DlqMessage) -> Self {
+        Self {
+            offset: msg.queue_offset,
+            msg_id: msg.msg_id.clone(),
+            origin_msg_id: msg.origin_msg_id.clone().unwrap_or_default(),
+            original_topic: msg.original_topic.clone().unwrap_or_default(),
+            reconsume_times: msg.reconsume_times,
+            store_timestamp: msg.store_timestamp,
+            tags: msg.tags.clone().unwrap_or_default(),
+            keys: msg.keys.clone().unwrap_or_default(),
+            body_size: msg.body_size,
+        }
+    }
+}
+
+#[derive(Tabled)]
+struct ResendDlqPrint {
+    result: &'static str,
+    count: usize,
+    offsets: String,
+}
@@ -16,4 +16,10 @@
  */
 
 pub mod command_line;
+pub mod commercial_stats;
+pub mod consumer_offsets;
 pub mod content_show;
+pub mod dlq_messages;
+pub mod message_trace;
+pub mod resend_messages;
+pub mod topic_list;
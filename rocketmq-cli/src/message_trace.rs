@@ -0,0 +1,96 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use rocketmq_client::trace::trace_view::TraceFilter;
+use rocketmq_client::trace::trace_view::TraceView;
+use rocketmq_common::UtilAll::time_millis_to_human_string2;
+use rocketmq_remoting::code::request_code::RequestCode;
+use rocketmq_remoting::protocol::body::query_message_trace_body::QueryMessageTraceBody;
+use rocketmq_remoting::protocol::header::query_message_trace_request_header::QueryMessageTraceRequestHeader;
+use tabled::Table;
+use tabled::Tabled;
+
+use crate::dlq_messages::invoke;
+
+/// Reads the trace records of the messages matching `filter` from the trace topic of every
+/// broker in `broker_addrs` and prints them as one send, transaction and consume timeline.
+pub fn print_message_trace(
+    broker_addrs: Vec<String>,
+    request_header: QueryMessageTraceRequestHeader,
+    filter: &TraceFilter,
+    timeout_millis: u64,
+) {
+    let mut records = Vec::new();
+    for broker_addr in broker_addrs {
+        let Some(body) = invoke::<QueryMessageTraceBody>(
+            broker_addr.clone(),
+            RequestCode::QueryMessageTrace,
+            request_header.clone(),
+            timeout_millis,
+        ) else {
+            continue;
+        };
+        if body.truncated {
+            println!(
+                "{} returned the first {} trace messages only, narrow the time window to see the \
+                 rest",
+                broker_addr,
+                body.records.len()
+            );
+        }
+        records.extend(body.records);
+    }
+    let timeline = TraceView::timeline(&records, filter);
+    if timeline.is_empty() {
+        match filter {
+            TraceFilter::MsgId(msg_id) => println!("no trace found for message id {}", msg_id),
+            TraceFilter::Key(key) => println!("no trace found for message key {}", key),
+        }
+        return;
+    }
+    println!("{}", Table::new(timeline.iter().map(TraceViewPrint::from)));
+}
+
+#[derive(Tabled)]
+struct TraceViewPrint {
+    time: String,
+    step: String,
+    group: String,
+    client_host: String,
+    store_host: String,
+    topic: String,
+    msg_id: String,
+    cost_ms: i32,
+    status: String,
+    detail: String,
+}
+
+impl From<&TraceView> for TraceViewPrint {
+    fn from(view: &TraceView) -> Self {
+        Self {
+            time: time_millis_to_human_string2(view.time_stamp as i64),
+            step: view.trace_type.to_string(),
+            group: view.group_name.clone(),
+            client_host: view.client_host.clone(),
+            store_host: view.store_host.clone(),
+            topic: view.topic.clone(),
+            msg_id: view.msg_id.clone(),
+            cost_ms: view.cost_time,
+            status: view.status.clone(),
+            detail: view.detail.clone(),
+        }
+    }
+}
@@ -0,0 +1,123 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::sync::Arc;
+
+use rocketmq_remoting::clients::rocketmq_default_impl::RocketmqDefaultClient;
+use rocketmq_remoting::clients::RemotingClient;
+use rocketmq_remoting::code::request_code::RequestCode;
+use rocketmq_remoting::code::response_code::ResponseCode;
+use rocketmq_remoting::protocol::body::resend_commit_log_messages_response_body::ResendCommitLogMessagesResponseBody;
+use rocketmq_remoting::protocol::header::resend_commit_log_messages_request_header::ResendCommitLogMessagesRequestHeader;
+use rocketmq_remoting::protocol::remoting_command::RemotingCommand;
+use rocketmq_remoting::protocol::RemotingDeserializable;
+use rocketmq_remoting::request_processor::default_request_processor::DefaultRemotingRequestProcessor;
+use rocketmq_remoting::runtime::config::client_config::TokioClientConfig;
+use tabled::Table;
+use tabled::Tabled;
+
+/// Asks the broker at `broker_addr` to resend the messages selected by `request_header`,
+/// continuing from where each round stopped until the whole range has been scanned.
+pub fn resend_messages(
+    broker_addr: String,
+    request_header: ResendCommitLogMessagesRequestHeader,
+    timeout_millis: u64,
+) {
+    let client = RocketmqDefaultClient::new(
+        Arc::new(TokioClientConfig::default()),
+        DefaultRemotingRequestProcessor,
+    );
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let total = runtime.block_on(resend_all(
+        &client,
+        broker_addr,
+        request_header,
+        timeout_millis,
+    ));
+    println!("{}", Table::new([total]));
+}
+
+async fn resend_all(
+    client: &RocketmqDefaultClient,
+    broker_addr: String,
+    mut request_header: ResendCommitLogMessagesRequestHeader,
+    timeout_millis: u64,
+) -> ResendPrint {
+    let mut total = ResendPrint::default();
+    loop {
+        let request = RemotingCommand::create_request_command(
+            RequestCode::ResendCommitLogMessages,
+            request_header.clone(),
+        );
+        let response = match client
+            .invoke_async(Some(broker_addr.clone()), request, timeout_millis)
+            .await
+        {
+            Ok(response) => response,
+            Err(e) => {
+                println!("resend request to {} failed: {}", broker_addr, e);
+                break;
+            }
+        };
+        if response.code() != ResponseCode::Success as i32 {
+            println!(
+                "broker rejected the resend request, code: {}, remark: {}",
+                response.code(),
+                response.remark().cloned().unwrap_or_default()
+            );
+            break;
+        }
+        let Some(body) = response
+            .get_body()
+            .and_then(|body| ResendCommitLogMessagesResponseBody::decode(body).ok())
+        else {
+            println!("broker answered an invalid resend response");
+            break;
+        };
+        total.add(&body);
+        println!(
+            "resent {} of {} matched messages, commit log offset {} of {}",
+            body.resent, body.matched, body.next_offset, body.end_offset
+        );
+        let stalled = request_header
+            .begin_offset
+            .is_some_and(|begin_offset| body.next_offset <= begin_offset);
+        if body.is_finished() || stalled {
+            break;
+        }
+        request_header.begin_offset = Some(body.next_offset);
+    }
+    total
+}
+
+#[derive(Tabled, Default)]
+struct ResendPrint {
+    scanned: u64,
+    matched: u64,
+    resent: u64,
+    failed: u64,
+    next_offset: i64,
+}
+
+impl ResendPrint {
+    fn add(&mut self, body: &ResendCommitLogMessagesResponseBody) {
+        self.scanned += body.scanned;
+        self.matched += body.matched;
+        self.resent += body.resent;
+        self.failed += body.failed;
+        self.next_offset = body.next_offset;
+    }
+}
@@ -0,0 +1,85 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::sync::Arc;
+
+use rocketmq_remoting::clients::rocketmq_default_impl::RocketmqDefaultClient;
+use rocketmq_remoting::clients::RemotingClient;
+use rocketmq_remoting::code::request_code::RequestCode;
+use rocketmq_remoting::code::response_code::ResponseCode;
+use rocketmq_remoting::protocol::body::topic::topic_list::TopicList;
+use rocketmq_remoting::protocol::header::namesrv::topic_operation_header::GetTopicListRequestHeader;
+use rocketmq_remoting::protocol::header::namesrv::topic_operation_header::GetTopicsByClusterRequestHeader;
+use rocketmq_remoting::protocol::remoting_command::RemotingCommand;
+use rocketmq_remoting::request_processor::default_request_processor::DefaultRemotingRequestProcessor;
+use rocketmq_remoting::runtime::config::client_config::TokioClientConfig;
+
+/// Prints the topics known to the name server at `namesrv_addr`, or only the ones of
+/// `cluster`, one per line and sorted.
+pub fn print_topic_list(namesrv_addr: String, cluster: Option<String>, timeout_millis: u64) {
+    let client = RocketmqDefaultClient::new(
+        Arc::new(TokioClientConfig::default()),
+        DefaultRemotingRequestProcessor,
+    );
+    let request = match cluster {
+        Some(cluster) => RemotingCommand::create_request_command(
+            RequestCode::GetTopicsByCluster,
+            GetTopicsByClusterRequestHeader {
+                cluster,
+                compressed: Some(true),
+            },
+        ),
+        None => RemotingCommand::create_request_command(
+            RequestCode::GetAllTopicListFromNameserver,
+            GetTopicListRequestHeader::new(true),
+        ),
+    };
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let response = match runtime.block_on(client.invoke_async(
+        Some(namesrv_addr.clone()),
+        request,
+        timeout_millis,
+    )) {
+        Ok(response) => response,
+        Err(e) => {
+            println!("request to {} failed: {}", namesrv_addr, e);
+            return;
+        }
+    };
+    if response.code() != ResponseCode::Success as i32 {
+        println!(
+            "name server rejected the request, code: {}, remark: {}",
+            response.code(),
+            response.remark().cloned().unwrap_or_default()
+        );
+        return;
+    }
+    let compressed = response
+        .ext_fields()
+        .and_then(|fields| fields.get(GetTopicListRequestHeader::COMPRESSED))
+        .is_some_and(|compressed| compressed == "true");
+    let Some(mut topic_list) = response
+        .get_body()
+        .and_then(|body| TopicList::decode_body(body, compressed))
+    else {
+        println!("name server answered an invalid topic list");
+        return;
+    };
+    topic_list.topic_list.sort();
+    for topic in topic_list.topic_list {
+        println!("{}", topic);
+    }
+}
@@ -0,0 +1,130 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use rocketmq_remoting::error::Error;
+use rocketmq_remoting::protocol::remoting_command::RemotingCommand;
+use rocketmq_remoting::runtime::RPCHook;
+
+use crate::acl::acl_utils;
+use crate::acl::credentials_provider::CredentialsProvider;
+use crate::acl::credentials_provider::StaticCredentialsProvider;
+use crate::acl::session_credentials::SessionCredentials;
+use crate::acl::session_credentials::ACCESS_KEY;
+use crate::acl::session_credentials::SECURITY_TOKEN;
+use crate::acl::session_credentials::SIGNATURE;
+
+/// Signs every request with the credentials its provider answers at that moment, so callers
+/// keep working across credential rotation.
+///
+/// The access key and the optional security token are added to the ext fields first and are
+/// covered by the signature, which is computed over the sorted ext field values and the body.
+pub struct AclClientRPCHook {
+    provider: Arc<dyn CredentialsProvider>,
+}
+
+impl AclClientRPCHook {
+    pub fn new(provider: Arc<dyn CredentialsProvider>) -> Self {
+        Self { provider }
+    }
+
+    pub fn with_credentials(credentials: SessionCredentials) -> Self {
+        Self::new(Arc::new(StaticCredentialsProvider::new(credentials)))
+    }
+
+    pub fn provider(&self) -> &Arc<dyn CredentialsProvider> {
+        &self.provider
+    }
+}
+
+impl RPCHook for AclClientRPCHook {
+    fn do_before_request(
+        &self,
+        _remote_addr: SocketAddr,
+        request: &mut RemotingCommand,
+    ) -> rocketmq_remoting::Result<()> {
+        let credentials = self.provider.get_credentials().map_err(|e| {
+            Error::RemotingCommandException(format!("get acl credentials failed: {}", e))
+        })?;
+        request.add_ext_field(ACCESS_KEY, credentials.access_key);
+        if let Some(security_token) = credentials.security_token {
+            request.add_ext_field(SECURITY_TOKEN, security_token);
+        }
+        let content = acl_utils::combine_request_content(request);
+        let signature = acl_utils::cal_signature(&content, credentials.secret_key.as_str());
+        request.add_ext_field(SIGNATURE, signature);
+        Ok(())
+    }
+
+    fn do_after_response(
+        &self,
+        _remote_addr: SocketAddr,
+        _response: &mut RemotingCommand,
+    ) -> rocketmq_remoting::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use parking_lot::Mutex;
+
+    use super::*;
+    use crate::Result;
+
+    struct SwappableProvider(Mutex<SessionCredentials>);
+
+    impl CredentialsProvider for SwappableProvider {
+        fn get_credentials(&self) -> Result<SessionCredentials> {
+            Ok(self.0.lock().clone())
+        }
+    }
+
+    #[test]
+    fn signs_with_current_credentials() {
+        let provider = Arc::new(SwappableProvider(Mutex::new(SessionCredentials::new(
+            "ak1", "sk1",
+        ))));
+        let hook = AclClientRPCHook::new(provider.clone());
+        let addr: SocketAddr = "127.0.0.1:10911".parse().unwrap();
+        let sign = |hook: &AclClientRPCHook| {
+            let mut request =
+                RemotingCommand::create_remoting_command(10).set_body(Some("body".as_bytes()));
+            request.add_ext_field("topic", "TopicTest");
+            hook.do_before_request(addr, &mut request).unwrap();
+            request.ext_fields().unwrap().clone()
+        };
+
+        let fields = sign(&hook);
+        assert_eq!(fields.get(ACCESS_KEY).unwrap(), "ak1");
+        assert!(!fields.contains_key(SECURITY_TOKEN));
+        // Values ordered by key: AccessKey, topic.
+        assert_eq!(
+            fields.get(SIGNATURE).unwrap(),
+            &acl_utils::cal_signature(b"ak1TopicTestbody", "sk1")
+        );
+
+        *provider.0.lock() = SessionCredentials::new("ak2", "sk2").with_security_token("token");
+        let fields = sign(&hook);
+        assert_eq!(fields.get(ACCESS_KEY).unwrap(), "ak2");
+        assert_eq!(
+            fields.get(SIGNATURE).unwrap(),
+            &acl_utils::cal_signature(b"ak2tokenTopicTestbody", "sk2")
+        );
+    }
+}
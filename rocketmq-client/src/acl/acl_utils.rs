@@ -0,0 +1,97 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::collections::BTreeMap;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use hmac::Hmac;
+use hmac::Mac;
+use rocketmq_remoting::protocol::remoting_command::RemotingCommand;
+use sha1::Sha1;
+
+use crate::acl::session_credentials::SIGNATURE;
+
+/// The bytes the signature is computed over: the values of the ext fields ordered by key,
+/// skipping the signature itself, followed by the body.
+pub(crate) fn combine_request_content(request: &RemotingCommand) -> Vec<u8> {
+    let fields: BTreeMap<&String, &String> = request
+        .ext_fields()
+        .into_iter()
+        .flatten()
+        .filter(|(key, _)| key.as_str() != SIGNATURE)
+        .collect();
+    let mut content: Vec<u8> = fields
+        .values()
+        .flat_map(|value| value.as_bytes())
+        .copied()
+        .collect();
+    if let Some(body) = request.body() {
+        content.extend_from_slice(body);
+    }
+    content
+}
+
+/// Base64 encoded HmacSHA1 of `data`, the signature format the broker's ACL checks.
+pub(crate) fn cal_signature(data: &[u8], secret_key: &str) -> String {
+    STANDARD.encode(hmac_sha1(secret_key.as_bytes(), data))
+}
+
+fn hmac_sha1(key: &[u8], data: &[u8]) -> [u8; 20] {
+    // HMAC accepts keys of any length
+    let mut mac = Hmac::<Sha1>::new_from_slice(key).unwrap();
+    mac.update(data);
+    mac.finalize().into_bytes().into()
+}
+
+#[cfg(test)]
+mod tests {
+    use sha1::Digest;
+
+    use super::*;
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    #[test]
+    fn sha1_and_hmac_sha1_vectors() {
+        assert_eq!(
+            hex(&Sha1::digest(b"abc")),
+            "a9993e364706816aba3e25717850c26c9cd0d89d"
+        );
+        assert_eq!(
+            hex(&Sha1::digest([b'a'; 1000])),
+            "291e9a6c66994949b57ba5e650361e98fc36b1ba"
+        );
+        // RFC 2202 test cases 2 and 6.
+        assert_eq!(
+            hex(&hmac_sha1(b"Jefe", b"what do ya want for nothing?")),
+            "effcdf6ae5eb2fa2d27416d5f184df9c259a7c79"
+        );
+        assert_eq!(
+            hex(&hmac_sha1(
+                &[0xaa; 80],
+                b"Test Using Larger Than Block-Size Key - Hash Key First"
+            )),
+            "aa4ae5e15272d00e95705637ce8a3b55ed402112"
+        );
+        assert_eq!(
+            cal_signature(b"what do ya want for nothing?", "Jefe"),
+            "7/zfauXrL6LSdBbV8YTfnCWafHk="
+        );
+    }
+}
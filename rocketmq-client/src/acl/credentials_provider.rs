@@ -0,0 +1,75 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::env;
+
+use crate::acl::session_credentials::SessionCredentials;
+use crate::error::MQClientError;
+use crate::Result;
+
+pub const ACCESS_KEY_ENV: &str = "ROCKETMQ_ACCESS_KEY";
+pub const SECRET_KEY_ENV: &str = "ROCKETMQ_SECRET_KEY";
+pub const SECURITY_TOKEN_ENV: &str = "ROCKETMQ_SECURITY_TOKEN";
+
+/// Source of the credentials the ACL hook signs requests with.
+///
+/// The hook asks the provider on every request instead of keeping a copy, so a provider whose
+/// credentials change over time (a reloaded file, a token from an STS) is picked up by clients
+/// which are already running without rebuilding them.
+pub trait CredentialsProvider: Send + Sync {
+    /// Returns the credentials to sign the next request with. Called once per request, so
+    /// implementations should answer from a cache and only go to their source when needed.
+    fn get_credentials(&self) -> Result<SessionCredentials>;
+}
+
+/// Always answers the same credentials.
+pub struct StaticCredentialsProvider {
+    credentials: SessionCredentials,
+}
+
+impl StaticCredentialsProvider {
+    pub fn new(credentials: SessionCredentials) -> Self {
+        Self { credentials }
+    }
+}
+
+impl CredentialsProvider for StaticCredentialsProvider {
+    fn get_credentials(&self) -> Result<SessionCredentials> {
+        Ok(self.credentials.clone())
+    }
+}
+
+/// Reads `ROCKETMQ_ACCESS_KEY`, `ROCKETMQ_SECRET_KEY` and the optional `ROCKETMQ_SECURITY_TOKEN`
+/// on every call.
+#[derive(Default)]
+pub struct EnvironmentCredentialsProvider;
+
+impl CredentialsProvider for EnvironmentCredentialsProvider {
+    fn get_credentials(&self) -> Result<SessionCredentials> {
+        let read = |key: &str| env::var(key).ok().filter(|value| !value.is_empty());
+        match (read(ACCESS_KEY_ENV), read(SECRET_KEY_ENV)) {
+            (Some(access_key), Some(secret_key)) => Ok(SessionCredentials {
+                access_key,
+                secret_key,
+                security_token: read(SECURITY_TOKEN_ENV),
+            }),
+            _ => Err(MQClientError::IllegalState(format!(
+                "environment variables {} and {} must be set",
+                ACCESS_KEY_ENV, SECRET_KEY_ENV
+            ))),
+        }
+    }
+}
@@ -0,0 +1,171 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+use std::time::Instant;
+use std::time::SystemTime;
+
+use parking_lot::Mutex;
+use rocketmq_common::utils::parse_config_file::parse_properties;
+use serde::Deserialize;
+use serde::Serialize;
+use tracing::info;
+use tracing::warn;
+
+use crate::acl::credentials_provider::CredentialsProvider;
+use crate::acl::session_credentials::SessionCredentials;
+use crate::error::MQClientError;
+use crate::Result;
+
+const DEFAULT_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CredentialsFile {
+    access_key: String,
+    secret_key: String,
+    security_token: String,
+}
+
+#[derive(Default)]
+struct State {
+    credentials: Option<SessionCredentials>,
+    modified: Option<SystemTime>,
+    last_check: Option<Instant>,
+}
+
+/// Reads the credentials from a properties file (`accessKey`, `secretKey` and the optional
+/// `securityToken`) and reloads it when its modification time changes.
+///
+/// The file is checked at most once per `check_interval`. A file which cannot be read or
+/// parsed after a rotation keeps the previously loaded credentials in use.
+pub struct FileCredentialsProvider {
+    path: PathBuf,
+    check_interval: Duration,
+    state: Mutex<State>,
+}
+
+impl FileCredentialsProvider {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self::with_check_interval(path, DEFAULT_CHECK_INTERVAL)
+    }
+
+    pub fn with_check_interval(path: impl Into<PathBuf>, check_interval: Duration) -> Self {
+        Self {
+            path: path.into(),
+            check_interval,
+            state: Mutex::new(State::default()),
+        }
+    }
+
+    fn load(&self) -> Result<SessionCredentials> {
+        let content = fs::read_to_string(&self.path).map_err(|e| {
+            MQClientError::IllegalState(format!(
+                "read credentials file {:?} failed: {}",
+                self.path, e
+            ))
+        })?;
+        let file = parse_properties::<CredentialsFile>(content.as_str()).map_err(|e| {
+            MQClientError::IllegalState(format!(
+                "parse credentials file {:?} failed: {}",
+                self.path, e
+            ))
+        })?;
+        let credentials = SessionCredentials {
+            access_key: file.access_key,
+            secret_key: file.secret_key,
+            security_token: Some(file.security_token).filter(|token| !token.is_empty()),
+        };
+        if credentials.is_empty() {
+            return Err(MQClientError::IllegalState(format!(
+                "credentials file {:?} must contain accessKey and secretKey",
+                self.path
+            )));
+        }
+        Ok(credentials)
+    }
+}
+
+impl CredentialsProvider for FileCredentialsProvider {
+    fn get_credentials(&self) -> Result<SessionCredentials> {
+        let mut state = self.state.lock();
+        let due = state
+            .last_check
+            .is_none_or(|last_check| last_check.elapsed() >= self.check_interval);
+        if due {
+            state.last_check = Some(Instant::now());
+            let modified = fs::metadata(&self.path)
+                .and_then(|metadata| metadata.modified())
+                .ok();
+            if state.credentials.is_none() || modified != state.modified {
+                match self.load() {
+                    Ok(credentials) => {
+                        if state.credentials.is_some() {
+                            info!("credentials file {:?} changed, reloaded", self.path);
+                        }
+                        state.credentials = Some(credentials);
+                        state.modified = modified;
+                    }
+                    Err(e) if state.credentials.is_some() => {
+                        warn!("{}, keep using the previously loaded credentials", e);
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+        state.credentials.clone().ok_or_else(|| {
+            MQClientError::IllegalState(format!("no credentials loaded from {:?}", self.path))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reloads_rotated_file() {
+        let path = std::env::temp_dir().join(format!(
+            "rocketmq-credentials-{}.properties",
+            std::process::id()
+        ));
+        fs::write(&path, "accessKey=ak1\nsecretKey=sk1\n").unwrap();
+        let provider = FileCredentialsProvider::with_check_interval(&path, Duration::ZERO);
+        assert_eq!(
+            provider.get_credentials().unwrap(),
+            SessionCredentials::new("ak1", "sk1")
+        );
+
+        fs::write(&path, "accessKey=ak2\nsecretKey=sk2\nsecurityToken=token\n").unwrap();
+        // Bump the modification time explicitly, file systems with coarse timestamps would
+        // otherwise not tell the two writes apart.
+        let file = fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(1))
+            .unwrap();
+        assert_eq!(
+            provider.get_credentials().unwrap(),
+            SessionCredentials::new("ak2", "sk2").with_security_token("token")
+        );
+
+        fs::write(&path, "accessKey=ak3\n").unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(2))
+            .unwrap();
+        assert_eq!(provider.get_credentials().unwrap().access_key, "ak2");
+        fs::remove_file(&path).unwrap();
+    }
+}
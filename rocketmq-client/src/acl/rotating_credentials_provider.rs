@@ -0,0 +1,146 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::time::Duration;
+
+use parking_lot::Mutex;
+use rocketmq_common::TimeUtils::get_current_millis;
+use tracing::warn;
+
+use crate::acl::credentials_provider::CredentialsProvider;
+use crate::acl::session_credentials::SessionCredentials;
+use crate::Result;
+
+const DEFAULT_REFRESH_AHEAD: Duration = Duration::from_secs(60);
+
+/// Credentials paired with the epoch millis at which they stop being accepted.
+#[derive(Clone, Debug)]
+pub struct ExpiringCredentials {
+    pub credentials: SessionCredentials,
+    pub expiration_millis: u64,
+}
+
+type CredentialsFetcher = dyn Fn() -> Result<ExpiringCredentials> + Send + Sync;
+
+/// Caches short lived credentials, e.g. a security token from an STS, and fetches new ones
+/// `refresh_ahead` before the cached ones expire.
+///
+/// When a refresh fails the cached credentials stay in use until they actually expire, so a
+/// briefly unavailable token service does not fail requests right away.
+pub struct RotatingCredentialsProvider {
+    fetcher: Box<CredentialsFetcher>,
+    refresh_ahead: Duration,
+    cached: Mutex<Option<ExpiringCredentials>>,
+}
+
+impl RotatingCredentialsProvider {
+    pub fn new<F>(fetcher: F) -> Self
+    where
+        F: Fn() -> Result<ExpiringCredentials> + Send + Sync + 'static,
+    {
+        Self::with_refresh_ahead(fetcher, DEFAULT_REFRESH_AHEAD)
+    }
+
+    pub fn with_refresh_ahead<F>(fetcher: F, refresh_ahead: Duration) -> Self
+    where
+        F: Fn() -> Result<ExpiringCredentials> + Send + Sync + 'static,
+    {
+        Self {
+            fetcher: Box::new(fetcher),
+            refresh_ahead,
+            cached: Mutex::new(None),
+        }
+    }
+}
+
+impl CredentialsProvider for RotatingCredentialsProvider {
+    fn get_credentials(&self) -> Result<SessionCredentials> {
+        let mut cached = self.cached.lock();
+        let now = get_current_millis();
+        let refresh_at = |value: &ExpiringCredentials| {
+            value
+                .expiration_millis
+                .saturating_sub(self.refresh_ahead.as_millis() as u64)
+        };
+        if let Some(value) = cached.as_ref().filter(|value| now < refresh_at(value)) {
+            return Ok(value.credentials.clone());
+        }
+        match (self.fetcher)() {
+            Ok(value) => {
+                let credentials = value.credentials.clone();
+                *cached = Some(value);
+                Ok(credentials)
+            }
+            Err(e) => match cached
+                .as_ref()
+                .filter(|value| now < value.expiration_millis)
+            {
+                Some(value) => {
+                    warn!(
+                        "refresh credentials failed, keep using the cached ones until {}: {}",
+                        value.expiration_millis, e
+                    );
+                    Ok(value.credentials.clone())
+                }
+                None => Err(e),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicU32;
+    use std::sync::atomic::Ordering;
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::error::MQClientError;
+
+    #[test]
+    fn refreshes_before_expiry_and_survives_failures() {
+        let calls = Arc::new(AtomicU32::new(0));
+        let counter = calls.clone();
+        let provider = RotatingCredentialsProvider::with_refresh_ahead(
+            move || {
+                let call = counter.fetch_add(1, Ordering::SeqCst);
+                match call {
+                    // Valid for 30s, inside the refresh window from the start.
+                    0 => Ok(ExpiringCredentials {
+                        credentials: SessionCredentials::new("ak", "sk").with_security_token("t0"),
+                        expiration_millis: get_current_millis() + 30_000,
+                    }),
+                    1 => Err(MQClientError::RequestFailed("sts unavailable".to_string())),
+                    _ => Ok(ExpiringCredentials {
+                        credentials: SessionCredentials::new("ak", "sk").with_security_token("t2"),
+                        expiration_millis: get_current_millis() + 3_600_000,
+                    }),
+                }
+            },
+            Duration::from_secs(60),
+        );
+
+        let token = |provider: &RotatingCredentialsProvider| {
+            provider.get_credentials().unwrap().security_token.unwrap()
+        };
+        assert_eq!(token(&provider), "t0");
+        // The refresh fails, the cached token has not expired yet.
+        assert_eq!(token(&provider), "t0");
+        assert_eq!(token(&provider), "t2");
+        assert_eq!(token(&provider), "t2");
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+}
@@ -16,6 +16,10 @@
  */
 pub mod access_channel;
 pub mod client_config;
+pub mod client_config_handle;
+pub mod client_event_listener;
+pub mod connection_string;
 pub mod mq_admin;
+pub mod priority_queue_layout;
 pub mod query_result;
 pub mod validators;
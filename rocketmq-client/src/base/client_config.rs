@@ -20,7 +20,9 @@ use std::sync::atomic::Ordering;
 use std::sync::Arc;
 use std::time::Duration;
 
+use rand::Rng;
 use rocketmq_common::common::message::message_queue::MessageQueue;
+use rocketmq_common::common::mix_all;
 use rocketmq_common::utils::name_server_address_utils::NameServerAddressUtils;
 use rocketmq_common::utils::name_server_address_utils::NAMESRV_ENDPOINT_PATTERN;
 use rocketmq_common::utils::network_util::NetworkUtil;
@@ -30,7 +32,12 @@ use rocketmq_remoting::protocol::namespace_util::NamespaceUtil;
 use rocketmq_remoting::protocol::request_type::RequestType;
 use rocketmq_remoting::protocol::LanguageCode;
 
+use crate::acl::session_credentials::SessionCredentials;
 use crate::base::access_channel::AccessChannel;
+use crate::base::connection_string::ConnectionString;
+use crate::base::connection_string::CONNECTION_STRING_ENV;
+use crate::trace::trace_dispatcher::TraceDispatcherConfig;
+use crate::Result;
 
 pub const SEND_MESSAGE_WITH_VIP_CHANNEL_PROPERTY: &str = "com.rocketmq.sendMessageWithVIPChannel";
 pub const SOCKS_PROXY_CONFIG: &str = "com.rocketmq.socks.proxy.config";
@@ -39,6 +46,19 @@ pub const DECODE_DECOMPRESS_BODY: &str = "com.rocketmq.decompress.body";
 pub const SEND_LATENCY_ENABLE: &str = "com.rocketmq.sendLatencyEnable";
 pub const START_DETECTOR_ENABLE: &str = "com.rocketmq.startDetectorEnable";
 pub const HEART_BEAT_V2: &str = "com.rocketmq.heartbeat.v2";
+pub const TOPIC_ROUTE_CACHE_ENABLE: &str = "rocketmq.client.topicRouteCacheEnable";
+pub const TOPIC_ROUTE_CACHE_FILE: &str = "rocketmq.client.topicRouteCacheFile";
+pub const CLIENT_NAME: &str = "rocketmq.client.name";
+pub const CLIENT_LOG_ROOT: &str = "rocketmq.client.logRoot";
+pub const CLIENT_LOG_FILE_NAME: &str = "rocketmq.client.logFileName";
+pub const CLIENT_LOG_MAX_SIZE: &str = "rocketmq.client.logFileMaxSize";
+pub const CLIENT_LOG_MAX_INDEX: &str = "rocketmq.client.logFileMaxIndex";
+pub const CLIENT_LOG_LEVEL: &str = "rocketmq.client.logLevel";
+pub const SHUTDOWN_HOOK_ENABLE: &str = "rocketmq.client.shutdownHookEnable";
+
+/// Instance name of clients which were not given one, replaced by `PID#nanos` at start so that
+/// several clients of one process do not share a client id.
+pub const DEFAULT_INSTANCE_NAME: &str = "DEFAULT";
 
 #[derive(Clone)]
 pub struct ClientConfig {
@@ -52,7 +72,22 @@ pub struct ClientConfig {
     pub access_channel: AccessChannel,
     pub poll_name_server_interval: u32,
     pub heartbeat_broker_interval: u32,
+    /// Consecutive heartbeat failures to a broker before its topic routes are refreshed and it
+    /// is isolated from sending.
+    pub heartbeat_failure_threshold: u32,
     pub persist_consumer_offset_interval: u32,
+    /// Upper bound, in milliseconds, of a random delay added to the first run of the heartbeat,
+    /// route poll and offset persist loops, so clients restarted together spread out.
+    pub scheduled_task_startup_jitter: u32,
+    /// Start those loops at a random phase within their interval rather than a fixed one.
+    pub scheduled_task_random_phase: bool,
+    /// How often, in milliseconds, consumer lag metrics are refreshed from the brokers.
+    pub consumer_lag_refresh_interval: u32,
+    /// How often, in milliseconds, the routes of topics no producer or consumer uses anymore
+    /// are looked for, 0 keeps them all.
+    pub topic_route_gc_interval: u32,
+    /// How long, in milliseconds, a topic stays unused before its route is dropped.
+    pub topic_route_gc_grace_period: u32,
     pub pull_time_delay_millis_when_exception: u32,
     pub unit_mode: bool,
     pub unit_name: Option<String>,
@@ -72,6 +107,30 @@ pub struct ClientConfig {
     pub enable_heartbeat_channel_event_listener: bool,
     pub enable_trace: bool,
     pub trace_topic: Option<String>,
+    pub trace_dispatcher_config: TraceDispatcherConfig,
+    /// Load the topic route cache at start and persist it at shutdown.
+    pub enable_topic_route_cache: bool,
+    pub topic_route_cache_file: String,
+    /// Directory of the rolling client log file, client logs go to stdout when unset.
+    pub log_root: Option<String>,
+    pub log_file_name: String,
+    /// Size in bytes at which the client log file is rolled.
+    pub log_file_max_size: u64,
+    /// Rolled client log files kept, `<log_file_name>.1` being the most recent one.
+    pub log_file_max_index: u32,
+    /// Level of the client logs, e.g. `INFO`, see [`crate::client_log::set_log_level`].
+    pub log_level: String,
+    /// Shut every client down on SIGINT or SIGTERM before the process exits, see
+    /// [`crate::shutdown::install_shutdown_signal_handler`].
+    pub enable_shutdown_hook: bool,
+    /// How long, in milliseconds, that shutdown may take before the process exits anyway.
+    pub shutdown_hook_deadline_millis: u64,
+    /// Zone, e.g. the availability zone, the client runs in. Producers send to the brokers
+    /// registered in the same zone while one of them is healthy.
+    pub client_zone: Option<String>,
+    /// Credentials from the connection string or the environment, producers built without an
+    /// RPC hook sign their requests with them.
+    pub credentials: Option<SessionCredentials>,
 }
 
 impl Default for ClientConfig {
@@ -85,8 +144,8 @@ impl ClientConfig {
         ClientConfig {
             namesrv_addr: NameServerAddressUtils::get_name_server_addresses(),
             client_ip: NetworkUtil::get_local_address(),
-            instance_name: env::var("rocketmq.client.name")
-                .unwrap_or_else(|_| "DEFAULT".to_string()),
+            instance_name: env::var(CLIENT_NAME)
+                .unwrap_or_else(|_| DEFAULT_INSTANCE_NAME.to_string()),
             client_callback_executor_threads: num_cpus::get(),
             namespace: None,
             namespace_initialized: Arc::new(AtomicBool::new(false)),
@@ -94,7 +153,13 @@ impl ClientConfig {
             access_channel: AccessChannel::Local,
             poll_name_server_interval: Duration::from_secs(30).as_millis() as u32,
             heartbeat_broker_interval: Duration::from_secs(30).as_millis() as u32,
+            heartbeat_failure_threshold: 3,
             persist_consumer_offset_interval: Duration::from_secs(5).as_millis() as u32,
+            scheduled_task_startup_jitter: 0,
+            scheduled_task_random_phase: false,
+            consumer_lag_refresh_interval: Duration::from_secs(10).as_millis() as u32,
+            topic_route_gc_interval: Duration::from_secs(60).as_millis() as u32,
+            topic_route_gc_grace_period: Duration::from_secs(300).as_millis() as u32,
             pull_time_delay_millis_when_exception: 1000,
             unit_mode: false,
             unit_name: None,
@@ -130,7 +195,68 @@ impl ClientConfig {
             enable_heartbeat_channel_event_listener: true,
             enable_trace: false,
             trace_topic: None,
+            trace_dispatcher_config: TraceDispatcherConfig::default(),
+            enable_topic_route_cache: env::var(TOPIC_ROUTE_CACHE_ENABLE)
+                .unwrap_or_else(|_| "false".to_string())
+                .parse::<bool>()
+                .unwrap_or(false),
+            topic_route_cache_file: env::var(TOPIC_ROUTE_CACHE_FILE).unwrap_or_else(|_| {
+                dirs::home_dir()
+                    .unwrap_or_default()
+                    .join(".rocketmq_route_cache")
+                    .join("topic_route.json")
+                    .to_string_lossy()
+                    .to_string()
+            }),
+            log_root: env::var(CLIENT_LOG_ROOT).ok(),
+            log_file_name: env::var(CLIENT_LOG_FILE_NAME)
+                .unwrap_or_else(|_| "rocketmq_client.log".to_string()),
+            log_file_max_size: env::var(CLIENT_LOG_MAX_SIZE)
+                .ok()
+                .and_then(|size| size.parse::<u64>().ok())
+                .unwrap_or(1024 * 1024 * 1024),
+            log_file_max_index: env::var(CLIENT_LOG_MAX_INDEX)
+                .ok()
+                .and_then(|index| index.parse::<u32>().ok())
+                .unwrap_or(10),
+            log_level: env::var(CLIENT_LOG_LEVEL).unwrap_or_else(|_| "INFO".to_string()),
+            enable_shutdown_hook: env::var(SHUTDOWN_HOOK_ENABLE)
+                .unwrap_or_else(|_| "false".to_string())
+                .parse::<bool>()
+                .unwrap_or(false),
+            shutdown_hook_deadline_millis: Duration::from_secs(10).as_millis() as u64,
+            client_zone: env::var(mix_all::ROCKETMQ_ZONE_ENV)
+                .ok()
+                .filter(|zone| !zone.is_empty()),
+            credentials: None,
+        }
+    }
+
+    /// Configuration from a `rocketmq://` connection string, see [`ConnectionString`]. Options
+    /// missing from the string keep the values of [`new`](Self::new).
+    pub fn from_connection_string(connection_string: &str) -> Result<Self> {
+        let mut client_config = ClientConfig::new();
+        ConnectionString::parse(connection_string)?.apply_to(&mut client_config);
+        Ok(client_config)
+    }
+
+    /// Configuration from the environment. From lowest to highest precedence: the values of
+    /// [`new`](Self::new), the connection string in `ROCKETMQ_CONNECTION_STRING`, then each of
+    /// `ROCKETMQ_NAMESRV_ADDR`, `ROCKETMQ_NAMESPACE`, `ROCKETMQ_INSTANCE_NAME`,
+    /// `ROCKETMQ_UNIT_NAME`, `ROCKETMQ_ACCESS_CHANNEL`, `ROCKETMQ_USE_TLS`,
+    /// `ROCKETMQ_API_TIMEOUT_MILLIS` and `ROCKETMQ_ACCESS_KEY`/`ROCKETMQ_SECRET_KEY`/
+    /// `ROCKETMQ_SECURITY_TOKEN`.
+    pub fn from_env() -> Result<Self> {
+        Self::from_env_with(|key| env::var(key).ok())
+    }
+
+    pub(crate) fn from_env_with(lookup: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        let mut client_config = ClientConfig::new();
+        if let Some(connection_string) = lookup(CONNECTION_STRING_ENV).filter(|s| !s.is_empty()) {
+            ConnectionString::parse(&connection_string)?.apply_to(&mut client_config);
         }
+        ConnectionString::from_env_vars(lookup)?.apply_to(&mut client_config);
+        Ok(client_config)
     }
 }
 
@@ -173,15 +299,22 @@ impl ClientConfig {
         self.namespace.clone()
     }
 
+    /// Replaces the default instance name with `PID#nanos`, a name set by the application is
+    /// kept. Two clients of one process with the default name would otherwise get the same
+    /// client id, and the brokers would count them as a single consumer when rebalancing.
     pub fn change_instance_name_to_pid(&mut self) {
-        if self.instance_name == "DEFAULT" {
+        if self.instance_name == DEFAULT_INSTANCE_NAME {
             self.instance_name = format!("{}#{}", std::process::id(), get_current_nano());
         }
     }
 
+    /// Client id in the format of the Java client, `IP@instanceName[@unitName][@STREAM]`. An
+    /// IPv6 address goes in without brackets, the same as the Java client writes it.
     pub fn build_mq_client_id(&self) -> String {
         let mut sb = String::new();
-        sb.push_str(self.client_ip.as_ref().unwrap());
+        sb.push_str(NetworkUtil::normalize_host(
+            self.client_ip.as_deref().unwrap_or("127.0.0.1"),
+        ));
 
         sb.push('@');
         sb.push_str(self.instance_name.as_str());
@@ -200,6 +333,23 @@ impl ClientConfig {
         sb
     }
 
+    /// Delay before the first run of a scheduled loop firing every `interval`: `base`, plus up
+    /// to `scheduled_task_startup_jitter`, plus a random phase within `interval` if enabled.
+    pub fn scheduled_task_initial_delay(&self, base: Duration, interval: Duration) -> Duration {
+        let mut delay = base;
+        if self.scheduled_task_startup_jitter > 0 {
+            delay += Duration::from_millis(
+                rand::thread_rng().gen_range(0..=self.scheduled_task_startup_jitter as u64),
+            );
+        }
+        if self.scheduled_task_random_phase && !interval.is_zero() {
+            delay += Duration::from_millis(
+                rand::thread_rng().gen_range(0..interval.as_millis().max(1) as u64),
+            );
+        }
+        delay
+    }
+
     pub fn get_namesrv_addr(&self) -> Option<String> {
         if StringUtils::is_not_empty_str(self.namesrv_addr.as_deref())
             && NAMESRV_ENDPOINT_PATTERN.is_match(self.namesrv_addr.as_ref().unwrap().as_str())
@@ -212,3 +362,48 @@ impl ClientConfig {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn client_id_uses_instance_and_unit_name() {
+        let mut config = ClientConfig::new();
+        config.client_ip = Some("10.0.0.1".to_string());
+        config.instance_name = DEFAULT_INSTANCE_NAME.to_string();
+        config.unit_name = Some("unit-a".to_string());
+        assert_eq!(config.build_mq_client_id(), "10.0.0.1@DEFAULT@unit-a");
+
+        config.change_instance_name_to_pid();
+        let (pid, nanos) = config.instance_name.split_once('#').unwrap();
+        assert_eq!(pid, std::process::id().to_string());
+        assert!(nanos.parse::<u64>().is_ok());
+        let mut other = config.clone();
+        other.instance_name = DEFAULT_INSTANCE_NAME.to_string();
+        other.change_instance_name_to_pid();
+        assert_ne!(config.build_mq_client_id(), other.build_mq_client_id());
+
+        config.instance_name = "orders".to_string();
+        config.change_instance_name_to_pid();
+        config.unit_name = None;
+        config.client_ip = None;
+        assert_eq!(config.build_mq_client_id(), "127.0.0.1@orders");
+    }
+
+    #[test]
+    fn scheduled_task_initial_delay_stays_within_jitter_and_phase() {
+        let mut config = ClientConfig::new();
+        let base = Duration::from_secs(1);
+        let interval = Duration::from_secs(30);
+        assert_eq!(config.scheduled_task_initial_delay(base, interval), base);
+
+        config.scheduled_task_startup_jitter = 500;
+        config.scheduled_task_random_phase = true;
+        for _ in 0..100 {
+            let delay = config.scheduled_task_initial_delay(base, interval);
+            assert!(delay >= base);
+            assert!(delay < base + Duration::from_millis(500) + interval);
+        }
+    }
+}
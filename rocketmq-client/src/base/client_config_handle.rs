@@ -0,0 +1,167 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::watch;
+use tokio::time::Instant;
+use tracing::warn;
+
+use crate::base::client_config::ClientConfig;
+
+/// Intervals, in milliseconds, of the scheduled tasks of a client instance.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScheduledTaskIntervals {
+    pub poll_name_server_interval: u32,
+    pub heartbeat_broker_interval: u32,
+    pub persist_consumer_offset_interval: u32,
+}
+
+impl From<&ClientConfig> for ScheduledTaskIntervals {
+    fn from(client_config: &ClientConfig) -> Self {
+        ScheduledTaskIntervals {
+            poll_name_server_interval: client_config.poll_name_server_interval,
+            heartbeat_broker_interval: client_config.heartbeat_broker_interval,
+            persist_consumer_offset_interval: client_config.persist_consumer_offset_interval,
+        }
+    }
+}
+
+/// Live view of the settings of a running client instance. The scheduled tasks watch it, a new
+/// interval applies to the run in progress: a task due earlier under it runs at once.
+#[derive(Clone)]
+pub struct ClientConfigHandle {
+    intervals: Arc<watch::Sender<ScheduledTaskIntervals>>,
+}
+
+impl ClientConfigHandle {
+    pub fn new(client_config: &ClientConfig) -> Self {
+        let (intervals, _) = watch::channel(ScheduledTaskIntervals::from(client_config));
+        ClientConfigHandle {
+            intervals: Arc::new(intervals),
+        }
+    }
+
+    pub fn intervals(&self) -> ScheduledTaskIntervals {
+        *self.intervals.borrow()
+    }
+
+    pub fn subscribe(&self) -> watch::Receiver<ScheduledTaskIntervals> {
+        self.intervals.subscribe()
+    }
+
+    pub fn set_poll_name_server_interval(&self, poll_name_server_interval: u32) {
+        self.update(
+            "poll_name_server_interval",
+            poll_name_server_interval,
+            |intervals| &mut intervals.poll_name_server_interval,
+        );
+    }
+
+    pub fn set_heartbeat_broker_interval(&self, heartbeat_broker_interval: u32) {
+        self.update(
+            "heartbeat_broker_interval",
+            heartbeat_broker_interval,
+            |intervals| &mut intervals.heartbeat_broker_interval,
+        );
+    }
+
+    pub fn set_persist_consumer_offset_interval(&self, persist_consumer_offset_interval: u32) {
+        self.update(
+            "persist_consumer_offset_interval",
+            persist_consumer_offset_interval,
+            |intervals| &mut intervals.persist_consumer_offset_interval,
+        );
+    }
+
+    fn update(
+        &self,
+        name: &str,
+        interval: u32,
+        field: impl FnOnce(&mut ScheduledTaskIntervals) -> &mut u32,
+    ) {
+        if interval == 0 {
+            warn!("{} must be positive, 0 is ignored", name);
+            return;
+        }
+        self.intervals.send_if_modified(|intervals| {
+            let current = field(intervals);
+            let modified = *current != interval;
+            *current = interval;
+            modified
+        });
+    }
+}
+
+/// Waits until `interval_of` the watched intervals has elapsed since `started`, following the
+/// updates made in the meantime.
+pub(crate) async fn sleep_until_next_run(
+    receiver: &mut watch::Receiver<ScheduledTaskIntervals>,
+    started: Instant,
+    interval_of: impl Fn(&ScheduledTaskIntervals) -> u32,
+) {
+    loop {
+        let interval = interval_of(&receiver.borrow_and_update());
+        let next_run = started + Duration::from_millis(interval as u64);
+        tokio::select! {
+            _ = tokio::time::sleep_until(next_run) => return,
+            changed = receiver.changed() => {
+                if changed.is_err() {
+                    // The handle is gone, the interval can no longer change
+                    tokio::time::sleep_until(next_run).await;
+                    return;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn shorter_interval_applies_to_the_pending_run() {
+        let mut client_config = ClientConfig::new();
+        client_config.heartbeat_broker_interval = 60_000;
+        let handle = ClientConfigHandle::new(&client_config);
+        let mut receiver = handle.subscribe();
+
+        let started = Instant::now();
+        let updater = handle.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            updater.set_heartbeat_broker_interval(50);
+        });
+        tokio::time::timeout(
+            Duration::from_secs(5),
+            sleep_until_next_run(&mut receiver, started, |intervals| {
+                intervals.heartbeat_broker_interval
+            }),
+        )
+        .await
+        .unwrap();
+        assert!(started.elapsed() >= Duration::from_millis(50));
+
+        handle.set_heartbeat_broker_interval(0);
+        assert_eq!(handle.intervals().heartbeat_broker_interval, 50);
+        assert_eq!(
+            handle.intervals().poll_name_server_interval,
+            client_config.poll_name_server_interval
+        );
+    }
+}
@@ -0,0 +1,37 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+/// Events of the client instance worth surfacing to the application, beyond the logs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClientEvent {
+    /// Heartbeats to a broker failed `consecutive_failures` times in a row. The routes of the
+    /// topics on that broker have been refreshed and producers avoid it for a while.
+    BrokerHeartbeatFailed {
+        broker_name: String,
+        broker_addr: String,
+        consecutive_failures: u32,
+    },
+    /// A broker that had reached the heartbeat failure threshold answers heartbeats again.
+    BrokerHeartbeatRecovered {
+        broker_name: String,
+        broker_addr: String,
+    },
+}
+
+/// Listener registered on the client instance to receive [`ClientEvent`]s.
+pub trait ClientEventListener: Send + Sync + 'static {
+    fn on_event(&self, event: &ClientEvent);
+}
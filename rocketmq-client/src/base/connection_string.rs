@@ -0,0 +1,314 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::str::FromStr;
+
+use crate::acl::credentials_provider::ACCESS_KEY_ENV;
+use crate::acl::credentials_provider::SECRET_KEY_ENV;
+use crate::acl::credentials_provider::SECURITY_TOKEN_ENV;
+use crate::acl::session_credentials::SessionCredentials;
+use crate::base::access_channel::AccessChannel;
+use crate::base::client_config::ClientConfig;
+use crate::error::ClientErrorKind;
+use crate::error::MQClientError::IllegalClientConfig;
+use crate::Result;
+
+/// Connection string [`ClientConfig::from_env`] starts from.
+pub const CONNECTION_STRING_ENV: &str = "ROCKETMQ_CONNECTION_STRING";
+/// Name server addresses, separated by `;` or `,`.
+pub const NAMESRV_ADDR_ENV: &str = "ROCKETMQ_NAMESRV_ADDR";
+pub const NAMESPACE_ENV: &str = "ROCKETMQ_NAMESPACE";
+pub const INSTANCE_NAME_ENV: &str = "ROCKETMQ_INSTANCE_NAME";
+pub const UNIT_NAME_ENV: &str = "ROCKETMQ_UNIT_NAME";
+/// `LOCAL` or `CLOUD`.
+pub const ACCESS_CHANNEL_ENV: &str = "ROCKETMQ_ACCESS_CHANNEL";
+pub const USE_TLS_ENV: &str = "ROCKETMQ_USE_TLS";
+pub const API_TIMEOUT_ENV: &str = "ROCKETMQ_API_TIMEOUT_MILLIS";
+
+const SCHEME: &str = "rocketmq://";
+const TLS_SCHEME: &str = "rocketmqs://";
+
+/// A client configuration in one string, e.g.
+/// `rocketmq://host1:9876,host2:9876?accessKey=ak&secretKey=sk&namespace=ns`.
+///
+/// The `rocketmqs://` scheme turns TLS on. The options are `accessKey`, `secretKey`,
+/// `securityToken`, `namespace`, `instanceName`, `unitName`, `accessChannel` (`LOCAL` or
+/// `CLOUD`), `useTls` and `timeout` (milliseconds of the client API calls). Values are percent
+/// decoded, unknown options are rejected so a typo does not go unnoticed.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConnectionString {
+    pub namesrv_addr: Option<String>,
+    pub credentials: Option<SessionCredentials>,
+    pub namespace: Option<String>,
+    pub instance_name: Option<String>,
+    pub unit_name: Option<String>,
+    pub access_channel: Option<AccessChannel>,
+    pub use_tls: Option<bool>,
+    pub timeout_millis: Option<u64>,
+}
+
+impl ConnectionString {
+    pub fn parse(connection_string: &str) -> Result<Self> {
+        let connection_string = connection_string.trim();
+        let (rest, tls) = if let Some(rest) = connection_string.strip_prefix(SCHEME) {
+            (rest, false)
+        } else if let Some(rest) = connection_string.strip_prefix(TLS_SCHEME) {
+            (rest, true)
+        } else {
+            return Err(illegal("the scheme must be rocketmq:// or rocketmqs://"));
+        };
+        let (hosts, query) = rest.split_once('?').unwrap_or((rest, ""));
+        let hosts = hosts.trim_end_matches('/');
+        let mut parsed = ConnectionString {
+            namesrv_addr: (!hosts.is_empty()).then(|| join_addresses(hosts)),
+            use_tls: tls.then_some(true),
+            ..Default::default()
+        };
+
+        let mut access_key = None;
+        let mut secret_key = None;
+        let mut security_token = None;
+        for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let value = percent_decode(value)?;
+            match key {
+                "accessKey" => access_key = Some(value),
+                "secretKey" => secret_key = Some(value),
+                "securityToken" => security_token = Some(value),
+                "namespace" => parsed.namespace = Some(value),
+                "instanceName" => parsed.instance_name = Some(value),
+                "unitName" => parsed.unit_name = Some(value),
+                "accessChannel" => parsed.access_channel = Some(parse_access_channel(&value)?),
+                "useTls" => parsed.use_tls = Some(parse_value(key, &value)?),
+                "timeout" => parsed.timeout_millis = Some(parse_value(key, &value)?),
+                _ => return Err(illegal(&format!("unknown option {}", key))),
+            }
+        }
+        parsed.credentials = credentials(access_key, secret_key, security_token)?;
+        Ok(parsed)
+    }
+
+    /// Sets the options present in the connection string on `client_config`.
+    pub fn apply_to(&self, client_config: &mut ClientConfig) {
+        if let Some(namesrv_addr) = &self.namesrv_addr {
+            client_config.namesrv_addr = Some(namesrv_addr.clone());
+        }
+        if let Some(credentials) = &self.credentials {
+            client_config.credentials = Some(credentials.clone());
+        }
+        if let Some(namespace) = &self.namespace {
+            client_config.namespace = Some(namespace.clone());
+        }
+        if let Some(instance_name) = &self.instance_name {
+            client_config.instance_name = instance_name.clone();
+        }
+        if let Some(unit_name) = &self.unit_name {
+            client_config.unit_name = Some(unit_name.clone());
+        }
+        if let Some(access_channel) = self.access_channel {
+            client_config.access_channel = access_channel;
+        }
+        if let Some(use_tls) = self.use_tls {
+            client_config.use_tls = use_tls;
+        }
+        if let Some(timeout_millis) = self.timeout_millis {
+            client_config.mq_client_api_timeout = timeout_millis;
+        }
+    }
+
+    /// Options set through the `ROCKETMQ_*` environment variables, read with `lookup`.
+    pub(crate) fn from_env_vars(lookup: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        let read = |key: &str| lookup(key).filter(|value| !value.is_empty());
+        Ok(ConnectionString {
+            namesrv_addr: read(NAMESRV_ADDR_ENV).map(|addr| join_addresses(&addr)),
+            credentials: credentials(
+                read(ACCESS_KEY_ENV),
+                read(SECRET_KEY_ENV),
+                read(SECURITY_TOKEN_ENV),
+            )?,
+            namespace: read(NAMESPACE_ENV),
+            instance_name: read(INSTANCE_NAME_ENV),
+            unit_name: read(UNIT_NAME_ENV),
+            access_channel: read(ACCESS_CHANNEL_ENV)
+                .map(|value| parse_access_channel(&value))
+                .transpose()?,
+            use_tls: read(USE_TLS_ENV)
+                .map(|value| parse_value(USE_TLS_ENV, &value))
+                .transpose()?,
+            timeout_millis: read(API_TIMEOUT_ENV)
+                .map(|value| parse_value(API_TIMEOUT_ENV, &value))
+                .transpose()?,
+        })
+    }
+}
+
+impl FromStr for ConnectionString {
+    type Err = crate::error::MQClientError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        ConnectionString::parse(s)
+    }
+}
+
+fn illegal(reason: &str) -> crate::error::MQClientError {
+    IllegalClientConfig(ClientErrorKind::IllegalConnectionString, reason.to_string())
+}
+
+/// Name server addresses in the `;` separated form of [`ClientConfig::namesrv_addr`].
+fn join_addresses(addresses: &str) -> String {
+    addresses
+        .split([',', ';'])
+        .map(str::trim)
+        .filter(|addr| !addr.is_empty())
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+fn credentials(
+    access_key: Option<String>,
+    secret_key: Option<String>,
+    security_token: Option<String>,
+) -> Result<Option<SessionCredentials>> {
+    match (access_key, secret_key) {
+        (Some(access_key), Some(secret_key)) => {
+            let credentials = SessionCredentials::new(access_key, secret_key);
+            Ok(Some(match security_token {
+                Some(security_token) => credentials.with_security_token(security_token),
+                None => credentials,
+            }))
+        }
+        (None, None) => Ok(None),
+        _ => Err(illegal("accessKey and secretKey must be set together")),
+    }
+}
+
+fn parse_access_channel(value: &str) -> Result<AccessChannel> {
+    match value.to_ascii_uppercase().as_str() {
+        "LOCAL" => Ok(AccessChannel::Local),
+        "CLOUD" => Ok(AccessChannel::Cloud),
+        _ => Err(illegal(&format!("unknown access channel {}", value))),
+    }
+}
+
+fn parse_value<T: FromStr>(key: &str, value: &str) -> Result<T> {
+    value
+        .parse::<T>()
+        .map_err(|_| illegal(&format!("invalid value {} of {}", value, key)))
+}
+
+fn percent_decode(value: &str) -> Result<String> {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let hex = bytes
+                    .get(i + 1..i + 3)
+                    .and_then(|hex| std::str::from_utf8(hex).ok())
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                    .ok_or_else(|| illegal(&format!("invalid percent encoding in {}", value)))?;
+                decoded.push(hex);
+                i += 3;
+            }
+            b'+' => {
+                decoded.push(b' ');
+                i += 1;
+            }
+            byte => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(decoded).map_err(|_| illegal(&format!("{} is not valid UTF-8", value)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    #[test]
+    fn parses_hosts_and_options() {
+        let parsed = ConnectionString::parse(
+            "rocketmqs://host1:9876,host2:9876/?accessKey=ak&secretKey=s%2Bk%3D&namespace=ns&\
+             accessChannel=cloud&timeout=5000",
+        )
+        .unwrap();
+        assert_eq!(
+            parsed.namesrv_addr.as_deref(),
+            Some("host1:9876;host2:9876")
+        );
+        assert_eq!(
+            parsed.credentials,
+            Some(SessionCredentials::new("ak", "s+k="))
+        );
+        assert_eq!(parsed.namespace.as_deref(), Some("ns"));
+        assert_eq!(parsed.access_channel, Some(AccessChannel::Cloud));
+        assert_eq!(parsed.use_tls, Some(true));
+        assert_eq!(parsed.timeout_millis, Some(5000));
+
+        let mut client_config = ClientConfig::new();
+        parsed.apply_to(&mut client_config);
+        assert_eq!(
+            client_config.namesrv_addr.as_deref(),
+            Some("host1:9876;host2:9876")
+        );
+        assert!(client_config.use_tls);
+        assert_eq!(client_config.mq_client_api_timeout, 5000);
+    }
+
+    #[test]
+    fn rejects_malformed_connection_strings() {
+        for connection_string in [
+            "http://host:9876",
+            "rocketmq://host:9876?accessKey=ak",
+            "rocketmq://host:9876?acessKey=ak",
+            "rocketmq://host:9876?timeout=soon",
+            "rocketmq://host:9876?namespace=%zz",
+        ] {
+            assert!(
+                ConnectionString::parse(connection_string).is_err(),
+                "{}",
+                connection_string
+            );
+        }
+    }
+
+    #[test]
+    fn environment_variables_override_the_connection_string() {
+        let env = HashMap::from([
+            (
+                CONNECTION_STRING_ENV,
+                "rocketmq://host1:9876?namespace=ns&instanceName=app",
+            ),
+            (NAMESPACE_ENV, "ns-override"),
+            (ACCESS_KEY_ENV, "ak"),
+            (SECRET_KEY_ENV, "sk"),
+        ]);
+        let client_config =
+            ClientConfig::from_env_with(|key| env.get(key).map(|value| value.to_string())).unwrap();
+        assert_eq!(client_config.namesrv_addr.as_deref(), Some("host1:9876"));
+        assert_eq!(client_config.namespace.as_deref(), Some("ns-override"));
+        assert_eq!(client_config.instance_name, "app");
+        assert_eq!(
+            client_config.credentials,
+            Some(SessionCredentials::new("ak", "sk"))
+        );
+    }
+}
@@ -0,0 +1,100 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::ops::Range;
+
+use crate::error::MQClientError;
+use crate::Result;
+
+/// Maps priority levels onto disjoint ranges of the queues of a topic, so priority delivery
+/// works on a plain topic without broker support.
+///
+/// Level 0 is the highest priority. The queues of each broker are split evenly and in order
+/// across the levels, the first levels taking the remainder, and each level carries the pull
+/// weight consumers give its queues.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PriorityQueueLayout {
+    weights: Vec<u32>,
+}
+
+impl PriorityQueueLayout {
+    /// `weights` holds the pull weight of every level, highest priority first.
+    pub fn new(weights: Vec<u32>) -> Result<Self> {
+        if weights.is_empty() {
+            return Err(MQClientError::IllegalArgument(
+                "priority queue layout needs at least one level".to_string(),
+            ));
+        }
+        if weights.contains(&0) {
+            return Err(MQClientError::IllegalArgument(
+                "priority level weights must be positive".to_string(),
+            ));
+        }
+        Ok(Self { weights })
+    }
+
+    pub fn levels(&self) -> usize {
+        self.weights.len()
+    }
+
+    pub fn weight(&self, level: usize) -> u32 {
+        self.weights.get(level).copied().unwrap_or(0)
+    }
+
+    /// Queue ids of `level` among `queue_nums` queues, empty when there are fewer queues than
+    /// levels left for it.
+    pub fn level_range(&self, level: usize, queue_nums: usize) -> Range<usize> {
+        let levels = self.levels();
+        if level >= levels {
+            return 0..0;
+        }
+        let base = queue_nums / levels;
+        let remainder = queue_nums % levels;
+        let start = level * base + level.min(remainder);
+        let len = base + usize::from(level < remainder);
+        start..start + len
+    }
+
+    pub fn level_of_queue(&self, queue_id: usize, queue_nums: usize) -> Option<usize> {
+        if queue_id >= queue_nums {
+            return None;
+        }
+        (0..self.levels()).find(|level| self.level_range(*level, queue_nums).contains(&queue_id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn level_ranges_are_disjoint_and_cover_all_queues() {
+        let layout = PriorityQueueLayout::new(vec![4, 2, 1]).unwrap();
+        assert_eq!(layout.level_range(0, 8), 0..3);
+        assert_eq!(layout.level_range(1, 8), 3..6);
+        assert_eq!(layout.level_range(2, 8), 6..8);
+        assert_eq!(layout.level_range(3, 8), 0..0);
+        assert_eq!(layout.level_of_queue(5, 8), Some(1));
+        assert_eq!(layout.level_of_queue(8, 8), None);
+        assert_eq!(layout.level_range(2, 2), 2..2);
+    }
+
+    #[test]
+    fn zero_weight_is_rejected() {
+        assert!(PriorityQueueLayout::new(vec![]).is_err());
+        assert!(PriorityQueueLayout::new(vec![3, 0]).is_err());
+    }
+}
@@ -20,9 +20,17 @@ use rocketmq_common::common::config::TopicConfig;
 use rocketmq_common::common::constant::PermName;
 use rocketmq_common::common::message::MessageConst;
 use rocketmq_common::common::message::MessageTrait;
+use rocketmq_common::common::mix_all;
 use rocketmq_common::common::topic::TopicValidator;
+use rocketmq_common::utils::name_server_address_utils::NAMESRV_ENDPOINT_PATTERN;
+use rocketmq_common::utils::network_util::NetworkUtil;
 use rocketmq_remoting::code::response_code::ResponseCode;
+use rocketmq_remoting::protocol::heartbeat::message_model::MessageModel;
 
+use crate::consumer::default_mq_push_consumer::ConsumerConfig;
+use crate::error::ClientErrorKind;
+use crate::error::MQClientError;
+use crate::error::MQClientError::IllegalClientConfig;
 use crate::error::MQClientError::MQClientException;
 use crate::producer::default_mq_producer::ProducerConfig;
 use crate::Result;
@@ -35,22 +43,22 @@ impl Validators {
 
     pub fn check_group(group: &str) -> Result<()> {
         if group.trim().is_empty() {
-            return Err(MQClientException(
-                -1,
+            return Err(IllegalClientConfig(
+                ClientErrorKind::IllegalGroupName,
                 "the specified group is blank".to_string(),
             ));
         }
 
         if group.len() > Self::CHARACTER_MAX_LENGTH {
-            return Err(MQClientException(
-                -1,
+            return Err(IllegalClientConfig(
+                ClientErrorKind::IllegalGroupName,
                 "the specified group is longer than group max length 255.".to_string(),
             ));
         }
 
         if TopicValidator::is_topic_or_group_illegal(group) {
-            return Err(MQClientException(
-                -1,
+            return Err(IllegalClientConfig(
+                ClientErrorKind::IllegalGroupName,
                 format!(
                     "the specified group[{}] contains illegal characters, allowing only \
                      ^[%|a-zA-Z0-9_-]+$",
@@ -62,6 +70,99 @@ impl Validators {
         Ok(())
     }
 
+    /// Checks the group of a producer, the default and inner producer groups are reserved.
+    pub fn check_producer_group(group: &str) -> Result<()> {
+        Self::check_group(group)?;
+        if group == mix_all::DEFAULT_PRODUCER_GROUP || group == mix_all::CLIENT_INNER_PRODUCER_GROUP
+        {
+            return Err(IllegalClientConfig(
+                ClientErrorKind::ReservedGroupName,
+                format!(
+                    "the specified group name[{}] is reserved, please specify another one.",
+                    group
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Checks the group of a consumer, the default and tools consumer groups are reserved.
+    pub fn check_consumer_group(group: &str) -> Result<()> {
+        Self::check_group(group)?;
+        if group == mix_all::DEFAULT_CONSUMER_GROUP || group == mix_all::TOOLS_CONSUMER_GROUP {
+            return Err(IllegalClientConfig(
+                ClientErrorKind::ReservedGroupName,
+                format!(
+                    "the specified group name[{}] is reserved, please specify another one.",
+                    group
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Checks the options of a consumer against each other and against their ranges.
+    pub fn check_consumer_config(consumer_config: &ConsumerConfig) -> Result<()> {
+        Self::check_consumer_group(consumer_config.consumer_group())?;
+        if consumer_config.consume_orderly()
+            && consumer_config.message_model() == MessageModel::Broadcasting
+        {
+            return Err(IllegalClientConfig(
+                ClientErrorKind::ConflictingOptions,
+                "orderly consumption can not be enabled for a broadcasting consumer".to_string(),
+            ));
+        }
+        for (name, value) in [
+            ("consumeThreadMin", consumer_config.consume_thread_min()),
+            ("consumeThreadMax", consumer_config.consume_thread_max()),
+        ] {
+            if !(1..=1000).contains(&value) {
+                return Err(IllegalClientConfig(
+                    ClientErrorKind::IllegalOptionValue,
+                    format!("{} {} out of range [1, 1000]", name, value),
+                ));
+            }
+        }
+        if consumer_config.consume_thread_min() > consumer_config.consume_thread_max() {
+            return Err(IllegalClientConfig(
+                ClientErrorKind::ConflictingOptions,
+                format!(
+                    "consumeThreadMin {} is larger than consumeThreadMax {}",
+                    consumer_config.consume_thread_min(),
+                    consumer_config.consume_thread_max()
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Checks a `host:port;host:port` name server address list, http endpoints are resolved later.
+    pub fn check_name_server_addr(namesrv_addr: &str) -> Result<()> {
+        if NAMESRV_ENDPOINT_PATTERN.is_match(namesrv_addr) {
+            return Ok(());
+        }
+        let addrs = namesrv_addr
+            .split(';')
+            .map(str::trim)
+            .filter(|addr| !addr.is_empty())
+            .collect::<Vec<_>>();
+        if addrs.is_empty() {
+            return Err(IllegalClientConfig(
+                ClientErrorKind::IllegalNameServerAddress,
+                "the specified name server address is blank".to_string(),
+            ));
+        }
+        for addr in addrs {
+            if NetworkUtil::split_host_port(addr).is_none() {
+                return Err(IllegalClientConfig(
+                    ClientErrorKind::IllegalNameServerAddress,
+                    format!("the name server address[{}] is not host:port", addr),
+                ));
+            }
+        }
+        Ok(())
+    }
+
     pub fn check_message<M>(msg: Option<&M>, producer_config: &ProducerConfig) -> Result<()>
     where
         M: MessageTrait,
@@ -91,7 +192,10 @@ impl Validators {
             ));
         }
 
-        if length > producer_config.max_message_size() as usize {
+        let offloaded = producer_config
+            .claim_check()
+            .is_some_and(|claim_check| length > claim_check.body_over_howmuch());
+        if length > producer_config.max_message_size() as usize && !offloaded {
             return Err(MQClientException(
                 ResponseCode::MessageIllegal as i32,
                 format!(
@@ -101,6 +205,16 @@ impl Validators {
             ));
         }
 
+        if let Err(e) = producer_config
+            .message_properties_limits()
+            .check(msg.get_properties())
+        {
+            return Err(MQClientException(
+                ResponseCode::MessageIllegal as i32,
+                e.to_string(),
+            ));
+        }
+
         let lmq_path = msg.get_user_property(MessageConst::PROPERTY_INNER_MULTI_DISPATCH);
         if let Some(value) = lmq_path {
             if value.contains(std::path::MAIN_SEPARATOR) {
@@ -120,31 +234,24 @@ impl Validators {
 
     pub fn check_topic(topic: &str) -> Result<()> {
         if topic.trim().is_empty() {
-            return Err(MQClientException(
-                -1,
+            return Err(MQClientError::IllegalArgument(
                 "The specified topic is blank".to_string(),
             ));
         }
 
         if topic.len() > Self::TOPIC_MAX_LENGTH {
-            return Err(MQClientException(
-                -1,
-                format!(
-                    "The specified topic is longer than topic max length {}.",
-                    Self::TOPIC_MAX_LENGTH
-                ),
-            ));
+            return Err(MQClientError::IllegalArgument(format!(
+                "The specified topic is longer than topic max length {}.",
+                Self::TOPIC_MAX_LENGTH
+            )));
         }
 
         if TopicValidator::is_topic_or_group_illegal(topic) {
-            return Err(MQClientException(
-                -1,
-                format!(
-                    "The specified topic[{}] contains illegal characters, allowing only \
-                     ^[%|a-zA-Z0-9_-]+$",
-                    topic
-                ),
-            ));
+            return Err(MQClientError::IllegalArgument(format!(
+                "The specified topic[{}] contains illegal characters, allowing only \
+                 ^[%|a-zA-Z0-9_-]+$",
+                topic
+            )));
         }
 
         Ok(())
@@ -152,20 +259,20 @@ impl Validators {
 
     pub fn is_system_topic(topic: &str) -> Result<()> {
         if TopicValidator::is_system_topic(topic) {
-            return Err(MQClientException(
-                -1,
-                format!("The topic[{}] is conflict with system topic.", topic),
-            ));
+            return Err(MQClientError::IllegalArgument(format!(
+                "The topic[{}] is conflict with system topic.",
+                topic
+            )));
         }
         Ok(())
     }
 
     pub fn is_not_allowed_send_topic(topic: &str) -> Result<()> {
         if TopicValidator::is_not_allowed_send_topic(topic) {
-            return Err(MQClientException(
-                -1,
-                format!("Sending message to topic[{}] is forbidden.", topic),
-            ));
+            return Err(MQClientError::IllegalArgument(format!(
+                "Sending message to topic[{}] is forbidden.",
+                topic
+            )));
         }
 
         Ok(())
@@ -185,13 +292,68 @@ impl Validators {
     pub fn check_broker_config(broker_config: &HashMap<String, String>) -> Result<()> {
         if let Some(broker_permission) = broker_config.get("brokerPermission") {
             if !PermName::is_valid(broker_permission.parse().unwrap()) {
-                return Err(MQClientException(
-                    -1,
-                    format!("brokerPermission value: {} is invalid.", broker_permission),
-                ));
+                return Err(MQClientError::IllegalArgument(format!(
+                    "brokerPermission value: {} is invalid.",
+                    broker_permission
+                )));
             }
         }
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error_kind(result: Result<()>) -> Option<ClientErrorKind> {
+        match result {
+            Err(IllegalClientConfig(kind, _)) => Some(kind),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn check_producer_group_rejects_illegal_and_reserved_names() {
+        assert!(Validators::check_producer_group("please_rename_unique_group_name").is_ok());
+        assert_eq!(
+            error_kind(Validators::check_producer_group(" ")),
+            Some(ClientErrorKind::IllegalGroupName)
+        );
+        assert_eq!(
+            error_kind(Validators::check_producer_group("group name")),
+            Some(ClientErrorKind::IllegalGroupName)
+        );
+        assert_eq!(
+            error_kind(Validators::check_producer_group(&"g".repeat(256))),
+            Some(ClientErrorKind::IllegalGroupName)
+        );
+        assert_eq!(
+            error_kind(Validators::check_producer_group(
+                mix_all::DEFAULT_PRODUCER_GROUP
+            )),
+            Some(ClientErrorKind::ReservedGroupName)
+        );
+    }
+
+    #[test]
+    fn check_name_server_addr_accepts_host_port_lists_and_endpoints() {
+        assert!(Validators::check_name_server_addr("127.0.0.1:9876").is_ok());
+        assert!(Validators::check_name_server_addr("127.0.0.1:9876;namesrv:9877;").is_ok());
+        assert!(Validators::check_name_server_addr("http://jmenv.tbsite.net:8080").is_ok());
+        assert!(Validators::check_name_server_addr("[fd00::1]:9876;fd00::2:9876").is_ok());
+        assert_eq!(
+            error_kind(Validators::check_name_server_addr("127.0.0.1")),
+            Some(ClientErrorKind::IllegalNameServerAddress)
+        );
+        assert_eq!(
+            error_kind(Validators::check_name_server_addr("127.0.0.1:98765")),
+            Some(ClientErrorKind::IllegalNameServerAddress)
+        );
+        assert_eq!(
+            error_kind(Validators::check_name_server_addr(";")),
+            Some(ClientErrorKind::IllegalNameServerAddress)
+        );
+    }
+}
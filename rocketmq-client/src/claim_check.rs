@@ -0,0 +1,169 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::sync::Arc;
+
+use bytes::Bytes;
+use rocketmq_common::common::message::message_batch::MessageBatch;
+use rocketmq_common::common::message::message_client_id_setter::MessageClientIDSetter;
+use rocketmq_common::common::message::MessageTrait;
+
+use crate::error::MQClientError;
+use crate::Result;
+
+/// Original size of a body kept in a [`BlobStore`], the body of the message is the URI of the
+/// blob when set.
+pub const PROPERTY_CLAIM_CHECK_BODY_SIZE: &str = "__CLAIM_CHECK_BODY_SIZE";
+
+/// External storage for message bodies too large to go through the broker, e.g. S3 or OSS.
+pub trait BlobStore: Send + Sync + 'static {
+    /// Stores `payload` under `key` and returns the URI to fetch it with.
+    fn put(&self, key: &str, payload: &Bytes) -> Result<String>;
+
+    /// Fetches the payload stored at `uri`.
+    fn get(&self, uri: &str) -> Result<Bytes>;
+}
+
+/// Claim-check for large messages: the producer uploads bodies above a threshold to a
+/// [`BlobStore`] and sends the URI of the blob instead, the consumer swaps the body back before
+/// the listener sees the message.
+#[derive(Clone)]
+pub struct ClaimCheck {
+    blob_store: Arc<dyn BlobStore>,
+    body_over_howmuch: usize,
+}
+
+impl ClaimCheck {
+    pub fn new(blob_store: Arc<dyn BlobStore>, body_over_howmuch: u32) -> Self {
+        Self {
+            blob_store,
+            body_over_howmuch: body_over_howmuch as usize,
+        }
+    }
+
+    pub fn body_over_howmuch(&self) -> usize {
+        self.body_over_howmuch
+    }
+
+    /// Uploads the body of `msg` when it is larger than the threshold and replaces it with the
+    /// URI of the blob. Batches are left alone, their messages are offloaded one by one before
+    /// batching. Returns whether the body was offloaded.
+    pub fn offload<T: MessageTrait>(&self, msg: &mut T) -> Result<bool> {
+        if msg.as_any().downcast_ref::<MessageBatch>().is_some() {
+            return Ok(false);
+        }
+        let Some(body) = msg
+            .get_body()
+            .filter(|body| body.len() > self.body_over_howmuch)
+        else {
+            return Ok(false);
+        };
+        let body = body.clone();
+        let key = format!(
+            "{}/{}",
+            msg.get_topic(),
+            MessageClientIDSetter::get_uniq_id(msg)
+                .unwrap_or_else(MessageClientIDSetter::create_uniq_id)
+        );
+        let uri = self.blob_store.put(key.as_str(), &body)?;
+        msg.put_property(
+            PROPERTY_CLAIM_CHECK_BODY_SIZE,
+            body.len().to_string().as_str(),
+        );
+        msg.set_body(Bytes::from(uri));
+        *msg.get_compressed_body_mut() = None;
+        Ok(true)
+    }
+
+    /// Fetches the body of a message offloaded by [`offload`](Self::offload) and puts it back.
+    /// Returns whether the body was restored, messages sent without a claim-check are left
+    /// alone.
+    pub fn restore<T: MessageTrait>(&self, msg: &mut T) -> Result<bool> {
+        let Some(body_size) = msg.get_property(PROPERTY_CLAIM_CHECK_BODY_SIZE) else {
+            return Ok(false);
+        };
+        let uri = msg
+            .get_body()
+            .map(|body| String::from_utf8_lossy(body).into_owned())
+            .unwrap_or_default();
+        let body = self.blob_store.get(uri.as_str())?;
+        if body_size.parse::<usize>().ok() != Some(body.len()) {
+            return Err(MQClientError::IllegalState(format!(
+                "blob {} has {} bytes, the message claims {}",
+                uri,
+                body.len(),
+                body_size
+            )));
+        }
+        msg.set_body(body);
+        msg.clear_property(PROPERTY_CLAIM_CHECK_BODY_SIZE);
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use parking_lot::Mutex;
+    use rocketmq_common::common::message::message_single::Message;
+
+    use super::*;
+
+    #[derive(Default)]
+    struct MemoryBlobStore {
+        blobs: Mutex<HashMap<String, Bytes>>,
+    }
+
+    impl BlobStore for MemoryBlobStore {
+        fn put(&self, key: &str, payload: &Bytes) -> Result<String> {
+            let uri = format!("mem://{}", key);
+            self.blobs.lock().insert(uri.clone(), payload.clone());
+            Ok(uri)
+        }
+
+        fn get(&self, uri: &str) -> Result<Bytes> {
+            self.blobs
+                .lock()
+                .get(uri)
+                .cloned()
+                .ok_or_else(|| MQClientError::IllegalState(format!("no blob {}", uri)))
+        }
+    }
+
+    #[test]
+    fn offloads_large_bodies_and_restores_them() {
+        let blob_store = Arc::new(MemoryBlobStore::default());
+        let claim_check = ClaimCheck::new(blob_store.clone(), 16);
+
+        let mut small = Message::new("TopicTest", b"small body");
+        assert!(!claim_check.offload(&mut small).unwrap());
+        assert!(!claim_check.restore(&mut small).unwrap());
+        assert_eq!(small.get_body().unwrap().as_ref(), b"small body");
+
+        let payload = vec![7u8; 1024];
+        let mut large = Message::new("TopicTest", payload.as_slice());
+        MessageClientIDSetter::set_uniq_id(&mut large);
+        assert!(claim_check.offload(&mut large).unwrap());
+        let uri = String::from_utf8(large.get_body().unwrap().to_vec()).unwrap();
+        assert!(uri.starts_with("mem://TopicTest/"));
+        assert_eq!(blob_store.blobs.lock().len(), 1);
+
+        assert!(claim_check.restore(&mut large).unwrap());
+        assert_eq!(large.get_body().unwrap().as_ref(), payload.as_slice());
+        assert!(large.get_property(PROPERTY_CLAIM_CHECK_BODY_SIZE).is_none());
+    }
+}
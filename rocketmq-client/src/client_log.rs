@@ -0,0 +1,262 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::fs;
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::io;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::atomic::AtomicU8;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use tracing::level_filters::LevelFilter;
+use tracing::subscriber::Interest;
+use tracing::Metadata;
+use tracing::Subscriber;
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::layer::Filter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::Layer;
+
+use crate::base::client_config::ClientConfig;
+
+/// Targets of the logs written on behalf of the client, the remoting and common crates log
+/// the client's connections and messages.
+const CLIENT_LOG_TARGETS: [&str; 3] = ["rocketmq_client", "rocketmq_remoting", "rocketmq_common"];
+
+static CLIENT_LOG_LEVEL: AtomicU8 = AtomicU8::new(encode_level(LevelFilter::INFO));
+
+/// Changes the level of the client logs at runtime, for every client of the process and
+/// without touching the logs of the application.
+pub fn set_log_level(level: LevelFilter) {
+    CLIENT_LOG_LEVEL.store(encode_level(level), Ordering::Release);
+}
+
+pub fn log_level() -> LevelFilter {
+    match CLIENT_LOG_LEVEL.load(Ordering::Acquire) {
+        0 => LevelFilter::OFF,
+        1 => LevelFilter::ERROR,
+        2 => LevelFilter::WARN,
+        3 => LevelFilter::INFO,
+        4 => LevelFilter::DEBUG,
+        _ => LevelFilter::TRACE,
+    }
+}
+
+const fn encode_level(level: LevelFilter) -> u8 {
+    match level {
+        LevelFilter::OFF => 0,
+        LevelFilter::ERROR => 1,
+        LevelFilter::WARN => 2,
+        LevelFilter::INFO => 3,
+        LevelFilter::DEBUG => 4,
+        _ => 5,
+    }
+}
+
+fn is_client_target(target: &str) -> bool {
+    CLIENT_LOG_TARGETS.iter().any(|client_target| {
+        target
+            .strip_prefix(client_target)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+    })
+}
+
+/// Per-layer filter letting only the client logs through, at the level of [`log_level`].
+/// The level is checked on every event so [`set_log_level`] applies immediately.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ClientLogFilter;
+
+impl<S> Filter<S> for ClientLogFilter {
+    fn enabled(&self, meta: &Metadata<'_>, _cx: &Context<'_, S>) -> bool {
+        is_client_target(meta.target()) && *meta.level() <= log_level()
+    }
+
+    fn callsite_enabled(&self, meta: &'static Metadata<'static>) -> Interest {
+        if is_client_target(meta.target()) {
+            Interest::sometimes()
+        } else {
+            Interest::never()
+        }
+    }
+}
+
+/// Layer writing the client logs to the rolling file configured by `config.log_root`, or to
+/// stdout when none is. Events of a client instance carry its `client{client_id=..}` span,
+/// so clients embedded in one process can be told apart. Applications add the layer to
+/// their own subscriber; it also sets the client log level from `config.log_level`.
+pub fn client_log_layer<S>(config: &ClientConfig) -> io::Result<Box<dyn Layer<S> + Send + Sync>>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    set_log_level(
+        config
+            .log_level
+            .parse::<LevelFilter>()
+            .unwrap_or(LevelFilter::INFO),
+    );
+    let layer = match config.log_root.as_deref() {
+        Some(log_root) => {
+            let writer = RollingFileWriter::new(
+                Path::new(log_root).join(config.log_file_name.as_str()),
+                config.log_file_max_size,
+                config.log_file_max_index,
+            )?;
+            tracing_subscriber::fmt::layer()
+                .with_ansi(false)
+                .with_thread_names(true)
+                .with_writer(Arc::new(writer))
+                .with_filter(ClientLogFilter)
+                .boxed()
+        }
+        None => tracing_subscriber::fmt::layer()
+            .with_thread_names(true)
+            .with_filter(ClientLogFilter)
+            .boxed(),
+    };
+    Ok(layer)
+}
+
+/// Installs a global subscriber with only the [`client_log_layer`]. Returns `false` if the
+/// application installed one already, add the layer to that one instead.
+pub fn init_client_logger(config: &ClientConfig) -> io::Result<bool> {
+    Ok(tracing_subscriber::registry()
+        .with(client_log_layer(config)?)
+        .try_init()
+        .is_ok())
+}
+
+/// Log file rolled by size: when a write would take it past `max_size` it is renamed to
+/// `<file>.1`, the older ones shifted up to `<file>.<max_index>` and the oldest dropped.
+pub struct RollingFileWriter {
+    path: PathBuf,
+    max_size: u64,
+    max_index: u32,
+    file: Mutex<RollingFile>,
+}
+
+struct RollingFile {
+    file: File,
+    size: u64,
+}
+
+impl RollingFileWriter {
+    pub fn new(path: impl Into<PathBuf>, max_size: u64, max_index: u32) -> io::Result<Self> {
+        let path = path.into();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let file = open_append(&path)?;
+        let size = file.metadata()?.len();
+        Ok(Self {
+            path,
+            max_size,
+            max_index,
+            file: Mutex::new(RollingFile { file, size }),
+        })
+    }
+
+    fn rolled_path(&self, index: u32) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".{}", index));
+        path.into()
+    }
+
+    fn roll(&self, current: &mut RollingFile) -> io::Result<()> {
+        current.file.flush()?;
+        if self.max_index == 0 {
+            current.file = File::create(&self.path)?;
+        } else {
+            let oldest = self.rolled_path(self.max_index);
+            if oldest.exists() {
+                fs::remove_file(oldest)?;
+            }
+            for index in (1..self.max_index).rev() {
+                let rolled = self.rolled_path(index);
+                if rolled.exists() {
+                    fs::rename(rolled, self.rolled_path(index + 1))?;
+                }
+            }
+            fs::rename(&self.path, self.rolled_path(1))?;
+            current.file = open_append(&self.path)?;
+        }
+        current.size = 0;
+        Ok(())
+    }
+}
+
+impl Write for &RollingFileWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut current = self.file.lock();
+        if current.size > 0 && current.size + buf.len() as u64 > self.max_size {
+            self.roll(&mut current)?;
+        }
+        let written = current.file.write(buf)?;
+        current.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.lock().file.flush()
+    }
+}
+
+fn open_append(path: &Path) -> io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rolls_the_log_file_by_size() {
+        let dir = std::env::temp_dir().join(format!("rocketmq_client_log_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let writer = RollingFileWriter::new(dir.join("client.log"), 10, 2).unwrap();
+        for line in ["first\n", "second\n", "third\n", "fourth\n"] {
+            (&writer).write_all(line.as_bytes()).unwrap();
+        }
+        assert_eq!(
+            fs::read_to_string(dir.join("client.log")).unwrap(),
+            "fourth\n"
+        );
+        assert_eq!(
+            fs::read_to_string(dir.join("client.log.1")).unwrap(),
+            "third\n"
+        );
+        assert_eq!(
+            fs::read_to_string(dir.join("client.log.2")).unwrap(),
+            "second\n"
+        );
+        assert!(!dir.join("client.log.3").exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn filters_client_targets() {
+        assert!(is_client_target("rocketmq_client"));
+        assert!(is_client_target("rocketmq_remoting::clients"));
+        assert!(!is_client_target("rocketmq_client_ext"));
+        assert!(!is_client_target("app::service"));
+    }
+}
@@ -16,3 +16,4 @@
  */
 pub mod client_error_code;
 pub mod thread_local_index;
+pub(crate) mod token_bucket;
@@ -24,4 +24,5 @@ impl ClientErrorCode {
     pub const NOT_FOUND_TOPIC_EXCEPTION: i32 = 10005;
     pub const REQUEST_TIMEOUT_EXCEPTION: i32 = 10006;
     pub const CREATE_REPLY_MESSAGE_EXCEPTION: i32 = 10007;
+    pub const SEND_RATE_LIMITED_EXCEPTION: i32 = 10008;
 }
@@ -0,0 +1,77 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::time::Duration;
+use std::time::Instant;
+
+/// Token bucket refilled at `permits_per_second`, holding at most one second of permits.
+pub(crate) struct TokenBucket {
+    permits_per_second: f64,
+    available_permits: f64,
+    last_refill_time: Instant,
+}
+
+impl TokenBucket {
+    pub(crate) fn new(permits_per_second: u32) -> Self {
+        TokenBucket {
+            permits_per_second: permits_per_second as f64,
+            available_permits: permits_per_second as f64,
+            last_refill_time: Instant::now(),
+        }
+    }
+
+    /// Takes `permits` permits, or returns how long the caller has to wait for them. Requests
+    /// larger than the bucket capacity are capped to the capacity.
+    pub(crate) fn try_acquire(&mut self, permits: u32) -> Result<(), Duration> {
+        let permits = (permits as f64).min(self.permits_per_second);
+        let now = Instant::now();
+        let elapsed = now
+            .saturating_duration_since(self.last_refill_time)
+            .as_secs_f64();
+        self.available_permits = (self.available_permits + elapsed * self.permits_per_second)
+            .min(self.permits_per_second);
+        self.last_refill_time = now;
+        if self.available_permits >= permits {
+            self.available_permits -= permits;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64(
+                (permits - self.available_permits) / self.permits_per_second,
+            ))
+        }
+    }
+
+    /// Gives back `permits` taken for a request that did not go through.
+    pub(crate) fn release(&mut self, permits: u32) {
+        self.available_permits =
+            (self.available_permits + permits as f64).min(self.permits_per_second);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn token_bucket_capacity_is_one_second_of_permits() {
+        let mut bucket = TokenBucket::new(5);
+        assert!(bucket.try_acquire(3).is_ok());
+        assert!(bucket.try_acquire(2).is_ok());
+        let wait = bucket.try_acquire(1).unwrap_err();
+        assert!(wait <= Duration::from_millis(200));
+        assert!(bucket.try_acquire(100).is_err());
+    }
+}
@@ -14,5 +14,13 @@
  * See the License for the specific language governing permissions and
  * limitations under the License.
  */
+pub mod allocate_message_queue_strategy;
 pub(crate) mod consumer_impl;
+pub mod consumer_lag;
+pub mod consumer_stats_manager;
+pub mod default_mq_push_consumer;
+pub mod default_mq_push_consumer_builder;
+pub mod message_queue_listener;
 pub(crate) mod mq_consumer_inner;
+pub mod rebalance_strategy;
+pub mod store;
@@ -0,0 +1,37 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use rocketmq_common::common::message::message_queue::MessageQueue;
+
+use crate::Result;
+
+/// Strategy deciding which queues of a topic the consumer `current_cid` pulls from.
+///
+/// Every consumer of the group runs the strategy on its own, so an implementation must give
+/// the same division of `mq_all` among `cid_all` on each of them. Callers pass both lists
+/// sorted.
+pub trait AllocateMessageQueueStrategy: Send + Sync {
+    fn allocate(
+        &self,
+        consumer_group: &str,
+        current_cid: &str,
+        mq_all: &[MessageQueue],
+        cid_all: &[String],
+    ) -> Result<Vec<MessageQueue>>;
+
+    /// Name of the strategy, reported in consumer running info.
+    fn get_name(&self) -> &'static str;
+}
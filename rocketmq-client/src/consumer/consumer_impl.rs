@@ -14,5 +14,12 @@
  * See the License for the specific language governing permissions and
  * limitations under the License.
  */
+pub(crate) mod consume_flow_control;
+pub(crate) mod default_mq_push_consumer_impl;
+pub(crate) mod graceful_shutdown;
+pub(crate) mod message_queue_listener_table;
+pub(crate) mod process_queue;
 pub(crate) mod pull_message_service;
+pub(crate) mod rebalance_impl;
 pub(crate) mod rebalance_service;
+pub(crate) mod subscription_table;
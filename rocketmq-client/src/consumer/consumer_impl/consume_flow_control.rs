@@ -0,0 +1,318 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+
+use parking_lot::Mutex;
+use rocketmq_common::common::message::message_queue::MessageQueue;
+use rocketmq_runtime::RocketMQRuntime;
+use tokio::sync::OwnedSemaphorePermit;
+use tokio::sync::Semaphore;
+
+use crate::common::token_bucket::TokenBucket;
+use crate::consumer::message_queue_listener::MessageQueueListener;
+use crate::error::MQClientError;
+use crate::Result;
+
+#[derive(Debug, Clone)]
+pub struct ConsumeFlowControlConfig {
+    /// Number of worker threads running the listener.
+    pub consume_thread_min: u32,
+    /// Maximum number of message batches consumed concurrently.
+    pub consume_thread_max: u32,
+    /// Maximum number of batches of one message queue consumed concurrently, 0 means
+    /// unlimited.
+    pub consume_concurrency_per_queue: u32,
+    /// Maximum number of messages dispatched to the listener per second, 0 means unlimited.
+    pub consume_max_tps: u32,
+    /// How long shutdown waits for the batches handed to the listener to finish, 0 means not
+    /// to wait.
+    pub await_termination_millis_when_shutdown: u64,
+}
+
+impl Default for ConsumeFlowControlConfig {
+    fn default() -> Self {
+        ConsumeFlowControlConfig {
+            consume_thread_min: 20,
+            consume_thread_max: 20,
+            consume_concurrency_per_queue: 0,
+            consume_max_tps: 0,
+            await_termination_millis_when_shutdown: 0,
+        }
+    }
+}
+
+impl ConsumeFlowControlConfig {
+    /// Dispatches one batch per message queue at a time, as orderly consumption requires. An
+    /// orderly POP consumer acks a batch before the broker delivers the next one of its queue.
+    pub fn orderly() -> Self {
+        ConsumeFlowControlConfig {
+            consume_concurrency_per_queue: 1,
+            ..Default::default()
+        }
+    }
+
+    pub fn check_config(&self) -> Result<()> {
+        if self.consume_thread_min < 1 || self.consume_thread_min > 1000 {
+            return Err(MQClientError::IllegalArgument(
+                "consumeThreadMin Out of range [1, 1000]".to_string(),
+            ));
+        }
+        if self.consume_thread_max < 1 || self.consume_thread_max > 1000 {
+            return Err(MQClientError::IllegalArgument(
+                "consumeThreadMax Out of range [1, 1000]".to_string(),
+            ));
+        }
+        if self.consume_thread_min > self.consume_thread_max {
+            return Err(MQClientError::IllegalArgument(format!(
+                "consumeThreadMin ({}) is larger than consumeThreadMax ({})",
+                self.consume_thread_min, self.consume_thread_max
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Held while a batch is handed to the listener, releases the concurrency slots on drop.
+pub struct ConsumePermit {
+    _consume_permit: OwnedSemaphorePermit,
+    _queue_permit: Option<OwnedSemaphorePermit>,
+}
+
+/// Gate in front of the message listener: caps the number of batches consumed concurrently,
+/// overall and per message queue, and the number of messages dispatched per second.
+///
+/// Each push consumer owns one, sized from its consume thread settings. Its rebalance drops the
+/// state of the queues taken away, and its shutdown waits for the permits handed out.
+pub struct ConsumeFlowControl {
+    config: ConsumeFlowControlConfig,
+    consume_semaphore: Arc<Semaphore>,
+    queue_semaphores: Mutex<HashMap<MessageQueue, Arc<Semaphore>>>,
+    rate_limiter: Option<Mutex<TokenBucket>>,
+    stopped: AtomicBool,
+}
+
+impl ConsumeFlowControl {
+    pub fn new(config: ConsumeFlowControlConfig) -> Self {
+        ConsumeFlowControl {
+            consume_semaphore: Arc::new(Semaphore::new(config.consume_thread_max as usize)),
+            queue_semaphores: Mutex::new(HashMap::new()),
+            rate_limiter: (config.consume_max_tps > 0)
+                .then(|| Mutex::new(TokenBucket::new(config.consume_max_tps))),
+            stopped: AtomicBool::new(false),
+            config,
+        }
+    }
+
+    pub fn config(&self) -> &ConsumeFlowControlConfig {
+        &self.config
+    }
+
+    /// Runtime running the listener, sized by `consume_thread_min`. The number of batches in
+    /// flight is bounded by `consume_thread_max` through [`ConsumeFlowControl::acquire`].
+    pub fn new_consume_runtime(&self) -> RocketMQRuntime {
+        RocketMQRuntime::new_multi(self.config.consume_thread_min as usize, "consume-message")
+    }
+
+    /// Waits until a batch of `msg_count` messages from `mq` may be dispatched to the
+    /// listener, `None` once the consumer is shutting down.
+    pub async fn acquire(&self, mq: &MessageQueue, msg_count: u32) -> Option<ConsumePermit> {
+        if self.stopped.load(Ordering::Acquire) {
+            return None;
+        }
+        let queue_semaphore = if self.config.consume_concurrency_per_queue > 0 {
+            Some(
+                self.queue_semaphores
+                    .lock()
+                    .entry(mq.clone())
+                    .or_insert_with(|| {
+                        Arc::new(Semaphore::new(
+                            self.config.consume_concurrency_per_queue as usize,
+                        ))
+                    })
+                    .clone(),
+            )
+        } else {
+            None
+        };
+        // Semaphores are never closed, acquiring can not fail
+        let queue_permit = match queue_semaphore {
+            Some(semaphore) => Some(semaphore.acquire_owned().await.unwrap()),
+            None => None,
+        };
+        let consume_permit = self
+            .consume_semaphore
+            .clone()
+            .acquire_owned()
+            .await
+            .unwrap();
+        if self.stopped.load(Ordering::Acquire) {
+            return None;
+        }
+        if let Some(rate_limiter) = self.rate_limiter.as_ref() {
+            loop {
+                let wait = match rate_limiter.lock().try_acquire(msg_count) {
+                    Ok(_) => break,
+                    Err(wait) => wait,
+                };
+                tokio::time::sleep(wait).await;
+            }
+        }
+        Some(ConsumePermit {
+            _consume_permit: consume_permit,
+            _queue_permit: queue_permit,
+        })
+    }
+
+    /// Stops dispatching to the listener and waits up to `await_termination_millis_when_shutdown`
+    /// for the batches in flight to finish. Returns whether none is left in flight.
+    pub async fn shutdown(&self) -> bool {
+        self.stopped.store(true, Ordering::Release);
+        let consume_thread_max = self.config.consume_thread_max;
+        let drained = self.consume_semaphore.acquire_many(consume_thread_max);
+        tokio::time::timeout(
+            Duration::from_millis(self.config.await_termination_millis_when_shutdown),
+            drained,
+        )
+        .await
+        .is_ok()
+    }
+
+    /// Number of batches of `mq` that can still be dispatched without waiting.
+    pub fn available_queue_permits(&self, mq: &MessageQueue) -> Option<usize> {
+        if self.config.consume_concurrency_per_queue == 0 {
+            return None;
+        }
+        Some(self.queue_semaphores.lock().get(mq).map_or(
+            self.config.consume_concurrency_per_queue as usize,
+            |semaphore| semaphore.available_permits(),
+        ))
+    }
+
+    /// Drops the state kept for a message queue no longer assigned to this consumer.
+    pub fn remove_message_queue(&self, mq: &MessageQueue) {
+        self.queue_semaphores.lock().remove(mq);
+    }
+}
+
+impl MessageQueueListener for ConsumeFlowControl {
+    fn message_queue_changed(
+        &self,
+        topic: &str,
+        _mq_all: &HashSet<MessageQueue>,
+        mq_divided: &HashSet<MessageQueue>,
+    ) {
+        self.queue_semaphores
+            .lock()
+            .retain(|mq, _| mq.get_topic() != topic || mq_divided.contains(mq));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use super::*;
+
+    #[test]
+    fn check_consume_thread_range() {
+        assert!(ConsumeFlowControlConfig::default().check_config().is_ok());
+        let config = ConsumeFlowControlConfig {
+            consume_thread_min: 30,
+            consume_thread_max: 20,
+            ..Default::default()
+        };
+        assert!(config.check_config().is_err());
+        let config = ConsumeFlowControlConfig {
+            consume_thread_max: 1001,
+            ..Default::default()
+        };
+        assert!(config.check_config().is_err());
+    }
+
+    #[tokio::test]
+    async fn per_queue_concurrency_cap() {
+        let flow_control = ConsumeFlowControl::new(ConsumeFlowControlConfig::orderly());
+        let mq = MessageQueue::from_parts("TopicTest", "broker-a", 0);
+        let other = MessageQueue::from_parts("TopicTest", "broker-a", 1);
+        let permit = flow_control.acquire(&mq, 1).await.unwrap();
+        assert_eq!(flow_control.available_queue_permits(&mq), Some(0));
+        assert!(
+            tokio::time::timeout(Duration::from_millis(50), flow_control.acquire(&mq, 1))
+                .await
+                .is_err()
+        );
+        let _other_permit = flow_control.acquire(&other, 1).await.unwrap();
+        drop(permit);
+        assert_eq!(flow_control.available_queue_permits(&mq), Some(1));
+    }
+
+    #[tokio::test]
+    async fn rebalance_drops_state_of_queues_taken_away() {
+        let flow_control = ConsumeFlowControl::new(ConsumeFlowControlConfig::orderly());
+        let mq = MessageQueue::from_parts("TopicTest", "broker-a", 0);
+        let other = MessageQueue::from_parts("OtherTopic", "broker-a", 0);
+        let _permit = flow_control.acquire(&mq, 1).await.unwrap();
+        let _other_permit = flow_control.acquire(&other, 1).await.unwrap();
+
+        let mq_all = HashSet::from([mq.clone()]);
+        flow_control.message_queue_changed("TopicTest", &mq_all, &HashSet::new());
+        assert_eq!(flow_control.available_queue_permits(&mq), Some(1));
+        assert_eq!(flow_control.available_queue_permits(&other), Some(0));
+    }
+
+    #[tokio::test]
+    async fn consume_rate_limit() {
+        let flow_control = ConsumeFlowControl::new(ConsumeFlowControlConfig {
+            consume_max_tps: 20,
+            ..Default::default()
+        });
+        let mq = MessageQueue::from_parts("TopicTest", "broker-a", 0);
+        let start = Instant::now();
+        for _ in 0..3 {
+            let _permit = flow_control.acquire(&mq, 10).await;
+        }
+        assert!(start.elapsed() >= Duration::from_millis(400));
+    }
+
+    #[tokio::test]
+    async fn shutdown_awaits_batches_in_flight() {
+        let flow_control = Arc::new(ConsumeFlowControl::new(ConsumeFlowControlConfig {
+            await_termination_millis_when_shutdown: 1000,
+            ..Default::default()
+        }));
+        let mq = MessageQueue::from_parts("TopicTest", "broker-a", 0);
+        let permit = flow_control.acquire(&mq, 1).await.unwrap();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            drop(permit);
+        });
+        assert!(flow_control.shutdown().await);
+        assert!(flow_control.acquire(&mq, 1).await.is_none());
+
+        let flow_control = ConsumeFlowControl::new(ConsumeFlowControlConfig {
+            await_termination_millis_when_shutdown: 50,
+            ..Default::default()
+        });
+        let _permit = flow_control.acquire(&mq, 1).await.unwrap();
+        assert!(!flow_control.shutdown().await);
+    }
+}
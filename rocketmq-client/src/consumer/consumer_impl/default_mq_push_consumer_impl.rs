@@ -0,0 +1,638 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use rocketmq_common::common::base::service_state::ServiceState;
+use rocketmq_common::common::consumer::consume_from_where::ConsumeFromWhere;
+use rocketmq_common::common::message::message_queue::MessageQueue;
+use rocketmq_common::common::FAQUrl;
+use rocketmq_common::ArcRefCellWrapper;
+use rocketmq_remoting::protocol::body::consumer_running_info::ConsumerRunningInfo;
+use rocketmq_remoting::protocol::heartbeat::consume_type::ConsumeType;
+use rocketmq_remoting::protocol::heartbeat::message_model::MessageModel;
+use rocketmq_remoting::protocol::heartbeat::subscription_data::SubscriptionData;
+use rocketmq_remoting::runtime::RPCHook;
+use tracing::info;
+
+use crate::base::client_config::ClientConfig;
+use crate::base::validators::Validators;
+use crate::consumer::consumer_impl::consume_flow_control::ConsumeFlowControl;
+use crate::consumer::consumer_impl::consume_flow_control::ConsumeFlowControlConfig;
+use crate::consumer::consumer_impl::graceful_shutdown::shutdown_gracefully;
+use crate::consumer::consumer_impl::message_queue_listener_table::MessageQueueListenerTable;
+use crate::consumer::consumer_impl::rebalance_impl::RebalanceImpl;
+use crate::consumer::consumer_impl::subscription_table::SubscriptionTable;
+use crate::consumer::consumer_lag::ConsumerLagMetrics;
+use crate::consumer::default_mq_push_consumer::ConsumerConfig;
+use crate::consumer::message_queue_listener::MessageQueueListener;
+use crate::consumer::mq_consumer_inner::MQConsumerInner;
+use crate::consumer::mq_consumer_inner::RebalanceFuture;
+use crate::consumer::store::offset_persist_service::OffsetPersistService;
+use crate::consumer::store::offset_store::OffsetStore;
+use crate::error::MQClientError;
+use crate::factory::mq_client_instance::MQClientInstance;
+use crate::implementation::mq_client_manager::MQClientManager;
+use crate::Result;
+
+#[derive(Clone)]
+pub struct DefaultMQPushConsumerImpl {
+    client_config: ClientConfig,
+    consumer_config: Arc<ConsumerConfig>,
+    rpc_hook: Option<Arc<Box<dyn RPCHook>>>,
+    service_state: ServiceState,
+    subscription_table: Arc<SubscriptionTable>,
+    message_queue_listener_table: MessageQueueListenerTable,
+    rebalance_impl: RebalanceImpl,
+    consumer_lag_metrics: ConsumerLagMetrics,
+    flow_control: Arc<ConsumeFlowControl>,
+    offset_persist_service: OffsetPersistService,
+    client_instance: Option<ArcRefCellWrapper<MQClientInstance>>,
+}
+
+impl DefaultMQPushConsumerImpl {
+    pub fn new(
+        client_config: ClientConfig,
+        consumer_config: ConsumerConfig,
+        rpc_hook: Option<Arc<Box<dyn RPCHook>>>,
+    ) -> Self {
+        let subscription_table = Arc::new(SubscriptionTable::new());
+        let message_queue_listener_table = MessageQueueListenerTable::new();
+        let consumer_lag_metrics =
+            ConsumerLagMetrics::new(consumer_config.consumer_group.as_str(), &client_config);
+        let flow_control = Arc::new(ConsumeFlowControl::new(ConsumeFlowControlConfig {
+            consume_thread_min: consumer_config.consume_thread_min,
+            consume_thread_max: consumer_config.consume_thread_max,
+            consume_concurrency_per_queue: u32::from(consumer_config.consume_orderly),
+            await_termination_millis_when_shutdown: consumer_config
+                .await_termination_millis_when_shutdown,
+            ..Default::default()
+        }));
+        let offset_persist_service = OffsetPersistService::in_memory(
+            consumer_config.consumer_group.as_str(),
+            &client_config,
+        );
+        let rebalance_impl = RebalanceImpl::new(
+            consumer_config.consumer_group.as_str(),
+            consumer_config.message_model,
+            consumer_config.allocate_message_queue_strategy.clone(),
+            subscription_table.clone(),
+            message_queue_listener_table.clone(),
+            consumer_lag_metrics.clone(),
+            flow_control.clone(),
+            offset_persist_service.clone(),
+        );
+        Self {
+            client_config,
+            consumer_config: Arc::new(consumer_config),
+            rpc_hook,
+            service_state: ServiceState::CreateJust,
+            subscription_table,
+            message_queue_listener_table,
+            rebalance_impl,
+            consumer_lag_metrics,
+            flow_control,
+            offset_persist_service,
+            client_instance: None,
+        }
+    }
+
+    /// Keeps the consume progress in `offset_store` instead of in memory, takes effect on the
+    /// next start.
+    pub fn set_offset_store(&mut self, offset_store: Box<dyn OffsetStore>) {
+        self.offset_persist_service = OffsetPersistService::new(
+            self.consumer_config.consumer_group.as_str(),
+            offset_store,
+            &self.client_config,
+        );
+        self.rebalance_impl
+            .set_offset_persist_service(self.offset_persist_service.clone());
+    }
+
+    pub fn client_config(&self) -> &ClientConfig {
+        &self.client_config
+    }
+
+    pub fn consumer_config(&self) -> &ConsumerConfig {
+        &self.consumer_config
+    }
+
+    pub async fn start(&mut self) -> Result<()> {
+        match self.service_state {
+            ServiceState::CreateJust => {
+                info!(
+                    "the consumer [{}] start beginning. messageModel={:?}, isUnitMode={}",
+                    self.consumer_config.consumer_group,
+                    self.consumer_config.message_model,
+                    self.client_config.unit_mode
+                );
+                self.service_state = ServiceState::StartFailed;
+                self.check_config()?;
+                self.copy_subscription()?;
+                self.offset_persist_service.start()?;
+                if self.consumer_config.message_model == MessageModel::Clustering {
+                    self.client_config.change_instance_name_to_pid();
+                }
+                let client_instance = MQClientManager::get_instance()
+                    .get_or_create_mq_client_instance(
+                        self.client_config.clone(),
+                        self.rpc_hook.clone(),
+                    )
+                    .await;
+                self.rebalance_impl
+                    .set_client_instance(client_instance.clone());
+                self.client_instance = Some(client_instance);
+                let self_clone = self.clone();
+                let register_ok = self
+                    .client_instance
+                    .as_mut()
+                    .unwrap()
+                    .register_consumer(self.consumer_config.consumer_group.as_str(), self_clone)
+                    .await;
+                if !register_ok {
+                    self.offset_persist_service.shutdown();
+                    self.service_state = ServiceState::CreateJust;
+                    return Err(MQClientError::IllegalState(format!(
+                        "The consumer group[{}] has been created before, specify another name \
+                         please. {}",
+                        self.consumer_config.consumer_group,
+                        FAQUrl::suggest_todo(FAQUrl::GROUP_NAME_DUPLICATE_URL)
+                    )));
+                }
+                Box::pin(self.client_instance.as_mut().unwrap().start()).await?;
+                self.consumer_lag_metrics
+                    .start((**self.client_instance.as_ref().unwrap()).clone());
+                info!(
+                    "the consumer [{}] start OK.",
+                    self.consumer_config.consumer_group
+                );
+                self.service_state = ServiceState::Running;
+            }
+            ServiceState::Running | ServiceState::StartFailed | ServiceState::ShutdownAlready => {
+                return Err(MQClientError::IllegalState(format!(
+                    "The PushConsumer service state not OK, maybe started once, {:?}, {}",
+                    self.service_state,
+                    FAQUrl::suggest_todo(FAQUrl::CLIENT_SERVICE_NOT_OK)
+                )));
+            }
+        }
+        let client_instance = self.client_instance.as_mut().unwrap();
+        for topic in self.subscription_table.topics() {
+            client_instance
+                .update_topic_route_info_from_name_server_topic(topic.as_str())
+                .await;
+        }
+        client_instance
+            .send_heartbeat_to_all_broker_with_lock()
+            .await;
+        client_instance.re_balance_immediately().await;
+        Ok(())
+    }
+
+    /// Stops dispatching to the listener and waits for the messages in flight, then persists
+    /// the offsets and unregisters the consumer, so the consumers taking the queues over start
+    /// after the messages consumed here.
+    pub async fn shutdown(&mut self) {
+        if let ServiceState::Running = self.service_state {
+            self.consumer_lag_metrics.shutdown();
+            let client_instance = self.client_instance.as_mut().unwrap();
+            shutdown_gracefully(
+                self.consumer_config.consumer_group.as_str(),
+                &self.flow_control,
+                &self.offset_persist_service,
+                client_instance,
+            )
+            .await;
+            client_instance.shutdown().await;
+            info!(
+                "the consumer [{}] shutdown OK",
+                self.consumer_config.consumer_group
+            );
+            self.service_state = ServiceState::ShutdownAlready;
+        }
+    }
+
+    fn check_config(&self) -> Result<()> {
+        Validators::check_consumer_config(&self.consumer_config)
+    }
+
+    fn copy_subscription(&self) -> Result<()> {
+        for (topic, sub_expression) in self.consumer_config.subscription.iter() {
+            self.subscription_table
+                .subscribe(topic.as_str(), sub_expression.as_str())?;
+        }
+        Ok(())
+    }
+
+    pub async fn subscribe(&mut self, topic: &str, sub_expression: &str) -> Result<()> {
+        match (self.service_state, self.client_instance.as_mut()) {
+            (ServiceState::Running, Some(client_instance)) => {
+                client_instance
+                    .subscribe(
+                        self.consumer_config.consumer_group.as_str(),
+                        topic,
+                        sub_expression,
+                    )
+                    .await
+            }
+            _ => self
+                .subscription_table
+                .subscribe(topic, sub_expression)
+                .map(|_| ()),
+        }
+    }
+
+    pub async fn unsubscribe(&mut self, topic: &str) -> bool {
+        match (self.service_state, self.client_instance.as_mut()) {
+            (ServiceState::Running, Some(client_instance)) => {
+                client_instance
+                    .unsubscribe(self.consumer_config.consumer_group.as_str(), topic)
+                    .await
+            }
+            _ => self.subscription_table.unsubscribe(topic).is_some(),
+        }
+    }
+
+    pub fn register_message_queue_listener(
+        &self,
+        topic: impl Into<String>,
+        listener: Arc<dyn MessageQueueListener>,
+    ) {
+        self.message_queue_listener_table
+            .register_message_queue_listener(topic, listener);
+    }
+
+    /// Queues owned by the consumer per topic, as of the last rebalance.
+    pub fn allocation(&self) -> HashMap<String /* topic */, HashSet<MessageQueue>> {
+        self.rebalance_impl.allocation()
+    }
+
+    pub fn consumer_lag_metrics(&self) -> &ConsumerLagMetrics {
+        &self.consumer_lag_metrics
+    }
+
+    /// Rebalances the consumer through its client instance and returns the queues it owns
+    /// afterwards.
+    pub async fn rebalance_now(&self) -> Result<HashMap<String, HashSet<MessageQueue>>> {
+        let client_instance = match (self.service_state, self.client_instance.as_ref()) {
+            (ServiceState::Running, Some(client_instance)) => client_instance,
+            _ => {
+                return Err(MQClientError::IllegalState(format!(
+                    "The consumer service state not OK, {:?}",
+                    self.service_state
+                )))
+            }
+        };
+        client_instance
+            .rebalance_now(self.consumer_config.consumer_group.as_str())
+            .await
+            .ok_or_else(|| {
+                MQClientError::IllegalState(format!(
+                    "The consumer group[{}] is not registered",
+                    self.consumer_config.consumer_group
+                ))
+            })
+    }
+}
+
+impl MQConsumerInner for DefaultMQPushConsumerImpl {
+    fn group_name(&self) -> &str {
+        self.consumer_config.consumer_group.as_str()
+    }
+
+    fn message_model(&self) -> MessageModel {
+        self.consumer_config.message_model
+    }
+
+    fn consume_type(&self) -> ConsumeType {
+        ConsumeType::ConsumePassively
+    }
+
+    fn consume_from_where(&self) -> ConsumeFromWhere {
+        self.consumer_config.consume_from_where
+    }
+
+    fn subscriptions(&self) -> HashSet<SubscriptionData> {
+        self.subscription_table.subscriptions()
+    }
+
+    fn subscribe(&self, topic: &str, sub_expression: &str) -> Result<()> {
+        self.subscription_table
+            .subscribe(topic, sub_expression)
+            .map(|_| ())
+    }
+
+    fn unsubscribe(&self, topic: &str) -> bool {
+        self.subscription_table.unsubscribe(topic).is_some()
+    }
+
+    fn do_rebalance(&self) -> RebalanceFuture<'_, ()> {
+        Box::pin(async {
+            self.rebalance_impl.do_rebalance().await;
+        })
+    }
+
+    fn try_rebalance(&self) -> RebalanceFuture<'_, bool> {
+        Box::pin(self.rebalance_impl.do_rebalance())
+    }
+
+    fn allocation(&self) -> HashMap<String, HashSet<MessageQueue>> {
+        DefaultMQPushConsumerImpl::allocation(self)
+    }
+
+    fn persist_consumer_offset(&self) {
+        self.offset_persist_service.persist_all();
+    }
+
+    fn update_topic_subscribe_info(&mut self, topic: &str, info: &HashSet<MessageQueue>) {
+        if self.subscription_table.get(topic).is_some() {
+            self.rebalance_impl
+                .topic_subscribe_info_table()
+                .write()
+                .insert(topic.to_string(), info.clone());
+        }
+    }
+
+    fn is_subscribe_topic_need_update(&self, topic: &str) -> bool {
+        self.subscription_table.get(topic).is_some()
+            && !self
+                .rebalance_impl
+                .topic_subscribe_info_table()
+                .read()
+                .contains_key(topic)
+    }
+
+    fn is_unit_mode(&self) -> bool {
+        self.client_config.unit_mode
+    }
+
+    fn consumer_running_info(&self) -> ConsumerRunningInfo {
+        ConsumerRunningInfo {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use rocketmq_common::common::mix_all;
+    use rocketmq_remoting::code::request_code::RequestCode;
+    use rocketmq_remoting::net::channel::Channel;
+    use rocketmq_remoting::protocol::body::get_consumer_listby_group_response_body::GetConsumerListByGroupResponseBody;
+    use rocketmq_remoting::protocol::remoting_command::RemotingCommand;
+    use rocketmq_remoting::protocol::route::route_data_view::BrokerData;
+    use rocketmq_remoting::protocol::route::topic_route_data::TopicRouteData;
+    use rocketmq_remoting::protocol::RemotingSerializable;
+    use rocketmq_remoting::remoting_server::flow_control::RequestFlowController;
+    use rocketmq_remoting::runtime::connection_handler_context::ConnectionHandlerContext;
+    use rocketmq_remoting::runtime::processor::RequestProcessor;
+    use tokio::net::TcpListener;
+
+    use super::*;
+    use crate::consumer::store::memory_offset_store::MemoryOffsetStore;
+    use crate::consumer::store::read_offset_type::ReadOffsetType;
+
+    /// Answers the consumer list requests with `consumer_id_list`.
+    #[derive(Clone, Default)]
+    struct ConsumerListBroker {
+        consumer_id_list: Arc<parking_lot::Mutex<Vec<String>>>,
+    }
+
+    impl RequestProcessor for ConsumerListBroker {
+        async fn process_request(
+            &mut self,
+            _channel: Channel,
+            _ctx: ConnectionHandlerContext,
+            request: RemotingCommand,
+        ) -> rocketmq_remoting::Result<Option<RemotingCommand>> {
+            assert_eq!(
+                RequestCode::from(request.code()),
+                RequestCode::GetConsumerListByGroup
+            );
+            let body = GetConsumerListByGroupResponseBody {
+                consumer_id_list: self.consumer_id_list.lock().clone(),
+                ..Default::default()
+            };
+            Ok(Some(
+                RemotingCommand::create_response_command().set_body(Some(body.encode())),
+            ))
+        }
+    }
+
+    #[test]
+    fn rebalance_now_picks_up_a_new_consumer_of_the_group() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let broker = ConsumerListBroker::default();
+        broker
+            .consumer_id_list
+            .lock()
+            .push("127.0.0.1@test".to_string());
+        let mq_all = (0..4)
+            .map(|queue_id| MessageQueue::from_parts("TopicTest", "broker-a", queue_id))
+            .collect::<HashSet<_>>();
+        let (consumer, client_instance) = runtime.block_on(async {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = listener.local_addr().unwrap().to_string();
+            tokio::spawn(rocketmq_remoting::remoting_server::server::run(
+                listener,
+                std::future::pending::<()>(),
+                broker.clone(),
+                None,
+                vec![],
+                Arc::new(RequestFlowController::new(&[])),
+            ));
+            let mut client_instance = ArcRefCellWrapper::new(MQClientInstance::new(
+                ClientConfig::default(),
+                0,
+                "127.0.0.1@test".to_string(),
+                None,
+            ));
+            client_instance.topic_route_table.write().await.insert(
+                "TopicTest".to_string(),
+                TopicRouteData {
+                    broker_datas: vec![BrokerData::new(
+                        "DefaultCluster".to_string(),
+                        "broker-a".to_string(),
+                        HashMap::from([(mix_all::MASTER_BROKER_ADDR_ID, addr)]),
+                        None,
+                    )],
+                    ..Default::default()
+                },
+            );
+
+            let mut consumer = DefaultMQPushConsumerImpl::new(
+                ClientConfig::default(),
+                ConsumerConfig {
+                    consumer_group: "GroupTest".to_string(),
+                    ..Default::default()
+                },
+                None,
+            );
+            MQConsumerInner::subscribe(&consumer, "TopicTest", "*").unwrap();
+            consumer
+                .rebalance_impl
+                .set_client_instance(client_instance.clone());
+            consumer.client_instance = Some(client_instance.clone());
+            consumer.service_state = ServiceState::Running;
+            assert!(
+                client_instance
+                    .register_consumer("GroupTest", consumer.clone())
+                    .await
+            );
+            consumer.update_topic_subscribe_info("TopicTest", &mq_all);
+            assert!(consumer.allocation().is_empty());
+
+            let allocation = consumer.rebalance_now().await.unwrap();
+            assert_eq!(allocation["TopicTest"], mq_all);
+            assert_eq!(consumer.allocation(), allocation);
+            let lagging_queues = |consumer: &DefaultMQPushConsumerImpl| {
+                consumer
+                    .consumer_lag_metrics()
+                    .lag_metrics()
+                    .into_keys()
+                    .collect::<HashSet<_>>()
+            };
+            assert_eq!(lagging_queues(&consumer), mq_all);
+
+            // The new consumer is not seen before the next rebalance
+            broker
+                .consumer_id_list
+                .lock()
+                .push("127.0.0.1@other".to_string());
+            assert_eq!(consumer.allocation()["TopicTest"], mq_all);
+
+            let allocation = consumer.rebalance_now().await.unwrap();
+            assert_eq!(allocation["TopicTest"].len(), 2);
+            assert_eq!(consumer.allocation(), allocation);
+            assert_eq!(lagging_queues(&consumer), allocation["TopicTest"]);
+            (consumer, client_instance)
+        });
+        drop(runtime);
+        drop(consumer);
+        drop(client_instance);
+    }
+
+    /// Remembers the offsets it was asked to persist.
+    #[derive(Default)]
+    struct PersistedOffsetStore {
+        offsets: MemoryOffsetStore,
+        persisted: parking_lot::Mutex<HashMap<MessageQueue, i64>>,
+    }
+
+    impl OffsetStore for Arc<PersistedOffsetStore> {
+        fn load(&self) -> Result<()> {
+            Ok(())
+        }
+
+        fn update_offset(&self, mq: &MessageQueue, offset: i64, increase_only: bool) {
+            self.offsets.update_offset(mq, offset, increase_only);
+        }
+
+        fn read_offset(&self, mq: &MessageQueue, read_type: ReadOffsetType) -> Result<Option<i64>> {
+            self.offsets.read_offset(mq, read_type)
+        }
+
+        fn persist_all(&self, mqs: &HashSet<MessageQueue>) -> Result<()> {
+            for mq in mqs {
+                self.persist(mq)?;
+            }
+            Ok(())
+        }
+
+        fn persist(&self, mq: &MessageQueue) -> Result<()> {
+            if let Some(offset) = self
+                .offsets
+                .read_offset(mq, ReadOffsetType::ReadFromMemory)?
+            {
+                self.persisted.lock().insert(mq.clone(), offset);
+            }
+            Ok(())
+        }
+
+        fn remove_offset(&self, mq: &MessageQueue) {
+            self.offsets.remove_offset(mq);
+        }
+
+        fn clone_offset_table(&self, topic: &str) -> HashMap<MessageQueue, i64> {
+            self.offsets.clone_offset_table(topic)
+        }
+    }
+
+    #[test]
+    fn shutdown_persists_offsets_after_messages_in_flight() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let store = Arc::new(PersistedOffsetStore::default());
+        let mq = MessageQueue::from_parts("TopicTest", "broker-a", 0);
+        let (consumer, client_instance) = runtime.block_on(async {
+            let mut client_instance = ArcRefCellWrapper::new(MQClientInstance::new(
+                ClientConfig::default(),
+                0,
+                "127.0.0.1@test".to_string(),
+                None,
+            ));
+            let mut consumer = DefaultMQPushConsumerImpl::new(
+                ClientConfig::default(),
+                ConsumerConfig {
+                    consumer_group: "GroupTest".to_string(),
+                    message_model: MessageModel::Broadcasting,
+                    await_termination_millis_when_shutdown: 1000,
+                    ..Default::default()
+                },
+                None,
+            );
+            consumer.set_offset_store(Box::new(store.clone()));
+            MQConsumerInner::subscribe(&consumer, "TopicTest", "*").unwrap();
+            consumer
+                .rebalance_impl
+                .set_client_instance(client_instance.clone());
+            consumer.client_instance = Some(client_instance.clone());
+            consumer.offset_persist_service.start().unwrap();
+            consumer.service_state = ServiceState::Running;
+            assert!(
+                client_instance
+                    .register_consumer("GroupTest", consumer.clone())
+                    .await
+            );
+            consumer.update_topic_subscribe_info("TopicTest", &HashSet::from([mq.clone()]));
+            consumer.do_rebalance().await;
+
+            // The listener is still consuming a batch when the shutdown starts
+            let permit = consumer.flow_control.acquire(&mq, 1).await.unwrap();
+            let offset_store = consumer.offset_persist_service.offset_store().clone();
+            let consumed_mq = mq.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                offset_store.update_offset(&consumed_mq, 10, true);
+                drop(permit);
+            });
+            consumer.shutdown().await;
+            assert_eq!(store.persisted.lock().get(&mq), Some(&10));
+            assert!(consumer.flow_control.acquire(&mq, 1).await.is_none());
+            // The group was unregistered
+            assert!(
+                client_instance
+                    .register_consumer("GroupTest", consumer.clone())
+                    .await
+            );
+            (consumer, client_instance)
+        });
+        drop(runtime);
+        drop(consumer);
+        drop(client_instance);
+    }
+}
@@ -0,0 +1,44 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use tracing::warn;
+
+use crate::consumer::consumer_impl::consume_flow_control::ConsumeFlowControl;
+use crate::consumer::store::offset_persist_service::OffsetPersistService;
+use crate::factory::mq_client_instance::MQClientInstance;
+
+/// Shuts a push consumer down without aborting the batches handed to the listener.
+///
+/// Dispatching stops first and the batches in flight get up to
+/// `await_termination_millis_when_shutdown` to finish, so the offsets persisted afterwards
+/// cover them and they are not redelivered to the consumers taking the queues over. The group
+/// is unregistered last.
+pub async fn shutdown_gracefully(
+    consumer_group: &str,
+    flow_control: &ConsumeFlowControl,
+    offset_persist_service: &OffsetPersistService,
+    client_instance: &mut MQClientInstance,
+) {
+    if !flow_control.shutdown().await {
+        warn!(
+            "consumer {} still consuming after {}ms, shutting down anyway",
+            consumer_group,
+            flow_control.config().await_termination_millis_when_shutdown
+        );
+    }
+    offset_persist_service.shutdown();
+    client_instance.unregister_consumer(consumer_group).await;
+}
@@ -0,0 +1,141 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use parking_lot::RwLock;
+use rocketmq_common::common::message::message_queue::MessageQueue;
+
+use crate::consumer::message_queue_listener::MessageQueueListener;
+
+/// Message queue listeners registered per topic, notified when a rebalance changes the queues
+/// assigned to the consumer.
+#[derive(Default, Clone)]
+pub struct MessageQueueListenerTable {
+    listener_table: Arc<RwLock<HashMap<String /* topic */, Arc<dyn MessageQueueListener>>>>,
+    assigned_table: Arc<RwLock<HashMap<String /* topic */, HashSet<MessageQueue>>>>,
+}
+
+impl MessageQueueListenerTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `listener` for `topic`, replacing the listener registered before.
+    pub fn register_message_queue_listener(
+        &self,
+        topic: impl Into<String>,
+        listener: Arc<dyn MessageQueueListener>,
+    ) {
+        self.listener_table.write().insert(topic.into(), listener);
+    }
+
+    pub fn unregister_message_queue_listener(&self, topic: &str) {
+        self.listener_table.write().remove(topic);
+        self.assigned_table.write().remove(topic);
+    }
+
+    pub fn has_listener(&self, topic: &str) -> bool {
+        self.listener_table.read().contains_key(topic)
+    }
+
+    /// Queues assigned to the consumer per topic by the last rebalance of each topic.
+    pub fn assigned_message_queues(&self) -> HashMap<String /* topic */, HashSet<MessageQueue>> {
+        self.assigned_table.read().clone()
+    }
+
+    /// Called with the result of a rebalance of `topic`, notifies the listener of the topic
+    /// when the assigned queues differ from the previous rebalance. Returns whether the
+    /// assignment changed.
+    pub fn message_queue_changed(
+        &self,
+        topic: &str,
+        mq_all: &HashSet<MessageQueue>,
+        mq_divided: &HashSet<MessageQueue>,
+    ) -> bool {
+        {
+            let mut assigned_table = self.assigned_table.write();
+            if assigned_table.get(topic) == Some(mq_divided) {
+                return false;
+            }
+            assigned_table.insert(topic.to_string(), mq_divided.clone());
+        }
+        let listener = self.listener_table.read().get(topic).cloned();
+        if let Some(listener) = listener {
+            listener.message_queue_changed(topic, mq_all, mq_divided);
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::atomic::Ordering;
+
+    use super::*;
+
+    #[derive(Default)]
+    struct CountListener {
+        count: AtomicUsize,
+        divided: AtomicUsize,
+    }
+
+    impl MessageQueueListener for CountListener {
+        fn message_queue_changed(
+            &self,
+            _topic: &str,
+            _mq_all: &HashSet<MessageQueue>,
+            mq_divided: &HashSet<MessageQueue>,
+        ) {
+            self.count.fetch_add(1, Ordering::Relaxed);
+            self.divided.store(mq_divided.len(), Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn notify_only_when_assignment_changes() {
+        let table = MessageQueueListenerTable::new();
+        let listener = Arc::new(CountListener::default());
+        table.register_message_queue_listener("TopicTest", listener.clone());
+        assert!(table.has_listener("TopicTest"));
+
+        let mq_all: HashSet<MessageQueue> = (0..4)
+            .map(|queue_id| MessageQueue::from_parts("TopicTest", "broker-a", queue_id))
+            .collect();
+        let mq_divided: HashSet<MessageQueue> = mq_all
+            .iter()
+            .filter(|mq| mq.get_queue_id() < 2)
+            .cloned()
+            .collect();
+
+        assert!(table.message_queue_changed("TopicTest", &mq_all, &mq_divided));
+        assert!(!table.message_queue_changed("TopicTest", &mq_all, &mq_divided));
+        assert!(table.message_queue_changed("TopicTest", &mq_all, &mq_all));
+        assert_eq!(listener.count.load(Ordering::Relaxed), 2);
+        assert_eq!(listener.divided.load(Ordering::Relaxed), 4);
+
+        table.unregister_message_queue_listener("TopicTest");
+        assert!(table.message_queue_changed("TopicTest", &mq_all, &mq_divided));
+        assert_eq!(listener.count.load(Ordering::Relaxed), 2);
+        assert_eq!(
+            table.assigned_message_queues(),
+            HashMap::from([("TopicTest".to_string(), mq_divided)])
+        );
+    }
+}
@@ -0,0 +1,274 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::env;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+
+use parking_lot::RwLock;
+use rocketmq_common::common::message::message_queue::MessageQueue;
+use rocketmq_common::TimeUtils::get_current_millis;
+use tracing::info;
+use tracing::warn;
+
+pub const PULL_MAX_IDLE_TIME: &str = "rocketmq.client.pull.pullMaxIdleTime";
+
+/// Consume state of a message queue assigned to the consumer, alive from the rebalance that
+/// assigns the queue until the one that takes it away or finds its pulls stalled.
+pub struct ProcessQueue {
+    last_pull_timestamp: AtomicU64,
+    last_consume_timestamp: AtomicU64,
+    dropped: AtomicBool,
+}
+
+impl Default for ProcessQueue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProcessQueue {
+    pub fn new() -> Self {
+        let now = get_current_millis();
+        Self {
+            last_pull_timestamp: AtomicU64::new(now),
+            last_consume_timestamp: AtomicU64::new(now),
+            dropped: AtomicBool::new(false),
+        }
+    }
+
+    pub fn last_pull_timestamp(&self) -> u64 {
+        self.last_pull_timestamp.load(Ordering::Acquire)
+    }
+
+    /// Records a pull of the queue, called each time a pull request of the queue is sent.
+    pub fn update_last_pull_timestamp(&self) {
+        self.last_pull_timestamp
+            .store(get_current_millis(), Ordering::Release);
+    }
+
+    pub fn last_consume_timestamp(&self) -> u64 {
+        self.last_consume_timestamp.load(Ordering::Acquire)
+    }
+
+    /// Records that messages of the queue were handed to the listener.
+    pub fn update_last_consume_timestamp(&self) {
+        self.last_consume_timestamp
+            .store(get_current_millis(), Ordering::Release);
+    }
+
+    /// Whether the queue has not been pulled for longer than `pull_max_idle_time`, e.g.
+    /// because its pull task died, so a rebalance should drop it and assign it again.
+    pub fn is_pull_expired(&self, pull_max_idle_time: Duration) -> bool {
+        self.is_pull_expired_at(get_current_millis(), pull_max_idle_time)
+    }
+
+    fn is_pull_expired_at(&self, now: u64, pull_max_idle_time: Duration) -> bool {
+        now.saturating_sub(self.last_pull_timestamp()) > pull_max_idle_time.as_millis() as u64
+    }
+
+    pub fn is_dropped(&self) -> bool {
+        self.dropped.load(Ordering::Acquire)
+    }
+
+    /// Marks the queue dropped, its pull and consume tasks stop when they see it.
+    pub fn set_dropped(&self, dropped: bool) {
+        self.dropped.store(dropped, Ordering::Release);
+    }
+}
+
+/// Queues added to and removed from a [`ProcessQueueTable`] by a rebalance.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ProcessQueueTableChange {
+    pub added: Vec<MessageQueue>,
+    pub removed: Vec<MessageQueue>,
+}
+
+impl ProcessQueueTableChange {
+    pub fn is_changed(&self) -> bool {
+        !self.added.is_empty() || !self.removed.is_empty()
+    }
+}
+
+/// Process queues of the message queues assigned to a consumer. Rebalances drop the queues
+/// no longer assigned, the ones whose pull has been idle longer than `pull_max_idle_time`
+/// (`rocketmq.client.pull.pullMaxIdleTime`, 120 seconds by default) and the ones of topics
+/// the consumer unsubscribed from, so the state of stale queues does not pile up.
+pub struct ProcessQueueTable {
+    pull_max_idle_time: Duration,
+    table: RwLock<HashMap<MessageQueue, Arc<ProcessQueue>>>,
+}
+
+impl Default for ProcessQueueTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProcessQueueTable {
+    pub fn new() -> Self {
+        Self::with_pull_max_idle_time(Duration::from_millis(
+            env::var(PULL_MAX_IDLE_TIME)
+                .ok()
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(120_000),
+        ))
+    }
+
+    pub fn with_pull_max_idle_time(pull_max_idle_time: Duration) -> Self {
+        Self {
+            pull_max_idle_time,
+            table: RwLock::new(HashMap::new()),
+        }
+    }
+
+    pub fn pull_max_idle_time(&self) -> Duration {
+        self.pull_max_idle_time
+    }
+
+    pub fn get(&self, mq: &MessageQueue) -> Option<Arc<ProcessQueue>> {
+        self.table.read().get(mq).cloned()
+    }
+
+    pub fn message_queues(&self) -> HashSet<MessageQueue> {
+        self.table.read().keys().cloned().collect()
+    }
+
+    /// Applies the queues of `topic` a rebalance assigned to the consumer: drops the queues of
+    /// the topic not in `mq_set` or whose pull expired, and creates a process queue for each
+    /// assigned queue without one. An expired queue still assigned is created again, so its
+    /// pull restarts.
+    pub fn update_process_queue_table_in_rebalance(
+        &self,
+        topic: &str,
+        mq_set: &HashSet<MessageQueue>,
+    ) -> ProcessQueueTableChange {
+        let mut change = ProcessQueueTableChange::default();
+        let mut table = self.table.write();
+        table.retain(|mq, process_queue| {
+            if mq.get_topic() != topic {
+                return true;
+            }
+            if !mq_set.contains(mq) {
+                info!("doRebalance, remove unnecessary mq, {:?}", mq);
+            } else if process_queue.is_pull_expired(self.pull_max_idle_time) {
+                warn!(
+                    "doRebalance, remove pull expired mq, {:?}, last pull {}",
+                    mq,
+                    process_queue.last_pull_timestamp()
+                );
+            } else {
+                return true;
+            }
+            process_queue.set_dropped(true);
+            change.removed.push(mq.clone());
+            false
+        });
+        for mq in mq_set {
+            if !table.contains_key(mq) {
+                table.insert(mq.clone(), Arc::new(ProcessQueue::new()));
+                change.added.push(mq.clone());
+            }
+        }
+        change
+    }
+
+    /// Drops the process queues of topics the consumer is no longer subscribed to, which no
+    /// rebalance visits anymore. Returns the dropped queues.
+    pub fn truncate_message_queue_not_my_topic(
+        &self,
+        subscribed_topics: &HashSet<String>,
+    ) -> Vec<MessageQueue> {
+        let mut removed = Vec::new();
+        self.table.write().retain(|mq, process_queue| {
+            if subscribed_topics.contains(mq.get_topic()) {
+                return true;
+            }
+            process_queue.set_dropped(true);
+            info!("doRebalance, truncateMessageQueueNotMyTopic, {:?}", mq);
+            removed.push(mq.clone());
+            false
+        });
+        removed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message_queue(topic: &str, queue_id: i32) -> MessageQueue {
+        MessageQueue::from_parts(topic, "broker-a", queue_id)
+    }
+
+    #[test]
+    fn pull_expires_after_max_idle_time() {
+        let process_queue = ProcessQueue::new();
+        let idle = Duration::from_secs(120);
+        let last_pull = process_queue.last_pull_timestamp();
+        assert!(!process_queue.is_pull_expired_at(last_pull + 1000, idle));
+        assert!(process_queue.is_pull_expired_at(last_pull + 121_000, idle));
+        assert!(!process_queue.is_pull_expired(idle));
+    }
+
+    #[test]
+    fn rebalance_drops_unassigned_expired_and_orphaned_queues() {
+        let table = ProcessQueueTable::with_pull_max_idle_time(Duration::from_secs(120));
+        let assigned: HashSet<MessageQueue> = (0..2)
+            .map(|queue_id| message_queue("TopicA", queue_id))
+            .collect();
+        let change = table.update_process_queue_table_in_rebalance("TopicA", &assigned);
+        assert_eq!(change.added.len(), 2);
+        table.update_process_queue_table_in_rebalance(
+            "TopicB",
+            &[message_queue("TopicB", 0)].into(),
+        );
+
+        let dropped = table.get(&message_queue("TopicA", 1)).unwrap();
+        let change = table.update_process_queue_table_in_rebalance(
+            "TopicA",
+            &[message_queue("TopicA", 0)].into(),
+        );
+        assert_eq!(change.removed, vec![message_queue("TopicA", 1)]);
+        assert!(dropped.is_dropped());
+        assert!(!table
+            .update_process_queue_table_in_rebalance("TopicA", &[message_queue("TopicA", 0)].into())
+            .is_changed());
+
+        // a queue whose pull stalled is dropped and assigned again with a new process queue
+        let stalled = table.get(&message_queue("TopicA", 0)).unwrap();
+        stalled.last_pull_timestamp.store(0, Ordering::Release);
+        let change = table.update_process_queue_table_in_rebalance(
+            "TopicA",
+            &[message_queue("TopicA", 0)].into(),
+        );
+        assert_eq!(change.removed, change.added);
+        assert!(stalled.is_dropped());
+        assert!(!table.get(&message_queue("TopicA", 0)).unwrap().is_dropped());
+
+        let removed = table.truncate_message_queue_not_my_topic(&["TopicA".to_string()].into());
+        assert_eq!(removed, vec![message_queue("TopicB", 0)]);
+        assert_eq!(
+            table.message_queues(),
+            [message_queue("TopicA", 0)].into_iter().collect()
+        );
+    }
+}
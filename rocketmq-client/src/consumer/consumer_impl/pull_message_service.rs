@@ -23,4 +23,8 @@ impl PullMessageService {
     pub async fn start(&mut self) {
         println!("PullMessageService started");
     }
+
+    pub async fn shutdown(&mut self) {
+        println!("PullMessageService shutdown");
+    }
 }
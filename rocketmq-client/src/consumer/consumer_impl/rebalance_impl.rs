@@ -0,0 +1,196 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use parking_lot::RwLock;
+use rocketmq_common::common::message::message_queue::MessageQueue;
+use rocketmq_common::common::mix_all;
+use rocketmq_common::ArcRefCellWrapper;
+use rocketmq_remoting::protocol::heartbeat::message_model::MessageModel;
+use tracing::error;
+use tracing::info;
+use tracing::warn;
+
+use crate::consumer::allocate_message_queue_strategy::AllocateMessageQueueStrategy;
+use crate::consumer::consumer_impl::consume_flow_control::ConsumeFlowControl;
+use crate::consumer::consumer_impl::message_queue_listener_table::MessageQueueListenerTable;
+use crate::consumer::consumer_impl::subscription_table::SubscriptionTable;
+use crate::consumer::consumer_lag::ConsumerLagMetrics;
+use crate::consumer::message_queue_listener::MessageQueueListener;
+use crate::consumer::store::offset_persist_service::OffsetPersistService;
+use crate::factory::mq_client_instance::MQClientInstance;
+
+/// Divides the queues of the topics a consumer subscribes to among the consumers of its group.
+///
+/// The queues of a topic come from its route, the consumers of the group from a broker of the
+/// topic. The queues assigned by the last rebalance of each topic are kept in the message queue
+/// listener table, which also tells the listener of the topic when they change. The lag metrics,
+/// the flow control and the offset persist service of the consumer track the assigned queues of
+/// every topic.
+#[derive(Clone)]
+pub struct RebalanceImpl {
+    consumer_group: String,
+    message_model: MessageModel,
+    allocate_message_queue_strategy: Arc<dyn AllocateMessageQueueStrategy>,
+    subscription_table: Arc<SubscriptionTable>,
+    topic_subscribe_info_table: Arc<RwLock<HashMap<String /* topic */, HashSet<MessageQueue>>>>,
+    message_queue_listener_table: MessageQueueListenerTable,
+    consumer_lag_metrics: ConsumerLagMetrics,
+    flow_control: Arc<ConsumeFlowControl>,
+    offset_persist_service: OffsetPersistService,
+    client_instance: Option<ArcRefCellWrapper<MQClientInstance>>,
+}
+
+impl RebalanceImpl {
+    pub fn new(
+        consumer_group: impl Into<String>,
+        message_model: MessageModel,
+        allocate_message_queue_strategy: Arc<dyn AllocateMessageQueueStrategy>,
+        subscription_table: Arc<SubscriptionTable>,
+        message_queue_listener_table: MessageQueueListenerTable,
+        consumer_lag_metrics: ConsumerLagMetrics,
+        flow_control: Arc<ConsumeFlowControl>,
+        offset_persist_service: OffsetPersistService,
+    ) -> Self {
+        Self {
+            consumer_group: consumer_group.into(),
+            message_model,
+            allocate_message_queue_strategy,
+            subscription_table,
+            topic_subscribe_info_table: Arc::new(RwLock::new(HashMap::new())),
+            message_queue_listener_table,
+            consumer_lag_metrics,
+            flow_control,
+            offset_persist_service,
+            client_instance: None,
+        }
+    }
+
+    pub fn set_client_instance(&mut self, client_instance: ArcRefCellWrapper<MQClientInstance>) {
+        self.client_instance = Some(client_instance);
+    }
+
+    pub fn set_offset_persist_service(&mut self, offset_persist_service: OffsetPersistService) {
+        self.offset_persist_service = offset_persist_service;
+    }
+
+    pub fn topic_subscribe_info_table(
+        &self,
+    ) -> &Arc<RwLock<HashMap<String /* topic */, HashSet<MessageQueue>>>> {
+        &self.topic_subscribe_info_table
+    }
+
+    /// Queues assigned to the consumer per subscribed topic, as of the last rebalance.
+    pub fn allocation(&self) -> HashMap<String /* topic */, HashSet<MessageQueue>> {
+        let topics = self.subscription_table.topics();
+        self.message_queue_listener_table
+            .assigned_message_queues()
+            .into_iter()
+            .filter(|(topic, _)| topics.contains(topic))
+            .collect()
+    }
+
+    /// Rebalances every subscribed topic, returning `false` if any of them could not be
+    /// balanced.
+    pub async fn do_rebalance(&self) -> bool {
+        let mut balanced = true;
+        let topics = self.subscription_table.topics();
+        for topic in topics.iter() {
+            if !self.rebalance_by_topic(topic.as_str()).await {
+                balanced = false;
+            }
+        }
+        for mq in self.consumer_lag_metrics.lag_metrics().keys() {
+            if !topics.contains(mq.get_topic()) {
+                self.consumer_lag_metrics.untrack(mq);
+            }
+        }
+        balanced
+    }
+
+    async fn rebalance_by_topic(&self, topic: &str) -> bool {
+        let Some(mq_set) = self.topic_subscribe_info_table.read().get(topic).cloned() else {
+            if !topic.starts_with(mix_all::RETRY_GROUP_TOPIC_PREFIX) {
+                warn!(
+                    "doRebalance, {}, but the topic[{}] not exist.",
+                    self.consumer_group, topic
+                );
+            }
+            return true;
+        };
+        let mq_divided = match self.message_model {
+            MessageModel::Broadcasting => mq_set.clone(),
+            MessageModel::Clustering => {
+                let Some(client_instance) = self.client_instance.as_ref() else {
+                    return false;
+                };
+                let mut cid_all = client_instance
+                    .find_consumer_id_list(topic, self.consumer_group.as_str())
+                    .await;
+                if cid_all.is_empty() {
+                    warn!(
+                        "doRebalance, {} {}, get consumer id list failed",
+                        self.consumer_group, topic
+                    );
+                    return true;
+                }
+                let mut mq_all = mq_set.iter().cloned().collect::<Vec<_>>();
+                mq_all.sort();
+                cid_all.sort();
+                match self.allocate_message_queue_strategy.allocate(
+                    self.consumer_group.as_str(),
+                    client_instance.client_id.as_str(),
+                    &mq_all,
+                    &cid_all,
+                ) {
+                    Ok(mqs) => mqs.into_iter().collect::<HashSet<_>>(),
+                    Err(e) => {
+                        error!(
+                            "allocate message queue exception. strategy name: {}, ex: {}",
+                            self.allocate_message_queue_strategy.get_name(),
+                            e
+                        );
+                        return false;
+                    }
+                }
+            }
+        };
+        if self
+            .message_queue_listener_table
+            .message_queue_changed(topic, &mq_set, &mq_divided)
+        {
+            self.consumer_lag_metrics
+                .message_queue_changed(topic, &mq_set, &mq_divided);
+            self.flow_control
+                .message_queue_changed(topic, &mq_set, &mq_divided);
+            self.offset_persist_service
+                .message_queue_changed(topic, &mq_set, &mq_divided);
+            info!(
+                "client rebalanced result changed. allocateMessageQueueStrategyName={}, group={}, \
+                 topic={}, mqAllSize={}, rebalanceResultSize={}",
+                self.allocate_message_queue_strategy.get_name(),
+                self.consumer_group,
+                topic,
+                mq_set.len(),
+                mq_divided.len()
+            );
+        }
+        true
+    }
+}
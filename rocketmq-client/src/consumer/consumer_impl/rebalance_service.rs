@@ -14,14 +14,112 @@
  * See the License for the specific language governing permissions and
  * limitations under the License.
  */
-pub struct RebalanceService {}
+use std::env;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::Notify;
+use tracing::info;
+
+use crate::factory::mq_client_instance::MQClientInstance;
+
+pub const REBALANCE_WAIT_INTERVAL: &str = "rocketmq.client.rebalance.waitInterval";
+
+/// Rebalances the consumers of a client instance every `rocketmq.client.rebalance.waitInterval`
+/// milliseconds, or as soon as it is woken up, until it is shut down.
+pub struct RebalanceService {
+    wait_interval: Duration,
+    notify: Arc<Notify>,
+    stopped: Arc<AtomicBool>,
+}
+
+impl Default for RebalanceService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl RebalanceService {
     pub fn new() -> Self {
-        RebalanceService {}
+        RebalanceService {
+            wait_interval: Duration::from_millis(
+                env::var(REBALANCE_WAIT_INTERVAL)
+                    .ok()
+                    .and_then(|value| value.parse().ok())
+                    .unwrap_or(20_000),
+            ),
+            notify: Arc::new(Notify::new()),
+            stopped: Arc::new(AtomicBool::new(false)),
+        }
     }
 
-    pub async fn start(&mut self) {
-        println!("RebalanceService started");
+    pub async fn start(&mut self, client_instance: MQClientInstance) {
+        let wait_interval = self.wait_interval;
+        let notify = self.notify.clone();
+        let stopped = self.stopped.clone();
+        stopped.store(false, Ordering::Release);
+        tokio::spawn(async move {
+            info!("RebalanceService started");
+            loop {
+                tokio::select! {
+                    _ = tokio::time::sleep(wait_interval) => {}
+                    _ = notify.notified() => {}
+                }
+                if stopped.load(Ordering::Acquire) {
+                    break;
+                }
+                client_instance.do_rebalance().await;
+            }
+            info!("RebalanceService end");
+        });
+    }
+
+    /// Ends the rebalance loop, which releases the client instance it holds.
+    pub fn shutdown(&self) {
+        self.stopped.store(true, Ordering::Release);
+        self.notify.notify_one();
+    }
+
+    /// Runs the next rebalance now instead of at the end of the wait interval.
+    pub fn wakeup(&self) {
+        self.notify.notify_one();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::client_config::ClientConfig;
+
+    #[test]
+    fn shutdown_ends_the_rebalance_loop() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let instance = runtime.block_on(async {
+            let instance = MQClientInstance::new(
+                ClientConfig::default(),
+                0,
+                "127.0.0.1@test".to_string(),
+                None,
+            );
+            let held = || Arc::strong_count(&instance.topic_route_table);
+            let released = held();
+            let mut rebalance_service = RebalanceService::new();
+            rebalance_service.start(instance.clone()).await;
+            assert_eq!(held(), released + 1);
+
+            rebalance_service.shutdown();
+            tokio::time::timeout(Duration::from_secs(5), async {
+                while held() != released {
+                    tokio::task::yield_now().await;
+                }
+            })
+            .await
+            .unwrap();
+            instance
+        });
+        drop(runtime);
+        drop(instance);
     }
 }
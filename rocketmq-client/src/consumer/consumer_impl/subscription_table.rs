@@ -0,0 +1,142 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+use parking_lot::RwLock;
+use rocketmq_common::common::message::message_queue::MessageQueue;
+use rocketmq_remoting::protocol::filter::filter_api::FilterAPI;
+use rocketmq_remoting::protocol::heartbeat::subscription_data::SubscriptionData;
+use tracing::warn;
+
+use crate::consumer::consumer_impl::process_queue::ProcessQueueTable;
+use crate::consumer::store::offset_store::OffsetStore;
+use crate::error::MQClientError;
+use crate::Result;
+
+/// Subscriptions of a consumer by topic. They can change while the consumer runs, the next
+/// heartbeat reports them to the brokers and the next rebalance assigns or releases the queues
+/// of the topics.
+#[derive(Default)]
+pub struct SubscriptionTable {
+    table: RwLock<HashMap<String, SubscriptionData>>,
+}
+
+impl SubscriptionTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Subscribes to the tags of `topic` matching `sub_expression`, `*` for all of them,
+    /// replacing the previous subscription to the topic. Returns whether the subscription
+    /// changed.
+    pub fn subscribe(&self, topic: &str, sub_expression: &str) -> Result<bool> {
+        let subscription = FilterAPI::build_subscription_data(topic, sub_expression)
+            .map_err(MQClientError::IllegalArgument)?;
+        let mut table = self.table.write();
+        if table.get(topic).is_some_and(|current| {
+            current.sub_string == subscription.sub_string
+                && current.expression_type == subscription.expression_type
+        }) {
+            return Ok(false);
+        }
+        table.insert(topic.to_string(), subscription);
+        Ok(true)
+    }
+
+    /// Removes the subscription to `topic`, returning it.
+    pub fn unsubscribe(&self, topic: &str) -> Option<SubscriptionData> {
+        self.table.write().remove(topic)
+    }
+
+    pub fn get(&self, topic: &str) -> Option<SubscriptionData> {
+        self.table.read().get(topic).cloned()
+    }
+
+    pub fn topics(&self) -> HashSet<String> {
+        self.table.read().keys().cloned().collect()
+    }
+
+    pub fn subscriptions(&self) -> HashSet<SubscriptionData> {
+        self.table.read().values().cloned().collect()
+    }
+}
+
+/// Drops the process queues of the topics no longer in `subscription_table`, persisting and
+/// then forgetting their offsets, so consuming a topic subscribed again resumes from the
+/// stored offsets. Returns the released queues.
+pub fn release_unsubscribed_queues(
+    subscription_table: &SubscriptionTable,
+    process_queue_table: &ProcessQueueTable,
+    offset_store: &dyn OffsetStore,
+) -> Vec<MessageQueue> {
+    let released =
+        process_queue_table.truncate_message_queue_not_my_topic(&subscription_table.topics());
+    for mq in released.iter() {
+        if let Err(e) = offset_store.persist(mq) {
+            warn!("persist offset of unsubscribed {:?} failed: {}", mq, e);
+        }
+        offset_store.remove_offset(mq);
+    }
+    released
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::consumer::store::memory_offset_store::MemoryOffsetStore;
+
+    #[test]
+    fn unsubscribing_releases_the_queues_and_offsets_of_the_topic() {
+        let subscription_table = SubscriptionTable::new();
+        assert!(subscription_table.subscribe("TopicA", "*").unwrap());
+        assert!(subscription_table
+            .subscribe("TopicB", "TagA || TagB")
+            .unwrap());
+        assert!(!subscription_table
+            .subscribe("TopicB", "TagA || TagB")
+            .unwrap());
+        assert_eq!(
+            subscription_table.get("TopicB").unwrap().tags_set,
+            ["TagA".to_string(), "TagB".to_string()].into()
+        );
+
+        let process_queue_table =
+            ProcessQueueTable::with_pull_max_idle_time(Duration::from_secs(120));
+        let offset_store = MemoryOffsetStore::new();
+        let queue_a = MessageQueue::from_parts("TopicA", "broker-a", 0);
+        let queue_b = MessageQueue::from_parts("TopicB", "broker-a", 0);
+        for mq in [&queue_a, &queue_b] {
+            process_queue_table
+                .update_process_queue_table_in_rebalance(mq.get_topic(), &[mq.clone()].into());
+            offset_store.update_offset(mq, 10, false);
+        }
+
+        assert!(subscription_table.unsubscribe("TopicB").is_some());
+        let released =
+            release_unsubscribed_queues(&subscription_table, &process_queue_table, &offset_store);
+        assert_eq!(released, vec![queue_b]);
+        assert_eq!(
+            process_queue_table.message_queues(),
+            [queue_a.clone()].into()
+        );
+        assert_eq!(offset_store.clone_offset_table("").len(), 1);
+        assert_eq!(subscription_table.topics(), ["TopicA".to_string()].into());
+    }
+}
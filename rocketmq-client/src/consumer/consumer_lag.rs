@@ -0,0 +1,328 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+
+use parking_lot::RwLock;
+use rocketmq_common::common::message::message_queue::MessageQueue;
+use rocketmq_common::TimeUtils::get_current_millis;
+use tracing::info;
+use tracing::warn;
+
+use crate::base::client_config::ClientConfig;
+use crate::consumer::message_queue_listener::MessageQueueListener;
+use crate::error::MQClientError;
+use crate::factory::mq_client_instance::MQClientInstance;
+
+/// Message accumulation of one queue: how far the committed offset of the group trails the
+/// broker max offset.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QueueLag {
+    pub broker_max_offset: i64,
+    pub committed_offset: i64,
+    pub lag: i64,
+    pub update_timestamp: u64,
+}
+
+/// Raised when the lag of a queue crosses the configured threshold.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LagThresholdEvent {
+    Exceeded {
+        message_queue: MessageQueue,
+        lag: i64,
+    },
+    Recovered {
+        message_queue: MessageQueue,
+        lag: i64,
+    },
+}
+
+pub type LagThresholdCallback = Arc<dyn Fn(&LagThresholdEvent) + Send + Sync>;
+
+struct LagThreshold {
+    lag: i64,
+    callback: LagThresholdCallback,
+}
+
+/// Per-queue lag of a consumer group, meant as an auto-scaling hint.
+///
+/// The tracked queues follow the rebalance result through [`MessageQueueListener`]; the lag of
+/// each is refreshed every `consumer_lag_refresh_interval` once [`ConsumerLagMetrics::start`]
+/// is called.
+#[derive(Clone)]
+pub struct ConsumerLagMetrics {
+    consumer_group: String,
+    refresh_interval: Duration,
+    mq_client_api_timeout: u64,
+    lag_table: Arc<RwLock<HashMap<MessageQueue, QueueLag>>>,
+    exceeded_queues: Arc<RwLock<HashSet<MessageQueue>>>,
+    threshold: Arc<RwLock<Option<LagThreshold>>>,
+    stopped: Arc<AtomicBool>,
+}
+
+impl ConsumerLagMetrics {
+    pub fn new(consumer_group: impl Into<String>, client_config: &ClientConfig) -> Self {
+        Self {
+            consumer_group: consumer_group.into(),
+            refresh_interval: Duration::from_millis(
+                client_config.consumer_lag_refresh_interval as u64,
+            ),
+            mq_client_api_timeout: client_config.mq_client_api_timeout,
+            lag_table: Arc::new(RwLock::new(HashMap::new())),
+            exceeded_queues: Arc::new(RwLock::new(HashSet::new())),
+            threshold: Arc::new(RwLock::new(None)),
+            stopped: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Calls `callback` whenever the lag of a queue goes above `lag`, and again once it is back
+    /// at or below it.
+    pub fn set_lag_threshold<F>(&self, lag: i64, callback: F)
+    where
+        F: Fn(&LagThresholdEvent) + Send + Sync + 'static,
+    {
+        *self.threshold.write() = Some(LagThreshold {
+            lag,
+            callback: Arc::new(callback),
+        });
+        self.exceeded_queues.write().clear();
+    }
+
+    /// Snapshot of the lag of every tracked queue.
+    pub fn lag_metrics(&self) -> HashMap<MessageQueue, QueueLag> {
+        self.lag_table.read().clone()
+    }
+
+    pub fn total_lag(&self) -> i64 {
+        self.lag_table
+            .read()
+            .values()
+            .map(|queue_lag| queue_lag.lag)
+            .sum()
+    }
+
+    pub fn track(&self, message_queue: MessageQueue) {
+        self.lag_table.write().entry(message_queue).or_default();
+    }
+
+    pub fn untrack(&self, message_queue: &MessageQueue) {
+        self.lag_table.write().remove(message_queue);
+        self.exceeded_queues.write().remove(message_queue);
+    }
+
+    /// Records the offsets of a tracked queue. A negative committed offset means the group has
+    /// never committed, so the whole queue counts as lag.
+    pub fn record(
+        &self,
+        message_queue: &MessageQueue,
+        broker_max_offset: i64,
+        committed_offset: i64,
+    ) {
+        let lag = (broker_max_offset - committed_offset.max(0)).max(0);
+        {
+            let mut lag_table = self.lag_table.write();
+            let Some(queue_lag) = lag_table.get_mut(message_queue) else {
+                return;
+            };
+            *queue_lag = QueueLag {
+                broker_max_offset,
+                committed_offset,
+                lag,
+                update_timestamp: get_current_millis(),
+            };
+        }
+        self.check_threshold(message_queue, lag);
+    }
+
+    fn check_threshold(&self, message_queue: &MessageQueue, lag: i64) {
+        let threshold = self.threshold.read();
+        let Some(threshold) = threshold.as_ref() else {
+            return;
+        };
+        let event = if lag > threshold.lag {
+            if !self.exceeded_queues.write().insert(message_queue.clone()) {
+                return;
+            }
+            LagThresholdEvent::Exceeded {
+                message_queue: message_queue.clone(),
+                lag,
+            }
+        } else {
+            if !self.exceeded_queues.write().remove(message_queue) {
+                return;
+            }
+            LagThresholdEvent::Recovered {
+                message_queue: message_queue.clone(),
+                lag,
+            }
+        };
+        (threshold.callback)(&event);
+    }
+
+    /// Queries the broker max offset and committed offset of every tracked queue.
+    pub async fn refresh(&self, client_instance: &MQClientInstance) {
+        let message_queues: Vec<MessageQueue> = self.lag_table.read().keys().cloned().collect();
+        for message_queue in message_queues {
+            let Some(broker_addr) = client_instance
+                .find_broker_address_in_publish(message_queue.get_broker_name())
+                .await
+            else {
+                warn!(
+                    "refresh consumer lag of {} failed, broker {} not found",
+                    message_queue,
+                    message_queue.get_broker_name()
+                );
+                continue;
+            };
+            let mut mq_client_api_impl = client_instance.get_mq_client_api_impl();
+            let broker_max_offset = match mq_client_api_impl
+                .get_max_offset(&broker_addr, &message_queue, self.mq_client_api_timeout)
+                .await
+            {
+                Ok(offset) => offset.value(),
+                Err(e) => {
+                    warn!("refresh consumer lag of {} failed: {}", message_queue, e);
+                    continue;
+                }
+            };
+            let committed_offset = match mq_client_api_impl
+                .query_consumer_offset(
+                    &broker_addr,
+                    &self.consumer_group,
+                    &message_queue,
+                    self.mq_client_api_timeout,
+                )
+                .await
+            {
+                Ok(offset) => offset.value(),
+                Err(MQClientError::OffsetNotFoundException(..)) => -1,
+                Err(e) => {
+                    warn!("refresh consumer lag of {} failed: {}", message_queue, e);
+                    continue;
+                }
+            };
+            self.record(&message_queue, broker_max_offset, committed_offset);
+        }
+    }
+
+    pub fn start(&self, client_instance: MQClientInstance) {
+        self.stopped.store(false, Ordering::Release);
+        let metrics = self.clone();
+        tokio::spawn(async move {
+            info!(
+                "ScheduledTask refreshConsumerLag of {} started",
+                metrics.consumer_group
+            );
+            while !metrics.stopped.load(Ordering::Acquire) {
+                let current_execution_time = tokio::time::Instant::now();
+                metrics.refresh(&client_instance).await;
+                let next_execution_time = current_execution_time + metrics.refresh_interval;
+                tokio::time::sleep_until(next_execution_time).await;
+            }
+        });
+    }
+
+    pub fn shutdown(&self) {
+        self.stopped.store(true, Ordering::Release);
+    }
+}
+
+impl MessageQueueListener for ConsumerLagMetrics {
+    fn message_queue_changed(
+        &self,
+        topic: &str,
+        _mq_all: &HashSet<MessageQueue>,
+        mq_divided: &HashSet<MessageQueue>,
+    ) {
+        let removed: Vec<MessageQueue> = self
+            .lag_table
+            .read()
+            .keys()
+            .filter(|mq| mq.get_topic() == topic && !mq_divided.contains(*mq))
+            .cloned()
+            .collect();
+        for message_queue in removed.iter() {
+            self.untrack(message_queue);
+        }
+        for message_queue in mq_divided {
+            self.track(message_queue.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    fn message_queue(queue_id: i32) -> MessageQueue {
+        MessageQueue::from_parts("TopicTest", "broker-a", queue_id)
+    }
+
+    #[test]
+    fn record_computes_lag_of_tracked_queues_only() {
+        let metrics = ConsumerLagMetrics::new("group", &ClientConfig::default());
+        metrics.track(message_queue(0));
+        metrics.record(&message_queue(0), 100, 40);
+        metrics.record(&message_queue(1), 100, 40);
+        metrics.track(message_queue(2));
+        metrics.record(&message_queue(2), 30, -1);
+
+        let lag_metrics = metrics.lag_metrics();
+        assert_eq!(lag_metrics.len(), 2);
+        assert_eq!(lag_metrics[&message_queue(0)].lag, 60);
+        assert_eq!(lag_metrics[&message_queue(2)].lag, 30);
+        assert_eq!(metrics.total_lag(), 90);
+    }
+
+    #[test]
+    fn threshold_callback_fires_on_crossing() {
+        let metrics = ConsumerLagMetrics::new("group", &ClientConfig::default());
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let events_cloned = events.clone();
+        metrics.set_lag_threshold(50, move |event| {
+            events_cloned.lock().unwrap().push(event.clone())
+        });
+        metrics.message_queue_changed(
+            "TopicTest",
+            &HashSet::new(),
+            &HashSet::from([message_queue(0)]),
+        );
+        metrics.record(&message_queue(0), 100, 10);
+        metrics.record(&message_queue(0), 120, 10);
+        metrics.record(&message_queue(0), 120, 100);
+
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![
+                LagThresholdEvent::Exceeded {
+                    message_queue: message_queue(0),
+                    lag: 90
+                },
+                LagThresholdEvent::Recovered {
+                    message_queue: message_queue(0),
+                    lag: 20
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,314 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+use parking_lot::RwLock;
+use rocketmq_common::common::message::message_queue::MessageQueue;
+
+use crate::consumer::message_queue_listener::MessageQueueListener;
+
+/// Upper bounds, in milliseconds, of the pull round-trip time histogram buckets. Pulls slower
+/// than the last bound land in an extra overflow bucket.
+pub const PULL_RT_BUCKET_BOUNDS_MILLIS: [u64; 12] =
+    [1, 2, 5, 10, 20, 50, 100, 200, 500, 1000, 5000, 30000];
+
+/// Receives every pull recorded by [`ConsumerStatsManager`], e.g. to feed an OpenTelemetry
+/// histogram and counters.
+pub trait PullStatsExporter: Send + Sync + 'static {
+    fn export_pull(
+        &self,
+        consumer_group: &str,
+        message_queue: &MessageQueue,
+        rt: Duration,
+        message_count: usize,
+    );
+}
+
+/// Pull statistics of one message queue.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PullStats {
+    pub pull_times: u64,
+    pub empty_pull_times: u64,
+    pub pulled_messages: u64,
+    pub total_rt_millis: u64,
+    pub max_rt_millis: u64,
+    /// Pull count per bucket of [`PULL_RT_BUCKET_BOUNDS_MILLIS`], the last one counts the pulls
+    /// slower than every bound.
+    pub rt_buckets: [u64; PULL_RT_BUCKET_BOUNDS_MILLIS.len() + 1],
+}
+
+impl Default for PullStats {
+    fn default() -> Self {
+        PullStats {
+            pull_times: 0,
+            empty_pull_times: 0,
+            pulled_messages: 0,
+            total_rt_millis: 0,
+            max_rt_millis: 0,
+            rt_buckets: [0; PULL_RT_BUCKET_BOUNDS_MILLIS.len() + 1],
+        }
+    }
+}
+
+impl PullStats {
+    fn record(&mut self, rt_millis: u64, message_count: usize) {
+        self.pull_times += 1;
+        if message_count == 0 {
+            self.empty_pull_times += 1;
+        }
+        self.pulled_messages += message_count as u64;
+        self.total_rt_millis += rt_millis;
+        self.max_rt_millis = self.max_rt_millis.max(rt_millis);
+        let bucket = PULL_RT_BUCKET_BOUNDS_MILLIS
+            .iter()
+            .position(|bound| rt_millis <= *bound)
+            .unwrap_or(PULL_RT_BUCKET_BOUNDS_MILLIS.len());
+        self.rt_buckets[bucket] += 1;
+    }
+
+    pub fn avg_rt_millis(&self) -> f64 {
+        if self.pull_times == 0 {
+            return 0.0;
+        }
+        self.total_rt_millis as f64 / self.pull_times as f64
+    }
+
+    pub fn messages_per_pull(&self) -> f64 {
+        if self.pull_times == 0 {
+            return 0.0;
+        }
+        self.pulled_messages as f64 / self.pull_times as f64
+    }
+
+    /// Share of the pulls which found no message, between 0 and 1.
+    pub fn empty_pull_ratio(&self) -> f64 {
+        if self.pull_times == 0 {
+            return 0.0;
+        }
+        self.empty_pull_times as f64 / self.pull_times as f64
+    }
+
+    /// Upper bound of the histogram bucket holding the `percentile` (0 to 100) pull round-trip
+    /// time, the max round-trip time when it falls in the overflow bucket.
+    pub fn rt_percentile_millis(&self, percentile: f64) -> u64 {
+        if self.pull_times == 0 {
+            return 0;
+        }
+        let rank = ((percentile.clamp(0.0, 100.0) / 100.0) * self.pull_times as f64).ceil();
+        let rank = (rank as u64).max(1);
+        let mut seen = 0;
+        for (bucket, count) in self.rt_buckets.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                return PULL_RT_BUCKET_BOUNDS_MILLIS
+                    .get(bucket)
+                    .map_or(self.max_rt_millis, |bound| (*bound).min(self.max_rt_millis));
+            }
+        }
+        self.max_rt_millis
+    }
+}
+
+/// Pull statistics of one consumer group, per message queue.
+///
+/// The tracked queues follow the rebalance result through [`MessageQueueListener`], stats of the
+/// queues no longer assigned to the consumer are dropped.
+#[derive(Clone)]
+pub struct ConsumerPullStats {
+    consumer_group: String,
+    stats_table: Arc<RwLock<HashMap<MessageQueue, PullStats>>>,
+    exporter: Arc<RwLock<Option<Arc<dyn PullStatsExporter>>>>,
+}
+
+impl ConsumerPullStats {
+    fn new(
+        consumer_group: String,
+        exporter: Arc<RwLock<Option<Arc<dyn PullStatsExporter>>>>,
+    ) -> Self {
+        ConsumerPullStats {
+            consumer_group,
+            stats_table: Arc::new(RwLock::new(HashMap::new())),
+            exporter,
+        }
+    }
+
+    pub fn consumer_group(&self) -> &str {
+        self.consumer_group.as_str()
+    }
+
+    /// Records one pull of `message_queue` which took `rt` and returned `message_count`
+    /// messages, 0 for a pull which found nothing.
+    pub fn record_pull(&self, message_queue: &MessageQueue, rt: Duration, message_count: usize) {
+        self.stats_table
+            .write()
+            .entry(message_queue.clone())
+            .or_default()
+            .record(rt.as_millis() as u64, message_count);
+        if let Some(exporter) = self.exporter.read().as_ref() {
+            exporter.export_pull(
+                self.consumer_group.as_str(),
+                message_queue,
+                rt,
+                message_count,
+            );
+        }
+    }
+
+    pub fn pull_stats(&self, message_queue: &MessageQueue) -> Option<PullStats> {
+        self.stats_table.read().get(message_queue).cloned()
+    }
+
+    /// Snapshot of the pull statistics of every queue pulled so far.
+    pub fn pull_stats_table(&self) -> HashMap<MessageQueue, PullStats> {
+        self.stats_table.read().clone()
+    }
+
+    pub fn remove(&self, message_queue: &MessageQueue) {
+        self.stats_table.write().remove(message_queue);
+    }
+}
+
+impl MessageQueueListener for ConsumerPullStats {
+    fn message_queue_changed(
+        &self,
+        topic: &str,
+        _mq_all: &HashSet<MessageQueue>,
+        mq_divided: &HashSet<MessageQueue>,
+    ) {
+        self.stats_table
+            .write()
+            .retain(|mq, _| mq.get_topic() != topic || mq_divided.contains(mq));
+    }
+}
+
+/// Client-wide registry of the pull statistics of every consumer group.
+#[derive(Default)]
+pub struct ConsumerStatsManager {
+    consumer_pull_stats: RwLock<HashMap<String /* consumer group */, ConsumerPullStats>>,
+    exporter: Arc<RwLock<Option<Arc<dyn PullStatsExporter>>>>,
+}
+
+impl ConsumerStatsManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Pull statistics of `consumer_group`, created on first use.
+    pub fn consumer_pull_stats(&self, consumer_group: &str) -> ConsumerPullStats {
+        if let Some(stats) = self.consumer_pull_stats.read().get(consumer_group) {
+            return stats.clone();
+        }
+        self.consumer_pull_stats
+            .write()
+            .entry(consumer_group.to_string())
+            .or_insert_with(|| {
+                ConsumerPullStats::new(consumer_group.to_string(), self.exporter.clone())
+            })
+            .clone()
+    }
+
+    pub fn record_pull(
+        &self,
+        consumer_group: &str,
+        message_queue: &MessageQueue,
+        rt: Duration,
+        message_count: usize,
+    ) {
+        self.consumer_pull_stats(consumer_group)
+            .record_pull(message_queue, rt, message_count);
+    }
+
+    pub fn remove_consumer_group(&self, consumer_group: &str) {
+        self.consumer_pull_stats.write().remove(consumer_group);
+    }
+
+    /// Forwards every pull recorded from now on to `exporter`.
+    pub fn set_exporter(&self, exporter: impl PullStatsExporter) {
+        *self.exporter.write() = Some(Arc::new(exporter));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::atomic::Ordering;
+
+    use super::*;
+
+    fn message_queue(queue_id: i32) -> MessageQueue {
+        MessageQueue::from_parts("TopicTest", "broker-a", queue_id)
+    }
+
+    #[test]
+    fn record_pull_aggregates_per_queue() {
+        let manager = ConsumerStatsManager::new();
+        manager.record_pull("group", &message_queue(0), Duration::from_millis(4), 32);
+        manager.record_pull("group", &message_queue(0), Duration::from_millis(8), 0);
+        manager.record_pull("group", &message_queue(0), Duration::from_millis(40), 16);
+        manager.record_pull("group", &message_queue(0), Duration::from_secs(60), 0);
+        manager.record_pull("group", &message_queue(1), Duration::from_millis(1), 1);
+
+        let stats = manager
+            .consumer_pull_stats("group")
+            .pull_stats(&message_queue(0))
+            .unwrap();
+        assert_eq!(stats.pull_times, 4);
+        assert_eq!(stats.pulled_messages, 48);
+        assert_eq!(stats.messages_per_pull(), 12.0);
+        assert_eq!(stats.empty_pull_ratio(), 0.5);
+        assert_eq!(stats.max_rt_millis, 60_000);
+        assert_eq!(stats.rt_percentile_millis(50.0), 10);
+        assert_eq!(stats.rt_percentile_millis(75.0), 50);
+        assert_eq!(stats.rt_percentile_millis(100.0), 60_000);
+        assert_eq!(
+            manager
+                .consumer_pull_stats("group")
+                .pull_stats_table()
+                .len(),
+            2
+        );
+    }
+
+    #[test]
+    fn rebalance_drops_unassigned_queues_and_exporter_sees_pulls() {
+        struct CountingExporter(Arc<AtomicUsize>);
+        impl PullStatsExporter for CountingExporter {
+            fn export_pull(&self, _: &str, _: &MessageQueue, _: Duration, message_count: usize) {
+                self.0.fetch_add(message_count, Ordering::Relaxed);
+            }
+        }
+
+        let manager = ConsumerStatsManager::new();
+        let exported = Arc::new(AtomicUsize::new(0));
+        manager.set_exporter(CountingExporter(exported.clone()));
+        let stats = manager.consumer_pull_stats("group");
+        stats.record_pull(&message_queue(0), Duration::from_millis(3), 5);
+        stats.record_pull(&message_queue(1), Duration::from_millis(3), 7);
+        assert_eq!(exported.load(Ordering::Relaxed), 12);
+
+        stats.message_queue_changed(
+            "TopicTest",
+            &HashSet::new(),
+            &HashSet::from([message_queue(1)]),
+        );
+        assert!(stats.pull_stats(&message_queue(0)).is_none());
+        assert!(stats.pull_stats(&message_queue(1)).is_some());
+    }
+}
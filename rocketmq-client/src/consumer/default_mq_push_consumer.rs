@@ -0,0 +1,203 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use rocketmq_common::common::consumer::consume_from_where::ConsumeFromWhere;
+use rocketmq_common::common::message::message_queue::MessageQueue;
+use rocketmq_remoting::protocol::heartbeat::message_model::MessageModel;
+
+use crate::base::client_config::ClientConfig;
+use crate::consumer::allocate_message_queue_strategy::AllocateMessageQueueStrategy;
+use crate::consumer::consumer_impl::default_mq_push_consumer_impl::DefaultMQPushConsumerImpl;
+use crate::consumer::consumer_lag::LagThresholdEvent;
+use crate::consumer::consumer_lag::QueueLag;
+use crate::consumer::default_mq_push_consumer_builder::DefaultMQPushConsumerBuilder;
+use crate::consumer::message_queue_listener::MessageQueueListener;
+use crate::consumer::rebalance_strategy::allocate_message_queue_averagely::AllocateMessageQueueAveragely;
+use crate::Result;
+
+#[derive(Clone)]
+pub struct ConsumerConfig {
+    /// Consumers of the same role are required to have exactly the same subscriptions and
+    /// consumer group to correctly achieve load balance.
+    pub(crate) consumer_group: String,
+    /// Clustering divides the queues of a topic among the consumers of the group, broadcasting
+    /// delivers every message to each of them.
+    pub(crate) message_model: MessageModel,
+    /// Where a consumer group seen for the first time starts consuming.
+    pub(crate) consume_from_where: ConsumeFromWhere,
+    /// Strategy dividing the queues of a topic among the consumers of the group.
+    pub(crate) allocate_message_queue_strategy: Arc<dyn AllocateMessageQueueStrategy>,
+    /// Subscription expression per topic.
+    pub(crate) subscription: HashMap<String, String>,
+    /// Consumes the messages of a queue one at a time, in queue order. Only meaningful for
+    /// clustering consumers, which own their queues.
+    pub(crate) consume_orderly: bool,
+    /// Minimum number of consuming threads.
+    pub(crate) consume_thread_min: u32,
+    /// Maximum number of consuming threads.
+    pub(crate) consume_thread_max: u32,
+    /// How long shutdown waits for the messages handed to the listener before persisting the
+    /// offsets, 0 means not to wait.
+    pub(crate) await_termination_millis_when_shutdown: u64,
+}
+
+impl Default for ConsumerConfig {
+    fn default() -> Self {
+        Self {
+            consumer_group: String::new(),
+            message_model: MessageModel::Clustering,
+            consume_from_where: ConsumeFromWhere::ConsumeFromLastOffset,
+            allocate_message_queue_strategy: Arc::new(AllocateMessageQueueAveragely),
+            subscription: HashMap::new(),
+            consume_orderly: false,
+            consume_thread_min: 20,
+            consume_thread_max: 20,
+            await_termination_millis_when_shutdown: 0,
+        }
+    }
+}
+
+impl ConsumerConfig {
+    pub fn consumer_group(&self) -> &str {
+        &self.consumer_group
+    }
+
+    pub fn message_model(&self) -> MessageModel {
+        self.message_model
+    }
+
+    pub fn consume_from_where(&self) -> ConsumeFromWhere {
+        self.consume_from_where
+    }
+
+    pub fn allocate_message_queue_strategy(&self) -> &Arc<dyn AllocateMessageQueueStrategy> {
+        &self.allocate_message_queue_strategy
+    }
+
+    pub fn subscription(&self) -> &HashMap<String, String> {
+        &self.subscription
+    }
+
+    pub fn consume_orderly(&self) -> bool {
+        self.consume_orderly
+    }
+
+    pub fn consume_thread_min(&self) -> u32 {
+        self.consume_thread_min
+    }
+
+    pub fn consume_thread_max(&self) -> u32 {
+        self.consume_thread_max
+    }
+
+    pub fn await_termination_millis_when_shutdown(&self) -> u64 {
+        self.await_termination_millis_when_shutdown
+    }
+}
+
+/// Consumer which is handed the queues of its topics by the rebalance of its group.
+pub struct DefaultMQPushConsumer {
+    pub(crate) default_mqpush_consumer_impl: DefaultMQPushConsumerImpl,
+}
+
+impl DefaultMQPushConsumer {
+    pub fn builder() -> DefaultMQPushConsumerBuilder {
+        DefaultMQPushConsumerBuilder::new()
+    }
+
+    pub fn client_config(&self) -> &ClientConfig {
+        self.default_mqpush_consumer_impl.client_config()
+    }
+
+    pub fn consumer_config(&self) -> &ConsumerConfig {
+        self.default_mqpush_consumer_impl.consumer_config()
+    }
+
+    pub async fn start(&mut self) -> Result<()> {
+        self.default_mqpush_consumer_impl.start().await
+    }
+
+    pub async fn shutdown(&mut self) {
+        self.default_mqpush_consumer_impl.shutdown().await
+    }
+
+    /// Subscribes to the tags of `topic` matching `sub_expression`, `*` for all of them. Once
+    /// started, the brokers learn of the subscription right away and the queues of the topic are
+    /// rebalanced.
+    pub async fn subscribe(&mut self, topic: &str, sub_expression: &str) -> Result<()> {
+        self.default_mqpush_consumer_impl
+            .subscribe(topic, sub_expression)
+            .await
+    }
+
+    /// Stops consuming `topic`. Returns whether the consumer was subscribed to it.
+    pub async fn unsubscribe(&mut self, topic: &str) -> bool {
+        self.default_mqpush_consumer_impl.unsubscribe(topic).await
+    }
+
+    /// Calls `listener` whenever a rebalance changes the queues of `topic` assigned to this
+    /// consumer.
+    pub fn register_message_queue_listener(
+        &self,
+        topic: impl Into<String>,
+        listener: Arc<dyn MessageQueueListener>,
+    ) {
+        self.default_mqpush_consumer_impl
+            .register_message_queue_listener(topic, listener);
+    }
+
+    /// Queues owned by this consumer per topic, as of the last rebalance.
+    pub fn allocation(&self) -> HashMap<String /* topic */, HashSet<MessageQueue>> {
+        self.default_mqpush_consumer_impl.allocation()
+    }
+
+    /// Lag of every queue owned by this consumer, refreshed every
+    /// `consumer_lag_refresh_interval` while the consumer runs.
+    pub fn lag_metrics(&self) -> HashMap<MessageQueue, QueueLag> {
+        self.default_mqpush_consumer_impl
+            .consumer_lag_metrics()
+            .lag_metrics()
+    }
+
+    pub fn total_lag(&self) -> i64 {
+        self.default_mqpush_consumer_impl
+            .consumer_lag_metrics()
+            .total_lag()
+    }
+
+    /// Calls `callback` whenever the lag of an owned queue goes above `lag`, and again once it
+    /// is back at or below it.
+    pub fn set_lag_threshold<F>(&self, lag: i64, callback: F)
+    where
+        F: Fn(&LagThresholdEvent) + Send + Sync + 'static,
+    {
+        self.default_mqpush_consumer_impl
+            .consumer_lag_metrics()
+            .set_lag_threshold(lag, callback);
+    }
+
+    /// Rebalances right away instead of waiting for the rebalance service, e.g. to check the
+    /// assignment after scaling the consumers, and returns the queues owned afterwards.
+    pub async fn rebalance_now(
+        &self,
+    ) -> Result<HashMap<String /* topic */, HashSet<MessageQueue>>> {
+        self.default_mqpush_consumer_impl.rebalance_now().await
+    }
+}
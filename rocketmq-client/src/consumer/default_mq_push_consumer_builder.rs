@@ -0,0 +1,288 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use rocketmq_common::common::consumer::consume_from_where::ConsumeFromWhere;
+use rocketmq_remoting::protocol::heartbeat::message_model::MessageModel;
+use rocketmq_remoting::runtime::RPCHook;
+
+use crate::acl::acl_client_rpc_hook::AclClientRPCHook;
+use crate::base::client_config::ClientConfig;
+use crate::base::validators::Validators;
+use crate::consumer::allocate_message_queue_strategy::AllocateMessageQueueStrategy;
+use crate::consumer::consumer_impl::default_mq_push_consumer_impl::DefaultMQPushConsumerImpl;
+use crate::consumer::default_mq_push_consumer::ConsumerConfig;
+use crate::consumer::default_mq_push_consumer::DefaultMQPushConsumer;
+use crate::consumer::store::offset_store::OffsetStore;
+use crate::Result;
+
+#[derive(Default)]
+pub struct DefaultMQPushConsumerBuilder {
+    client_config: Option<ClientConfig>,
+    consumer_group: Option<String>,
+    message_model: Option<MessageModel>,
+    consume_from_where: Option<ConsumeFromWhere>,
+    allocate_message_queue_strategy: Option<Arc<dyn AllocateMessageQueueStrategy>>,
+    subscription: HashMap<String, String>,
+    consume_orderly: Option<bool>,
+    consume_thread_min: Option<u32>,
+    consume_thread_max: Option<u32>,
+    await_termination_millis_when_shutdown: Option<u64>,
+    offset_store: Option<Box<dyn OffsetStore>>,
+    rpc_hook: Option<Arc<Box<dyn RPCHook>>>,
+}
+
+impl DefaultMQPushConsumerBuilder {
+    pub fn new() -> Self {
+        Self {
+            client_config: Some(Default::default()),
+            consumer_group: None,
+            message_model: None,
+            consume_from_where: None,
+            allocate_message_queue_strategy: None,
+            subscription: HashMap::new(),
+            consume_orderly: None,
+            consume_thread_min: None,
+            consume_thread_max: None,
+            await_termination_millis_when_shutdown: None,
+            offset_store: None,
+            rpc_hook: None,
+        }
+    }
+
+    pub fn client_config(mut self, client_config: ClientConfig) -> Self {
+        self.client_config = Some(client_config);
+        self
+    }
+
+    pub fn consumer_group(mut self, consumer_group: impl Into<String>) -> Self {
+        self.consumer_group = Some(consumer_group.into());
+        self
+    }
+
+    pub fn name_server_addr(mut self, name_server_addr: String) -> Self {
+        if let Some(client_config) = self.client_config.as_mut() {
+            client_config.namesrv_addr = Some(name_server_addr);
+            client_config
+                .namespace_initialized
+                .store(false, std::sync::atomic::Ordering::Release);
+        }
+        self
+    }
+
+    pub fn message_model(mut self, message_model: MessageModel) -> Self {
+        self.message_model = Some(message_model);
+        self
+    }
+
+    pub fn consume_from_where(mut self, consume_from_where: ConsumeFromWhere) -> Self {
+        self.consume_from_where = Some(consume_from_where);
+        self
+    }
+
+    pub fn allocate_message_queue_strategy(
+        mut self,
+        allocate_message_queue_strategy: Arc<dyn AllocateMessageQueueStrategy>,
+    ) -> Self {
+        self.allocate_message_queue_strategy = Some(allocate_message_queue_strategy);
+        self
+    }
+
+    /// Subscribes to the tags of `topic` matching `sub_expression` once the consumer starts.
+    pub fn subscribe(
+        mut self,
+        topic: impl Into<String>,
+        sub_expression: impl Into<String>,
+    ) -> Self {
+        self.subscription
+            .insert(topic.into(), sub_expression.into());
+        self
+    }
+
+    pub fn consume_orderly(mut self, consume_orderly: bool) -> Self {
+        self.consume_orderly = Some(consume_orderly);
+        self
+    }
+
+    pub fn consume_thread_min(mut self, consume_thread_min: u32) -> Self {
+        self.consume_thread_min = Some(consume_thread_min);
+        self
+    }
+
+    pub fn consume_thread_max(mut self, consume_thread_max: u32) -> Self {
+        self.consume_thread_max = Some(consume_thread_max);
+        self
+    }
+
+    pub fn await_termination_millis_when_shutdown(
+        mut self,
+        await_termination_millis_when_shutdown: u64,
+    ) -> Self {
+        self.await_termination_millis_when_shutdown = Some(await_termination_millis_when_shutdown);
+        self
+    }
+
+    /// Keeps the consume progress in `offset_store` instead of in memory.
+    pub fn offset_store(mut self, offset_store: Box<dyn OffsetStore>) -> Self {
+        self.offset_store = Some(offset_store);
+        self
+    }
+
+    pub fn rpc_hook(mut self, rpc_hook: Box<dyn RPCHook>) -> Self {
+        self.rpc_hook = Some(Arc::new(rpc_hook));
+        self
+    }
+
+    /// Builds the consumer, rejecting a configuration its `start()` would refuse.
+    pub fn try_build(self) -> Result<DefaultMQPushConsumer> {
+        if let Some(namesrv_addr) = self
+            .client_config
+            .as_ref()
+            .and_then(|client_config| client_config.namesrv_addr.as_deref())
+        {
+            Validators::check_name_server_addr(namesrv_addr)?;
+        }
+        let consumer = self.build();
+        Validators::check_consumer_config(consumer.consumer_config())?;
+        Ok(consumer)
+    }
+
+    pub fn build(self) -> DefaultMQPushConsumer {
+        let mut rpc_hook = self.rpc_hook;
+        let client_config = self.client_config.unwrap_or_default();
+        if let (None, Some(credentials)) = (&rpc_hook, &client_config.credentials) {
+            let hook: Box<dyn RPCHook> =
+                Box::new(AclClientRPCHook::with_credentials(credentials.clone()));
+            rpc_hook = Some(Arc::new(hook));
+        }
+        let mut consumer_config = ConsumerConfig::default();
+        if let Some(consumer_group) = self.consumer_group {
+            consumer_config.consumer_group = consumer_group;
+        }
+        if let Some(message_model) = self.message_model {
+            consumer_config.message_model = message_model;
+        }
+        if let Some(consume_from_where) = self.consume_from_where {
+            consumer_config.consume_from_where = consume_from_where;
+        }
+        if let Some(allocate_message_queue_strategy) = self.allocate_message_queue_strategy {
+            consumer_config.allocate_message_queue_strategy = allocate_message_queue_strategy;
+        }
+        consumer_config.subscription = self.subscription;
+        if let Some(consume_orderly) = self.consume_orderly {
+            consumer_config.consume_orderly = consume_orderly;
+        }
+        if let Some(consume_thread_min) = self.consume_thread_min {
+            consumer_config.consume_thread_min = consume_thread_min;
+        }
+        if let Some(consume_thread_max) = self.consume_thread_max {
+            consumer_config.consume_thread_max = consume_thread_max;
+        }
+        if let Some(await_termination_millis_when_shutdown) =
+            self.await_termination_millis_when_shutdown
+        {
+            consumer_config.await_termination_millis_when_shutdown =
+                await_termination_millis_when_shutdown;
+        }
+        let mut default_mqpush_consumer_impl =
+            DefaultMQPushConsumerImpl::new(client_config, consumer_config, rpc_hook);
+        if let Some(offset_store) = self.offset_store {
+            default_mqpush_consumer_impl.set_offset_store(offset_store);
+        }
+        DefaultMQPushConsumer {
+            default_mqpush_consumer_impl,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rocketmq_common::common::mix_all;
+
+    use super::*;
+    use crate::error::ClientErrorKind;
+    use crate::error::MQClientError;
+
+    fn error_kind(result: Result<DefaultMQPushConsumer>) -> Option<ClientErrorKind> {
+        match result {
+            Err(MQClientError::IllegalClientConfig(kind, _)) => Some(kind),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn try_build_rejects_reserved_group_and_bad_name_server() {
+        assert!(DefaultMQPushConsumer::builder()
+            .consumer_group("please_rename_unique_group_name")
+            .try_build()
+            .is_ok());
+        assert_eq!(
+            error_kind(DefaultMQPushConsumer::builder().try_build()),
+            Some(ClientErrorKind::IllegalGroupName)
+        );
+        assert_eq!(
+            error_kind(
+                DefaultMQPushConsumer::builder()
+                    .consumer_group(mix_all::DEFAULT_CONSUMER_GROUP)
+                    .try_build()
+            ),
+            Some(ClientErrorKind::ReservedGroupName)
+        );
+        assert_eq!(
+            error_kind(
+                DefaultMQPushConsumer::builder()
+                    .consumer_group("please_rename_unique_group_name")
+                    .name_server_addr("127.0.0.1".to_string())
+                    .try_build()
+            ),
+            Some(ClientErrorKind::IllegalNameServerAddress)
+        );
+    }
+
+    #[test]
+    fn try_build_rejects_conflicting_options() {
+        assert_eq!(
+            error_kind(
+                DefaultMQPushConsumer::builder()
+                    .consumer_group("please_rename_unique_group_name")
+                    .message_model(MessageModel::Broadcasting)
+                    .consume_orderly(true)
+                    .try_build()
+            ),
+            Some(ClientErrorKind::ConflictingOptions)
+        );
+        assert_eq!(
+            error_kind(
+                DefaultMQPushConsumer::builder()
+                    .consumer_group("please_rename_unique_group_name")
+                    .consume_thread_min(30)
+                    .try_build()
+            ),
+            Some(ClientErrorKind::ConflictingOptions)
+        );
+        assert_eq!(
+            error_kind(
+                DefaultMQPushConsumer::builder()
+                    .consumer_group("please_rename_unique_group_name")
+                    .consume_thread_min(0)
+                    .try_build()
+            ),
+            Some(ClientErrorKind::IllegalOptionValue)
+        );
+    }
+}
@@ -14,7 +14,10 @@
  * See the License for the specific language governing permissions and
  * limitations under the License.
  */
+use std::collections::HashMap;
 use std::collections::HashSet;
+use std::future::Future;
+use std::pin::Pin;
 
 use rocketmq_common::common::consumer::consume_from_where::ConsumeFromWhere;
 use rocketmq_common::common::message::message_queue::MessageQueue;
@@ -23,6 +26,11 @@ use rocketmq_remoting::protocol::heartbeat::consume_type::ConsumeType;
 use rocketmq_remoting::protocol::heartbeat::message_model::MessageModel;
 use rocketmq_remoting::protocol::heartbeat::subscription_data::SubscriptionData;
 
+use crate::Result;
+
+/// Future of a rebalance, boxed so consumers can be kept as trait objects.
+pub type RebalanceFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
 pub trait MQConsumerInner: Send + Sync + 'static {
     fn group_name(&self) -> &str;
 
@@ -32,11 +40,24 @@ pub trait MQConsumerInner: Send + Sync + 'static {
 
     fn consume_from_where(&self) -> ConsumeFromWhere;
 
-    fn subscriptions(&self) -> &HashSet<SubscriptionData>;
+    fn subscriptions(&self) -> HashSet<SubscriptionData>;
+
+    /// Subscribes to `topic` with the tag expression `sub_expression` while the consumer runs,
+    /// replacing the previous subscription to the topic.
+    fn subscribe(&self, topic: &str, sub_expression: &str) -> Result<()>;
+
+    /// Stops consuming `topic` while the consumer runs, dropping the process queues and the
+    /// cached offsets of its queues. Returns whether the consumer was subscribed to it.
+    fn unsubscribe(&self, topic: &str) -> bool;
+
+    fn do_rebalance(&self) -> RebalanceFuture<'_, ()>;
 
-    fn do_rebalance(&self);
+    /// Rebalances every subscribed topic, returning `false` if any of them could not be
+    /// balanced.
+    fn try_rebalance(&self) -> RebalanceFuture<'_, bool>;
 
-    fn try_rebalance(&self) -> bool;
+    /// Queues owned by this consumer per topic, as of the last rebalance.
+    fn allocation(&self) -> HashMap<String /* topic */, HashSet<MessageQueue>>;
 
     fn persist_consumer_offset(&self);
 
@@ -0,0 +1,57 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+pub mod allocate_message_queue_averagely;
+pub mod allocate_message_queue_by_zone;
+pub mod allocate_message_queue_sticky;
+
+use rocketmq_common::common::message::message_queue::MessageQueue;
+use tracing::info;
+
+use crate::error::MQClientError;
+use crate::Result;
+
+/// Rejects empty inputs and returns whether `current_cid` takes part in the allocation.
+pub(crate) fn check(
+    consumer_group: &str,
+    current_cid: &str,
+    mq_all: &[MessageQueue],
+    cid_all: &[String],
+) -> Result<bool> {
+    if current_cid.is_empty() {
+        return Err(MQClientError::IllegalArgument(
+            "currentCID is empty".to_string(),
+        ));
+    }
+    if mq_all.is_empty() {
+        return Err(MQClientError::IllegalArgument(
+            "mqAll is null or mqAll empty".to_string(),
+        ));
+    }
+    if cid_all.is_empty() {
+        return Err(MQClientError::IllegalArgument(
+            "cidAll is null or cidAll empty".to_string(),
+        ));
+    }
+    if !cid_all.iter().any(|cid| cid == current_cid) {
+        info!(
+            "[BUG] ConsumerGroup: {} The consumerId: {} not in cidAll: {:?}",
+            consumer_group, current_cid, cid_all
+        );
+        return Ok(false);
+    }
+    Ok(true)
+}
@@ -0,0 +1,91 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use rocketmq_common::common::message::message_queue::MessageQueue;
+
+use crate::consumer::allocate_message_queue_strategy::AllocateMessageQueueStrategy;
+use crate::consumer::rebalance_strategy::check;
+use crate::Result;
+
+/// Gives each consumer a contiguous run of queues, the first `mq_all.len() % cid_all.len()`
+/// consumers taking one more than the others.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct AllocateMessageQueueAveragely;
+
+impl AllocateMessageQueueStrategy for AllocateMessageQueueAveragely {
+    fn allocate(
+        &self,
+        consumer_group: &str,
+        current_cid: &str,
+        mq_all: &[MessageQueue],
+        cid_all: &[String],
+    ) -> Result<Vec<MessageQueue>> {
+        if !check(consumer_group, current_cid, mq_all, cid_all)? {
+            return Ok(Vec::new());
+        }
+        let index = cid_all
+            .iter()
+            .position(|cid| cid == current_cid)
+            .unwrap_or_default();
+        let mod_ = mq_all.len() % cid_all.len();
+        let average_size = if mq_all.len() <= cid_all.len() {
+            1
+        } else if mod_ > 0 && index < mod_ {
+            mq_all.len() / cid_all.len() + 1
+        } else {
+            mq_all.len() / cid_all.len()
+        };
+        let start_index = if mod_ > 0 && index < mod_ {
+            index * average_size
+        } else {
+            index * average_size + mod_
+        };
+        let range = average_size.min(mq_all.len().saturating_sub(start_index));
+        Ok((0..range)
+            .map(|i| mq_all[(start_index + i) % mq_all.len()].clone())
+            .collect())
+    }
+
+    fn get_name(&self) -> &'static str {
+        "AVG"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn allocate_splits_queues_evenly() {
+        let mq_all: Vec<MessageQueue> = (0..5)
+            .map(|id| MessageQueue::from_parts("T", "broker-a", id))
+            .collect();
+        let cid_all = vec!["c0".to_string(), "c1".to_string()];
+        let strategy = AllocateMessageQueueAveragely;
+        assert_eq!(
+            strategy.allocate("g", "c0", &mq_all, &cid_all).unwrap(),
+            mq_all[..3]
+        );
+        assert_eq!(
+            strategy.allocate("g", "c1", &mq_all, &cid_all).unwrap(),
+            mq_all[3..]
+        );
+        assert!(strategy
+            .allocate("g", "c2", &mq_all, &cid_all)
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,152 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use rocketmq_common::common::message::message_queue::MessageQueue;
+
+use crate::consumer::allocate_message_queue_strategy::AllocateMessageQueueStrategy;
+use crate::consumer::rebalance_strategy::check;
+use crate::Result;
+
+/// Tells the zone brokers and consumers run in, e.g. from the zones of the topic route and a
+/// naming convention of the client ids.
+pub trait ZoneResolver: Send + Sync {
+    fn broker_zone(&self, message_queue: &MessageQueue) -> Option<String>;
+
+    fn consumer_zone(&self, client_id: &str) -> Option<String>;
+}
+
+/// Pulls from the brokers of the zone of the consumer: the queues of a zone are divided by
+/// `strategy` among the consumers of that zone only. Queues of a zone without a consumer, or
+/// whose zone is unknown, are divided among all consumers so that nothing is left unconsumed.
+pub struct AllocateMessageQueueByZone {
+    strategy: Arc<dyn AllocateMessageQueueStrategy>,
+    resolver: Arc<dyn ZoneResolver>,
+}
+
+impl AllocateMessageQueueByZone {
+    pub fn new(
+        strategy: Arc<dyn AllocateMessageQueueStrategy>,
+        resolver: Arc<dyn ZoneResolver>,
+    ) -> Self {
+        Self { strategy, resolver }
+    }
+}
+
+impl AllocateMessageQueueStrategy for AllocateMessageQueueByZone {
+    fn allocate(
+        &self,
+        consumer_group: &str,
+        current_cid: &str,
+        mq_all: &[MessageQueue],
+        cid_all: &[String],
+    ) -> Result<Vec<MessageQueue>> {
+        if !check(consumer_group, current_cid, mq_all, cid_all)? {
+            return Ok(Vec::new());
+        }
+        let mut zone_mqs: BTreeMap<Option<String>, Vec<MessageQueue>> = BTreeMap::new();
+        for mq in mq_all {
+            zone_mqs
+                .entry(self.resolver.broker_zone(mq))
+                .or_default()
+                .push(mq.clone());
+        }
+        let mut zone_cids: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for cid in cid_all {
+            if let Some(zone) = self.resolver.consumer_zone(cid) {
+                zone_cids.entry(zone).or_default().push(cid.clone());
+            }
+        }
+        let current_zone = self.resolver.consumer_zone(current_cid);
+        let mut result = Vec::new();
+        for (zone, mqs) in zone_mqs {
+            match zone.as_ref().and_then(|zone| zone_cids.get(zone)) {
+                Some(cids) => {
+                    if zone == current_zone {
+                        result.extend(self.strategy.allocate(
+                            consumer_group,
+                            current_cid,
+                            &mqs,
+                            cids,
+                        )?);
+                    }
+                }
+                None => result.extend(self.strategy.allocate(
+                    consumer_group,
+                    current_cid,
+                    &mqs,
+                    cid_all,
+                )?),
+            }
+        }
+        Ok(result)
+    }
+
+    fn get_name(&self) -> &'static str {
+        "ZONE"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consumer::rebalance_strategy::allocate_message_queue_averagely::AllocateMessageQueueAveragely;
+
+    struct PrefixZoneResolver;
+
+    impl ZoneResolver for PrefixZoneResolver {
+        fn broker_zone(&self, message_queue: &MessageQueue) -> Option<String> {
+            message_queue
+                .get_broker_name()
+                .split_once('-')
+                .map(|(zone, _)| zone.to_string())
+        }
+
+        fn consumer_zone(&self, client_id: &str) -> Option<String> {
+            client_id.split_once('-').map(|(zone, _)| zone.to_string())
+        }
+    }
+
+    #[test]
+    fn allocate_keeps_queues_in_the_zone_of_the_consumer() {
+        let mq_all: Vec<MessageQueue> = ["az1-broker", "az2-broker", "az3-broker"]
+            .into_iter()
+            .flat_map(|broker_name| {
+                (0..2).map(move |id| MessageQueue::from_parts("T", broker_name, id))
+            })
+            .collect();
+        let cid_all = vec![
+            "az1-c0".to_string(),
+            "az1-c1".to_string(),
+            "az2-c0".to_string(),
+        ];
+        let strategy = AllocateMessageQueueByZone::new(
+            Arc::new(AllocateMessageQueueAveragely),
+            Arc::new(PrefixZoneResolver),
+        );
+
+        let allocated: Vec<Vec<MessageQueue>> = cid_all
+            .iter()
+            .map(|cid| strategy.allocate("g", cid, &mq_all, &cid_all).unwrap())
+            .collect();
+        // az3 has no consumer, its queues are shared by everyone.
+        assert_eq!(allocated[0], vec![mq_all[0].clone(), mq_all[4].clone()]);
+        assert_eq!(allocated[1], vec![mq_all[1].clone(), mq_all[5].clone()]);
+        assert_eq!(allocated[2], mq_all[2..4].to_vec());
+    }
+}
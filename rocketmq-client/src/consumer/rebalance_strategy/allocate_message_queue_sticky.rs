@@ -0,0 +1,266 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use rocketmq_common::common::message::message_queue::MessageQueue;
+use rocketmq_common::utils::crc32_utils::crc32;
+
+use crate::consumer::allocate_message_queue_strategy::AllocateMessageQueueStrategy;
+use crate::consumer::rebalance_strategy::check;
+use crate::Result;
+
+/// Wraps a strategy so queues mostly stay with the consumer owning them when the group
+/// membership or the queues of the topic change, which keeps queue movement and offset churn low
+/// during rolling deploys.
+///
+/// Each consumer takes as many queues as the wrapped strategy would give it, but which queues is
+/// decided by a weight of every queue and consumer pair: the pairs are handed out heaviest first,
+/// as long as the queue is free and the consumer below its quota. The weight of a pair does not
+/// depend on the other members, so a consumer leaving or joining only moves the queues it held or
+/// wins. The division is a function of `mq_all` and `cid_all` alone, every consumer of the group
+/// computes the same one without remembering anything between rebalances.
+pub struct AllocateMessageQueueSticky<S> {
+    delegate: S,
+}
+
+impl<S: AllocateMessageQueueStrategy> AllocateMessageQueueSticky<S> {
+    pub fn new(delegate: S) -> Self {
+        Self { delegate }
+    }
+
+    /// Divides `mq_all` among `cid_all`, in the order of `cid_all`.
+    fn assign(
+        &self,
+        consumer_group: &str,
+        mq_all: &[MessageQueue],
+        cid_all: &[String],
+    ) -> Result<Vec<Vec<MessageQueue>>> {
+        let mut quotas = cid_all
+            .iter()
+            .map(|cid| {
+                self.delegate
+                    .allocate(consumer_group, cid, mq_all, cid_all)
+                    .map(|mqs| mqs.len())
+            })
+            .collect::<Result<Vec<_>>>()?;
+        // A delegate leaving queues out would strand them, spread the rest round robin.
+        let missing = mq_all.len().saturating_sub(quotas.iter().sum());
+        for index in 0..missing {
+            quotas[index % cid_all.len()] += 1;
+        }
+
+        let mut pairs = Vec::with_capacity(mq_all.len() * cid_all.len());
+        for (mq_index, mq) in mq_all.iter().enumerate() {
+            for (cid_index, cid) in cid_all.iter().enumerate() {
+                pairs.push((weight(cid, mq), cid_index, mq_index));
+            }
+        }
+        pairs.sort_unstable_by(|a, b| b.cmp(a));
+
+        let mut assignment = vec![Vec::new(); cid_all.len()];
+        let mut taken = vec![false; mq_all.len()];
+        let mut remaining = mq_all.len();
+        for (_, cid_index, mq_index) in pairs {
+            if taken[mq_index] || assignment[cid_index].len() >= quotas[cid_index] {
+                continue;
+            }
+            taken[mq_index] = true;
+            assignment[cid_index].push(mq_all[mq_index].clone());
+            remaining -= 1;
+            if remaining == 0 {
+                break;
+            }
+        }
+        for mqs in assignment.iter_mut() {
+            mqs.sort();
+        }
+        Ok(assignment)
+    }
+}
+
+/// Weight of handing `mq` to `cid`, the same on every client.
+fn weight(cid: &str, mq: &MessageQueue) -> u32 {
+    crc32(
+        format!(
+            "{}@{}@{}@{}",
+            cid,
+            mq.get_broker_name(),
+            mq.get_topic(),
+            mq.get_queue_id()
+        )
+        .as_bytes(),
+    )
+}
+
+impl<S: AllocateMessageQueueStrategy> AllocateMessageQueueStrategy
+    for AllocateMessageQueueSticky<S>
+{
+    fn allocate(
+        &self,
+        consumer_group: &str,
+        current_cid: &str,
+        mq_all: &[MessageQueue],
+        cid_all: &[String],
+    ) -> Result<Vec<MessageQueue>> {
+        if !check(consumer_group, current_cid, mq_all, cid_all)? {
+            return Ok(Vec::new());
+        }
+        let index = cid_all
+            .iter()
+            .position(|cid| cid == current_cid)
+            .unwrap_or_default();
+        Ok(self
+            .assign(consumer_group, mq_all, cid_all)?
+            .swap_remove(index))
+    }
+
+    fn get_name(&self) -> &'static str {
+        "STICKY"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::consumer::rebalance_strategy::allocate_message_queue_averagely::AllocateMessageQueueAveragely;
+
+    fn queues(count: i32) -> Vec<MessageQueue> {
+        (0..count)
+            .map(|id| MessageQueue::from_parts("T", "broker-a", id))
+            .collect()
+    }
+
+    fn cids(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    /// Owner of every queue, each consumer allocating with a strategy of its own.
+    fn owners(
+        strategy: impl Fn() -> Box<dyn AllocateMessageQueueStrategy>,
+        mq_all: &[MessageQueue],
+        cid_all: &[String],
+    ) -> HashMap<MessageQueue, String> {
+        let mut owners = HashMap::new();
+        for cid in cid_all {
+            let mqs = strategy().allocate("g", cid, mq_all, cid_all).unwrap();
+            let quota = AllocateMessageQueueAveragely
+                .allocate("g", cid, mq_all, cid_all)
+                .unwrap()
+                .len();
+            assert_eq!(mqs.len(), quota, "{} takes its share", cid);
+            for mq in mqs {
+                let previous = owners.insert(mq.clone(), cid.clone());
+                assert!(previous.is_none(), "{:?} is allocated twice", mq);
+            }
+        }
+        assert_eq!(owners.len(), mq_all.len(), "every queue is allocated");
+        owners
+    }
+
+    fn sticky() -> Box<dyn AllocateMessageQueueStrategy> {
+        Box::new(AllocateMessageQueueSticky::new(
+            AllocateMessageQueueAveragely,
+        ))
+    }
+
+    fn averagely() -> Box<dyn AllocateMessageQueueStrategy> {
+        Box::new(AllocateMessageQueueAveragely)
+    }
+
+    fn moved(
+        before: &HashMap<MessageQueue, String>,
+        after: &HashMap<MessageQueue, String>,
+        cid_all: &[String],
+    ) -> usize {
+        before
+            .iter()
+            .filter(|(mq, cid)| cid_all.contains(cid) && after[*mq] != **cid)
+            .count()
+    }
+
+    #[test]
+    fn allocations_of_the_members_are_disjoint_and_cover_the_queues() {
+        for (queue_count, names) in [
+            (9, &["c1", "c2", "c9"][..]),
+            (8, &["c0", "c1", "c2"][..]),
+            (2, &["c0", "c1", "c2"][..]),
+            (16, &["c0"][..]),
+        ] {
+            owners(sticky, &queues(queue_count), &cids(names));
+        }
+    }
+
+    #[test]
+    fn replaced_consumer_queues_move_to_newcomer_only() {
+        let mq_all = queues(9);
+        let before = owners(sticky, &mq_all, &cids(&["c0", "c1", "c2"]));
+
+        // c0 is replaced by c9 in a rolling deploy.
+        let cid_all = cids(&["c1", "c2", "c9"]);
+        let after = owners(sticky, &mq_all, &cid_all);
+        for (mq, cid) in before.iter() {
+            if cid == "c0" {
+                assert_eq!(after[mq], "c9");
+            }
+        }
+        assert_eq!(moved(&before, &after, &cid_all), 0);
+    }
+
+    #[test]
+    fn rolling_deploy_moves_fewer_queues_than_the_delegate() {
+        let mq_all = queues(16);
+        let mut members = cids(&["c0", "c1", "c2", "c3"]);
+        let (mut sticky_moves, mut averagely_moves) = (0, 0);
+        for generation in 0..4 {
+            let mut next = members.clone();
+            next[generation] = format!("n{}", generation);
+            next.sort();
+            sticky_moves += moved(
+                &owners(sticky, &mq_all, &members),
+                &owners(sticky, &mq_all, &next),
+                &next,
+            );
+            averagely_moves += moved(
+                &owners(averagely, &mq_all, &members),
+                &owners(averagely, &mq_all, &next),
+                &next,
+            );
+            members = next;
+        }
+        assert!(
+            sticky_moves < averagely_moves,
+            "sticky moved {} queues, averagely {}",
+            sticky_moves,
+            averagely_moves
+        );
+    }
+
+    #[test]
+    fn allocation_does_not_depend_on_earlier_rebalances() {
+        let mq_all = queues(6);
+        let cid_all = cids(&["c0", "c1", "c2"]);
+        let strategy = AllocateMessageQueueSticky::new(AllocateMessageQueueAveragely);
+        let fresh = strategy.allocate("g", "c1", &mq_all, &cid_all).unwrap();
+        strategy
+            .allocate("g", "c1", &mq_all, &cids(&["c0", "c1"]))
+            .unwrap();
+        assert_eq!(
+            strategy.allocate("g", "c1", &mq_all, &cid_all).unwrap(),
+            fresh
+        );
+    }
+}
@@ -0,0 +1,77 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+use parking_lot::RwLock;
+use rocketmq_common::common::message::message_queue::MessageQueue;
+
+use crate::consumer::store::offset_store::OffsetStore;
+use crate::consumer::store::read_offset_type::ReadOffsetType;
+use crate::Result;
+
+/// Keeps offsets in memory only, so consuming restarts from the consume-from-where position
+/// after a restart. Useful for tests and for consumers which track progress themselves.
+#[derive(Default)]
+pub struct MemoryOffsetStore {
+    offset_table: RwLock<HashMap<MessageQueue, i64>>,
+}
+
+impl MemoryOffsetStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl OffsetStore for MemoryOffsetStore {
+    fn load(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn update_offset(&self, mq: &MessageQueue, offset: i64, increase_only: bool) {
+        let mut offset_table = self.offset_table.write();
+        let current = offset_table.entry(mq.clone()).or_insert(offset);
+        if !increase_only || offset > *current {
+            *current = offset;
+        }
+    }
+
+    fn read_offset(&self, mq: &MessageQueue, _read_type: ReadOffsetType) -> Result<Option<i64>> {
+        Ok(self.offset_table.read().get(mq).copied())
+    }
+
+    fn persist_all(&self, _mqs: &HashSet<MessageQueue>) -> Result<()> {
+        Ok(())
+    }
+
+    fn persist(&self, _mq: &MessageQueue) -> Result<()> {
+        Ok(())
+    }
+
+    fn remove_offset(&self, mq: &MessageQueue) {
+        self.offset_table.write().remove(mq);
+    }
+
+    fn clone_offset_table(&self, topic: &str) -> HashMap<MessageQueue, i64> {
+        self.offset_table
+            .read()
+            .iter()
+            .filter(|(mq, _)| topic.is_empty() || mq.get_topic() == topic)
+            .map(|(mq, offset)| (mq.clone(), *offset))
+            .collect()
+    }
+}
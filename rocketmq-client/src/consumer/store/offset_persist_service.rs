@@ -0,0 +1,251 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::collections::HashSet;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+
+use parking_lot::RwLock;
+use rocketmq_common::common::message::message_queue::MessageQueue;
+use tracing::info;
+use tracing::warn;
+
+use crate::base::client_config::ClientConfig;
+use crate::consumer::message_queue_listener::MessageQueueListener;
+use crate::consumer::store::memory_offset_store::MemoryOffsetStore;
+use crate::consumer::store::offset_store::OffsetStore;
+use crate::Result;
+
+/// Drives an [`OffsetStore`] through the consumer lifecycle.
+///
+/// The store is loaded on [`start`](Self::start), the offsets of the assigned queues are
+/// persisted every `persist_consumer_offset_interval` and on [`shutdown`](Self::shutdown), and
+/// a queue taken away by a rebalance, seen through [`MessageQueueListener`], is persisted and
+/// removed from the store.
+#[derive(Clone)]
+pub struct OffsetPersistService {
+    consumer_group: String,
+    offset_store: Arc<dyn OffsetStore>,
+    persist_interval: Duration,
+    assigned_queues: Arc<RwLock<HashSet<MessageQueue>>>,
+    stopped: Arc<AtomicBool>,
+}
+
+impl OffsetPersistService {
+    pub fn new(
+        consumer_group: impl Into<String>,
+        offset_store: Box<dyn OffsetStore>,
+        client_config: &ClientConfig,
+    ) -> Self {
+        Self {
+            consumer_group: consumer_group.into(),
+            offset_store: Arc::from(offset_store),
+            persist_interval: Duration::from_millis(
+                client_config.persist_consumer_offset_interval as u64,
+            ),
+            assigned_queues: Arc::new(RwLock::new(HashSet::new())),
+            stopped: Arc::new(AtomicBool::new(true)),
+        }
+    }
+
+    /// A service over a [`MemoryOffsetStore`], for consumers given no store.
+    pub fn in_memory(consumer_group: impl Into<String>, client_config: &ClientConfig) -> Self {
+        Self::new(
+            consumer_group,
+            Box::new(MemoryOffsetStore::new()),
+            client_config,
+        )
+    }
+
+    pub fn offset_store(&self) -> &Arc<dyn OffsetStore> {
+        &self.offset_store
+    }
+
+    pub fn assigned_queues(&self) -> HashSet<MessageQueue> {
+        self.assigned_queues.read().clone()
+    }
+
+    /// Loads the store and starts persisting periodically. Fails when the store cannot be
+    /// loaded, as consuming without the stored offsets would redeliver or skip messages.
+    pub fn start(&self) -> Result<()> {
+        self.offset_store.load()?;
+        self.stopped.store(false, Ordering::Release);
+        let service = self.clone();
+        tokio::spawn(async move {
+            info!(
+                "ScheduledTask persistConsumerOffset of {} started",
+                service.consumer_group
+            );
+            loop {
+                tokio::time::sleep(service.persist_interval).await;
+                if service.stopped.load(Ordering::Acquire) {
+                    break;
+                }
+                service.persist_all();
+            }
+        });
+        Ok(())
+    }
+
+    /// Stops the periodic persisting and persists the offsets of the assigned queues once more.
+    pub fn shutdown(&self) {
+        if !self.stopped.swap(true, Ordering::AcqRel) {
+            self.persist_all();
+        }
+    }
+
+    pub fn persist_all(&self) {
+        let assigned_queues = self.assigned_queues();
+        if assigned_queues.is_empty() {
+            return;
+        }
+        if let Err(e) = self.offset_store.persist_all(&assigned_queues) {
+            warn!(
+                "persist consumer offset of {} failed: {}",
+                self.consumer_group, e
+            );
+        }
+    }
+}
+
+impl MessageQueueListener for OffsetPersistService {
+    fn message_queue_changed(
+        &self,
+        topic: &str,
+        _mq_all: &HashSet<MessageQueue>,
+        mq_divided: &HashSet<MessageQueue>,
+    ) {
+        let removed: Vec<MessageQueue> = {
+            let mut assigned_queues = self.assigned_queues.write();
+            let removed = assigned_queues
+                .iter()
+                .filter(|mq| mq.get_topic() == topic && !mq_divided.contains(*mq))
+                .cloned()
+                .collect::<Vec<_>>();
+            for mq in removed.iter() {
+                assigned_queues.remove(mq);
+            }
+            assigned_queues.extend(mq_divided.iter().cloned());
+            removed
+        };
+        for mq in removed.iter() {
+            if let Err(e) = self.offset_store.persist(mq) {
+                warn!("persist offset of {} before dropping it failed: {}", mq, e);
+            }
+            self.offset_store.remove_offset(mq);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use parking_lot::Mutex;
+
+    use super::*;
+    use crate::consumer::store::read_offset_type::ReadOffsetType;
+
+    #[derive(Default)]
+    struct RecordingStore {
+        offsets: MemoryOffsetStore,
+        calls: Mutex<Vec<String>>,
+    }
+
+    impl OffsetStore for Arc<RecordingStore> {
+        fn load(&self) -> Result<()> {
+            self.calls.lock().push("load".to_string());
+            Ok(())
+        }
+
+        fn update_offset(&self, mq: &MessageQueue, offset: i64, increase_only: bool) {
+            self.offsets.update_offset(mq, offset, increase_only);
+        }
+
+        fn read_offset(&self, mq: &MessageQueue, read_type: ReadOffsetType) -> Result<Option<i64>> {
+            self.offsets.read_offset(mq, read_type)
+        }
+
+        fn persist_all(&self, mqs: &HashSet<MessageQueue>) -> Result<()> {
+            self.calls.lock().push(format!("persist_all {}", mqs.len()));
+            Ok(())
+        }
+
+        fn persist(&self, mq: &MessageQueue) -> Result<()> {
+            self.calls
+                .lock()
+                .push(format!("persist {}", mq.get_queue_id()));
+            Ok(())
+        }
+
+        fn remove_offset(&self, mq: &MessageQueue) {
+            self.calls
+                .lock()
+                .push(format!("remove {}", mq.get_queue_id()));
+            self.offsets.remove_offset(mq);
+        }
+
+        fn clone_offset_table(&self, topic: &str) -> HashMap<MessageQueue, i64> {
+            self.offsets.clone_offset_table(topic)
+        }
+    }
+
+    fn message_queue(queue_id: i32) -> MessageQueue {
+        MessageQueue::from_parts("TopicTest", "broker-a", queue_id)
+    }
+
+    #[tokio::test]
+    async fn drives_custom_store_through_lifecycle() {
+        let store = Arc::new(RecordingStore::default());
+        let service =
+            OffsetPersistService::new("group", Box::new(store.clone()), &ClientConfig::default());
+        service.start().unwrap();
+
+        let all: HashSet<MessageQueue> = (0..3).map(message_queue).collect();
+        service.message_queue_changed("TopicTest", &all, &all);
+        service
+            .offset_store()
+            .update_offset(&message_queue(0), 10, true);
+        service
+            .offset_store()
+            .update_offset(&message_queue(0), 5, true);
+        service
+            .offset_store()
+            .update_offset(&message_queue(2), 7, false);
+        assert_eq!(
+            service
+                .offset_store()
+                .read_offset(&message_queue(0), ReadOffsetType::ReadFromMemory)
+                .unwrap(),
+            Some(10)
+        );
+
+        let divided: HashSet<MessageQueue> = [message_queue(0), message_queue(1)].into();
+        service.message_queue_changed("TopicTest", &all, &divided);
+        assert!(!store
+            .offsets
+            .clone_offset_table("TopicTest")
+            .contains_key(&message_queue(2)));
+        service.shutdown();
+
+        assert_eq!(
+            *store.calls.lock(),
+            vec!["load", "persist 2", "remove 2", "persist_all 2"]
+        );
+    }
+}
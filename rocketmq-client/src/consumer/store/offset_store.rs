@@ -0,0 +1,55 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+use rocketmq_common::common::message::message_queue::MessageQueue;
+
+use crate::consumer::store::read_offset_type::ReadOffsetType;
+use crate::Result;
+
+/// Keeps the consume progress of a consumer.
+///
+/// Implement it to keep offsets outside the broker, e.g. in the database the consumer writes
+/// to, so the offset can be committed in the same transaction as the side effects of the
+/// message. The client calls [`load`](Self::load) on start, [`update_offset`](Self::update_offset)
+/// after messages are consumed, [`persist_all`](Self::persist_all) periodically and on
+/// shutdown, and [`persist`](Self::persist) followed by [`remove_offset`](Self::remove_offset)
+/// when a rebalance takes a queue away.
+pub trait OffsetStore: Send + Sync + 'static {
+    /// Loads the stored offsets, called once before consuming starts.
+    fn load(&self) -> Result<()>;
+
+    /// Records `offset` as the next offset to consume from `mq`. With `increase_only` an
+    /// offset below the recorded one is ignored.
+    fn update_offset(&self, mq: &MessageQueue, offset: i64, increase_only: bool);
+
+    /// Returns the next offset to consume from `mq`, `None` when nothing is stored for it.
+    fn read_offset(&self, mq: &MessageQueue, read_type: ReadOffsetType) -> Result<Option<i64>>;
+
+    /// Writes the offsets of `mqs` to the backing storage.
+    fn persist_all(&self, mqs: &HashSet<MessageQueue>) -> Result<()>;
+
+    /// Writes the offset of `mq` to the backing storage.
+    fn persist(&self, mq: &MessageQueue) -> Result<()>;
+
+    /// Forgets the cached offset of `mq`, called after the queue was reassigned.
+    fn remove_offset(&self, mq: &MessageQueue);
+
+    /// The cached offsets of the queues of `topic`, of every topic when `topic` is empty.
+    fn clone_offset_table(&self, topic: &str) -> HashMap<MessageQueue, i64>;
+}
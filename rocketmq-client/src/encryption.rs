@@ -0,0 +1,223 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use bytes::Bytes;
+use ring::aead::Aad;
+use ring::aead::LessSafeKey;
+use ring::aead::Nonce;
+use ring::aead::UnboundKey;
+use ring::aead::AES_256_GCM;
+use ring::aead::NONCE_LEN;
+use ring::rand::SecureRandom;
+use ring::rand::SystemRandom;
+use rocketmq_common::common::message::message_batch::MessageBatch;
+use rocketmq_common::common::message::MessageTrait;
+
+use crate::error::MQClientError;
+use crate::Result;
+
+/// Id of the key the body is encrypted with, the body is AES-256-GCM ciphertext when set.
+pub const PROPERTY_ENCRYPTION_KEY_ID: &str = "__ENCRYPTION_KEY_ID";
+/// Base64 encoded nonce the body was encrypted with.
+pub const PROPERTY_ENCRYPTION_NONCE: &str = "__ENCRYPTION_NONCE";
+
+/// Length in bytes of the AES-256 keys handed out by a [`KeyProvider`].
+pub const KEY_LEN: usize = 32;
+
+/// Source of the data keys, e.g. a static key table or a cache of data keys issued by a KMS.
+pub trait KeyProvider: Send + Sync + 'static {
+    /// Id and key new messages are encrypted with.
+    fn current_key(&self) -> Result<(String, [u8; KEY_LEN])>;
+
+    /// Key with `key_id`, to decrypt messages encrypted before a rotation as well.
+    fn key(&self, key_id: &str) -> Result<[u8; KEY_LEN]>;
+}
+
+/// [`KeyProvider`] over a fixed set of keys, the last one added encrypts.
+#[derive(Clone)]
+pub struct StaticKeyProvider {
+    current_key_id: String,
+    keys: HashMap<String, [u8; KEY_LEN]>,
+}
+
+impl StaticKeyProvider {
+    pub fn new(key_id: impl Into<String>, key: [u8; KEY_LEN]) -> Self {
+        let key_id = key_id.into();
+        Self {
+            keys: HashMap::from([(key_id.clone(), key)]),
+            current_key_id: key_id,
+        }
+    }
+
+    /// Adds `key` and encrypts with it from now on, the previous keys still decrypt.
+    pub fn rotate(mut self, key_id: impl Into<String>, key: [u8; KEY_LEN]) -> Self {
+        self.current_key_id = key_id.into();
+        self.keys.insert(self.current_key_id.clone(), key);
+        self
+    }
+}
+
+impl KeyProvider for StaticKeyProvider {
+    fn current_key(&self) -> Result<(String, [u8; KEY_LEN])> {
+        Ok((self.current_key_id.clone(), self.key(&self.current_key_id)?))
+    }
+
+    fn key(&self, key_id: &str) -> Result<[u8; KEY_LEN]> {
+        self.keys
+            .get(key_id)
+            .copied()
+            .ok_or_else(|| MQClientError::IllegalState(format!("unknown key id {}", key_id)))
+    }
+}
+
+/// End-to-end encryption of message bodies: the producer encrypts each body with AES-256-GCM
+/// before it leaves the client and the consumer decrypts it before the listener sees the
+/// message, so brokers only ever store ciphertext. Topic, tags, keys and properties stay in
+/// clear to keep routing and filtering working.
+#[derive(Clone)]
+pub struct MessageEncryption {
+    key_provider: Arc<dyn KeyProvider>,
+    random: SystemRandom,
+}
+
+impl MessageEncryption {
+    pub fn new(key_provider: Arc<dyn KeyProvider>) -> Self {
+        Self {
+            key_provider,
+            random: SystemRandom::new(),
+        }
+    }
+
+    /// Encrypts the body of `msg` with the current key. Batches are left alone, their messages
+    /// are encrypted one by one before batching, and so are messages already encrypted. Returns
+    /// whether the body was encrypted.
+    pub fn encrypt<T: MessageTrait>(&self, msg: &mut T) -> Result<bool> {
+        if msg.as_any().downcast_ref::<MessageBatch>().is_some()
+            || msg.get_property(PROPERTY_ENCRYPTION_KEY_ID).is_some()
+        {
+            return Ok(false);
+        }
+        let Some(body) = msg.get_body() else {
+            return Ok(false);
+        };
+        let (key_id, key) = self.key_provider.current_key()?;
+        let mut nonce = [0u8; NONCE_LEN];
+        self.random
+            .fill(&mut nonce)
+            .map_err(|_| MQClientError::IllegalState("generate nonce failed".to_string()))?;
+        let mut in_out = body.to_vec();
+        sealing_key(&key)?
+            .seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::from(key_id.as_bytes()),
+                &mut in_out,
+            )
+            .map_err(|_| MQClientError::IllegalState("encrypt message body failed".to_string()))?;
+        msg.put_property(PROPERTY_ENCRYPTION_KEY_ID, key_id.as_str());
+        msg.put_property(PROPERTY_ENCRYPTION_NONCE, STANDARD.encode(nonce).as_str());
+        msg.set_body(Bytes::from(in_out));
+        *msg.get_compressed_body_mut() = None;
+        Ok(true)
+    }
+
+    /// Decrypts the body of a message encrypted by [`encrypt`](Self::encrypt). Returns whether
+    /// the body was decrypted, messages sent in clear are left alone.
+    pub fn decrypt<T: MessageTrait>(&self, msg: &mut T) -> Result<bool> {
+        let Some(key_id) = msg.get_property(PROPERTY_ENCRYPTION_KEY_ID) else {
+            return Ok(false);
+        };
+        let nonce = msg
+            .get_property(PROPERTY_ENCRYPTION_NONCE)
+            .and_then(|nonce| STANDARD.decode(nonce).ok())
+            .and_then(|nonce| <[u8; NONCE_LEN]>::try_from(nonce.as_slice()).ok())
+            .ok_or_else(|| {
+                MQClientError::IllegalState("encrypted message without a valid nonce".to_string())
+            })?;
+        let key = self.key_provider.key(key_id.as_str())?;
+        let mut in_out = msg.get_body().map(|body| body.to_vec()).unwrap_or_default();
+        let plain_len = sealing_key(&key)?
+            .open_in_place(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::from(key_id.as_bytes()),
+                &mut in_out,
+            )
+            .map_err(|_| {
+                MQClientError::IllegalState(format!(
+                    "decrypt message body with key {} failed",
+                    key_id
+                ))
+            })?
+            .len();
+        in_out.truncate(plain_len);
+        msg.set_body(Bytes::from(in_out));
+        msg.clear_property(PROPERTY_ENCRYPTION_KEY_ID);
+        msg.clear_property(PROPERTY_ENCRYPTION_NONCE);
+        Ok(true)
+    }
+}
+
+fn sealing_key(key: &[u8; KEY_LEN]) -> Result<LessSafeKey> {
+    UnboundKey::new(&AES_256_GCM, key)
+        .map(LessSafeKey::new)
+        .map_err(|_| MQClientError::IllegalState("invalid AES-256 key".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use rocketmq_common::common::message::message_single::Message;
+
+    use super::*;
+
+    #[test]
+    fn encrypts_bodies_and_decrypts_them_after_rotation() {
+        let old_provider = StaticKeyProvider::new("k1", [1u8; KEY_LEN]);
+        let mut msg = Message::new("TopicTest", b"card number");
+        assert!(MessageEncryption::new(Arc::new(old_provider.clone()))
+            .encrypt(&mut msg)
+            .unwrap());
+        assert_ne!(msg.get_body().unwrap().as_ref(), b"card number");
+        assert_eq!(msg.get_property(PROPERTY_ENCRYPTION_KEY_ID).unwrap(), "k1");
+
+        let encryption =
+            MessageEncryption::new(Arc::new(old_provider.rotate("k2", [2u8; KEY_LEN])));
+        assert!(!encryption.encrypt(&mut msg).unwrap());
+        assert!(encryption.decrypt(&mut msg).unwrap());
+        assert_eq!(msg.get_body().unwrap().as_ref(), b"card number");
+        assert!(msg.get_property(PROPERTY_ENCRYPTION_NONCE).is_none());
+        assert!(!encryption.decrypt(&mut msg).unwrap());
+    }
+
+    #[test]
+    fn decrypt_rejects_tampered_bodies_and_unknown_keys() {
+        let encryption =
+            MessageEncryption::new(Arc::new(StaticKeyProvider::new("k1", [1u8; KEY_LEN])));
+        let mut msg = Message::new("TopicTest", b"payload");
+        encryption.encrypt(&mut msg).unwrap();
+        let mut tampered = msg.get_body().unwrap().to_vec();
+        tampered[0] ^= 1;
+        let mut tampered_msg = msg.clone();
+        tampered_msg.set_body(Bytes::from(tampered));
+        assert!(encryption.decrypt(&mut tampered_msg).is_err());
+
+        let other = MessageEncryption::new(Arc::new(StaticKeyProvider::new("k9", [9u8; KEY_LEN])));
+        assert!(other.decrypt(&mut msg).is_err());
+    }
+}
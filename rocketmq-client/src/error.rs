@@ -14,6 +14,7 @@
  * See the License for the specific language governing permissions and
  * limitations under the License.
  */
+use rocketmq_remoting::code::response_code::ResponseCode;
 use rocketmq_remoting::error::Error as RemotingError;
 use thiserror::Error;
 
@@ -36,4 +37,154 @@ pub enum MQClientError {
 
     #[error("{0}")]
     RemotingException(#[from] RemotingError),
+
+    #[error("Illegal client config {0:?}: {1}. {hint}", hint = .0.remediation())]
+    IllegalClientConfig(ClientErrorKind, String),
+
+    #[error("Illegal argument: {0}")]
+    IllegalArgument(String),
+
+    #[error("Illegal state: {0}")]
+    IllegalState(String),
+
+    #[error("Request failed: {0}")]
+    RequestFailed(String),
+
+    #[error("Payload serialization failed: {0}")]
+    PayloadSerialization(String),
+
+    #[error("Broker[{0}] runs version {1}, {2} requires {3} or later")]
+    BrokerVersionNotSupported(String, String, String, String),
+
+    #[error("Invoke broker[{0}] exception")]
+    InvokeBrokerException(String, #[source] Box<MQClientError>),
+
+    #[error("{0}")]
+    LocalFileException(String, #[source] Box<dyn std::error::Error + Send + Sync>),
+}
+
+impl MQClientError {
+    /// The response code the broker answered with, so callers can tell e.g. `FlushDiskTimeout`
+    /// from `TopicNotExist`. `None` for local errors and codes that are not response codes.
+    pub fn response_code(&self) -> Option<ResponseCode> {
+        let code = match self {
+            MQClientError::MQClientException(code, _)
+            | MQClientError::MQBrokerException(code, _, _)
+            | MQClientError::OffsetNotFoundException(code, _, _) => *code,
+            MQClientError::RemotingException(RemotingError::RpcException(code, _)) => *code,
+            MQClientError::InvokeBrokerException(_, source) => return source.response_code(),
+            _ => return None,
+        };
+        let response_code = ResponseCode::from(code);
+        (response_code as i32 == code).then_some(response_code)
+    }
+
+    /// Address of the broker the error came from, if any.
+    pub fn broker_addr(&self) -> Option<&str> {
+        match self {
+            MQClientError::MQBrokerException(_, broker_addr, _)
+            | MQClientError::OffsetNotFoundException(_, broker_addr, _)
+            | MQClientError::BrokerVersionNotSupported(broker_addr, ..)
+            | MQClientError::InvokeBrokerException(broker_addr, _) => Some(broker_addr.as_str()),
+            _ => None,
+        }
+    }
+}
+
+/// Why a producer or consumer configuration was rejected at `build()`/`start()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClientErrorKind {
+    /// The group name is blank, too long or has characters other than `%|a-zA-Z0-9_-`.
+    IllegalGroupName,
+    /// The group name is reserved for the default or inner groups of the client.
+    ReservedGroupName,
+    /// The name server address is neither a `host:port;host:port` list nor an http endpoint.
+    IllegalNameServerAddress,
+    /// Options that cannot be used together.
+    ConflictingOptions,
+    /// An option is outside of the range it accepts.
+    IllegalOptionValue,
+    /// The connection string is not a `rocketmq://` URI or one of its options is invalid.
+    IllegalConnectionString,
+}
+
+impl ClientErrorKind {
+    /// What to change to get the configuration accepted.
+    pub fn remediation(&self) -> &'static str {
+        match self {
+            ClientErrorKind::IllegalGroupName => {
+                "Use a name of at most 255 characters among ^[%|a-zA-Z0-9_-]+$"
+            }
+            ClientErrorKind::ReservedGroupName => "Specify a group name of your own",
+            ClientErrorKind::IllegalNameServerAddress => {
+                "Use host:port pairs separated by ';', e.g. 127.0.0.1:9876;127.0.0.2:9876"
+            }
+            ClientErrorKind::ConflictingOptions => "Remove one of the conflicting options",
+            ClientErrorKind::IllegalOptionValue => "Use a value within the range of the option",
+            ClientErrorKind::IllegalConnectionString => {
+                "Use rocketmq://host:port[,host:port][?option=value&...], e.g. \
+                 rocketmq://127.0.0.1:9876?namespace=ns"
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+
+    use super::*;
+
+    #[test]
+    fn broker_response_code_is_typed() {
+        let flush_disk_timeout = MQClientError::MQBrokerException(
+            ResponseCode::FlushDiskTimeout as i32,
+            "127.0.0.1:10911".to_string(),
+            "flush disk timeout".to_string(),
+        );
+        assert_eq!(
+            flush_disk_timeout.response_code(),
+            Some(ResponseCode::FlushDiskTimeout)
+        );
+        assert_eq!(flush_disk_timeout.broker_addr(), Some("127.0.0.1:10911"));
+
+        let topic_not_exist = MQClientError::InvokeBrokerException(
+            "127.0.0.1:10911".to_string(),
+            Box::new(MQClientError::MQBrokerException(
+                ResponseCode::TopicNotExist as i32,
+                "127.0.0.1:10911".to_string(),
+                "topic not exist".to_string(),
+            )),
+        );
+        assert_eq!(
+            topic_not_exist.response_code(),
+            Some(ResponseCode::TopicNotExist)
+        );
+        assert!(topic_not_exist.source().is_some());
+    }
+
+    #[test]
+    fn broker_version_not_supported_names_versions() {
+        let error = MQClientError::BrokerVersionNotSupported(
+            "127.0.0.1:10911".to_string(),
+            "V495".to_string(),
+            "PopMessage".to_string(),
+            "V500".to_string(),
+        );
+        assert_eq!(
+            error.to_string(),
+            "Broker[127.0.0.1:10911] runs version V495, PopMessage requires V500 or later"
+        );
+        assert_eq!(error.broker_addr(), Some("127.0.0.1:10911"));
+    }
+
+    #[test]
+    fn client_error_codes_are_not_response_codes() {
+        let error = MQClientError::MQClientException(10003, "broker not exist".to_string());
+        assert_eq!(error.response_code(), None);
+        assert_eq!(
+            MQClientError::IllegalState("shutdown".to_string()).response_code(),
+            None
+        );
+    }
 }
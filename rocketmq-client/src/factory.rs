@@ -15,3 +15,5 @@
  * limitations under the License.
  */
 pub mod mq_client_instance;
+pub mod topic_queue_mapping_cache;
+pub mod topic_route_cache;
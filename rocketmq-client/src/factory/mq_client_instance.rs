@@ -17,23 +17,37 @@
 use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::collections::HashSet;
+use std::future::Future;
 use std::sync::atomic::AtomicI64;
 use std::sync::Arc;
 use std::thread;
 use std::time::Duration;
+use std::time::Instant;
 
+use rand::Rng;
+use rocketmq_common::common::attribute::topic_message_type::TopicMessageType;
 use rocketmq_common::common::base::service_state::ServiceState;
 use rocketmq_common::common::constant::PermName;
+use rocketmq_common::common::message::message_accessor::MessageAccessor;
+use rocketmq_common::common::message::message_ext::MessageExt;
 use rocketmq_common::common::message::message_queue::MessageQueue;
+use rocketmq_common::common::message::message_single::Message;
+use rocketmq_common::common::message::MessageConst;
+use rocketmq_common::common::message::MessageTrait;
 use rocketmq_common::common::mix_all;
+use rocketmq_common::common::mq_version::RocketMqVersion;
 use rocketmq_common::ArcRefCellWrapper;
 use rocketmq_common::TimeUtils::get_current_millis;
+use rocketmq_common::TopicAttributes::TOPIC_MESSAGE_TYPE_ATTRIBUTE;
 use rocketmq_remoting::base::connection_net_event::ConnectionNetEvent;
+use rocketmq_remoting::code::request_code::RequestCode;
+use rocketmq_remoting::protocol::header::check_transaction_state_request_header::CheckTransactionStateRequestHeader;
 use rocketmq_remoting::protocol::heartbeat::consumer_data::ConsumerData;
 use rocketmq_remoting::protocol::heartbeat::heartbeat_data::HeartbeatData;
 use rocketmq_remoting::protocol::heartbeat::producer_data::ProducerData;
 use rocketmq_remoting::protocol::route::topic_route_data::TopicRouteData;
-use rocketmq_remoting::protocol::RemotingSerializable;
+use rocketmq_remoting::protocol::static_topic::topic_queue_mapping_detail::TopicQueueMappingDetail;
+use rocketmq_remoting::protocol::DataVersion;
 use rocketmq_remoting::rpc::client_metadata::ClientMetadata;
 use rocketmq_remoting::runtime::config::client_config::TokioClientConfig;
 use rocketmq_remoting::runtime::RPCHook;
@@ -41,22 +55,39 @@ use rocketmq_runtime::RocketMQRuntime;
 use tokio::runtime::Handle;
 use tokio::sync::Mutex;
 use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
 use tracing::info;
+use tracing::info_span;
 use tracing::warn;
+use tracing::Instrument;
+use tracing::Span;
 
 use crate::admin::mq_admin_ext_inner::MQAdminExtInner;
 use crate::base::client_config::ClientConfig;
+use crate::base::client_config_handle::sleep_until_next_run;
+use crate::base::client_config_handle::ClientConfigHandle;
+use crate::base::client_event_listener::ClientEvent;
+use crate::base::client_event_listener::ClientEventListener;
 use crate::consumer::consumer_impl::pull_message_service::PullMessageService;
 use crate::consumer::consumer_impl::rebalance_service::RebalanceService;
+use crate::consumer::consumer_stats_manager::ConsumerStatsManager;
 use crate::consumer::mq_consumer_inner::MQConsumerInner;
-use crate::error::MQClientError::MQClientException;
+use crate::error::MQClientError;
+use crate::factory::topic_queue_mapping_cache::QueueRemapStats;
+use crate::factory::topic_queue_mapping_cache::TopicQueueMappingCache;
+use crate::factory::topic_route_cache::TopicRouteCache;
 use crate::implementation::client_remoting_processor::ClientRemotingProcessor;
 use crate::implementation::mq_admin_impl::MQAdminImpl;
 use crate::implementation::mq_client_api_impl::MQClientAPIImpl;
+use crate::implementation::mq_client_manager::MQClientManager;
 use crate::producer::default_mq_producer::DefaultMQProducer;
 use crate::producer::default_mq_producer::ProducerConfig;
+use crate::producer::mq_producer::MQProducer;
 use crate::producer::producer_impl::mq_producer_inner::MQProducerInner;
 use crate::producer::producer_impl::topic_publish_info::TopicPublishInfo;
+use crate::shutdown::ShutdownHook;
+use crate::shutdown::ShutdownHooks;
+use crate::shutdown::ShutdownPhase;
 use crate::Result;
 
 #[derive(Clone)]
@@ -82,8 +113,10 @@ pub struct MQClientInstance {
     pub(crate) mq_client_api_impl: ArcRefCellWrapper<MQClientAPIImpl>,
     pub(crate) mq_admin_impl: ArcRefCellWrapper<MQAdminImpl>,
     pub(crate) topic_route_table: Arc<RwLock<HashMap<String /* Topic */, TopicRouteData>>>,
-    topic_end_points_table:
-        Arc<RwLock<HashMap<String /* Topic */, HashMap<MessageQueue, String /* brokerName */>>>>,
+    topic_route_version_table: Arc<RwLock<HashMap<String /* Topic */, String>>>,
+    topic_queue_mapping_cache: Arc<RwLock<TopicQueueMappingCache>>,
+    /// When each cached topic was first seen used by no producer or consumer.
+    unreferenced_topic_table: Arc<parking_lot::Mutex<HashMap<String /* Topic */, Instant>>>,
     lock_namesrv: Arc<Mutex<()>>,
     lock_heartbeat: Arc<Mutex<()>>,
 
@@ -96,6 +129,19 @@ pub struct MQClientInstance {
     broker_version_table:
         Arc<RwLock<HashMap<String /* Broker Name */, HashMap<String /* address */, i32>>>>,
     send_heartbeat_times_total: Arc<AtomicI64>,
+    /// Consecutive heartbeat failures of each broker address, cleared on success.
+    heartbeat_failure_table: Arc<parking_lot::Mutex<HashMap<String /* address */, u32>>>,
+    /// Subscription data version of each consumer group last returned by each broker.
+    subscription_data_version_table:
+        Arc<parking_lot::Mutex<HashMap<String /* address */, HashMap<String, DataVersion>>>>,
+    client_event_listeners: Arc<parking_lot::RwLock<Vec<Arc<dyn ClientEventListener>>>>,
+    consumer_stats_manager: Arc<ConsumerStatsManager>,
+    client_config_handle: ClientConfigHandle,
+    /// Span of the logs of this instance, tagged with its client id.
+    span: Span,
+    shutdown_hooks: Arc<ShutdownHooks>,
+    /// Tasks started by `start_scheduled_task`, aborted at shutdown.
+    scheduled_tasks: Arc<parking_lot::Mutex<Vec<JoinHandle<()>>>>,
 }
 
 impl MQClientInstance {
@@ -110,7 +156,7 @@ impl MQClientInstance {
         let mut rx = tx.subscribe();
         let mq_client_api_impl = ArcRefCellWrapper::new(MQClientAPIImpl::new(
             Arc::new(TokioClientConfig::default()),
-            ClientRemotingProcessor {},
+            ClientRemotingProcessor::new(),
             rpc_hook,
             client_config.clone(),
             Some(tx),
@@ -127,6 +173,7 @@ impl MQClientInstance {
                 })
             });
         }
+        let span = info_span!("client", client_id = %client_id);
         let instance = MQClientInstance {
             client_config: Arc::new(client_config.clone()),
             client_id,
@@ -137,12 +184,14 @@ impl MQClientInstance {
             mq_client_api_impl,
             mq_admin_impl: ArcRefCellWrapper::new(MQAdminImpl::new()),
             topic_route_table: Arc::new(Default::default()),
-            topic_end_points_table: Arc::new(Default::default()),
+            topic_route_version_table: Arc::new(Default::default()),
+            topic_queue_mapping_cache: Arc::new(Default::default()),
+            unreferenced_topic_table: Arc::new(Default::default()),
             lock_namesrv: Default::default(),
             lock_heartbeat: Default::default(),
             service_state: ServiceState::CreateJust,
             pull_message_service: ArcRefCellWrapper::new(PullMessageService {}),
-            rebalance_service: ArcRefCellWrapper::new(RebalanceService {}),
+            rebalance_service: ArcRefCellWrapper::new(RebalanceService::new()),
             default_mqproducer: ArcRefCellWrapper::new(
                 DefaultMQProducer::builder()
                     .producer_group(mix_all::CLIENT_INNER_PRODUCER_GROUP)
@@ -156,37 +205,149 @@ impl MQClientInstance {
             broker_addr_table,
             broker_version_table: Arc::new(Default::default()),
             send_heartbeat_times_total: Arc::new(AtomicI64::new(0)),
+            heartbeat_failure_table: Arc::new(Default::default()),
+            subscription_data_version_table: Arc::new(Default::default()),
+            client_event_listeners: Arc::new(Default::default()),
+            consumer_stats_manager: Arc::new(ConsumerStatsManager::new()),
+            client_config_handle: ClientConfigHandle::new(&client_config),
+            span,
+            shutdown_hooks: Arc::new(ShutdownHooks::default()),
+            scheduled_tasks: Arc::new(Default::default()),
         };
         let instance_ = instance.clone();
-        tokio::spawn(async move {
-            while let Ok(value) = rx.recv().await {
-                match value {
-                    ConnectionNetEvent::CONNECTED(remote_address) => {
-                        info!("ConnectionNetEvent CONNECTED");
-                        let broker_addr_table = instance_.broker_addr_table.read().await;
-                        for (broker_name, broker_addrs) in broker_addr_table.iter() {
-                            for (id, addr) in broker_addrs.iter() {
-                                if addr == remote_address.to_string().as_str()
-                                    && instance_
-                                        .send_heartbeat_to_broker(*id, broker_name, addr)
-                                        .await
-                                {
-                                    instance_.re_balance_immediately().await;
+        tokio::spawn(
+            async move {
+                while let Ok(value) = rx.recv().await {
+                    match value {
+                        ConnectionNetEvent::CONNECTED(remote_address) => {
+                            info!("ConnectionNetEvent CONNECTED");
+                            let broker_addr_table = instance_.broker_addr_table.read().await;
+                            for (broker_name, broker_addrs) in broker_addr_table.iter() {
+                                for (id, addr) in broker_addrs.iter() {
+                                    if addr == remote_address.to_string().as_str()
+                                        && instance_
+                                            .send_heartbeat_to_broker(*id, broker_name, addr)
+                                            .await
+                                    {
+                                        instance_.re_balance_immediately().await;
+                                    }
                                 }
                             }
                         }
+                        ConnectionNetEvent::DISCONNECTED => {}
+                        ConnectionNetEvent::EXCEPTION => {}
                     }
-                    ConnectionNetEvent::DISCONNECTED => {}
-                    ConnectionNetEvent::EXCEPTION => {}
                 }
+                warn!("ConnectionNetEvent recv error");
             }
-            warn!("ConnectionNetEvent recv error");
-        });
+            .instrument(instance.span.clone()),
+        );
         instance
     }
 
+    pub fn register_client_event_listener(&self, listener: impl ClientEventListener) {
+        self.client_event_listeners.write().push(Arc::new(listener));
+    }
+
+    /// Pull statistics of the consumers of this client, per consumer group and message queue.
+    pub fn consumer_stats_manager(&self) -> &Arc<ConsumerStatsManager> {
+        &self.consumer_stats_manager
+    }
+
+    /// Settings of this instance which can be changed while it runs, e.g. the intervals of the
+    /// heartbeat, route poll and offset persist tasks.
+    pub fn client_config_handle(&self) -> &ClientConfigHandle {
+        &self.client_config_handle
+    }
+
+    /// Span the background tasks of this instance log in, applications can instrument their
+    /// own calls into the client with it as well.
+    pub fn span(&self) -> &Span {
+        &self.span
+    }
+
     pub async fn re_balance_immediately(&self) {
-        println!("re_balance_immediately")
+        self.rebalance_service.wakeup();
+    }
+
+    /// Rebalances every registered consumer. Returns `false` if any of them could not be
+    /// balanced, e.g. because a queue lock is still held by another client.
+    pub async fn do_rebalance(&self) -> bool {
+        let mut balanced = true;
+        for (group, consumer) in self.consumer_table.read().await.iter() {
+            if !consumer.try_rebalance().await {
+                warn!("rebalance of consumer group[{}] is not balanced yet", group);
+                balanced = false;
+            }
+        }
+        balanced
+    }
+
+    /// Rebalances the consumer of `group` right away instead of waiting for the rebalance
+    /// service, and returns the queues it owns afterwards, `None` if no such consumer is
+    /// registered.
+    pub async fn rebalance_now(
+        &self,
+        group: &str,
+    ) -> Option<HashMap<String /* topic */, HashSet<MessageQueue>>> {
+        let consumer_table = self.consumer_table.read().await;
+        let consumer = consumer_table.get(group)?;
+        consumer.do_rebalance().await;
+        Some(consumer.allocation())
+    }
+
+    /// Subscribes the running consumer of `group` to `topic`: fetches the route of the topic,
+    /// reports the new subscription to the brokers with a heartbeat right away and rebalances
+    /// without waiting for the rebalance service.
+    pub async fn subscribe(
+        &mut self,
+        group: &str,
+        topic: &str,
+        sub_expression: &str,
+    ) -> Result<()> {
+        match self.consumer_table.read().await.get(group) {
+            Some(consumer) => consumer.subscribe(topic, sub_expression)?,
+            None => {
+                return Err(MQClientError::IllegalState(format!(
+                    "consumer group {} is not registered",
+                    group
+                )))
+            }
+        }
+        self.update_topic_route_info_from_name_server_topic(topic)
+            .await;
+        self.send_heartbeat_to_all_broker_with_lock().await;
+        self.re_balance_immediately().await;
+        Ok(())
+    }
+
+    /// Unsubscribes the running consumer of `group` from `topic`, the counterpart of
+    /// [`subscribe`](Self::subscribe). Returns whether the consumer was subscribed to it.
+    pub async fn unsubscribe(&mut self, group: &str, topic: &str) -> bool {
+        let unsubscribed = self
+            .consumer_table
+            .read()
+            .await
+            .get(group)
+            .is_some_and(|consumer| consumer.unsubscribe(topic));
+        if unsubscribed {
+            self.send_heartbeat_to_all_broker_with_lock().await;
+            self.re_balance_immediately().await;
+        }
+        unsubscribed
+    }
+
+    /// Queues currently owned by the consumer of `group` per topic, `None` if no such consumer is
+    /// registered.
+    pub async fn allocation(
+        &self,
+        group: &str,
+    ) -> Option<HashMap<String /* topic */, HashSet<MessageQueue>>> {
+        self.consumer_table
+            .read()
+            .await
+            .get(group)
+            .map(|consumer| consumer.allocation())
     }
 
     pub async fn start(&mut self) -> Result<()> {
@@ -199,12 +360,17 @@ impl MQClientInstance {
                 }
                 // Start request-response channel
                 self.mq_client_api_impl.start().await;
+                // Warm up topic routes from the local cache
+                if self.client_config.enable_topic_route_cache {
+                    self.load_topic_route_cache().await;
+                }
                 // Start various schedule tasks
                 self.start_scheduled_task();
                 // Start pull service
                 self.pull_message_service.start().await;
                 // Start rebalance service
-                self.rebalance_service.start().await;
+                let client_instance = self.clone();
+                self.rebalance_service.start(client_instance).await;
                 // Start push service
                 self.default_mqproducer
                     .default_mqproducer_impl
@@ -218,18 +384,184 @@ impl MQClientInstance {
             ServiceState::Running => {}
             ServiceState::ShutdownAlready => {}
             ServiceState::StartFailed => {
-                return Err(MQClientException(
-                    -1,
-                    format!(
-                        "The Factory object[{}] has been created before, and failed.",
-                        self.client_id
-                    ),
-                ));
+                return Err(MQClientError::IllegalState(format!(
+                    "The Factory object[{}] has been created before, and failed.",
+                    self.client_id
+                )));
             }
         }
         Ok(())
     }
 
+    pub async fn shutdown(&mut self) {
+        // Consumer
+        if !self.consumer_table.read().await.is_empty() {
+            return;
+        }
+        // AdminExt
+        if !self.admin_ext_table.read().await.is_empty() {
+            return;
+        }
+        // Producer
+        if self.producer_table.read().await.len() > 1 {
+            return;
+        }
+        if let ServiceState::Running = self.service_state {
+            Box::pin(
+                self.default_mqproducer
+                    .default_mqproducer_impl
+                    .as_mut()
+                    .unwrap()
+                    .shutdown_with_factory(false),
+            )
+            .await;
+            self.service_state = ServiceState::ShutdownAlready;
+            self.pull_message_service.shutdown().await;
+            for task in self.scheduled_tasks.lock().drain(..) {
+                task.abort();
+            }
+            self.mq_client_api_impl.shutdown();
+            self.rebalance_service.shutdown();
+            if self.client_config.enable_topic_route_cache {
+                self.persist_topic_route_cache().await;
+            }
+            MQClientManager::get_instance()
+                .remove_client_factory(self.client_id.as_str())
+                .await;
+            info!("the client factory [{}] shutdown OK", self.client_id);
+        }
+    }
+
+    /// Registers `hook` to run in `phase` when the process shuts its clients down through
+    /// [`shutdown_all`](crate::shutdown::shutdown_all).
+    pub fn register_shutdown_hook(
+        &self,
+        phase: ShutdownPhase,
+        name: impl Into<String>,
+        hook: ShutdownHook,
+    ) {
+        self.shutdown_hooks.register(phase, name, hook);
+    }
+
+    /// Runs the shutdown hooks of `phase`. The consumer phase also persists the offsets of
+    /// every registered consumer, whether it registered a hook or not.
+    pub(crate) async fn run_shutdown_hooks(&self, phase: ShutdownPhase) {
+        self.shutdown_hooks.run(phase).await;
+        if phase == ShutdownPhase::Consumer {
+            for consumer in self.consumer_table.read().await.values() {
+                consumer.persist_consumer_offset();
+            }
+        }
+    }
+
+    /// Shuts the instance down even though producers, consumers or admin clients are still
+    /// registered, once their shutdown hooks ran.
+    pub(crate) async fn shutdown_forcibly(&mut self) {
+        self.consumer_table.write().await.clear();
+        self.admin_ext_table.write().await.clear();
+        self.producer_table.write().await.clear();
+        self.shutdown().await;
+    }
+
+    /// Loads the topic routes persisted by a previous run and applies them to the registered
+    /// producers and consumers. The routes are refreshed by the scheduled name server poll.
+    pub async fn load_topic_route_cache(&mut self) {
+        let file_name = self.client_config.topic_route_cache_file.as_str();
+        let cache = match TopicRouteCache::load(file_name) {
+            Ok(Some(cache)) => cache,
+            Ok(None) => return,
+            Err(err) => {
+                warn!("load topic route cache failed, {}", err);
+                return;
+            }
+        };
+        let mut topic_route_table = self.topic_route_table.write().await;
+        let mut broker_addr_table = self.broker_addr_table.write().await;
+        for (broker_name, broker_addrs) in cache.broker_addr_table {
+            broker_addr_table.entry(broker_name).or_insert(broker_addrs);
+        }
+        drop(broker_addr_table);
+        for (topic, mut topic_route_data) in cache.topic_route_table {
+            if topic_route_table.contains_key(topic.as_str()) {
+                continue;
+            }
+            let mut publish_info =
+                topic_route_data2topic_publish_info(topic.as_str(), &mut topic_route_data);
+            publish_info.have_topic_router_info = true;
+            let mut producer_table = self.producer_table.write().await;
+            for value in producer_table.values_mut() {
+                value.update_topic_publish_info(topic.clone(), Some(publish_info.clone()));
+            }
+            drop(producer_table);
+            let mut consumer_table = self.consumer_table.write().await;
+            if !consumer_table.is_empty() {
+                let subscribe_info =
+                    topic_route_data2topic_subscribe_info(topic.as_str(), &topic_route_data);
+                for value in consumer_table.values_mut() {
+                    value.update_topic_subscribe_info(topic.as_str(), &subscribe_info);
+                }
+            }
+            drop(consumer_table);
+            topic_route_table.insert(topic, topic_route_data);
+        }
+        info!(
+            "load topic route cache from {} OK, {} topics",
+            file_name,
+            topic_route_table.len()
+        );
+    }
+
+    /// Writes the topic routes known by this instance to the local cache file.
+    pub async fn persist_topic_route_cache(&self) {
+        let cache = TopicRouteCache::new(
+            self.topic_route_table.read().await.clone(),
+            self.broker_addr_table.read().await.clone(),
+        );
+        let file_name = self.client_config.topic_route_cache_file.as_str();
+        if let Err(err) = cache.persist(file_name) {
+            warn!("persist topic route cache failed, {}", err);
+        }
+    }
+
+    /// Hands a transaction state check from the broker at `addr` to the producer of `group`.
+    /// Returns `false` if no such producer is registered.
+    pub async fn check_transaction_state(
+        &self,
+        group: &str,
+        addr: &str,
+        msg: &MessageExt,
+        check_request_header: &CheckTransactionStateRequestHeader,
+    ) -> bool {
+        match self.producer_table.read().await.get(group) {
+            Some(producer) => {
+                producer.check_transaction_state(addr, msg, check_request_header);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub async fn unregister_consumer(&mut self, group: &str) {
+        self.consumer_table.write().await.remove(group);
+    }
+
+    pub async fn register_consumer(&mut self, group: &str, consumer: impl MQConsumerInner) -> bool {
+        if group.is_empty() {
+            return false;
+        }
+        let mut consumer_table = self.consumer_table.write().await;
+        if consumer_table.contains_key(group) {
+            warn!("the consumer group[{}] exist already.", group);
+            return false;
+        }
+        consumer_table.insert(group.to_string(), Box::new(consumer));
+        true
+    }
+
+    pub async fn unregister_producer(&mut self, group: &str) {
+        self.producer_table.write().await.remove(group);
+    }
+
     pub async fn register_producer(&mut self, group: &str, producer: impl MQProducerInner) -> bool {
         if group.is_empty() {
             return false;
@@ -246,73 +578,175 @@ impl MQClientInstance {
     fn start_scheduled_task(&mut self) {
         if self.client_config.namesrv_addr.is_none() {
             let mut mq_client_api_impl = self.mq_client_api_impl.clone();
-            self.instance_runtime.get_handle().spawn(async move {
-                info!("ScheduledTask fetchNameServerAddr started");
-                tokio::time::sleep(Duration::from_secs(10)).await;
-                loop {
-                    let current_execution_time = tokio::time::Instant::now();
-                    mq_client_api_impl.fetch_name_server_addr().await;
-                    let next_execution_time = current_execution_time + Duration::from_secs(120);
-                    let delay =
-                        next_execution_time.saturating_duration_since(tokio::time::Instant::now());
-                    tokio::time::sleep(delay).await;
+            self.spawn_scheduled_task(
+                "fetchNameServerAddr",
+                async move {
+                    info!("ScheduledTask fetchNameServerAddr started");
+                    tokio::time::sleep(Duration::from_secs(10)).await;
+                    loop {
+                        let current_execution_time = tokio::time::Instant::now();
+                        mq_client_api_impl.fetch_name_server_addr().await;
+                        let next_execution_time = current_execution_time + Duration::from_secs(120);
+                        let delay = next_execution_time
+                            .saturating_duration_since(tokio::time::Instant::now());
+                        tokio::time::sleep(delay).await;
+                    }
                 }
-            });
+                .instrument(self.span.clone()),
+            );
         }
 
         let mut client_instance = self.clone();
-        let poll_name_server_interval = self.client_config.poll_name_server_interval;
-        self.instance_runtime.get_handle().spawn(async move {
-            info!("ScheduledTask updateTopicRouteInfoFromNameServer started");
-            tokio::time::sleep(Duration::from_millis(10)).await;
-            loop {
-                let current_execution_time = tokio::time::Instant::now();
-                client_instance
-                    .update_topic_route_info_from_name_server()
+        let mut intervals = self.client_config_handle.subscribe();
+        let initial_delay = self.client_config.scheduled_task_initial_delay(
+            Duration::from_millis(10),
+            Duration::from_millis(intervals.borrow().poll_name_server_interval as u64),
+        );
+        self.spawn_scheduled_task(
+            "updateTopicRouteInfoFromNameServer",
+            async move {
+                info!("ScheduledTask updateTopicRouteInfoFromNameServer started");
+                tokio::time::sleep(initial_delay).await;
+                loop {
+                    let current_execution_time = tokio::time::Instant::now();
+                    client_instance
+                        .update_topic_route_info_from_name_server()
+                        .await;
+                    sleep_until_next_run(&mut intervals, current_execution_time, |intervals| {
+                        intervals.poll_name_server_interval
+                    })
                     .await;
-                let next_execution_time = current_execution_time
-                    + Duration::from_millis(poll_name_server_interval as u64);
-                let delay =
-                    next_execution_time.saturating_duration_since(tokio::time::Instant::now());
-                tokio::time::sleep(delay).await;
+                }
             }
-        });
+            .instrument(self.span.clone()),
+        );
 
         let mut client_instance = self.clone();
-        let heartbeat_broker_interval = self.client_config.heartbeat_broker_interval;
-        self.instance_runtime.get_handle().spawn(async move {
-            info!("ScheduledTask send_heartbeat_to_all_broker started");
-            tokio::time::sleep(Duration::from_secs(1)).await;
-            loop {
-                let current_execution_time = tokio::time::Instant::now();
-                client_instance.clean_offline_broker().await;
-                client_instance
-                    .send_heartbeat_to_all_broker_with_lock()
+        let mut intervals = self.client_config_handle.subscribe();
+        let initial_delay = self.client_config.scheduled_task_initial_delay(
+            Duration::from_secs(1),
+            Duration::from_millis(intervals.borrow().heartbeat_broker_interval as u64),
+        );
+        self.spawn_scheduled_task(
+            "sendHeartbeatToAllBroker",
+            async move {
+                info!("ScheduledTask send_heartbeat_to_all_broker started");
+                tokio::time::sleep(initial_delay).await;
+                loop {
+                    let current_execution_time = tokio::time::Instant::now();
+                    client_instance.clean_offline_broker().await;
+                    client_instance
+                        .send_heartbeat_to_all_broker_with_lock()
+                        .await;
+                    sleep_until_next_run(&mut intervals, current_execution_time, |intervals| {
+                        intervals.heartbeat_broker_interval
+                    })
                     .await;
-                let next_execution_time = current_execution_time
-                    + Duration::from_millis(heartbeat_broker_interval as u64);
-                let delay =
-                    next_execution_time.saturating_duration_since(tokio::time::Instant::now());
-                tokio::time::sleep(delay).await;
+                }
             }
-        });
+            .instrument(self.span.clone()),
+        );
 
         let mut client_instance = self.clone();
-        let persist_consumer_offset_interval =
-            self.client_config.persist_consumer_offset_interval as u64;
-        self.instance_runtime.get_handle().spawn(async move {
-            info!("ScheduledTask persistAllConsumerOffset started");
-            tokio::time::sleep(Duration::from_secs(10)).await;
-            loop {
-                let current_execution_time = tokio::time::Instant::now();
-                client_instance.persist_all_consumer_offset().await;
-                let next_execution_time = current_execution_time
-                    + Duration::from_millis(persist_consumer_offset_interval);
-                let delay =
-                    next_execution_time.saturating_duration_since(tokio::time::Instant::now());
-                tokio::time::sleep(delay).await;
+        let mut intervals = self.client_config_handle.subscribe();
+        let initial_delay = self.client_config.scheduled_task_initial_delay(
+            Duration::from_secs(10),
+            Duration::from_millis(intervals.borrow().persist_consumer_offset_interval as u64),
+        );
+        self.spawn_scheduled_task(
+            "persistAllConsumerOffset",
+            async move {
+                info!("ScheduledTask persistAllConsumerOffset started");
+                tokio::time::sleep(initial_delay).await;
+                loop {
+                    let current_execution_time = tokio::time::Instant::now();
+                    client_instance.persist_all_consumer_offset().await;
+                    sleep_until_next_run(&mut intervals, current_execution_time, |intervals| {
+                        intervals.persist_consumer_offset_interval
+                    })
+                    .await;
+                }
             }
-        });
+            .instrument(self.span.clone()),
+        );
+
+        let interval = Duration::from_millis(self.client_config.topic_route_gc_interval as u64);
+        if interval.is_zero() {
+            return;
+        }
+        let client_instance = self.clone();
+        let initial_delay = self
+            .client_config
+            .scheduled_task_initial_delay(interval, interval);
+        self.spawn_scheduled_task(
+            "cleanUnreferencedTopicRoute",
+            async move {
+                info!("ScheduledTask cleanUnreferencedTopicRoute started");
+                tokio::time::sleep(initial_delay).await;
+                loop {
+                    let current_execution_time = tokio::time::Instant::now();
+                    client_instance.clean_unreferenced_topic_route().await;
+                    tokio::time::sleep_until(current_execution_time + interval).await;
+                }
+            }
+            .instrument(self.span.clone()),
+        );
+    }
+
+    fn spawn_scheduled_task<F>(&self, name: &str, task: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let handle = self.instance_runtime.spawn_named(name, task);
+        self.scheduled_tasks.lock().push(handle);
+    }
+
+    /// Drops the routes of the topics no producer or consumer has used for the grace period,
+    /// so long-lived clients sending to many short-lived topics do not grow without bound.
+    /// Returns the topics dropped.
+    pub async fn clean_unreferenced_topic_route(&self) -> Vec<String> {
+        let mut referenced = HashSet::new();
+        for producer in self.producer_table.read().await.values() {
+            referenced.extend(producer.get_publish_topic_list());
+        }
+        for consumer in self.consumer_table.read().await.values() {
+            referenced.extend(
+                consumer
+                    .subscriptions()
+                    .iter()
+                    .map(|subscription| subscription.topic.clone()),
+            );
+        }
+        let cached = self
+            .topic_route_table
+            .read()
+            .await
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
+        let expired = expire_unreferenced_topics(
+            &mut self.unreferenced_topic_table.lock(),
+            cached,
+            &referenced,
+            Instant::now(),
+            Duration::from_millis(self.client_config.topic_route_gc_grace_period as u64),
+        );
+        for topic in expired.iter() {
+            self.remove_topic(topic).await;
+            info!("removed the route of unused topic {}", topic);
+        }
+        expired
+    }
+
+    /// Drops everything cached for `topic`: its route, route version and queue endpoints.
+    /// Returns whether a route was cached. A producer or consumer still using the topic fetches
+    /// the route again on its next use.
+    pub async fn remove_topic(&self, topic: &str) -> bool {
+        let removed = self.topic_route_table.write().await.remove(topic).is_some();
+        self.topic_route_version_table.write().await.remove(topic);
+        self.topic_queue_mapping_cache.write().await.remove(topic);
+        self.unreferenced_topic_table.lock().remove(topic);
+        removed
     }
 
     pub async fn update_topic_route_info_from_name_server(&mut self) {
@@ -352,13 +786,47 @@ impl MQClientInstance {
             }
             result
         } else {
-            self.mq_client_api_impl
-                .get_topic_route_info_from_name_server(
+            let topic_route_version = if self.topic_route_table.read().await.contains_key(topic) {
+                self.topic_route_version_table
+                    .read()
+                    .await
+                    .get(topic)
+                    .cloned()
+            } else {
+                None
+            };
+            match self
+                .mq_client_api_impl
+                .get_topic_route_info_from_name_server_if_changed(
                     topic,
                     self.client_config.mq_client_api_timeout,
+                    topic_route_version,
                 )
                 .await
-                .unwrap_or(None)
+            {
+                Ok(Some((topic_route_data, version))) => {
+                    let mut topic_route_version_table =
+                        self.topic_route_version_table.write().await;
+                    match version {
+                        Some(version) => {
+                            topic_route_version_table.insert(topic.to_string(), version);
+                        }
+                        None => {
+                            topic_route_version_table.remove(topic);
+                        }
+                    }
+                    Some(topic_route_data)
+                }
+                // Unchanged on the name server, go on with the route already held so producers
+                // and consumers that do not have it yet still get it.
+                Ok(None) => self
+                    .topic_route_table
+                    .read()
+                    .await
+                    .get(topic)
+                    .map(TopicRouteData::from_existing),
+                Err(_) => None,
+            }
         };
         if let Some(mut topic_route_data) = topic_route_data {
             let mut topic_route_table = self.topic_route_table.write().await;
@@ -381,18 +849,13 @@ impl MQClientInstance {
                 drop(broker_addr_table);
 
                 // Update endpoint map
+                if let Some(mapping_by_broker) =
+                    topic_route_data.topic_queue_mapping_by_broker.as_ref()
                 {
-                    let mq_end_points = ClientMetadata::topic_route_data2endpoints_for_static_topic(
-                        topic,
-                        &topic_route_data,
-                    );
-                    if let Some(mq_end_points) = mq_end_points {
-                        if !mq_end_points.is_empty() {
-                            let mut topic_end_points_table =
-                                self.topic_end_points_table.write().await;
-                            topic_end_points_table.insert(topic.to_string(), mq_end_points);
-                        }
-                    }
+                    self.topic_queue_mapping_cache
+                        .write()
+                        .await
+                        .update_from_route(topic, mapping_by_broker);
                 }
 
                 // Update Pub info
@@ -401,7 +864,12 @@ impl MQClientInstance {
                         topic_route_data2topic_publish_info(topic, &mut topic_route_data);
                     publish_info.have_topic_router_info = true;
                     let mut producer_table = self.producer_table.write().await;
-                    for (_, value) in producer_table.iter_mut() {
+                    if !producer_table.is_empty() {
+                        publish_info.topic_message_type = self
+                            .fetch_topic_message_type(topic, &topic_route_data)
+                            .await;
+                    }
+                    for value in producer_table.values_mut() {
                         value.update_topic_publish_info(
                             topic.to_string(),
                             Some(publish_info.clone()),
@@ -415,7 +883,7 @@ impl MQClientInstance {
                     if !consumer_table.is_empty() {
                         let subscribe_info =
                             topic_route_data2topic_subscribe_info(topic, &topic_route_data);
-                        for (_, value) in consumer_table.iter_mut() {
+                        for value in consumer_table.values_mut() {
                             value.update_topic_subscribe_info(topic, &subscribe_info);
                         }
                     }
@@ -503,15 +971,177 @@ impl MQClientInstance {
     }
 
     pub async fn get_broker_name_from_message_queue(&self, message_queue: &MessageQueue) -> String {
-        let guard = self.topic_end_points_table.read().await;
-        if let Some(broker_name) = guard.get(message_queue.get_topic()) {
-            if let Some(addr) = broker_name.get(message_queue) {
-                return addr.clone();
-            }
+        let guard = self.topic_queue_mapping_cache.read().await;
+        if let Some(broker_name) = guard.get_broker_name(message_queue) {
+            return broker_name.clone();
         }
         message_queue.get_broker_name().to_string()
     }
 
+    /// Called when a broker answered `NOT_LEADER_FOR_QUEUE` for a logical queue of a static
+    /// topic, the queue was remapped by a newer epoch. Only the mappings of the topic are
+    /// fetched again, from its brokers, instead of the whole route from the name server.
+    pub async fn on_not_leader_for_queue(&self, message_queue: &MessageQueue) -> bool {
+        self.topic_queue_mapping_cache
+            .write()
+            .await
+            .on_not_leader_response();
+        self.refresh_topic_queue_mapping(message_queue.get_topic())
+            .await
+    }
+
+    /// Called when a send to the broker of `message_queue` failed to connect, e.g. after a
+    /// broker failover. The broker cached for the queue is dropped and the route of the topic
+    /// is refreshed right away rather than on the next poll. Returns whether a refresh ran.
+    pub async fn on_send_connection_failure(&mut self, message_queue: &MessageQueue) -> bool {
+        let topic = message_queue.get_topic();
+        if !self
+            .topic_queue_mapping_cache
+            .write()
+            .await
+            .on_connection_failure(message_queue, std::time::Instant::now())
+        {
+            return false;
+        }
+        if !self
+            .update_topic_route_info_from_name_server_topic(topic)
+            .await
+        {
+            // an unchanged route does not rebuild the queues of a static topic
+            self.refresh_topic_queue_mapping(topic).await;
+        }
+        true
+    }
+
+    /// Fetches the queue mapping of `topic` from every broker holding one and merges the
+    /// newer ones into the cache. Returns whether any logical queue moved.
+    pub async fn refresh_topic_queue_mapping(&self, topic: &str) -> bool {
+        let broker_names = self
+            .topic_queue_mapping_cache
+            .read()
+            .await
+            .broker_names(topic);
+        let mut remapped = false;
+        for broker_name in broker_names {
+            let Some(addr) = self.find_broker_address_in_publish(&broker_name).await else {
+                continue;
+            };
+            match self
+                .mq_client_api_impl
+                .mut_from_ref()
+                .get_topic_config_and_queue_mapping(
+                    addr.as_str(),
+                    topic,
+                    self.client_config.mq_client_api_timeout,
+                )
+                .await
+            {
+                Ok(topic_config_and_queue_mapping) => {
+                    let Some(mapping_detail) =
+                        topic_config_and_queue_mapping.topic_queue_mapping_detail
+                    else {
+                        continue;
+                    };
+                    remapped |= self
+                        .topic_queue_mapping_cache
+                        .write()
+                        .await
+                        .update_broker_mapping(
+                            topic,
+                            broker_name.as_str(),
+                            TopicQueueMappingDetail::clone_as_mapping_info(&mapping_detail),
+                        );
+                }
+                Err(e) => {
+                    warn!(
+                        "fetch queue mapping of topic {} from broker {} failed: {}",
+                        topic, broker_name, e
+                    );
+                }
+            }
+        }
+        remapped
+    }
+
+    pub async fn queue_remap_stats(&self) -> QueueRemapStats {
+        self.topic_queue_mapping_cache.read().await.stats()
+    }
+
+    /// Message type declared by the `message.type` attribute of `topic`, asked to the first
+    /// master that answers. `None` when none of them does.
+    async fn fetch_topic_message_type(
+        &self,
+        topic: &str,
+        topic_route_data: &TopicRouteData,
+    ) -> Option<TopicMessageType> {
+        for broker_data in topic_route_data.broker_datas.iter() {
+            let Some(addr) = broker_data
+                .broker_addrs()
+                .get(&mix_all::MASTER_BROKER_ADDR_ID)
+            else {
+                continue;
+            };
+            match self
+                .mq_client_api_impl
+                .mut_from_ref()
+                .get_topic_config(addr, topic, self.client_config.mq_client_api_timeout)
+                .await
+            {
+                Ok(topic_config) => {
+                    return Some(
+                        topic_config
+                            .attributes
+                            .get(TOPIC_MESSAGE_TYPE_ATTRIBUTE.get_name())
+                            .map(|message_type| TopicMessageType::from(message_type.clone()))
+                            .unwrap_or(TopicMessageType::Unspecified),
+                    );
+                }
+                Err(err) => warn!(
+                    "get topic config of {} from broker {} failed, {}",
+                    topic, addr, err
+                ),
+            }
+        }
+        None
+    }
+
+    /// Sends a message the consumer could not consume back to the broker at `addr` for
+    /// redelivery through the retry topic of `consumer_group`. When the broker does not take it
+    /// back, e.g. it is unreachable or the message with the retry properties added exceeds its
+    /// max message size, the message is republished to the retry topic as a new message through
+    /// the inner producer, with its reconsume times carried over.
+    pub async fn send_message_back(
+        &self,
+        addr: &str,
+        msg: &MessageExt,
+        consumer_group: &str,
+        delay_level: i32,
+        timeout_millis: u64,
+        max_reconsume_times: i32,
+    ) -> Result<()> {
+        let mut mq_client_api_impl = self.mq_client_api_impl.clone();
+        let Err(e) = mq_client_api_impl
+            .consumer_send_message_back(
+                addr,
+                msg,
+                consumer_group,
+                delay_level,
+                timeout_millis,
+                max_reconsume_times,
+            )
+            .await
+        else {
+            return Ok(());
+        };
+        warn!(
+            "send msgId[{}] back to {} failed, republish it to the retry topic of {}: {}",
+            msg.msg_id, addr, consumer_group, e
+        );
+        let retry_msg = build_retry_message(msg, consumer_group, max_reconsume_times);
+        let mut default_mqproducer = self.default_mqproducer.clone();
+        default_mqproducer.send(retry_msg).await.map(|_| ())
+    }
+
     pub async fn find_broker_address_in_publish(&self, broker_name: &str) -> Option<String> {
         if broker_name.is_empty() {
             return None;
@@ -519,11 +1149,57 @@ impl MQClientInstance {
         let guard = self.broker_addr_table.read().await;
         let map = guard.get(broker_name);
         if let Some(map) = map {
-            return map.get(&(mix_all::MASTER_ID as i64)).cloned();
+            return map.get(&mix_all::MASTER_BROKER_ADDR_ID).cloned();
         }
         None
     }
 
+    /// Address of a broker hosting `topic`, the master if it is up. Picks one of the brokers at
+    /// random so the queries spread over them.
+    pub async fn find_broker_addr_by_topic(&self, topic: &str) -> Option<String> {
+        let topic_route_table = self.topic_route_table.read().await;
+        let broker_datas = &topic_route_table.get(topic)?.broker_datas;
+        if broker_datas.is_empty() {
+            return None;
+        }
+        let broker_addrs =
+            broker_datas[rand::thread_rng().gen_range(0..broker_datas.len())].broker_addrs();
+        broker_addrs
+            .get(&mix_all::MASTER_BROKER_ADDR_ID)
+            .or_else(|| broker_addrs.values().next())
+            .cloned()
+    }
+
+    /// Client ids of the consumers of `group`, as known by a broker of `topic`. Empty if the
+    /// broker can not be asked.
+    pub async fn find_consumer_id_list(&self, topic: &str, group: &str) -> Vec<String> {
+        let Some(broker_addr) = self.find_broker_addr_by_topic(topic).await else {
+            warn!(
+                "no broker found for topic {} to get the consumers of {}",
+                topic, group
+            );
+            return Vec::new();
+        };
+        match self
+            .get_mq_client_api_impl()
+            .get_consumer_id_list_by_group(
+                broker_addr.as_str(),
+                group,
+                self.client_config.mq_client_api_timeout,
+            )
+            .await
+        {
+            Ok(cid_all) => cid_all,
+            Err(e) => {
+                warn!(
+                    "getConsumerIdListByGroup exception, {} {}: {}",
+                    broker_addr, group, e
+                );
+                Vec::new()
+            }
+        }
+    }
+
     async fn send_heartbeat_to_all_broker_v2(&self, is_rebalance: bool) -> bool {
         unimplemented!()
     }
@@ -551,7 +1227,7 @@ impl MQClientInstance {
                 if addr.is_empty() {
                     continue;
                 }
-                if consumer_empty && *id != mix_all::MASTER_ID as i64 {
+                if consumer_empty && *id != mix_all::MASTER_BROKER_ADDR_ID {
                     continue;
                 }
                 self.send_heartbeat_to_broker_inner(*id, broker_name, addr, &heartbeat_data)
@@ -562,6 +1238,48 @@ impl MQClientInstance {
         true
     }
 
+    /// The version the broker at `addr` reported in its last heartbeat response, `None` before
+    /// the first heartbeat to it succeeded.
+    pub async fn find_broker_version(&self, broker_name: &str, addr: &str) -> Option<i32> {
+        self.broker_version_table
+            .read()
+            .await
+            .get(broker_name)
+            .and_then(|versions| versions.get(addr))
+            .copied()
+    }
+
+    /// The versions reported by the brokers, by broker name and address.
+    pub async fn broker_version_table(&self) -> HashMap<String, HashMap<String, i32>> {
+        self.broker_version_table.read().await.clone()
+    }
+
+    /// Fails with [`MQClientError::BrokerVersionNotSupported`] when the broker at `addr` is
+    /// known to be older than `request_code` requires, instead of letting the broker fail to
+    /// decode it. Brokers whose version is not known yet pass.
+    pub async fn check_broker_version(
+        &self,
+        broker_name: &str,
+        addr: &str,
+        request_code: RequestCode,
+    ) -> Result<()> {
+        let Some(min_version) = request_code.min_broker_version() else {
+            return Ok(());
+        };
+        match self.find_broker_version(broker_name, addr).await {
+            Some(version) if version < i32::from(min_version) => {
+                Err(MQClientError::BrokerVersionNotSupported(
+                    addr.to_string(),
+                    RocketMqVersion::try_from(version)
+                        .map_or_else(|_| version.to_string(), |version| version.to_string()),
+                    format!("{:?}", request_code),
+                    min_version.to_string(),
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+
     pub async fn send_heartbeat_to_broker(&self, id: i64, broker_name: &str, addr: &str) -> bool {
         if self.lock_heartbeat.try_lock().is_ok() {
             let heartbeat_data = self.prepare_heartbeat_data(false).await;
@@ -575,7 +1293,12 @@ impl MQClientInstance {
                 return false;
             }
 
-            if self.client_config.use_heartbeat_v2 {
+            if self.client_config.use_heartbeat_v2
+                && self
+                    .find_broker_version(broker_name, addr)
+                    .await
+                    .is_none_or(|version| version >= i32::from(HEARTBEAT_V2_MIN_BROKER_VERSION))
+            {
                 unimplemented!("sendHeartbeatToBrokerV2")
             } else {
                 self.send_heartbeat_to_broker_inner(id, broker_name, addr, &heartbeat_data)
@@ -593,7 +1316,7 @@ impl MQClientInstance {
         addr: &str,
         heartbeat_data: &HeartbeatData,
     ) -> bool {
-        if let Ok(version) = self
+        if let Ok((version, subscription_data_versions)) = self
             .mq_client_api_impl
             .mut_from_ref()
             .send_heartbeat(
@@ -622,6 +1345,9 @@ impl MQClientInstance {
                     broker_name, id, addr,
                 );
             }
+            self.on_heartbeat_success(broker_name, addr);
+            self.on_subscription_data_versions(addr, subscription_data_versions)
+                .await;
             return true;
         }
         if self.is_broker_in_name_server(addr).await {
@@ -636,9 +1362,171 @@ impl MQClientInstance {
                 broker_name, id, addr
             )
         }
+        self.on_heartbeat_failure(broker_name, addr).await;
         false
     }
 
+    /// Compares the subscription data versions the broker returned with the ones it returned on
+    /// the previous heartbeat. A changed version means the subscriptions of the group changed on
+    /// the broker, possibly by another member, so the subscriptions of the group are sent to the
+    /// broker again and the group is rebalanced right away instead of on the next rebalance
+    /// round.
+    async fn on_subscription_data_versions(
+        &self,
+        addr: &str,
+        subscription_data_versions: HashMap<String, DataVersion>,
+    ) {
+        let changed_groups = {
+            let mut table = self.subscription_data_version_table.lock();
+            let known = table.entry(addr.to_string()).or_default();
+            let mut changed_groups = Vec::new();
+            for (group, data_version) in subscription_data_versions {
+                match known.get(&group) {
+                    Some(previous) if *previous == data_version => {}
+                    Some(previous) => {
+                        info!(
+                            "subscription data version of group[{}] changed on broker[{}], {} -> \
+                             {}",
+                            group, addr, previous, data_version
+                        );
+                        changed_groups.push(group.clone());
+                        known.insert(group, data_version);
+                    }
+                    None => {
+                        known.insert(group, data_version);
+                    }
+                }
+            }
+            changed_groups
+        };
+        if changed_groups.is_empty() {
+            return;
+        }
+        self.resend_subscriptions(addr, &changed_groups).await;
+        let consumer_table = self.consumer_table.read().await;
+        for group in changed_groups {
+            if let Some(consumer) = consumer_table.get(&group) {
+                consumer.do_rebalance().await;
+            }
+        }
+    }
+
+    /// Sends the subscriptions of `groups` to the broker at `addr` in a heartbeat of their own,
+    /// and takes the versions the broker answers with as the known ones.
+    async fn resend_subscriptions(&self, addr: &str, groups: &[String]) {
+        let mut heartbeat_data = self.prepare_heartbeat_data(false).await;
+        heartbeat_data.producer_data_set.clear();
+        heartbeat_data
+            .consumer_data_set
+            .retain(|consumer_data| groups.contains(&consumer_data.group_name));
+        if heartbeat_data.consumer_data_set.is_empty() {
+            return;
+        }
+        match self
+            .mq_client_api_impl
+            .mut_from_ref()
+            .send_heartbeat(
+                addr,
+                &heartbeat_data,
+                self.client_config.mq_client_api_timeout,
+            )
+            .await
+        {
+            Ok((_, subscription_data_versions)) => {
+                let mut table = self.subscription_data_version_table.lock();
+                let known = table.entry(addr.to_string()).or_default();
+                for (group, data_version) in subscription_data_versions {
+                    if groups.contains(&group) {
+                        known.insert(group, data_version);
+                    }
+                }
+            }
+            Err(err) => warn!(
+                "resend the subscriptions of groups {:?} to broker[{}] failed: {}",
+                groups, addr, err
+            ),
+        }
+    }
+
+    fn on_heartbeat_success(&self, broker_name: &str, addr: &str) {
+        let failures = self
+            .heartbeat_failure_table
+            .lock()
+            .remove(addr)
+            .unwrap_or_default();
+        if failures >= self.client_config.heartbeat_failure_threshold.max(1) {
+            info!(
+                "heartbeat to broker[{} {}] recovered after {} failures",
+                broker_name, addr, failures
+            );
+            self.emit_client_event(ClientEvent::BrokerHeartbeatRecovered {
+                broker_name: broker_name.to_string(),
+                broker_addr: addr.to_string(),
+            });
+        }
+    }
+
+    /// Escalates every `heartbeat_failure_threshold` consecutive failures: the broker is isolated
+    /// in the producers' fault tolerance table, the routes of its topics are refreshed and the
+    /// listeners are told.
+    async fn on_heartbeat_failure(&self, broker_name: &str, addr: &str) {
+        let failures = {
+            let mut table = self.heartbeat_failure_table.lock();
+            let failures = table.entry(addr.to_string()).or_default();
+            *failures += 1;
+            *failures
+        };
+        if failures % self.client_config.heartbeat_failure_threshold.max(1) != 0 {
+            return;
+        }
+        warn!(
+            "heartbeat to broker[{} {}] failed {} times in a row, isolate it and refresh the \
+             route of its topics",
+            broker_name, addr, failures
+        );
+        for producer in self.producer_table.read().await.values() {
+            producer.isolate_broker(broker_name);
+        }
+
+        let topics = self
+            .topic_route_table
+            .read()
+            .await
+            .iter()
+            .filter(|(_, route)| {
+                route
+                    .broker_datas
+                    .iter()
+                    .any(|bd| bd.broker_addrs().values().any(|value| value == addr))
+            })
+            .map(|(topic, _)| topic.clone())
+            .collect::<Vec<_>>();
+        // The heartbeat loop holds the broker address table, refresh once it is released
+        let mut instance = self.clone();
+        tokio::spawn(
+            async move {
+                for topic in topics {
+                    instance
+                        .update_topic_route_info_from_name_server_topic(topic.as_str())
+                        .await;
+                }
+            }
+            .instrument(self.span.clone()),
+        );
+
+        self.emit_client_event(ClientEvent::BrokerHeartbeatFailed {
+            broker_name: broker_name.to_string(),
+            broker_addr: addr.to_string(),
+            consecutive_failures: failures,
+        });
+    }
+
+    fn emit_client_event(&self, event: ClientEvent) {
+        for listener in self.client_event_listeners.read().iter() {
+            listener.on_event(&event);
+        }
+    }
+
     async fn is_broker_in_name_server(&self, broker_name: &str) -> bool {
         let broker_addr_table = self.topic_route_table.read().await;
         for (_, value) in broker_addr_table.iter() {
@@ -662,11 +1550,11 @@ impl MQClientInstance {
         let consumer_table = self.consumer_table.read().await;
         for (_, value) in consumer_table.iter() {
             let mut consumer_data = ConsumerData {
-                group_name: value.group_name().to_json(),
+                group_name: value.group_name().to_string(),
                 consume_type: value.consume_type(),
                 message_model: value.message_model(),
                 consume_from_where: value.consume_from_where(),
-                subscription_data_set: value.subscriptions().clone(),
+                subscription_data_set: value.subscriptions(),
                 unit_mode: value.is_unit_mode(),
             };
             if !is_without_sub {
@@ -690,6 +1578,76 @@ impl MQClientInstance {
     }
 }
 
+/// Brokers older than this do not understand the heartbeat v2 header, the client falls back
+/// to the full heartbeat for them.
+const HEARTBEAT_V2_MIN_BROKER_VERSION: RocketMqVersion = RocketMqVersion::V511;
+
+/// Records when each of the `cached` topics missing from `referenced` was first seen unused and
+/// returns the ones unused for at least `grace_period`, forgetting the ones used again.
+fn expire_unreferenced_topics(
+    unreferenced: &mut HashMap<String, Instant>,
+    cached: Vec<String>,
+    referenced: &HashSet<String>,
+    now: Instant,
+    grace_period: Duration,
+) -> Vec<String> {
+    unreferenced.retain(|topic, _| !referenced.contains(topic));
+    cached
+        .into_iter()
+        .filter(|topic| !referenced.contains(topic))
+        .filter(|topic| {
+            let since = *unreferenced.entry(topic.clone()).or_insert(now);
+            now.saturating_duration_since(since) >= grace_period
+        })
+        .collect()
+}
+
+/// Reconsume times a message sent back goes to the DLQ after, when the consumer leaves it to
+/// the default.
+const DEFAULT_MAX_RECONSUME_TIMES: i32 = 16;
+
+/// New message to the retry topic of `consumer_group` in place of `msg`, for when the broker
+/// does not take `msg` back. It keeps the body, flag and properties of `msg`, counts one more
+/// reconsume and is delayed longer with every reconsume.
+fn build_retry_message(
+    msg: &MessageExt,
+    consumer_group: &str,
+    max_reconsume_times: i32,
+) -> Message {
+    let mut retry_msg = Message {
+        topic: mix_all::get_retry_topic(consumer_group),
+        flag: msg.get_flag(),
+        body: msg.get_body().cloned(),
+        ..Message::default()
+    };
+    MessageAccessor::set_properties(&mut retry_msg, msg.get_properties().clone());
+    let origin_msg_id = MessageAccessor::get_origin_message_id(msg)
+        .filter(|id| !id.is_empty())
+        .unwrap_or_else(|| msg.msg_id.clone());
+    MessageAccessor::set_origin_message_id(&mut retry_msg, origin_msg_id.as_str());
+    MessageAccessor::put_property(
+        &mut retry_msg,
+        MessageConst::PROPERTY_RETRY_TOPIC,
+        msg.get_topic(),
+    );
+    MessageAccessor::set_reconsume_time(
+        &mut retry_msg,
+        (msg.reconsume_times + 1).to_string().as_str(),
+    );
+    let max_reconsume_times = if max_reconsume_times == -1 {
+        DEFAULT_MAX_RECONSUME_TIMES
+    } else {
+        max_reconsume_times
+    };
+    MessageAccessor::set_max_reconsume_times(
+        &mut retry_msg,
+        max_reconsume_times.to_string().as_str(),
+    );
+    MessageAccessor::clear_property(&mut retry_msg, MessageConst::PROPERTY_TRANSACTION_PREPARED);
+    retry_msg.set_delay_time_level(3 + msg.reconsume_times);
+    retry_msg
+}
+
 pub fn topic_route_data2topic_publish_info(
     topic: &str,
     route: &mut TopicRouteData,
@@ -757,7 +1715,7 @@ pub fn topic_route_data2topic_publish_info(
                     .as_ref()
                     .unwrap()
                     .broker_addrs()
-                    .contains_key(&(mix_all::MASTER_ID as i64))
+                    .contains_key(&mix_all::MASTER_BROKER_ADDR_ID)
                 {
                     continue;
                 }
@@ -778,3 +1736,388 @@ pub fn topic_route_data2topic_subscribe_info(
 ) -> HashSet<MessageQueue> {
     unimplemented!("topicRouteData2TopicSubscribeInfo")
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::atomic::Ordering;
+
+    use rocketmq_common::common::consumer::consume_from_where::ConsumeFromWhere;
+    use rocketmq_remoting::code::request_code::RequestCode;
+    use rocketmq_remoting::net::channel::Channel;
+    use rocketmq_remoting::protocol::body::consumer_running_info::ConsumerRunningInfo;
+    use rocketmq_remoting::protocol::filter::filter_api::FilterAPI;
+    use rocketmq_remoting::protocol::heartbeat::consume_type::ConsumeType;
+    use rocketmq_remoting::protocol::heartbeat::message_model::MessageModel;
+    use rocketmq_remoting::protocol::heartbeat::subscription_data::SubscriptionData;
+    use rocketmq_remoting::protocol::remoting_command::RemotingCommand;
+    use rocketmq_remoting::remoting_server::flow_control::RequestFlowController;
+    use rocketmq_remoting::runtime::connection_handler_context::ConnectionHandlerContext;
+    use rocketmq_remoting::runtime::processor::RequestProcessor;
+    use tokio::net::TcpListener;
+
+    use super::*;
+    use crate::consumer::mq_consumer_inner::RebalanceFuture;
+
+    /// Records the heartbeats it receives and answers with the subscription data version
+    /// `version` of every consumer group.
+    #[derive(Clone, Default)]
+    struct HeartbeatBroker {
+        heartbeats: Arc<parking_lot::Mutex<Vec<HeartbeatData>>>,
+        version: Arc<parking_lot::Mutex<DataVersion>>,
+    }
+
+    impl RequestProcessor for HeartbeatBroker {
+        async fn process_request(
+            &mut self,
+            _channel: Channel,
+            _ctx: ConnectionHandlerContext,
+            request: RemotingCommand,
+        ) -> rocketmq_remoting::Result<Option<RemotingCommand>> {
+            assert_eq!(RequestCode::from(request.code()), RequestCode::HeartBeat);
+            let heartbeat_data =
+                serde_json::from_slice::<HeartbeatData>(request.body().as_ref().unwrap()).unwrap();
+            let versions = heartbeat_data
+                .consumer_data_set
+                .iter()
+                .map(|consumer_data| {
+                    let mut version = DataVersion::new();
+                    version.assign_new_one(&self.version.lock());
+                    (consumer_data.group_name.clone(), version)
+                })
+                .collect::<HashMap<_, _>>();
+            self.heartbeats.lock().push(heartbeat_data);
+            let mut response = RemotingCommand::create_response_command();
+            response.add_ext_field(
+                mix_all::SUBSCRIPTION_DATA_VERSION.to_string(),
+                serde_json::to_string(&versions).unwrap(),
+            );
+            Ok(Some(response))
+        }
+    }
+
+    struct RebalanceCountingConsumer {
+        subscriptions: HashSet<SubscriptionData>,
+        rebalances: Arc<AtomicUsize>,
+    }
+
+    impl MQConsumerInner for RebalanceCountingConsumer {
+        fn group_name(&self) -> &str {
+            "GroupTest"
+        }
+
+        fn message_model(&self) -> MessageModel {
+            MessageModel::Clustering
+        }
+
+        fn consume_type(&self) -> ConsumeType {
+            ConsumeType::ConsumePassively
+        }
+
+        fn consume_from_where(&self) -> ConsumeFromWhere {
+            ConsumeFromWhere::ConsumeFromLastOffset
+        }
+
+        fn subscriptions(&self) -> HashSet<SubscriptionData> {
+            self.subscriptions.clone()
+        }
+
+        fn subscribe(&self, _topic: &str, _sub_expression: &str) -> Result<()> {
+            Ok(())
+        }
+
+        fn unsubscribe(&self, _topic: &str) -> bool {
+            false
+        }
+
+        fn do_rebalance(&self) -> RebalanceFuture<'_, ()> {
+            self.rebalances.fetch_add(1, Ordering::SeqCst);
+            Box::pin(async {})
+        }
+
+        fn try_rebalance(&self) -> RebalanceFuture<'_, bool> {
+            Box::pin(async {
+                self.do_rebalance().await;
+                true
+            })
+        }
+
+        fn allocation(&self) -> HashMap<String, HashSet<MessageQueue>> {
+            HashMap::new()
+        }
+
+        fn persist_consumer_offset(&self) {}
+
+        fn update_topic_subscribe_info(&mut self, _topic: &str, _info: &HashSet<MessageQueue>) {}
+
+        fn is_subscribe_topic_need_update(&self, _topic: &str) -> bool {
+            false
+        }
+
+        fn is_unit_mode(&self) -> bool {
+            false
+        }
+
+        fn consumer_running_info(&self) -> ConsumerRunningInfo {
+            ConsumerRunningInfo {}
+        }
+    }
+
+    #[test]
+    fn changed_subscription_data_version_resends_the_subscriptions() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let broker = HeartbeatBroker::default();
+        let rebalances = Arc::new(AtomicUsize::new(0));
+        let instance = runtime.block_on(async {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = listener.local_addr().unwrap().to_string();
+            tokio::spawn(rocketmq_remoting::remoting_server::server::run(
+                listener,
+                std::future::pending::<()>(),
+                broker.clone(),
+                None,
+                vec![],
+                Arc::new(RequestFlowController::new(&[])),
+            ));
+            let mut instance = MQClientInstance::new(
+                ClientConfig::default(),
+                0,
+                "127.0.0.1@test".to_string(),
+                None,
+            );
+            let subscription = FilterAPI::build_subscription_data("TopicTest", "*").unwrap();
+            instance
+                .register_consumer(
+                    "GroupTest",
+                    RebalanceCountingConsumer {
+                        subscriptions: HashSet::from([subscription]),
+                        rebalances: rebalances.clone(),
+                    },
+                )
+                .await;
+            let heartbeat_data = instance.prepare_heartbeat_data(false).await;
+
+            assert!(
+                instance
+                    .send_heartbeat_to_broker_inner(0, "broker-a", &addr, &heartbeat_data)
+                    .await
+            );
+            assert_eq!(broker.heartbeats.lock().len(), 1);
+            assert_eq!(rebalances.load(Ordering::SeqCst), 0);
+
+            broker.version.lock().next_version();
+            assert!(
+                instance
+                    .send_heartbeat_to_broker_inner(0, "broker-a", &addr, &heartbeat_data)
+                    .await
+            );
+            let heartbeats = broker.heartbeats.lock();
+            assert_eq!(heartbeats.len(), 3);
+            let resent = &heartbeats[2];
+            assert!(resent.producer_data_set.is_empty());
+            let consumer_data = resent.consumer_data_set.iter().next().unwrap();
+            assert_eq!(consumer_data.group_name, "GroupTest");
+            assert_eq!(
+                consumer_data
+                    .subscription_data_set
+                    .iter()
+                    .next()
+                    .unwrap()
+                    .topic,
+                "TopicTest"
+            );
+            drop(heartbeats);
+            assert_eq!(rebalances.load(Ordering::SeqCst), 1);
+
+            // The version the resend returned is known, the next heartbeat changes nothing
+            assert!(
+                instance
+                    .send_heartbeat_to_broker_inner(0, "broker-a", &addr, &heartbeat_data)
+                    .await
+            );
+            assert_eq!(broker.heartbeats.lock().len(), 4);
+            assert_eq!(rebalances.load(Ordering::SeqCst), 1);
+            instance
+        });
+        drop(runtime);
+        drop(instance);
+    }
+
+    #[derive(Clone, Default)]
+    struct RecordingListener {
+        events: Arc<parking_lot::Mutex<Vec<ClientEvent>>>,
+    }
+
+    impl ClientEventListener for RecordingListener {
+        fn on_event(&self, event: &ClientEvent) {
+            self.events.lock().push(event.clone());
+        }
+    }
+
+    #[test]
+    fn heartbeat_failures_escalate_at_the_threshold() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let listener = RecordingListener::default();
+        let instance = runtime.block_on(async {
+            let client_config = ClientConfig {
+                heartbeat_failure_threshold: 2,
+                ..ClientConfig::default()
+            };
+            let instance =
+                MQClientInstance::new(client_config, 0, "127.0.0.1@test".to_string(), None);
+            instance.register_client_event_listener(listener.clone());
+
+            instance.on_heartbeat_failure("broker-a", "addr-a").await;
+            instance.on_heartbeat_failure("broker-b", "addr-b").await;
+            assert!(listener.events.lock().is_empty());
+
+            instance.on_heartbeat_failure("broker-a", "addr-a").await;
+            instance.on_heartbeat_failure("broker-a", "addr-a").await;
+            instance.on_heartbeat_failure("broker-a", "addr-a").await;
+            instance.on_heartbeat_success("broker-a", "addr-a");
+            // below the threshold, nothing to recover from
+            instance.on_heartbeat_success("broker-b", "addr-b");
+            instance
+        });
+        drop(runtime);
+        drop(instance);
+
+        let failed = |consecutive_failures| ClientEvent::BrokerHeartbeatFailed {
+            broker_name: "broker-a".to_string(),
+            broker_addr: "addr-a".to_string(),
+            consecutive_failures,
+        };
+        assert_eq!(
+            *listener.events.lock(),
+            vec![
+                failed(2),
+                failed(4),
+                ClientEvent::BrokerHeartbeatRecovered {
+                    broker_name: "broker-a".to_string(),
+                    broker_addr: "addr-a".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn shutdown_stops_the_background_tasks_and_forgets_the_instance() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let client_config = ClientConfig {
+            instance_name: "shutdown_test".to_string(),
+            enable_topic_route_cache: false,
+            ..ClientConfig::default()
+        };
+        let (instance, recreated) = runtime.block_on(async {
+            let mut instance = MQClientManager::get_instance()
+                .get_or_create_mq_client_instance(client_config.clone(), None)
+                .await;
+            let topic_route_table = instance.topic_route_table.clone();
+            let held = || Arc::strong_count(&topic_route_table);
+            let released = held();
+            instance.start_scheduled_task();
+            let client_instance = (*instance).clone();
+            instance.rebalance_service.start(client_instance).await;
+            instance.service_state = ServiceState::Running;
+            assert!(held() > released);
+
+            instance.shutdown().await;
+            assert_eq!(instance.service_state, ServiceState::ShutdownAlready);
+            tokio::time::timeout(Duration::from_secs(5), async {
+                while held() != released {
+                    tokio::task::yield_now().await;
+                }
+            })
+            .await
+            .unwrap();
+
+            let recreated = MQClientManager::get_instance()
+                .get_or_create_mq_client_instance(client_config.clone(), None)
+                .await;
+            assert_eq!(recreated.service_state, ServiceState::CreateJust);
+            MQClientManager::get_instance()
+                .remove_client_factory(recreated.client_id.as_str())
+                .await;
+            (instance, recreated)
+        });
+        drop(runtime);
+        drop(instance);
+        drop(recreated);
+    }
+
+    #[test]
+    fn retry_message_carries_over_the_reconsume_times() {
+        let mut msg = MessageExt::default();
+        msg.message = Message::with_tags("TopicTest", "TagA", b"payload");
+        msg.msg_id = "MSG_ID".to_string();
+        msg.reconsume_times = 2;
+        msg.put_property(MessageConst::PROPERTY_TRANSACTION_PREPARED, "true");
+
+        let retry_msg = build_retry_message(&msg, "GroupTest", -1);
+        assert_eq!(retry_msg.topic, mix_all::get_retry_topic("GroupTest"));
+        assert_eq!(retry_msg.body.as_deref(), Some(&b"payload"[..]));
+        assert_eq!(retry_msg.get_tags().as_deref(), Some("TagA"));
+        assert_eq!(
+            MessageAccessor::get_origin_message_id(&retry_msg).as_deref(),
+            Some("MSG_ID")
+        );
+        assert_eq!(
+            retry_msg
+                .get_property(MessageConst::PROPERTY_RETRY_TOPIC)
+                .as_deref(),
+            Some("TopicTest")
+        );
+        assert_eq!(
+            MessageAccessor::get_reconsume_time(&retry_msg).as_deref(),
+            Some("3")
+        );
+        assert_eq!(
+            MessageAccessor::get_max_reconsume_times(&retry_msg).as_deref(),
+            Some("16")
+        );
+        assert!(retry_msg
+            .get_property(MessageConst::PROPERTY_TRANSACTION_PREPARED)
+            .is_none());
+        assert_eq!(retry_msg.get_delay_time_level(), 5);
+    }
+
+    #[test]
+    fn unreferenced_topics_expire_after_the_grace_period() {
+        let mut unreferenced = HashMap::new();
+        let grace_period = Duration::from_secs(60);
+        let start = Instant::now();
+        let cached = || vec!["used".to_string(), "unused".to_string()];
+        let referenced = HashSet::from(["used".to_string()]);
+
+        assert!(expire_unreferenced_topics(
+            &mut unreferenced,
+            cached(),
+            &referenced,
+            start,
+            grace_period
+        )
+        .is_empty());
+        assert_eq!(
+            expire_unreferenced_topics(
+                &mut unreferenced,
+                cached(),
+                &referenced,
+                start + grace_period,
+                grace_period
+            ),
+            vec!["unused".to_string()]
+        );
+
+        // used again in between, the grace period starts over
+        let all = HashSet::from(["used".to_string(), "unused".to_string()]);
+        expire_unreferenced_topics(&mut unreferenced, cached(), &all, start, grace_period);
+        assert!(expire_unreferenced_topics(
+            &mut unreferenced,
+            cached(),
+            &referenced,
+            start + grace_period,
+            grace_period
+        )
+        .is_empty());
+    }
+}
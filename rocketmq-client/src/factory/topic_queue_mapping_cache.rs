@@ -0,0 +1,335 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::collections::HashMap;
+use std::time::Duration;
+use std::time::Instant;
+
+use rocketmq_common::common::message::message_queue::MessageQueue;
+use rocketmq_common::common::mix_all;
+use rocketmq_remoting::protocol::static_topic::topic_queue_info::TopicQueueMappingInfo;
+use rocketmq_remoting::rpc::client_metadata::ClientMetadata;
+use tracing::info;
+use tracing::warn;
+
+/// Counters of the logical queue remapping seen by a client instance.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QueueRemapStats {
+    /// Mappings accepted into the cache, from routes or from brokers.
+    pub mapping_updates: u64,
+    /// Mappings dropped because they were older than the cached ones or malformed.
+    pub rejected_mappings: u64,
+    /// Logical queues which moved to another broker.
+    pub remapped_queues: u64,
+    /// Requests answered with `NOT_LEADER_FOR_QUEUE`.
+    pub not_leader_responses: u64,
+    /// Sends which failed to connect to the broker of their queue.
+    pub connection_failures: u64,
+}
+
+/// Shortest time between two route refreshes of a topic caused by connection failures, so a
+/// broker going down does not turn every failed send into a name server request.
+pub const ROUTE_REFRESH_ON_FAILURE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Logical queue mappings of the static topics a client instance uses, per broker.
+///
+/// A mapping is only replaced by one of the same scope with an epoch at least as new, so a
+/// route from a lagging name server cannot undo a remap already learnt from a broker. The
+/// broker each logical queue is written to is derived from the cached mappings and can be
+/// refreshed for a single broker without fetching the whole route again.
+#[derive(Debug, Default)]
+pub struct TopicQueueMappingCache {
+    mapping_table:
+        HashMap<String /* Topic */, HashMap<String /* brokerName */, TopicQueueMappingInfo>>,
+    end_points_table:
+        HashMap<String /* Topic */, HashMap<MessageQueue, String /* brokerName */>>,
+    /// Last route refresh of a topic caused by a connection failure.
+    failure_refresh_table: HashMap<String /* Topic */, Instant>,
+    stats: QueueRemapStats,
+}
+
+impl TopicQueueMappingCache {
+    /// Replaces the mappings of `topic` with the ones of a fresh route, keeping the cached
+    /// mapping of a broker when the route's one is older. Returns whether any logical queue
+    /// moved.
+    pub fn update_from_route(
+        &mut self,
+        topic: &str,
+        mapping_by_broker: &HashMap<String, TopicQueueMappingInfo>,
+    ) -> bool {
+        let cached = self.mapping_table.remove(topic).unwrap_or_default();
+        let mut mappings = HashMap::with_capacity(mapping_by_broker.len());
+        for (broker_name, info) in mapping_by_broker {
+            let info = match self.validate(topic, broker_name, info, cached.get(broker_name)) {
+                Some(info) => info.clone(),
+                None => match cached.get(broker_name) {
+                    Some(cached_info) => cached_info.clone(),
+                    None => continue,
+                },
+            };
+            mappings.insert(broker_name.clone(), info);
+        }
+        self.put(topic, mappings)
+    }
+
+    /// Replaces the mapping of `topic` on one broker, e.g. the one fetched from the broker
+    /// after it rejected a request for a queue it no longer leads. Returns whether any logical
+    /// queue moved.
+    pub fn update_broker_mapping(
+        &mut self,
+        topic: &str,
+        broker_name: &str,
+        info: TopicQueueMappingInfo,
+    ) -> bool {
+        let mut mappings = self.mapping_table.remove(topic).unwrap_or_default();
+        if self
+            .validate(topic, broker_name, &info, mappings.get(broker_name))
+            .is_some()
+        {
+            mappings.insert(broker_name.to_string(), info);
+        }
+        self.put(topic, mappings)
+    }
+
+    pub fn remove(&mut self, topic: &str) {
+        self.mapping_table.remove(topic);
+        self.end_points_table.remove(topic);
+        self.failure_refresh_table.remove(topic);
+    }
+
+    /// Broker the logical queue `mq` is written to, `None` when `topic` is not static.
+    pub fn get_broker_name(&self, mq: &MessageQueue) -> Option<&String> {
+        self.end_points_table.get(mq.get_topic())?.get(mq)
+    }
+
+    /// Brokers holding a mapping of `topic`.
+    pub fn broker_names(&self, topic: &str) -> Vec<String> {
+        self.mapping_table
+            .get(topic)
+            .map(|mappings| mappings.keys().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    pub fn mapping(&self, topic: &str, broker_name: &str) -> Option<&TopicQueueMappingInfo> {
+        self.mapping_table.get(topic)?.get(broker_name)
+    }
+
+    pub fn on_not_leader_response(&mut self) {
+        self.stats.not_leader_responses += 1;
+    }
+
+    /// Forgets the broker of `mq` after a send to it failed to connect, the queue resolves to
+    /// no broker until the mapping is refreshed. Returns whether the route of the topic should
+    /// be refreshed now, which is at most once per [`ROUTE_REFRESH_ON_FAILURE_INTERVAL`].
+    pub fn on_connection_failure(&mut self, mq: &MessageQueue, now: Instant) -> bool {
+        self.stats.connection_failures += 1;
+        if let Some(end_points) = self.end_points_table.get_mut(mq.get_topic()) {
+            if let Some(broker_name) = end_points.remove(mq) {
+                info!(
+                    "invalidate broker {} of queue {:?} after a connection failure",
+                    broker_name, mq
+                );
+            }
+        }
+        match self.failure_refresh_table.get(mq.get_topic()) {
+            Some(last) if now.duration_since(*last) < ROUTE_REFRESH_ON_FAILURE_INTERVAL => false,
+            _ => {
+                self.failure_refresh_table
+                    .insert(mq.get_topic().to_string(), now);
+                true
+            }
+        }
+    }
+
+    pub fn stats(&self) -> QueueRemapStats {
+        self.stats
+    }
+
+    fn validate<'a>(
+        &mut self,
+        topic: &str,
+        broker_name: &str,
+        info: &'a TopicQueueMappingInfo,
+        cached: Option<&TopicQueueMappingInfo>,
+    ) -> Option<&'a TopicQueueMappingInfo> {
+        let malformed = info.topic.as_deref().is_some_and(|t| t != topic)
+            || info.bname.as_deref() != Some(broker_name)
+            || info.scope.is_none()
+            || info.curr_id_map.is_none();
+        if malformed {
+            warn!(
+                "ignore malformed queue mapping of topic {} on broker {}: {:?}",
+                topic, broker_name, info
+            );
+            self.stats.rejected_mappings += 1;
+            return None;
+        }
+        if let Some(cached) = cached {
+            if cached.scope == info.scope && cached.epoch > info.epoch {
+                info!(
+                    "ignore stale queue mapping of topic {} on broker {}, epoch {} < {}",
+                    topic, broker_name, info.epoch, cached.epoch
+                );
+                self.stats.rejected_mappings += 1;
+                return None;
+            }
+            if cached == info {
+                return Some(info);
+            }
+        }
+        self.stats.mapping_updates += 1;
+        Some(info)
+    }
+
+    fn put(&mut self, topic: &str, mappings: HashMap<String, TopicQueueMappingInfo>) -> bool {
+        if mappings.is_empty() {
+            self.remove(topic);
+            return false;
+        }
+        let end_points = ClientMetadata::mapping_infos2endpoints(topic, &mappings);
+        self.mapping_table.insert(topic.to_string(), mappings);
+        let remapped = self
+            .end_points_table
+            .get(topic)
+            .map_or(0, |old_end_points| {
+                end_points
+                    .iter()
+                    .filter(|(mq, broker_name)| {
+                        // a queue briefly claimed by no broker in between moved only once
+                        broker_name.as_str() != mix_all::LOGICAL_QUEUE_MOCK_BROKER_NAME_NOT_EXIST
+                            && old_end_points
+                                .get(*mq)
+                                .is_some_and(|old_broker_name| old_broker_name != *broker_name)
+                    })
+                    .count()
+            });
+        if remapped > 0 {
+            info!("{} logical queues of topic {} remapped", remapped, topic);
+            self.stats.remapped_queues += remapped as u64;
+        }
+        self.end_points_table.insert(topic.to_string(), end_points);
+        remapped > 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rocketmq_remoting::protocol::static_topic::topic_queue_mapping_utils::TopicQueueMappingUtils;
+
+    use super::*;
+
+    fn mapping(bname: &str, epoch: i64, ids: &[(i32, i32)]) -> TopicQueueMappingInfo {
+        TopicQueueMappingInfo {
+            curr_id_map: Some(ids.iter().copied().collect()),
+            ..TopicQueueMappingInfo::new("TopicTest".to_string(), 2, bname.to_string(), epoch)
+        }
+    }
+
+    fn logical_queue(scope: &str, global_id: i32) -> MessageQueue {
+        MessageQueue::from_parts(
+            "TopicTest",
+            TopicQueueMappingUtils::get_mock_broker_name(scope),
+            global_id,
+        )
+    }
+
+    #[test]
+    fn broker_refresh_moves_queues_and_stale_routes_are_ignored() {
+        let scope = TopicQueueMappingInfo::default().scope.unwrap();
+        let mut cache = TopicQueueMappingCache::default();
+        let route = HashMap::from([
+            (
+                "broker-a".to_string(),
+                mapping("broker-a", 1, &[(0, 0), (1, 1)]),
+            ),
+            ("broker-b".to_string(), mapping("broker-b", 1, &[])),
+        ]);
+        assert!(!cache.update_from_route("TopicTest", &route));
+        assert_eq!(
+            cache.get_broker_name(&logical_queue(&scope, 1)).unwrap(),
+            "broker-a"
+        );
+
+        // queue 1 moved to broker-b, learnt from the brokers before the name server
+        cache.update_broker_mapping("TopicTest", "broker-a", mapping("broker-a", 2, &[(0, 0)]));
+        assert!(cache.update_broker_mapping(
+            "TopicTest",
+            "broker-b",
+            mapping("broker-b", 2, &[(1, 0)])
+        ));
+        assert_eq!(
+            cache.get_broker_name(&logical_queue(&scope, 1)).unwrap(),
+            "broker-b"
+        );
+
+        assert!(!cache.update_from_route("TopicTest", &route));
+        assert_eq!(
+            cache.get_broker_name(&logical_queue(&scope, 1)).unwrap(),
+            "broker-b"
+        );
+        let stats = cache.stats();
+        assert_eq!(stats.remapped_queues, 1);
+        assert_eq!(stats.rejected_mappings, 2);
+        assert_eq!(stats.mapping_updates, 4);
+    }
+
+    #[test]
+    fn connection_failures_invalidate_the_queue_and_throttle_refreshes() {
+        let scope = TopicQueueMappingInfo::default().scope.unwrap();
+        let mut cache = TopicQueueMappingCache::default();
+        let route = HashMap::from([(
+            "broker-a".to_string(),
+            mapping("broker-a", 1, &[(0, 0), (1, 1)]),
+        )]);
+        cache.update_from_route("TopicTest", &route);
+
+        let now = Instant::now();
+        assert!(cache.on_connection_failure(&logical_queue(&scope, 0), now));
+        assert!(cache.get_broker_name(&logical_queue(&scope, 0)).is_none());
+        assert_eq!(
+            cache.get_broker_name(&logical_queue(&scope, 1)).unwrap(),
+            "broker-a"
+        );
+        assert!(!cache.on_connection_failure(&logical_queue(&scope, 1), now));
+        assert!(cache.on_connection_failure(
+            &logical_queue(&scope, 1),
+            now + ROUTE_REFRESH_ON_FAILURE_INTERVAL
+        ));
+
+        // a refresh from the broker restores the queue
+        cache.update_broker_mapping(
+            "TopicTest",
+            "broker-a",
+            mapping("broker-a", 1, &[(0, 0), (1, 1)]),
+        );
+        assert_eq!(
+            cache.get_broker_name(&logical_queue(&scope, 0)).unwrap(),
+            "broker-a"
+        );
+        assert_eq!(cache.stats().connection_failures, 3);
+    }
+
+    #[test]
+    fn malformed_mappings_are_rejected() {
+        let mut cache = TopicQueueMappingCache::default();
+        let mut info = mapping("broker-a", 1, &[(0, 0)]);
+        info.curr_id_map = None;
+        cache.update_broker_mapping("TopicTest", "broker-a", info);
+        cache.update_broker_mapping("TopicTest", "broker-b", mapping("broker-a", 1, &[(0, 0)]));
+        assert!(cache.broker_names("TopicTest").is_empty());
+        assert_eq!(cache.stats().rejected_mappings, 2);
+    }
+}
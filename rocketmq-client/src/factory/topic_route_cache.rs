@@ -0,0 +1,138 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::collections::HashMap;
+
+use rocketmq_common::FileUtils;
+use rocketmq_remoting::protocol::route::topic_route_data::TopicRouteData;
+use rocketmq_remoting::protocol::RemotingDeserializable;
+use rocketmq_remoting::protocol::RemotingSerializable;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::error::MQClientError::LocalFileException;
+use crate::Result;
+
+/// Local snapshot of the topic routes known by a client instance.
+///
+/// The snapshot is written when the client instance shuts down and can be loaded at start
+/// before the first name server query completes, so producers can send right after a
+/// restart even if the name servers are briefly unavailable.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TopicRouteCache {
+    pub topic_route_table: HashMap<String /* Topic */, TopicRouteData>,
+    pub broker_addr_table: HashMap<String /* Broker Name */, HashMap<i64, String>>,
+}
+
+impl TopicRouteCache {
+    pub fn new(
+        topic_route_table: HashMap<String, TopicRouteData>,
+        broker_addr_table: HashMap<String, HashMap<i64, String>>,
+    ) -> Self {
+        TopicRouteCache {
+            topic_route_table,
+            broker_addr_table,
+        }
+    }
+
+    /// Loads the cache from `file_name`, returns `None` if the file does not exist or is
+    /// empty.
+    pub fn load(file_name: &str) -> Result<Option<TopicRouteCache>> {
+        let content = FileUtils::file_to_string(file_name).map_err(|err| {
+            LocalFileException(
+                format!("read topic route cache file {} failed", file_name),
+                Box::new(err),
+            )
+        })?;
+        if content.is_empty() {
+            return Ok(None);
+        }
+        let cache = TopicRouteCache::decode(content.as_bytes()).map_err(|err| {
+            LocalFileException(
+                format!("decode topic route cache file {} failed", file_name),
+                Box::new(err),
+            )
+        })?;
+        Ok(Some(cache))
+    }
+
+    /// Writes the cache to `file_name`, the previous file is kept as `<file_name>.bak`.
+    pub fn persist(&self, file_name: &str) -> Result<()> {
+        FileUtils::string_to_file(self.to_json_pretty().as_str(), file_name).map_err(|err| {
+            LocalFileException(
+                format!("write topic route cache file {} failed", file_name),
+                Box::new(err),
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rocketmq_remoting::protocol::route::route_data_view::BrokerData;
+    use rocketmq_remoting::protocol::route::route_data_view::QueueData;
+
+    use super::*;
+
+    #[test]
+    fn persist_and_load_topic_route_cache() {
+        let mut broker_addrs = HashMap::new();
+        broker_addrs.insert(0, "127.0.0.1:10911".to_string());
+        let mut broker_addr_table = HashMap::new();
+        broker_addr_table.insert("broker-a".to_string(), broker_addrs.clone());
+        let topic_route_data = TopicRouteData {
+            queue_datas: vec![QueueData::new("broker-a".to_string(), 4, 4, 6, 0)],
+            broker_datas: vec![BrokerData::new(
+                "DefaultCluster".to_string(),
+                "broker-a".to_string(),
+                broker_addrs,
+                None,
+            )],
+            ..Default::default()
+        };
+        let mut topic_route_table = HashMap::new();
+        topic_route_table.insert("TopicTest".to_string(), topic_route_data.clone());
+
+        let file = std::env::temp_dir()
+            .join("rocketmq-client-test")
+            .join("topic_route_cache.json");
+        let file_name = file.to_string_lossy().to_string();
+        TopicRouteCache::new(topic_route_table, broker_addr_table)
+            .persist(file_name.as_str())
+            .unwrap();
+
+        let cache = TopicRouteCache::load(file_name.as_str()).unwrap().unwrap();
+        assert_eq!(
+            cache.topic_route_table.get("TopicTest"),
+            Some(&topic_route_data)
+        );
+        assert_eq!(
+            cache
+                .broker_addr_table
+                .get("broker-a")
+                .and_then(|addrs| addrs.get(&0)),
+            Some(&"127.0.0.1:10911".to_string())
+        );
+        let _ = std::fs::remove_dir_all(file.parent().unwrap());
+    }
+
+    #[test]
+    fn load_missing_topic_route_cache() {
+        let cache = TopicRouteCache::load("/not/exist/topic_route_cache.json").unwrap();
+        assert!(cache.is_none());
+    }
+}
@@ -14,4 +14,21 @@
  * See the License for the specific language governing permissions and
  * limitations under the License.
  */
-pub struct EndTransactionContext {}
+use rocketmq_common::common::message::message_single::Message;
+
+use crate::producer::local_transaction_state::LocalTransactionState;
+
+/// An end transaction request the producer sent for a transactional message, after executing
+/// or checking its local transaction.
+pub struct EndTransactionContext {
+    pub producer_group: String,
+    pub broker_addr: String,
+    pub message: Message,
+    pub msg_id: String,
+    pub transaction_id: String,
+    pub transaction_state: LocalTransactionState,
+    /// Whether the state answers a check of the broker rather than the send of the message.
+    pub from_transaction_check: bool,
+    /// Times the broker checked the transaction so far, `0` outside of checks.
+    pub check_times: i32,
+}
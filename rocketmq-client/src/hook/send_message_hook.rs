@@ -19,7 +19,9 @@ use crate::hook::send_message_context::SendMessageContext;
 pub trait SendMessageHook: Send + Sync {
     fn hook_name(&self) -> &str;
 
-    fn send_message_before(&self, context: &Option<SendMessageContext<'_>>);
+    /// Runs before the request is sent, hooks may attach state for the `send_message_after` call,
+    /// such as `mq_trace_context`.
+    fn send_message_before(&self, context: &mut Option<SendMessageContext<'_>>);
 
     fn send_message_after(&self, context: &Option<SendMessageContext<'_>>);
 }
@@ -14,19 +14,24 @@
  * See the License for the specific language governing permissions and
  * limitations under the License.
  */
-use std::net::SocketAddr;
+use std::sync::Arc;
 
 use bytes::Bytes;
+use parking_lot::RwLock;
 use rocketmq_common::common::compression::compressor_factory::CompressorFactory;
 use rocketmq_common::common::message::message_ext::MessageExt;
 use rocketmq_common::common::message::MessageConst;
+use rocketmq_common::common::message::MessageTrait;
 use rocketmq_common::common::sys_flag::message_sys_flag::MessageSysFlag;
+use rocketmq_common::utils::network_util::NetworkUtil;
 use rocketmq_common::MessageAccessor::MessageAccessor;
 use rocketmq_common::MessageDecoder;
 use rocketmq_common::TimeUtils::get_current_millis;
+use rocketmq_common::WeakCellWrapper;
 use rocketmq_remoting::code::request_code::RequestCode;
 use rocketmq_remoting::code::response_code::ResponseCode;
 use rocketmq_remoting::net::channel::Channel;
+use rocketmq_remoting::protocol::header::check_transaction_state_request_header::CheckTransactionStateRequestHeader;
 use rocketmq_remoting::protocol::header::reply_message_request_header::ReplyMessageRequestHeader;
 use rocketmq_remoting::protocol::remoting_command::RemotingCommand;
 use rocketmq_remoting::runtime::connection_handler_context::ConnectionHandlerContext;
@@ -36,10 +41,13 @@ use tracing::debug;
 use tracing::info;
 use tracing::warn;
 
+use crate::factory::mq_client_instance::MQClientInstance;
 use crate::producer::request_future_holder::REQUEST_FUTURE_HOLDER;
 
-#[derive(Clone)]
-pub struct ClientRemotingProcessor;
+#[derive(Clone, Default)]
+pub struct ClientRemotingProcessor {
+    client_instance: Arc<RwLock<Option<WeakCellWrapper<MQClientInstance>>>>,
+}
 
 impl RequestProcessor for ClientRemotingProcessor {
     async fn process_request(
@@ -51,6 +59,9 @@ impl RequestProcessor for ClientRemotingProcessor {
         let request_code = RequestCode::from(request.code());
         info!("process_request: {:?}", request_code);
         match request_code {
+            RequestCode::CheckTransactionState => {
+                self.check_transaction_state(channel, request).await
+            }
             RequestCode::PushReplyMessageToClient => self.receive_reply_message(ctx, request).await,
             _ => {
                 info!("Unknown request code: {:?}", request_code);
@@ -61,6 +72,81 @@ impl RequestProcessor for ClientRemotingProcessor {
 }
 
 impl ClientRemotingProcessor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_client_instance(&self, client_instance: WeakCellWrapper<MQClientInstance>) {
+        *self.client_instance.write() = Some(client_instance);
+    }
+
+    async fn check_transaction_state(
+        &mut self,
+        channel: Channel,
+        request: RemotingCommand,
+    ) -> Result<Option<RemotingCommand>> {
+        let request_header =
+            match request.decode_command_custom_header::<CheckTransactionStateRequestHeader>() {
+                Some(request_header) => request_header,
+                None => {
+                    warn!("checkTransactionState, decode request header failed");
+                    return Ok(None);
+                }
+            };
+        let message_ext = request.get_body().and_then(|body| {
+            MessageDecoder::decode(&mut body.clone(), true, true, false, false, false)
+        });
+        let mut message_ext = match message_ext {
+            Some(message_ext) => message_ext,
+            None => {
+                warn!("checkTransactionState, decode message failed");
+                return Ok(None);
+            }
+        };
+        if let Some(transaction_id) =
+            message_ext.get_property(MessageConst::PROPERTY_UNIQ_CLIENT_MESSAGE_ID_KEYIDX)
+        {
+            if !transaction_id.is_empty() {
+                message_ext.set_transaction_id(transaction_id.as_str());
+            }
+        }
+        let group = match message_ext.get_property(MessageConst::PROPERTY_PRODUCER_GROUP) {
+            Some(group) => group,
+            None => {
+                warn!("checkTransactionState, pick producer group failed");
+                return Ok(None);
+            }
+        };
+        let client_instance = self
+            .client_instance
+            .read()
+            .as_ref()
+            .and_then(|client_instance| client_instance.upgrade());
+        match client_instance {
+            Some(client_instance) => {
+                let addr = channel.remote_address().to_string();
+                if !client_instance
+                    .check_transaction_state(
+                        group.as_str(),
+                        addr.as_str(),
+                        &message_ext,
+                        &request_header,
+                    )
+                    .await
+                {
+                    debug!(
+                        "checkTransactionState, pick producer by group[{}] failed",
+                        group
+                    );
+                }
+            }
+            None => {
+                warn!("checkTransactionState, client instance is not available");
+            }
+        }
+        Ok(None)
+    }
+
     async fn receive_reply_message(
         &mut self,
         ctx: ConnectionHandlerContext,
@@ -77,10 +163,15 @@ impl ClientRemotingProcessor {
         msg.queue_id = request_header.queue_id;
         msg.store_timestamp = request_header.store_timestamp;
         if !request_header.born_host.is_empty() {
-            match request_header.born_host.parse::<SocketAddr>() {
-                Ok(value) => msg.born_host = value,
-                Err(_) => {
-                    warn!("parse born_host failed: {}", request_header.store_host);
+            match NetworkUtil::string_to_socket_address(&request_header.born_host) {
+                Some(value) => {
+                    msg.born_host = value;
+                    if value.is_ipv6() {
+                        msg.with_born_host_v6_flag();
+                    }
+                }
+                None => {
+                    warn!("parse born_host failed: {}", request_header.born_host);
                     return Ok(Some(
                         response
                             .set_code(ResponseCode::SystemError)
@@ -90,9 +181,14 @@ impl ClientRemotingProcessor {
             }
         }
         if !request_header.store_host.is_empty() {
-            match request_header.store_host.parse::<SocketAddr>() {
-                Ok(value) => msg.store_host = value,
-                Err(_) => {
+            match NetworkUtil::string_to_socket_address(&request_header.store_host) {
+                Some(value) => {
+                    msg.store_host = value;
+                    if value.is_ipv6() {
+                        msg.with_store_host_v6_flag();
+                    }
+                }
+                None => {
                     warn!("parse store_host failed: {}", request_header.store_host);
                     return Ok(Some(
                         response
@@ -14,26 +14,52 @@
  * See the License for the specific language governing permissions and
  * limitations under the License.
  */
+use std::collections::HashMap;
+use std::future::Future;
+
+use rocketmq_common::common::boundary_type::BoundaryType;
 use rocketmq_common::common::message::message_queue::MessageQueue;
+use rocketmq_common::common::mix_all;
+use rocketmq_common::common::offset::QueueOffset;
+use rocketmq_common::common::topic::TopicValidator;
 use rocketmq_common::ArcRefCellWrapper;
+use rocketmq_common::WeakCellWrapper;
+use rocketmq_remoting::protocol::body::get_consumer_listby_group_response_body::GetConsumerListByGroupResponseBody;
 use rocketmq_remoting::protocol::namespace_util::NamespaceUtil;
 
 use crate::base::client_config::ClientConfig;
+use crate::common::client_error_code::ClientErrorCode;
+use crate::error::MQClientError;
 use crate::error::MQClientError::MQClientException;
 use crate::factory::mq_client_instance;
+use crate::factory::mq_client_instance::MQClientInstance;
 use crate::implementation::mq_client_api_impl::MQClientAPIImpl;
 use crate::Result;
 
 pub struct MQAdminImpl {
     timeout_millis: u64,
+    client: Option<WeakCellWrapper<MQClientInstance>>,
 }
 
 impl MQAdminImpl {
     pub fn new() -> Self {
         MQAdminImpl {
             timeout_millis: 60000,
+            client: None,
         }
     }
+
+    pub fn set_client(&mut self, client: WeakCellWrapper<MQClientInstance>) {
+        self.client = Some(client);
+    }
+
+    pub fn timeout_millis(&self) -> u64 {
+        self.timeout_millis
+    }
+
+    pub fn set_timeout_millis(&mut self, timeout_millis: u64) {
+        self.timeout_millis = timeout_millis;
+    }
 }
 
 impl MQAdminImpl {
@@ -84,11 +110,269 @@ impl MQAdminImpl {
             }
         }
         Err(MQClientException(
-            -1,
+            ClientErrorCode::NOT_FOUND_TOPIC_EXCEPTION,
             format!(
                 "Unknow why, Can not find Message Queue for this topic, {}",
                 topic
             ),
         ))
     }
+
+    /// Searches the offset of the first message in `mq` stored at or after `timestamp`.
+    #[inline]
+    pub async fn search_offset(
+        &mut self,
+        mq: &MessageQueue,
+        timestamp: u64,
+    ) -> Result<QueueOffset> {
+        self.search_offset_with_boundary_type(mq, timestamp, BoundaryType::Lower)
+            .await
+    }
+
+    pub async fn search_offset_with_boundary_type(
+        &mut self,
+        mq: &MessageQueue,
+        timestamp: u64,
+        boundary_type: BoundaryType,
+    ) -> Result<QueueOffset> {
+        let (client, broker_addr) = self.find_broker_addr(mq).await?;
+        client
+            .mq_client_api_impl
+            .mut_from_ref()
+            .search_offset(
+                broker_addr.as_str(),
+                mq,
+                timestamp as i64,
+                boundary_type,
+                self.timeout_millis,
+            )
+            .await
+            .map_err(|err| Self::invoke_broker_error(broker_addr.as_str(), err))
+    }
+
+    pub async fn max_offset(&mut self, mq: &MessageQueue) -> Result<QueueOffset> {
+        let (client, broker_addr) = self.find_broker_addr(mq).await?;
+        client
+            .mq_client_api_impl
+            .mut_from_ref()
+            .get_max_offset(broker_addr.as_str(), mq, self.timeout_millis)
+            .await
+            .map_err(|err| Self::invoke_broker_error(broker_addr.as_str(), err))
+    }
+
+    pub async fn min_offset(&mut self, mq: &MessageQueue) -> Result<QueueOffset> {
+        let (client, broker_addr) = self.find_broker_addr(mq).await?;
+        client
+            .mq_client_api_impl
+            .mut_from_ref()
+            .get_min_offset(broker_addr.as_str(), mq, self.timeout_millis)
+            .await
+            .map_err(|err| Self::invoke_broker_error(broker_addr.as_str(), err))
+    }
+
+    pub async fn earliest_msg_store_time(&mut self, mq: &MessageQueue) -> Result<i64> {
+        let (client, broker_addr) = self.find_broker_addr(mq).await?;
+        client
+            .mq_client_api_impl
+            .mut_from_ref()
+            .get_earliest_msg_storetime(broker_addr.as_str(), mq, self.timeout_millis)
+            .await
+            .map_err(|err| Self::invoke_broker_error(broker_addr.as_str(), err))
+    }
+
+    /// Changes the queue nums of an existing `topic` on every master broker hosting it, keeping
+    /// the rest of its config. The route is refreshed afterwards so this client sees the new
+    /// queues at once, others within one name server poll interval.
+    ///
+    /// Brokers refuse to shrink the read queues over unconsumed messages unless `force` is set.
+    pub async fn update_topic_queue_nums(
+        &mut self,
+        topic: &str,
+        read_queue_nums: u32,
+        write_queue_nums: u32,
+        force: bool,
+    ) -> Result<()> {
+        let Some(mut client) = self.client.as_ref().and_then(|client| client.upgrade()) else {
+            return Err(MQClientError::IllegalState(
+                "The MQClientInstance of MQAdminImpl is not available".to_string(),
+            ));
+        };
+        let Some(topic_route_data) = client
+            .mq_client_api_impl
+            .get_topic_route_info_from_name_server_detail(topic, self.timeout_millis, true)
+            .await?
+        else {
+            return Err(MQClientException(
+                ClientErrorCode::NOT_FOUND_TOPIC_EXCEPTION,
+                format!("The topic[{}] not exist", topic),
+            ));
+        };
+        for broker_data in topic_route_data.broker_datas.iter() {
+            let Some(broker_addr) = broker_data
+                .broker_addrs()
+                .get(&mix_all::MASTER_BROKER_ADDR_ID)
+            else {
+                continue;
+            };
+            let mq_client_api_impl = client.mq_client_api_impl.mut_from_ref();
+            let mut topic_config = mq_client_api_impl
+                .get_topic_config(broker_addr, topic, self.timeout_millis)
+                .await
+                .map_err(|err| Self::invoke_broker_error(broker_addr, err))?;
+            topic_config.read_queue_nums = read_queue_nums;
+            topic_config.write_queue_nums = write_queue_nums;
+            mq_client_api_impl
+                .create_topic(
+                    broker_addr,
+                    TopicValidator::AUTO_CREATE_TOPIC_KEY_TOPIC,
+                    &topic_config,
+                    force,
+                    self.timeout_millis,
+                )
+                .await
+                .map_err(|err| Self::invoke_broker_error(broker_addr, err))?;
+        }
+        client
+            .update_topic_route_info_from_name_server_topic(topic)
+            .await;
+        Ok(())
+    }
+
+    /// Online and recently offline consumers of `consumer_group` as seen by the broker at
+    /// `broker_addr`, to find out why the group rebalances to nothing.
+    pub async fn query_consumer_offline_history(
+        &mut self,
+        broker_addr: &str,
+        consumer_group: &str,
+    ) -> Result<GetConsumerListByGroupResponseBody> {
+        let Some(client) = self.client.as_ref().and_then(|client| client.upgrade()) else {
+            return Err(MQClientError::IllegalState(
+                "The MQClientInstance of MQAdminImpl is not available".to_string(),
+            ));
+        };
+        client
+            .mq_client_api_impl
+            .mut_from_ref()
+            .get_consumer_offline_history(broker_addr, consumer_group, self.timeout_millis)
+            .await
+            .map_err(|err| Self::invoke_broker_error(broker_addr, err))
+    }
+
+    /// Runs `f` against the master of every broker in `cluster_name` one after another and
+    /// returns its outcome per master address, so one unreachable broker does not hide the
+    /// results of the others.
+    pub async fn for_each_broker_in_cluster<T, F, Fut>(
+        &mut self,
+        cluster_name: &str,
+        mut f: F,
+    ) -> Result<Vec<(String, Result<T>)>>
+    where
+        F: FnMut(ArcRefCellWrapper<MQClientAPIImpl>, String) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let Some(client) = self.client.as_ref().and_then(|client| client.upgrade()) else {
+            return Err(MQClientError::IllegalState(
+                "The MQClientInstance of MQAdminImpl is not available".to_string(),
+            ));
+        };
+        let cluster_info = client
+            .mq_client_api_impl
+            .get_broker_cluster_info(self.timeout_millis)
+            .await?;
+        if cluster_info.broker_names_of_cluster(cluster_name).is_none() {
+            return Err(MQClientException(
+                ClientErrorCode::BROKER_NOT_EXIST_EXCEPTION,
+                format!("The cluster[{}] not exist", cluster_name),
+            ));
+        }
+        let mut results = Vec::new();
+        for broker_addr in cluster_info.master_addrs_of_cluster(cluster_name) {
+            let result = f(client.mq_client_api_impl.clone(), broker_addr.clone())
+                .await
+                .map_err(|err| Self::invoke_broker_error(broker_addr.as_str(), err));
+            results.push((broker_addr, result));
+        }
+        Ok(results)
+    }
+
+    /// Updates the config of every master in `cluster_name` with `properties`.
+    pub async fn update_broker_config_in_cluster(
+        &mut self,
+        cluster_name: &str,
+        properties: HashMap<String, String>,
+    ) -> Result<Vec<(String, Result<()>)>> {
+        let timeout_millis = self.timeout_millis;
+        self.for_each_broker_in_cluster(cluster_name, |mut mq_client_api_impl, broker_addr| {
+            let properties = properties.clone();
+            async move {
+                mq_client_api_impl
+                    .update_broker_config(broker_addr.as_str(), &properties, timeout_millis)
+                    .await
+            }
+        })
+        .await
+    }
+
+    /// [`query_consumer_offline_history`](Self::query_consumer_offline_history) against every
+    /// master in `cluster_name`.
+    pub async fn query_consumer_offline_history_in_cluster(
+        &mut self,
+        cluster_name: &str,
+        consumer_group: &str,
+    ) -> Result<Vec<(String, Result<GetConsumerListByGroupResponseBody>)>> {
+        let timeout_millis = self.timeout_millis;
+        self.for_each_broker_in_cluster(cluster_name, |mut mq_client_api_impl, broker_addr| {
+            let consumer_group = consumer_group.to_string();
+            async move {
+                mq_client_api_impl
+                    .get_consumer_offline_history(
+                        broker_addr.as_str(),
+                        consumer_group.as_str(),
+                        timeout_millis,
+                    )
+                    .await
+            }
+        })
+        .await
+    }
+
+    /// Finds the master address of the broker hosting `mq`, the topic route is refreshed
+    /// from the name server once if the broker is unknown.
+    async fn find_broker_addr(
+        &self,
+        mq: &MessageQueue,
+    ) -> Result<(ArcRefCellWrapper<MQClientInstance>, String)> {
+        let Some(mut client) = self.client.as_ref().and_then(|client| client.upgrade()) else {
+            return Err(MQClientError::IllegalState(
+                "The MQClientInstance of MQAdminImpl is not available".to_string(),
+            ));
+        };
+        let broker_name = client.get_broker_name_from_message_queue(mq).await;
+        let mut broker_addr = client
+            .find_broker_address_in_publish(broker_name.as_str())
+            .await;
+        if broker_addr.is_none() {
+            client
+                .update_topic_route_info_from_name_server_topic(mq.get_topic())
+                .await;
+            let broker_name = client.get_broker_name_from_message_queue(mq).await;
+            broker_addr = client
+                .find_broker_address_in_publish(broker_name.as_str())
+                .await;
+        }
+        match broker_addr {
+            Some(broker_addr) => Ok((client, broker_addr)),
+            None => Err(MQClientException(
+                ClientErrorCode::BROKER_NOT_EXIST_EXCEPTION,
+                format!("The broker[{}] not exist", mq.get_broker_name()),
+            )),
+        }
+    }
+
+    fn invoke_broker_error(
+        broker_addr: &str,
+        err: crate::error::MQClientError,
+    ) -> crate::error::MQClientError {
+        crate::error::MQClientError::InvokeBrokerException(broker_addr.to_string(), Box::new(err))
+    }
 }
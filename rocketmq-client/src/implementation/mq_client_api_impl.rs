@@ -14,14 +14,18 @@
  * See the License for the specific language governing permissions and
  * limitations under the License.
  */
+use std::collections::HashMap;
 use std::sync::atomic::AtomicU32;
 use std::sync::Arc;
 use std::time::Instant;
 
 use bytes::Bytes;
 use lazy_static::lazy_static;
+use rocketmq_common::common::boundary_type::BoundaryType;
+use rocketmq_common::common::config::TopicConfig;
 use rocketmq_common::common::message::message_batch::MessageBatch;
 use rocketmq_common::common::message::message_client_id_setter::MessageClientIDSetter;
+use rocketmq_common::common::message::message_ext::MessageExt;
 use rocketmq_common::common::message::message_queue::MessageQueue;
 use rocketmq_common::common::message::MessageConst;
 use rocketmq_common::common::message::MessageTrait;
@@ -29,25 +33,55 @@ use rocketmq_common::common::mix_all;
 use rocketmq_common::common::namesrv::default_top_addressing::DefaultTopAddressing;
 use rocketmq_common::common::namesrv::name_server_update_callback::NameServerUpdateCallback;
 use rocketmq_common::common::namesrv::top_addressing::TopAddressing;
+use rocketmq_common::common::offset::QueueOffset;
 use rocketmq_common::common::topic::TopicValidator;
+use rocketmq_common::common::TopicFilterType;
+use rocketmq_common::utils::serde_json_utils::SerdeJsonUtils;
 use rocketmq_common::ArcRefCellWrapper;
+use rocketmq_common::WeakCellWrapper;
 use rocketmq_remoting::base::connection_net_event::ConnectionNetEvent;
 use rocketmq_remoting::clients::rocketmq_default_impl::RocketmqDefaultClient;
 use rocketmq_remoting::clients::RemotingClient;
 use rocketmq_remoting::code::request_code::RequestCode;
 use rocketmq_remoting::code::response_code::ResponseCode;
+use rocketmq_remoting::protocol::body::broker_body::cluster_info::ClusterInfo;
+use rocketmq_remoting::protocol::body::get_consumer_listby_group_response_body::GetConsumerListByGroupResponseBody;
+use rocketmq_remoting::protocol::body::topic::topic_list::TopicList;
+use rocketmq_remoting::protocol::command_custom_header::CommandCustomHeader;
 use rocketmq_remoting::protocol::header::client_request_header::GetRouteInfoRequestHeader;
+use rocketmq_remoting::protocol::header::consumer_send_msg_back_request_header::ConsumerSendMsgBackRequestHeader;
+use rocketmq_remoting::protocol::header::create_topic_request_header::CreateTopicRequestHeader;
+use rocketmq_remoting::protocol::header::end_transaction_request_header::EndTransactionRequestHeader;
+use rocketmq_remoting::protocol::header::get_consumer_listby_group_request_header::GetConsumerListByGroupRequestHeader;
+use rocketmq_remoting::protocol::header::get_earliest_msg_storetime_request_header::GetEarliestMsgStoretimeRequestHeader;
+use rocketmq_remoting::protocol::header::get_earliest_msg_storetime_response_header::GetEarliestMsgStoretimeResponseHeader;
+use rocketmq_remoting::protocol::header::get_max_offset_request_header::GetMaxOffsetRequestHeader;
+use rocketmq_remoting::protocol::header::get_max_offset_response_header::GetMaxOffsetResponseHeader;
+use rocketmq_remoting::protocol::header::get_min_offset_request_header::GetMinOffsetRequestHeader;
+use rocketmq_remoting::protocol::header::get_min_offset_response_header::GetMinOffsetResponseHeader;
+use rocketmq_remoting::protocol::header::get_topic_config_request_header::GetTopicConfigRequestHeader;
 use rocketmq_remoting::protocol::header::heartbeat_request_header::HeartbeatRequestHeader;
 use rocketmq_remoting::protocol::header::message_operation_header::send_message_request_header::SendMessageRequestHeader;
 use rocketmq_remoting::protocol::header::message_operation_header::send_message_request_header_v2::SendMessageRequestHeaderV2;
 use rocketmq_remoting::protocol::header::message_operation_header::send_message_response_header::SendMessageResponseHeader;
+use rocketmq_remoting::protocol::header::namesrv::topic_operation_header::GetTopicListRequestHeader;
+use rocketmq_remoting::protocol::header::namesrv::topic_operation_header::GetTopicsByClusterRequestHeader;
+use rocketmq_remoting::protocol::header::namesrv::topic_operation_header::TopicRequestHeader as OffsetTopicRequestHeader;
+use rocketmq_remoting::protocol::header::query_consumer_offset_request_header::QueryConsumerOffsetRequestHeader;
+use rocketmq_remoting::protocol::header::query_consumer_offset_response_header::QueryConsumerOffsetResponseHeader;
+use rocketmq_remoting::protocol::header::search_offset_request_header::SearchOffsetRequestHeader;
+use rocketmq_remoting::protocol::header::search_offset_response_header::SearchOffsetResponseHeader;
 use rocketmq_remoting::protocol::heartbeat::heartbeat_data::HeartbeatData;
 use rocketmq_remoting::protocol::namespace_util::NamespaceUtil;
 use rocketmq_remoting::protocol::remoting_command::RemotingCommand;
 use rocketmq_remoting::protocol::route::topic_route_data::TopicRouteData;
+use rocketmq_remoting::protocol::static_topic::topic_config_and_queue_mapping::TopicConfigAndQueueMapping;
+use rocketmq_remoting::protocol::DataVersion;
 use rocketmq_remoting::protocol::RemotingDeserializable;
 use rocketmq_remoting::protocol::RemotingSerializable;
 use rocketmq_remoting::remoting::RemotingService;
+use rocketmq_remoting::rpc::rpc_request_header::RpcRequestHeader;
+use rocketmq_remoting::rpc::topic_request_header::TopicRequestHeader;
 use rocketmq_remoting::runtime::config::client_config::TokioClientConfig;
 use rocketmq_remoting::runtime::RPCHook;
 use tracing::error;
@@ -76,7 +110,7 @@ lazy_static! {
 pub struct MQClientAPIImpl {
     remoting_client: RocketmqDefaultClient<ClientRemotingProcessor>,
     top_addressing: Box<dyn TopAddressing>,
-    // client_remoting_processor: ClientRemotingProcessor,
+    client_remoting_processor: ClientRemotingProcessor,
     name_srv_addr: Option<String>,
     client_config: ClientConfig,
 }
@@ -95,8 +129,11 @@ impl MQClientAPIImpl {
         client_config: ClientConfig,
         tx: Option<tokio::sync::broadcast::Sender<ConnectionNetEvent>>,
     ) -> Self {
-        let mut default_client =
-            RocketmqDefaultClient::new_with_cl(tokio_client_config, client_remoting_processor, tx);
+        let mut default_client = RocketmqDefaultClient::new_with_cl(
+            tokio_client_config,
+            client_remoting_processor.clone(),
+            tx,
+        );
         if let Some(hook) = rpc_hook {
             default_client.register_rpc_hook(hook);
         }
@@ -107,7 +144,7 @@ impl MQClientAPIImpl {
                 mix_all::get_ws_addr(),
                 client_config.unit_name.clone(),
             )),
-            //client_remoting_processor,
+            client_remoting_processor,
             name_srv_addr: None,
             client_config,
         }
@@ -117,6 +154,10 @@ impl MQClientAPIImpl {
         self.remoting_client.start().await;
     }
 
+    pub fn shutdown(&mut self) {
+        self.remoting_client.shutdown();
+    }
+
     pub async fn fetch_name_server_addr(&mut self) -> Option<String> {
         let addrs = self.top_addressing.fetch_ns_addr();
         if addrs.is_some() && !addrs.as_ref().unwrap().is_empty() {
@@ -178,9 +219,35 @@ impl MQClientAPIImpl {
         timeout_millis: u64,
         allow_topic_not_exist: bool,
     ) -> Result<Option<TopicRouteData>> {
+        self.query_topic_route_info(topic, timeout_millis, allow_topic_not_exist, None)
+            .await
+            .map(|route| route.map(|(topic_route_data, _)| topic_route_data))
+    }
+
+    /// Queries the route of `topic` together with its version. When `topic_route_version` is
+    /// still the current version on the name server, no route is transferred and `Ok(None)` is
+    /// returned.
+    pub async fn get_topic_route_info_from_name_server_if_changed(
+        &self,
+        topic: &str,
+        timeout_millis: u64,
+        topic_route_version: Option<String>,
+    ) -> Result<Option<(TopicRouteData, Option<String>)>> {
+        self.query_topic_route_info(topic, timeout_millis, true, topic_route_version)
+            .await
+    }
+
+    async fn query_topic_route_info(
+        &self,
+        topic: &str,
+        timeout_millis: u64,
+        allow_topic_not_exist: bool,
+        topic_route_version: Option<String>,
+    ) -> Result<Option<(TopicRouteData, Option<String>)>> {
         let request_header = GetRouteInfoRequestHeader {
             topic: topic.to_string(),
             accept_standard_json_only: None,
+            topic_route_version,
             topic_request_header: None,
         };
         let request = RemotingCommand::create_request_command(
@@ -202,10 +269,17 @@ impl MQClientAPIImpl {
                             let route_data =
                                 TopicRouteData::decode(body.as_ref().unwrap().as_ref());
                             if let Ok(data) = route_data {
-                                return Ok(Some(data));
+                                let version = result
+                                    .ext_fields()
+                                    .and_then(|fields| {
+                                        fields.get(GetRouteInfoRequestHeader::TOPIC_ROUTE_VERSION)
+                                    })
+                                    .cloned();
+                                return Ok(Some((data, version)));
                             }
                         }
                     }
+                    ResponseCode::TopicRouteNotModified => return Ok(None),
                     ResponseCode::TopicNotExist => {
                         if allow_topic_not_exist {
                             warn!(
@@ -230,10 +304,143 @@ impl MQClientAPIImpl {
         }
     }
 
-    pub fn get_name_server_address_list(&self) -> &[String] {
+    pub fn get_name_server_address_list(&self) -> Vec<String> {
         self.remoting_client.get_name_server_address_list()
     }
 
+    /// The brokers known to the name server, by broker name and by cluster.
+    pub async fn get_broker_cluster_info(&self, timeout_millis: u64) -> Result<ClusterInfo> {
+        let request = RemotingCommand::create_remoting_command(RequestCode::GetBrokerClusterInfo);
+        let response = self
+            .remoting_client
+            .invoke_async(None, request, timeout_millis)
+            .await?;
+        if ResponseCode::from(response.code()) == ResponseCode::Success {
+            if let Some(Ok(cluster_info)) = response
+                .body()
+                .as_ref()
+                .map(|body| ClusterInfo::decode(body.as_ref()))
+            {
+                return Ok(cluster_info);
+            }
+        }
+        Err(MQClientError::MQClientException(
+            response.code(),
+            response.remark().cloned().unwrap_or_default(),
+        ))
+    }
+
+    /// All the topics known to the name server.
+    pub async fn get_topic_list_from_name_server(&self, timeout_millis: u64) -> Result<TopicList> {
+        self.get_topic_list(
+            RequestCode::GetAllTopicListFromNameserver,
+            GetTopicListRequestHeader::new(true),
+            timeout_millis,
+        )
+        .await
+    }
+
+    /// Topics with queues on a broker of `cluster`.
+    pub async fn get_topics_by_cluster(
+        &self,
+        cluster: &str,
+        timeout_millis: u64,
+    ) -> Result<TopicList> {
+        let request_header = GetTopicsByClusterRequestHeader {
+            cluster: cluster.to_string(),
+            compressed: Some(true),
+        };
+        self.get_topic_list(
+            RequestCode::GetTopicsByCluster,
+            request_header,
+            timeout_millis,
+        )
+        .await
+    }
+
+    /// Cluster and broker names, which are topics as well, and the address of one broker.
+    pub async fn get_system_topic_list(&self, timeout_millis: u64) -> Result<TopicList> {
+        self.get_topic_list(
+            RequestCode::GetSystemTopicListFromNs,
+            GetTopicListRequestHeader::new(true),
+            timeout_millis,
+        )
+        .await
+    }
+
+    pub async fn get_unit_topic_list(
+        &self,
+        contain_retry: bool,
+        timeout_millis: u64,
+    ) -> Result<TopicList> {
+        self.get_topic_list(
+            RequestCode::GetUnitTopicList,
+            GetTopicListRequestHeader::new(true),
+            timeout_millis,
+        )
+        .await
+        .map(|topic_list| filter_retry_topics(topic_list, contain_retry))
+    }
+
+    pub async fn get_has_unit_sub_topic_list(
+        &self,
+        contain_retry: bool,
+        timeout_millis: u64,
+    ) -> Result<TopicList> {
+        self.get_topic_list(
+            RequestCode::GetHasUnitSubTopicList,
+            GetTopicListRequestHeader::new(true),
+            timeout_millis,
+        )
+        .await
+        .map(|topic_list| filter_retry_topics(topic_list, contain_retry))
+    }
+
+    pub async fn get_has_unit_sub_un_unit_topic_list(
+        &self,
+        contain_retry: bool,
+        timeout_millis: u64,
+    ) -> Result<TopicList> {
+        self.get_topic_list(
+            RequestCode::GetHasUnitSubUnunitTopicList,
+            GetTopicListRequestHeader::new(true),
+            timeout_millis,
+        )
+        .await
+        .map(|topic_list| filter_retry_topics(topic_list, contain_retry))
+    }
+
+    async fn get_topic_list(
+        &self,
+        request_code: RequestCode,
+        request_header: impl CommandCustomHeader + Send + Sync + 'static,
+        timeout_millis: u64,
+    ) -> Result<TopicList> {
+        let request = RemotingCommand::create_request_command(request_code, request_header);
+        let response = self
+            .remoting_client
+            .invoke_async(None, request, timeout_millis)
+            .await?;
+        if ResponseCode::from(response.code()) == ResponseCode::Success {
+            // name servers which do not support compression ignore the request for it
+            let compressed = response
+                .ext_fields()
+                .and_then(|fields| fields.get(GetTopicListRequestHeader::COMPRESSED))
+                .is_some_and(|compressed| compressed == "true");
+            if let Some(topic_list) = response
+                .body()
+                .as_ref()
+                .and_then(|body| TopicList::decode_body(body, compressed))
+            {
+                return Ok(topic_list);
+            }
+        }
+        Err(MQClientError::MQClientException(
+            response.code(),
+            response.remark().cloned().unwrap_or_default(),
+        ))
+    }
+
     pub async fn send_message<T>(
         &mut self,
         addr: &str,
@@ -541,7 +748,7 @@ impl MQClientAPIImpl {
             transaction_id: response_header.transaction_id().map(|s| s.to_string()),
             region_id: Some(region_id),
             trace_on,
-            ..Default::default()
+            raw_resp_body: response.body().clone(),
         };
 
         Ok(send_result)
@@ -610,12 +817,14 @@ impl MQClientAPIImpl {
         }
     }
 
+    /// Sends the heartbeat and returns the broker version together with the subscription data
+    /// version the broker holds for each consumer group of the heartbeat.
     pub async fn send_heartbeat(
         &mut self,
         addr: &str,
         heartbeat_data: &HeartbeatData,
         timeout_millis: u64,
-    ) -> Result<i32> {
+    ) -> Result<(i32, HashMap<String /* group */, DataVersion>)> {
         let request = RemotingCommand::create_request_command(
             RequestCode::HeartBeat,
             HeartbeatRequestHeader::default(),
@@ -627,7 +836,12 @@ impl MQClientAPIImpl {
             .invoke_async(Some(addr.to_string()), request, timeout_millis)
             .await?;
         if ResponseCode::from(response.code()) == ResponseCode::Success {
-            return Ok(response.version());
+            let subscription_data_versions = response
+                .ext_fields()
+                .and_then(|fields| fields.get(mix_all::SUBSCRIPTION_DATA_VERSION))
+                .and_then(|versions| SerdeJsonUtils::from_json_str(versions).ok())
+                .unwrap_or_default();
+            return Ok((response.version(), subscription_data_versions));
         }
         Err(MQClientError::MQBrokerException(
             response.code(),
@@ -635,4 +849,554 @@ impl MQClientAPIImpl {
             addr.to_string(),
         ))
     }
+
+    pub async fn get_max_offset(
+        &mut self,
+        addr: &str,
+        message_queue: &MessageQueue,
+        timeout_millis: u64,
+    ) -> Result<QueueOffset> {
+        let request_header = GetMaxOffsetRequestHeader {
+            topic: message_queue.get_topic().to_string(),
+            queue_id: message_queue.get_queue_id(),
+            committed: true,
+            topic_request_header: Some(Self::broker_topic_request_header(message_queue)),
+        };
+        let request =
+            RemotingCommand::create_request_command(RequestCode::GetMaxOffset, request_header);
+        let response = self.invoke_broker(addr, request, timeout_millis).await?;
+        if ResponseCode::from(response.code()) == ResponseCode::Success {
+            if let Some(response_header) =
+                response.decode_command_custom_header::<GetMaxOffsetResponseHeader>()
+            {
+                return Ok(QueueOffset::new(response_header.offset));
+            }
+        }
+        Err(MQClientError::MQBrokerException(
+            response.code(),
+            response.remark().cloned().unwrap_or_default(),
+            addr.to_string(),
+        ))
+    }
+
+    pub async fn get_min_offset(
+        &mut self,
+        addr: &str,
+        message_queue: &MessageQueue,
+        timeout_millis: u64,
+    ) -> Result<QueueOffset> {
+        let request_header = GetMinOffsetRequestHeader {
+            topic: message_queue.get_topic().to_string(),
+            queue_id: message_queue.get_queue_id(),
+            topic_request_header: Some(Self::broker_topic_request_header(message_queue)),
+        };
+        let request =
+            RemotingCommand::create_request_command(RequestCode::GetMinOffset, request_header);
+        let response = self.invoke_broker(addr, request, timeout_millis).await?;
+        if ResponseCode::from(response.code()) == ResponseCode::Success {
+            if let Some(response_header) =
+                response.decode_command_custom_header::<GetMinOffsetResponseHeader>()
+            {
+                return Ok(QueueOffset::new(response_header.offset));
+            }
+        }
+        Err(MQClientError::MQBrokerException(
+            response.code(),
+            response.remark().cloned().unwrap_or_default(),
+            addr.to_string(),
+        ))
+    }
+
+    pub async fn get_topic_config(
+        &mut self,
+        addr: &str,
+        topic: &str,
+        timeout_millis: u64,
+    ) -> Result<TopicConfig> {
+        self.get_topic_config_and_queue_mapping(addr, topic, timeout_millis)
+            .await
+            .map(|topic_config_and_queue_mapping| topic_config_and_queue_mapping.topic_config)
+    }
+
+    /// Topic config of `topic` on the broker at `addr`, along with the logical queues the
+    /// broker hosts when it is a static topic.
+    pub async fn get_topic_config_and_queue_mapping(
+        &mut self,
+        addr: &str,
+        topic: &str,
+        timeout_millis: u64,
+    ) -> Result<TopicConfigAndQueueMapping> {
+        let request_header = GetTopicConfigRequestHeader {
+            topic: topic.to_string(),
+            topic_request_header: None,
+        };
+        let request =
+            RemotingCommand::create_request_command(RequestCode::GetTopicConfig, request_header);
+        let response = self.invoke_broker(addr, request, timeout_millis).await?;
+        if ResponseCode::from(response.code()) == ResponseCode::Success {
+            if let Some(Ok(topic_config_and_queue_mapping)) = response
+                .body()
+                .as_ref()
+                .map(|body| TopicConfigAndQueueMapping::decode(body.as_ref()))
+            {
+                return Ok(topic_config_and_queue_mapping);
+            }
+        }
+        Err(MQClientError::MQBrokerException(
+            response.code(),
+            response.remark().cloned().unwrap_or_default(),
+            addr.to_string(),
+        ))
+    }
+
+    /// Client ids of the consumers of `consumer_group` connected to the broker at `addr`.
+    pub async fn get_consumer_id_list_by_group(
+        &mut self,
+        addr: &str,
+        consumer_group: &str,
+        timeout_millis: u64,
+    ) -> Result<Vec<String>> {
+        let request_header = GetConsumerListByGroupRequestHeader {
+            consumer_group: consumer_group.to_string(),
+            rpc: None,
+        };
+        let request = RemotingCommand::create_request_command(
+            RequestCode::GetConsumerListByGroup,
+            request_header,
+        );
+        let response = self.invoke_broker(addr, request, timeout_millis).await?;
+        if ResponseCode::from(response.code()) == ResponseCode::Success {
+            if let Some(Ok(body)) = response
+                .body()
+                .as_ref()
+                .map(|body| GetConsumerListByGroupResponseBody::decode(body.as_ref()))
+            {
+                return Ok(body.consumer_id_list);
+            }
+        }
+        Err(MQClientError::MQBrokerException(
+            response.code(),
+            response.remark().cloned().unwrap_or_default(),
+            addr.to_string(),
+        ))
+    }
+
+    /// Online consumers of `consumer_group` on the broker at `addr`, with the ones that went
+    /// offline recently.
+    pub async fn get_consumer_offline_history(
+        &mut self,
+        addr: &str,
+        consumer_group: &str,
+        timeout_millis: u64,
+    ) -> Result<GetConsumerListByGroupResponseBody> {
+        let request_header = GetConsumerListByGroupRequestHeader {
+            consumer_group: consumer_group.to_string(),
+            rpc: None,
+        };
+        let request = RemotingCommand::create_request_command(
+            RequestCode::GetConsumerOfflineHistory,
+            request_header,
+        );
+        let response = self.invoke_broker(addr, request, timeout_millis).await?;
+        if ResponseCode::from(response.code()) == ResponseCode::Success {
+            if let Some(Ok(body)) = response
+                .body()
+                .as_ref()
+                .map(|body| GetConsumerListByGroupResponseBody::decode(body.as_ref()))
+            {
+                return Ok(body);
+            }
+        }
+        Err(MQClientError::MQBrokerException(
+            response.code(),
+            response.remark().cloned().unwrap_or_default(),
+            addr.to_string(),
+        ))
+    }
+
+    /// Creates `topic_config` on the broker at `addr`, or updates it if the topic exists. The
+    /// broker refuses to shrink the read queues over unconsumed messages unless `force` is set.
+    pub async fn create_topic(
+        &mut self,
+        addr: &str,
+        default_topic: &str,
+        topic_config: &TopicConfig,
+        force: bool,
+        timeout_millis: u64,
+    ) -> Result<()> {
+        let request_header = CreateTopicRequestHeader {
+            topic: topic_config.topic_name.clone().unwrap_or_default(),
+            default_topic: default_topic.to_string(),
+            read_queue_nums: topic_config.read_queue_nums as i32,
+            write_queue_nums: topic_config.write_queue_nums as i32,
+            perm: topic_config.perm as i32,
+            topic_filter_type: match topic_config.topic_filter_type {
+                TopicFilterType::SingleTag => "SINGLE_TAG".to_string(),
+                TopicFilterType::MultiTag => "MULTI_TAG".to_string(),
+            },
+            topic_sys_flag: Some(topic_config.topic_sys_flag as i32),
+            order: topic_config.order,
+            attributes: None,
+            force: Some(force),
+            topic_request_header: None,
+        };
+        let request = RemotingCommand::create_request_command(
+            RequestCode::UpdateAndCreateTopic,
+            request_header,
+        );
+        let response = self.invoke_broker(addr, request, timeout_millis).await?;
+        if ResponseCode::from(response.code()) == ResponseCode::Success {
+            return Ok(());
+        }
+        Err(MQClientError::MQBrokerException(
+            response.code(),
+            response.remark().cloned().unwrap_or_default(),
+            addr.to_string(),
+        ))
+    }
+
+    /// Updates the config of the broker at `addr` with `properties`, keys not given keep their
+    /// value.
+    pub async fn update_broker_config(
+        &mut self,
+        addr: &str,
+        properties: &HashMap<String, String>,
+        timeout_millis: u64,
+    ) -> Result<()> {
+        let body = properties
+            .iter()
+            .map(|(key, value)| format!("{}={}\n", key, value))
+            .collect::<String>();
+        let request = RemotingCommand::create_remoting_command(RequestCode::UpdateBrokerConfig)
+            .set_body(Some(body));
+        let response = self.invoke_broker(addr, request, timeout_millis).await?;
+        if ResponseCode::from(response.code()) == ResponseCode::Success {
+            return Ok(());
+        }
+        Err(MQClientError::MQBrokerException(
+            response.code(),
+            response.remark().cloned().unwrap_or_default(),
+            addr.to_string(),
+        ))
+    }
+
+    /// Committed offset of `consumer_group` on `message_queue`. A queue the group has never
+    /// committed on yields `OffsetNotFoundException`.
+    pub async fn query_consumer_offset(
+        &mut self,
+        addr: &str,
+        consumer_group: &str,
+        message_queue: &MessageQueue,
+        timeout_millis: u64,
+    ) -> Result<QueueOffset> {
+        let request_header = QueryConsumerOffsetRequestHeader {
+            consumer_group: consumer_group.to_string(),
+            topic: message_queue.get_topic().to_string(),
+            queue_id: message_queue.get_queue_id(),
+            set_zero_if_not_found: None,
+            topic_request_header: Some(OffsetTopicRequestHeader {
+                lo: None,
+                rpc: Some(RpcRequestHeader {
+                    broker_name: Some(message_queue.get_broker_name().to_string()),
+                    ..Default::default()
+                }),
+            }),
+        };
+        let request = RemotingCommand::create_request_command(
+            RequestCode::QueryConsumerOffset,
+            request_header,
+        );
+        let response = self.invoke_broker(addr, request, timeout_millis).await?;
+        match ResponseCode::from(response.code()) {
+            ResponseCode::Success => {
+                if let Some(offset) = response
+                    .decode_command_custom_header::<QueryConsumerOffsetResponseHeader>()
+                    .and_then(|response_header| response_header.offset)
+                {
+                    return Ok(QueueOffset::new(offset));
+                }
+            }
+            ResponseCode::QueryNotFound => {
+                return Err(MQClientError::OffsetNotFoundException(
+                    response.code(),
+                    addr.to_string(),
+                    response.remark().cloned().unwrap_or_default(),
+                ));
+            }
+            _ => {}
+        }
+        Err(MQClientError::MQBrokerException(
+            response.code(),
+            response.remark().cloned().unwrap_or_default(),
+            addr.to_string(),
+        ))
+    }
+
+    pub async fn search_offset(
+        &mut self,
+        addr: &str,
+        message_queue: &MessageQueue,
+        timestamp: i64,
+        boundary_type: BoundaryType,
+        timeout_millis: u64,
+    ) -> Result<QueueOffset> {
+        let request_header = SearchOffsetRequestHeader {
+            topic: message_queue.get_topic().to_string(),
+            queue_id: message_queue.get_queue_id(),
+            timestamp,
+            boundary_type: Some(boundary_type),
+            topic_request_header: Some(Self::broker_topic_request_header(message_queue)),
+        };
+        let request = RemotingCommand::create_request_command(
+            RequestCode::SearchOffsetByTimestamp,
+            request_header,
+        );
+        let response = self.invoke_broker(addr, request, timeout_millis).await?;
+        if ResponseCode::from(response.code()) == ResponseCode::Success {
+            if let Some(response_header) =
+                response.decode_command_custom_header::<SearchOffsetResponseHeader>()
+            {
+                return Ok(QueueOffset::new(response_header.offset));
+            }
+        }
+        Err(MQClientError::MQBrokerException(
+            response.code(),
+            response.remark().cloned().unwrap_or_default(),
+            addr.to_string(),
+        ))
+    }
+
+    /// Sends a message the consumer could not consume back to the broker at `addr`, which
+    /// redelivers it through the retry topic of `consumer_group` after `delay_level`, or moves
+    /// it to the DLQ past `max_consume_retry_times`.
+    pub async fn consumer_send_message_back(
+        &mut self,
+        addr: &str,
+        msg: &MessageExt,
+        consumer_group: &str,
+        delay_level: i32,
+        timeout_millis: u64,
+        max_consume_retry_times: i32,
+    ) -> Result<()> {
+        let request_header = ConsumerSendMsgBackRequestHeader {
+            offset: msg.commit_log_offset,
+            group: consumer_group.to_string(),
+            delay_level,
+            origin_msg_id: Some(msg.msg_id.clone()),
+            origin_topic: Some(msg.get_topic().to_string()),
+            unit_mode: false,
+            max_reconsume_times: Some(max_consume_retry_times),
+        };
+        let request = RemotingCommand::create_request_command(
+            RequestCode::ConsumerSendMsgBack,
+            request_header,
+        );
+        let response = self.invoke_broker(addr, request, timeout_millis).await?;
+        if ResponseCode::from(response.code()) == ResponseCode::Success {
+            return Ok(());
+        }
+        Err(MQClientError::MQBrokerException(
+            response.code(),
+            response.remark().cloned().unwrap_or_default(),
+            addr.to_string(),
+        ))
+    }
+
+    pub async fn get_earliest_msg_storetime(
+        &mut self,
+        addr: &str,
+        message_queue: &MessageQueue,
+        timeout_millis: u64,
+    ) -> Result<i64> {
+        let request_header = GetEarliestMsgStoretimeRequestHeader {
+            topic: message_queue.get_topic().to_string(),
+            queue_id: message_queue.get_queue_id(),
+            topic_request_header: Some(Self::broker_topic_request_header(message_queue)),
+        };
+        let request = RemotingCommand::create_request_command(
+            RequestCode::GetEarliestMsgStoreTime,
+            request_header,
+        );
+        let response = self.invoke_broker(addr, request, timeout_millis).await?;
+        if ResponseCode::from(response.code()) == ResponseCode::Success {
+            if let Some(response_header) =
+                response.decode_command_custom_header::<GetEarliestMsgStoretimeResponseHeader>()
+            {
+                return Ok(response_header.timestamp);
+            }
+        }
+        Err(MQClientError::MQBrokerException(
+            response.code(),
+            response.remark().cloned().unwrap_or_default(),
+            addr.to_string(),
+        ))
+    }
+
+    fn broker_topic_request_header(message_queue: &MessageQueue) -> TopicRequestHeader {
+        TopicRequestHeader {
+            rpc_request_header: Some(RpcRequestHeader {
+                broker_name: Some(message_queue.get_broker_name().to_string()),
+                ..Default::default()
+            }),
+            lo: None,
+        }
+    }
+
+    pub async fn end_transaction_oneway(
+        &mut self,
+        addr: &str,
+        request_header: EndTransactionRequestHeader,
+        remark: Option<String>,
+        timeout_millis: u64,
+    ) {
+        let request =
+            RemotingCommand::create_request_command(RequestCode::EndTransaction, request_header)
+                .set_remark(remark);
+        self.remoting_client
+            .invoke_oneway(addr.to_string(), request, timeout_millis)
+            .await;
+    }
+
+    pub fn set_client_instance(&self, client_instance: WeakCellWrapper<MQClientInstance>) {
+        self.client_remoting_processor
+            .set_client_instance(client_instance);
+    }
+
+    async fn invoke_broker(
+        &mut self,
+        addr: &str,
+        request: RemotingCommand,
+        timeout_millis: u64,
+    ) -> Result<RemotingCommand> {
+        let addr = mix_all::broker_vip_channel(self.client_config.vip_channel_enabled, addr);
+        let request = request.set_language(self.client_config.language);
+        Ok(self
+            .remoting_client
+            .invoke_async(Some(addr), request, timeout_millis)
+            .await?)
+    }
+}
+
+fn filter_retry_topics(mut topic_list: TopicList, contain_retry: bool) -> TopicList {
+    if !contain_retry {
+        topic_list
+            .topic_list
+            .retain(|topic| !topic.starts_with(mix_all::RETRY_GROUP_TOPIC_PREFIX));
+    }
+    topic_list
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use rocketmq_remoting::net::channel::Channel;
+    use rocketmq_remoting::remoting_server::flow_control::RequestFlowController;
+    use rocketmq_remoting::runtime::connection_handler_context::ConnectionHandlerContext;
+    use rocketmq_remoting::runtime::processor::RequestProcessor;
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    /// Answers the offset requests the way a broker holding offsets 100..200 of a queue with one
+    /// message per second from timestamp 1_000_000 does.
+    #[derive(Clone)]
+    struct OffsetBroker;
+
+    impl RequestProcessor for OffsetBroker {
+        async fn process_request(
+            &mut self,
+            _channel: Channel,
+            _ctx: ConnectionHandlerContext,
+            request: RemotingCommand,
+        ) -> rocketmq_remoting::Result<Option<RemotingCommand>> {
+            let response = match RequestCode::from(request.code()) {
+                RequestCode::SearchOffsetByTimestamp => {
+                    let header = request
+                        .decode_command_custom_header::<SearchOffsetRequestHeader>()
+                        .unwrap();
+                    let offset = 100 + (header.timestamp - 1_000_000) / 1000;
+                    let offset = match header.boundary_type {
+                        Some(BoundaryType::Upper) => offset + 1,
+                        _ => offset,
+                    };
+                    RemotingCommand::create_response_command_with_header(
+                        SearchOffsetResponseHeader { offset },
+                    )
+                }
+                RequestCode::GetMaxOffset => RemotingCommand::create_response_command_with_header(
+                    GetMaxOffsetResponseHeader { offset: 200 },
+                ),
+                RequestCode::GetMinOffset => RemotingCommand::create_response_command_with_header(
+                    GetMinOffsetResponseHeader { offset: 100 },
+                ),
+                RequestCode::GetEarliestMsgStoreTime => {
+                    RemotingCommand::create_response_command_with_header(
+                        GetEarliestMsgStoretimeResponseHeader {
+                            timestamp: 1_000_000,
+                        },
+                    )
+                }
+                _ => RemotingCommand::create_response_command_with_code(
+                    ResponseCode::RequestCodeNotSupported,
+                ),
+            };
+            Ok(Some(response))
+        }
+    }
+
+    async fn start_broker() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        tokio::spawn(rocketmq_remoting::remoting_server::server::run(
+            listener,
+            std::future::pending::<()>(),
+            OffsetBroker,
+            None,
+            vec![],
+            Arc::new(RequestFlowController::new(&[])),
+        ));
+        addr
+    }
+
+    #[tokio::test]
+    async fn queries_queue_offsets_and_store_time() {
+        let addr = start_broker().await;
+        let mut api = MQClientAPIImpl::new(
+            Arc::new(TokioClientConfig::default()),
+            ClientRemotingProcessor::new(),
+            None,
+            ClientConfig::default(),
+            None,
+        );
+        api.start().await;
+        let mq = MessageQueue::from_parts("TopicTest", "broker-a", 0);
+
+        assert_eq!(
+            api.search_offset(&addr, &mq, 1_030_000, BoundaryType::Lower, 3000)
+                .await
+                .unwrap(),
+            QueueOffset::new(130)
+        );
+        assert_eq!(
+            api.search_offset(&addr, &mq, 1_030_000, BoundaryType::Upper, 3000)
+                .await
+                .unwrap(),
+            QueueOffset::new(131)
+        );
+        assert_eq!(
+            api.get_max_offset(&addr, &mq, 3000).await.unwrap(),
+            QueueOffset::new(200)
+        );
+        assert_eq!(
+            api.get_min_offset(&addr, &mq, 3000).await.unwrap(),
+            QueueOffset::new(100)
+        );
+        assert_eq!(
+            api.get_earliest_msg_storetime(&addr, &mq, 3000)
+                .await
+                .unwrap(),
+            1_000_000
+        );
+    }
 }
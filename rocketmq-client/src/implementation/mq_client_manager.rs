@@ -15,19 +15,24 @@
  * limitations under the License.
  */
 use std::collections::HashMap;
+use std::sync::atomic::AtomicBool;
 use std::sync::atomic::AtomicI32;
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
+use std::time::Duration;
 
 use lazy_static::lazy_static;
 use rocketmq_common::ArcRefCellWrapper;
 use rocketmq_remoting::runtime::RPCHook;
 use tokio::sync::RwLock;
 use tracing::info;
+use tracing::warn;
 
 use crate::base::client_config::ClientConfig;
 use crate::factory::mq_client_instance::MQClientInstance;
 use crate::producer::produce_accumulator::ProduceAccumulator;
+use crate::shutdown::install_shutdown_signal_handler;
+use crate::shutdown::ShutdownPhase;
 
 type ClientInstanceHashMap =
     HashMap<String /* clientId */, ArcRefCellWrapper<MQClientInstance>>;
@@ -39,6 +44,7 @@ pub struct MQClientManager {
     factory_table: Arc<RwLock<ClientInstanceHashMap>>,
     accumulator_table: Arc<RwLock<AccumulatorHashMap>>,
     factory_index_generator: AtomicI32,
+    shutdown_signal_handler_installed: AtomicBool,
 }
 
 impl MQClientManager {
@@ -47,6 +53,7 @@ impl MQClientManager {
             factory_index_generator: AtomicI32::new(0),
             factory_table: Arc::new(RwLock::new(HashMap::new())),
             accumulator_table: Arc::new(RwLock::new(HashMap::new())),
+            shutdown_signal_handler_installed: AtomicBool::new(false),
         }
     }
 
@@ -63,6 +70,15 @@ impl MQClientManager {
         rpc_hook: Option<Arc<Box<dyn RPCHook>>>,
     ) -> ArcRefCellWrapper<MQClientInstance> {
         let client_id = client_config.build_mq_client_id();
+        if client_config.enable_shutdown_hook
+            && !self
+                .shutdown_signal_handler_installed
+                .swap(true, Ordering::AcqRel)
+        {
+            install_shutdown_signal_handler(Duration::from_millis(
+                client_config.shutdown_hook_deadline_millis,
+            ));
+        }
         let mut factory_table = self.factory_table.write().await;
         let instance = factory_table.entry(client_id.clone()).or_insert_with(|| {
             let instance = MQClientInstance::new(
@@ -72,7 +88,15 @@ impl MQClientManager {
                 rpc_hook,
             );
             info!("Created new MQClientInstance for clientId: [{}]", client_id);
-            ArcRefCellWrapper::new(instance)
+            let instance = ArcRefCellWrapper::new(instance);
+            instance
+                .mq_admin_impl
+                .mut_from_ref()
+                .set_client(ArcRefCellWrapper::downgrade(&instance));
+            instance
+                .mq_client_api_impl
+                .set_client_instance(ArcRefCellWrapper::downgrade(&instance));
+            instance
         });
         instance.clone()
     }
@@ -99,4 +123,44 @@ impl MQClientManager {
     pub async fn remove_client_factory(&self, client_id: &str) {
         self.factory_table.write().await.remove(client_id);
     }
+
+    /// See [`shutdown_all`](crate::shutdown::shutdown_all).
+    pub async fn shutdown_all(&self, deadline: Duration) -> bool {
+        let instances = self
+            .factory_table
+            .write()
+            .await
+            .drain()
+            .map(|(_, instance)| instance)
+            .collect::<Vec<_>>();
+        let accumulators = self
+            .accumulator_table
+            .write()
+            .await
+            .drain()
+            .map(|(_, accumulator)| accumulator)
+            .collect::<Vec<_>>();
+        let shutdown = async move {
+            for phase in ShutdownPhase::ALL {
+                for instance in instances.iter() {
+                    instance.run_shutdown_hooks(phase).await;
+                }
+                if phase == ShutdownPhase::Producer {
+                    for mut accumulator in accumulators.iter().cloned() {
+                        accumulator.shutdown();
+                    }
+                }
+            }
+            for mut instance in instances {
+                instance.shutdown_forcibly().await;
+            }
+        };
+        let completed = tokio::time::timeout(deadline, shutdown).await.is_ok();
+        if completed {
+            info!("all client instances shutdown OK");
+        } else {
+            warn!("client instances still shutting down after {:?}", deadline);
+        }
+        completed
+    }
 }
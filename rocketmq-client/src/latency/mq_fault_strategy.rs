@@ -42,6 +42,7 @@ pub struct MQFaultStrategy {
     not_available_duration: &'static [u64],
     reachable_filter: Box<dyn QueueFilter>,
     available_filter: Box<dyn QueueFilter>,
+    client_zone: Option<String>,
 }
 
 impl MQFaultStrategy {
@@ -61,6 +62,7 @@ impl MQFaultStrategy {
             available_filter: Box::new(AvailableFilter {
                 latency_fault_tolerance,
             }),
+            client_zone: client_config.client_zone.clone(),
         }
     }
 
@@ -89,10 +91,27 @@ impl MQFaultStrategy {
         THREAD_BROKER_FILTER.with(|filer| {
             filer.borrow_mut().last_broker_name = last_broker_name.map(|s| s.to_string());
         });
-        if self.send_latency_fault_enable.load(Ordering::Relaxed) {
-            if reset_index {
-                tp_info.reset_index();
+        let send_latency_fault_enable = self.send_latency_fault_enable.load(Ordering::Relaxed);
+        if send_latency_fault_enable && reset_index {
+            tp_info.reset_index();
+        }
+        // Stay in the zone of the client while one of its brokers is healthy, the brokers of
+        // the other zones only take over when none is.
+        if let Some(client_zone) = self.client_zone.as_deref() {
+            let broker_filter = THREAD_BROKER_FILTER.with_borrow(|f| f.clone());
+            let mq = if send_latency_fault_enable {
+                tp_info.select_one_message_queue_in_zone(
+                    client_zone,
+                    &[self.available_filter.as_ref(), &broker_filter],
+                )
+            } else {
+                tp_info.select_one_message_queue_in_zone(client_zone, &[&broker_filter])
+            };
+            if mq.is_some() {
+                return mq;
             }
+        }
+        if send_latency_fault_enable {
             let broker_filter = THREAD_BROKER_FILTER.with_borrow(|f| f.clone());
             let filter = &[self.available_filter.as_ref(), &broker_filter];
             let mut mq = tp_info.select_one_message_queue(filter);
@@ -196,3 +215,57 @@ impl QueueFilter for AvailableFilter {
         tolerance.is_available(&message_queue.get_broker_name().to_string())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use rocketmq_remoting::protocol::route::route_data_view::BrokerData;
+    use rocketmq_remoting::protocol::route::topic_route_data::TopicRouteData;
+
+    use super::*;
+
+    #[test]
+    fn select_prefers_healthy_brokers_of_the_client_zone() {
+        let broker_datas = [("broker-a", "zone-a"), ("broker-b", "zone-b")]
+            .into_iter()
+            .map(|(broker_name, zone)| {
+                BrokerData::new(
+                    "cluster".to_string(),
+                    broker_name.to_string(),
+                    HashMap::new(),
+                    Some(zone.to_string()),
+                )
+            })
+            .collect();
+        let tp_info = TopicPublishInfo {
+            message_queue_list: ["broker-a", "broker-b"]
+                .into_iter()
+                .flat_map(|broker_name| {
+                    (0..4).map(move |id| MessageQueue::from_parts("T", broker_name, id))
+                })
+                .collect(),
+            topic_route_data: Some(TopicRouteData {
+                broker_datas,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let mut client_config = ClientConfig::new();
+        client_config.client_zone = Some("zone-b".to_string());
+        let mut strategy = MQFaultStrategy::new(&client_config);
+        strategy.set_send_latency_fault_enable(true);
+
+        for _ in 0..8 {
+            let mq = strategy
+                .select_one_message_queue(&tp_info, None, false)
+                .unwrap();
+            assert_eq!(mq.get_broker_name(), "broker-b");
+        }
+        strategy.update_fault_item("broker-b", 0, true, true);
+        let mq = strategy
+            .select_one_message_queue(&tp_info, None, false)
+            .unwrap();
+        assert_eq!(mq.get_broker_name(), "broker-a");
+    }
+}
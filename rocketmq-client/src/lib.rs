@@ -19,16 +19,26 @@
 
 use crate::error::MQClientError;
 
+pub mod acl;
 mod admin;
 pub mod base;
+pub mod claim_check;
+pub mod client_log;
 mod common;
-mod consumer;
+pub mod consumer;
+pub mod encryption;
 pub mod error;
 mod factory;
 mod hook;
 mod implementation;
 mod latency;
 pub mod producer;
-mod trace;
+pub mod proto;
+pub mod schema;
+pub mod shutdown;
+pub mod trace;
+pub mod typed;
+
+pub use crate::shutdown::shutdown_all;
 
 pub type Result<T> = std::result::Result<T, MQClientError>;
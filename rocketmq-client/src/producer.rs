@@ -19,12 +19,14 @@ pub mod default_mq_producer;
 pub mod local_transaction_state;
 pub mod message_queue_selector;
 pub mod mq_producer;
+pub mod priority_queue_selector;
 pub mod produce_accumulator;
 pub mod producer_impl;
 pub mod request_callback;
 pub(crate) mod request_future_holder;
 pub(crate) mod request_response_future;
 pub mod send_callback;
+pub mod send_callback_executor;
 pub mod send_result;
 pub mod send_status;
 pub mod transaction_listener;
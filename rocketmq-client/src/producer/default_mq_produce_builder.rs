@@ -14,18 +14,35 @@
  * See the License for the specific language governing permissions and
  * limitations under the License.
  */
+use std::collections::HashMap;
 use std::collections::HashSet;
 use std::sync::Arc;
 
 use rocketmq_common::common::compression::compression_type::CompressionType;
 use rocketmq_common::common::compression::compressor::Compressor;
+use rocketmq_common::common::message::message_properties_codec::MessagePropertiesLimits;
 use rocketmq_remoting::runtime::RPCHook;
 
+use crate::acl::acl_client_rpc_hook::AclClientRPCHook;
+use crate::acl::credentials_provider::CredentialsProvider;
 use crate::base::client_config::ClientConfig;
+use crate::base::validators::Validators;
+use crate::claim_check::BlobStore;
+use crate::claim_check::ClaimCheck;
+use crate::encryption::KeyProvider;
+use crate::encryption::MessageEncryption;
+use crate::error::ClientErrorKind;
+use crate::error::MQClientError::IllegalClientConfig;
 use crate::producer::default_mq_producer::DefaultMQProducer;
 use crate::producer::produce_accumulator::ProduceAccumulator;
 use crate::producer::producer_impl::default_mq_producer_impl::DefaultMQProducerImpl;
+use crate::producer::send_callback_executor::CallbackOverflowPolicy;
+use crate::producer::transaction_listener::TransactionListener;
+use crate::schema::SchemaValidation;
+use crate::schema::SchemaValidator;
 use crate::trace::trace_dispatcher::TraceDispatcher;
+use crate::trace::trace_dispatcher::TraceDispatcherConfig;
+use crate::Result;
 
 #[derive(Default)]
 pub struct DefaultMQProducerBuilder {
@@ -43,15 +60,33 @@ pub struct DefaultMQProducerBuilder {
     retry_another_broker_when_not_store_ok: Option<bool>,
     max_message_size: Option<u32>,
     trace_dispatcher: Option<Arc<Box<dyn TraceDispatcher + Send + Sync>>>,
+    trace_dispatcher_config: Option<TraceDispatcherConfig>,
     auto_batch: Option<bool>,
     produce_accumulator: Option<ProduceAccumulator>,
     enable_backpressure_for_async_mode: Option<bool>,
     back_pressure_for_async_send_num: Option<u32>,
     back_pressure_for_async_send_size: Option<u32>,
+    max_send_tps: Option<u32>,
+    topic_max_send_tps: HashMap<String, u32>,
+    block_when_send_rate_limited: Option<bool>,
+    topic_blacklist_ttl_millis: Option<u64>,
+    transaction_listener: Option<Arc<Box<dyn TransactionListener>>>,
+    check_thread_pool_size: Option<u32>,
+    check_request_hold_max: Option<u32>,
+    check_local_transaction_timeout: Option<u64>,
+    callback_executor_threads: Option<u32>,
+    callback_queue_capacity: Option<u32>,
+    callback_overflow_policy: Option<CallbackOverflowPolicy>,
+    message_properties_limits: Option<MessagePropertiesLimits>,
     rpc_hook: Option<Arc<Box<dyn RPCHook>>>,
     compress_level: Option<i32>,
     compress_type: Option<CompressionType>,
     compressor: Option<Arc<Box<dyn Compressor + Send + Sync>>>,
+    compress_batch_body_over_howmuch: Option<u32>,
+    batch_compress_type: Option<CompressionType>,
+    claim_check: Option<ClaimCheck>,
+    message_encryption: Option<MessageEncryption>,
+    schema_validation: Option<SchemaValidation>,
 }
 
 impl DefaultMQProducerBuilder {
@@ -71,15 +106,33 @@ impl DefaultMQProducerBuilder {
             retry_another_broker_when_not_store_ok: None,
             max_message_size: None,
             trace_dispatcher: None,
+            trace_dispatcher_config: None,
             auto_batch: None,
             produce_accumulator: None,
             enable_backpressure_for_async_mode: None,
             back_pressure_for_async_send_num: None,
             back_pressure_for_async_send_size: None,
+            max_send_tps: None,
+            topic_max_send_tps: HashMap::new(),
+            block_when_send_rate_limited: None,
+            topic_blacklist_ttl_millis: None,
+            transaction_listener: None,
+            check_thread_pool_size: None,
+            check_request_hold_max: None,
+            callback_executor_threads: None,
+            callback_queue_capacity: None,
+            callback_overflow_policy: None,
+            check_local_transaction_timeout: None,
+            message_properties_limits: None,
             rpc_hook: None,
             compress_level: None,
             compress_type: None,
+            compress_batch_body_over_howmuch: None,
+            batch_compress_type: None,
             compressor: None,
+            claim_check: None,
+            message_encryption: None,
+            schema_validation: None,
         }
     }
 
@@ -121,6 +174,21 @@ impl DefaultMQProducerBuilder {
         self
     }
 
+    /// Instance name in the client id, clients with the default name get `PID#nanos` at start.
+    pub fn instance_name(mut self, instance_name: impl Into<String>) -> Self {
+        if let Some(client_config) = self.client_config.as_mut() {
+            client_config.instance_name = instance_name.into();
+        }
+        self
+    }
+
+    pub fn unit_name(mut self, unit_name: impl Into<String>) -> Self {
+        if let Some(client_config) = self.client_config.as_mut() {
+            client_config.unit_name = Some(unit_name.into());
+        }
+        self
+    }
+
     pub fn create_topic_key(mut self, create_topic_key: String) -> Self {
         self.create_topic_key = Some(create_topic_key);
         self
@@ -209,11 +277,83 @@ impl DefaultMQProducerBuilder {
         self
     }
 
+    pub fn max_send_tps(mut self, max_send_tps: u32) -> Self {
+        self.max_send_tps = Some(max_send_tps);
+        self
+    }
+
+    pub fn topic_max_send_tps(mut self, topic: impl Into<String>, max_send_tps: u32) -> Self {
+        self.topic_max_send_tps.insert(topic.into(), max_send_tps);
+        self
+    }
+
+    pub fn block_when_send_rate_limited(mut self, block_when_send_rate_limited: bool) -> Self {
+        self.block_when_send_rate_limited = Some(block_when_send_rate_limited);
+        self
+    }
+
+    pub fn topic_blacklist_ttl_millis(mut self, topic_blacklist_ttl_millis: u64) -> Self {
+        self.topic_blacklist_ttl_millis = Some(topic_blacklist_ttl_millis);
+        self
+    }
+
+    pub fn transaction_listener(mut self, transaction_listener: impl TransactionListener) -> Self {
+        self.transaction_listener = Some(Arc::new(Box::new(transaction_listener)));
+        self
+    }
+
+    pub fn check_thread_pool_size(mut self, check_thread_pool_size: u32) -> Self {
+        self.check_thread_pool_size = Some(check_thread_pool_size);
+        self
+    }
+
+    pub fn check_request_hold_max(mut self, check_request_hold_max: u32) -> Self {
+        self.check_request_hold_max = Some(check_request_hold_max);
+        self
+    }
+
+    pub fn check_local_transaction_timeout(mut self, check_local_transaction_timeout: u64) -> Self {
+        self.check_local_transaction_timeout = Some(check_local_transaction_timeout);
+        self
+    }
+
+    pub fn callback_executor_threads(mut self, callback_executor_threads: u32) -> Self {
+        self.callback_executor_threads = Some(callback_executor_threads);
+        self
+    }
+
+    pub fn callback_queue_capacity(mut self, callback_queue_capacity: u32) -> Self {
+        self.callback_queue_capacity = Some(callback_queue_capacity);
+        self
+    }
+
+    pub fn callback_overflow_policy(
+        mut self,
+        callback_overflow_policy: CallbackOverflowPolicy,
+    ) -> Self {
+        self.callback_overflow_policy = Some(callback_overflow_policy);
+        self
+    }
+
+    pub fn message_properties_limits(
+        mut self,
+        message_properties_limits: MessagePropertiesLimits,
+    ) -> Self {
+        self.message_properties_limits = Some(message_properties_limits);
+        self
+    }
+
     pub fn rpc_hook(mut self, rpc_hook: Box<dyn RPCHook>) -> Self {
         self.rpc_hook = Some(Arc::new(rpc_hook));
         self
     }
 
+    /// Signs requests with the ACL credentials `provider` answers at send time, replacing any
+    /// hook set with [`rpc_hook`](Self::rpc_hook).
+    pub fn credentials_provider(self, provider: Arc<dyn CredentialsProvider>) -> Self {
+        self.rpc_hook(Box::new(AclClientRPCHook::new(provider)))
+    }
+
     pub fn compress_level(mut self, compress_level: i32) -> Self {
         self.compress_level = Some(compress_level);
         self
@@ -224,14 +364,113 @@ impl DefaultMQProducerBuilder {
         self
     }
 
+    /// Compresses the encoded body of batches of at least `compress_batch_body_over_howmuch`
+    /// bytes as a whole.
+    pub fn compress_batch_body_over_howmuch(
+        mut self,
+        compress_batch_body_over_howmuch: u32,
+    ) -> Self {
+        self.compress_batch_body_over_howmuch = Some(compress_batch_body_over_howmuch);
+        self
+    }
+
+    pub fn batch_compress_type(mut self, batch_compress_type: CompressionType) -> Self {
+        self.batch_compress_type = Some(batch_compress_type);
+        self
+    }
+
     pub fn compressor(mut self, compressor: Arc<Box<dyn Compressor + Send + Sync>>) -> Self {
         self.compressor = Some(compressor);
         self
     }
 
+    /// Uploads message bodies larger than `body_over_howmuch` bytes to `blob_store` and sends
+    /// the URI of the blob instead. Consumers restore the body with
+    /// [`ClaimCheck::restore`](crate::claim_check::ClaimCheck::restore) over the same store.
+    pub fn claim_check(mut self, blob_store: Arc<dyn BlobStore>, body_over_howmuch: u32) -> Self {
+        self.claim_check = Some(ClaimCheck::new(blob_store, body_over_howmuch));
+        self
+    }
+
+    /// Encrypts message bodies with AES-256-GCM under the current key of `key_provider`.
+    /// Consumers decrypt them with
+    /// [`MessageEncryption::decrypt`](crate::encryption::MessageEncryption::decrypt) over a
+    /// provider holding the same keys.
+    pub fn message_encryption(mut self, key_provider: Arc<dyn KeyProvider>) -> Self {
+        self.message_encryption = Some(MessageEncryption::new(key_provider));
+        self
+    }
+
+    /// Validates and serializes the bodies of the messages sent to `topic` with `validator`,
+    /// e.g. a [`ConfluentSchemaRegistry`](crate::schema::ConfluentSchemaRegistry). The topic is
+    /// the one sent to the broker, with the namespace when one is set. Consumers deserialize the
+    /// bodies with [`SchemaValidation::deserialize`] over the same validators.
+    pub fn schema_validator(
+        mut self,
+        topic: impl Into<String>,
+        validator: Arc<dyn SchemaValidator>,
+    ) -> Self {
+        self.schema_validation
+            .get_or_insert_with(SchemaValidation::default)
+            .register(topic, validator);
+        self
+    }
+
+    pub fn trace_dispatcher_config(
+        mut self,
+        trace_dispatcher_config: TraceDispatcherConfig,
+    ) -> Self {
+        self.trace_dispatcher_config = Some(trace_dispatcher_config);
+        self
+    }
+
+    /// Validates the options before building: the producer group, the name server address and
+    /// the options that cannot be used together.
+    pub fn try_build(self) -> Result<DefaultMQProducer> {
+        if let Some(producer_group) = self.producer_group.as_deref() {
+            Validators::check_producer_group(producer_group)?;
+        }
+        if let Some(namesrv_addr) = self
+            .client_config
+            .as_ref()
+            .and_then(|client_config| client_config.namesrv_addr.as_deref())
+        {
+            Validators::check_name_server_addr(namesrv_addr)?;
+        }
+        if self.auto_batch == Some(true) && self.transaction_listener.is_some() {
+            return Err(IllegalClientConfig(
+                ClientErrorKind::ConflictingOptions,
+                "auto batch can not be enabled for a transaction producer".to_string(),
+            ));
+        }
+        if self.enable_backpressure_for_async_mode == Some(true)
+            && (self.back_pressure_for_async_send_num == Some(0)
+                || self.back_pressure_for_async_send_size == Some(0))
+        {
+            return Err(IllegalClientConfig(
+                ClientErrorKind::ConflictingOptions,
+                "back pressure for async mode is enabled with a zero send num or size limit"
+                    .to_string(),
+            ));
+        }
+        Ok(self.build())
+    }
+
     pub fn build(self) -> DefaultMQProducer {
         let mut mq_producer = DefaultMQProducer::default();
-        if let Some(client_config) = self.client_config {
+        let mut rpc_hook = self.rpc_hook;
+        let mut client_config = self.client_config;
+        if let Some(trace_dispatcher_config) = self.trace_dispatcher_config {
+            client_config
+                .get_or_insert_with(|| mq_producer.client_config().clone())
+                .trace_dispatcher_config = trace_dispatcher_config;
+        }
+        if let Some(client_config) = client_config {
+            if let (None, Some(credentials)) = (&rpc_hook, &client_config.credentials) {
+                let hook: Box<dyn RPCHook> =
+                    Box::new(AclClientRPCHook::with_credentials(credentials.clone()));
+                rpc_hook = Some(Arc::new(hook));
+            }
             mq_producer.set_client_config(client_config);
         }
 
@@ -289,10 +528,50 @@ impl DefaultMQProducerBuilder {
         if let Some(back_pressure_for_async_send_size) = self.back_pressure_for_async_send_size {
             mq_producer.set_back_pressure_for_async_send_size(back_pressure_for_async_send_size);
         }
-        mq_producer.set_rpc_hook(self.rpc_hook);
+        if let Some(max_send_tps) = self.max_send_tps {
+            mq_producer.set_max_send_tps(max_send_tps);
+        }
+        for (topic, max_send_tps) in self.topic_max_send_tps {
+            mq_producer.set_topic_max_send_tps(topic, max_send_tps);
+        }
+        if let Some(block_when_send_rate_limited) = self.block_when_send_rate_limited {
+            mq_producer.set_block_when_send_rate_limited(block_when_send_rate_limited);
+        }
+        if let Some(topic_blacklist_ttl_millis) = self.topic_blacklist_ttl_millis {
+            mq_producer.set_topic_blacklist_ttl_millis(topic_blacklist_ttl_millis);
+        }
+        mq_producer.set_transaction_listener(self.transaction_listener);
+        if let Some(check_thread_pool_size) = self.check_thread_pool_size {
+            mq_producer.set_check_thread_pool_size(check_thread_pool_size);
+        }
+        if let Some(check_request_hold_max) = self.check_request_hold_max {
+            mq_producer.set_check_request_hold_max(check_request_hold_max);
+        }
+        if let Some(callback_executor_threads) = self.callback_executor_threads {
+            mq_producer.set_callback_executor_threads(callback_executor_threads);
+        }
+        if let Some(callback_queue_capacity) = self.callback_queue_capacity {
+            mq_producer.set_callback_queue_capacity(callback_queue_capacity);
+        }
+        if let Some(callback_overflow_policy) = self.callback_overflow_policy {
+            mq_producer.set_callback_overflow_policy(callback_overflow_policy);
+        }
+        if let Some(check_local_transaction_timeout) = self.check_local_transaction_timeout {
+            mq_producer.set_check_local_transaction_timeout(check_local_transaction_timeout);
+        }
+        if let Some(message_properties_limits) = self.message_properties_limits {
+            mq_producer.set_message_properties_limits(message_properties_limits);
+        }
+        mq_producer.set_rpc_hook(rpc_hook);
         if let Some(compress_level) = self.compress_level {
             mq_producer.set_compress_level(compress_level);
         }
+        if let Some(compress_batch_body_over_howmuch) = self.compress_batch_body_over_howmuch {
+            mq_producer.set_compress_batch_body_over_howmuch(compress_batch_body_over_howmuch);
+        }
+        if let Some(batch_compress_type) = self.batch_compress_type {
+            mq_producer.set_batch_compress_type(batch_compress_type);
+        }
         if let Some(compress_type) = self.compress_type {
             mq_producer.set_compress_type(compress_type);
         }
@@ -300,6 +579,18 @@ impl DefaultMQProducerBuilder {
             mq_producer.set_compressor(Some(compressor));
         }
 
+        if let Some(claim_check) = self.claim_check {
+            mq_producer.set_claim_check(Some(claim_check));
+        }
+
+        if let Some(message_encryption) = self.message_encryption {
+            mq_producer.set_message_encryption(Some(message_encryption));
+        }
+
+        if let Some(schema_validation) = self.schema_validation {
+            mq_producer.set_schema_validation(Some(schema_validation));
+        }
+
         if let Some(default_mqproducer_impl) = self.default_mqproducer_impl {
             mq_producer.set_default_mqproducer_impl(default_mqproducer_impl);
         } else {
@@ -314,3 +605,89 @@ impl DefaultMQProducerBuilder {
         mq_producer
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use rocketmq_common::common::message::message_ext::MessageExt;
+    use rocketmq_common::common::message::message_single::Message;
+
+    use super::*;
+    use crate::error::MQClientError;
+    use crate::producer::local_transaction_state::LocalTransactionState;
+
+    struct NoopTransactionListener;
+
+    impl TransactionListener for NoopTransactionListener {
+        fn execute_local_transaction(
+            &self,
+            _msg: &Message,
+            _arg: &dyn std::any::Any,
+        ) -> LocalTransactionState {
+            LocalTransactionState::Unknown
+        }
+
+        fn check_local_transaction(&self, _msg: &MessageExt) -> LocalTransactionState {
+            LocalTransactionState::Unknown
+        }
+    }
+
+    fn error_kind(result: Result<DefaultMQProducer>) -> Option<ClientErrorKind> {
+        match result {
+            Err(MQClientError::IllegalClientConfig(kind, _)) => Some(kind),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn try_build_rejects_reserved_group_and_bad_name_server() {
+        assert_eq!(
+            error_kind(
+                DefaultMQProducer::builder()
+                    .producer_group(rocketmq_common::common::mix_all::DEFAULT_PRODUCER_GROUP)
+                    .try_build()
+            ),
+            Some(ClientErrorKind::ReservedGroupName)
+        );
+        assert_eq!(
+            error_kind(
+                DefaultMQProducer::builder()
+                    .producer_group("please_rename_unique_group_name")
+                    .name_server_addr("127.0.0.1".to_string())
+                    .try_build()
+            ),
+            Some(ClientErrorKind::IllegalNameServerAddress)
+        );
+    }
+
+    #[test]
+    fn try_build_rejects_conflicting_options() {
+        assert_eq!(
+            error_kind(
+                DefaultMQProducer::builder()
+                    .producer_group("please_rename_unique_group_name")
+                    .auto_batch(true)
+                    .transaction_listener(NoopTransactionListener)
+                    .try_build()
+            ),
+            Some(ClientErrorKind::ConflictingOptions)
+        );
+    }
+
+    #[test]
+    fn trace_dispatcher_config_survives_a_later_client_config() {
+        let producer = DefaultMQProducer::builder()
+            .trace_dispatcher_config(TraceDispatcherConfig {
+                sampling_rate: 0.5,
+                ..Default::default()
+            })
+            .client_config(ClientConfig::default())
+            .build();
+        assert_eq!(
+            producer
+                .client_config()
+                .trace_dispatcher_config
+                .sampling_rate,
+            0.5
+        );
+    }
+}
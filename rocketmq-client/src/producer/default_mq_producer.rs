@@ -15,6 +15,7 @@
  * limitations under the License.
  */
 use std::any::Any;
+use std::collections::HashMap;
 use std::collections::HashSet;
 use std::sync::Arc;
 
@@ -23,6 +24,7 @@ use rocketmq_common::common::compression::compressor::Compressor;
 use rocketmq_common::common::compression::compressor_factory::CompressorFactory;
 use rocketmq_common::common::message::message_batch::MessageBatch;
 use rocketmq_common::common::message::message_client_id_setter::MessageClientIDSetter;
+use rocketmq_common::common::message::message_properties_codec::MessagePropertiesLimits;
 use rocketmq_common::common::message::message_queue::MessageQueue;
 use rocketmq_common::common::message::message_single::Message;
 use rocketmq_common::common::message::MessageConst;
@@ -34,23 +36,33 @@ use rocketmq_common::common::topic::TopicValidator;
 use rocketmq_common::ArcRefCellWrapper;
 use rocketmq_remoting::code::response_code::ResponseCode;
 use rocketmq_remoting::runtime::RPCHook;
+use serde::Serialize;
 use tracing::error;
 
 use crate::base::client_config::ClientConfig;
 use crate::base::validators::Validators;
-use crate::error::MQClientError::MQClientException;
+use crate::claim_check::ClaimCheck;
+use crate::encryption::MessageEncryption;
+use crate::error::MQClientError;
 use crate::producer::default_mq_produce_builder::DefaultMQProducerBuilder;
 use crate::producer::mq_producer::MQProducer;
 use crate::producer::produce_accumulator::ProduceAccumulator;
 use crate::producer::producer_impl::default_mq_producer_impl::DefaultMQProducerImpl;
 use crate::producer::send_callback::SendMessageCallback;
+use crate::producer::send_callback_executor::CallbackOverflowPolicy;
 use crate::producer::send_result::SendResult;
+use crate::producer::transaction_listener::TransactionListener;
 use crate::producer::transaction_send_result::TransactionSendResult;
+use crate::proto::encode_proto;
+use crate::schema::SchemaValidation;
+use crate::shutdown::ShutdownPhase;
 use crate::trace::async_trace_dispatcher::AsyncTraceDispatcher;
 use crate::trace::hook::end_transaction_trace_hook_impl::EndTransactionTraceHookImpl;
 use crate::trace::hook::send_message_trace_hook_impl::SendMessageTraceHookImpl;
 use crate::trace::trace_dispatcher::TraceDispatcher;
 use crate::trace::trace_dispatcher::Type;
+use crate::typed::encode_typed;
+use crate::typed::ContentType;
 use crate::Result;
 
 #[derive(Clone)]
@@ -98,10 +110,48 @@ pub struct ProducerConfig {
     /// on BackpressureForAsyncMode, limit maximum message size of on-going sending async messages
     /// default is 100M
     back_pressure_for_async_send_size: u32,
+    /// Maximum number of send requests per second for all topics, 0 means unlimited.
+    max_send_tps: u32,
+    /// Maximum number of send requests per second for a single topic, 0 means unlimited.
+    topic_max_send_tps: HashMap<String, u32>,
+    /// Indicate whether to wait for a permit when the send rate is limited, or fail at once.
+    block_when_send_rate_limited: bool,
+    /// How long, in milliseconds, a topic stays blacklisted on a broker that rejected a send
+    /// with `NO_PERMISSION` or `TOPIC_NOT_EXIST`, 0 disables the blacklist.
+    topic_blacklist_ttl_millis: u64,
+    /// Listener checking the local transaction state on behalf of the broker.
+    transaction_listener: Option<Arc<Box<dyn TransactionListener>>>,
+    /// Number of transaction state checks executed concurrently.
+    check_thread_pool_size: u32,
+    /// Maximum number of transaction state checks waiting or running, extra checks are
+    /// dropped and asked again by the broker later.
+    check_request_hold_max: u32,
+    /// Timeout in milliseconds of `TransactionListener::check_local_transaction`, the state is
+    /// reported as unknown once it expires.
+    check_local_transaction_timeout: u64,
+    /// Number of threads running the send callbacks.
+    callback_executor_threads: u32,
+    /// Maximum number of send callbacks waiting for a callback thread.
+    callback_queue_capacity: u32,
+    /// What to do with a send callback when the callback queue is full.
+    callback_overflow_policy: CallbackOverflowPolicy,
+    /// Limits on message properties, the broker applies the same checks.
+    message_properties_limits: MessagePropertiesLimits,
     rpc_hook: Option<Arc<Box<dyn RPCHook>>>,
     compress_level: i32,
     compress_type: CompressionType,
     compressor: Option<Arc<Box<dyn Compressor + Send + Sync>>>,
+    /// Batches whose encoded body is at least this many bytes are compressed as a whole rather
+    /// than message by message, `0` sends batches uncompressed.
+    compress_batch_body_over_howmuch: u32,
+    batch_compress_type: CompressionType,
+    /// Offloads bodies above its threshold to external storage, `max_message_size` does not
+    /// apply to them.
+    claim_check: Option<ClaimCheck>,
+    /// Encrypts message bodies before they leave the client.
+    message_encryption: Option<MessageEncryption>,
+    /// Validates and serializes the bodies of the topics governed by a schema.
+    schema_validation: Option<SchemaValidation>,
 }
 
 impl ProducerConfig {
@@ -174,6 +224,54 @@ impl ProducerConfig {
         self.back_pressure_for_async_send_size
     }
 
+    pub fn max_send_tps(&self) -> u32 {
+        self.max_send_tps
+    }
+
+    pub fn topic_max_send_tps(&self) -> &HashMap<String, u32> {
+        &self.topic_max_send_tps
+    }
+
+    pub fn block_when_send_rate_limited(&self) -> bool {
+        self.block_when_send_rate_limited
+    }
+
+    pub fn topic_blacklist_ttl_millis(&self) -> u64 {
+        self.topic_blacklist_ttl_millis
+    }
+
+    pub fn transaction_listener(&self) -> &Option<Arc<Box<dyn TransactionListener>>> {
+        &self.transaction_listener
+    }
+
+    pub fn check_thread_pool_size(&self) -> u32 {
+        self.check_thread_pool_size
+    }
+
+    pub fn check_request_hold_max(&self) -> u32 {
+        self.check_request_hold_max
+    }
+
+    pub fn callback_executor_threads(&self) -> u32 {
+        self.callback_executor_threads
+    }
+
+    pub fn callback_queue_capacity(&self) -> u32 {
+        self.callback_queue_capacity
+    }
+
+    pub fn callback_overflow_policy(&self) -> CallbackOverflowPolicy {
+        self.callback_overflow_policy
+    }
+
+    pub fn message_properties_limits(&self) -> &MessagePropertiesLimits {
+        &self.message_properties_limits
+    }
+
+    pub fn check_local_transaction_timeout(&self) -> u64 {
+        self.check_local_transaction_timeout
+    }
+
     pub fn rpc_hook(&self) -> &Option<Arc<Box<dyn RPCHook>>> {
         &self.rpc_hook
     }
@@ -189,6 +287,26 @@ impl ProducerConfig {
     pub fn compressor(&self) -> &Option<Arc<Box<dyn Compressor + Send + Sync>>> {
         &self.compressor
     }
+
+    pub fn compress_batch_body_over_howmuch(&self) -> u32 {
+        self.compress_batch_body_over_howmuch
+    }
+
+    pub fn batch_compress_type(&self) -> CompressionType {
+        self.batch_compress_type
+    }
+
+    pub fn claim_check(&self) -> Option<&ClaimCheck> {
+        self.claim_check.as_ref()
+    }
+
+    pub fn message_encryption(&self) -> Option<&MessageEncryption> {
+        self.message_encryption.as_ref()
+    }
+
+    pub fn schema_validation(&self) -> Option<&SchemaValidation> {
+        self.schema_validation.as_ref()
+    }
 }
 
 impl Default for ProducerConfig {
@@ -226,6 +344,18 @@ impl Default for ProducerConfig {
             enable_backpressure_for_async_mode: false,
             back_pressure_for_async_send_num: 10000,
             back_pressure_for_async_send_size: 100 * 1024 * 1024,
+            max_send_tps: 0,
+            topic_max_send_tps: HashMap::new(),
+            topic_blacklist_ttl_millis: 0,
+            block_when_send_rate_limited: true,
+            transaction_listener: None,
+            check_thread_pool_size: 1,
+            check_request_hold_max: 2000,
+            check_local_transaction_timeout: 3000,
+            callback_executor_threads: 1,
+            callback_queue_capacity: 10000,
+            callback_overflow_policy: CallbackOverflowPolicy::CallerRuns,
+            message_properties_limits: MessagePropertiesLimits::default(),
             rpc_hook: None,
             compress_level: std::env::var(MESSAGE_COMPRESS_LEVEL)
                 .unwrap_or("5".to_string())
@@ -235,6 +365,11 @@ impl Default for ProducerConfig {
             compressor: Some(Arc::new(CompressorFactory::get_compressor(
                 compression_type,
             ))),
+            compress_batch_body_over_howmuch: 0,
+            batch_compress_type: compression_type,
+            claim_check: None,
+            message_encryption: None,
+            schema_validation: None,
         }
     }
 }
@@ -326,6 +461,54 @@ impl DefaultMQProducer {
         self.producer_config.back_pressure_for_async_send_size
     }
 
+    pub fn max_send_tps(&self) -> u32 {
+        self.producer_config.max_send_tps
+    }
+
+    pub fn topic_max_send_tps(&self) -> &HashMap<String, u32> {
+        &self.producer_config.topic_max_send_tps
+    }
+
+    pub fn block_when_send_rate_limited(&self) -> bool {
+        self.producer_config.block_when_send_rate_limited
+    }
+
+    pub fn topic_blacklist_ttl_millis(&self) -> u64 {
+        self.producer_config.topic_blacklist_ttl_millis
+    }
+
+    pub fn transaction_listener(&self) -> &Option<Arc<Box<dyn TransactionListener>>> {
+        &self.producer_config.transaction_listener
+    }
+
+    pub fn check_thread_pool_size(&self) -> u32 {
+        self.producer_config.check_thread_pool_size
+    }
+
+    pub fn check_request_hold_max(&self) -> u32 {
+        self.producer_config.check_request_hold_max
+    }
+
+    pub fn callback_executor_threads(&self) -> u32 {
+        self.producer_config.callback_executor_threads
+    }
+
+    pub fn callback_queue_capacity(&self) -> u32 {
+        self.producer_config.callback_queue_capacity
+    }
+
+    pub fn callback_overflow_policy(&self) -> CallbackOverflowPolicy {
+        self.producer_config.callback_overflow_policy
+    }
+
+    pub fn message_properties_limits(&self) -> &MessagePropertiesLimits {
+        &self.producer_config.message_properties_limits
+    }
+
+    pub fn check_local_transaction_timeout(&self) -> u64 {
+        self.producer_config.check_local_transaction_timeout
+    }
+
     pub fn rpc_hook(&self) -> &Option<Arc<Box<dyn RPCHook>>> {
         &self.producer_config.rpc_hook
     }
@@ -342,6 +525,14 @@ impl DefaultMQProducer {
         &self.producer_config.compressor
     }
 
+    pub fn compress_batch_body_over_howmuch(&self) -> u32 {
+        self.producer_config.compress_batch_body_over_howmuch
+    }
+
+    pub fn batch_compress_type(&self) -> CompressionType {
+        self.producer_config.batch_compress_type
+    }
+
     pub fn set_client_config(&mut self, client_config: ClientConfig) {
         self.client_config = client_config;
     }
@@ -440,6 +631,65 @@ impl DefaultMQProducer {
         self.producer_config.back_pressure_for_async_send_size = back_pressure_for_async_send_size;
     }
 
+    pub fn set_max_send_tps(&mut self, max_send_tps: u32) {
+        self.producer_config.max_send_tps = max_send_tps;
+    }
+
+    pub fn set_topic_max_send_tps(&mut self, topic: impl Into<String>, max_send_tps: u32) {
+        self.producer_config
+            .topic_max_send_tps
+            .insert(topic.into(), max_send_tps);
+    }
+
+    pub fn set_block_when_send_rate_limited(&mut self, block_when_send_rate_limited: bool) {
+        self.producer_config.block_when_send_rate_limited = block_when_send_rate_limited;
+    }
+
+    pub fn set_topic_blacklist_ttl_millis(&mut self, topic_blacklist_ttl_millis: u64) {
+        self.producer_config.topic_blacklist_ttl_millis = topic_blacklist_ttl_millis;
+    }
+
+    pub fn set_transaction_listener(
+        &mut self,
+        transaction_listener: Option<Arc<Box<dyn TransactionListener>>>,
+    ) {
+        self.producer_config.transaction_listener = transaction_listener;
+    }
+
+    pub fn set_check_thread_pool_size(&mut self, check_thread_pool_size: u32) {
+        self.producer_config.check_thread_pool_size = check_thread_pool_size;
+    }
+
+    pub fn set_check_request_hold_max(&mut self, check_request_hold_max: u32) {
+        self.producer_config.check_request_hold_max = check_request_hold_max;
+    }
+
+    pub fn set_callback_executor_threads(&mut self, callback_executor_threads: u32) {
+        self.producer_config.callback_executor_threads = callback_executor_threads;
+    }
+
+    pub fn set_callback_queue_capacity(&mut self, callback_queue_capacity: u32) {
+        self.producer_config.callback_queue_capacity = callback_queue_capacity;
+    }
+
+    pub fn set_callback_overflow_policy(
+        &mut self,
+        callback_overflow_policy: CallbackOverflowPolicy,
+    ) {
+        self.producer_config.callback_overflow_policy = callback_overflow_policy;
+    }
+
+    pub fn set_check_local_transaction_timeout(&mut self, check_local_transaction_timeout: u64) {
+        self.producer_config.check_local_transaction_timeout = check_local_transaction_timeout;
+    }
+
+    pub fn set_message_properties_limits(
+        &mut self,
+        message_properties_limits: MessagePropertiesLimits,
+    ) {
+        self.producer_config.message_properties_limits = message_properties_limits;
+    }
+
     pub fn set_rpc_hook(&mut self, rpc_hook: Option<Arc<Box<dyn RPCHook>>>) {
         self.producer_config.rpc_hook = rpc_hook;
     }
@@ -456,6 +706,26 @@ impl DefaultMQProducer {
         self.producer_config.compressor = compressor;
     }
 
+    pub fn set_compress_batch_body_over_howmuch(&mut self, compress_batch_body_over_howmuch: u32) {
+        self.producer_config.compress_batch_body_over_howmuch = compress_batch_body_over_howmuch;
+    }
+
+    pub fn set_batch_compress_type(&mut self, batch_compress_type: CompressionType) {
+        self.producer_config.batch_compress_type = batch_compress_type;
+    }
+
+    pub fn set_claim_check(&mut self, claim_check: Option<ClaimCheck>) {
+        self.producer_config.claim_check = claim_check;
+    }
+
+    pub fn set_message_encryption(&mut self, message_encryption: Option<MessageEncryption>) {
+        self.producer_config.message_encryption = message_encryption;
+    }
+
+    pub fn set_schema_validation(&mut self, schema_validation: Option<SchemaValidation>) {
+        self.producer_config.schema_validation = schema_validation;
+    }
+
     pub fn producer_config(&self) -> &ProducerConfig {
         &self.producer_config
     }
@@ -472,8 +742,14 @@ impl DefaultMQProducer {
             Ok(mut msg_batch) => {
                 for message in msg_batch.messages.as_mut().unwrap() {
                     Validators::check_message(Some(message), &self.producer_config)?;
-                    MessageClientIDSetter::set_uniq_id(message);
                     message.set_topic(self.with_namespace(message.get_topic()).as_str());
+                    if let Some(schema_validation) = self.producer_config.schema_validation() {
+                        schema_validation.serialize(message)?;
+                    }
+                    if let Some(message_encryption) = self.producer_config.message_encryption() {
+                        message_encryption.encrypt(message)?;
+                    }
+                    MessageClientIDSetter::set_uniq_id(message);
                 }
                 MessageClientIDSetter::set_uniq_id(&mut msg_batch.final_message);
                 msg_batch.set_body(msg_batch.encode());
@@ -482,19 +758,70 @@ impl DefaultMQProducer {
             }
             Err(err) => {
                 error!("Failed to initiate the MessageBatch: {:?}", err);
-                Err(MQClientException(
-                    -1,
+                Err(MQClientError::IllegalArgument(
                     "Failed to initiate the MessageBatch".to_string(),
                 ))
             }
         }
     }
 
+    /// Runs `send_callback` on the callback executor of the producer instead of the task which
+    /// completes the send, and names the message in the logs if the callback panics.
+    fn isolate_send_callback<M: MessageTrait>(
+        &self,
+        msg: &mut M,
+        send_callback: SendMessageCallback,
+    ) -> SendMessageCallback {
+        if msg.as_any().downcast_ref::<MessageBatch>().is_none() {
+            MessageClientIDSetter::set_uniq_id(msg);
+        }
+        self.default_mqproducer_impl
+            .as_ref()
+            .unwrap()
+            .send_callback_executor()
+            .isolate(MessageClientIDSetter::get_uniq_id(msg), send_callback)
+    }
+
     #[inline]
     pub fn get_auto_batch(&self) -> bool {
         self.producer_config.produce_accumulator.is_some() && self.producer_config.auto_batch
     }
 
+    /// Sends `payload` encoded with prost to `topic`, see [`encode_proto`]. Consumers decode
+    /// it with [`decode_proto`](crate::proto::decode_proto).
+    pub async fn send_proto<P: prost::Message>(
+        &mut self,
+        topic: &str,
+        tags: Option<&str>,
+        payload: &P,
+    ) -> Result<SendResult> {
+        self.send(encode_proto(topic, tags, payload)).await
+    }
+
+    /// Sends `payload` serialized as JSON to `topic`, see [`encode_typed`]. Consumers decode it
+    /// with [`decode_typed`](crate::typed::decode_typed).
+    pub async fn send_json<T: Serialize>(
+        &mut self,
+        topic: &str,
+        payload: &T,
+    ) -> Result<SendResult> {
+        self.send_typed(topic, ContentType::Json, None, payload)
+            .await
+    }
+
+    /// Sends `payload` serialized as `content_type` to `topic`, tagged with `schema_version`
+    /// if given.
+    pub async fn send_typed<T: Serialize>(
+        &mut self,
+        topic: &str,
+        content_type: ContentType,
+        schema_version: Option<&str>,
+        payload: &T,
+    ) -> Result<SendResult> {
+        self.send(encode_typed(topic, content_type, schema_version, payload)?)
+            .await
+    }
+
     pub async fn send_direct<M>(
         &mut self,
         msg: M,
@@ -615,6 +942,38 @@ impl DefaultMQProducer {
     pub fn with_namespace(&mut self, resource: &str) -> String {
         self.client_config.with_namespace(resource)
     }
+
+    /// Lets [`shutdown_all`](crate::shutdown::shutdown_all) shut this producer down, and its
+    /// trace dispatcher after every producer of the process stopped emitting traces.
+    fn register_shutdown_hooks(&self) {
+        let Some(producer_impl) = self.default_mqproducer_impl.clone() else {
+            return;
+        };
+        let Some(client_instance) = producer_impl.client_instance().cloned() else {
+            return;
+        };
+        let producer_group = self.producer_config.producer_group.clone();
+        let mut hook_producer_impl = producer_impl.clone();
+        client_instance.register_shutdown_hook(
+            ShutdownPhase::Producer,
+            format!("producer {}", producer_group),
+            Box::new(move || {
+                Box::pin(async move { hook_producer_impl.shutdown_with_factory(false).await })
+            }),
+        );
+        if let Some(trace_dispatcher) = self.producer_config.trace_dispatcher.clone() {
+            client_instance.register_shutdown_hook(
+                ShutdownPhase::Trace,
+                format!("trace dispatcher of producer {}", producer_group),
+                Box::new(move || {
+                    Box::pin(async move {
+                        let _ = trace_dispatcher.flush();
+                        trace_dispatcher.shutdown();
+                    })
+                }),
+            );
+        }
+    }
 }
 
 impl MQProducer for DefaultMQProducer {
@@ -634,7 +993,11 @@ impl MQProducer for DefaultMQProducer {
             let mut dispatcher = AsyncTraceDispatcher::new(
                 self.producer_config.producer_group.as_str(),
                 Type::Produce,
-                self.client_config.trace_topic.clone().unwrap().as_str(),
+                self.client_config
+                    .trace_topic
+                    .as_deref()
+                    .unwrap_or(TopicValidator::RMQ_SYS_TRACE_TOPIC),
+                self.client_config.trace_dispatcher_config.clone(),
                 self.producer_config.rpc_hook.clone(),
             );
             dispatcher.set_host_producer(self.default_mqproducer_impl.as_ref().unwrap().clone());
@@ -649,13 +1012,23 @@ impl MQProducer for DefaultMQProducer {
                 .register_end_transaction_hook(EndTransactionTraceHookImpl::new(dispatcher))
         }
 
-        if let Some(ref mut trace_dispatcher) = self.producer_config.trace_dispatcher {
-            //TODO: trace
+        if let Some(ref trace_dispatcher) = self.producer_config.trace_dispatcher {
+            trace_dispatcher.start(
+                self.client_config
+                    .get_namesrv_addr()
+                    .unwrap_or_default()
+                    .as_str(),
+                self.client_config.access_channel,
+            )?;
         }
+        self.register_shutdown_hooks();
         Ok(())
     }
 
     async fn shutdown(&mut self) {
+        if let Some(ref mut default_mqproducer_impl) = self.default_mqproducer_impl {
+            default_mqproducer_impl.shutdown().await;
+        }
         if let Some(ref mut produce_accumulator) = self.producer_config.produce_accumulator {
             produce_accumulator.shutdown();
         }
@@ -706,7 +1079,7 @@ impl MQProducer for DefaultMQProducer {
         F: Fn(Option<&SendResult>, Option<&dyn std::error::Error>) + Send + Sync + 'static,
     {
         msg.set_topic(self.with_namespace(msg.get_topic()).as_str());
-        let send_callback_inner = Arc::new(send_callback);
+        let send_callback_inner = self.isolate_send_callback(&mut msg, Arc::new(send_callback));
         let result =
             if self.get_auto_batch() && msg.as_any().downcast_ref::<MessageBatch>().is_none() {
                 self.send_by_accumulator(msg, None, Some(send_callback_inner.clone()))
@@ -731,10 +1104,11 @@ impl MQProducer for DefaultMQProducer {
         F: Fn(Option<&SendResult>, Option<&dyn std::error::Error>) + Send + Sync + 'static,
     {
         msg.set_topic(self.with_namespace(msg.topic.as_str()).as_str());
+        let send_callback = self.isolate_send_callback(&mut msg, Arc::new(send_callback));
         self.default_mqproducer_impl
             .as_mut()
             .unwrap()
-            .async_send_with_callback_timeout(msg, Some(Arc::new(send_callback)), timeout)
+            .async_send_with_callback_timeout(msg, Some(send_callback), timeout)
             .await?;
         Ok(())
     }
@@ -799,13 +1173,13 @@ impl MQProducer for DefaultMQProducer {
     {
         msg.set_topic(self.with_namespace(msg.get_topic()).as_str());
         self.client_config.queue_with_namespace(&mut mq);
+        let send_callback = self.isolate_send_callback(&mut msg, Arc::new(send_callback));
 
         if self.get_auto_batch() && msg.as_any().downcast_ref::<MessageBatch>().is_none() {
-            self.send_by_accumulator(msg, Some(mq), Some(Arc::new(send_callback)))
+            self.send_by_accumulator(msg, Some(mq), Some(send_callback))
                 .await
         } else {
-            self.send_direct(msg, Some(mq), Some(Arc::new(send_callback)))
-                .await
+            self.send_direct(msg, Some(mq), Some(send_callback)).await
         }?;
 
         Ok(())
@@ -823,15 +1197,11 @@ impl MQProducer for DefaultMQProducer {
         F: Fn(Option<&SendResult>, Option<&dyn std::error::Error>) + Send + Sync + 'static,
     {
         msg.set_topic(self.with_namespace(msg.get_topic()).as_str());
+        let send_callback = self.isolate_send_callback(&mut msg, Arc::new(send_callback));
         self.default_mqproducer_impl
             .as_mut()
             .unwrap()
-            .async_send_batch_to_queue_with_callback_timeout(
-                msg,
-                mq,
-                Some(Arc::new(send_callback)),
-                timeout,
-            )
+            .async_send_batch_to_queue_with_callback_timeout(msg, mq, Some(send_callback), timeout)
             .await
     }
 
@@ -924,6 +1294,8 @@ impl MQProducer for DefaultMQProducer {
         T: std::any::Any + Sync + Send,
     {
         msg.set_topic(self.with_namespace(msg.get_topic()).as_str());
+        let send_callback =
+            send_callback.map(|send_callback| self.isolate_send_callback(&mut msg, send_callback));
         let mut mq = self
             .default_mqproducer_impl
             .as_mut()
@@ -961,6 +1333,8 @@ impl MQProducer for DefaultMQProducer {
         T: std::any::Any + Sync + Send,
     {
         msg.set_topic(self.with_namespace(msg.get_topic()).as_str());
+        let send_callback =
+            send_callback.map(|send_callback| self.isolate_send_callback(&mut msg, send_callback));
         self.default_mqproducer_impl
             .as_mut()
             .unwrap()
@@ -1065,11 +1439,12 @@ impl MQProducer for DefaultMQProducer {
     where
         F: Fn(Option<&SendResult>, Option<&dyn std::error::Error>) + Send + Sync + 'static,
     {
-        let batch = self.batch(msgs)?;
+        let mut batch = self.batch(msgs)?;
+        let f = self.isolate_send_callback(&mut batch, Arc::new(f));
         self.default_mqproducer_impl
             .as_mut()
             .unwrap()
-            .async_send_with_callback(batch, Some(Arc::new(f)))
+            .async_send_with_callback(batch, Some(f))
             .await?;
         Ok(())
     }
@@ -1083,11 +1458,12 @@ impl MQProducer for DefaultMQProducer {
     where
         F: Fn(Option<&SendResult>, Option<&dyn std::error::Error>) + Send + Sync + 'static,
     {
-        let batch = self.batch(msgs)?;
+        let mut batch = self.batch(msgs)?;
+        let f = self.isolate_send_callback(&mut batch, Arc::new(f));
         self.default_mqproducer_impl
             .as_mut()
             .unwrap()
-            .async_send_with_callback_timeout(batch, Some(Arc::new(f)), timeout)
+            .async_send_with_callback_timeout(batch, Some(f), timeout)
             .await?;
         Ok(())
     }
@@ -1101,11 +1477,12 @@ impl MQProducer for DefaultMQProducer {
     where
         F: Fn(Option<&SendResult>, Option<&dyn std::error::Error>) + Send + Sync + 'static,
     {
-        let batch = self.batch(msgs)?;
+        let mut batch = self.batch(msgs)?;
+        let f = self.isolate_send_callback(&mut batch, Arc::new(f));
         self.default_mqproducer_impl
             .as_mut()
             .unwrap()
-            .async_send_with_message_queue_callback(batch, mq, Some(Arc::new(f)))
+            .async_send_with_message_queue_callback(batch, mq, Some(f))
             .await
     }
 
@@ -1119,11 +1496,12 @@ impl MQProducer for DefaultMQProducer {
     where
         F: Fn(Option<&SendResult>, Option<&dyn std::error::Error>) + Send + Sync + 'static,
     {
-        let batch = self.batch(msgs)?;
+        let mut batch = self.batch(msgs)?;
+        let f = self.isolate_send_callback(&mut batch, Arc::new(f));
         self.default_mqproducer_impl
             .as_mut()
             .unwrap()
-            .async_send_batch_to_queue_with_callback_timeout(batch, mq, Some(Arc::new(f)), timeout)
+            .async_send_batch_to_queue_with_callback_timeout(batch, mq, Some(f), timeout)
             .await
     }
 
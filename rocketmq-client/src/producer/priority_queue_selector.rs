@@ -0,0 +1,126 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use rocketmq_common::common::message::message_queue::MessageQueue;
+use rocketmq_common::common::message::MessageTrait;
+
+use crate::base::priority_queue_layout::PriorityQueueLayout;
+
+/// Selects a queue within the range of the priority level passed as the `arg` of
+/// `send_with_selector`, round robin inside the range.
+///
+/// The level is read from a `usize`, `u32` or `i32` argument; anything else, and levels past
+/// the last one, fall back to the lowest priority.
+#[derive(Clone)]
+pub struct PriorityQueueSelector {
+    layout: Arc<PriorityQueueLayout>,
+    send_index: Arc<AtomicUsize>,
+}
+
+impl PriorityQueueSelector {
+    pub fn new(layout: PriorityQueueLayout) -> Self {
+        Self {
+            layout: Arc::new(layout),
+            send_index: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    pub fn layout(&self) -> &PriorityQueueLayout {
+        &self.layout
+    }
+
+    pub fn select(
+        &self,
+        mqs: &[MessageQueue],
+        _msg: &dyn MessageTrait,
+        arg: &dyn Any,
+    ) -> Option<MessageQueue> {
+        let lowest = self.layout.levels() - 1;
+        let level = Self::level_of_arg(arg).map_or(lowest, |level| level.min(lowest));
+        let mut queue_nums: HashMap<&str, usize> = HashMap::new();
+        for mq in mqs {
+            *queue_nums.entry(mq.get_broker_name()).or_default() += 1;
+        }
+        let candidates: Vec<&MessageQueue> = mqs
+            .iter()
+            .filter(|mq| {
+                self.layout
+                    .level_range(level, queue_nums[mq.get_broker_name()])
+                    .contains(&(mq.get_queue_id() as usize))
+            })
+            .collect();
+        if candidates.is_empty() {
+            return None;
+        }
+        let index = self.send_index.fetch_add(1, Ordering::Relaxed);
+        Some(candidates[index % candidates.len()].clone())
+    }
+
+    /// Adapts the selector to the closure taken by `send_with_selector`.
+    pub fn selector_fn(
+        &self,
+    ) -> impl Fn(&[MessageQueue], &dyn MessageTrait, &dyn Any) -> Option<MessageQueue>
+           + Send
+           + Sync
+           + 'static {
+        let selector = self.clone();
+        move |mqs, msg, arg| selector.select(mqs, msg, arg)
+    }
+
+    fn level_of_arg(arg: &dyn Any) -> Option<usize> {
+        if let Some(level) = arg.downcast_ref::<usize>() {
+            return Some(*level);
+        }
+        if let Some(level) = arg.downcast_ref::<u32>() {
+            return Some(*level as usize);
+        }
+        arg.downcast_ref::<i32>()
+            .and_then(|level| usize::try_from(*level).ok())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rocketmq_common::common::message::message_single::Message;
+
+    use super::*;
+
+    #[test]
+    fn select_stays_within_level_range() {
+        let selector = PriorityQueueSelector::new(PriorityQueueLayout::new(vec![3, 1]).unwrap());
+        let mqs: Vec<MessageQueue> = ["broker-a", "broker-b"]
+            .iter()
+            .flat_map(|broker| (0..4).map(move |id| MessageQueue::from_parts("T", *broker, id)))
+            .collect();
+        let msg = Message::new("T", b"body");
+        for _ in 0..8 {
+            let high = selector.select(&mqs, &msg, &0u32).unwrap();
+            assert!(high.get_queue_id() < 2);
+            let low = selector.select(&mqs, &msg, &"unknown").unwrap();
+            assert!(low.get_queue_id() >= 2);
+        }
+        let picked: std::collections::HashSet<MessageQueue> = (0..4)
+            .map(|_| selector.select(&mqs, &msg, &0usize).unwrap())
+            .collect();
+        assert_eq!(picked.len(), 4);
+    }
+}
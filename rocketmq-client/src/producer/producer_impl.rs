@@ -17,4 +17,6 @@
 pub(crate) mod default_mq_producer_impl;
 pub(crate) mod mq_producer_inner;
 pub mod queue_filter;
+pub mod send_rate_limiter;
+pub mod topic_blacklist;
 pub mod topic_publish_info;
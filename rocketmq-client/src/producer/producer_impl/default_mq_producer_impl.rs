@@ -42,7 +42,9 @@ use rocketmq_common::ArcRefCellWrapper;
 use rocketmq_common::MessageAccessor::MessageAccessor;
 use rocketmq_common::MessageDecoder;
 use rocketmq_common::TimeUtils::get_current_millis;
+use rocketmq_remoting::code::response_code::ResponseCode;
 use rocketmq_remoting::protocol::header::check_transaction_state_request_header::CheckTransactionStateRequestHeader;
+use rocketmq_remoting::protocol::header::end_transaction_request_header::EndTransactionRequestHeader;
 use rocketmq_remoting::protocol::header::message_operation_header::send_message_request_header::SendMessageRequestHeader;
 use rocketmq_remoting::protocol::namespace_util::NamespaceUtil;
 use rocketmq_remoting::rpc::rpc_request_header::RpcRequestHeader;
@@ -53,11 +55,13 @@ use tokio::runtime::Handle;
 use tokio::sync::RwLock;
 use tokio::sync::Semaphore;
 use tokio_util::bytes::Bytes;
+use tracing::info;
 use tracing::warn;
 
 use crate::base::client_config::ClientConfig;
 use crate::base::validators::Validators;
 use crate::common::client_error_code::ClientErrorCode;
+use crate::error::ClientErrorKind;
 use crate::error::MQClientError;
 use crate::error::MQClientError::MQClientException;
 use crate::error::MQClientError::RemotingTooMuchRequestException;
@@ -65,6 +69,7 @@ use crate::error::MQClientError::RequestTimeoutException;
 use crate::factory::mq_client_instance::MQClientInstance;
 use crate::hook::check_forbidden_context::CheckForbiddenContext;
 use crate::hook::check_forbidden_hook::CheckForbiddenHook;
+use crate::hook::end_transaction_context::EndTransactionContext;
 use crate::hook::end_transaction_hook::EndTransactionHook;
 use crate::hook::send_message_context::SendMessageContext;
 use crate::hook::send_message_hook::SendMessageHook;
@@ -74,13 +79,17 @@ use crate::latency::mq_fault_strategy::MQFaultStrategy;
 use crate::latency::resolver::Resolver;
 use crate::latency::service_detector::ServiceDetector;
 use crate::producer::default_mq_producer::ProducerConfig;
+use crate::producer::local_transaction_state::LocalTransactionState;
 use crate::producer::message_queue_selector::MessageQueueSelectorFn;
 use crate::producer::producer_impl::mq_producer_inner::MQProducerInner;
+use crate::producer::producer_impl::send_rate_limiter::SendRateLimiter;
+use crate::producer::producer_impl::topic_blacklist::TopicBlacklist;
 use crate::producer::producer_impl::topic_publish_info::TopicPublishInfo;
 use crate::producer::request_callback::RequestCallbackFn;
 use crate::producer::request_future_holder::REQUEST_FUTURE_HOLDER;
 use crate::producer::request_response_future::RequestResponseFuture;
 use crate::producer::send_callback::SendMessageCallback;
+use crate::producer::send_callback_executor::SendCallbackExecutor;
 use crate::producer::send_result::SendResult;
 use crate::producer::send_status::SendStatus;
 use crate::producer::transaction_listener::TransactionListener;
@@ -100,8 +109,14 @@ pub struct DefaultMQProducerImpl {
     mq_fault_strategy: ArcRefCellWrapper<MQFaultStrategy>,
     semaphore_async_send_num: Arc<Semaphore>,
     semaphore_async_send_size: Arc<Semaphore>,
+    send_rate_limiter: Arc<SendRateLimiter>,
+    topic_blacklist: Arc<TopicBlacklist>,
+    check_runtime: Option<Arc<RocketMQRuntime>>,
+    check_request_hold_semaphore: Arc<Semaphore>,
+    check_execute_semaphore: Arc<Semaphore>,
     async_sender_runtime: Option<Arc<RocketMQRuntime>>,
     default_async_sender_runtime: Option<Arc<RocketMQRuntime>>,
+    send_callback_executor: Arc<SendCallbackExecutor>,
 }
 
 #[allow(unused_must_use)]
@@ -119,6 +134,21 @@ impl DefaultMQProducerImpl {
                 .back_pressure_for_async_send_size()
                 .max(1024 * 1024) as usize,
         );
+        let send_rate_limiter = SendRateLimiter::new(
+            producer_config.max_send_tps(),
+            producer_config.topic_max_send_tps(),
+            producer_config.block_when_send_rate_limited(),
+        );
+        let topic_blacklist = TopicBlacklist::new(producer_config.topic_blacklist_ttl_millis());
+        let check_request_hold_semaphore =
+            Semaphore::new(producer_config.check_request_hold_max().max(1) as usize);
+        let check_execute_semaphore =
+            Semaphore::new(producer_config.check_thread_pool_size().max(1) as usize);
+        let send_callback_executor = SendCallbackExecutor::new(
+            producer_config.callback_executor_threads() as usize,
+            producer_config.callback_queue_capacity() as usize,
+            producer_config.callback_overflow_policy(),
+        );
         let topic_publish_info_table = Arc::new(RwLock::new(HashMap::new()));
         DefaultMQProducerImpl {
             client_config: client_config.clone(),
@@ -133,11 +163,17 @@ impl DefaultMQProducerImpl {
             mq_fault_strategy: ArcRefCellWrapper::new(MQFaultStrategy::new(&client_config)),
             semaphore_async_send_num: Arc::new(semaphore_async_send_num),
             semaphore_async_send_size: Arc::new(semaphore_async_send_size),
+            send_rate_limiter: Arc::new(send_rate_limiter),
+            topic_blacklist: Arc::new(topic_blacklist),
+            check_runtime: None,
+            check_request_hold_semaphore: Arc::new(check_request_hold_semaphore),
+            check_execute_semaphore: Arc::new(check_execute_semaphore),
             async_sender_runtime: None,
             default_async_sender_runtime: Some(Arc::new(RocketMQRuntime::new_multi(
                 num_cpus::get(),
                 "async-sender",
             ))),
+            send_callback_executor: Arc::new(send_callback_executor),
         }
     }
 
@@ -240,16 +276,16 @@ impl DefaultMQProducerImpl {
         let begin_start_time = Instant::now();
         self.make_sure_state_ok()?;
         Validators::check_message(Some(&msg), self.producer_config.as_ref())?;
+        self.send_rate_limiter.acquire(mq.get_topic()).await?;
+        self.topic_blacklist
+            .check(mq.get_topic(), mq.get_broker_name())?;
 
         if msg.get_topic() != mq.get_topic() {
-            return Err(MQClientError::MQClientException(
-                -1,
-                format!(
-                    "message topic [{}] is not equal with message queue topic [{}]",
-                    msg.get_topic(),
-                    mq.get_topic()
-                ),
-            ));
+            return Err(MQClientError::IllegalArgument(format!(
+                "message topic [{}] is not equal with message queue topic [{}]",
+                msg.get_topic(),
+                mq.get_topic()
+            )));
         }
         let cost_time = begin_start_time.elapsed().as_millis() as u64;
         if timeout < cost_time {
@@ -261,15 +297,21 @@ impl DefaultMQProducerImpl {
                 ),
             ));
         }
-        self.send_kernel_impl(
-            &mut msg,
-            &mq,
-            CommunicationMode::Sync,
-            None,
-            None,
-            timeout - cost_time,
-        )
-        .await
+        let result = self
+            .send_kernel_impl(
+                &mut msg,
+                &mq,
+                CommunicationMode::Sync,
+                None,
+                None,
+                timeout - cost_time,
+            )
+            .await;
+        if let Err(ref err) = result {
+            self.on_send_rejected(mq.get_topic(), mq.get_broker_name(), err)
+                .await;
+        }
+        result
     }
 
     #[inline]
@@ -372,9 +414,15 @@ impl DefaultMQProducerImpl {
         let begin_start_time = Instant::now();
         self.make_sure_state_ok()?;
         Validators::check_message(Some(&msg), self.producer_config.as_ref())?;
+        self.send_rate_limiter.acquire(msg.get_topic()).await?;
         let topic_publish_info = self.try_to_find_topic_publish_info(msg.get_topic()).await;
         if let Some(topic_publish_info) = topic_publish_info {
             if topic_publish_info.ok() {
+                topic_publish_info.check_message_type(
+                    msg.get_topic(),
+                    msg.get_properties(),
+                    true,
+                )?;
                 let message_queue_list = self
                     .client_instance
                     .as_mut()
@@ -413,14 +461,14 @@ impl DefaultMQProducerImpl {
                         .await;
                 }
                 return Err(MQClientError::MQClientException(
-                    -1,
+                    ClientErrorCode::NOT_FOUND_TOPIC_EXCEPTION,
                     "select message queue return null.".to_string(),
                 ));
             }
         }
         self.validate_name_server_setting()?;
         Err(MQClientError::MQClientException(
-            -1,
+            ClientErrorCode::NOT_FOUND_TOPIC_EXCEPTION,
             format!("No route info for this topic, {}", msg.get_topic()),
         ))
     }
@@ -449,17 +497,22 @@ impl DefaultMQProducerImpl {
                 send_callback_inner.as_ref().unwrap()(None, Some(&err));
                 return;
             }
+            if let Err(err) = producer_impl
+                .send_rate_limiter
+                .acquire(mq.get_topic())
+                .await
+            {
+                send_callback_inner.as_ref().unwrap()(None, Some(&err));
+                return;
+            }
             if msg.get_topic() != mq.get_topic() {
                 send_callback_inner.as_ref().unwrap()(
                     None,
-                    Some(&MQClientError::MQClientException(
-                        -1,
-                        format!(
-                            "message topic [{}] is not equal with message queue topic [{}]",
-                            msg.get_topic(),
-                            mq.get_topic()
-                        ),
-                    )),
+                    Some(&MQClientError::IllegalArgument(format!(
+                        "message topic [{}] is not equal with message queue topic [{}]",
+                        msg.get_topic(),
+                        mq.get_topic()
+                    ))),
                 );
                 return;
             }
@@ -648,6 +701,11 @@ impl DefaultMQProducerImpl {
     }
 
     #[inline]
+    /// Executor running the send callbacks of the application.
+    pub fn send_callback_executor(&self) -> &Arc<SendCallbackExecutor> {
+        &self.send_callback_executor
+    }
+
     pub fn get_async_sender_executor(&self) -> &Arc<RocketMQRuntime> {
         if let Some(ref async_sender_runtime) = self.async_sender_runtime {
             async_sender_runtime
@@ -667,6 +725,7 @@ impl DefaultMQProducerImpl {
         T: MessageTrait + Clone + Send + Sync,
     {
         self.make_sure_state_ok()?;
+        self.send_rate_limiter.acquire(msg.get_topic()).await?;
         let invoke_id = random::<u64>();
         let begin_timestamp_first = Instant::now();
         let mut begin_timestamp_prev = begin_timestamp_first;
@@ -675,6 +734,18 @@ impl DefaultMQProducerImpl {
         let topic_publish_info = self.try_to_find_topic_publish_info(topic.as_str()).await;
         if let Some(topic_publish_info) = topic_publish_info {
             if topic_publish_info.ok() {
+                topic_publish_info.check_message_type(
+                    topic.as_str(),
+                    msg.get_properties(),
+                    false,
+                )?;
+                self.topic_blacklist.check_all(
+                    topic.as_str(),
+                    topic_publish_info
+                        .message_queue_list
+                        .iter()
+                        .map(|mq| mq.get_broker_name()),
+                )?;
                 let mut call_timeout = false;
                 let mut mq: Option<MessageQueue> = None;
                 let mut exception: Option<MQClientError> = None;
@@ -701,6 +772,13 @@ impl DefaultMQProducerImpl {
                     );
                     if mq_selected.is_some() {
                         mq = mq_selected;
+                        if let Err(err) = self
+                            .topic_blacklist
+                            .check(topic.as_str(), mq.as_ref().unwrap().get_broker_name())
+                        {
+                            exception = Some(err);
+                            continue;
+                        }
                         brokers_sent[times as usize] =
                             mq.as_ref().unwrap().get_broker_name().to_string();
                         begin_timestamp_prev = Instant::now();
@@ -793,6 +871,23 @@ impl DefaultMQProducerImpl {
                                         true,
                                         false,
                                     );
+                                    self.on_send_rejected(
+                                        topic.as_str(),
+                                        mq.as_ref().unwrap().get_broker_name(),
+                                        &err,
+                                    )
+                                    .await;
+                                    if code == ResponseCode::NotLeaderForQueue as i32 {
+                                        // the logical queue moved to another broker, resend once
+                                        // the mapping of the topic is refreshed
+                                        self.client_instance
+                                            .as_ref()
+                                            .unwrap()
+                                            .on_not_leader_for_queue(mq.as_ref().unwrap())
+                                            .await;
+                                        exception = Some(err);
+                                        continue;
+                                    }
                                     if self.producer_config.retry_response_codes().contains(&code) {
                                         exception = Some(err);
                                         continue;
@@ -822,6 +917,12 @@ impl DefaultMQProducerImpl {
                                             true,
                                         );
                                     }
+                                    self.client_instance
+                                        .as_ref()
+                                        .unwrap()
+                                        .mut_from_ref()
+                                        .on_send_connection_failure(mq.as_ref().unwrap())
+                                        .await;
                                     exception = Some(err);
                                     continue;
                                 }
@@ -854,36 +955,18 @@ impl DefaultMQProducerImpl {
                     FAQUrl::suggest_todo(FAQUrl::SEND_MSG_FAILED)
                 );
 
-                return if let Some(err) = exception {
-                    match err {
-                        MQClientError::MQClientException(_, _) => Err(MQClientException(
-                            ClientErrorCode::BROKER_NOT_EXIST_EXCEPTION,
-                            info,
-                        )),
-                        RemotingTooMuchRequestException(_) => Err(MQClientException(
-                            ClientErrorCode::BROKER_NOT_EXIST_EXCEPTION,
-                            info,
-                        )),
-                        MQClientError::MQBrokerException(_, _, _) => Err(MQClientException(
-                            ClientErrorCode::BROKER_NOT_EXIST_EXCEPTION,
-                            info,
-                        )),
-                        MQClientError::RequestTimeoutException(_, _) => Err(MQClientException(
-                            ClientErrorCode::BROKER_NOT_EXIST_EXCEPTION,
-                            info,
-                        )),
-                        MQClientError::OffsetNotFoundException(_, _, _) => Err(MQClientException(
-                            ClientErrorCode::BROKER_NOT_EXIST_EXCEPTION,
-                            info,
-                        )),
-                        MQClientError::RemotingException(_) => Err(MQClientException(
-                            ClientErrorCode::BROKER_NOT_EXIST_EXCEPTION,
-                            info,
-                        )),
+                // Keep the broker response code so callers can tell why the send failed
+                let code = match exception {
+                    Some(MQClientError::MQBrokerException(code, _, _)) => code,
+                    Some(MQClientError::RequestTimeoutException(_, _)) => {
+                        ClientErrorCode::ACCESS_BROKER_TIMEOUT
                     }
-                } else {
-                    Err(MQClientException(-1, info))
+                    Some(MQClientError::RemotingException(_)) => {
+                        ClientErrorCode::CONNECT_BROKER_EXCEPTION
+                    }
+                    _ => ClientErrorCode::BROKER_NOT_EXIST_EXCEPTION,
                 };
+                return Err(MQClientException(code, info));
             }
         }
         self.validate_name_server_setting()?;
@@ -956,7 +1039,7 @@ impl DefaultMQProducerImpl {
 
         if broker_addr.is_none() {
             return Err(MQClientError::MQClientException(
-                -1,
+                ClientErrorCode::BROKER_NOT_EXIST_EXCEPTION,
                 format!("The broker[{}] not exist", broker_name,),
             ));
         }
@@ -975,12 +1058,28 @@ impl DefaultMQProducerImpl {
             msg.set_instance_id(self.client_config.get_namespace().unwrap().as_str());
             topic_with_namespace = true;
         }
+        if let Some(schema_validation) = self.producer_config.schema_validation() {
+            schema_validation.serialize(msg)?;
+        }
+        if let Some(message_encryption) = self.producer_config.message_encryption() {
+            message_encryption.encrypt(msg)?;
+        }
+        if let Some(claim_check) = self.producer_config.claim_check() {
+            claim_check.offload(msg)?;
+        }
         let mut sys_flag = 0i32;
         let mut msg_body_compressed = false;
         if self.try_to_compress_message(msg) {
             sys_flag |= MessageSysFlag::COMPRESSED_FLAG;
             sys_flag |= self.producer_config.compress_type().get_compression_flag();
             msg_body_compressed = true;
+        } else if batch && self.try_to_compress_batch(msg) {
+            sys_flag |= MessageSysFlag::COMPRESSED_FLAG;
+            sys_flag |= self
+                .producer_config
+                .batch_compress_type()
+                .get_compression_flag();
+            msg_body_compressed = true;
         }
         let tran_msg = msg.get_property(MessageConst::PROPERTY_TRANSACTION_PREPARED);
         if let Some(value) = tran_msg {
@@ -1036,8 +1135,8 @@ impl DefaultMQProducerImpl {
             if msg_type_flag {
                 send_message_context.msg_type = Some(MessageType::DelayMsg);
             }
-            let send_message_context = Some(send_message_context);
-            self.execute_send_message_hook_before(&send_message_context);
+            let mut send_message_context = Some(send_message_context);
+            self.execute_send_message_hook_before(&mut send_message_context);
             send_message_context
         } else {
             None
@@ -1165,7 +1264,10 @@ impl DefaultMQProducerImpl {
         }
     }
 
-    pub fn execute_send_message_hook_before(&mut self, context: &Option<SendMessageContext<'_>>) {
+    pub fn execute_send_message_hook_before(
+        &mut self,
+        context: &mut Option<SendMessageContext<'_>>,
+    ) {
         if self.has_send_message_hook() {
             for hook in self.send_message_hook_list.iter() {
                 hook.send_message_before(context);
@@ -1185,6 +1287,17 @@ impl DefaultMQProducerImpl {
         !self.send_message_hook_list.is_empty()
     }
 
+    #[inline]
+    pub fn has_end_transaction_hook(&self) -> bool {
+        !self.end_transaction_hook_list.is_empty()
+    }
+
+    pub fn execute_end_transaction_hook(&self, context: &EndTransactionContext) {
+        for hook in self.end_transaction_hook_list.iter() {
+            hook.end_transaction(context);
+        }
+    }
+
     #[inline]
     pub fn has_check_forbidden_hook(&self) -> bool {
         !self.check_forbidden_hook_list.is_empty()
@@ -1221,7 +1334,47 @@ impl DefaultMQProducerImpl {
         false
     }
 
+    /// Compresses the encoded body of a batch as a whole once it reaches
+    /// `compress_batch_body_over_howmuch`, the broker decompresses it before storing the
+    /// messages. Returns whether the body was compressed.
+    fn try_to_compress_batch<T: MessageTrait>(&self, msg: &mut T) -> bool {
+        let threshold = self.producer_config.compress_batch_body_over_howmuch();
+        if threshold == 0 {
+            return false;
+        }
+        let Some(body) = msg.get_body() else {
+            return false;
+        };
+        if body.len() < threshold as usize {
+            return false;
+        }
+        let compressed = self.producer_config.batch_compress_type().compression(body);
+        msg.set_compressed_body_mut(compressed);
+        true
+    }
+
     #[inline]
+    /// Blacklists the topic on the broker after a `NO_PERMISSION` or `TOPIC_NOT_EXIST`
+    /// rejection and refreshes its route, later sends skip the broker until the entry expires.
+    async fn on_send_rejected(&self, topic: &str, broker_name: &str, err: &MQClientError) {
+        if !self.topic_blacklist.on_send_error(topic, broker_name, err) {
+            return;
+        }
+        warn!(
+            "topic {} rejected by broker {}, blacklisted for {}ms: {}",
+            topic,
+            broker_name,
+            self.producer_config.topic_blacklist_ttl_millis(),
+            err
+        );
+        self.client_instance
+            .as_ref()
+            .unwrap()
+            .mut_from_ref()
+            .update_topic_route_info_from_name_server_topic(topic)
+            .await;
+    }
+
     pub fn select_one_message_queue(
         &self,
         tp_info: &TopicPublishInfo,
@@ -1291,14 +1444,11 @@ impl DefaultMQProducerImpl {
 
     fn make_sure_state_ok(&self) -> Result<()> {
         if self.service_state != ServiceState::Running {
-            return Err(MQClientError::MQClientException(
-                -1,
-                format!(
-                    "The producer service state not OK, {:?} {}",
-                    self.service_state,
-                    FAQUrl::suggest_todo(FAQUrl::CLIENT_SERVICE_NOT_OK)
-                ),
-            ));
+            return Err(MQClientError::IllegalState(format!(
+                "The producer service state not OK, {:?} {}",
+                self.service_state,
+                FAQUrl::suggest_todo(FAQUrl::CLIENT_SERVICE_NOT_OK)
+            )));
         }
         Ok(())
     }
@@ -1349,14 +1499,14 @@ impl DefaultMQProducerImpl {
                     return Ok(message_queue);
                 }
                 return Err(MQClientError::MQClientException(
-                    -1,
+                    ClientErrorCode::NOT_FOUND_TOPIC_EXCEPTION,
                     "select message queue return None.".to_string(),
                 ));
             }
         }
         self.validate_name_server_setting();
         Err(MQClientException(
-            -1,
+            ClientErrorCode::NOT_FOUND_TOPIC_EXCEPTION,
             "select message queue return null.".to_string(),
         ))
     }
@@ -1433,9 +1583,8 @@ impl DefaultMQProducerImpl {
             if let Some(error) = err {
                 request_response_future_inner.set_send_request_ok(false);
                 request_response_future_inner.put_response_message(None);
-                request_response_future_inner.set_cause(Box::new(
-                    MQClientError::MQClientException(-1, error.to_string()),
-                ));
+                request_response_future_inner
+                    .set_cause(Box::new(MQClientError::RequestFailed(error.to_string())));
             }
         };
         let topic = msg.get_topic().to_string();
@@ -1497,10 +1646,8 @@ impl DefaultMQProducerImpl {
                 return;
             }
             if let Some(error) = err {
-                request_response_future.set_cause(Box::new(MQClientError::MQClientException(
-                    -1,
-                    error.to_string(),
-                )));
+                request_response_future
+                    .set_cause(Box::new(MQClientError::RequestFailed(error.to_string())));
                 Self::request_fail(correlation_id.as_str());
             }
         };
@@ -1550,12 +1697,12 @@ impl DefaultMQProducerImpl {
             if let Some(error) = err {
                 request_response_future_inner.set_send_request_ok(false);
                 request_response_future_inner.put_response_message(None);
-                request_response_future_inner.set_cause(Box::new(
-                    MQClientError::MQClientException(-1, error.to_string()),
-                ));
+                request_response_future_inner
+                    .set_cause(Box::new(MQClientError::RequestFailed(error.to_string())));
             }
         };
         let topic = msg.get_topic().to_string();
+        self.send_rate_limiter.acquire(mq.get_topic()).await?;
         let _ = self
             .send_kernel_impl(
                 &mut msg,
@@ -1607,13 +1754,12 @@ impl DefaultMQProducerImpl {
                 return;
             }
             if let Some(error) = err {
-                request_response_future.set_cause(Box::new(MQClientError::MQClientException(
-                    -1,
-                    error.to_string(),
-                )));
+                request_response_future
+                    .set_cause(Box::new(MQClientError::RequestFailed(error.to_string())));
                 Self::request_fail(correlation_id.as_str());
             }
         };
+        self.send_rate_limiter.acquire(mq.get_topic()).await?;
         let _ = self
             .send_kernel_impl(
                 &mut msg,
@@ -1658,10 +1804,8 @@ impl DefaultMQProducerImpl {
                 return;
             }
             if let Some(error) = err {
-                request_response_future.set_cause(Box::new(MQClientError::MQClientException(
-                    -1,
-                    error.to_string(),
-                )));
+                request_response_future
+                    .set_cause(Box::new(MQClientError::RequestFailed(error.to_string())));
                 Self::request_fail(correlation_id.as_str());
             }
         };
@@ -1715,9 +1859,8 @@ impl DefaultMQProducerImpl {
             if let Some(error) = err {
                 //request_response_future_inner.set_send_request_ok(false);
                 request_response_future_inner.put_response_message(None);
-                request_response_future_inner.set_cause(Box::new(
-                    MQClientError::MQClientException(-1, error.to_string()),
-                ));
+                request_response_future_inner
+                    .set_cause(Box::new(MQClientError::RequestFailed(error.to_string())));
             }
         };
         let topic = msg.get_topic().to_string();
@@ -1762,16 +1905,13 @@ impl DefaultMQProducerImpl {
                 ),
             ))
         } else {
-            Err(MQClientException(
-                -1,
-                format!(
-                    "send request message to <{}> fail, {}",
-                    topic,
-                    request_response_future
-                        .get_cause()
-                        .map_or("".to_string(), |cause| { cause.to_string() })
-                ),
-            ))
+            Err(MQClientError::RequestFailed(format!(
+                "send request message to <{}> fail, {}",
+                topic,
+                request_response_future
+                    .get_cause()
+                    .map_or("".to_string(), |cause| { cause.to_string() })
+            )))
         }
     }
 
@@ -1881,14 +2021,14 @@ impl DefaultMQProducerImpl {
                     return Ok(result.unwrap());
                 }
                 return Err(MQClientError::MQClientException(
-                    -1,
+                    ClientErrorCode::NOT_FOUND_TOPIC_EXCEPTION,
                     "select message queue return None.".to_string(),
                 ));
             }
         }
         self.validate_name_server_setting()?;
         Err(MQClientError::MQClientException(
-            -1,
+            ClientErrorCode::NOT_FOUND_TOPIC_EXCEPTION,
             "No route info for this topic, ".to_string(),
         ))
     }*/
@@ -1917,8 +2057,8 @@ impl MQProducerInner for DefaultMQProducerImpl {
         .unwrap_or(false)
     }
 
-    fn get_check_listener(&self) -> Arc<Box<dyn TransactionListener>> {
-        todo!()
+    fn get_check_listener(&self) -> Option<Arc<Box<dyn TransactionListener>>> {
+        self.producer_config.transaction_listener().clone()
     }
 
     fn check_transaction_state(
@@ -1927,7 +2067,98 @@ impl MQProducerInner for DefaultMQProducerImpl {
         msg: &MessageExt,
         check_request_header: &CheckTransactionStateRequestHeader,
     ) {
-        todo!()
+        let (Some(transaction_listener), Some(check_runtime)) =
+            (self.get_check_listener(), self.check_runtime.as_ref())
+        else {
+            warn!(
+                "CheckTransactionState, pick transactionCheckListener by group[{}] failed",
+                self.producer_config.producer_group()
+            );
+            return;
+        };
+        // Keep the remoting read loop free: drop the check when too many are pending, the
+        // broker asks again later
+        let hold_permit = match self
+            .check_request_hold_semaphore
+            .clone()
+            .try_acquire_owned()
+        {
+            Ok(permit) => permit,
+            Err(_) => {
+                warn!(
+                    "CheckTransactionState, too many pending checks, drop the check of msgId[{}]",
+                    msg.msg_id()
+                );
+                return;
+            }
+        };
+        let unique_key = msg
+            .get_property(MessageConst::PROPERTY_UNIQ_CLIENT_MESSAGE_ID_KEYIDX)
+            .unwrap_or_else(|| msg.msg_id().to_string());
+        let request_header = EndTransactionRequestHeader {
+            topic: check_request_header.topic.clone(),
+            producer_group: self.producer_config.producer_group().to_string(),
+            tran_state_table_offset: check_request_header.tran_state_table_offset,
+            commit_log_offset: check_request_header.commit_log_offset,
+            commit_or_rollback: MessageSysFlag::TRANSACTION_NOT_TYPE,
+            from_transaction_check: true,
+            msg_id: unique_key.clone(),
+            transaction_id: check_request_header.transaction_id.clone(),
+            rpc_request_header: check_request_header.rpc_request_header.clone(),
+        };
+        let execute_semaphore = self.check_execute_semaphore.clone();
+        let check_timeout =
+            Duration::from_millis(self.producer_config.check_local_transaction_timeout());
+        let client_instance = self.client_instance.clone();
+        let addr = addr.to_string();
+        let end_transaction_hook_list = self.end_transaction_hook_list.clone();
+        let mut end_transaction_context =
+            self.has_end_transaction_hook()
+                .then(|| EndTransactionContext {
+                    producer_group: self.producer_config.producer_group().to_string(),
+                    broker_addr: addr.clone(),
+                    message: msg.message.clone(),
+                    msg_id: unique_key,
+                    transaction_id: check_request_header
+                        .transaction_id
+                        .clone()
+                        .unwrap_or_default(),
+                    transaction_state: LocalTransactionState::Unknown,
+                    from_transaction_check: true,
+                    check_times: msg
+                        .get_property(MessageConst::PROPERTY_TRANSACTION_CHECK_TIMES)
+                        .and_then(|times| times.parse().ok())
+                        .unwrap_or_default(),
+                });
+        let msg = msg.clone();
+        check_runtime.get_handle().spawn(async move {
+            let _hold_permit = hold_permit;
+            let (local_transaction_state, remark) = Self::check_local_transaction_with_timeout(
+                transaction_listener,
+                execute_semaphore,
+                msg,
+                check_timeout,
+            )
+            .await;
+            let mut request_header = request_header;
+            request_header.commit_or_rollback = match local_transaction_state {
+                LocalTransactionState::CommitMessage => MessageSysFlag::TRANSACTION_COMMIT_TYPE,
+                LocalTransactionState::RollbackMessage => MessageSysFlag::TRANSACTION_ROLLBACK_TYPE,
+                LocalTransactionState::Unknown => MessageSysFlag::TRANSACTION_NOT_TYPE,
+            };
+            if let Some(mut client_instance) = client_instance {
+                client_instance
+                    .mq_client_api_impl
+                    .end_transaction_oneway(addr.as_str(), request_header, remark, 3000)
+                    .await;
+            }
+            if let Some(context) = end_transaction_context.as_mut() {
+                context.transaction_state = local_transaction_state;
+                for hook in end_transaction_hook_list.iter() {
+                    hook.end_transaction(context);
+                }
+            }
+        });
     }
 
     fn update_topic_publish_info(&mut self, topic: String, info: Option<TopicPublishInfo>) {
@@ -1939,7 +2170,15 @@ impl MQProducerInner for DefaultMQProducerImpl {
         let _ = thread::spawn(move || {
             handle.block_on(async move {
                 let mut write_guard = topic_publish_info_table.write().await;
-                write_guard.insert(topic, info.unwrap());
+                let mut info = info.unwrap();
+                // Keep the message type learned before when the brokers could not tell it this
+                // time.
+                if info.topic_message_type.is_none() {
+                    info.topic_message_type = write_guard
+                        .get(&topic)
+                        .and_then(|old| old.topic_message_type);
+                }
+                write_guard.insert(topic, info);
             })
         })
         .join();
@@ -1948,6 +2187,15 @@ impl MQProducerInner for DefaultMQProducerImpl {
     fn is_unit_mode(&self) -> bool {
         self.client_config.unit_mode
     }
+
+    fn isolate_broker(&self, broker_name: &str) {
+        self.update_fault_item(
+            broker_name,
+            self.client_config.mq_client_api_timeout,
+            true,
+            false,
+        );
+    }
 }
 
 impl DefaultMQProducerImpl {
@@ -1955,6 +2203,10 @@ impl DefaultMQProducerImpl {
         self.start_with_factory(true).await
     }
 
+    pub(crate) fn client_instance(&self) -> Option<&ArcRefCellWrapper<MQClientInstance>> {
+        self.client_instance.as_ref()
+    }
+
     pub async fn start_with_factory(&mut self, start_factory: bool) -> Result<()> {
         match self.service_state {
             ServiceState::CreateJust => {
@@ -1983,6 +2235,7 @@ impl DefaultMQProducerImpl {
                 self.mq_fault_strategy
                     .set_service_detector(Box::new(service_detector));
                 self.client_instance = Some(client_instance);
+                self.init_transaction_env();
                 let self_clone = self.clone();
                 let register_ok = self
                     .client_instance
@@ -1992,15 +2245,12 @@ impl DefaultMQProducerImpl {
                     .await;
                 if !register_ok {
                     self.service_state = ServiceState::CreateJust;
-                    return Err(MQClientError::MQClientException(
-                        -1,
-                        format!(
-                            "The producer group[{}] has been created before, specify another name \
-                             please. {}",
-                            self.producer_config.producer_group(),
-                            FAQUrl::suggest_todo(FAQUrl::GROUP_NAME_DUPLICATE_URL)
-                        ),
-                    ));
+                    return Err(MQClientError::IllegalState(format!(
+                        "The producer group[{}] has been created before, specify another name \
+                         please. {}",
+                        self.producer_config.producer_group(),
+                        FAQUrl::suggest_todo(FAQUrl::GROUP_NAME_DUPLICATE_URL)
+                    )));
                 }
                 if start_factory {
                     Box::pin(self.client_instance.as_mut().unwrap().start()).await?;
@@ -2012,33 +2262,108 @@ impl DefaultMQProducerImpl {
                 self.service_state = ServiceState::Running;
             }
             ServiceState::Running => {
-                return Err(MQClientError::MQClientException(
-                    -1,
+                return Err(MQClientError::IllegalState(
                     "The producer service state is Running".to_string(),
                 ));
             }
             ServiceState::ShutdownAlready => {
-                return Err(MQClientError::MQClientException(
-                    -1,
+                return Err(MQClientError::IllegalState(
                     "The producer service state is ShutdownAlready".to_string(),
                 ));
             }
             ServiceState::StartFailed => {
-                return Err(MQClientError::MQClientException(
-                    -1,
-                    format!(
-                        "The producer service state not OK, maybe started once,{:?},{}",
-                        self.service_state,
-                        FAQUrl::suggest_todo(FAQUrl::CLIENT_SERVICE_NOT_OK)
-                    ),
-                ));
+                return Err(MQClientError::IllegalState(format!(
+                    "The producer service state not OK, maybe started once,{:?},{}",
+                    self.service_state,
+                    FAQUrl::suggest_todo(FAQUrl::CLIENT_SERVICE_NOT_OK)
+                )));
             }
         }
         Ok(())
     }
 
-    pub fn register_end_transaction_hook(&mut self, hook: impl EndTransactionHook) {
-        todo!()
+    pub async fn shutdown(&mut self) {
+        self.shutdown_with_factory(true).await
+    }
+
+    fn init_transaction_env(&mut self) {
+        if self.producer_config.transaction_listener().is_some() && self.check_runtime.is_none() {
+            self.check_runtime = Some(Arc::new(RocketMQRuntime::new_multi(
+                self.producer_config.check_thread_pool_size().max(1) as usize,
+                "transaction-check",
+            )));
+        }
+    }
+
+    /// Runs the user check on a blocking thread, at most `check_thread_pool_size` at a time.
+    /// The state is unknown when the check fails or does not return within `check_timeout`.
+    async fn check_local_transaction_with_timeout(
+        transaction_listener: Arc<Box<dyn TransactionListener>>,
+        execute_semaphore: Arc<Semaphore>,
+        msg: MessageExt,
+        check_timeout: Duration,
+    ) -> (LocalTransactionState, Option<String>) {
+        // The semaphore is never closed
+        let execute_permit = execute_semaphore.acquire_owned().await.unwrap();
+        let msg_id = msg.msg_id().to_string();
+        let check_task = tokio::task::spawn_blocking(move || {
+            // A check that times out keeps its slot until it returns
+            let _execute_permit = execute_permit;
+            transaction_listener.check_local_transaction(&msg)
+        });
+        match tokio::time::timeout(check_timeout, check_task).await {
+            Ok(Ok(local_transaction_state)) => (local_transaction_state, None),
+            Ok(Err(err)) => {
+                warn!(
+                    "Broker check transaction state, but checkLocalTransactionState exception, \
+                     msgId: {}, {}",
+                    msg_id, err
+                );
+                (
+                    LocalTransactionState::Unknown,
+                    Some(format!("checkLocalTransactionState Exception: {}", err)),
+                )
+            }
+            Err(_) => {
+                warn!(
+                    "Broker check transaction state, but checkLocalTransactionState timeout after \
+                     {}ms, msgId: {}",
+                    check_timeout.as_millis(),
+                    msg_id
+                );
+                (
+                    LocalTransactionState::Unknown,
+                    Some(format!(
+                        "checkLocalTransactionState timeout after {}ms",
+                        check_timeout.as_millis()
+                    )),
+                )
+            }
+        }
+    }
+
+    pub async fn shutdown_with_factory(&mut self, shutdown_factory: bool) {
+        if let ServiceState::Running = self.service_state {
+            if let Some(client_instance) = self.client_instance.as_mut() {
+                client_instance
+                    .unregister_producer(self.producer_config.producer_group())
+                    .await;
+                if shutdown_factory {
+                    client_instance.shutdown().await;
+                }
+            }
+            info!(
+                "the producer [{}] shutdown OK",
+                self.producer_config.producer_group()
+            );
+            self.service_state = ServiceState::ShutdownAlready;
+        }
+    }
+
+    pub fn register_end_transaction_hook(&mut self, hook: impl EndTransactionHook + 'static) {
+        info!("register end transaction hook, {}", hook.hook_name());
+        self.end_transaction_hook_list
+            .push(Arc::new(Box::new(hook)));
     }
 
     pub fn register_send_message_hook(&mut self, hook: impl SendMessageHook) {
@@ -2049,8 +2374,8 @@ impl DefaultMQProducerImpl {
     fn check_config(&self) -> Result<()> {
         Validators::check_group(self.producer_config.producer_group())?;
         if self.producer_config.producer_group() == DEFAULT_PRODUCER_GROUP {
-            return Err(MQClientError::MQClientException(
-                -1,
+            return Err(MQClientError::IllegalClientConfig(
+                ClientErrorKind::ReservedGroupName,
                 format!(
                     "The specified group name[{}] is equal to default group, please specify \
                      another one.",
@@ -2058,6 +2383,9 @@ impl DefaultMQProducerImpl {
                 ),
             ));
         }
+        if let Some(namesrv_addr) = self.client_config.namesrv_addr.as_deref() {
+            Validators::check_name_server_addr(namesrv_addr)?;
+        }
         Ok(())
     }
 
@@ -2090,3 +2418,79 @@ impl Resolver for DefaultResolver {
         todo!()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use rocketmq_common::common::message::message_single::Message;
+
+    use super::*;
+
+    struct CheckListener {
+        delay: Duration,
+        panic: bool,
+    }
+
+    impl TransactionListener for CheckListener {
+        fn execute_local_transaction(
+            &self,
+            _msg: &Message,
+            _arg: &dyn std::any::Any,
+        ) -> LocalTransactionState {
+            LocalTransactionState::Unknown
+        }
+
+        fn check_local_transaction(&self, _msg: &MessageExt) -> LocalTransactionState {
+            std::thread::sleep(self.delay);
+            if self.panic {
+                panic!("check failed");
+            }
+            LocalTransactionState::CommitMessage
+        }
+    }
+
+    async fn check(
+        listener: CheckListener,
+        semaphore: &Arc<Semaphore>,
+        timeout: Duration,
+    ) -> (LocalTransactionState, Option<String>) {
+        DefaultMQProducerImpl::check_local_transaction_with_timeout(
+            Arc::new(Box::new(listener)),
+            semaphore.clone(),
+            MessageExt::default(),
+            timeout,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn check_local_transaction_with_timeout_reports_unknown_on_timeout_or_failure() {
+        let semaphore = Arc::new(Semaphore::new(1));
+        let listener = CheckListener {
+            delay: Duration::ZERO,
+            panic: false,
+        };
+        assert_eq!(
+            check(listener, &semaphore, Duration::from_secs(1)).await,
+            (LocalTransactionState::CommitMessage, None)
+        );
+
+        let listener = CheckListener {
+            delay: Duration::from_millis(300),
+            panic: false,
+        };
+        let (state, remark) = check(listener, &semaphore, Duration::from_millis(50)).await;
+        assert_eq!(state, LocalTransactionState::Unknown);
+        assert!(remark.unwrap().contains("timeout after 50ms"));
+        // The timed out check keeps the only executor slot until it returns
+        assert_eq!(semaphore.available_permits(), 0);
+
+        let listener = CheckListener {
+            delay: Duration::ZERO,
+            panic: true,
+        };
+        let (state, remark) = check(listener, &semaphore, Duration::from_secs(1)).await;
+        assert_eq!(state, LocalTransactionState::Unknown);
+        assert!(remark.unwrap().contains("Exception"));
+        assert_eq!(semaphore.available_permits(), 1);
+    }
+}
@@ -28,7 +28,7 @@ pub trait MQProducerInner: Send + Sync + 'static {
 
     fn is_publish_topic_need_update(&self, topic: &str) -> bool;
 
-    fn get_check_listener(&self) -> Arc<Box<dyn TransactionListener>>;
+    fn get_check_listener(&self) -> Option<Arc<Box<dyn TransactionListener>>>;
 
     fn check_transaction_state(
         &self,
@@ -40,4 +40,8 @@ pub trait MQProducerInner: Send + Sync + 'static {
     fn update_topic_publish_info(&mut self, topic: String, info: Option<TopicPublishInfo>);
 
     fn is_unit_mode(&self) -> bool;
+
+    /// Mark the broker as unavailable in the fault tolerance table, so sends skip it until it
+    /// recovers.
+    fn isolate_broker(&self, broker_name: &str);
 }
@@ -0,0 +1,155 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::collections::HashMap;
+
+use parking_lot::Mutex;
+use rocketmq_remoting::protocol::namespace_util::NamespaceUtil;
+
+use crate::common::client_error_code::ClientErrorCode;
+use crate::common::token_bucket::TokenBucket;
+use crate::error::MQClientError::MQClientException;
+use crate::Result;
+
+/// Limits the number of send requests per second issued by a producer, globally and per
+/// topic. A limit of 0 means unlimited.
+pub struct SendRateLimiter {
+    global_bucket: Option<Mutex<TokenBucket>>,
+    topic_buckets: HashMap<String /* topic */, Mutex<TokenBucket>>,
+    block_when_limited: bool,
+}
+
+impl SendRateLimiter {
+    pub fn new(
+        max_send_tps: u32,
+        topic_max_send_tps: &HashMap<String, u32>,
+        block_when_limited: bool,
+    ) -> Self {
+        SendRateLimiter {
+            global_bucket: (max_send_tps > 0).then(|| Mutex::new(TokenBucket::new(max_send_tps))),
+            topic_buckets: topic_max_send_tps
+                .iter()
+                .filter(|(_, tps)| **tps > 0)
+                .map(|(topic, tps)| (topic.clone(), Mutex::new(TokenBucket::new(*tps))))
+                .collect(),
+            block_when_limited,
+        }
+    }
+
+    #[inline]
+    pub fn is_enabled(&self) -> bool {
+        self.global_bucket.is_some() || !self.topic_buckets.is_empty()
+    }
+
+    /// Acquires a send permit for `topic`, the topic may carry the client namespace.
+    ///
+    /// Waits until a permit is available when blocking is enabled, otherwise fails fast with
+    /// [`ClientErrorCode::SEND_RATE_LIMITED_EXCEPTION`].
+    pub async fn acquire(&self, topic: &str) -> Result<()> {
+        if !self.is_enabled() {
+            return Ok(());
+        }
+        let topic_bucket = self.topic_buckets.get(topic).or_else(|| {
+            self.topic_buckets
+                .get(&NamespaceUtil::without_namespace(topic))
+        });
+        if let Some(bucket) = topic_bucket {
+            self.acquire_from(bucket, topic).await?;
+        }
+        if let Some(bucket) = self.global_bucket.as_ref() {
+            if let Err(e) = self.acquire_from(bucket, topic).await {
+                // The send is rejected, so it must not count against the topic
+                if let Some(topic_bucket) = topic_bucket {
+                    topic_bucket.lock().release(1);
+                }
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+
+    async fn acquire_from(&self, bucket: &Mutex<TokenBucket>, topic: &str) -> Result<()> {
+        loop {
+            let wait = match bucket.lock().try_acquire(1) {
+                Ok(_) => return Ok(()),
+                Err(wait) => wait,
+            };
+            if !self.block_when_limited {
+                return Err(MQClientException(
+                    ClientErrorCode::SEND_RATE_LIMITED_EXCEPTION,
+                    format!("send rate limited, topic={}", topic),
+                ));
+            }
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use std::time::Instant;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn unlimited_when_no_tps_configured() {
+        let limiter = SendRateLimiter::new(0, &HashMap::new(), false);
+        assert!(!limiter.is_enabled());
+        for _ in 0..1000 {
+            assert!(limiter.acquire("TopicTest").await.is_ok());
+        }
+    }
+
+    #[tokio::test]
+    async fn fail_fast_when_topic_limit_exceeded() {
+        let mut topic_max_send_tps = HashMap::new();
+        topic_max_send_tps.insert("TopicTest".to_string(), 2);
+        let limiter = SendRateLimiter::new(0, &topic_max_send_tps, false);
+        assert!(limiter.acquire("TopicTest").await.is_ok());
+        assert!(limiter.acquire("ns%TopicTest").await.is_ok());
+        match limiter.acquire("TopicTest").await {
+            Err(MQClientException(code, _)) => {
+                assert_eq!(code, ClientErrorCode::SEND_RATE_LIMITED_EXCEPTION)
+            }
+            _ => panic!("expected rate limited error"),
+        }
+        assert!(limiter.acquire("OtherTopic").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn global_rejection_refunds_topic_permit() {
+        let mut topic_max_send_tps = HashMap::new();
+        topic_max_send_tps.insert("TopicTest".to_string(), 2);
+        let limiter = SendRateLimiter::new(1, &topic_max_send_tps, false);
+        assert!(limiter.acquire("TopicTest").await.is_ok());
+        assert!(limiter.acquire("TopicTest").await.is_err());
+        assert!(limiter.topic_buckets["TopicTest"]
+            .lock()
+            .try_acquire(1)
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn block_until_permit_available() {
+        let limiter = SendRateLimiter::new(10, &HashMap::new(), true);
+        let start = Instant::now();
+        for _ in 0..12 {
+            limiter.acquire("TopicTest").await.unwrap();
+        }
+        assert!(start.elapsed() >= Duration::from_millis(150));
+    }
+}
@@ -0,0 +1,203 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::collections::HashMap;
+use std::time::Duration;
+use std::time::Instant;
+
+use parking_lot::Mutex;
+use rocketmq_remoting::code::response_code::ResponseCode;
+
+use crate::error::MQClientError;
+use crate::Result;
+
+struct BlacklistEntry {
+    expire_at: Instant,
+    code: i32,
+    broker_addr: String,
+    remark: String,
+}
+
+/// Topic/broker pairs whose sends were rejected with `NO_PERMISSION` or `TOPIC_NOT_EXIST`.
+///
+/// Sends to a blacklisted pair fail fast with the original rejection until the entry expires,
+/// instead of hitting the broker again. A TTL of 0 disables the blacklist.
+pub struct TopicBlacklist {
+    ttl: Duration,
+    entries: Mutex<HashMap<(String /* topic */, String /* broker name */), BlacklistEntry>>,
+}
+
+impl TopicBlacklist {
+    pub fn new(ttl_millis: u64) -> Self {
+        TopicBlacklist {
+            ttl: Duration::from_millis(ttl_millis),
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    #[inline]
+    pub fn is_enabled(&self) -> bool {
+        !self.ttl.is_zero()
+    }
+
+    /// Blacklists the pair if `err` is a permission or existence rejection, returns whether it
+    /// was added.
+    pub fn on_send_error(&self, topic: &str, broker_name: &str, err: &MQClientError) -> bool {
+        if !self.is_enabled() {
+            return false;
+        }
+        let MQClientError::MQBrokerException(code, remark, broker_addr) = err else {
+            return false;
+        };
+        if *code != ResponseCode::NoPermission as i32 && *code != ResponseCode::TopicNotExist as i32
+        {
+            return false;
+        }
+        self.entries.lock().insert(
+            (topic.to_string(), broker_name.to_string()),
+            BlacklistEntry {
+                expire_at: Instant::now() + self.ttl,
+                code: *code,
+                broker_addr: broker_addr.clone(),
+                remark: remark.clone(),
+            },
+        );
+        true
+    }
+
+    pub fn is_blacklisted(&self, topic: &str, broker_name: &str) -> bool {
+        self.check(topic, broker_name).is_err()
+    }
+
+    /// Fails with the rejection recorded for the pair while it is blacklisted.
+    pub fn check(&self, topic: &str, broker_name: &str) -> Result<()> {
+        if !self.is_enabled() {
+            return Ok(());
+        }
+        let mut entries = self.entries.lock();
+        let key = (topic.to_string(), broker_name.to_string());
+        let Some(entry) = entries.get(&key) else {
+            return Ok(());
+        };
+        let now = Instant::now();
+        if entry.expire_at <= now {
+            entries.remove(&key);
+            return Ok(());
+        }
+        Err(MQClientError::MQBrokerException(
+            entry.code,
+            format!(
+                "topic {} is blacklisted on broker {} for another {}ms, last rejection: {}",
+                topic,
+                broker_name,
+                (entry.expire_at - now).as_millis(),
+                entry.remark
+            ),
+            entry.broker_addr.clone(),
+        ))
+    }
+
+    /// Fails with a recorded rejection if every broker in `broker_names` is blacklisted for
+    /// `topic`.
+    pub fn check_all<'a>(
+        &self,
+        topic: &str,
+        broker_names: impl IntoIterator<Item = &'a str>,
+    ) -> Result<()> {
+        let mut rejection = None;
+        for broker_name in broker_names {
+            match self.check(topic, broker_name) {
+                Ok(()) => return Ok(()),
+                Err(err) => rejection = Some(err),
+            }
+        }
+        rejection.map_or(Ok(()), Err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rejection(code: ResponseCode) -> MQClientError {
+        MQClientError::MQBrokerException(
+            code as i32,
+            "no permission".to_string(),
+            "127.0.0.1:10911".to_string(),
+        )
+    }
+
+    #[test]
+    fn blacklists_permission_and_existence_rejections_only() {
+        let blacklist = TopicBlacklist::new(60_000);
+        assert!(!blacklist.on_send_error(
+            "TopicTest",
+            "broker-a",
+            &rejection(ResponseCode::SystemBusy)
+        ));
+        assert!(blacklist.check("TopicTest", "broker-a").is_ok());
+
+        assert!(blacklist.on_send_error(
+            "TopicTest",
+            "broker-a",
+            &rejection(ResponseCode::NoPermission)
+        ));
+        match blacklist.check("TopicTest", "broker-a") {
+            Err(MQClientError::MQBrokerException(code, _, broker_addr)) => {
+                assert_eq!(code, ResponseCode::NoPermission as i32);
+                assert_eq!(broker_addr, "127.0.0.1:10911");
+            }
+            _ => panic!("expected the recorded rejection"),
+        }
+        assert!(blacklist.check("TopicTest", "broker-b").is_ok());
+        assert!(blacklist
+            .check_all("TopicTest", ["broker-a", "broker-b"])
+            .is_ok());
+
+        assert!(blacklist.on_send_error(
+            "TopicTest",
+            "broker-b",
+            &rejection(ResponseCode::TopicNotExist)
+        ));
+        assert!(blacklist
+            .check_all("TopicTest", ["broker-a", "broker-b"])
+            .is_err());
+    }
+
+    #[test]
+    fn entries_expire_after_ttl() {
+        let blacklist = TopicBlacklist::new(20);
+        blacklist.on_send_error(
+            "TopicTest",
+            "broker-a",
+            &rejection(ResponseCode::NoPermission),
+        );
+        assert!(blacklist.is_blacklisted("TopicTest", "broker-a"));
+        std::thread::sleep(Duration::from_millis(30));
+        assert!(!blacklist.is_blacklisted("TopicTest", "broker-a"));
+    }
+
+    #[test]
+    fn disabled_when_ttl_is_zero() {
+        let blacklist = TopicBlacklist::new(0);
+        assert!(!blacklist.on_send_error(
+            "TopicTest",
+            "broker-a",
+            &rejection(ResponseCode::NoPermission)
+        ));
+        assert!(blacklist.check("TopicTest", "broker-a").is_ok());
+    }
+}
@@ -14,11 +14,17 @@
  * See the License for the specific language governing permissions and
  * limitations under the License.
  */
+use std::collections::HashMap;
+
+use rocketmq_common::common::attribute::topic_message_type::TopicMessageType;
 use rocketmq_common::common::message::message_queue::MessageQueue;
+use rocketmq_remoting::code::response_code::ResponseCode;
 use rocketmq_remoting::protocol::route::topic_route_data::TopicRouteData;
 
 use crate::common::thread_local_index::ThreadLocalIndex;
+use crate::error::MQClientError;
 use crate::producer::producer_impl::queue_filter::QueueFilter;
+use crate::Result;
 
 #[derive(Default, Clone)]
 pub struct TopicPublishInfo {
@@ -27,6 +33,9 @@ pub struct TopicPublishInfo {
     pub message_queue_list: Vec<MessageQueue>,
     pub send_which_queue: ThreadLocalIndex,
     pub topic_route_data: Option<TopicRouteData>,
+    /// Message type declared by the `message.type` attribute of the topic, `None` when the
+    /// brokers did not tell it.
+    pub topic_message_type: Option<TopicMessageType>,
 }
 
 impl TopicPublishInfo {
@@ -37,7 +46,43 @@ impl TopicPublishInfo {
             message_queue_list: vec![],
             send_which_queue: ThreadLocalIndex,
             topic_route_data: None,
+            topic_message_type: None,
+        }
+    }
+
+    /// Checks a message with `properties` may be sent to the topic, so that e.g. a delay
+    /// message is rejected locally instead of by the broker of a FIFO topic. Topics without a
+    /// declared type, `UNSPECIFIED` or `MIXED` accept every message. Plain messages sent
+    /// `orderly` through a queue selector count as FIFO ones.
+    pub fn check_message_type(
+        &self,
+        topic: &str,
+        properties: &HashMap<String, String>,
+        orderly: bool,
+    ) -> Result<()> {
+        let Some(topic_message_type) = self.topic_message_type else {
+            return Ok(());
+        };
+        if matches!(
+            topic_message_type,
+            TopicMessageType::Unspecified | TopicMessageType::Mixed
+        ) {
+            return Ok(());
+        }
+        let message_type = match TopicMessageType::parse_from_message_property(properties) {
+            TopicMessageType::Normal if orderly => TopicMessageType::Fifo,
+            message_type => message_type,
+        };
+        if message_type != topic_message_type {
+            return Err(MQClientError::MQClientException(
+                ResponseCode::MessageIllegal as i32,
+                format!(
+                    "The message type {} does not match the message type {} of topic {}",
+                    message_type, topic_message_type, topic
+                ),
+            ));
         }
+        Ok(())
     }
 
     pub fn ok(&self) -> bool {
@@ -48,6 +93,37 @@ impl TopicPublishInfo {
         self.send_which_queue.reset();
     }
 
+    /// Zone `broker_name` registered in on the name servers.
+    pub fn broker_zone(&self, broker_name: &str) -> Option<&str> {
+        self.topic_route_data
+            .as_ref()?
+            .broker_datas
+            .iter()
+            .find(|broker_data| broker_data.broker_name() == broker_name)?
+            .zone_name()
+            .as_deref()
+    }
+
+    /// Next queue of a broker in `zone` passing all `filters`, `None` when the zone has none.
+    pub fn select_one_message_queue_in_zone(
+        &self,
+        zone: &str,
+        filters: &[&dyn QueueFilter],
+    ) -> Option<MessageQueue> {
+        let message_queue_list = &self.message_queue_list;
+        for _ in 0..message_queue_list.len() {
+            let index =
+                (self.send_which_queue.increment_and_get() % message_queue_list.len() as i32).abs();
+            let mq = &message_queue_list[index as usize];
+            if self.broker_zone(mq.get_broker_name()) == Some(zone)
+                && filters.iter().all(|filter| filter.filter(mq))
+            {
+                return Some(mq.clone());
+            }
+        }
+        None
+    }
+
     #[inline]
     pub fn select_one_message_queue(&self, filters: &[&dyn QueueFilter]) -> Option<MessageQueue> {
         self.select_one_message_queue_with_filters(&self.message_queue_list, filters)
@@ -83,3 +159,45 @@ impl TopicPublishInfo {
         Some(message_queue_list[index as usize].clone())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use rocketmq_common::common::message::MessageConst;
+
+    use super::*;
+
+    #[test]
+    fn check_message_type_rejects_mismatches_on_typed_topics() {
+        let delayed = HashMap::from([(
+            MessageConst::PROPERTY_DELAY_TIME_LEVEL.to_string(),
+            "3".to_string(),
+        )]);
+        let mut info = TopicPublishInfo::new();
+        assert!(info
+            .check_message_type("TopicTest", &delayed, false)
+            .is_ok());
+
+        info.topic_message_type = Some(TopicMessageType::Fifo);
+        assert!(info
+            .check_message_type("TopicTest", &delayed, false)
+            .is_err());
+
+        info.topic_message_type = Some(TopicMessageType::Delay);
+        assert!(info
+            .check_message_type("TopicTest", &delayed, false)
+            .is_ok());
+        assert!(info
+            .check_message_type("TopicTest", &HashMap::new(), false)
+            .is_err());
+
+        info.topic_message_type = Some(TopicMessageType::Fifo);
+        assert!(info
+            .check_message_type("TopicTest", &HashMap::new(), true)
+            .is_ok());
+
+        info.topic_message_type = Some(TopicMessageType::Mixed);
+        assert!(info
+            .check_message_type("TopicTest", &HashMap::new(), false)
+            .is_ok());
+    }
+}
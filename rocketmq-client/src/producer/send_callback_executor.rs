@@ -0,0 +1,264 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::any::Any;
+use std::panic;
+use std::panic::AssertUnwindSafe;
+use std::sync::mpsc;
+use std::sync::mpsc::Receiver;
+use std::sync::mpsc::SyncSender;
+use std::sync::mpsc::TrySendError;
+use std::sync::Arc;
+use std::thread;
+
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+use tracing::error;
+use tracing::warn;
+
+use crate::error::MQClientError;
+use crate::producer::send_callback::SendMessageCallback;
+use crate::producer::send_result::SendResult;
+
+type Task = Box<dyn FnOnce() + Send>;
+
+/// What to do with a send callback when the callback queue is full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CallbackOverflowPolicy {
+    /// Runs the callback on the task which completed the send, slowing the producer down.
+    #[default]
+    CallerRuns,
+    /// Drops the callback and logs an error with the message id.
+    Discard,
+}
+
+/// Runs the send callbacks of a producer on dedicated threads, so slow or panicking user code
+/// never stalls or takes down the tasks doing network IO.
+///
+/// The threads are started with the first callback, producers which never send asynchronously
+/// do not pay for them.
+pub struct SendCallbackExecutor {
+    threads: usize,
+    queue_capacity: usize,
+    overflow_policy: CallbackOverflowPolicy,
+    sender: OnceCell<SyncSender<Task>>,
+}
+
+impl SendCallbackExecutor {
+    pub fn new(
+        threads: usize,
+        queue_capacity: usize,
+        overflow_policy: CallbackOverflowPolicy,
+    ) -> Self {
+        SendCallbackExecutor {
+            threads: threads.max(1),
+            queue_capacity,
+            overflow_policy,
+            sender: OnceCell::new(),
+        }
+    }
+
+    /// Wraps `send_callback` so that each invocation runs on this executor with panics caught.
+    /// `msg_id` names the message in the logs when the send fails before a result carrying the
+    /// message id exists.
+    ///
+    /// The callback gets a copy of the send result and an [`MQClientError::RequestFailed`]
+    /// carrying the message of the original error.
+    pub fn isolate(
+        self: &Arc<Self>,
+        msg_id: Option<String>,
+        send_callback: SendMessageCallback,
+    ) -> SendMessageCallback {
+        let executor = self.clone();
+        Arc::new(move |send_result, err| {
+            let send_result = send_result.cloned();
+            // the error is only borrowed for the call, keep its message for the deferred callback
+            let err = err.map(|err| MQClientError::RequestFailed(err.to_string()));
+            let msg_id = send_result
+                .as_ref()
+                .and_then(|send_result| send_result.msg_id.clone())
+                .or_else(|| msg_id.clone())
+                .unwrap_or_default();
+            let send_callback = send_callback.clone();
+            executor.execute(
+                msg_id.clone(),
+                Box::new(move || {
+                    run_contained(msg_id.as_str(), send_result, err, send_callback);
+                }),
+            );
+        })
+    }
+
+    fn execute(&self, msg_id: String, task: Task) {
+        let sender = self.sender.get_or_init(|| self.start());
+        match sender.try_send(task) {
+            Ok(()) => {}
+            Err(TrySendError::Full(task)) => match self.overflow_policy {
+                CallbackOverflowPolicy::CallerRuns => task(),
+                CallbackOverflowPolicy::Discard => {
+                    error!(
+                        "send callback queue is full, callback of message {} discarded",
+                        msg_id
+                    );
+                }
+            },
+            Err(TrySendError::Disconnected(task)) => {
+                warn!(
+                    "send callback threads are gone, run the callback of {} inline",
+                    msg_id
+                );
+                task()
+            }
+        }
+    }
+
+    fn start(&self) -> SyncSender<Task> {
+        let (sender, receiver) = mpsc::sync_channel::<Task>(self.queue_capacity);
+        let receiver = Arc::new(Mutex::new(receiver));
+        for index in 0..self.threads {
+            let receiver = receiver.clone();
+            if let Err(err) = thread::Builder::new()
+                .name(format!("SendCallbackThread_{}", index))
+                .spawn(move || run_worker(receiver))
+            {
+                error!("start send callback thread failed: {}", err);
+            }
+        }
+        sender
+    }
+}
+
+fn run_worker(receiver: Arc<Mutex<Receiver<Task>>>) {
+    loop {
+        let task = receiver.lock().recv();
+        match task {
+            Ok(task) => task(),
+            // The executor is dropped
+            Err(_) => return,
+        }
+    }
+}
+
+fn run_contained(
+    msg_id: &str,
+    send_result: Option<SendResult>,
+    err: Option<MQClientError>,
+    send_callback: SendMessageCallback,
+) {
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        send_callback(
+            send_result.as_ref(),
+            err.as_ref().map(|err| err as &dyn std::error::Error),
+        )
+    }));
+    if let Err(panic) = result {
+        error!(
+            "send callback of message {} panicked: {}",
+            msg_id,
+            panic_message(panic.as_ref())
+        );
+    }
+}
+
+fn panic_message(panic: &(dyn Any + Send)) -> &str {
+    if let Some(message) = panic.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = panic.downcast_ref::<String>() {
+        message.as_str()
+    } else {
+        "unknown panic"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::atomic::Ordering;
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn callbacks_run_off_the_caller_thread_and_panics_are_contained() {
+        let executor = Arc::new(SendCallbackExecutor::new(
+            1,
+            16,
+            CallbackOverflowPolicy::CallerRuns,
+        ));
+        let (tx, rx) = mpsc::channel();
+        let callback = executor.isolate(
+            Some("msg-1".to_string()),
+            Arc::new(move |_, err| {
+                let _ = tx.send((
+                    thread::current().name().map(str::to_string),
+                    err.map(|err| err.to_string()),
+                ));
+            }),
+        );
+        callback(None, Some(&MQClientError::IllegalState("boom".to_string())));
+        let (thread_name, err) = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(thread_name.as_deref(), Some("SendCallbackThread_0"));
+        assert_eq!(err.as_deref(), Some("Request failed: Illegal state: boom"));
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let calls_cloned = calls.clone();
+        let panicking = executor.isolate(
+            None,
+            Arc::new(move |_, _| {
+                calls_cloned.fetch_add(1, Ordering::SeqCst);
+                panic!("user callback failed");
+            }),
+        );
+        panicking(Some(&SendResult::default()), None);
+        panicking(Some(&SendResult::default()), None);
+        for _ in 0..500 {
+            if calls.load(Ordering::SeqCst) == 2 {
+                break;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn full_queue_applies_overflow_policy() {
+        let executor = SendCallbackExecutor::new(1, 0, CallbackOverflowPolicy::Discard);
+        let ran = Arc::new(AtomicUsize::new(0));
+        let ran_cloned = ran.clone();
+        // nobody receives on a rendezvous channel, so it is always full
+        let (sender, _receiver) = mpsc::sync_channel(0);
+        executor.sender.get_or_init(|| sender);
+        executor.execute(
+            "msg-1".to_string(),
+            Box::new(move || {
+                ran_cloned.fetch_add(1, Ordering::SeqCst);
+            }),
+        );
+        assert_eq!(ran.load(Ordering::SeqCst), 0);
+
+        let executor = SendCallbackExecutor::new(1, 0, CallbackOverflowPolicy::CallerRuns);
+        let (sender, _receiver) = mpsc::sync_channel(0);
+        executor.sender.get_or_init(|| sender);
+        let ran_cloned = ran.clone();
+        executor.execute(
+            "msg-2".to_string(),
+            Box::new(move || {
+                ran_cloned.fetch_add(1, Ordering::SeqCst);
+            }),
+        );
+        assert_eq!(ran.load(Ordering::SeqCst), 1);
+    }
+}
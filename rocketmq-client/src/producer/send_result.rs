@@ -15,6 +15,7 @@
  * limitations under the License.
  */
 
+use bytes::Bytes;
 use rocketmq_common::common::message::message_queue::MessageQueue;
 use serde::Deserialize;
 use serde::Serialize;
@@ -31,7 +32,7 @@ pub struct SendResult {
     pub offset_msg_id: Option<String>,
     pub region_id: Option<String>,
     pub trace_on: bool,
-    pub raw_resp_body: Option<Vec<u8>>,
+    pub raw_resp_body: Option<Bytes>,
 }
 
 impl Default for SendResult {
@@ -129,7 +130,7 @@ impl SendResult {
         self.offset_msg_id = Some(offset_msg_id);
     }
 
-    pub fn set_raw_resp_body(&mut self, body: Vec<u8>) {
+    pub fn set_raw_resp_body(&mut self, body: Bytes) {
         self.raw_resp_body = Some(body);
     }
 
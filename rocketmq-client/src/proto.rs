@@ -0,0 +1,114 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use rocketmq_common::common::message::message_single::Message;
+use rocketmq_common::common::message::MessageTrait;
+
+use crate::error::MQClientError;
+use crate::Result;
+
+/// Content type of the body of a message, e.g. [`CONTENT_TYPE_PROTOBUF`].
+pub const PROPERTY_CONTENT_TYPE: &str = "CONTENT_TYPE";
+pub const CONTENT_TYPE_PROTOBUF: &str = "application/x-protobuf";
+
+/// Message to `topic` carrying `payload` encoded with prost, tagged with `tags` if given and
+/// with [`CONTENT_TYPE_PROTOBUF`] as content type.
+pub fn encode_proto<P: prost::Message>(
+    topic: impl Into<String>,
+    tags: Option<&str>,
+    payload: &P,
+) -> Message {
+    let body = payload.encode_to_vec();
+    let mut msg = match tags {
+        Some(tags) => Message::with_tags(topic, tags, body.as_slice()),
+        None => Message::new(topic, body.as_slice()),
+    };
+    msg.put_property(PROPERTY_CONTENT_TYPE, CONTENT_TYPE_PROTOBUF);
+    msg
+}
+
+/// Decodes the body of `msg` as a `P`. Messages with another content type than
+/// [`CONTENT_TYPE_PROTOBUF`] are rejected, messages without one are decoded anyway so those
+/// sent by clients which do not set it can be read.
+pub fn decode_proto<P: prost::Message + Default, T: MessageTrait>(msg: &T) -> Result<P> {
+    if let Some(content_type) = msg.get_property(PROPERTY_CONTENT_TYPE) {
+        if content_type != CONTENT_TYPE_PROTOBUF {
+            return Err(MQClientError::PayloadSerialization(format!(
+                "message of topic {} has content type {}, not {}",
+                msg.get_topic(),
+                content_type,
+                CONTENT_TYPE_PROTOBUF
+            )));
+        }
+    }
+    P::decode(msg.get_body().map(|body| body.as_ref()).unwrap_or_default()).map_err(|e| {
+        MQClientError::PayloadSerialization(format!(
+            "decode {} from message of topic {} failed: {}",
+            std::any::type_name::<P>(),
+            msg.get_topic(),
+            e
+        ))
+    })
+}
+
+/// Decodes the bodies of `msgs`, e.g. the messages of a poll, stopping at the first one that
+/// is not a `P`.
+pub fn decode_proto_all<P: prost::Message + Default, T: MessageTrait>(
+    msgs: &[T],
+) -> Result<Vec<P>> {
+    msgs.iter().map(decode_proto).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    struct OrderCreated {
+        #[prost(string, tag = "1")]
+        order_id: String,
+        #[prost(uint64, tag = "2")]
+        amount: u64,
+    }
+
+    #[test]
+    fn proto_payloads_round_trip() {
+        let order = OrderCreated {
+            order_id: "o-1".to_string(),
+            amount: 42,
+        };
+        let msg = encode_proto("Orders", Some("created"), &order);
+        assert_eq!(msg.get_tags().unwrap(), "created");
+        assert_eq!(
+            msg.get_property(PROPERTY_CONTENT_TYPE).unwrap(),
+            CONTENT_TYPE_PROTOBUF
+        );
+        assert_eq!(decode_proto::<OrderCreated, _>(&msg).unwrap(), order);
+        assert_eq!(
+            decode_proto_all::<OrderCreated, _>(&[msg.clone(), msg]).unwrap(),
+            vec![order.clone(), order]
+        );
+
+        let mut json = Message::new("Orders", br#"{"orderId":"o-1"}"#);
+        json.put_property(PROPERTY_CONTENT_TYPE, "application/json");
+        assert!(matches!(
+            decode_proto::<OrderCreated, _>(&json),
+            Err(MQClientError::PayloadSerialization(_))
+        ));
+        let garbage = Message::new("Orders", &[0xff, 0xff, 0xff]);
+        assert!(decode_proto::<OrderCreated, _>(&garbage).is_err());
+    }
+}
@@ -0,0 +1,321 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use bytes::BufMut;
+use bytes::Bytes;
+use bytes::BytesMut;
+use parking_lot::RwLock;
+use rocketmq_common::common::message::message_batch::MessageBatch;
+use rocketmq_common::common::message::MessageTrait;
+use rocketmq_common::utils::http_tiny_client::HttpTinyClient;
+use rocketmq_remoting::code::response_code::ResponseCode;
+use serde::Deserialize;
+
+use crate::error::MQClientError;
+use crate::Result;
+
+/// Set on messages whose body went through the [`SchemaValidator`] of their topic.
+pub const PROPERTY_SCHEMA_SERIALIZED: &str = "__SCHEMA_SERIALIZED";
+
+/// Governs the bodies of the messages of a topic with a schema, e.g. an Avro or Protobuf one
+/// kept in a schema registry.
+pub trait SchemaValidator: Send + Sync + 'static {
+    /// Checks that `body`, about to be sent to `topic`, conforms to the schema of the topic.
+    fn validate(&self, topic: &str, body: &Bytes) -> Result<()>;
+
+    /// Body actually sent for `body`, e.g. framed with the id of its schema. Called after a
+    /// successful [`validate`](Self::validate).
+    fn serialize(&self, _topic: &str, body: Bytes) -> Result<Bytes> {
+        Ok(body)
+    }
+
+    /// Body handed to the application for the `body` of a message consumed from `topic`, the
+    /// reverse of [`serialize`](Self::serialize).
+    fn deserialize(&self, _topic: &str, body: Bytes) -> Result<Bytes> {
+        Ok(body)
+    }
+}
+
+/// The [`SchemaValidator`] of each governed topic. The producer runs the validator of the topic
+/// on every message before sending it and the consumer runs it on every message of the topic
+/// before the listener sees it; messages of other topics are left alone.
+#[derive(Clone, Default)]
+pub struct SchemaValidation {
+    validators: HashMap<String /* Topic */, Arc<dyn SchemaValidator>>,
+}
+
+impl SchemaValidation {
+    pub fn register(&mut self, topic: impl Into<String>, validator: Arc<dyn SchemaValidator>) {
+        self.validators.insert(topic.into(), validator);
+    }
+
+    pub fn validator(&self, topic: &str) -> Option<&Arc<dyn SchemaValidator>> {
+        self.validators.get(topic)
+    }
+
+    /// Validates and serializes the body of `msg` with the validator of its topic. Batches are
+    /// left alone, their messages are serialized one by one before batching, and so are
+    /// messages already serialized. Returns whether the body was serialized.
+    pub fn serialize<T: MessageTrait>(&self, msg: &mut T) -> Result<bool> {
+        if msg.as_any().downcast_ref::<MessageBatch>().is_some()
+            || msg.get_property(PROPERTY_SCHEMA_SERIALIZED).is_some()
+        {
+            return Ok(false);
+        }
+        let Some(validator) = self.validators.get(msg.get_topic()) else {
+            return Ok(false);
+        };
+        let body = msg.get_body().cloned().unwrap_or_default();
+        validator.validate(msg.get_topic(), &body)?;
+        let body = validator.serialize(msg.get_topic(), body)?;
+        msg.set_body(body);
+        *msg.get_compressed_body_mut() = None;
+        msg.put_property(PROPERTY_SCHEMA_SERIALIZED, "true");
+        Ok(true)
+    }
+
+    /// Deserializes the body of a message serialized by [`serialize`](Self::serialize).
+    /// Returns whether the body was deserialized.
+    pub fn deserialize<T: MessageTrait>(&self, msg: &mut T) -> Result<bool> {
+        if msg.get_property(PROPERTY_SCHEMA_SERIALIZED).is_none() {
+            return Ok(false);
+        }
+        let Some(validator) = self.validators.get(msg.get_topic()) else {
+            return Ok(false);
+        };
+        let body = msg.get_body().cloned().unwrap_or_default();
+        let body = validator.deserialize(msg.get_topic(), body)?;
+        msg.set_body(body);
+        msg.clear_property(PROPERTY_SCHEMA_SERIALIZED);
+        Ok(true)
+    }
+}
+
+/// First byte of a body in the wire format of the Confluent serializers.
+const CONFLUENT_MAGIC_BYTE: u8 = 0;
+const CONFLUENT_HEADER_LEN: usize = 5;
+
+#[derive(Deserialize)]
+struct RegisteredSchema {
+    id: u32,
+}
+
+/// [`SchemaValidator`] against a schema registry with the HTTP API of the Confluent Schema
+/// Registry.
+///
+/// The schema of a topic is the latest version of the subject `<topic>-value`, the subject
+/// naming of the Confluent serializers. Bodies are expected to be already encoded with the
+/// Avro or Protobuf serializer of the application, either bare or framed in the Confluent wire
+/// format, a zero byte and the big endian id of the schema. A topic without a registered
+/// subject, or a framed body with a schema id the registry does not know, is rejected. Bare
+/// bodies are framed with the id of the latest schema of the topic and consumers get the bare
+/// body back. Ids are cached, so the registry is only asked for subjects and ids not seen yet.
+pub struct ConfluentSchemaRegistry {
+    base_url: String,
+    timeout_millis: u64,
+    latest_ids: RwLock<HashMap<String /* Subject */, u32>>,
+    known_ids: RwLock<HashMap<u32, ()>>,
+}
+
+impl ConfluentSchemaRegistry {
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            timeout_millis: 3000,
+            latest_ids: RwLock::new(HashMap::new()),
+            known_ids: RwLock::new(HashMap::new()),
+        }
+    }
+
+    pub fn with_timeout_millis(mut self, timeout_millis: u64) -> Self {
+        self.timeout_millis = timeout_millis;
+        self
+    }
+
+    fn subject(topic: &str) -> String {
+        format!("{}-value", topic)
+    }
+
+    /// Id of the latest schema registered under the subject of `topic`.
+    pub fn latest_schema_id(&self, topic: &str) -> Result<u32> {
+        let subject = Self::subject(topic);
+        if let Some(id) = self.latest_ids.read().get(subject.as_str()) {
+            return Ok(*id);
+        }
+        let schema: RegisteredSchema = self.get(
+            format!("/subjects/{}/versions/latest", subject).as_str(),
+            || {
+                MQClientError::MQClientException(
+                    ResponseCode::MessageIllegal as i32,
+                    format!("topic {} has no schema under subject {}", topic, subject),
+                )
+            },
+        )?;
+        self.latest_ids.write().insert(subject, schema.id);
+        self.known_ids.write().insert(schema.id, ());
+        Ok(schema.id)
+    }
+
+    fn check_schema_id(&self, id: u32) -> Result<()> {
+        if self.known_ids.read().contains_key(&id) {
+            return Ok(());
+        }
+        self.get::<serde_json::Value>(format!("/schemas/ids/{}", id).as_str(), || {
+            MQClientError::MQClientException(
+                ResponseCode::MessageIllegal as i32,
+                format!("schema id {} is not registered", id),
+            )
+        })?;
+        self.known_ids.write().insert(id, ());
+        Ok(())
+    }
+
+    /// GETs `path` from the registry. The blocking HTTP client runs on its own thread, this is
+    /// called from the async send path.
+    fn get<T: for<'de> Deserialize<'de>>(
+        &self,
+        path: &str,
+        not_found: impl FnOnce() -> MQClientError,
+    ) -> Result<T> {
+        let url = format!("{}{}", self.base_url, path);
+        let result = std::thread::scope(|scope| {
+            scope
+                .spawn(|| HttpTinyClient::http_get(&url, None, None, "UTF-8", self.timeout_millis))
+                .join()
+        })
+        .map_err(|_| MQClientError::IllegalState(format!("GET {} panicked", url)))?
+        .map_err(|e| MQClientError::RequestFailed(format!("GET {} failed: {}", url, e)))?;
+        match result.code {
+            200 => serde_json::from_str(result.content.as_str())
+                .map_err(|e| MQClientError::RequestFailed(format!("GET {} answered {}", url, e))),
+            404 => Err(not_found()),
+            code => Err(MQClientError::RequestFailed(format!(
+                "GET {} answered {}: {}",
+                url, code, result.content
+            ))),
+        }
+    }
+}
+
+/// Schema id and payload of a body in the Confluent wire format, `None` for a bare body.
+pub fn confluent_unframe(body: &Bytes) -> Option<(u32, Bytes)> {
+    if body.len() < CONFLUENT_HEADER_LEN || body[0] != CONFLUENT_MAGIC_BYTE {
+        return None;
+    }
+    let id = u32::from_be_bytes([body[1], body[2], body[3], body[4]]);
+    Some((id, body.slice(CONFLUENT_HEADER_LEN..)))
+}
+
+/// `payload` in the Confluent wire format under schema `id`.
+pub fn confluent_frame(id: u32, payload: &[u8]) -> Bytes {
+    let mut framed = BytesMut::with_capacity(CONFLUENT_HEADER_LEN + payload.len());
+    framed.put_u8(CONFLUENT_MAGIC_BYTE);
+    framed.put_u32(id);
+    framed.put_slice(payload);
+    framed.freeze()
+}
+
+impl SchemaValidator for ConfluentSchemaRegistry {
+    fn validate(&self, topic: &str, body: &Bytes) -> Result<()> {
+        let latest_id = self.latest_schema_id(topic)?;
+        match confluent_unframe(body) {
+            Some((id, _)) if id != latest_id => self.check_schema_id(id),
+            _ => Ok(()),
+        }
+    }
+
+    fn serialize(&self, topic: &str, body: Bytes) -> Result<Bytes> {
+        if confluent_unframe(&body).is_some() {
+            return Ok(body);
+        }
+        Ok(confluent_frame(self.latest_schema_id(topic)?, &body))
+    }
+
+    fn deserialize(&self, topic: &str, body: Bytes) -> Result<Bytes> {
+        let (id, payload) = confluent_unframe(&body).ok_or_else(|| {
+            MQClientError::IllegalState(format!(
+                "message of topic {} is not in the schema registry wire format",
+                topic
+            ))
+        })?;
+        self.check_schema_id(id)?;
+        Ok(payload)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rocketmq_common::common::message::message_single::Message;
+
+    use super::*;
+
+    /// Accepts JSON objects only and frames them like the registry does.
+    struct JsonObjectValidator;
+
+    impl SchemaValidator for JsonObjectValidator {
+        fn validate(&self, _topic: &str, body: &Bytes) -> Result<()> {
+            match serde_json::from_slice::<serde_json::Value>(body) {
+                Ok(serde_json::Value::Object(_)) => Ok(()),
+                _ => Err(MQClientError::IllegalArgument(
+                    "not a JSON object".to_string(),
+                )),
+            }
+        }
+
+        fn serialize(&self, _topic: &str, body: Bytes) -> Result<Bytes> {
+            Ok(confluent_frame(7, &body))
+        }
+
+        fn deserialize(&self, _topic: &str, body: Bytes) -> Result<Bytes> {
+            Ok(confluent_unframe(&body).unwrap().1)
+        }
+    }
+
+    #[test]
+    fn only_governed_topics_are_validated_and_serialized() {
+        let mut validation = SchemaValidation::default();
+        validation.register("Orders", Arc::new(JsonObjectValidator));
+
+        let mut invalid = Message::new("Orders", b"[1, 2]");
+        assert!(validation.serialize(&mut invalid).is_err());
+
+        let mut msg = Message::new("Orders", br#"{"id": 1}"#);
+        assert!(validation.serialize(&mut msg).unwrap());
+        assert!(!validation.serialize(&mut msg).unwrap());
+        assert_eq!(confluent_unframe(msg.get_body().unwrap()).unwrap().0, 7);
+
+        assert!(validation.deserialize(&mut msg).unwrap());
+        assert_eq!(msg.get_body().unwrap().as_ref(), br#"{"id": 1}"#);
+        assert!(msg.get_property(PROPERTY_SCHEMA_SERIALIZED).is_none());
+
+        let mut other = Message::new("Logs", b"free text");
+        assert!(!validation.serialize(&mut other).unwrap());
+    }
+
+    #[test]
+    fn confluent_framing_round_trips() {
+        let framed = confluent_frame(258, b"payload");
+        assert_eq!(&framed[..5], &[0, 0, 0, 1, 2]);
+        assert_eq!(
+            confluent_unframe(&framed),
+            Some((258, Bytes::from_static(b"payload")))
+        );
+        assert_eq!(confluent_unframe(&Bytes::from_static(b"{}")), None);
+    }
+}
@@ -0,0 +1,147 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::future::Future;
+use std::pin::Pin;
+use std::time::Duration;
+
+use parking_lot::Mutex;
+use tracing::info;
+
+use crate::implementation::mq_client_manager::MQClientManager;
+
+/// Parts of a client in the order [`shutdown_all`] shuts them down: consumers first so their
+/// offsets are persisted while the producers they send back through still run, then the
+/// producers, then the trace dispatchers which ship the traces the producers emitted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ShutdownPhase {
+    Consumer,
+    Producer,
+    Trace,
+}
+
+impl ShutdownPhase {
+    pub const ALL: [ShutdownPhase; 3] = [
+        ShutdownPhase::Consumer,
+        ShutdownPhase::Producer,
+        ShutdownPhase::Trace,
+    ];
+}
+
+pub type ShutdownHook = Box<dyn FnOnce() -> Pin<Box<dyn Future<Output = ()> + Send>> + Send>;
+
+/// Shutdown hooks registered with a client instance, each run at most once.
+#[derive(Default)]
+pub struct ShutdownHooks {
+    hooks: Mutex<Vec<(ShutdownPhase, String, ShutdownHook)>>,
+}
+
+impl ShutdownHooks {
+    pub fn register(&self, phase: ShutdownPhase, name: impl Into<String>, hook: ShutdownHook) {
+        self.hooks.lock().push((phase, name.into(), hook));
+    }
+
+    /// Runs the hooks of `phase` in registration order.
+    pub async fn run(&self, phase: ShutdownPhase) {
+        let hooks = {
+            let mut hooks = self.hooks.lock();
+            let (run, keep) = std::mem::take(&mut *hooks)
+                .into_iter()
+                .partition::<Vec<_>, _>(|(hook_phase, _, _)| *hook_phase == phase);
+            *hooks = keep;
+            run
+        };
+        for (_, name, hook) in hooks {
+            hook().await;
+            info!("shutdown hook {} done", name);
+        }
+    }
+}
+
+/// Shuts down every client instance of the process, phase by phase across the instances, and
+/// the instances themselves last. Returns `false` if `deadline` elapsed first, the instances
+/// are dropped from the client manager either way.
+pub async fn shutdown_all(deadline: Duration) -> bool {
+    MQClientManager::get_instance().shutdown_all(deadline).await
+}
+
+/// Shuts every client instance down with [`shutdown_all`] on SIGINT or SIGTERM, then exits the
+/// process the way the signal would have. Installed on the first client instance created when
+/// `enable_shutdown_hook` is set, applications with their own signal handling call
+/// [`shutdown_all`] instead.
+pub fn install_shutdown_signal_handler(deadline: Duration) {
+    tokio::spawn(async move {
+        let exit_code = wait_for_signal().await;
+        if !shutdown_all(deadline).await {
+            info!("client shutdown did not finish within {:?}", deadline);
+        }
+        std::process::exit(exit_code);
+    });
+}
+
+#[cfg(unix)]
+async fn wait_for_signal() -> i32 {
+    use tokio::signal::unix::signal;
+    use tokio::signal::unix::SignalKind;
+    let mut term = signal(SignalKind::terminate()).expect("failed to register signal handler");
+    let mut int = signal(SignalKind::interrupt()).expect("failed to register signal handler");
+    tokio::select! {
+        _ = term.recv() => {
+            info!("Received SIGTERM, shutting down the clients");
+            128 + 15
+        }
+        _ = int.recv() => {
+            info!("Received SIGINT, shutting down the clients");
+            128 + 2
+        }
+    }
+}
+
+#[cfg(windows)]
+async fn wait_for_signal() -> i32 {
+    let _ = tokio::signal::ctrl_c().await;
+    info!("Received ctrl-c, shutting down the clients");
+    130
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn runs_the_hooks_of_a_phase_once() {
+        let hooks = ShutdownHooks::default();
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        for (phase, name) in [
+            (ShutdownPhase::Producer, "producer"),
+            (ShutdownPhase::Consumer, "consumer"),
+        ] {
+            let calls = calls.clone();
+            hooks.register(
+                phase,
+                name,
+                Box::new(move || Box::pin(async move { calls.lock().push(name) })),
+            );
+        }
+        for phase in ShutdownPhase::ALL {
+            hooks.run(phase).await;
+        }
+        hooks.run(ShutdownPhase::Consumer).await;
+        assert_eq!(*calls.lock(), vec!["consumer", "producer"]);
+    }
+}
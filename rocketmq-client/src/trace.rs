@@ -16,4 +16,10 @@
  */
 pub mod async_trace_dispatcher;
 pub mod hook;
+pub mod trace_bean;
+pub mod trace_constants;
+pub mod trace_context;
+pub mod trace_data_encoder;
 pub mod trace_dispatcher;
+pub mod trace_type;
+pub mod trace_view;
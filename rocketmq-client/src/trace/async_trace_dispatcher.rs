@@ -15,44 +15,202 @@
  * limitations under the License.
  */
 use std::any::Any;
+use std::collections::HashSet;
+use std::collections::VecDeque;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
 
+use parking_lot::Mutex;
+use rocketmq_common::common::message::message_single::Message;
 use rocketmq_common::ArcRefCellWrapper;
 use rocketmq_remoting::runtime::RPCHook;
+use tokio::sync::Notify;
+use tracing::error;
+use tracing::info;
+use tracing::warn;
 
 use crate::base::access_channel::AccessChannel;
+use crate::base::client_config::ClientConfig;
+use crate::producer::default_mq_producer::DefaultMQProducer;
+use crate::producer::mq_producer::MQProducer;
 use crate::producer::producer_impl::default_mq_producer_impl::DefaultMQProducerImpl;
+use crate::trace::trace_constants::GROUP_NAME_PREFIX;
+use crate::trace::trace_constants::TRACE_INSTANCE_NAME;
+use crate::trace::trace_context::TraceContext;
+use crate::trace::trace_data_encoder::TraceDataEncoder;
 use crate::trace::trace_dispatcher::TraceDispatcher;
+use crate::trace::trace_dispatcher::TraceDispatcherConfig;
 use crate::trace::trace_dispatcher::Type;
 
-pub struct AsyncTraceDispatcher;
+/// Counters of the trace contexts that never made it to the trace topic.
+#[derive(Debug, Default)]
+pub struct TraceDispatcherStats {
+    sampled_out: AtomicU64,
+    discarded: AtomicU64,
+    send_failed: AtomicU64,
+}
+
+impl TraceDispatcherStats {
+    /// Contexts skipped by sampling.
+    pub fn sampled_out(&self) -> u64 {
+        self.sampled_out.load(Ordering::Relaxed)
+    }
+
+    /// Contexts dropped because the queue was full.
+    pub fn discarded(&self) -> u64 {
+        self.discarded.load(Ordering::Relaxed)
+    }
+
+    /// Contexts lost because their trace message could not be sent.
+    pub fn send_failed(&self) -> u64 {
+        self.send_failed.load(Ordering::Relaxed)
+    }
+}
+
+/// Queues trace contexts and sends them in batches from a background task, through a producer
+/// of its own.
+pub struct AsyncTraceDispatcher {
+    group: String,
+    type_: Type,
+    trace_topic_name: String,
+    config: TraceDispatcherConfig,
+    rpc_hook: Option<Arc<Box<dyn RPCHook>>>,
+    host_producer: Option<ArcRefCellWrapper<DefaultMQProducerImpl>>,
+    namespace_v2: Option<String>,
+    queue: Arc<Mutex<VecDeque<TraceContext>>>,
+    sample_counter: AtomicU64,
+    stats: Arc<TraceDispatcherStats>,
+    notify: Arc<Notify>,
+    started: AtomicBool,
+    stopped: Arc<AtomicBool>,
+}
 
 impl AsyncTraceDispatcher {
     pub fn new(
         group: &str,
         type_: Type,
         trace_topic_name: &str,
+        config: TraceDispatcherConfig,
         rpc_hook: Option<Arc<Box<dyn RPCHook>>>,
     ) -> Self {
-        AsyncTraceDispatcher
+        AsyncTraceDispatcher {
+            group: group.to_string(),
+            type_,
+            trace_topic_name: trace_topic_name.to_string(),
+            queue: Arc::new(Mutex::new(VecDeque::with_capacity(config.queue_capacity))),
+            config,
+            rpc_hook,
+            host_producer: None,
+            namespace_v2: None,
+            sample_counter: AtomicU64::new(0),
+            stats: Arc::new(TraceDispatcherStats::default()),
+            notify: Arc::new(Notify::new()),
+            started: AtomicBool::new(false),
+            stopped: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    pub fn stats(&self) -> &TraceDispatcherStats {
+        &self.stats
+    }
+
+    /// Keeps exactly `sampling_rate` of the contexts, spread evenly over the appended ones.
+    fn sample(&self) -> bool {
+        let rate = self.config.sampling_rate.clamp(0.0, 1.0);
+        let n = self.sample_counter.fetch_add(1, Ordering::Relaxed) as f64;
+        ((n + 1.0) * rate).floor() > (n * rate).floor()
+    }
+
+    fn build_trace_producer(
+        &self,
+        name_srv_addr: &str,
+        access_channel: AccessChannel,
+    ) -> DefaultMQProducer {
+        let client_config = ClientConfig {
+            instance_name: TRACE_INSTANCE_NAME.to_string(),
+            access_channel,
+            namespace_v2: self.namespace_v2.clone(),
+            namesrv_addr: Some(
+                self.config
+                    .trace_namesrv_addr
+                    .clone()
+                    .unwrap_or_else(|| name_srv_addr.to_string()),
+            ),
+            ..ClientConfig::default()
+        };
+        let mut builder = DefaultMQProducer::builder()
+            .client_config(client_config)
+            .producer_group(format!("{}{}", GROUP_NAME_PREFIX, self.group))
+            .send_msg_timeout(5000)
+            .max_message_size(self.config.max_batch_bytes as u32);
+        if let Some(trace_cluster) = self.config.trace_cluster.as_ref() {
+            builder = builder.create_topic_key(trace_cluster.clone());
+        }
+        let mut producer = builder.build();
+        producer.set_rpc_hook(self.rpc_hook.clone());
+        producer
     }
 }
 
 impl TraceDispatcher for AsyncTraceDispatcher {
     fn start(&self, name_srv_addr: &str, access_channel: AccessChannel) -> crate::Result<()> {
-        todo!()
+        if self.started.swap(true, Ordering::AcqRel) {
+            return Ok(());
+        }
+        let mut producer = self.build_trace_producer(name_srv_addr, access_channel);
+        let worker = TraceWorker {
+            trace_topic_name: self.trace_topic_name.clone(),
+            config: self.config.clone(),
+            queue: self.queue.clone(),
+            stats: self.stats.clone(),
+            notify: self.notify.clone(),
+            stopped: self.stopped.clone(),
+        };
+        tokio::spawn(async move {
+            if let Err(e) = producer.start().await {
+                error!("start trace producer failed: {}", e);
+                return;
+            }
+            info!("trace dispatcher of {} started", worker.trace_topic_name);
+            worker.run(&mut producer).await;
+            producer.shutdown().await;
+        });
+        Ok(())
     }
 
     fn append(&self, ctx: &dyn Any) -> bool {
-        todo!()
+        let Some(ctx) = ctx.downcast_ref::<TraceContext>() else {
+            return false;
+        };
+        if !self.sample() {
+            self.stats.sampled_out.fetch_add(1, Ordering::Relaxed);
+            return true;
+        }
+        let len = {
+            let mut queue = self.queue.lock();
+            if queue.len() >= self.config.queue_capacity {
+                self.stats.discarded.fetch_add(1, Ordering::Relaxed);
+                return false;
+            }
+            queue.push_back(ctx.clone());
+            queue.len()
+        };
+        if len >= self.config.max_batch_num {
+            self.notify.notify_one();
+        }
+        true
     }
 
     fn flush(&self) -> crate::Result<()> {
-        todo!()
+        self.notify.notify_one();
+        Ok(())
     }
 
     fn shutdown(&self) {
-        todo!()
+        self.stopped.store(true, Ordering::Release);
+        self.notify.notify_one();
     }
 
     fn as_any(&self) -> &dyn Any {
@@ -65,7 +223,129 @@ impl TraceDispatcher for AsyncTraceDispatcher {
 }
 
 impl AsyncTraceDispatcher {
-    pub fn set_host_producer(&mut self, host_producer: ArcRefCellWrapper<DefaultMQProducerImpl>) {}
+    pub fn set_host_producer(&mut self, host_producer: ArcRefCellWrapper<DefaultMQProducerImpl>) {
+        self.host_producer = Some(host_producer);
+    }
 
-    pub fn set_namespace_v2(&mut self, namespace_v2: Option<String>) {}
+    pub fn set_namespace_v2(&mut self, namespace_v2: Option<String>) {
+        self.namespace_v2 = namespace_v2;
+    }
+}
+
+struct TraceWorker {
+    trace_topic_name: String,
+    config: TraceDispatcherConfig,
+    queue: Arc<Mutex<VecDeque<TraceContext>>>,
+    stats: Arc<TraceDispatcherStats>,
+    notify: Arc<Notify>,
+    stopped: Arc<AtomicBool>,
+}
+
+impl TraceWorker {
+    async fn run(&self, producer: &mut DefaultMQProducer) {
+        loop {
+            if !self.stopped.load(Ordering::Acquire) {
+                tokio::select! {
+                    _ = self.notify.notified() => {}
+                    _ = tokio::time::sleep(self.config.flush_interval) => {}
+                }
+            }
+            let contexts: Vec<TraceContext> = self.queue.lock().drain(..).collect();
+            self.send_trace_data(producer, contexts).await;
+            if self.stopped.load(Ordering::Acquire) && self.queue.lock().is_empty() {
+                break;
+            }
+        }
+    }
+
+    async fn send_trace_data(&self, producer: &mut DefaultMQProducer, contexts: Vec<TraceContext>) {
+        let mut data = String::new();
+        let mut keys = HashSet::new();
+        let mut num = 0;
+        for ctx in contexts.iter() {
+            let Some(bean) = TraceDataEncoder::encoder_from_context_bean(ctx) else {
+                continue;
+            };
+            if num > 0
+                && (data.len() + bean.trans_data.len() > self.config.max_batch_bytes
+                    || num >= self.config.max_batch_num)
+            {
+                self.send_batch(producer, &mut data, &mut keys, &mut num)
+                    .await;
+            }
+            data.push_str(&bean.trans_data);
+            keys.extend(bean.trans_key);
+            num += 1;
+        }
+        if num > 0 {
+            self.send_batch(producer, &mut data, &mut keys, &mut num)
+                .await;
+        }
+    }
+
+    async fn send_batch(
+        &self,
+        producer: &mut DefaultMQProducer,
+        data: &mut String,
+        keys: &mut HashSet<String>,
+        num: &mut usize,
+    ) {
+        let keys = keys.drain().collect::<Vec<String>>().join(" ");
+        let message = Message::with_keys(
+            self.trace_topic_name.as_str(),
+            "",
+            keys,
+            std::mem::take(data).as_bytes(),
+        );
+        if let Err(e) = producer.send(message).await {
+            warn!("send {} trace contexts failed: {}", num, e);
+            self.stats
+                .send_failed
+                .fetch_add(*num as u64, Ordering::Relaxed);
+        }
+        *num = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trace::trace_bean::TraceBean;
+
+    fn dispatcher(config: TraceDispatcherConfig) -> AsyncTraceDispatcher {
+        AsyncTraceDispatcher::new("group", Type::Produce, "RMQ_SYS_TRACE_TOPIC", config, None)
+    }
+
+    fn context() -> TraceContext {
+        TraceContext {
+            trace_beans: vec![TraceBean::default()],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn append_samples_contexts() {
+        let dispatcher = dispatcher(TraceDispatcherConfig {
+            sampling_rate: 0.25,
+            ..Default::default()
+        });
+        for _ in 0..8 {
+            assert!(dispatcher.append(&context()));
+        }
+        assert_eq!(dispatcher.queue.lock().len(), 2);
+        assert_eq!(dispatcher.stats().sampled_out(), 6);
+    }
+
+    #[test]
+    fn append_drops_when_queue_is_full() {
+        let dispatcher = dispatcher(TraceDispatcherConfig {
+            queue_capacity: 2,
+            ..Default::default()
+        });
+        assert!(dispatcher.append(&context()));
+        assert!(dispatcher.append(&context()));
+        assert!(!dispatcher.append(&context()));
+        assert!(!dispatcher.append(&"not a trace context"));
+        assert_eq!(dispatcher.stats().discarded(), 1);
+    }
 }
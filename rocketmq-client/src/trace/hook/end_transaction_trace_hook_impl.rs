@@ -16,9 +16,18 @@
  */
 use std::sync::Arc;
 
+use rocketmq_common::common::message::message_enum::MessageType;
+use rocketmq_common::common::message::MessageTrait;
+use rocketmq_common::utils::network_util::NetworkUtil;
+use rocketmq_common::TimeUtils::get_current_millis;
+
 use crate::hook::end_transaction_context::EndTransactionContext;
 use crate::hook::end_transaction_hook::EndTransactionHook;
+use crate::trace::trace_bean::TraceBean;
+use crate::trace::trace_constants::TRACE_TOPIC_PREFIX;
+use crate::trace::trace_context::TraceContext;
 use crate::trace::trace_dispatcher::TraceDispatcher;
+use crate::trace::trace_type::TraceType;
 
 pub struct EndTransactionTraceHookImpl {
     trace_dispatcher: Arc<Box<dyn TraceDispatcher + Send + Sync>>,
@@ -36,6 +45,70 @@ impl EndTransactionHook for EndTransactionTraceHookImpl {
     }
 
     fn end_transaction(&self, context: &EndTransactionContext) {
-        todo!()
+        if let Some(trace_context) = end_transaction_trace_context(context) {
+            self.trace_dispatcher.append(&trace_context);
+        }
+    }
+}
+
+/// EndTransaction trace of `context`, `None` for the messages of the trace topic itself.
+fn end_transaction_trace_context(context: &EndTransactionContext) -> Option<TraceContext> {
+    let message = &context.message;
+    if message.get_topic().starts_with(TRACE_TOPIC_PREFIX) {
+        return None;
+    }
+    Some(TraceContext {
+        trace_type: TraceType::EndTransaction,
+        time_stamp: get_current_millis(),
+        group_name: context.producer_group.clone(),
+        trace_beans: vec![TraceBean {
+            topic: message.get_topic().to_string(),
+            msg_id: context.msg_id.clone(),
+            tags: message.get_tags().unwrap_or_default(),
+            keys: message.get_keys().unwrap_or_default(),
+            store_host: context.broker_addr.clone(),
+            client_host: NetworkUtil::get_local_address().unwrap_or_default(),
+            msg_type: MessageType::TransMsgCommit,
+            transaction_state: Some(context.transaction_state),
+            transaction_id: Some(context.transaction_id.clone()),
+            from_transaction_check: context.from_transaction_check,
+            check_times: context.check_times,
+            ..Default::default()
+        }],
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use rocketmq_common::common::message::message_single::Message;
+
+    use super::*;
+    use crate::producer::local_transaction_state::LocalTransactionState;
+
+    #[test]
+    fn traces_the_state_of_checked_transactions() {
+        let mut context = EndTransactionContext {
+            producer_group: "tx_group".to_string(),
+            broker_addr: "127.0.0.1:10911".to_string(),
+            message: Message::with_keys("TopicTest", "TagA", "order-1", b"body"),
+            msg_id: "msg-1".to_string(),
+            transaction_id: "tx-1".to_string(),
+            transaction_state: LocalTransactionState::CommitMessage,
+            from_transaction_check: true,
+            check_times: 2,
+        };
+        let trace_context = end_transaction_trace_context(&context).unwrap();
+        assert_eq!(trace_context.trace_type, TraceType::EndTransaction);
+        assert_eq!(trace_context.group_name, "tx_group");
+        let bean = &trace_context.trace_beans[0];
+        assert_eq!(bean.keys, "order-1");
+        assert_eq!(bean.store_host, "127.0.0.1:10911");
+        assert_eq!(bean.transaction_id.as_deref(), Some("tx-1"));
+        assert!(bean.from_transaction_check);
+        assert_eq!(bean.check_times, 2);
+
+        context.message = Message::new(format!("{}TraceTopic", TRACE_TOPIC_PREFIX), b"body");
+        assert!(end_transaction_trace_context(&context).is_none());
     }
 }
@@ -16,9 +16,18 @@
  */
 use std::sync::Arc;
 
+use rocketmq_common::common::message::message_enum::MessageType;
+use rocketmq_common::TimeUtils::get_current_millis;
+use rocketmq_remoting::protocol::namespace_util::NamespaceUtil;
+
 use crate::hook::send_message_context::SendMessageContext;
 use crate::hook::send_message_hook::SendMessageHook;
+use crate::producer::send_status::SendStatus;
+use crate::trace::trace_bean::TraceBean;
+use crate::trace::trace_constants::TRACE_TOPIC_PREFIX;
+use crate::trace::trace_context::TraceContext;
 use crate::trace::trace_dispatcher::TraceDispatcher;
+use crate::trace::trace_type::TraceType;
 
 pub struct SendMessageTraceHookImpl {
     trace_dispatcher: Arc<Box<dyn TraceDispatcher + Send + Sync>>,
@@ -29,16 +38,135 @@ impl SendMessageTraceHookImpl {
         Self { trace_dispatcher }
     }
 }
+
 impl SendMessageHook for SendMessageTraceHookImpl {
     fn hook_name(&self) -> &str {
-        todo!()
+        "SendMessageTraceHook"
     }
 
-    fn send_message_before(&self, context: &Option<SendMessageContext<'_>>) {
-        todo!()
+    fn send_message_before(&self, context: &mut Option<SendMessageContext<'_>>) {
+        let Some(context) = context.as_mut() else {
+            return;
+        };
+        if let Some(trace_context) = send_trace_context(context) {
+            context.mq_trace_context = Some(Arc::new(Box::new(trace_context)));
+        }
     }
 
     fn send_message_after(&self, context: &Option<SendMessageContext<'_>>) {
-        todo!()
+        if let Some(trace_context) = context.as_ref().and_then(complete_send_trace_context) {
+            self.trace_dispatcher.append(&trace_context);
+        }
+    }
+}
+
+/// Pub trace of the message in `context` started now, `None` for the messages of the trace
+/// topic itself.
+fn send_trace_context(context: &SendMessageContext<'_>) -> Option<TraceContext> {
+    let message = context.message.as_ref()?;
+    if message.get_topic().starts_with(TRACE_TOPIC_PREFIX) {
+        return None;
+    }
+    Some(TraceContext {
+        trace_type: TraceType::Pub,
+        time_stamp: get_current_millis(),
+        group_name: NamespaceUtil::without_namespace(
+            context.producer_group.as_deref().unwrap_or_default(),
+        ),
+        trace_beans: vec![TraceBean {
+            topic: NamespaceUtil::without_namespace(message.get_topic()),
+            tags: message.get_tags().unwrap_or_default(),
+            keys: message.get_keys().unwrap_or_default(),
+            store_host: context.broker_addr.clone().unwrap_or_default(),
+            body_length: message.get_body().map_or(0, |body| body.len() as i32),
+            msg_type: context.msg_type.unwrap_or(MessageType::NormalMsg),
+            ..Default::default()
+        }],
+        ..Default::default()
+    })
+}
+
+/// The trace `send_message_before` attached to `context`, completed with the send result.
+/// `None` if the send failed or the broker has tracing turned off.
+fn complete_send_trace_context(context: &SendMessageContext<'_>) -> Option<TraceContext> {
+    let mut trace_context = context
+        .mq_trace_context
+        .as_ref()?
+        .downcast_ref::<TraceContext>()?
+        .clone();
+    let send_result = context.send_result.as_ref()?;
+    if send_result.region_id.is_none() || !send_result.trace_on {
+        return None;
+    }
+    let cost_time = get_current_millis().saturating_sub(trace_context.time_stamp);
+    trace_context.cost_time = cost_time as i32;
+    trace_context.is_success = send_result.send_status == SendStatus::SendOk;
+    trace_context.region_id = send_result.region_id.clone().unwrap_or_default();
+    let store_time = (trace_context.time_stamp + cost_time / 2) as i64;
+    for bean in trace_context.trace_beans.iter_mut() {
+        bean.msg_id = send_result.msg_id.clone().unwrap_or_default();
+        bean.offset_msg_id = send_result.offset_msg_id.clone().unwrap_or_default();
+        bean.store_time = store_time;
+    }
+    Some(trace_context)
+}
+
+#[cfg(test)]
+mod tests {
+    use rocketmq_common::common::message::message_single::Message;
+
+    use super::*;
+    use crate::producer::send_result::SendResult;
+
+    #[test]
+    fn traces_the_send_result_of_the_message() {
+        let mut context = SendMessageContext {
+            producer_group: Some("ns%producer_group".to_string()),
+            message: Some(Box::new(Message::with_keys(
+                "ns%TopicTest",
+                "TagA",
+                "order-1",
+                b"body",
+            ))),
+            broker_addr: Some("127.0.0.1:10911".to_string()),
+            ..Default::default()
+        };
+        let trace_context = send_trace_context(&context).unwrap();
+        assert_eq!(trace_context.trace_type, TraceType::Pub);
+        assert_eq!(trace_context.group_name, "producer_group");
+        let bean = &trace_context.trace_beans[0];
+        assert_eq!(bean.topic, "TopicTest");
+        assert_eq!(bean.keys, "order-1");
+        assert_eq!(bean.body_length, 4);
+        assert_eq!(bean.msg_type, MessageType::NormalMsg);
+        context.mq_trace_context = Some(Arc::new(Box::new(trace_context)));
+
+        // a failed send has no result and is not traced
+        assert!(complete_send_trace_context(&context).is_none());
+
+        context.send_result = Some(SendResult {
+            send_status: SendStatus::SendOk,
+            msg_id: Some("msg-1".to_string()),
+            offset_msg_id: Some("offset-msg-1".to_string()),
+            region_id: Some("DefaultRegion".to_string()),
+            trace_on: true,
+            ..Default::default()
+        });
+        let trace_context = complete_send_trace_context(&context).unwrap();
+        assert!(trace_context.is_success);
+        assert_eq!(trace_context.region_id, "DefaultRegion");
+        let bean = &trace_context.trace_beans[0];
+        assert_eq!(bean.msg_id, "msg-1");
+        assert_eq!(bean.offset_msg_id, "offset-msg-1");
+        assert!(bean.store_time >= trace_context.time_stamp as i64);
+
+        context.send_result.as_mut().unwrap().trace_on = false;
+        assert!(complete_send_trace_context(&context).is_none());
+
+        context.message = Some(Box::new(Message::new(
+            format!("{}TraceTopic", TRACE_TOPIC_PREFIX),
+            b"body",
+        )));
+        assert!(send_trace_context(&context).is_none());
     }
 }
@@ -0,0 +1,40 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use rocketmq_common::common::message::message_enum::MessageType;
+
+use crate::producer::local_transaction_state::LocalTransactionState;
+
+/// Trace record of a single message.
+#[derive(Debug, Clone, Default)]
+pub struct TraceBean {
+    pub topic: String,
+    pub msg_id: String,
+    pub offset_msg_id: String,
+    pub tags: String,
+    pub keys: String,
+    pub store_host: String,
+    pub client_host: String,
+    pub store_time: i64,
+    pub retry_times: i32,
+    pub body_length: i32,
+    pub msg_type: MessageType,
+    pub transaction_state: Option<LocalTransactionState>,
+    pub transaction_id: Option<String>,
+    pub from_transaction_check: bool,
+    /// Times the broker checked the transaction when the state was sent.
+    pub check_times: i32,
+}
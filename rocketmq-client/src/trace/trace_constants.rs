@@ -0,0 +1,22 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+pub const GROUP_NAME_PREFIX: &str = "_INNER_TRACE_PRODUCER-";
+/// Prefix of the trace topics, the messages sent to them are not traced themselves.
+pub const TRACE_TOPIC_PREFIX: &str = "rmq_sys_TRACE_DATA_";
+pub const TRACE_INSTANCE_NAME: &str = "PID_CLIENT_INNER_TRACE_PRODUCER";
+pub const CONTENT_SPLITOR: char = '\u{1}';
+pub const FIELD_SPLITOR: char = '\u{2}';
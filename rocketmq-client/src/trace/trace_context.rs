@@ -0,0 +1,33 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use crate::trace::trace_bean::TraceBean;
+use crate::trace::trace_type::TraceType;
+
+/// One traced operation, such as a send or a consume, covering one or more messages.
+#[derive(Debug, Clone, Default)]
+pub struct TraceContext {
+    pub trace_type: TraceType,
+    pub time_stamp: u64,
+    pub region_id: String,
+    pub region_name: String,
+    pub group_name: String,
+    pub cost_time: i32,
+    pub is_success: bool,
+    pub request_id: String,
+    pub context_code: i32,
+    pub trace_beans: Vec<TraceBean>,
+}
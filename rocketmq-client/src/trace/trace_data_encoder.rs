@@ -0,0 +1,413 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::collections::HashSet;
+use std::fmt::Write;
+use std::str::FromStr;
+
+use rocketmq_common::common::message::message_enum::MessageType;
+
+use crate::producer::local_transaction_state::LocalTransactionState;
+use crate::trace::trace_bean::TraceBean;
+use crate::trace::trace_constants::CONTENT_SPLITOR;
+use crate::trace::trace_constants::FIELD_SPLITOR;
+use crate::trace::trace_context::TraceContext;
+use crate::trace::trace_type::TraceType;
+
+/// Encoded trace data of one context, and the keys the trace message is indexed by.
+#[derive(Debug, Clone, Default)]
+pub struct TraceTransferBean {
+    pub trans_data: String,
+    pub trans_key: HashSet<String>,
+}
+
+pub struct TraceDataEncoder;
+
+impl TraceDataEncoder {
+    /// Encodes `ctx` in the layout the broker side trace tools parse. `None` when the context
+    /// carries no trace beans.
+    pub fn encoder_from_context_bean(ctx: &TraceContext) -> Option<TraceTransferBean> {
+        if ctx.trace_beans.is_empty() {
+            return None;
+        }
+        let mut trans_data = String::new();
+        let c = CONTENT_SPLITOR;
+        match ctx.trace_type {
+            TraceType::Pub => {
+                let bean = &ctx.trace_beans[0];
+                let _ = write!(
+                    trans_data,
+                    "{}{c}{}{c}{}{c}{}{c}{}{c}{}{c}{}{c}{}{c}{}{c}{}{c}{}{c}{}{c}{}{c}{}{FIELD_SPLITOR}",
+                    ctx.trace_type,
+                    ctx.time_stamp,
+                    ctx.region_id,
+                    ctx.group_name,
+                    bean.topic,
+                    bean.msg_id,
+                    bean.tags,
+                    bean.keys,
+                    bean.store_host,
+                    bean.body_length,
+                    ctx.cost_time,
+                    message_type_ordinal(bean.msg_type),
+                    bean.offset_msg_id,
+                    ctx.is_success,
+                );
+            }
+            TraceType::SubBefore => {
+                for bean in ctx.trace_beans.iter() {
+                    let _ = write!(
+                        trans_data,
+                        "{}{c}{}{c}{}{c}{}{c}{}{c}{}{c}{}{c}{}{FIELD_SPLITOR}",
+                        ctx.trace_type,
+                        ctx.time_stamp,
+                        ctx.region_id,
+                        ctx.group_name,
+                        ctx.request_id,
+                        bean.msg_id,
+                        bean.retry_times,
+                        bean.keys,
+                    );
+                }
+            }
+            TraceType::SubAfter => {
+                for bean in ctx.trace_beans.iter() {
+                    let _ = write!(
+                        trans_data,
+                        "{}{c}{}{c}{}{c}{}{c}{}{c}{}{c}{}{c}{}{c}{}{FIELD_SPLITOR}",
+                        ctx.trace_type,
+                        ctx.request_id,
+                        bean.msg_id,
+                        ctx.cost_time,
+                        ctx.is_success,
+                        bean.keys,
+                        ctx.context_code,
+                        ctx.time_stamp,
+                        ctx.group_name,
+                    );
+                }
+            }
+            TraceType::EndTransaction => {
+                let bean = &ctx.trace_beans[0];
+                let _ = write!(
+                    trans_data,
+                    "{}{c}{}{c}{}{c}{}{c}{}{c}{}{c}{}{c}{}{c}{}{c}{}{c}{}{c}{}{c}{}{c}{}{FIELD_SPLITOR}",
+                    ctx.trace_type,
+                    ctx.time_stamp,
+                    ctx.region_id,
+                    ctx.group_name,
+                    bean.topic,
+                    bean.msg_id,
+                    bean.tags,
+                    bean.keys,
+                    bean.store_host,
+                    message_type_ordinal(bean.msg_type),
+                    bean.transaction_id.as_deref().unwrap_or_default(),
+                    bean.transaction_state
+                        .map(transaction_state_name)
+                        .unwrap_or_default(),
+                    bean.from_transaction_check,
+                    bean.check_times,
+                );
+            }
+        }
+
+        let mut trans_key = HashSet::new();
+        for bean in ctx.trace_beans.iter() {
+            trans_key.insert(bean.msg_id.clone());
+            trans_key.extend(
+                bean.keys
+                    .split(' ')
+                    .filter(|key| !key.is_empty())
+                    .map(str::to_string),
+            );
+        }
+        Some(TraceTransferBean {
+            trans_data,
+            trans_key,
+        })
+    }
+
+    /// Decodes the trace data of a trace message back into its contexts, one per encoded
+    /// record. Records of an unknown type or with too few fields are skipped.
+    pub fn decoder_from_trace_data_string(trace_data: &str) -> Vec<TraceContext> {
+        trace_data
+            .split(FIELD_SPLITOR)
+            .filter_map(|record| {
+                let fields: Vec<&str> = record.split(CONTENT_SPLITOR).collect();
+                decode_record(&fields)
+            })
+            .collect()
+    }
+}
+
+fn decode_record(fields: &[&str]) -> Option<TraceContext> {
+    let field = |index: usize| fields.get(index).copied().unwrap_or_default().to_string();
+    let flag = |index: usize| fields.get(index).is_some_and(|value| *value == "true");
+    match *fields.first()? {
+        "Pub" if fields.len() >= 14 => Some(TraceContext {
+            trace_type: TraceType::Pub,
+            time_stamp: parse_field(fields, 1)?,
+            region_id: field(2),
+            group_name: field(3),
+            cost_time: parse_field(fields, 10).unwrap_or_default(),
+            is_success: flag(13),
+            trace_beans: vec![TraceBean {
+                topic: field(4),
+                msg_id: field(5),
+                tags: field(6),
+                keys: field(7),
+                store_host: field(8),
+                body_length: parse_field(fields, 9).unwrap_or_default(),
+                msg_type: message_type_from_ordinal(parse_field(fields, 11)?),
+                offset_msg_id: field(12),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }),
+        "SubBefore" if fields.len() >= 8 => Some(TraceContext {
+            trace_type: TraceType::SubBefore,
+            time_stamp: parse_field(fields, 1)?,
+            region_id: field(2),
+            group_name: field(3),
+            request_id: field(4),
+            trace_beans: vec![TraceBean {
+                msg_id: field(5),
+                retry_times: parse_field(fields, 6).unwrap_or_default(),
+                keys: field(7),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }),
+        "SubAfter" if fields.len() >= 9 => Some(TraceContext {
+            trace_type: TraceType::SubAfter,
+            request_id: field(1),
+            cost_time: parse_field(fields, 3).unwrap_or_default(),
+            is_success: flag(4),
+            context_code: parse_field(fields, 6).unwrap_or_default(),
+            time_stamp: parse_field(fields, 7)?,
+            group_name: field(8),
+            trace_beans: vec![TraceBean {
+                msg_id: field(2),
+                keys: field(5),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }),
+        "EndTransaction" if fields.len() >= 14 => Some(TraceContext {
+            trace_type: TraceType::EndTransaction,
+            time_stamp: parse_field(fields, 1)?,
+            region_id: field(2),
+            group_name: field(3),
+            is_success: true,
+            trace_beans: vec![TraceBean {
+                topic: field(4),
+                msg_id: field(5),
+                tags: field(6),
+                keys: field(7),
+                store_host: field(8),
+                msg_type: message_type_from_ordinal(parse_field(fields, 9)?),
+                transaction_id: Some(field(10)).filter(|id| !id.is_empty()),
+                transaction_state: transaction_state_from_name(fields[11]),
+                from_transaction_check: flag(12),
+                check_times: parse_field(fields, 13).unwrap_or_default(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }),
+        _ => None,
+    }
+}
+
+fn message_type_ordinal(msg_type: MessageType) -> i32 {
+    match msg_type {
+        MessageType::NormalMsg => 0,
+        MessageType::TransMsgHalf => 1,
+        MessageType::TransMsgCommit => 2,
+        MessageType::DelayMsg => 3,
+        MessageType::OrderMsg => 4,
+    }
+}
+
+fn parse_field<T: FromStr>(fields: &[&str], index: usize) -> Option<T> {
+    fields.get(index).and_then(|value| value.parse().ok())
+}
+
+fn message_type_from_ordinal(ordinal: i32) -> MessageType {
+    match ordinal {
+        1 => MessageType::TransMsgHalf,
+        2 => MessageType::TransMsgCommit,
+        3 => MessageType::DelayMsg,
+        4 => MessageType::OrderMsg,
+        _ => MessageType::NormalMsg,
+    }
+}
+
+fn transaction_state_from_name(name: &str) -> Option<LocalTransactionState> {
+    match name {
+        "COMMIT_MESSAGE" => Some(LocalTransactionState::CommitMessage),
+        "ROLLBACK_MESSAGE" => Some(LocalTransactionState::RollbackMessage),
+        "UNKNOW" => Some(LocalTransactionState::Unknown),
+        _ => None,
+    }
+}
+
+fn transaction_state_name(transaction_state: LocalTransactionState) -> &'static str {
+    match transaction_state {
+        LocalTransactionState::CommitMessage => "COMMIT_MESSAGE",
+        LocalTransactionState::RollbackMessage => "ROLLBACK_MESSAGE",
+        LocalTransactionState::Unknown => "UNKNOW",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_pub_context() {
+        let ctx = TraceContext {
+            trace_type: TraceType::Pub,
+            time_stamp: 1000,
+            region_id: "DefaultRegion".to_string(),
+            group_name: "group".to_string(),
+            cost_time: 5,
+            is_success: true,
+            trace_beans: vec![TraceBean {
+                topic: "TopicTest".to_string(),
+                msg_id: "msg-1".to_string(),
+                keys: "k1 k2".to_string(),
+                body_length: 16,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let bean = TraceDataEncoder::encoder_from_context_bean(&ctx).unwrap();
+        let fields: Vec<&str> = bean
+            .trans_data
+            .trim_end_matches(FIELD_SPLITOR)
+            .split(CONTENT_SPLITOR)
+            .collect();
+        assert_eq!(fields.len(), 14);
+        assert_eq!(fields[0], "Pub");
+        assert_eq!(fields[4], "TopicTest");
+        assert_eq!(fields[13], "true");
+        assert_eq!(
+            bean.trans_key,
+            HashSet::from(["msg-1".to_string(), "k1".to_string(), "k2".to_string()])
+        );
+    }
+
+    #[test]
+    fn encode_end_transaction_context() {
+        let ctx = TraceContext {
+            trace_type: TraceType::EndTransaction,
+            group_name: "tx_group".to_string(),
+            trace_beans: vec![TraceBean {
+                topic: "TopicTest".to_string(),
+                msg_id: "msg-1".to_string(),
+                msg_type: MessageType::TransMsgCommit,
+                transaction_id: Some("tx-1".to_string()),
+                transaction_state: Some(LocalTransactionState::RollbackMessage),
+                from_transaction_check: true,
+                check_times: 3,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let bean = TraceDataEncoder::encoder_from_context_bean(&ctx).unwrap();
+        let fields: Vec<&str> = bean
+            .trans_data
+            .trim_end_matches(FIELD_SPLITOR)
+            .split(CONTENT_SPLITOR)
+            .collect();
+        assert_eq!(fields.len(), 14);
+        assert_eq!(fields[0], "EndTransaction");
+        assert_eq!(fields[9], "2");
+        assert_eq!(fields[10], "tx-1");
+        assert_eq!(fields[11], "ROLLBACK_MESSAGE");
+        assert_eq!(fields[12], "true");
+        assert_eq!(fields[13], "3");
+    }
+
+    #[test]
+    fn encode_empty_context() {
+        assert!(TraceDataEncoder::encoder_from_context_bean(&TraceContext::default()).is_none());
+    }
+
+    #[test]
+    fn decode_encoded_contexts() {
+        let bean = TraceBean {
+            topic: "TopicTest".to_string(),
+            msg_id: "msg-1".to_string(),
+            keys: "order-1".to_string(),
+            store_host: "127.0.0.1:10911".to_string(),
+            msg_type: MessageType::TransMsgCommit,
+            transaction_id: Some("tx-1".to_string()),
+            transaction_state: Some(LocalTransactionState::CommitMessage),
+            check_times: 2,
+            ..Default::default()
+        };
+        let contexts = [
+            TraceContext {
+                trace_type: TraceType::Pub,
+                time_stamp: 1000,
+                group_name: "producer".to_string(),
+                cost_time: 3,
+                is_success: true,
+                trace_beans: vec![bean.clone()],
+                ..Default::default()
+            },
+            TraceContext {
+                trace_type: TraceType::EndTransaction,
+                time_stamp: 1200,
+                group_name: "producer".to_string(),
+                trace_beans: vec![bean.clone()],
+                ..Default::default()
+            },
+            TraceContext {
+                trace_type: TraceType::SubAfter,
+                time_stamp: 1500,
+                group_name: "consumer".to_string(),
+                request_id: "req-1".to_string(),
+                cost_time: 7,
+                trace_beans: vec![bean],
+                ..Default::default()
+            },
+        ];
+        let trace_data: String = contexts
+            .iter()
+            .filter_map(TraceDataEncoder::encoder_from_context_bean)
+            .map(|bean| bean.trans_data)
+            .collect();
+
+        let decoded = TraceDataEncoder::decoder_from_trace_data_string(&trace_data);
+        assert_eq!(decoded.len(), 3);
+        assert_eq!(decoded[0].trace_type, TraceType::Pub);
+        assert_eq!(decoded[0].cost_time, 3);
+        assert_eq!(decoded[0].trace_beans[0].store_host, "127.0.0.1:10911");
+        assert_eq!(
+            decoded[1].trace_beans[0].transaction_state,
+            Some(LocalTransactionState::CommitMessage)
+        );
+        assert_eq!(decoded[1].trace_beans[0].check_times, 2);
+        assert_eq!(decoded[2].trace_type, TraceType::SubAfter);
+        assert_eq!(decoded[2].group_name, "consumer");
+        assert!(!decoded[2].is_success);
+        assert_eq!(decoded[2].trace_beans[0].msg_id, "msg-1");
+        assert!(TraceDataEncoder::decoder_from_trace_data_string("Pub\u{1}1").is_empty());
+    }
+}
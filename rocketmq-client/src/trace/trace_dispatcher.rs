@@ -15,6 +15,7 @@
  * limitations under the License.
  */
 use std::any::Any;
+use std::time::Duration;
 
 use crate::base::access_channel::AccessChannel;
 use crate::Result;
@@ -24,6 +25,40 @@ pub enum Type {
     Consume,
 }
 
+/// Tuning of the trace dispatcher, so tracing never becomes the bottleneck of a high TPS
+/// producer.
+#[derive(Debug, Clone)]
+pub struct TraceDispatcherConfig {
+    /// Fraction of the trace contexts that are dispatched, in `0.0..=1.0`.
+    pub sampling_rate: f64,
+    /// Contexts waiting to be sent; once full, newly appended contexts are dropped.
+    pub queue_capacity: usize,
+    /// Upper bound on the body of one trace message.
+    pub max_batch_bytes: usize,
+    /// Upper bound on the contexts packed into one trace message.
+    pub max_batch_num: usize,
+    /// How long a context may wait for its batch to fill up.
+    pub flush_interval: Duration,
+    /// Name server of a dedicated trace cluster, defaults to the one of the host client.
+    pub trace_namesrv_addr: Option<String>,
+    /// Cluster the trace topic is created in when it does not exist yet.
+    pub trace_cluster: Option<String>,
+}
+
+impl Default for TraceDispatcherConfig {
+    fn default() -> Self {
+        Self {
+            sampling_rate: 1.0,
+            queue_capacity: 2048,
+            max_batch_bytes: 128 * 1024,
+            max_batch_num: 100,
+            flush_interval: Duration::from_millis(500),
+            trace_namesrv_addr: None,
+            trace_cluster: None,
+        }
+    }
+}
+
 pub trait TraceDispatcher: Any {
     fn start(&self, name_srv_addr: &str, access_channel: AccessChannel) -> Result<()>;
     fn append(&self, ctx: &dyn std::any::Any) -> bool;
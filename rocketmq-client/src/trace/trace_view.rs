@@ -0,0 +1,199 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use rocketmq_remoting::protocol::body::query_message_trace_body::MessageTraceRecord;
+
+use crate::producer::local_transaction_state::LocalTransactionState;
+use crate::trace::trace_data_encoder::TraceDataEncoder;
+use crate::trace::trace_type::TraceType;
+
+/// Which messages of the trace records a timeline is assembled for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TraceFilter {
+    MsgId(String),
+    Key(String),
+}
+
+/// One step of the life of a message, sent, transaction ended, consume started or consume
+/// finished, as a client traced it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceView {
+    pub trace_type: TraceType,
+    pub time_stamp: u64,
+    pub group_name: String,
+    /// Address of the client that traced the step.
+    pub client_host: String,
+    pub store_host: String,
+    pub topic: String,
+    pub msg_id: String,
+    pub keys: String,
+    pub cost_time: i32,
+    pub status: String,
+    pub detail: String,
+}
+
+impl TraceView {
+    /// Decodes the trace records read from the trace topic and returns the steps of the
+    /// messages matching `filter`, ordered in time, send before store before consume when the
+    /// clocks of the clients agree on the millisecond.
+    pub fn timeline(records: &[MessageTraceRecord], filter: &TraceFilter) -> Vec<TraceView> {
+        let mut views: Vec<TraceView> = records
+            .iter()
+            .flat_map(|record| {
+                TraceDataEncoder::decoder_from_trace_data_string(&record.trace_data)
+                    .into_iter()
+                    .flat_map(move |ctx| {
+                        ctx.trace_beans
+                            .iter()
+                            .filter(|bean| match filter {
+                                TraceFilter::MsgId(msg_id) => bean.msg_id == *msg_id,
+                                TraceFilter::Key(key) => bean.keys.split(' ').any(|k| k == key),
+                            })
+                            .map(|bean| TraceView {
+                                trace_type: ctx.trace_type,
+                                time_stamp: ctx.time_stamp,
+                                group_name: ctx.group_name.clone(),
+                                client_host: record.born_host.clone(),
+                                store_host: bean.store_host.clone(),
+                                topic: bean.topic.clone(),
+                                msg_id: bean.msg_id.clone(),
+                                keys: bean.keys.clone(),
+                                cost_time: ctx.cost_time,
+                                status: match ctx.trace_type {
+                                    TraceType::SubBefore => "consuming".to_string(),
+                                    TraceType::EndTransaction => match bean.transaction_state {
+                                        Some(LocalTransactionState::CommitMessage) => "commit",
+                                        Some(LocalTransactionState::RollbackMessage) => "rollback",
+                                        _ => "unknown",
+                                    }
+                                    .to_string(),
+                                    _ if ctx.is_success => "success".to_string(),
+                                    _ => "failed".to_string(),
+                                },
+                                detail: match ctx.trace_type {
+                                    TraceType::Pub => format!(
+                                        "{:?}, {} bytes, offset msg id {}",
+                                        bean.msg_type, bean.body_length, bean.offset_msg_id
+                                    ),
+                                    TraceType::SubBefore => {
+                                        format!("retry times {}", bean.retry_times)
+                                    }
+                                    TraceType::SubAfter => {
+                                        format!("context code {}", ctx.context_code)
+                                    }
+                                    TraceType::EndTransaction if bean.from_transaction_check => {
+                                        format!(
+                                            "transaction {}, check {}",
+                                            bean.transaction_id.as_deref().unwrap_or_default(),
+                                            bean.check_times
+                                        )
+                                    }
+                                    TraceType::EndTransaction => format!(
+                                        "transaction {}",
+                                        bean.transaction_id.as_deref().unwrap_or_default()
+                                    ),
+                                },
+                            })
+                            .collect::<Vec<_>>()
+                    })
+            })
+            .collect();
+        views.sort_by_key(|view| (view.time_stamp, phase(view.trace_type)));
+        views
+    }
+}
+
+fn phase(trace_type: TraceType) -> u8 {
+    match trace_type {
+        TraceType::Pub => 0,
+        TraceType::EndTransaction => 1,
+        TraceType::SubBefore => 2,
+        TraceType::SubAfter => 3,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trace::trace_bean::TraceBean;
+    use crate::trace::trace_context::TraceContext;
+
+    fn record(born_host: &str, contexts: &[TraceContext]) -> MessageTraceRecord {
+        MessageTraceRecord {
+            born_host: born_host.to_string(),
+            trace_data: contexts
+                .iter()
+                .filter_map(TraceDataEncoder::encoder_from_context_bean)
+                .map(|bean| bean.trans_data)
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    fn context(trace_type: TraceType, time_stamp: u64, msg_id: &str) -> TraceContext {
+        TraceContext {
+            trace_type,
+            time_stamp,
+            is_success: true,
+            trace_beans: vec![TraceBean {
+                topic: "TopicTest".to_string(),
+                msg_id: msg_id.to_string(),
+                keys: "order-1".to_string(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn timeline_orders_the_steps_of_the_message() {
+        let records = [
+            record(
+                "10.0.0.2:5000",
+                &[
+                    context(TraceType::SubAfter, 2000, "msg-1"),
+                    context(TraceType::SubBefore, 2000, "msg-1"),
+                ],
+            ),
+            record(
+                "10.0.0.1:5000",
+                &[
+                    context(TraceType::Pub, 1000, "msg-1"),
+                    context(TraceType::Pub, 1100, "msg-2"),
+                ],
+            ),
+        ];
+
+        let timeline = TraceView::timeline(&records, &TraceFilter::MsgId("msg-1".to_string()));
+        let steps: Vec<(TraceType, &str)> = timeline
+            .iter()
+            .map(|view| (view.trace_type, view.client_host.as_str()))
+            .collect();
+        assert_eq!(
+            steps,
+            vec![
+                (TraceType::Pub, "10.0.0.1:5000"),
+                (TraceType::SubBefore, "10.0.0.2:5000"),
+                (TraceType::SubAfter, "10.0.0.2:5000"),
+            ]
+        );
+        assert_eq!(timeline[1].status, "consuming");
+        assert_eq!(timeline[2].status, "success");
+
+        let by_key = TraceView::timeline(&records, &TraceFilter::Key("order-1".to_string()));
+        assert_eq!(by_key.len(), 4);
+    }
+}
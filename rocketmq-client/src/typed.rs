@@ -0,0 +1,306 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::marker::PhantomData;
+
+use rocketmq_common::common::message::message_ext::MessageExt;
+use rocketmq_common::common::message::message_single::Message;
+use rocketmq_common::common::message::MessageConst;
+use rocketmq_common::common::message::MessageTrait;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tracing::warn;
+
+use crate::error::MQClientError;
+use crate::producer::default_mq_producer::DefaultMQProducer;
+use crate::producer::mq_producer::MQProducer;
+use crate::proto::PROPERTY_CONTENT_TYPE;
+use crate::Result;
+
+/// Version of the schema the payload of a message was written with, set by the application.
+pub const PROPERTY_SCHEMA_VERSION: &str = "SCHEMA_VERSION";
+/// Why a message was sent to the parking topic.
+pub const PROPERTY_PARKING_REASON: &str = "PARKING_REASON";
+/// Topic and id of the message a parked message is a copy of.
+pub const PROPERTY_PARKING_ORIGIN_TOPIC: &str = "PARKING_ORIGIN_TOPIC";
+pub const PROPERTY_PARKING_ORIGIN_MSG_ID: &str = "PARKING_ORIGIN_MSG_ID";
+
+/// Encodings of typed payloads, told apart by the [`PROPERTY_CONTENT_TYPE`] of the message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentType {
+    Json,
+    Cbor,
+}
+
+impl ContentType {
+    pub fn mime(&self) -> &'static str {
+        match self {
+            ContentType::Json => "application/json",
+            ContentType::Cbor => "application/cbor",
+        }
+    }
+
+    pub fn from_mime(mime: &str) -> Option<ContentType> {
+        match mime {
+            "application/json" => Some(ContentType::Json),
+            "application/cbor" => Some(ContentType::Cbor),
+            _ => None,
+        }
+    }
+
+    pub fn serialize<T: Serialize>(&self, payload: &T) -> Result<Vec<u8>> {
+        let result = match self {
+            ContentType::Json => serde_json::to_vec(payload).map_err(|e| e.to_string()),
+            ContentType::Cbor => {
+                let mut body = Vec::new();
+                ciborium::into_writer(payload, &mut body)
+                    .map(|_| body)
+                    .map_err(|e| e.to_string())
+            }
+        };
+        result.map_err(|e| {
+            MQClientError::PayloadSerialization(format!(
+                "encode {} as {} failed: {}",
+                std::any::type_name::<T>(),
+                self.mime(),
+                e
+            ))
+        })
+    }
+
+    pub fn deserialize<T: DeserializeOwned>(&self, body: &[u8]) -> Result<T> {
+        let result = match self {
+            ContentType::Json => serde_json::from_slice(body).map_err(|e| e.to_string()),
+            ContentType::Cbor => ciborium::from_reader(body).map_err(|e| e.to_string()),
+        };
+        result.map_err(|e| {
+            MQClientError::PayloadSerialization(format!(
+                "decode {} from {} failed: {}",
+                std::any::type_name::<T>(),
+                self.mime(),
+                e
+            ))
+        })
+    }
+}
+
+/// Message to `topic` carrying `payload` encoded as `content_type`, with the content type and,
+/// if given, the schema version set as properties.
+pub fn encode_typed<T: Serialize>(
+    topic: impl Into<String>,
+    content_type: ContentType,
+    schema_version: Option<&str>,
+    payload: &T,
+) -> Result<Message> {
+    let mut msg = Message::new(topic, content_type.serialize(payload)?.as_slice());
+    msg.put_property(PROPERTY_CONTENT_TYPE, content_type.mime());
+    if let Some(schema_version) = schema_version {
+        msg.put_property(PROPERTY_SCHEMA_VERSION, schema_version);
+    }
+    Ok(msg)
+}
+
+/// Decodes the payload of `msg` with the encoding named by its content type, JSON when it has
+/// none.
+pub fn decode_typed<T: DeserializeOwned, M: MessageTrait>(msg: &M) -> Result<T> {
+    let content_type = match msg.get_property(PROPERTY_CONTENT_TYPE) {
+        None => ContentType::Json,
+        Some(mime) => ContentType::from_mime(mime.as_str()).ok_or_else(|| {
+            MQClientError::PayloadSerialization(format!(
+                "message of topic {} has unsupported content type {}",
+                msg.get_topic(),
+                mime
+            ))
+        })?,
+    };
+    content_type.deserialize(msg.get_body().map(|body| body.as_ref()).unwrap_or_default())
+}
+
+/// Listener of the messages of a topic with typed payloads.
+pub trait TypedMessageListener<T>: Send + Sync {
+    /// Consumes `payload`, decoded from `msg`. An error has the message consumed again later.
+    fn consume(&self, msg: &MessageExt, payload: T) -> Result<()>;
+}
+
+/// What [`TypedListenerAdapter::consume`] did with a list of messages.
+#[derive(Debug, Default)]
+pub struct TypedConsumeReport {
+    pub consumed: usize,
+    /// Messages whose payload did not decode and were sent to the parking topic.
+    pub parked: usize,
+    /// Messages to consume again later: the listener failed on them, or they did not decode
+    /// and could not be parked.
+    pub failed: Vec<MessageExt>,
+}
+
+/// Adapts a [`TypedMessageListener`] to raw messages: decodes each payload with
+/// [`decode_typed`] and hands it to the listener. A payload that does not decode will not on a
+/// retry either, so with a parking topic set the message is sent there instead of being
+/// retried, keeping the body and the reason in [`PROPERTY_PARKING_REASON`].
+pub struct TypedListenerAdapter<T, L> {
+    listener: L,
+    parking_topic: Option<String>,
+    _payload: PhantomData<fn() -> T>,
+}
+
+impl<T: DeserializeOwned, L: TypedMessageListener<T>> TypedListenerAdapter<T, L> {
+    pub fn new(listener: L) -> Self {
+        Self {
+            listener,
+            parking_topic: None,
+            _payload: PhantomData,
+        }
+    }
+
+    pub fn parking_topic(mut self, parking_topic: impl Into<String>) -> Self {
+        self.parking_topic = Some(parking_topic.into());
+        self
+    }
+
+    /// Consumes `msgs`, sending the ones that do not decode to the parking topic with
+    /// `producer`.
+    pub async fn consume(
+        &self,
+        producer: &mut DefaultMQProducer,
+        msgs: &[MessageExt],
+    ) -> TypedConsumeReport {
+        let mut report = TypedConsumeReport::default();
+        for msg in msgs {
+            let payload = match decode_typed::<T, _>(msg) {
+                Ok(payload) => payload,
+                Err(e) => {
+                    let Some(parking_topic) = self.parking_topic.as_deref() else {
+                        warn!("message {} does not decode: {}", msg.msg_id(), e);
+                        report.failed.push(msg.clone());
+                        continue;
+                    };
+                    match producer
+                        .send(parking_message(parking_topic, msg, e.to_string().as_str()))
+                        .await
+                    {
+                        Ok(_) => report.parked += 1,
+                        Err(send_error) => {
+                            warn!(
+                                "park message {} to {} failed: {}",
+                                msg.msg_id(),
+                                parking_topic,
+                                send_error
+                            );
+                            report.failed.push(msg.clone());
+                        }
+                    }
+                    continue;
+                }
+            };
+            match self.listener.consume(msg, payload) {
+                Ok(()) => report.consumed += 1,
+                Err(_) => report.failed.push(msg.clone()),
+            }
+        }
+        report
+    }
+}
+
+/// Copy of `msg` for `parking_topic`, keeping its body, tags and keys.
+fn parking_message(parking_topic: &str, msg: &MessageExt, reason: &str) -> Message {
+    let mut parked = Message::new(
+        parking_topic,
+        msg.get_body().map(|body| body.as_ref()).unwrap_or_default(),
+    );
+    for name in [
+        PROPERTY_CONTENT_TYPE,
+        PROPERTY_SCHEMA_VERSION,
+        MessageConst::PROPERTY_TAGS,
+        MessageConst::PROPERTY_KEYS,
+    ] {
+        if let Some(value) = msg.get_property(name) {
+            parked.put_property(name, value.as_str());
+        }
+    }
+    parked.put_property(PROPERTY_PARKING_REASON, reason);
+    parked.put_property(PROPERTY_PARKING_ORIGIN_TOPIC, msg.get_topic());
+    parked.put_property(PROPERTY_PARKING_ORIGIN_MSG_ID, msg.msg_id());
+    parked
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct OrderCreated {
+        order_id: String,
+        amount: u64,
+    }
+
+    fn consumed(msg: Message) -> MessageExt {
+        let mut msg_ext = MessageExt::default();
+        msg_ext.set_topic(msg.get_topic());
+        msg_ext.set_body(msg.get_body().cloned().unwrap_or_default());
+        for (name, value) in msg.get_properties() {
+            msg_ext.put_property(name, value);
+        }
+        msg_ext
+    }
+
+    #[test]
+    fn payloads_round_trip_in_the_negotiated_encoding() {
+        let order = OrderCreated {
+            order_id: "o-1".to_string(),
+            amount: 42,
+        };
+        for content_type in [ContentType::Json, ContentType::Cbor] {
+            let msg = encode_typed("Orders", content_type, Some("2"), &order).unwrap();
+            assert_eq!(
+                msg.get_property(PROPERTY_CONTENT_TYPE).unwrap(),
+                content_type.mime()
+            );
+            assert_eq!(msg.get_property(PROPERTY_SCHEMA_VERSION).unwrap(), "2");
+            assert_eq!(decode_typed::<OrderCreated, _>(&msg).unwrap(), order);
+        }
+
+        let bare = Message::new("Orders", br#"{"order_id":"o-1","amount":42}"#);
+        assert_eq!(decode_typed::<OrderCreated, _>(&bare).unwrap(), order);
+
+        let mut xml = Message::new("Orders", b"<order/>");
+        xml.put_property(PROPERTY_CONTENT_TYPE, "application/xml");
+        assert!(matches!(
+            decode_typed::<OrderCreated, _>(&xml),
+            Err(MQClientError::PayloadSerialization(_))
+        ));
+    }
+
+    #[test]
+    fn parking_message_keeps_the_body_and_the_reason() {
+        let mut msg = Message::with_tags("Orders", "created", b"not json");
+        msg.put_property(PROPERTY_CONTENT_TYPE, ContentType::Json.mime());
+        let msg = consumed(msg);
+        let parked = parking_message("Orders_PARKING", &msg, "bad payload");
+        assert_eq!(parked.get_topic(), "Orders_PARKING");
+        assert_eq!(parked.get_body().unwrap().as_ref(), b"not json");
+        assert_eq!(parked.get_tags().unwrap(), "created");
+        assert_eq!(
+            parked.get_property(PROPERTY_PARKING_REASON).unwrap(),
+            "bad payload"
+        );
+        assert_eq!(
+            parked.get_property(PROPERTY_PARKING_ORIGIN_TOPIC).unwrap(),
+            "Orders"
+        );
+    }
+}
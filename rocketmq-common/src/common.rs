@@ -45,6 +45,7 @@ pub mod message;
 pub mod mix_all;
 pub mod mq_version;
 pub mod namesrv;
+pub mod offset;
 pub mod pop_ack_constants;
 
 pub mod running;
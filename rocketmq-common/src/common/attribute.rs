@@ -19,6 +19,7 @@ pub mod attribute_parser;
 pub mod attribute_util;
 pub mod cleanup_policy;
 pub mod cq_type;
+pub mod delay_mode;
 pub mod topic_attributes;
 pub mod topic_message_type;
 
@@ -46,7 +47,10 @@ pub trait AttributeTrait {
     ///
     /// # Arguments
     /// * `value` - A string slice representing the value to be verified.
-    fn verify(&self, value: &str);
+    ///
+    /// # Returns
+    /// `true` when `value` is a valid value of the attribute.
+    fn verify(&self, value: &str) -> bool;
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
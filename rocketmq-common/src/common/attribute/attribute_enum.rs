@@ -35,8 +35,8 @@ impl AttributeTrait for EnumAttribute {
         self.attribute.changeable
     }
 
-    fn verify(&self, _value: &str) {
-        todo!()
+    fn verify(&self, value: &str) -> bool {
+        self.universe.contains(value)
     }
 }
 
@@ -52,8 +52,4 @@ impl EnumAttribute {
     pub fn get_universe(&self) -> &HashSet<String> {
         &self.universe
     }
-
-    pub fn verify(&self, value: &str) -> bool {
-        !self.universe.contains(value)
-    }
 }
@@ -16,19 +16,21 @@
  */
 use std::collections::HashMap;
 use std::collections::HashSet;
-use std::string::ToString;
-use std::sync::Arc;
 
 use tracing::info;
 
 use crate::common::attribute::AttributeTrait;
 
+/// Applies `new_attributes`, keys prefixed by `+` to add or update and by `-` to delete, to
+/// `current_attributes` and returns the result. On creation only additions are accepted. Every
+/// key must be one of `all`, the unchangeable ones can only be set on creation, and values must
+/// verify.
 pub fn alter_current_attributes<A: AttributeTrait>(
     create: bool,
-    all: HashMap<String, A>,
+    all: &HashMap<String, A>,
     current_attributes: HashMap<String, String>,
     new_attributes: HashMap<String, String>,
-) -> HashMap<String, String> {
+) -> Result<HashMap<String, String>, String> {
     let mut init: HashMap<String, String> = HashMap::new();
     let mut add: HashMap<String, String> = HashMap::new();
     let mut update: HashMap<String, String> = HashMap::new();
@@ -37,61 +39,64 @@ pub fn alter_current_attributes<A: AttributeTrait>(
 
     for (key, value) in new_attributes {
         let real_key = real_key(key.as_str());
-        validate(&real_key);
-        duplication_check(&mut keys, &real_key);
+        validate(&real_key)?;
+        duplication_check(&mut keys, &real_key)?;
 
         if create {
             if key.starts_with('+') {
-                init.insert(real_key.clone(), value);
+                init.insert(real_key, value);
             } else {
-                panic!(
+                return Err(format!(
                     "only add attribute is supported while creating topic. key: {}",
                     real_key
-                );
+                ));
             }
         } else if key.starts_with('+') {
             if !current_attributes.contains_key(&real_key) {
-                add.insert(real_key.clone(), value);
+                add.insert(real_key, value);
             } else {
-                update.insert(real_key.clone(), value);
+                update.insert(real_key, value);
             }
         } else if key.starts_with('-') {
             if !current_attributes.contains_key(&real_key) {
-                panic!("attempt to delete a nonexistent key: {}", real_key);
+                return Err(format!("attempt to delete a nonexistent key: {}", real_key));
             }
-            delete.insert(real_key.clone(), value);
+            delete.insert(real_key, value);
         } else {
-            panic!("wrong format key: {}", real_key);
+            return Err(format!("wrong format key: {}", real_key));
         }
     }
 
-    validate_alter(&all, &init, true, false);
-    validate_alter(&all, &add, false, false);
-    validate_alter(&all, &update, false, false);
-    validate_alter(&all, &delete, false, true);
+    validate_alter(all, &init, true, false)?;
+    validate_alter(all, &add, false, false)?;
+    validate_alter(all, &update, false, false)?;
+    validate_alter(all, &delete, false, true)?;
 
     info!("add: {:?}, update: {:?}, delete: {:?}", add, update, delete);
 
-    let mut final_attributes = current_attributes.clone();
+    let mut final_attributes = current_attributes;
     final_attributes.extend(init);
     final_attributes.extend(add);
+    final_attributes.extend(update);
     for key in delete.keys() {
         final_attributes.remove(key);
     }
 
-    final_attributes
+    Ok(final_attributes)
 }
 
-fn duplication_check(keys: &mut HashSet<String>, key: &String) {
+fn duplication_check(keys: &mut HashSet<String>, key: &String) -> Result<(), String> {
     if !keys.insert(key.clone()) {
-        panic!("alter duplication key. key: {}", key);
+        return Err(format!("alter duplication key. key: {}", key));
     }
+    Ok(())
 }
 
-fn validate(kv_attribute: &str) {
+fn validate(kv_attribute: &str) -> Result<(), String> {
     if kv_attribute.is_empty() || kv_attribute.contains('+') || kv_attribute.contains('-') {
-        panic!("kv string format wrong.");
+        return Err("kv string format wrong.".to_string());
     }
+    Ok(())
 }
 
 fn validate_alter<A: AttributeTrait>(
@@ -99,23 +104,96 @@ fn validate_alter<A: AttributeTrait>(
     alter: &HashMap<String, String>,
     init: bool,
     delete: bool,
-) {
+) -> Result<(), String> {
     for (key, value) in alter {
-        let attribute = all.get(key);
-        if attribute.is_none() {
-            panic!("unsupported key: {}", key);
-        }
-        let attribute = attribute.unwrap();
+        let Some(attribute) = all.get(key) else {
+            return Err(format!("unsupported key: {}", key));
+        };
         if !init && !attribute.changeable() {
-            panic!("attempt to update an unchangeable attribute. key: {}", key);
+            return Err(format!(
+                "attempt to update an unchangeable attribute. key: {}",
+                key
+            ));
         }
 
-        if !delete {
-            attribute.verify(value);
+        if !delete && !attribute.verify(value) {
+            return Err(format!("value is not in set: {}, key: {}", value, key));
         }
     }
+    Ok(())
 }
 
 fn real_key(key: &str) -> String {
     key.chars().skip(1).collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TopicAttributes::ALL;
+
+    fn attributes(kvs: &[(&str, &str)]) -> HashMap<String, String> {
+        kvs.iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn creation_only_adds_known_attributes_with_valid_values() {
+        let created = alter_current_attributes(
+            true,
+            &ALL,
+            HashMap::new(),
+            attributes(&[("+message.type", "FIFO"), ("+queue.type", "BatchCQ")]),
+        )
+        .unwrap();
+        assert_eq!(
+            created,
+            attributes(&[("message.type", "FIFO"), ("queue.type", "BatchCQ")])
+        );
+
+        for new_attributes in [
+            attributes(&[("-message.type", "")]),
+            attributes(&[("+message.type", "fifo")]),
+            attributes(&[("+unknown", "x")]),
+        ] {
+            assert!(alter_current_attributes(true, &ALL, HashMap::new(), new_attributes).is_err());
+        }
+    }
+
+    #[test]
+    fn update_alters_changeable_attributes_only() {
+        let current = attributes(&[("message.type", "FIFO"), ("queue.type", "SimpleCQ")]);
+        let updated = alter_current_attributes(
+            false,
+            &ALL,
+            current.clone(),
+            attributes(&[("+message.type", "DELAY")]),
+        )
+        .unwrap();
+        assert_eq!(
+            updated,
+            attributes(&[("message.type", "DELAY"), ("queue.type", "SimpleCQ")])
+        );
+
+        let deleted = alter_current_attributes(
+            false,
+            &ALL,
+            current.clone(),
+            attributes(&[("-message.type", "")]),
+        )
+        .unwrap();
+        assert_eq!(deleted, attributes(&[("queue.type", "SimpleCQ")]));
+
+        for new_attributes in [
+            attributes(&[("+queue.type", "BatchCQ")]),
+            attributes(&[("+cleanup.policy", "COMPACTION")]),
+            attributes(&[("-cleanup.policy", "")]),
+            attributes(&[("message.type", "DELAY")]),
+        ] {
+            assert!(
+                alter_current_attributes(false, &ALL, current.clone(), new_attributes).is_err()
+            );
+        }
+    }
+}
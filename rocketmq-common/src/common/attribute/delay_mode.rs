@@ -0,0 +1,80 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+
+/// How the broker schedules the delay messages of a topic: with the legacy delay levels or with
+/// the timer wheel, which delays by the level's time as well.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum DelayMode {
+    #[default]
+    Level,
+    TimerWheel,
+}
+
+impl DelayMode {
+    pub fn delay_mode_set() -> HashSet<String> {
+        [Self::Level.to_string(), Self::TimerWheel.to_string()]
+            .into_iter()
+            .collect()
+    }
+}
+
+impl fmt::Display for DelayMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DelayMode::Level => write!(f, "LEVEL"),
+            DelayMode::TimerWheel => write!(f, "TIMER_WHEEL"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ParseDelayModeError;
+
+impl fmt::Display for ParseDelayModeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid delay mode")
+    }
+}
+
+impl FromStr for DelayMode {
+    type Err = ParseDelayModeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_uppercase().as_str() {
+            "LEVEL" => Ok(DelayMode::Level),
+            "TIMER_WHEEL" => Ok(DelayMode::TimerWheel),
+            _ => Err(ParseDelayModeError),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delay_mode_round_trips_through_its_name() {
+        for mode in [DelayMode::Level, DelayMode::TimerWheel] {
+            assert_eq!(mode.to_string().parse(), Ok(mode));
+        }
+        assert_eq!("timer_wheel".parse(), Ok(DelayMode::TimerWheel));
+        assert!("wheel".parse::<DelayMode>().is_err());
+    }
+}
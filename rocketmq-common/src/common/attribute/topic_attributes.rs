@@ -3,6 +3,7 @@ use std::collections::HashMap;
 use lazy_static::lazy_static;
 
 use crate::common::attribute::attribute_enum::EnumAttribute;
+use crate::common::attribute::delay_mode::DelayMode;
 use crate::common::attribute::topic_message_type::TopicMessageType;
 use crate::common::attribute::Attribute;
 use crate::hashset;
@@ -24,6 +25,14 @@ lazy_static! {
         universe: TopicMessageType::topic_message_type_set(),
         default_value: TopicMessageType::Normal.to_string(),
     };
+    pub static ref DELAY_MODE_ATTRIBUTE: EnumAttribute = EnumAttribute {
+        attribute: Attribute {
+            name: String::from("delay.mode"),
+            changeable: true,
+        },
+        universe: DelayMode::delay_mode_set(),
+        default_value: DelayMode::Level.to_string(),
+    };
     pub static ref QUEUE_TYPE_ATTRIBUTE: EnumAttribute = EnumAttribute {
         attribute: Attribute {
             name: String::from("queue.type"),
@@ -46,6 +55,10 @@ lazy_static! {
             TOPIC_MESSAGE_TYPE_ATTRIBUTE.get_name().to_string(),
             TOPIC_MESSAGE_TYPE_ATTRIBUTE.clone(),
         );
+        map.insert(
+            DELAY_MODE_ATTRIBUTE.get_name().to_string(),
+            DELAY_MODE_ATTRIBUTE.clone(),
+        );
         map
     };
 }
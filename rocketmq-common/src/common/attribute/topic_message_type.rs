@@ -21,7 +21,7 @@ use std::str::FromStr;
 
 use crate::common::message::MessageConst;
 
-#[derive(Debug, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum TopicMessageType {
     Unspecified,
     Normal,
@@ -23,10 +23,12 @@ use serde::Deserialize;
 use serde::Serialize;
 
 use crate::common::constant::PermName;
+use crate::common::message::message_properties_codec::MessagePropertiesLimits;
 use crate::common::mix_all;
 use crate::common::mix_all::NAMESRV_ADDR_PROPERTY;
 use crate::common::server::config::ServerConfig;
 use crate::common::topic::TopicValidator;
+use crate::utils::network_util::NetworkUtil;
 
 const DEFAULT_CLUSTER_NAME: &str = "DefaultCluster";
 
@@ -126,6 +128,13 @@ pub struct BrokerConfig {
     pub store_path_root_dir: String,
     pub enable_split_registration: bool,
     pub split_registration_size: i32,
+    /// Zlib compress the register broker body, which shrinks the payload of brokers hosting
+    /// many topics by an order of magnitude.
+    pub compressed_register: bool,
+    /// Once every name server has the full topic configs, only register the topic configs
+    /// changed since, found by the data version. Deleted topics fall back to a full
+    /// registration.
+    pub enable_incremental_registration: bool,
     pub register_broker_timeout_mills: i32,
     pub is_in_broker_container: bool,
     pub commercial_size_per_msg: i32,
@@ -158,17 +167,65 @@ pub struct BrokerConfig {
     pub reject_pull_consumer_enable: bool,
     pub consumer_offset_update_version_step: i64,
     pub enable_broadcast_offset_store: bool,
+    /// Copy pulled messages into a heap buffer before writing the response, otherwise they are
+    /// written straight from the mapped commit log files.
     pub transfer_msg_by_heap: bool,
     pub short_polling_time_mills: u64,
     pub long_polling_enable: bool,
     pub max_error_rate_of_bloom_filter: i32,
     pub expect_consumer_num_use_filter: i32,
     pub bit_map_length_consume_queue_ext: i32,
+    /// Evaluate the SQL92 subscriptions of a topic at dispatch and keep the bloom filter bits
+    /// of the matching ones in the consume queue ext, pulls then skip the messages whose bits
+    /// miss without reading the commit log.
+    pub enable_calc_filter_bit_map: bool,
     pub validate_system_topic_when_update_topic: bool,
     pub enable_mixed_message_type: bool,
     pub auto_delete_unused_stats: bool,
     pub forward_timeout: u64,
     pub store_reply_message_enable: bool,
+    pub notify_consumer_ids_changed_enable: bool,
+    pub max_message_property_key_length: usize,
+    pub max_message_property_value_length: usize,
+    pub max_message_property_count: usize,
+    pub max_message_properties_size: usize,
+    /// Cold data, in bytes, a single consumer group may read per second before it is throttled.
+    pub cg_cold_read_threshold: i64,
+    /// Cold data, in bytes, all consumer groups together may read per second before every
+    /// group that reads cold data is throttled.
+    pub global_cold_read_threshold: i64,
+    /// Number of consumers gone offline the broker remembers, the oldest ones are dropped
+    /// first.
+    pub consumer_offline_history_size: usize,
+    /// How long, in milliseconds, an offline consumer is remembered.
+    pub consumer_offline_history_retention_millis: u64,
+    /// Messages a consumer group may lag behind the max offset of a queue before its pulls are
+    /// redirected to the broker of `which_broker_when_consume_slowly`, 0 disables the check.
+    /// Only takes effect with `slave_read_enable`.
+    pub consumer_lagging_behind_threshold: i64,
+    /// YAML file declaring the topics to create or update before the broker accepts traffic,
+    /// see `topics.yaml`. `None` skips topic provisioning.
+    pub topic_bootstrap_file: Option<String>,
+    /// YAML file declaring the subscription groups to create or update before the broker
+    /// accepts traffic, see `groups.yaml`. `None` skips group provisioning.
+    pub subscription_group_bootstrap_file: Option<String>,
+    /// Names of the message store plugins wrapping the store, separated by commas, outermost
+    /// first. Empty uses the store as is.
+    pub message_store_plug_in: String,
+    /// Record the billed message counts and bytes of every send and pull per owner, topic and
+    /// group in one minute buckets, exported through `GetCommercialStats`.
+    pub commercial_stats_enable: bool,
+    /// Minutes of commercial stats kept for export, older buckets are dropped.
+    pub commercial_stats_retained_minutes: u64,
+    /// Reject sends to and pulls from system topics by clients other than the admin ones. Off by
+    /// default: once on, tools consuming internal topics such as `OFFSET_MOVED_EVENT` need
+    /// their address in `system_topic_admin_addresses` or the topic in the allowlist.
+    pub system_topic_protection_enable: bool,
+    /// System topics, separated by commas, every client may use besides the trace topic.
+    pub system_topic_allowlist: String,
+    /// IP addresses, separated by commas, of the admin clients which may use every system
+    /// topic.
+    pub system_topic_admin_addresses: String,
 }
 
 impl Default for BrokerConfig {
@@ -178,6 +235,7 @@ impl Default for BrokerConfig {
         let broker_ip1 = local_ip.to_string();
         let broker_ip2 = Some(local_ip.to_string());
         let listen_port = 10911;
+        let properties_limits = MessagePropertiesLimits::default();
 
         BrokerConfig {
             broker_identity,
@@ -202,6 +260,8 @@ impl Default for BrokerConfig {
                 .into_owned(),
             enable_split_registration: false,
             split_registration_size: 800,
+            compressed_register: false,
+            enable_incremental_registration: false,
             register_broker_timeout_mills: 24000,
             is_in_broker_container: false,
             commercial_size_per_msg: 4 * 1024,
@@ -240,16 +300,44 @@ impl Default for BrokerConfig {
             max_error_rate_of_bloom_filter: 20,
             expect_consumer_num_use_filter: 32,
             bit_map_length_consume_queue_ext: 64,
+            enable_calc_filter_bit_map: false,
             forward_timeout: 3 * 1000,
             validate_system_topic_when_update_topic: true,
             enable_mixed_message_type: false,
             auto_delete_unused_stats: false,
             store_reply_message_enable: true,
+            notify_consumer_ids_changed_enable: true,
+            max_message_property_key_length: properties_limits.max_key_length,
+            max_message_property_value_length: properties_limits.max_value_length,
+            max_message_property_count: properties_limits.max_property_count,
+            max_message_properties_size: properties_limits.max_properties_size,
+            cg_cold_read_threshold: 3 * 1024 * 1024,
+            global_cold_read_threshold: 100 * 1024 * 1024,
+            consumer_offline_history_size: 1024,
+            consumer_offline_history_retention_millis: 1000 * 60 * 60,
+            consumer_lagging_behind_threshold: 0,
+            topic_bootstrap_file: None,
+            subscription_group_bootstrap_file: None,
+            message_store_plug_in: String::new(),
+            commercial_stats_enable: false,
+            commercial_stats_retained_minutes: 60,
+            system_topic_protection_enable: false,
+            system_topic_allowlist: TopicValidator::RMQ_SYS_BENCHMARK_TOPIC.to_string(),
+            system_topic_admin_addresses: String::new(),
         }
     }
 }
 
 impl BrokerConfig {
+    pub fn message_properties_limits(&self) -> MessagePropertiesLimits {
+        MessagePropertiesLimits {
+            max_key_length: self.max_message_property_key_length,
+            max_value_length: self.max_message_property_value_length,
+            max_property_count: self.max_message_property_count,
+            max_properties_size: self.max_message_properties_size,
+        }
+    }
+
     pub fn broker_name(&self) -> String {
         self.broker_name.clone()
     }
@@ -283,7 +371,7 @@ impl BrokerConfig {
     }
 
     pub fn get_broker_addr(&self) -> String {
-        format!("{}:{}", self.broker_ip1, self.listen_port)
+        NetworkUtil::host_port(&self.broker_ip1, self.listen_port)
     }
 
     pub fn get_start_accept_send_request_time_stamp(&self) -> i64 {
@@ -361,6 +449,14 @@ impl BrokerConfig {
             "splitRegistrationSize".to_string(),
             self.split_registration_size.to_string(),
         );
+        properties.insert(
+            "compressedRegister".to_string(),
+            self.compressed_register.to_string(),
+        );
+        properties.insert(
+            "enableIncrementalRegistration".to_string(),
+            self.enable_incremental_registration.to_string(),
+        );
         properties.insert(
             "registerBrokerTimeoutMills".to_string(),
             self.register_broker_timeout_mills.to_string(),
@@ -510,6 +606,10 @@ impl BrokerConfig {
             "bitMapLengthConsumeQueueExt".to_string(),
             self.bit_map_length_consume_queue_ext.to_string(),
         );
+        properties.insert(
+            "enableCalcFilterBitMap".to_string(),
+            self.enable_calc_filter_bit_map.to_string(),
+        );
         properties.insert(
             "validateSystemTopicWhenUpdateTopic".to_string(),
             self.validate_system_topic_when_update_topic.to_string(),
@@ -526,6 +626,80 @@ impl BrokerConfig {
             "forwardTimeout".to_string(),
             self.forward_timeout.to_string(),
         );
+        properties.insert(
+            "notifyConsumerIdsChangedEnable".to_string(),
+            self.notify_consumer_ids_changed_enable.to_string(),
+        );
+        properties.insert(
+            "maxMessagePropertyKeyLength".to_string(),
+            self.max_message_property_key_length.to_string(),
+        );
+        properties.insert(
+            "maxMessagePropertyValueLength".to_string(),
+            self.max_message_property_value_length.to_string(),
+        );
+        properties.insert(
+            "maxMessagePropertyCount".to_string(),
+            self.max_message_property_count.to_string(),
+        );
+        properties.insert(
+            "maxMessagePropertiesSize".to_string(),
+            self.max_message_properties_size.to_string(),
+        );
+        properties.insert(
+            "cgColdReadThreshold".to_string(),
+            self.cg_cold_read_threshold.to_string(),
+        );
+        properties.insert(
+            "globalColdReadThreshold".to_string(),
+            self.global_cold_read_threshold.to_string(),
+        );
+        properties.insert(
+            "consumerOfflineHistorySize".to_string(),
+            self.consumer_offline_history_size.to_string(),
+        );
+        properties.insert(
+            "consumerOfflineHistoryRetentionMillis".to_string(),
+            self.consumer_offline_history_retention_millis.to_string(),
+        );
+        properties.insert(
+            "consumerLaggingBehindThreshold".to_string(),
+            self.consumer_lagging_behind_threshold.to_string(),
+        );
+        properties.insert(
+            "topicBootstrapFile".to_string(),
+            self.topic_bootstrap_file.clone().unwrap_or_default(),
+        );
+        properties.insert(
+            "subscriptionGroupBootstrapFile".to_string(),
+            self.subscription_group_bootstrap_file
+                .clone()
+                .unwrap_or_default(),
+        );
+        properties.insert(
+            "messageStorePlugIn".to_string(),
+            self.message_store_plug_in.clone(),
+        );
+        properties.insert(
+            "commercialStatsEnable".to_string(),
+            self.commercial_stats_enable.to_string(),
+        );
+        properties.insert(
+            "commercialStatsRetainedMinutes".to_string(),
+            self.commercial_stats_retained_minutes.to_string(),
+        );
+        properties.insert(
+            "systemTopicProtectionEnable".to_string(),
+            self.system_topic_protection_enable.to_string(),
+        );
+        properties.insert(
+            "systemTopicAllowlist".to_string(),
+            self.system_topic_allowlist.clone(),
+        );
+        properties.insert(
+            "systemTopicAdminAddresses".to_string(),
+            self.system_topic_admin_addresses.clone(),
+        );
         properties
     }
 }
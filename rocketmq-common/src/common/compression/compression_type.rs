@@ -69,20 +69,22 @@ impl CompressionType {
     }
 
     pub fn decompression(&self, data: &Bytes) -> Bytes {
+        self.try_decompression(data).unwrap()
+    }
+
+    /// Same as [`decompression`](Self::decompression), but corrupt data is an error instead of
+    /// a panic, for data received from peers.
+    pub fn try_decompression(&self, data: &Bytes) -> io::Result<Bytes> {
         match self {
-            CompressionType::LZ4 => {
-                let compressed = decompress_size_prepended(data.chunk()).unwrap();
-                Bytes::from(compressed)
-            }
-            CompressionType::Zstd => {
-                let compressed = zstd::decode_all(data.clone().reader()).unwrap();
-                Bytes::from(compressed)
-            }
+            CompressionType::LZ4 => decompress_size_prepended(data.chunk())
+                .map(Bytes::from)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string())),
+            CompressionType::Zstd => zstd::decode_all(data.clone().reader()).map(Bytes::from),
             CompressionType::Zlib => {
                 let mut zlib_encoder = ZlibDecoder::new(data.clone().reader());
                 let mut decompressed_data = Vec::new();
-                zlib_encoder.read_to_end(&mut decompressed_data).unwrap();
-                Bytes::from(decompressed_data)
+                zlib_encoder.read_to_end(&mut decompressed_data)?;
+                Ok(Bytes::from(decompressed_data))
             }
         }
     }
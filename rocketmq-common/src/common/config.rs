@@ -21,8 +21,10 @@ use serde::Deserialize;
 use serde::Serialize;
 
 use super::TopicFilterType;
+use crate::common::attribute::delay_mode::DelayMode;
 use crate::common::attribute::topic_message_type::TopicMessageType;
 use crate::common::constant::PermName;
+use crate::TopicAttributes::DELAY_MODE_ATTRIBUTE;
 use crate::TopicAttributes::TOPIC_MESSAGE_TYPE_ATTRIBUTE;
 
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
@@ -69,6 +71,14 @@ impl TopicConfig {
         TopicMessageType::Normal
     }
 
+    /// How the delay messages of the topic are scheduled, the delay levels unless set otherwise.
+    pub fn get_delay_mode(&self) -> DelayMode {
+        self.attributes
+            .get(DELAY_MODE_ATTRIBUTE.get_name())
+            .and_then(|content| content.parse().ok())
+            .unwrap_or_default()
+    }
+
     pub fn new(topic_name: impl Into<String>) -> Self {
         TopicConfig {
             topic_name: Some(topic_name.into()),
@@ -35,6 +35,7 @@ pub mod message_enum;
 pub mod message_ext;
 pub mod message_ext_broker_inner;
 pub mod message_id;
+pub mod message_properties_codec;
 pub mod message_queue;
 pub mod message_single;
 
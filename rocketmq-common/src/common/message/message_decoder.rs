@@ -29,7 +29,9 @@ use bytes::BytesMut;
 
 use crate::common::compression::compression_type::CompressionType;
 use crate::common::message::message_ext::MessageExt;
+use crate::common::message::message_properties_codec;
 use crate::common::message::message_single::Message;
+use crate::common::message::MessageConst;
 use crate::common::message::MessageVersion;
 use crate::common::sys_flag::message_sys_flag::MessageSysFlag;
 use crate::CRC32Utils::crc32;
@@ -52,49 +54,14 @@ pub const SYSFLAG_POSITION: usize = 4 + 4 + 4 + 4 + 4 + 8 + 8;
 pub const BORN_TIMESTAMP_POSITION: usize = 4 + 4 + 4 + 4 + 4 + 8 + 8 + 4 + 8;
 
 pub fn string_to_message_properties(properties: Option<&String>) -> HashMap<String, String> {
-    let mut map = HashMap::new();
-    if let Some(properties) = properties {
-        let mut index = 0;
-        let len = properties.len();
-        while index < len {
-            let new_index = properties[index..]
-                .find(PROPERTY_SEPARATOR)
-                .map_or(len, |i| index + i);
-            if new_index - index >= 3 {
-                if let Some(kv_sep_index) = properties[index..new_index].find(NAME_VALUE_SEPARATOR)
-                {
-                    let kv_sep_index = index + kv_sep_index;
-                    if kv_sep_index > index && kv_sep_index < new_index - 1 {
-                        let k = &properties[index..kv_sep_index];
-                        let v = &properties[kv_sep_index + 1..new_index];
-                        map.insert(k.to_string(), v.to_string());
-                    }
-                }
-            }
-            index = new_index + 1;
-        }
+    match properties {
+        Some(properties) => message_properties_codec::decode(properties),
+        None => HashMap::new(),
     }
-    map
 }
 
 pub fn message_properties_to_string(properties: &HashMap<String, String>) -> String {
-    let mut len = 0;
-    for (name, value) in properties.iter() {
-        len += name.len();
-
-        len += value.len();
-        len += 2; // separator
-    }
-
-    let mut sb = String::with_capacity(len);
-    for (name, value) in properties.iter() {
-        sb.push_str(name);
-        sb.push(NAME_VALUE_SEPARATOR);
-
-        sb.push_str(value);
-        sb.push(PROPERTY_SEPARATOR);
-    }
-    sb
+    message_properties_codec::encode(properties)
 }
 
 pub fn decode(
@@ -216,15 +183,14 @@ pub fn decode(
     if body_len > 0 {
         // Handle reading and processing body
         if read_body {
-            let mut body = vec![0; body_len as usize];
-            byte_buffer.copy_to_slice(&mut body);
+            // A slice of the source buffer, the body is not copied unless it is decompressed.
+            let mut body_bytes = byte_buffer.split_to(body_len as usize);
             if check_crc {
-                let crc = crc32(&body);
+                let crc = crc32(&body_bytes);
                 if crc != body_crc {
                     return None;
                 }
             }
-            let mut body_bytes = Bytes::from(body);
             if de_compress_body
                 && (sys_flag & MessageSysFlag::COMPRESSED_FLAG) == MessageSysFlag::COMPRESSED_FLAG
             {
@@ -235,40 +201,26 @@ pub fn decode(
             }
             msg_ext.message.body = Some(body_bytes);
         } else {
-            let _ = byte_buffer.split_to(
-                BORN_TIMESTAMP_POSITION
-                    + born_host_ip_length
-                    + 4
-                    + 8
-                    + store_host_ip_length
-                    + 4
-                    + 4
-                    + 8
-                    + 4
-                    + body_len as usize,
-            );
+            byte_buffer.advance(body_len as usize);
         }
     }
 
     // 16 TOPIC
     let topic_len = version.get_topic_length(byte_buffer);
-    let mut topic = vec![0; topic_len];
-    byte_buffer.copy_to_slice(&mut topic);
-    let topic_str = str::from_utf8(&topic).unwrap();
-    msg_ext.message.topic = topic_str.to_string();
+    let topic = byte_buffer.split_to(topic_len);
+    msg_ext.message.topic = String::from_utf8_lossy(&topic).into_owned();
 
     // 17 properties
     let properties_length = byte_buffer.get_i16();
     if properties_length > 0 {
         // Handle reading and processing properties
-        let mut properties = vec![0; properties_length as usize];
-        byte_buffer.copy_to_slice(&mut properties);
+        let properties = byte_buffer.split_to(properties_length as usize);
         if !is_set_properties_string {
-            let properties_string = String::from_utf8_lossy(properties.as_slice()).to_string();
+            let properties_string = String::from_utf8_lossy(&properties).to_string();
             let message_properties = string_to_message_properties(Some(&properties_string));
             msg_ext.message.properties = message_properties;
         } else {
-            let properties_string = String::from_utf8_lossy(properties.as_slice()).to_string();
+            let properties_string = String::from_utf8_lossy(&properties).to_string();
             let mut message_properties = string_to_message_properties(Some(&properties_string));
             message_properties.insert("propertiesString".to_string(), properties_string);
             msg_ext.message.properties = message_properties;
@@ -284,6 +236,54 @@ pub fn decode(
     Some(msg_ext)
 }
 
+/// Decodes only the properties of the stored message at the start of `buffer`, reading them in
+/// place instead of decoding the whole message. `None` if the buffer is too short for the lengths
+/// it declares.
+pub fn decode_properties(buffer: &[u8]) -> Option<HashMap<String, String>> {
+    let read_i32 = |pos: usize| -> Option<i32> {
+        Some(i32::from_be_bytes(
+            buffer.get(pos..pos + 4)?.try_into().ok()?,
+        ))
+    };
+    let version =
+        MessageVersion::value_of_magic_code(read_i32(MESSAGE_MAGIC_CODE_POSITION)?).ok()?;
+    let sys_flag = read_i32(SYSFLAG_POSITION)?;
+    let born_host_length = if sys_flag & MessageSysFlag::BORNHOST_V6_FLAG == 0 {
+        4 + 4
+    } else {
+        16 + 4
+    };
+    let store_host_length = if sys_flag & MessageSysFlag::STOREHOSTADDRESS_V6_FLAG == 0 {
+        4 + 4
+    } else {
+        16 + 4
+    };
+    let body_size_position =
+        SYSFLAG_POSITION + 4 + 8 + born_host_length + 8 + store_host_length + 4 + 8;
+    let body_size = read_i32(body_size_position)?.max(0) as usize;
+    let topic_length_position = body_size_position + 4 + body_size;
+    if buffer.len() < topic_length_position + version.get_topic_length_size() {
+        return None;
+    }
+    let topic_length = version.get_topic_length_at_index(buffer, topic_length_position);
+    let properties_position =
+        topic_length_position + version.get_topic_length_size() + topic_length;
+    let properties_length = i16::from_be_bytes(
+        buffer
+            .get(properties_position..properties_position + 2)?
+            .try_into()
+            .ok()?,
+    );
+    if properties_length <= 0 {
+        return Some(HashMap::new());
+    }
+    let properties = buffer
+        .get(properties_position + 2..properties_position + 2 + properties_length as usize)?;
+    Some(message_properties_codec::decode(&String::from_utf8_lossy(
+        properties,
+    )))
+}
+
 pub fn count_inner_msg_num(bytes: Option<Bytes>) -> u32 {
     match bytes {
         None => 0,
@@ -302,18 +302,94 @@ pub fn count_inner_msg_num(bytes: Option<Bytes>) -> u32 {
     }
 }
 
+/// Encodes the messages of a batch back to back into a single buffer.
 pub fn encode_messages(messages: &[Message]) -> Bytes {
     let mut bytes = BytesMut::new();
-    let mut all_size = 0;
     for message in messages {
-        let message_bytes = encode_message(message);
-        all_size += message_bytes.len();
-        bytes.put_slice(&message_bytes);
+        encode_message_into(message, &mut bytes);
     }
     bytes.freeze()
 }
 
+/// Decodes the messages of a batch body encoded by [`encode_messages`], stopping at the first
+/// truncated one.
+pub fn decode_messages(bytes: &mut Bytes) -> Vec<Message> {
+    let mut messages = Vec::new();
+    while bytes.remaining() >= 4 {
+        let store_size = bytes.slice(0..4).get_i32();
+        if store_size < 4 + 4 + 4 + 4 + 4 + 2 || store_size as usize > bytes.len() {
+            break;
+        }
+        let mut message_bytes = bytes.split_to(store_size as usize);
+        // TOTALSIZE, MAGICCODE and BODYCRC
+        message_bytes.advance(4 + 4 + 4);
+        let mut message = Message {
+            flag: message_bytes.get_i32(),
+            ..Default::default()
+        };
+        let body_len = message_bytes.get_i32();
+        if body_len < 0 || body_len as usize + 2 > message_bytes.len() {
+            break;
+        }
+        message.body = Some(message_bytes.split_to(body_len as usize));
+        let properties_length = message_bytes.get_i16();
+        if properties_length > 0 && properties_length as usize <= message_bytes.len() {
+            let properties = message_bytes.split_to(properties_length as usize);
+            message.properties =
+                message_properties_codec::decode(String::from_utf8_lossy(&properties).as_ref());
+        }
+        messages.push(message);
+    }
+    messages
+}
+
+/// Splits a batch stored as a single message, see [`MessageSysFlag::INNER_BATCH_FLAG`], into
+/// its messages. A batch compressed as a whole is decompressed first when `decompress_body` is
+/// set. The messages keep the queue and store information of the batch and take consecutive
+/// queue offsets from the one of the batch.
+pub fn decodes_batch(batch_msg: &MessageExt, decompress_body: bool) -> Result<Vec<MessageExt>> {
+    let Some(mut body) = batch_msg.message.body.clone() else {
+        return Ok(Vec::new());
+    };
+    let mut sys_flag = batch_msg.sys_flag;
+    if decompress_body && MessageSysFlag::check(sys_flag, MessageSysFlag::COMPRESSED_FLAG) {
+        body = MessageSysFlag::get_compression_type(sys_flag)
+            .try_decompression(&body)
+            .map_err(|e| {
+                crate::error::Error::RuntimeException(format!(
+                    "decompress batch {} failed: {}",
+                    batch_msg.msg_id, e
+                ))
+            })?;
+        sys_flag = MessageSysFlag::clear_compression_flags(sys_flag);
+    }
+    sys_flag &= !(MessageSysFlag::INNER_BATCH_FLAG | MessageSysFlag::NEED_UNWRAP_FLAG);
+    Ok(decode_messages(&mut body)
+        .into_iter()
+        .enumerate()
+        .map(|(index, mut message)| {
+            message.topic.clone_from(&batch_msg.message.topic);
+            let mut msg_ext = batch_msg.clone();
+            msg_ext.queue_offset = batch_msg.queue_offset + index as i64;
+            msg_ext.sys_flag = sys_flag;
+            if let Some(uniq_key) =
+                message.get_property(MessageConst::PROPERTY_UNIQ_CLIENT_MESSAGE_ID_KEYIDX)
+            {
+                msg_ext.msg_id = uniq_key;
+            }
+            msg_ext.message = message;
+            msg_ext
+        })
+        .collect())
+}
+
 pub fn encode_message(message: &Message) -> Bytes {
+    let mut bytes = BytesMut::new();
+    encode_message_into(message, &mut bytes);
+    bytes.freeze()
+}
+
+fn encode_message_into(message: &Message, bytes: &mut BytesMut) {
     let body = message.body.as_ref().unwrap();
     let body_len = body.len();
     let properties = message_properties_to_string(&message.properties);
@@ -327,7 +403,7 @@ pub fn encode_message(message: &Message) -> Bytes {
          + 4 + body_len // 4 BODY
          + 2 + properties_length;
 
-    let mut bytes = BytesMut::with_capacity(store_size);
+    bytes.reserve(store_size);
 
     // 1 TOTALSIZE
     bytes.put_i32(store_size as i32);
@@ -348,8 +424,6 @@ pub fn encode_message(message: &Message) -> Bytes {
     // 6 PROPERTIES
     bytes.put_i16(properties_length as i16);
     bytes.put_slice(properties_bytes);
-
-    bytes.freeze()
 }
 
 #[cfg(test)]
@@ -358,6 +432,82 @@ mod tests {
     use bytes::BytesMut;
 
     use super::*;
+    use crate::common::message::MessageTrait;
+    use crate::common::message::MESSAGE_MAGIC_CODE_V1;
+
+    fn stored_message(body: &[u8], topic: &str, properties: &str) -> Bytes {
+        let mut bytes = BytesMut::new();
+        bytes.put_i32(0);
+        bytes.put_i32(MESSAGE_MAGIC_CODE_V1);
+        bytes.put_u32(crc32(body));
+        bytes.put_i32(1);
+        bytes.put_i32(0);
+        bytes.put_i64(10);
+        bytes.put_i64(1024);
+        bytes.put_i32(0);
+        bytes.put_i64(1);
+        bytes.put_slice(&[127, 0, 0, 1]);
+        bytes.put_i32(9000);
+        bytes.put_i64(2);
+        bytes.put_slice(&[127, 0, 0, 1]);
+        bytes.put_i32(10911);
+        bytes.put_i32(0);
+        bytes.put_i64(0);
+        bytes.put_i32(body.len() as i32);
+        bytes.put_slice(body);
+        bytes.put_u8(topic.len() as u8);
+        bytes.put_slice(topic.as_bytes());
+        bytes.put_i16(properties.len() as i16);
+        bytes.put_slice(properties.as_bytes());
+        let len = bytes.len() as i32;
+        bytes[..4].copy_from_slice(&len.to_be_bytes());
+        bytes.freeze()
+    }
+
+    #[test]
+    fn decode_body_is_a_slice_of_the_source_buffer() {
+        let source = stored_message(b"hello world", "TopicTest", "KEYS\u{1}k1\u{2}");
+        let range = source.as_ptr() as usize..source.as_ptr() as usize + source.len();
+        let mut buffer = source.clone();
+        let msg = decode(&mut buffer, true, false, false, false, true).unwrap();
+        let body = msg.message.body.unwrap();
+        assert_eq!(body.as_ref(), b"hello world");
+        assert!(range.contains(&(body.as_ptr() as usize)));
+        assert_eq!(msg.message.topic, "TopicTest");
+        assert_eq!(msg.message.properties.get("KEYS").unwrap(), "k1");
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn decode_without_body_skips_only_the_body() {
+        let mut buffer = stored_message(b"hello world", "TopicTest", "");
+        let msg = decode(&mut buffer, false, false, false, false, false).unwrap();
+        assert!(msg.message.body.is_none());
+        assert_eq!(msg.message.topic, "TopicTest");
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn decode_with_corrupted_body_fails_crc_check() {
+        let mut corrupted =
+            BytesMut::from(stored_message(b"hello world", "TopicTest", "").as_ref());
+        corrupted[BORN_TIMESTAMP_POSITION + 8 + 8 + 8 + 4 + 8 + 4] ^= 0xff;
+        let mut buffer = corrupted.freeze();
+        assert!(decode(&mut buffer, true, false, false, false, true).is_none());
+    }
+
+    #[test]
+    fn decode_properties_reads_them_in_place() {
+        let source = stored_message(b"hello world", "TopicTest", "KEYS\u{1}k1\u{2}");
+        let properties = decode_properties(&source).unwrap();
+        assert_eq!(properties.get("KEYS").unwrap(), "k1");
+        assert!(
+            decode_properties(stored_message(b"", "TopicTest", "").as_ref())
+                .unwrap()
+                .is_empty()
+        );
+        assert!(decode_properties(&source[..source.len() - 4]).is_none());
+    }
 
     #[test]
     fn count_inner_msg_num_counts_correctly_for_multiple_messages() {
@@ -389,4 +539,36 @@ mod tests {
         bytes.put_i32(4);
         assert_eq!(count_inner_msg_num(Some(bytes.freeze())), 1);
     }
+
+    #[test]
+    fn decodes_batch_decompresses_and_splits_the_batch() {
+        let mut first = Message::new("TopicTest", b"first");
+        first.put_property(
+            MessageConst::PROPERTY_UNIQ_CLIENT_MESSAGE_ID_KEYIDX,
+            "uniq-1",
+        );
+        let second = Message::new("TopicTest", b"second");
+        let encoded = encode_messages(&[first, second]);
+        assert_eq!(decode_messages(&mut encoded.clone()).len(), 2);
+
+        let mut batch = MessageExt::default();
+        batch.message.topic = "TopicTest".to_string();
+        batch.queue_offset = 10;
+        batch.sys_flag = MessageSysFlag::INNER_BATCH_FLAG
+            | MessageSysFlag::COMPRESSED_FLAG
+            | CompressionType::Zstd.get_compression_flag();
+        batch.message.body = Some(CompressionType::Zstd.compression(&encoded));
+
+        let messages = decodes_batch(&batch, true).unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].message.body.as_deref(), Some(&b"first"[..]));
+        assert_eq!(messages[0].msg_id, "uniq-1");
+        assert_eq!(messages[1].message.body.as_deref(), Some(&b"second"[..]));
+        assert_eq!(messages[1].queue_offset, 11);
+        assert_eq!(messages[1].message.topic, "TopicTest");
+        assert_eq!(messages[1].sys_flag, 0);
+
+        batch.message.body = Some(Bytes::from_static(b"not compressed"));
+        assert!(decodes_batch(&batch, true).is_err());
+    }
 }
@@ -0,0 +1,283 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+use crate::error::Error::IllegalMessageProperties;
+use crate::MessageDecoder::NAME_VALUE_SEPARATOR;
+use crate::MessageDecoder::PROPERTY_SEPARATOR;
+use crate::Result;
+
+/// Escape character used to carry the `\u{0001}` and `\u{0002}` separators, and itself, inside
+/// property keys and values. Properties without these characters are encoded exactly as before,
+/// so the wire format stays compatible with peers that do not escape.
+pub const ESCAPE_CHAR: char = '\u{0003}';
+
+/// The properties length is stored in two bytes by the commit log.
+pub const MAX_PROPERTIES_SIZE: usize = i16::MAX as usize;
+
+/// Limits on message properties, checked by the producer before sending and by the broker before
+/// storing so that both sides reject the same messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MessagePropertiesLimits {
+    pub max_key_length: usize,
+    pub max_value_length: usize,
+    pub max_property_count: usize,
+    /// Maximum size in bytes of the encoded properties string.
+    pub max_properties_size: usize,
+}
+
+impl Default for MessagePropertiesLimits {
+    fn default() -> Self {
+        Self {
+            max_key_length: 255,
+            max_value_length: MAX_PROPERTIES_SIZE,
+            max_property_count: 256,
+            max_properties_size: MAX_PROPERTIES_SIZE,
+        }
+    }
+}
+
+impl MessagePropertiesLimits {
+    pub fn check(&self, properties: &HashMap<String, String>) -> Result<()> {
+        if properties.len() > self.max_property_count {
+            return Err(IllegalMessageProperties(format!(
+                "the message properties count {} over max value {}",
+                properties.len(),
+                self.max_property_count
+            )));
+        }
+        for (key, value) in properties {
+            self.check_property(key, value)?;
+        }
+        let size = encoded_len(properties);
+        if size > self.max_properties_size.min(MAX_PROPERTIES_SIZE) {
+            return Err(IllegalMessageProperties(format!(
+                "the message properties size {} over max value {}",
+                size,
+                self.max_properties_size.min(MAX_PROPERTIES_SIZE)
+            )));
+        }
+        Ok(())
+    }
+
+    /// Checks a properties string as received on the wire and returns the decoded properties.
+    pub fn check_encoded(&self, properties: &str) -> Result<HashMap<String, String>> {
+        if properties.len() > self.max_properties_size.min(MAX_PROPERTIES_SIZE) {
+            return Err(IllegalMessageProperties(format!(
+                "the message properties size {} over max value {}",
+                properties.len(),
+                self.max_properties_size.min(MAX_PROPERTIES_SIZE)
+            )));
+        }
+        let decoded = decode(properties);
+        if decoded.len() > self.max_property_count {
+            return Err(IllegalMessageProperties(format!(
+                "the message properties count {} over max value {}",
+                decoded.len(),
+                self.max_property_count
+            )));
+        }
+        for (key, value) in &decoded {
+            self.check_property(key, value)?;
+        }
+        Ok(decoded)
+    }
+
+    fn check_property(&self, key: &str, value: &str) -> Result<()> {
+        if key.is_empty() {
+            return Err(IllegalMessageProperties(
+                "the message property key is empty".to_string(),
+            ));
+        }
+        if key.len() > self.max_key_length {
+            return Err(IllegalMessageProperties(format!(
+                "the message property key {} length {} over max value {}",
+                key,
+                key.len(),
+                self.max_key_length
+            )));
+        }
+        if value.len() > self.max_value_length {
+            return Err(IllegalMessageProperties(format!(
+                "the message property {} value length {} over max value {}",
+                key,
+                value.len(),
+                self.max_value_length
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Encodes properties as `key\u{0001}value\u{0002}` pairs, escaping separators found inside keys
+/// and values.
+pub fn encode(properties: &HashMap<String, String>) -> String {
+    let mut sb = String::with_capacity(encoded_len(properties));
+    for (name, value) in properties.iter() {
+        sb.push_str(escape(name).as_ref());
+        sb.push(NAME_VALUE_SEPARATOR);
+        sb.push_str(escape(value).as_ref());
+        sb.push(PROPERTY_SEPARATOR);
+    }
+    sb
+}
+
+/// Decodes a string produced by [`encode`]. Malformed pairs are skipped.
+pub fn decode(properties: &str) -> HashMap<String, String> {
+    let mut map = HashMap::new();
+    let mut index = 0;
+    let len = properties.len();
+    while index < len {
+        let new_index = properties[index..]
+            .find(PROPERTY_SEPARATOR)
+            .map_or(len, |i| index + i);
+        if new_index - index >= 3 {
+            if let Some(kv_sep_index) = properties[index..new_index].find(NAME_VALUE_SEPARATOR) {
+                let kv_sep_index = index + kv_sep_index;
+                if kv_sep_index > index && kv_sep_index < new_index - 1 {
+                    let k = &properties[index..kv_sep_index];
+                    let v = &properties[kv_sep_index + 1..new_index];
+                    map.insert(unescape(k).into_owned(), unescape(v).into_owned());
+                }
+            }
+        }
+        index = new_index + 1;
+    }
+    map
+}
+
+/// Length in bytes of the string [`encode`] produces for `properties`.
+pub fn encoded_len(properties: &HashMap<String, String>) -> usize {
+    properties
+        .iter()
+        .map(|(name, value)| escaped_len(name) + escaped_len(value) + 2)
+        .sum()
+}
+
+fn is_reserved(c: char) -> bool {
+    c == NAME_VALUE_SEPARATOR || c == PROPERTY_SEPARATOR || c == ESCAPE_CHAR
+}
+
+fn escaped_len(s: &str) -> usize {
+    s.len() + s.chars().filter(|c| is_reserved(*c)).count()
+}
+
+pub fn escape(s: &str) -> Cow<'_, str> {
+    if !s.contains(is_reserved) {
+        return Cow::Borrowed(s);
+    }
+    let mut escaped = String::with_capacity(escaped_len(s));
+    for c in s.chars() {
+        match c {
+            NAME_VALUE_SEPARATOR => escaped.push_str("\u{0003}1"),
+            PROPERTY_SEPARATOR => escaped.push_str("\u{0003}2"),
+            ESCAPE_CHAR => escaped.push_str("\u{0003}3"),
+            c => escaped.push(c),
+        }
+    }
+    Cow::Owned(escaped)
+}
+
+pub fn unescape(s: &str) -> Cow<'_, str> {
+    if !s.contains(ESCAPE_CHAR) {
+        return Cow::Borrowed(s);
+    }
+    let mut unescaped = String::with_capacity(s.len());
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != ESCAPE_CHAR {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.peek() {
+            Some('1') => unescaped.push(NAME_VALUE_SEPARATOR),
+            Some('2') => unescaped.push(PROPERTY_SEPARATOR),
+            Some('3') => unescaped.push(ESCAPE_CHAR),
+            _ => {
+                unescaped.push(c);
+                continue;
+            }
+        }
+        chars.next();
+    }
+    Cow::Owned(unescaped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_without_separators_is_unchanged() {
+        let properties = HashMap::from([("KEYS".to_string(), "key1 key2".to_string())]);
+        let encoded = encode(&properties);
+        assert_eq!(encoded, "KEYS\u{0001}key1 key2\u{0002}");
+        assert_eq!(encoded.len(), encoded_len(&properties));
+        assert_eq!(decode(&encoded), properties);
+    }
+
+    #[test]
+    fn encode_escapes_separators() {
+        let properties =
+            HashMap::from([("a\u{0001}b".to_string(), "x\u{0002}y\u{0003}z".to_string())]);
+        let encoded = encode(&properties);
+        assert!(!encoded[..encoded.len() - 1].contains(PROPERTY_SEPARATOR));
+        assert_eq!(encoded.len(), encoded_len(&properties));
+        assert_eq!(decode(&encoded), properties);
+    }
+
+    #[test]
+    fn unescape_keeps_unknown_sequences() {
+        assert_eq!(unescape("a\u{0003}x"), "a\u{0003}x");
+        assert_eq!(unescape("a\u{0003}"), "a\u{0003}");
+    }
+
+    #[test]
+    fn limits_reject_long_keys_values_and_sizes() {
+        let limits = MessagePropertiesLimits {
+            max_key_length: 4,
+            max_value_length: 4,
+            max_property_count: 2,
+            max_properties_size: 16,
+        };
+        let ok = HashMap::from([("k".to_string(), "v".to_string())]);
+        assert!(limits.check(&ok).is_ok());
+        assert_eq!(limits.check_encoded(&encode(&ok)).unwrap(), ok);
+
+        let long_key = HashMap::from([("key12".to_string(), "v".to_string())]);
+        assert!(limits.check(&long_key).is_err());
+        assert!(limits.check_encoded(&encode(&long_key)).is_err());
+
+        let long_value = HashMap::from([("k".to_string(), "value".to_string())]);
+        assert!(limits.check(&long_value).is_err());
+
+        let too_many = HashMap::from([
+            ("k1".to_string(), "v".to_string()),
+            ("k2".to_string(), "v".to_string()),
+            ("k3".to_string(), "v".to_string()),
+        ]);
+        assert!(limits.check(&too_many).is_err());
+
+        let too_large = HashMap::from([
+            ("key1".to_string(), "val1".to_string()),
+            ("key2".to_string(), "val2".to_string()),
+        ]);
+        assert!(limits.check(&too_large).is_err());
+        assert!(limits.check_encoded(&encode(&too_large)).is_err());
+    }
+}
@@ -19,6 +19,8 @@ use std::env;
 
 use once_cell::sync::Lazy;
 
+use crate::utils::network_util::NetworkUtil;
+
 pub const ROCKETMQ_HOME_ENV: &str = "ROCKETMQ_HOME";
 pub const ROCKETMQ_HOME_PROPERTY: &str = "rocketmq.home.dir";
 pub const NAMESRV_ADDR_ENV: &str = "NAMESRV_ADDR";
@@ -43,8 +45,11 @@ pub const CID_ONSAPI_PULL_GROUP: &str = "CID_ONSAPI_PULL";
 pub const CID_RMQ_SYS_PREFIX: &str = "CID_RMQ_SYS_";
 pub const IS_SUPPORT_HEART_BEAT_V2: &str = "IS_SUPPORT_HEART_BEAT_V2";
 pub const IS_SUB_CHANGE: &str = "IS_SUB_CHANGE";
+pub const SUBSCRIPTION_DATA_VERSION: &str = "SUBSCRIPTION_DATA_VERSION";
 pub const DEFAULT_CHARSET: &str = "UTF-8";
 pub const MASTER_ID: u64 = 0;
+/// [`MASTER_ID`] as keyed in the broker address tables of the route data.
+pub const MASTER_BROKER_ADDR_ID: i64 = MASTER_ID as i64;
 pub const FIRST_SLAVE_ID: u64 = 1;
 pub const FIRST_BROKER_CONTROLLER_ID: u64 = 1;
 pub const UNIT_PRE_SIZE_FOR_MSG: i32 = 28;
@@ -134,7 +139,7 @@ pub fn broker_vip_channel(is_change: bool, broker_addr: &str) -> String {
         if let Some(split) = broker_addr.rfind(':') {
             let ip = &broker_addr[..split];
             if let Ok(port) = broker_addr[split + 1..].parse::<i32>() {
-                let broker_addr_new = format!("{}:{}", ip, port - 2);
+                let broker_addr_new = NetworkUtil::host_port(ip, port - 2);
                 return broker_addr_new;
             }
         }
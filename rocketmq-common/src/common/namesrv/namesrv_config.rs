@@ -18,11 +18,13 @@
 use std::env;
 
 use serde::Deserialize;
+use serde::Serialize;
 
 use crate::common::mix_all::ROCKETMQ_HOME_ENV;
 use crate::common::mix_all::ROCKETMQ_HOME_PROPERTY;
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct NamesrvConfig {
     #[serde(alias = "rocketmqHome")]
     pub rocketmq_home: String,
@@ -89,6 +91,10 @@ pub struct NamesrvConfig {
 
     #[serde(alias = "configBlackList")]
     pub config_black_list: String,
+
+    /// Cache the encoded route of each topic until the route tables change.
+    #[serde(alias = "enableTopicRouteCache")]
+    pub enable_topic_route_cache: bool,
 }
 
 impl Default for NamesrvConfig {
@@ -136,6 +142,7 @@ impl Default for NamesrvConfig {
             wait_seconds_for_service: 45,
             delete_topic_with_broker_registration: false,
             config_black_list: "configBlackList;configStorePath;kvConfigPath".to_string(),
+            enable_topic_route_cache: false,
         }
     }
 }
@@ -203,5 +210,6 @@ mod tests {
             config.config_black_list,
             "configBlackList;configStorePath;kvConfigPath".to_string()
         );
+        assert!(!config.enable_topic_route_cache);
     }
 }
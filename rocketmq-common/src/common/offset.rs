@@ -0,0 +1,240 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+//! Typed offsets of the store. Queue offsets count consume queue entries, commit log offsets
+//! count bytes, mixing them up or truncating them through casts used to go unnoticed.
+
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::num::TryFromIntError;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+macro_rules! offset_newtype {
+    ($(#[$meta:meta])* $name:ident) => {
+        $(#[$meta])*
+        #[derive(
+            Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+        )]
+        #[serde(transparent)]
+        pub struct $name(i64);
+
+        impl $name {
+            #[inline]
+            pub const fn new(offset: i64) -> Self {
+                Self(offset)
+            }
+
+            #[inline]
+            pub const fn value(self) -> i64 {
+                self.0
+            }
+
+            #[inline]
+            pub fn checked_add(self, delta: i64) -> Option<Self> {
+                self.0.checked_add(delta).map(Self)
+            }
+
+            #[inline]
+            pub fn checked_sub(self, delta: i64) -> Option<Self> {
+                self.0.checked_sub(delta).map(Self)
+            }
+
+            #[inline]
+            pub fn saturating_add(self, delta: i64) -> Self {
+                Self(self.0.saturating_add(delta))
+            }
+
+            /// How far `end` is ahead of this offset, `0` if it is not.
+            #[inline]
+            pub fn distance_to(self, end: Self) -> i64 {
+                end.0.saturating_sub(self.0).max(0)
+            }
+        }
+
+        impl From<i64> for $name {
+            #[inline]
+            fn from(offset: i64) -> Self {
+                Self(offset)
+            }
+        }
+
+        impl From<$name> for i64 {
+            #[inline]
+            fn from(offset: $name) -> Self {
+                offset.0
+            }
+        }
+
+        impl TryFrom<u64> for $name {
+            type Error = TryFromIntError;
+
+            #[inline]
+            fn try_from(offset: u64) -> Result<Self, Self::Error> {
+                i64::try_from(offset).map(Self)
+            }
+        }
+
+        impl Display for $name {
+            fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+                write!(f, "{}", self.0)
+            }
+        }
+    };
+}
+
+offset_newtype!(
+    /// Offset of a message in its consume queue, counted in entries.
+    QueueOffset
+);
+
+offset_newtype!(
+    /// Physical offset of a message in the commit log, counted in bytes.
+    CommitLogOffset
+);
+
+impl QueueOffset {
+    /// Byte position of the entry in a consume queue made of `unit_size` byte entries, `None` on
+    /// overflow.
+    #[inline]
+    pub fn to_byte_offset(self, unit_size: i32) -> Option<i64> {
+        self.0.checked_mul(i64::from(unit_size))
+    }
+
+    /// The entry at byte position `byte_offset` of a consume queue made of `unit_size` byte
+    /// entries.
+    #[inline]
+    pub fn from_byte_offset(byte_offset: i64, unit_size: i32) -> Self {
+        Self(byte_offset / i64::from(unit_size))
+    }
+}
+
+impl CommitLogOffset {
+    /// Offset right after a message of `size` bytes stored at this offset, `None` on overflow.
+    #[inline]
+    pub fn checked_add_size(self, size: i32) -> Option<Self> {
+        self.checked_add(i64::from(size))
+    }
+}
+
+/// Delay level of a scheduled message, `0` means not delayed.
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(transparent)]
+pub struct DelayLevel(i32);
+
+impl DelayLevel {
+    #[inline]
+    pub const fn new(level: i32) -> Self {
+        Self(level)
+    }
+
+    #[inline]
+    pub const fn value(self) -> i32 {
+        self.0
+    }
+
+    #[inline]
+    pub const fn is_delayed(self) -> bool {
+        self.0 > 0
+    }
+
+    #[inline]
+    pub fn checked_add(self, delta: i32) -> Option<Self> {
+        self.0.checked_add(delta).map(Self)
+    }
+}
+
+impl From<i32> for DelayLevel {
+    #[inline]
+    fn from(level: i32) -> Self {
+        Self(level)
+    }
+}
+
+impl From<DelayLevel> for i32 {
+    #[inline]
+    fn from(level: DelayLevel) -> Self {
+        level.0
+    }
+}
+
+impl TryFrom<i64> for DelayLevel {
+    type Error = TryFromIntError;
+
+    #[inline]
+    fn try_from(level: i64) -> Result<Self, Self::Error> {
+        i32::try_from(level).map(Self)
+    }
+}
+
+impl Display for DelayLevel {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn offsets_use_checked_arithmetic() {
+        assert_eq!(
+            QueueOffset::new(i64::MAX).checked_add(1),
+            None,
+            "queue offset overflow must not wrap"
+        );
+        assert_eq!(QueueOffset::new(3).to_byte_offset(20), Some(60));
+        assert_eq!(QueueOffset::new(i64::MAX).to_byte_offset(20), None);
+        assert_eq!(QueueOffset::from_byte_offset(60, 20), QueueOffset::new(3));
+        assert_eq!(
+            CommitLogOffset::new(100).checked_add_size(28),
+            Some(CommitLogOffset::new(128))
+        );
+        assert_eq!(
+            CommitLogOffset::new(100).distance_to(CommitLogOffset::new(150)),
+            50
+        );
+        assert_eq!(
+            CommitLogOffset::new(150).distance_to(CommitLogOffset::new(100)),
+            0
+        );
+    }
+
+    #[test]
+    fn conversions_reject_truncation() {
+        assert!(CommitLogOffset::try_from(u64::MAX).is_err());
+        assert_eq!(
+            CommitLogOffset::try_from(42u64),
+            Ok(CommitLogOffset::new(42))
+        );
+        assert!(DelayLevel::try_from(i64::from(i32::MAX) + 1).is_err());
+        assert_eq!(DelayLevel::try_from(3i64), Ok(DelayLevel::new(3)));
+        assert!(!DelayLevel::default().is_delayed());
+    }
+
+    #[test]
+    fn offsets_serialize_as_numbers() {
+        assert_eq!(serde_json::to_string(&QueueOffset::new(7)).unwrap(), "7");
+        assert_eq!(
+            serde_json::from_str::<DelayLevel>("2").unwrap(),
+            DelayLevel::new(2)
+        );
+    }
+}
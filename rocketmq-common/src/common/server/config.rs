@@ -18,10 +18,24 @@ use serde::Deserialize;
 use serde::Serialize;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
+#[serde(rename_all = "camelCase", default)]
 pub struct ServerConfig {
     pub listen_port: u32,
     pub bind_address: String,
+    /// Limits of the requests with a given request code, the requests over a limit are answered
+    /// with `FLOW_CONTROL` without being processed.
+    pub request_code_limits: Vec<RequestCodeLimit>,
+}
+
+/// QPS and concurrency limits of the requests with request code `code`, 0 is no limit.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RequestCodeLimit {
+    pub code: i32,
+    /// Requests accepted per second, bursts of up to one second worth of requests included.
+    pub max_qps: u32,
+    /// Requests processed at the same time.
+    pub max_concurrency: u32,
 }
 
 impl Default for ServerConfig {
@@ -29,6 +43,7 @@ impl Default for ServerConfig {
         ServerConfig {
             listen_port: 10911,
             bind_address: "0.0.0.0".to_string(),
+            request_code_limits: Vec::new(),
         }
     }
 }
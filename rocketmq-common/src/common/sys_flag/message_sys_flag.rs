@@ -31,6 +31,11 @@ impl MessageSysFlag {
         flag & !Self::COMPRESSED_FLAG
     }
 
+    /// Clears the compressed flag along with the compression type.
+    pub fn clear_compression_flags(flag: i32) -> i32 {
+        flag & !(Self::COMPRESSED_FLAG | Self::COMPRESSION_TYPE_COMPARATOR)
+    }
+
     pub fn get_compression_type(flag: i32) -> CompressionType {
         let compression_type_value = (flag & Self::COMPRESSION_TYPE_COMPARATOR) >> 8;
         CompressionType::find_by_value(compression_type_value)
@@ -21,9 +21,22 @@ pub enum Error {
     #[error("Serialization error: {0}")]
     JsonError(#[from] serde_json::Error),
 
+    #[error("Deserialization error at `{path}`: {source}, raw: {snippet}")]
+    JsonDecodeError {
+        /// Path of the field that failed to decode, `.` for the root value.
+        path: String,
+        /// Raw JSON around the failing position.
+        snippet: String,
+        #[source]
+        source: serde_json::Error,
+    },
+
     #[error("{0}")]
     RuntimeException(String),
 
     #[error("{0}")]
     UnsupportedOperationException(String),
+
+    #[error("{0}")]
+    IllegalMessageProperties(String),
 }
@@ -18,6 +18,7 @@ use std::collections::hash_map::DefaultHasher;
 use std::collections::HashSet;
 use std::hash::Hash;
 use std::hash::Hasher;
+use std::net::IpAddr;
 use std::net::SocketAddr;
 
 use bytes::BufMut;
@@ -26,6 +27,7 @@ use crate::common::message::message_ext::MessageExt;
 use crate::common::message::MessageConst;
 use crate::MessageDecoder::PROPERTY_SEPARATOR;
 use crate::UtilAll::bytes_to_string;
+use crate::UtilAll::string_to_bytes;
 
 pub fn get_sharding_key_index(sharding_key: &str, index_size: usize) -> usize {
     let mut hasher = DefaultHasher::new();
@@ -130,8 +132,23 @@ pub fn build_batch_message_id(
     message_id
 }
 
-pub fn parse_message_id(_msg_id: impl Into<String>) -> (SocketAddr, i64) {
-    unimplemented!()
+/// Parses an offset message id built by [`build_message_id`] back into the store host and
+/// the commit log offset. Returns `None` when the id is not a valid IPv4 or IPv6 message id.
+pub fn parse_message_id(msg_id: impl Into<String>) -> Option<(SocketAddr, i64)> {
+    let bytes = string_to_bytes(msg_id)?;
+    let ip_len = match bytes.len() {
+        16 => 4,
+        28 => 16,
+        _ => return None,
+    };
+    let ip = if ip_len == 4 {
+        IpAddr::from(<[u8; 4]>::try_from(&bytes[..4]).ok()?)
+    } else {
+        IpAddr::from(<[u8; 16]>::try_from(&bytes[..16]).ok()?)
+    };
+    let port = i32::from_be_bytes(bytes[ip_len..ip_len + 4].try_into().ok()?);
+    let offset = i64::from_be_bytes(bytes[ip_len + 4..].try_into().ok()?);
+    Some((SocketAddr::new(ip, port as u16), offset))
 }
 
 #[cfg(test)]
@@ -200,6 +217,20 @@ mod tests {
         assert_eq!(result, "7F0000010000000C0000000000000001");
     }
 
+    #[test]
+    fn test_parse_message_id() {
+        let socket_addr: SocketAddr = "127.0.0.1:12".parse().unwrap();
+        let msg_id = build_message_id(socket_addr, 1024);
+        assert_eq!(parse_message_id(msg_id), Some((socket_addr, 1024)));
+
+        let socket_addr: SocketAddr = "[::1]:10911".parse().unwrap();
+        let msg_id = build_message_id(socket_addr, 7);
+        assert_eq!(parse_message_id(msg_id), Some((socket_addr, 7)));
+
+        assert_eq!(parse_message_id(""), None);
+        assert_eq!(parse_message_id("7F000001"), None);
+    }
+
     #[test]
     fn build_batch_message_id_creates_correct_id_for_single_position() {
         let socket_addr = SocketAddr::new(Ipv4Addr::new(127, 0, 0, 1).into(), 8080);
@@ -14,7 +14,10 @@
  * See the License for the specific language governing permissions and
  * limitations under the License.
  */
+use std::fmt::Display;
 use std::net::IpAddr;
+use std::net::Ipv6Addr;
+use std::net::SocketAddr;
 
 pub struct NetworkUtil;
 
@@ -34,4 +37,79 @@ impl NetworkUtil {
             },
         }
     }
+
+    /// Host with the brackets of an IPv6 literal removed, `[::1]` gives `::1`.
+    pub fn normalize_host(host: &str) -> &str {
+        host.strip_prefix('[')
+            .and_then(|host| host.strip_suffix(']'))
+            .unwrap_or(host)
+    }
+
+    /// `host:port` address of `host`, IPv6 literals are put in brackets so the port can be
+    /// told apart from the address.
+    pub fn host_port(host: &str, port: impl Display) -> String {
+        let host = Self::normalize_host(host);
+        if host.parse::<Ipv6Addr>().is_ok() {
+            format!("[{}]:{}", host, port)
+        } else {
+            format!("{}:{}", host, port)
+        }
+    }
+
+    /// Splits `addr` into host and port. The port follows the last colon, so IPv6 literals
+    /// work with or without brackets, and the brackets are removed from the host.
+    pub fn split_host_port(addr: &str) -> Option<(&str, u16)> {
+        let (host, port) = addr.rsplit_once(':')?;
+        let host = Self::normalize_host(host);
+        if host.is_empty() {
+            return None;
+        }
+        Some((host, port.parse().ok()?))
+    }
+
+    /// Socket address of an `ip:port` address, `[v6]:port` and `v6:port` included.
+    pub fn string_to_socket_address(addr: &str) -> Option<SocketAddr> {
+        let (host, port) = Self::split_host_port(addr)?;
+        Some(SocketAddr::new(host.parse().ok()?, port))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_and_parses_ipv6_addresses() {
+        assert_eq!(NetworkUtil::host_port("10.0.0.1", 10911), "10.0.0.1:10911");
+        assert_eq!(NetworkUtil::host_port("fd00::1", 10911), "[fd00::1]:10911");
+        assert_eq!(
+            NetworkUtil::host_port("[fd00::1]", 10911),
+            "[fd00::1]:10911"
+        );
+        assert_eq!(NetworkUtil::host_port("broker-0", 10911), "broker-0:10911");
+
+        assert_eq!(
+            NetworkUtil::split_host_port("[fd00::1]:9876"),
+            Some(("fd00::1", 9876))
+        );
+        assert_eq!(
+            NetworkUtil::split_host_port("fd00::1:9876"),
+            Some(("fd00::1", 9876))
+        );
+        assert_eq!(
+            NetworkUtil::split_host_port("namesrv:9876"),
+            Some(("namesrv", 9876))
+        );
+        assert_eq!(NetworkUtil::split_host_port("[]:9876"), None);
+        assert_eq!(NetworkUtil::split_host_port("namesrv"), None);
+
+        let addr = NetworkUtil::string_to_socket_address("[fd00::1]:10911").unwrap();
+        assert!(addr.is_ipv6());
+        assert_eq!(addr.port(), 10911);
+        assert_eq!(
+            NetworkUtil::string_to_socket_address("fd00::1:10911"),
+            Some(addr)
+        );
+        assert!(NetworkUtil::string_to_socket_address("broker-0:10911").is_none());
+    }
 }
@@ -18,8 +18,12 @@
 use std::fmt::Debug;
 use std::path::PathBuf;
 
+use anyhow::Context;
 use config::Config;
+use serde::de::DeserializeOwned;
 use serde::Deserialize;
+use serde::Serialize;
+use serde_json::Value;
 
 pub fn parse_config_file<'de, C>(config_file: PathBuf) -> anyhow::Result<C, anyhow::Error>
 where
@@ -36,3 +40,112 @@ where
     //info!("parse config: {:?}", config_file);
     Ok(config_file)
 }
+
+/// Parses a Java style properties file (`key=value` per line, `#` or `!` comments) into `C`.
+///
+/// Keys are matched against the fields of `C` ignoring case and underscores, so both
+/// `kvConfigPath` and `kv_config_path` map to the same field. Values are converted to the
+/// type of the field in `C::default()`, keys which do not match any field are ignored and
+/// fields which are absent keep their default values.
+pub fn parse_properties_config_file<C>(config_file: PathBuf) -> anyhow::Result<C, anyhow::Error>
+where
+    C: Default + Serialize + DeserializeOwned,
+{
+    let content = std::fs::read_to_string(&config_file)
+        .with_context(|| format!("read config file {:?} failed", config_file))?;
+    parse_properties::<C>(content.as_str())
+        .with_context(|| format!("parse config file {:?} failed", config_file))
+}
+
+/// Parses properties `content` into `C`, see [`parse_properties_config_file`].
+pub fn parse_properties<C>(content: &str) -> anyhow::Result<C, anyhow::Error>
+where
+    C: Default + Serialize + DeserializeOwned,
+{
+    let mut config = serde_json::to_value(C::default())?;
+    let fields = config
+        .as_object_mut()
+        .context("config type must be a struct")?;
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with('!') {
+            continue;
+        }
+        let Some((key, value)) = line.split_once(['=', ':']) else {
+            continue;
+        };
+        let key = normalize_property_key(key);
+        let value = value.trim();
+        let Some((_, field)) = fields
+            .iter_mut()
+            .find(|(name, _)| normalize_property_key(name) == key)
+        else {
+            continue;
+        };
+        *field = match field {
+            Value::Bool(_) => Value::Bool(
+                value
+                    .parse::<bool>()
+                    .with_context(|| format!("invalid bool value for {}: {}", key, value))?,
+            ),
+            Value::Number(_) => Value::Number(
+                value
+                    .parse::<serde_json::Number>()
+                    .with_context(|| format!("invalid number value for {}: {}", key, value))?,
+            ),
+            _ => Value::String(value.to_string()),
+        };
+    }
+    Ok(serde_json::from_value::<C>(config)?)
+}
+
+fn normalize_property_key(key: &str) -> String {
+    key.trim()
+        .chars()
+        .filter(|c| *c != '_')
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Serialize, Deserialize)]
+    #[serde(default)]
+    struct TestConfig {
+        #[serde(alias = "listenPort")]
+        listen_port: u32,
+        #[serde(alias = "clusterTest")]
+        cluster_test: bool,
+        #[serde(alias = "productEnvName")]
+        product_env_name: String,
+    }
+
+    impl Default for TestConfig {
+        fn default() -> Self {
+            TestConfig {
+                listen_port: 9876,
+                cluster_test: false,
+                product_env_name: "center".to_string(),
+            }
+        }
+    }
+
+    #[test]
+    fn parse_properties_matches_camel_and_snake_case_keys() {
+        let config = parse_properties::<TestConfig>(
+            "# comment\nlistenPort=10000\ncluster_test = true\nunknownKey=1\n",
+        )
+        .unwrap();
+        assert_eq!(config.listen_port, 10000);
+        assert!(config.cluster_test);
+        assert_eq!(config.product_env_name, "center");
+    }
+
+    #[test]
+    fn parse_properties_rejects_invalid_value() {
+        assert!(parse_properties::<TestConfig>("listenPort=abc").is_err());
+        assert!(parse_properties::<TestConfig>("clusterTest=yes").is_err());
+    }
+}
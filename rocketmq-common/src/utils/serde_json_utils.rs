@@ -14,30 +14,103 @@
  * See the License for the specific language governing permissions and
  * limitations under the License.
  */
+use std::borrow::Cow;
+use std::io::Read;
+
+use flate2::read::GzDecoder;
+use serde::Deserialize;
+use serde_json::Value;
+
 use crate::error::Error;
 
+/// Magic header of gzip compressed data.
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Maximum number of bytes shown on each side of the failing position in decode errors.
+const SNIPPET_RADIUS: usize = 32;
+
+/// Maximum number of enum values repaired by a single lenient decode.
+const MAX_LENIENT_REPAIRS: usize = 64;
+
 pub struct SerdeJsonUtils;
 
 impl SerdeJsonUtils {
+    /// Decodes `bytes` into `T`.
+    ///
+    /// Gzip compressed bytes are decompressed first. Errors carry the path of the failing
+    /// field and the raw JSON around the failing position.
     pub fn decode<T>(bytes: &[u8]) -> Result<T, Error>
     where
         T: serde::de::DeserializeOwned,
     {
-        serde_json::from_slice::<T>(bytes).map_err(Error::JsonError)
+        let bytes = Self::decompress_if_gzip(bytes)?;
+        Self::decode_strict(bytes.as_ref())
+    }
+
+    /// Decodes `bytes` into `T` like [`SerdeJsonUtils::decode_lenient`] when `lenient` is set,
+    /// like [`SerdeJsonUtils::decode`] otherwise.
+    pub fn decode_with<T>(bytes: &[u8], lenient: bool) -> Result<T, Error>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        if lenient {
+            Self::decode_lenient(bytes)
+        } else {
+            Self::decode(bytes)
+        }
+    }
+
+    /// Decodes `bytes` into `T`, tolerating the JSON produced by Java brokers and clients:
+    ///
+    /// * an empty body is decoded as an empty object (`{}`)
+    /// * single quoted strings are accepted
+    /// * enum values are matched ignoring case
+    ///
+    /// Missing `Option` fields are decoded as `None` in both strict and lenient mode.
+    pub fn decode_lenient<T>(bytes: &[u8]) -> Result<T, Error>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        let bytes = Self::decompress_if_gzip(bytes)?;
+        let bytes: &[u8] = if bytes.iter().all(u8::is_ascii_whitespace) {
+            b"{}"
+        } else {
+            bytes.as_ref()
+        };
+        let text = String::from_utf8_lossy(bytes);
+        let normalized = normalize_single_quotes(text.as_ref());
+        let mut value = Self::decode_strict::<Value>(normalized.as_bytes())?;
+        for _ in 0..MAX_LENIENT_REPAIRS {
+            if let Ok(result) = T::deserialize(&value) {
+                return Ok(result);
+            }
+            let error = match serde_path_to_error::deserialize::<_, T>(&value) {
+                Ok(result) => return Ok(result),
+                Err(error) => error,
+            };
+            if !repair_enum_case(&mut value, &error) {
+                return Err(Error::JsonDecodeError {
+                    path: error.path().to_string(),
+                    snippet: value_snippet(&value, error.path()),
+                    source: error.into_inner(),
+                });
+            }
+        }
+        T::deserialize(&value).map_err(Error::JsonError)
     }
 
     pub fn from_json_str<T>(json: &str) -> Result<T, Error>
     where
         T: serde::de::DeserializeOwned,
     {
-        serde_json::from_str(json).map_err(Error::JsonError)
+        Self::decode_strict(json.as_bytes())
     }
 
     pub fn from_json_slice<T>(json: &[u8]) -> Result<T, Error>
     where
         T: serde::de::DeserializeOwned,
     {
-        serde_json::from_slice(json).map_err(Error::JsonError)
+        Self::decode_strict(json)
     }
 
     pub fn to_json<T>(value: &T) -> Result<String, Error>
@@ -69,6 +142,175 @@ impl SerdeJsonUtils {
     }
 }
 
+impl SerdeJsonUtils {
+    /// Decodes with plain serde_json, and decodes again tracking the path only to report a
+    /// failure.
+    fn decode_strict<T>(bytes: &[u8]) -> Result<T, Error>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        if let Ok(value) = serde_json::from_slice(bytes) {
+            return Ok(value);
+        }
+        let mut deserializer = serde_json::Deserializer::from_slice(bytes);
+        let (path, source) = match serde_path_to_error::deserialize::<_, T>(&mut deserializer) {
+            Ok(value) => match deserializer.end() {
+                Ok(_) => return Ok(value),
+                Err(source) => (".".to_string(), source),
+            },
+            Err(error) => (error.path().to_string(), error.into_inner()),
+        };
+        Err(Error::JsonDecodeError {
+            path,
+            snippet: raw_snippet(bytes, source.line(), source.column()),
+            source,
+        })
+    }
+
+    fn decompress_if_gzip(bytes: &[u8]) -> Result<Cow<'_, [u8]>, Error> {
+        if !bytes.starts_with(&GZIP_MAGIC) {
+            return Ok(Cow::Borrowed(bytes));
+        }
+        let mut decompressed = Vec::with_capacity(bytes.len() * 4);
+        GzDecoder::new(bytes)
+            .read_to_end(&mut decompressed)
+            .map_err(|error| {
+                Error::RuntimeException(format!("decompress gzip body failed: {}", error))
+            })?;
+        Ok(Cow::Owned(decompressed))
+    }
+}
+
+/// Returns the raw JSON around `line`:`column` (both 1-based, as reported by serde_json).
+fn raw_snippet(bytes: &[u8], line: usize, column: usize) -> String {
+    let text = String::from_utf8_lossy(bytes);
+    let Some(line_text) = text.lines().nth(line.saturating_sub(1)) else {
+        return String::new();
+    };
+    let chars = line_text.chars().collect::<Vec<_>>();
+    let position = column.min(chars.len());
+    let begin = position.saturating_sub(SNIPPET_RADIUS);
+    let end = (position + SNIPPET_RADIUS).min(chars.len());
+    chars[begin..end].iter().collect()
+}
+
+fn value_snippet(value: &Value, path: &serde_path_to_error::Path) -> String {
+    let snippet = value_at_path(value, path).unwrap_or(value).to_string();
+    if snippet.chars().count() > SNIPPET_RADIUS * 2 {
+        let truncated = snippet.chars().take(SNIPPET_RADIUS * 2).collect::<String>();
+        format!("{}...", truncated)
+    } else {
+        snippet
+    }
+}
+
+fn value_at_path<'a>(value: &'a Value, path: &serde_path_to_error::Path) -> Option<&'a Value> {
+    path.iter()
+        .try_fold(value, |current, segment| match segment {
+            serde_path_to_error::Segment::Seq { index } => current.get(*index),
+            serde_path_to_error::Segment::Map { key } => current.get(key.as_str()),
+            serde_path_to_error::Segment::Enum { variant } => current.get(variant.as_str()),
+            serde_path_to_error::Segment::Unknown => None,
+        })
+}
+
+fn value_at_path_mut<'a>(
+    value: &'a mut Value,
+    path: &serde_path_to_error::Path,
+) -> Option<&'a mut Value> {
+    path.iter()
+        .try_fold(value, |current, segment| match segment {
+            serde_path_to_error::Segment::Seq { index } => current.get_mut(*index),
+            serde_path_to_error::Segment::Map { key } => current.get_mut(key.as_str()),
+            serde_path_to_error::Segment::Enum { variant } => current.get_mut(variant.as_str()),
+            serde_path_to_error::Segment::Unknown => None,
+        })
+}
+
+/// Replaces an unknown enum value with the expected variant that matches it ignoring case,
+/// returns `false` if `error` is not such an error.
+fn repair_enum_case(
+    value: &mut Value,
+    error: &serde_path_to_error::Error<serde_json::Error>,
+) -> bool {
+    // serde reports unknown variants as: unknown variant `x`, expected one of `A`, `B`
+    let message = error.inner().to_string();
+    if !message.starts_with("unknown variant") {
+        return false;
+    }
+    let mut names = message.split('`').skip(1).step_by(2);
+    let Some(unknown) = names.next() else {
+        return false;
+    };
+    let Some(expected) = names.find(|name| name.eq_ignore_ascii_case(unknown)) else {
+        return false;
+    };
+    let Some(target) = value_at_path_mut(value, error.path()) else {
+        return false;
+    };
+    match target {
+        Value::String(variant) if variant == unknown => {
+            *variant = expected.to_string();
+            true
+        }
+        Value::Object(map) if map.len() == 1 && map.contains_key(unknown) => {
+            let content = map.remove(unknown).unwrap_or(Value::Null);
+            map.insert(expected.to_string(), content);
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Rewrites single quoted strings (`'value'`) into double quoted JSON strings.
+fn normalize_single_quotes(text: &str) -> Cow<'_, str> {
+    if !text.contains('\'') {
+        return Cow::Borrowed(text);
+    }
+    let mut output = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                output.push(c);
+                while let Some(c) = chars.next() {
+                    output.push(c);
+                    match c {
+                        '\\' => {
+                            if let Some(escaped) = chars.next() {
+                                output.push(escaped);
+                            }
+                        }
+                        '"' => break,
+                        _ => {}
+                    }
+                }
+            }
+            '\'' => {
+                output.push('"');
+                while let Some(c) = chars.next() {
+                    match c {
+                        '\\' => match chars.next() {
+                            Some('\'') => output.push('\''),
+                            Some(escaped) => {
+                                output.push('\\');
+                                output.push(escaped);
+                            }
+                            None => output.push('\\'),
+                        },
+                        '"' => output.push_str("\\\""),
+                        '\'' => break,
+                        _ => output.push(c),
+                    }
+                }
+                output.push('"');
+            }
+            _ => output.push(c),
+        }
+    }
+    Cow::Owned(output)
+}
+
 #[cfg(test)]
 mod tests {
     use serde_json::json;
@@ -132,6 +374,7 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    use std::collections::HashMap;
     use std::fmt::Debug;
 
     use serde::Deserialize;
@@ -205,4 +448,80 @@ mod tests {
         let result: Result<String, Error> = SerdeJsonUtils::to_json(&value);
         assert!(result.is_ok());
     }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    enum TestKind {
+        Normal,
+        Delay,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    #[serde(rename_all = "camelCase")]
+    struct TestRoute {
+        broker_name: String,
+        kind: TestKind,
+        remark: Option<String>,
+    }
+
+    #[test]
+    fn decode_error_contains_field_path_and_raw_snippet() {
+        let json = br#"{"brokerName":"broker-a","kind":"Normal","remark":1}"#;
+        let error = SerdeJsonUtils::decode::<TestRoute>(json).unwrap_err();
+        match error {
+            Error::JsonDecodeError { path, snippet, .. } => {
+                assert_eq!(path, "remark");
+                assert!(snippet.contains("\"remark\":1"));
+            }
+            other => panic!("unexpected error: {}", other),
+        }
+    }
+
+    #[test]
+    fn decode_gzip_body() {
+        use std::io::Write;
+
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder
+            .write_all(br#"{"brokerName":"broker-a","kind":"Delay"}"#)
+            .unwrap();
+        let compressed = encoder.finish().unwrap();
+        let route = SerdeJsonUtils::decode::<TestRoute>(compressed.as_slice()).unwrap();
+        assert_eq!(route.kind, TestKind::Delay);
+        assert_eq!(route.remark, None);
+    }
+
+    #[test]
+    fn decode_lenient_tolerates_java_quirks() {
+        let json = br#"{'brokerName':'broker-\'a\'',"kind":"DELAY"}"#;
+        assert!(SerdeJsonUtils::decode::<TestRoute>(json).is_err());
+        let route = SerdeJsonUtils::decode_lenient::<TestRoute>(json).unwrap();
+        assert_eq!(route.broker_name, "broker-'a'");
+        assert_eq!(route.kind, TestKind::Delay);
+        assert_eq!(route.remark, None);
+        assert!(SerdeJsonUtils::decode_with::<TestRoute>(json, false).is_err());
+        assert_eq!(
+            SerdeJsonUtils::decode_with::<TestRoute>(json, true).unwrap(),
+            route
+        );
+    }
+
+    #[test]
+    fn decode_lenient_empty_body() {
+        let value = SerdeJsonUtils::decode_lenient::<HashMap<String, String>>(b"  ").unwrap();
+        assert!(value.is_empty());
+        let error = SerdeJsonUtils::decode_lenient::<TestRoute>(b"").unwrap_err();
+        assert!(error.to_string().contains("brokerName"));
+    }
+
+    #[test]
+    fn decode_lenient_reports_unrepairable_enum() {
+        let json = br#"{"brokerName":"broker-a","kind":"Unknown"}"#;
+        match SerdeJsonUtils::decode_lenient::<TestRoute>(json).unwrap_err() {
+            Error::JsonDecodeError { path, snippet, .. } => {
+                assert_eq!(path, "kind");
+                assert_eq!(snippet, "\"Unknown\"");
+            }
+            other => panic!("unexpected error: {}", other),
+        }
+    }
 }
@@ -0,0 +1,133 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::RwLock;
+
+use lazy_static::lazy_static;
+
+use crate::error::FilterError;
+use crate::filter_spi::FilterSpi;
+
+lazy_static! {
+    static ref INSTANCE: FilterFactory = FilterFactory::default();
+}
+
+/// Registry of the filter types the broker accepts besides TAG.
+///
+/// Crates providing a filter type register it once at startup through
+/// [`FilterFactory::instance`]; the broker looks the type of a subscription up here to compile
+/// its expression.
+#[derive(Default)]
+pub struct FilterFactory {
+    filter_spi_table: RwLock<HashMap<String, Arc<dyn FilterSpi>>>,
+}
+
+impl FilterFactory {
+    pub fn instance() -> &'static FilterFactory {
+        &INSTANCE
+    }
+
+    /// Registers `filter_spi` under its type, a type can only be registered once.
+    pub fn register(&self, filter_spi: Arc<dyn FilterSpi>) -> Result<(), FilterError> {
+        let mut table = self.filter_spi_table.write().unwrap();
+        match table.entry(filter_spi.of_type().to_string()) {
+            Entry::Occupied(entry) => Err(FilterError::DuplicateType(entry.key().clone())),
+            Entry::Vacant(entry) => {
+                entry.insert(filter_spi);
+                Ok(())
+            }
+        }
+    }
+
+    pub fn unregister(&self, type_: &str) -> Option<Arc<dyn FilterSpi>> {
+        self.filter_spi_table.write().unwrap().remove(type_)
+    }
+
+    pub fn get(&self, type_: &str) -> Option<Arc<dyn FilterSpi>> {
+        self.filter_spi_table.read().unwrap().get(type_).cloned()
+    }
+
+    pub fn registered_types(&self) -> Vec<String> {
+        self.filter_spi_table
+            .read()
+            .unwrap()
+            .keys()
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::any::Any;
+    use std::error::Error;
+
+    use super::*;
+    use crate::expression::evaluation_context::EvaluationContext;
+    use crate::expression::Expression;
+
+    struct ConstantExpression(bool);
+
+    impl Expression for ConstantExpression {
+        fn evaluate(
+            &self,
+            _context: &dyn EvaluationContext,
+        ) -> Result<Box<dyn Any>, Box<dyn Error>> {
+            Ok(Box::new(self.0))
+        }
+    }
+
+    struct ConstantFilter;
+
+    impl FilterSpi for ConstantFilter {
+        fn compile(&self, expr: &str) -> Result<Box<dyn Expression + Send + Sync>, FilterError> {
+            match expr {
+                "TRUE" => Ok(Box::new(ConstantExpression(true))),
+                "FALSE" => Ok(Box::new(ConstantExpression(false))),
+                _ => Err(FilterError::Compile {
+                    expression: expr.to_string(),
+                    reason: "not a constant".to_string(),
+                }),
+            }
+        }
+
+        fn of_type(&self) -> &str {
+            "CONSTANT"
+        }
+    }
+
+    #[test]
+    fn register_and_lookup_filter_types() {
+        let factory = FilterFactory::default();
+        factory.register(Arc::new(ConstantFilter)).unwrap();
+        assert!(matches!(
+            factory.register(Arc::new(ConstantFilter)),
+            Err(FilterError::DuplicateType(_))
+        ));
+        assert_eq!(factory.registered_types(), vec!["CONSTANT".to_string()]);
+
+        let filter_spi = factory.get("CONSTANT").unwrap();
+        assert!(filter_spi.compile("TRUE").is_ok());
+        assert!(filter_spi.compile("MAYBE").is_err());
+        assert!(factory.get("SQL92").is_none());
+
+        assert!(factory.unregister("CONSTANT").is_some());
+        assert!(factory.get("CONSTANT").is_none());
+    }
+}
@@ -88,4 +88,98 @@ impl BloomFilter {
             None => false,
         }
     }
+
+    /// Bit positions of `s`, derived from two hashes of it by double hashing.
+    pub fn calc_bit_positions(&self, s: &str) -> Vec<i32> {
+        let hash1 = murmur3_32(s.as_bytes(), 0) as i32;
+        let hash2 = murmur3_32(s.as_bytes(), hash1 as u32) as i32;
+        (1..=self.k)
+            .map(|i| {
+                let mut combined_hash = hash1.wrapping_add(i.wrapping_mul(hash2));
+                if combined_hash < 0 {
+                    combined_hash = !combined_hash;
+                }
+                combined_hash % self.m
+            })
+            .collect()
+    }
+
+    pub fn generate(&self, s: &str) -> BloomFilterData {
+        BloomFilterData::new(self.calc_bit_positions(s), self.m as u32)
+    }
+
+    /// Sets the bits of `filter_data` in `bits`, a bit map of `m` bits. Returns `false` without
+    /// touching `bits` when the data was generated by another filter.
+    pub fn hash_to(&self, filter_data: &BloomFilterData, bits: &mut [u8]) -> bool {
+        if !self.is_valid(Some(filter_data)) || bits.len() * 8 < self.m as usize {
+            return false;
+        }
+        for &pos in filter_data.bit_pos() {
+            bits[pos as usize / 8] |= 1 << (pos % 8);
+        }
+        true
+    }
+
+    /// Whether all the bits of `filter_data` are set in `bits`. A hit may be a false positive,
+    /// a miss is certain. Invalid data always hits.
+    pub fn is_hit(&self, filter_data: &BloomFilterData, bits: &[u8]) -> bool {
+        if !self.is_valid(Some(filter_data)) || bits.len() * 8 < self.m as usize {
+            return true;
+        }
+        filter_data
+            .bit_pos()
+            .iter()
+            .all(|&pos| bits[pos as usize / 8] & (1 << (pos % 8)) != 0)
+    }
+}
+
+/// 32 bits MurmurHash3 of `data`.
+fn murmur3_32(data: &[u8], seed: u32) -> u32 {
+    const C1: u32 = 0xcc9e2d51;
+    const C2: u32 = 0x1b873593;
+    let mut hash = seed;
+    let mut chunks = data.chunks_exact(4);
+    for chunk in &mut chunks {
+        let k = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        hash ^= k.wrapping_mul(C1).rotate_left(15).wrapping_mul(C2);
+        hash = hash
+            .rotate_left(13)
+            .wrapping_mul(5)
+            .wrapping_add(0xe6546b64);
+    }
+    let tail = chunks.remainder();
+    if !tail.is_empty() {
+        let mut k = 0u32;
+        for (i, byte) in tail.iter().enumerate() {
+            k |= (*byte as u32) << (8 * i);
+        }
+        hash ^= k.wrapping_mul(C1).rotate_left(15).wrapping_mul(C2);
+    }
+    hash ^= data.len() as u32;
+    hash ^= hash >> 16;
+    hash = hash.wrapping_mul(0x85ebca6b);
+    hash ^= hash >> 13;
+    hash = hash.wrapping_mul(0xc2b2ae35);
+    hash ^ (hash >> 16)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hashed_data_hits_and_other_data_mostly_misses() {
+        let bloom_filter = BloomFilter::new(20, 32).unwrap();
+        let mut bits = vec![0u8; bloom_filter.m() as usize / 8];
+        let group_a = bloom_filter.generate("GroupA#TopicTest");
+        assert!(bloom_filter.is_valid(Some(&group_a)));
+        assert!(bloom_filter.hash_to(&group_a, &mut bits));
+        assert!(bloom_filter.is_hit(&group_a, &bits));
+
+        let misses = (0..100)
+            .filter(|i| !bloom_filter.is_hit(&bloom_filter.generate(&format!("G{}#T", i)), &bits))
+            .count();
+        assert!(misses > 50);
+        assert_eq!(murmur3_32(b"hello", 0), 0x248bfa47);
+    }
 }
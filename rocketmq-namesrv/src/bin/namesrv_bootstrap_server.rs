@@ -21,8 +21,9 @@ use clap::Parser;
 use rocketmq_common::common::namesrv::namesrv_config::NamesrvConfig;
 use rocketmq_common::common::server::config::ServerConfig;
 use rocketmq_common::EnvUtils::EnvUtils;
-use rocketmq_common::ParseConfigFile;
 use rocketmq_namesrv::bootstrap::Builder;
+use rocketmq_namesrv::parse_command_and_config_file;
+use rocketmq_namesrv::DEFAULT_NAMESRV_LISTEN_PORT;
 use rocketmq_rust::rocketmq;
 use tracing::info;
 
@@ -31,20 +32,44 @@ async fn main() -> anyhow::Result<()> {
     rocketmq_common::log::init_logger();
     let args = Args::parse();
     let home = EnvUtils::get_rocketmq_home();
-
     info!("Rocketmq(Rust) home: {}", home);
+
+    let (namesrv_config, mut server_config) = match args.config {
+        Some(ref config_file) => parse_command_and_config_file(config_file.clone())?,
+        None => {
+            let config_file = PathBuf::from(home).join("conf").join("namesrv.toml");
+            if config_file.exists() {
+                parse_command_and_config_file(config_file)?
+            } else {
+                (
+                    NamesrvConfig::default(),
+                    ServerConfig {
+                        listen_port: DEFAULT_NAMESRV_LISTEN_PORT,
+                        ..ServerConfig::default()
+                    },
+                )
+            }
+        }
+    };
+    // command line options take precedence over the config file
+    if let Some(port) = args.port {
+        server_config.listen_port = port;
+    }
+    if let Some(ip) = args.ip {
+        server_config.bind_address = ip;
+    }
+    if args.print_config_item {
+        println!("{:#?}", namesrv_config);
+        println!("{:#?}", server_config);
+        return Ok(());
+    }
     info!(
         "Rocketmq name remoting_server(Rust) running on: {}:{}",
-        args.ip, args.port
+        server_config.bind_address, server_config.listen_port
     );
-    let config_file = PathBuf::from(home).join("conf").join("namesrv.toml");
-    let namesrv_config = ParseConfigFile::parse_config_file::<NamesrvConfig>(config_file.clone())?;
     Builder::new()
         .set_name_server_config(namesrv_config)
-        .set_server_config(ServerConfig {
-            listen_port: args.port,
-            bind_address: args.ip,
-        })
+        .set_server_config(server_config)
         .build()
         .boot()
         .await;
@@ -59,27 +84,21 @@ async fn main() -> anyhow::Result<()> {
     about = "RocketMQ Name remoting_server(Rust)"
 )]
 struct Args {
-    /// rocketmq name remoting_server port
-    #[arg(
-        short,
-        long,
-        value_name = "PORT",
-        default_missing_value = "9876",
-        default_value = "9876",
-        required = false
-    )]
-    port: u32,
+    /// rocketmq name remoting_server port, overrides `listenPort` of the config
+    /// file [default: 9876]
+    #[arg(short, long, value_name = "PORT", required = false)]
+    port: Option<u32>,
 
-    /// rocketmq name remoting_server ip
-    #[arg(
-        short,
-        long,
-        value_name = "IP",
-        default_value = "0.0.0.0",
-        required = false
-    )]
-    ip: String,
-    /// rocketmq name remoting_server config file
-    #[arg(short, long, value_name = "FILE", default_missing_value = "None")]
+    /// rocketmq name remoting_server ip, overrides `bindAddress` of the config
+    /// file [default: 0.0.0.0]
+    #[arg(short, long, value_name = "IP", required = false)]
+    ip: Option<String>,
+
+    /// rocketmq name remoting_server config file(properties, toml, json or yaml)
+    #[arg(short, long, value_name = "FILE")]
     config: Option<PathBuf>,
+
+    /// Print all config items and exit
+    #[arg(long, default_value_t = false)]
+    print_config_item: bool,
 }
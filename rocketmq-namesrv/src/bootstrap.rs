@@ -27,6 +27,7 @@ use rocketmq_remoting::runtime::config::client_config::TokioClientConfig;
 use rocketmq_runtime::RocketMQRuntime;
 use tokio::select;
 use tokio::sync::broadcast;
+use tracing::info;
 
 use crate::processor::ClientRequestProcessor;
 use crate::processor::NameServerRequestProcessor;
@@ -49,20 +50,34 @@ struct NameServerRuntime {
     route_info_manager: Arc<parking_lot::RwLock<RouteInfoManager>>,
     kvconfig_manager: Arc<parking_lot::RwLock<KVConfigManager>>,
     name_server_runtime: Option<RocketMQRuntime>,
+    client_request_runtime: Option<RocketMQRuntime>,
     remoting_client: RocketmqDefaultClient,
 }
 
 impl NameServerBootstrap {
     pub async fn boot(mut self) {
+        self.name_server_runtime.initialize();
         select! {
             _ = self.name_server_runtime.start() =>{
 
             }
         }
+        self.name_server_runtime.shutdown();
     }
 }
 
 impl NameServerRuntime {
+    pub fn initialize(&mut self) {
+        self.kvconfig_manager.write().load();
+        if self.name_server_config.cluster_test {
+            info!(
+                "name server running in cluster test mode, product env: {}",
+                self.name_server_config.product_env_name
+            );
+        }
+    }
+
+    /// Starts the remoting server, returns once the server received a shutdown signal.
     pub async fn start(&mut self) {
         let (notify_conn_disconnect, _) = broadcast::channel::<SocketAddr>(100);
         let receiver = notify_conn_disconnect.subscribe();
@@ -89,6 +104,8 @@ impl NameServerRuntime {
             );
 
         let route_info_manager_arc = self.route_info_manager.clone();
+        let scan_not_active_broker_interval =
+            Duration::from_millis(self.name_server_config.scan_not_active_broker_interval);
         self.name_server_runtime
             .as_ref()
             .unwrap()
@@ -97,12 +114,36 @@ impl NameServerRuntime {
                     route_info_manager_arc.write().scan_not_active_broker();
                 },
                 Some(Duration::from_secs(5)),
-                Duration::from_secs(5),
+                scan_not_active_broker_interval,
             );
         NameServerRequestProcessor {
             client_request_processor: Arc::new(client_request_processor),
             default_request_processor: Arc::new(default_request_processor),
+            client_request_executor: self
+                .client_request_runtime
+                .as_ref()
+                .unwrap()
+                .get_handle()
+                .clone(),
+        }
+    }
+}
+
+impl NameServerRuntime {
+    /// Persists the kv config and stops the background runtimes.
+    pub fn shutdown(&mut self) {
+        let mut kvconfig_manager = self.kvconfig_manager.write();
+        if !kvconfig_manager.get_config_table().is_empty() {
+            kvconfig_manager.persist();
         }
+        drop(kvconfig_manager);
+        if let Some(runtime) = self.name_server_runtime.take() {
+            runtime.shutdown();
+        }
+        if let Some(runtime) = self.client_request_runtime.take() {
+            runtime.shutdown();
+        }
+        info!("name server shutdown successfully");
     }
 }
 
@@ -111,6 +152,9 @@ impl Drop for NameServerRuntime {
         if let Some(runtime) = self.name_server_runtime.take() {
             runtime.shutdown();
         }
+        if let Some(runtime) = self.client_request_runtime.take() {
+            runtime.shutdown();
+        }
     }
 }
 
@@ -140,7 +184,14 @@ impl Builder {
 
     pub fn build(self) -> NameServerBootstrap {
         let name_server_config = Arc::new(self.name_server_config.unwrap());
-        let runtime = RocketMQRuntime::new_multi(10, "namesrv-thread");
+        let runtime = RocketMQRuntime::new_multi(
+            name_server_config.default_thread_pool_nums.max(1) as usize,
+            "namesrv-thread",
+        );
+        let client_request_runtime = RocketMQRuntime::new_multi(
+            name_server_config.client_request_thread_pool_nums.max(1) as usize,
+            "namesrv-client-request-thread",
+        );
         let tokio_client_config = Arc::new(TokioClientConfig::default());
         let remoting_client = RocketmqDefaultClient::new(
             tokio_client_config.clone(),
@@ -160,6 +211,7 @@ impl Builder {
                     name_server_config,
                 ))),
                 name_server_runtime: Some(runtime),
+                client_request_runtime: Some(client_request_runtime),
                 remoting_client,
             },
         }
@@ -19,6 +19,7 @@
 
 pub use self::kvconfig::kvconfig_mananger::KVConfigManager;
 pub use self::namesrv_config_parse::parse_command_and_config_file;
+pub use self::namesrv_config_parse::DEFAULT_NAMESRV_LISTEN_PORT;
 pub use self::route::route_info_manager::RouteInfoManager;
 
 pub mod bootstrap;
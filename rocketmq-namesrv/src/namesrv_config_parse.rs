@@ -15,25 +15,128 @@
  * limitations under the License.
  */
 
+use std::path::Path;
 use std::path::PathBuf;
 
-use config::Config;
 use rocketmq_common::common::namesrv::namesrv_config::NamesrvConfig;
+use rocketmq_common::common::server::config::ServerConfig;
+use rocketmq_common::ParseConfigFile;
 use tracing::info;
 
+/// Default listen port of the name server.
+pub const DEFAULT_NAMESRV_LISTEN_PORT: u32 = 9876;
+
+/// Parses the name server configuration from `config_file`.
+///
+/// Files ending with `.toml`, `.json` or `.yaml` are parsed according to their
+/// extension, any other file (e.g. `namesrv.conf` or `namesrv.properties`) is
+/// treated as a Java style `key=value` properties file, so the same file used to
+/// start the Java name server can be reused. The [`ServerConfig`] (`listenPort`,
+/// `bindAddress`) is only read from properties files.
 pub fn parse_command_and_config_file(
     config_file: PathBuf,
-) -> anyhow::Result<NamesrvConfig, anyhow::Error> {
-    let namesrv_config = Config::builder()
-        .add_source(config::File::with_name(
-            config_file.to_string_lossy().into_owned().as_str(),
-        ))
-        .build()
-        .map_or(NamesrvConfig::default(), |result| {
-            result
-                .try_deserialize::<NamesrvConfig>()
-                .unwrap_or_default()
-        });
+) -> anyhow::Result<(NamesrvConfig, ServerConfig)> {
+    let default_server_config = ServerConfig {
+        listen_port: DEFAULT_NAMESRV_LISTEN_PORT,
+        ..ServerConfig::default()
+    };
+    let (namesrv_config, server_config) = if is_properties_file(config_file.as_path()) {
+        let namesrv_config =
+            ParseConfigFile::parse_properties_config_file::<NamesrvConfig>(config_file.clone())?;
+        let server_config =
+            ParseConfigFile::parse_properties_config_file::<NamesrvServerConfig>(config_file)?;
+        (namesrv_config, server_config.0)
+    } else {
+        (
+            ParseConfigFile::parse_config_file::<NamesrvConfig>(config_file)?,
+            default_server_config,
+        )
+    };
     info!("rocketmq-namesrv config: {:?}", namesrv_config);
-    Ok(namesrv_config)
+    info!("rocketmq-namesrv server config: {:?}", server_config);
+    Ok((namesrv_config, server_config))
+}
+
+fn is_properties_file(config_file: &Path) -> bool {
+    !matches!(
+        config_file
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(|extension| extension.to_ascii_lowercase())
+            .as_deref(),
+        Some("toml") | Some("json") | Some("yaml") | Some("yml")
+    )
+}
+
+/// [`ServerConfig`] whose default listen port is the name server port.
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(transparent)]
+struct NamesrvServerConfig(ServerConfig);
+
+impl Default for NamesrvServerConfig {
+    fn default() -> Self {
+        NamesrvServerConfig(ServerConfig {
+            listen_port: DEFAULT_NAMESRV_LISTEN_PORT,
+            ..ServerConfig::default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    fn write_config_file(name: &str, content: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(name);
+        let mut file = std::fs::File::create(&path).unwrap();
+        file.write_all(content.as_bytes()).unwrap();
+        path
+    }
+
+    #[test]
+    fn parse_properties_config_file() {
+        let path = write_config_file(
+            "rocketmq-namesrv-test-parse.conf",
+            concat!(
+                "# name server config\n",
+                "listenPort=19876\n",
+                "kvConfigPath=/tmp/kvConfig.json\n",
+                "clusterTest=true\n",
+                "productEnvName=test\n",
+                "defaultThreadPoolNums=4\n",
+                "clientRequestThreadPoolNums=2\n",
+            ),
+        );
+        let (namesrv_config, server_config) = parse_command_and_config_file(path.clone()).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(server_config.listen_port, 19876);
+        assert_eq!(server_config.bind_address, "0.0.0.0");
+        assert_eq!(namesrv_config.kv_config_path, "/tmp/kvConfig.json");
+        assert!(namesrv_config.cluster_test);
+        assert_eq!(namesrv_config.product_env_name, "test");
+        assert_eq!(namesrv_config.default_thread_pool_nums, 4);
+        assert_eq!(namesrv_config.client_request_thread_pool_nums, 2);
+    }
+
+    #[test]
+    fn parse_config_file_default_listen_port() {
+        let path = write_config_file(
+            "rocketmq-namesrv-test-default-port.properties",
+            "orderMessageEnable=true\n",
+        );
+        let (namesrv_config, server_config) = parse_command_and_config_file(path.clone()).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(server_config.listen_port, DEFAULT_NAMESRV_LISTEN_PORT);
+        assert!(namesrv_config.order_message_enable);
+    }
+
+    #[test]
+    fn parse_missing_config_file() {
+        let result = parse_command_and_config_file(PathBuf::from("/not/exist/namesrv.conf"));
+        assert!(result.is_err());
+    }
 }
@@ -18,11 +18,13 @@
 use std::sync::Arc;
 
 use rocketmq_remoting::code::request_code::RequestCode;
+use rocketmq_remoting::code::response_code::ResponseCode;
 use rocketmq_remoting::net::channel::Channel;
 use rocketmq_remoting::protocol::remoting_command::RemotingCommand;
 use rocketmq_remoting::runtime::connection_handler_context::ConnectionHandlerContext;
 use rocketmq_remoting::runtime::processor::RequestProcessor;
 use rocketmq_remoting::Result;
+use tokio::runtime::Handle;
 use tracing::info;
 
 pub use self::client_request_processor::ClientRequestProcessor;
@@ -34,6 +36,9 @@ pub mod default_request_processor;
 pub struct NameServerRequestProcessor {
     pub(crate) client_request_processor: Arc<ClientRequestProcessor>,
     pub(crate) default_request_processor: Arc<DefaultRequestProcessor>,
+    /// Runtime of `clientRequestThreadPoolNums` threads the route queries of clients run on,
+    /// so a burst of them does not hold up broker registrations.
+    pub(crate) client_request_executor: Handle,
 }
 
 impl Clone for NameServerRequestProcessor {
@@ -41,6 +46,7 @@ impl Clone for NameServerRequestProcessor {
         Self {
             client_request_processor: self.client_request_processor.clone(),
             default_request_processor: self.default_request_processor.clone(),
+            client_request_executor: self.client_request_executor.clone(),
         }
     }
 }
@@ -55,9 +61,20 @@ impl RequestProcessor for NameServerRequestProcessor {
         let request_code = RequestCode::from(request.code());
         info!("process_request: {:?}", request_code);
         let result = match request_code {
-            RequestCode::GetRouteinfoByTopic => self
-                .client_request_processor
-                .process_request(channel, ctx, request),
+            RequestCode::GetRouteinfoByTopic => {
+                let client_request_processor = self.client_request_processor.clone();
+                self.client_request_executor
+                    .spawn(async move {
+                        client_request_processor.process_request(channel, ctx, request)
+                    })
+                    .await
+                    .unwrap_or_else(|err| {
+                        Some(RemotingCommand::create_response_command_with_code_remark(
+                            ResponseCode::SystemError,
+                            format!("get route info failed: {}", err),
+                        ))
+                    })
+            }
             _ => self
                 .default_request_processor
                 .process_request(channel, ctx, request),
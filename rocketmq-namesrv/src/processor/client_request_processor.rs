@@ -35,6 +35,7 @@ use tracing::warn;
 
 use crate::kvconfig::kvconfig_mananger::KVConfigManager;
 use crate::route::route_info_manager::RouteInfoManager;
+use crate::route::topic_route_cache::CachedTopicRoute;
 
 pub struct ClientRequestProcessor {
     route_info_manager: Arc<parking_lot::RwLock<RouteInfoManager>>,
@@ -77,43 +78,82 @@ impl ClientRequestProcessor {
             )
             .set_remark(Some(String::from("name remoting_server not ready")));
         }
-        match self
-            .route_info_manager
-            .read()
-            .pickup_topic_route_data(request_header.topic.as_str())
-        {
-            None => RemotingCommand::create_response_command_with_code(ResponseCode::TopicNotExist)
-                .set_remark(Some(format!(
-                    "No topic route info in name remoting_server for the topic:{}{}",
-                    request_header.topic,
-                    FAQUrl::suggest_todo(FAQUrl::APPLY_TOPIC_URL)
-                ))),
-            Some(mut topic_route_data) => {
-                if self.need_check_namesrv_ready.load(Ordering::Relaxed) {
-                    self.need_check_namesrv_ready.store(false, Ordering::SeqCst);
+        let topic = request_header.topic.as_str();
+        // Routes carrying the order topic config depend on the kv config as well, which does not
+        // invalidate the cache, so they are always built afresh.
+        let use_cache = self.namesrv_config.enable_topic_route_cache
+            && !self.namesrv_config.order_message_enable;
+        let cached = if use_cache {
+            self.route_info_manager.read().topic_route_cache.get(topic)
+        } else {
+            None
+        };
+        let route = match cached {
+            Some(route) => route,
+            None => {
+                let route_info_manager = self.route_info_manager.read();
+                match route_info_manager.pickup_topic_route_data(topic) {
+                    None => {
+                        return RemotingCommand::create_response_command_with_code(
+                            ResponseCode::TopicNotExist,
+                        )
+                        .set_remark(Some(format!(
+                            "No topic route info in name remoting_server for the topic:{}{}",
+                            request_header.topic,
+                            FAQUrl::suggest_todo(FAQUrl::APPLY_TOPIC_URL)
+                        )));
+                    }
+                    Some(mut topic_route_data) => {
+                        if self.namesrv_config.order_message_enable {
+                            //get kv config
+                            let order_topic_config = self
+                                .kvconfig_manager
+                                .read()
+                                .get_kvconfig("ORDER_TOPIC_CONFIG", request_header.topic.clone());
+                            topic_route_data.order_topic_conf = order_topic_config;
+                        };
+                        /*let standard_json_only = request_header.accept_standard_json_only.unwrap_or(false);
+                        let content = if request.version() >= RocketMqVersion::into(RocketMqVersion::V494)
+                            || standard_json_only
+                        {
+                            //topic_route_data.encode()
+                            topic_route_data.encode()
+                        } else {
+                            topic_route_data.encode()
+                        };*/
+                        let route = CachedTopicRoute::new(Bytes::from(topic_route_data.encode()));
+                        if use_cache {
+                            route_info_manager
+                                .topic_route_cache
+                                .put(topic, route.clone());
+                        }
+                        route
+                    }
                 }
-                if self.namesrv_config.order_message_enable {
-                    //get kv config
-                    let order_topic_config = self
-                        .kvconfig_manager
-                        .read()
-                        .get_kvconfig("ORDER_TOPIC_CONFIG", request_header.topic.clone());
-                    topic_route_data.order_topic_conf = order_topic_config;
-                };
-                /*let standard_json_only = request_header.accept_standard_json_only.unwrap_or(false);
-                let content = if request.version() >= RocketMqVersion::into(RocketMqVersion::V494)
-                    || standard_json_only
-                {
-                    //topic_route_data.encode()
-                    topic_route_data.encode()
-                } else {
-                    topic_route_data.encode()
-                };*/
-                let content = topic_route_data.encode();
-                RemotingCommand::create_response_command_with_code(RemotingSysResponseCode::Success)
-                    .set_body(Some(Bytes::from(content)))
             }
+        };
+        if self.need_check_namesrv_ready.load(Ordering::Relaxed) {
+            self.need_check_namesrv_ready.store(false, Ordering::SeqCst);
         }
+
+        if request_header.topic_route_version.as_deref() == Some(route.version.as_str()) {
+            let mut response = RemotingCommand::create_response_command_with_code(
+                ResponseCode::TopicRouteNotModified,
+            );
+            response.add_ext_field(
+                GetRouteInfoRequestHeader::TOPIC_ROUTE_VERSION,
+                route.version,
+            );
+            return response;
+        }
+        let mut response =
+            RemotingCommand::create_response_command_with_code(RemotingSysResponseCode::Success)
+                .set_body(Some(route.body));
+        response.add_ext_field(
+            GetRouteInfoRequestHeader::TOPIC_ROUTE_VERSION,
+            route.version,
+        );
+        response
     }
 }
 
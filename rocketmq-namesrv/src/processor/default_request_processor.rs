@@ -29,6 +29,7 @@ use rocketmq_remoting::code::response_code::ResponseCode;
 use rocketmq_remoting::net::channel::Channel;
 use rocketmq_remoting::protocol::body::broker_body::broker_member_group::GetBrokerMemberGroupResponseBody;
 use rocketmq_remoting::protocol::body::broker_body::register_broker_body::RegisterBrokerBody;
+use rocketmq_remoting::protocol::body::topic::topic_list::TopicList;
 use rocketmq_remoting::protocol::body::topic_info_wrapper::topic_config_wrapper::TopicConfigAndMappingSerializeWrapper;
 use rocketmq_remoting::protocol::header::namesrv::broker_request::BrokerHeartbeatRequestHeader;
 use rocketmq_remoting::protocol::header::namesrv::broker_request::GetBrokerMemberGroupRequestHeader;
@@ -47,6 +48,7 @@ use rocketmq_remoting::protocol::header::namesrv::query_data_version_header::Que
 use rocketmq_remoting::protocol::header::namesrv::register_broker_header::RegisterBrokerRequestHeader;
 use rocketmq_remoting::protocol::header::namesrv::register_broker_header::RegisterBrokerResponseHeader;
 use rocketmq_remoting::protocol::header::namesrv::topic_operation_header::DeleteTopicFromNamesrvRequestHeader;
+use rocketmq_remoting::protocol::header::namesrv::topic_operation_header::GetTopicListRequestHeader;
 use rocketmq_remoting::protocol::header::namesrv::topic_operation_header::GetTopicsByClusterRequestHeader;
 use rocketmq_remoting::protocol::header::namesrv::topic_operation_header::RegisterTopicRequestHeader;
 use rocketmq_remoting::protocol::remoting_command::RemotingCommand;
@@ -237,6 +239,27 @@ impl DefaultRequestProcessor {
             .set_remark(Some(String::from("crc32 not match")));
         }
 
+        // An incremental registration only carries the changed topics, the broker has to
+        // register all of them first
+        if request_header.incremental
+            && self
+                .route_info_manager
+                .read()
+                .query_broker_topic_config(
+                    &request_header.cluster_name,
+                    &request_header.broker_addr,
+                )
+                .is_none()
+        {
+            return RemotingCommand::create_response_command_with_code(
+                RemotingSysResponseCode::SystemError,
+            )
+            .set_remark(Some(format!(
+                "broker {} is not registered, incremental registration needs a full one first",
+                request_header.broker_addr
+            )));
+        }
+
         let mut response_command = RemotingCommand::create_response_command();
         let broker_version = RocketMqVersion::try_from(request.version()).unwrap();
         let topic_config_wrapper;
@@ -269,6 +292,7 @@ impl DefaultRequestProcessor {
             request_header.heartbeat_timeout_millis,
             request_header.enable_acting_master,
             topic_config_wrapper,
+            request_header.incremental,
             filter_server_list,
             remote_addr,
         );
@@ -374,13 +398,12 @@ impl DefaultRequestProcessor {
             .set_command_custom_header(AddWritePermOfBrokerResponseHeader::new(add_topic_cnt))
     }
 
-    fn get_all_topic_list_from_nameserver(&self, _request: RemotingCommand) -> RemotingCommand {
+    fn get_all_topic_list_from_nameserver(&self, request: RemotingCommand) -> RemotingCommand {
         let rd_lock = self.route_info_manager.read();
         if rd_lock.namesrv_config.enable_all_topic_list {
             let topics = rd_lock.get_all_topic_list();
             drop(rd_lock); //release lock
-            return RemotingCommand::create_response_command()
-                .set_body(Some(Bytes::from(topics.encode())));
+            return topic_list_response(topics, &request);
         }
         RemotingCommand::create_response_command_with_code(RemotingSysResponseCode::SystemError)
             .set_remark(Some(String::from("disable")))
@@ -444,22 +467,33 @@ impl DefaultRequestProcessor {
             .set_remark(Some(String::from("disable")));
         }
 
-        let request_header = request
-            .decode_command_custom_header::<GetTopicsByClusterRequestHeader>()
-            .unwrap();
+        let Some(request_header) =
+            request.decode_command_custom_header::<GetTopicsByClusterRequestHeader>()
+        else {
+            return RemotingCommand::create_response_command_with_code(
+                RemotingSysResponseCode::SystemError,
+            )
+            .set_remark(Some(String::from("cluster is required")));
+        };
         let topics_by_cluster = self
             .route_info_manager
             .read()
             .get_topics_by_cluster(request_header.cluster.as_str());
-        RemotingCommand::create_response_command().set_body(Some(topics_by_cluster.encode()))
+        let compressed = request_header.compressed.unwrap_or(false);
+        let mut response = RemotingCommand::create_response_command()
+            .set_body(Some(topics_by_cluster.encode_body(compressed)));
+        if compressed {
+            response.add_ext_field(GetTopicListRequestHeader::COMPRESSED, "true");
+        }
+        response
     }
 
-    fn get_system_topic_list_from_ns(&self, _request: RemotingCommand) -> RemotingCommand {
+    fn get_system_topic_list_from_ns(&self, request: RemotingCommand) -> RemotingCommand {
         let topic_list = self.route_info_manager.read().get_system_topic_list();
-        RemotingCommand::create_response_command().set_body(Some(topic_list.encode()))
+        topic_list_response(topic_list, &request)
     }
 
-    fn get_unit_topic_list(&self, _request: RemotingCommand) -> RemotingCommand {
+    fn get_unit_topic_list(&self, request: RemotingCommand) -> RemotingCommand {
         if self
             .route_info_manager
             .read()
@@ -467,13 +501,13 @@ impl DefaultRequestProcessor {
             .enable_topic_list
         {
             let topic_list = self.route_info_manager.read().get_unit_topics();
-            return RemotingCommand::create_response_command().set_body(Some(topic_list.encode()));
+            return topic_list_response(topic_list, &request);
         }
         RemotingCommand::create_response_command_with_code(RemotingSysResponseCode::SystemError)
             .set_remark(Some(String::from("disable")))
     }
 
-    fn get_has_unit_sub_topic_list(&self, _request: RemotingCommand) -> RemotingCommand {
+    fn get_has_unit_sub_topic_list(&self, request: RemotingCommand) -> RemotingCommand {
         if self
             .route_info_manager
             .read()
@@ -481,13 +515,13 @@ impl DefaultRequestProcessor {
             .enable_topic_list
         {
             let topic_list = self.route_info_manager.read().get_has_unit_sub_topic_list();
-            return RemotingCommand::create_response_command().set_body(Some(topic_list.encode()));
+            return topic_list_response(topic_list, &request);
         }
         RemotingCommand::create_response_command_with_code(RemotingSysResponseCode::SystemError)
             .set_remark(Some(String::from("disable")))
     }
 
-    fn get_has_unit_sub_un_unit_topic_list(&self, _request: RemotingCommand) -> RemotingCommand {
+    fn get_has_unit_sub_un_unit_topic_list(&self, request: RemotingCommand) -> RemotingCommand {
         if self
             .route_info_manager
             .read()
@@ -498,13 +532,27 @@ impl DefaultRequestProcessor {
                 .route_info_manager
                 .read()
                 .get_has_unit_sub_un_unit_topic_list();
-            return RemotingCommand::create_response_command().set_body(Some(topic_list.encode()));
+            return topic_list_response(topic_list, &request);
         }
         RemotingCommand::create_response_command_with_code(RemotingSysResponseCode::SystemError)
             .set_remark(Some(String::from("disable")))
     }
 }
 
+/// Answers a topic list request, with a compressed body when the request asks for it.
+fn topic_list_response(topic_list: TopicList, request: &RemotingCommand) -> RemotingCommand {
+    let compressed = request
+        .decode_command_custom_header::<GetTopicListRequestHeader>()
+        .and_then(|request_header| request_header.compressed)
+        .unwrap_or(false);
+    let mut response = RemotingCommand::create_response_command()
+        .set_body(Some(topic_list.encode_body(compressed)));
+    if compressed {
+        response.add_ext_field(GetTopicListRequestHeader::COMPRESSED, "true");
+    }
+    response
+}
+
 fn extract_register_topic_config_from_request(
     request: &RemotingCommand,
 ) -> TopicConfigAndMappingSerializeWrapper {
@@ -16,3 +16,4 @@
  */
 
 pub mod route_info_manager;
+pub(crate) mod topic_route_cache;
@@ -49,6 +49,7 @@ use tracing::debug;
 use tracing::info;
 use tracing::warn;
 
+use crate::route::topic_route_cache::TopicRouteCache;
 use crate::route_info::broker_addr_info::BrokerAddrInfo;
 use crate::route_info::broker_addr_info::BrokerLiveInfo;
 use crate::route_info::broker_addr_info::BrokerStatusChangeInfo;
@@ -74,6 +75,7 @@ pub struct RouteInfoManager {
     pub(crate) topic_queue_mapping_info_table: TopicQueueMappingInfoTable,
     pub(crate) namesrv_config: Arc<NamesrvConfig>,
     pub(crate) remoting_client: Arc<RocketmqDefaultClient>,
+    pub(crate) topic_route_cache: TopicRouteCache,
 }
 
 #[allow(private_interfaces)]
@@ -91,13 +93,69 @@ impl RouteInfoManager {
             topic_queue_mapping_info_table: HashMap::new(),
             namesrv_config,
             remoting_client,
+            topic_route_cache: TopicRouteCache::default(),
         }
     }
 }
 
 //impl register broker
 impl RouteInfoManager {
+    /// Registers the broker and drops the cached topic routes when the registration changed
+    /// what a route query returns. Heartbeat registrations with an unchanged data version keep
+    /// the cache. An `incremental` registration only carries the changed topics, so the topics
+    /// missing from it are kept.
     pub fn register_broker(
+        &mut self,
+        cluster_name: String,
+        broker_addr: String,
+        broker_name: String,
+        broker_id: i64,
+        ha_server_addr: String,
+        zone_name: Option<String>,
+        timeout_millis: Option<i64>,
+        enable_acting_master: Option<bool>,
+        topic_config_serialize_wrapper: TopicConfigAndMappingSerializeWrapper,
+        incremental: bool,
+        filter_server_list: Vec<String>,
+        remote_addr: SocketAddr,
+    ) -> Option<RegisterBrokerResult> {
+        let broker_addr_info = BrokerAddrInfo::new(cluster_name.clone(), broker_addr.clone());
+        let broker_data_before = self.broker_addr_table.get(&broker_name).cloned();
+        let filter_server_before = self.filter_server_table.get(&broker_addr_info).cloned();
+        let topic_config_changed = self
+            .query_broker_topic_config(&cluster_name, &broker_addr)
+            .is_none_or(|data_version| {
+                data_version
+                    != topic_config_serialize_wrapper
+                        .topic_config_serialize_wrapper
+                        .data_version()
+            });
+
+        let result = self.do_register_broker(
+            cluster_name,
+            broker_addr,
+            broker_name.clone(),
+            broker_id,
+            ha_server_addr,
+            zone_name,
+            timeout_millis,
+            enable_acting_master,
+            topic_config_serialize_wrapper,
+            incremental,
+            filter_server_list,
+            remote_addr,
+        );
+
+        if topic_config_changed
+            || self.broker_addr_table.get(&broker_name) != broker_data_before.as_ref()
+            || self.filter_server_table.get(&broker_addr_info) != filter_server_before.as_ref()
+        {
+            self.topic_route_cache.invalidate_all();
+        }
+        result
+    }
+
+    fn do_register_broker(
         &mut self,
         cluster_name: String,
         broker_addr: String,
@@ -108,6 +166,7 @@ impl RouteInfoManager {
         _timeout_millis: Option<i64>,
         enable_acting_master: Option<bool>,
         topic_config_serialize_wrapper: TopicConfigAndMappingSerializeWrapper,
+        incremental: bool,
         filter_server_list: Vec<String>,
         remote_addr: SocketAddr,
     ) -> Option<RegisterBrokerResult> {
@@ -190,7 +249,7 @@ impl RouteInfoManager {
             .topic_config_serialize_wrapper
             .topic_config_table()
             .len();
-        if !broker_data.broker_addrs().contains_key(&broker_id) && size == 1 {
+        if !incremental && !broker_data.broker_addrs().contains_key(&broker_id) && size == 1 {
             warn!(
                 "Can't register topicConfigWrapper={:?} because broker[{}]={} has not registered.",
                 topic_config_serialize_wrapper
@@ -207,7 +266,7 @@ impl RouteInfoManager {
             .insert(broker_id, broker_addr.clone());
 
         register_first |= old_addr.is_none();
-        let is_master = mix_all::MASTER_ID == broker_id as u64;
+        let is_master = mix_all::MASTER_BROKER_ADDR_ID == broker_id;
 
         let is_prime_slave = enable_acting_master.is_some()
             && !is_master
@@ -224,6 +283,7 @@ impl RouteInfoManager {
             // Delete the topics that don't exist in tcTable from the current broker
             // Static topic is not supported currently
             if self.namesrv_config.delete_topic_with_broker_registration
+                && !incremental
                 && topic_queue_mapping_info_map.is_empty()
             {
                 let old_topic_set = self.topic_set_of_broker_name(&broker_name);
@@ -315,8 +375,10 @@ impl RouteInfoManager {
                 .insert(broker_addr_info.clone(), filter_server_list);
         }
 
-        if mix_all::MASTER_ID != broker_id as u64 {
-            let master_address = broker_data.broker_addrs().get(&(mix_all::MASTER_ID as i64));
+        if mix_all::MASTER_BROKER_ADDR_ID != broker_id {
+            let master_address = broker_data
+                .broker_addrs()
+                .get(&mix_all::MASTER_BROKER_ADDR_ID);
             if let Some(master_addr) = master_address {
                 let master_livie_info = self
                     .broker_live_table
@@ -421,7 +483,7 @@ impl RouteInfoManager {
                 !broker_data.broker_addrs().is_empty()
                     && !broker_data
                         .broker_addrs()
-                        .contains_key(&(mix_all::MASTER_ID as i64))
+                        .contains_key(&mix_all::MASTER_BROKER_ADDR_ID)
             });
 
             if !need_acting_master {
@@ -432,7 +494,7 @@ impl RouteInfoManager {
                 if broker_data.broker_addrs().is_empty()
                     || broker_data
                         .broker_addrs()
-                        .contains_key(&(mix_all::MASTER_ID as i64))
+                        .contains_key(&mix_all::MASTER_BROKER_ADDR_ID)
                     || !broker_data.enable_acting_master()
                 {
                     continue;
@@ -450,7 +512,7 @@ impl RouteInfoManager {
                                 {
                                     broker_data
                                         .broker_addrs_mut()
-                                        .insert(mix_all::MASTER_ID as i64, acting_master_addr);
+                                        .insert(mix_all::MASTER_BROKER_ADDR_ID, acting_master_addr);
                                 }
                             }
                         }
@@ -671,6 +733,7 @@ impl RouteInfoManager {
         broker_name: &str,
         request_code: RequestCode,
     ) -> i32 {
+        self.topic_route_cache.invalidate_all();
         let mut topic_cnt = 0;
         for (_topic, qd_map) in self.topic_queue_table.iter_mut() {
             let qd = qd_map.get_mut(broker_name).unwrap();
@@ -708,6 +771,7 @@ impl RouteInfoManager {
         topic: impl Into<String>,
         cluster_name: Option<impl Into<String>>,
     ) {
+        self.topic_route_cache.invalidate_all();
         let topic_inner = topic.into();
         if cluster_name.is_some() {
             let cluster_name_inner = cluster_name.map(|s| s.into()).unwrap();
@@ -735,6 +799,7 @@ impl RouteInfoManager {
         topic: impl Into<String>,
         queue_data_vec: Vec<QueueData>,
     ) {
+        self.topic_route_cache.invalidate_all();
         if queue_data_vec.is_empty() {
             return;
         }
@@ -773,14 +838,16 @@ impl RouteInfoManager {
         }
     }
 
+    /// Topics with queues on at least one broker of `cluster`, each listed once.
     pub(crate) fn get_topics_by_cluster(&self, cluster: &str) -> TopicList {
         let mut topic_list = Vec::new();
         if let Some(broker_name_set) = self.cluster_addr_table.get(cluster) {
-            for broker_name in broker_name_set {
-                for (topic, queue_data_map) in self.topic_queue_table.iter() {
-                    if let Some(_queue_data) = queue_data_map.get(broker_name) {
-                        topic_list.push(topic.to_string());
-                    }
+            for (topic, queue_data_map) in self.topic_queue_table.iter() {
+                if broker_name_set
+                    .iter()
+                    .any(|broker_name| queue_data_map.contains_key(broker_name))
+                {
+                    topic_list.push(topic.to_string());
                 }
             }
         }
@@ -908,6 +975,7 @@ impl RouteInfoManager {
         &mut self,
         un_register_requests: Vec<UnRegisterBrokerRequestHeader>,
     ) {
+        self.topic_route_cache.invalidate_all();
         let mut remove_broker = HashSet::<String>::new();
         let mut reduced_broker = HashSet::<String>::new();
         let mut need_notify_broker_map = HashMap::<String, BrokerStatusChangeInfo>::new();
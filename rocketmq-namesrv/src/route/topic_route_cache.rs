@@ -0,0 +1,90 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::collections::HashMap;
+
+use bytes::Bytes;
+use parking_lot::Mutex;
+use rocketmq_common::CRC32Utils::crc32;
+
+/// The encoded route of a topic and the version clients send back to ask whether it changed.
+#[derive(Clone, Debug)]
+pub(crate) struct CachedTopicRoute {
+    pub(crate) body: Bytes,
+    pub(crate) version: String,
+}
+
+impl CachedTopicRoute {
+    pub(crate) fn new(body: Bytes) -> Self {
+        let version = topic_route_version(&body);
+        Self { body, version }
+    }
+}
+
+/// Topic route cache of the name server.
+///
+/// Entries are filled by route queries and the whole cache is dropped whenever the route tables
+/// change, the next query of each topic rebuilds its entry.
+#[derive(Default)]
+pub(crate) struct TopicRouteCache {
+    table: Mutex<HashMap<String /* topic */, CachedTopicRoute>>,
+}
+
+impl TopicRouteCache {
+    pub(crate) fn get(&self, topic: &str) -> Option<CachedTopicRoute> {
+        self.table.lock().get(topic).cloned()
+    }
+
+    pub(crate) fn put(&self, topic: &str, route: CachedTopicRoute) {
+        self.table.lock().insert(topic.to_string(), route);
+    }
+
+    pub(crate) fn invalidate_all(&self) {
+        self.table.lock().clear();
+    }
+}
+
+/// Derives the route version from the encoded route, so two equal routes share a version even
+/// after the cache has been rebuilt.
+pub(crate) fn topic_route_version(body: &[u8]) -> String {
+    format!("{:x}-{:08x}", body.len(), crc32(body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn version_follows_content() {
+        let a = CachedTopicRoute::new(Bytes::from_static(b"{\"brokerDatas\":[]}"));
+        let b = CachedTopicRoute::new(Bytes::from_static(b"{\"brokerDatas\":[]}"));
+        let c = CachedTopicRoute::new(Bytes::from_static(b"{\"brokerDatas\":[{}]}"));
+        assert_eq!(a.version, b.version);
+        assert_ne!(a.version, c.version);
+    }
+
+    #[test]
+    fn invalidate_all_clears_entries() {
+        let cache = TopicRouteCache::default();
+        cache.put("TopicA", CachedTopicRoute::new(Bytes::from_static(b"a")));
+        cache.put("TopicB", CachedTopicRoute::new(Bytes::from_static(b"b")));
+        assert_eq!(cache.get("TopicA").unwrap().body, Bytes::from_static(b"a"));
+
+        cache.invalidate_all();
+        assert!(cache.get("TopicA").is_none());
+        assert!(cache.get("TopicB").is_none());
+    }
+}
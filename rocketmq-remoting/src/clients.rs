@@ -39,7 +39,7 @@ pub mod rocketmq_default_impl;
 /// clients connected to specific addresses.
 #[allow(async_fn_in_trait)]
 pub trait RemotingClient: RemotingService {
-    /// Updates the list of name remoting_server addresses.
+    /// Updates the list of name remoting_server addresses, swapping the whole list at once.
     ///
     /// # Arguments
     /// * `addrs` - A list of name remoting_server addresses to update.
@@ -49,7 +49,7 @@ pub trait RemotingClient: RemotingService {
     ///
     /// # Returns
     /// A vector containing the current list of name remoting_server addresses.
-    fn get_name_server_address_list(&self) -> &[String];
+    fn get_name_server_address_list(&self) -> Vec<String>;
 
     /// Retrieves a list of available name remoting_server addresses.
     ///
@@ -15,11 +15,14 @@
  * limitations under the License.
  */
 use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
 
 use futures_util::SinkExt;
 use futures_util::StreamExt;
 use rocketmq_common::ArcRefCellWrapper;
 use tokio::sync::mpsc::Receiver;
+use tokio::task::AbortHandle;
 use tracing::error;
 use tracing::info;
 use tracing::warn;
@@ -31,7 +34,11 @@ use crate::connection::Connection;
 use crate::error::Error::ConnectionInvalid;
 use crate::error::Error::Io;
 use crate::error::Error::RemoteException;
+use crate::net::channel::write_batch;
 use crate::net::channel::Channel;
+use crate::net::channel::ChannelMessage;
+use crate::net::channel::OutboundQueueStats;
+use crate::net::channel::WriteBatchConfig;
 use crate::protocol::remoting_command::RemotingCommand;
 use crate::protocol::RemotingCommandType;
 use crate::runtime::connection_handler_context::ConnectionHandlerContextWrapper;
@@ -50,6 +57,8 @@ pub struct Client {
     //connection: Connection,
     inner: ArcRefCellWrapper<ClientInner>,
     tx: tokio::sync::mpsc::Sender<SendMessage>,
+    /// The tasks reading from and writing to the connection, which own it.
+    io_tasks: Arc<[AbortHandle; 2]>,
 }
 
 struct ClientInner {
@@ -59,15 +68,26 @@ struct ClientInner {
     tx: tokio::sync::mpsc::Sender<SendMessage>,
 }
 
-type SendMessage = (
-    RemotingCommand,
-    Option<tokio::sync::oneshot::Sender<Result<RemotingCommand>>>,
-    Option<u64>,
-);
+type SendMessage = ChannelMessage;
 
-async fn run_send(mut client: ArcRefCellWrapper<ClientInner>, mut rx: Receiver<SendMessage>) {
-    while let Some((request, tx, timeout)) = rx.recv().await {
-        let _ = client.send(request, tx, timeout).await;
+async fn run_send(client: ArcRefCellWrapper<ClientInner>, mut rx: Receiver<SendMessage>) {
+    let config = WriteBatchConfig::default();
+    let mut connection = client.ctx.channel.connection.clone();
+    let mut response_table = client.response_table.clone();
+    let stats = client.channel.outbound_stats.clone();
+    while let Some(first) = rx.recv().await {
+        if let Err(error) = write_batch(
+            connection.as_mut(),
+            &mut rx,
+            first,
+            &mut response_table,
+            &config,
+            &stats,
+        )
+        .await
+        {
+            error!("send request failed: {}", error);
+        }
     }
 }
 
@@ -148,6 +168,7 @@ impl ClientInner {
     ) -> Result<(
         tokio::sync::mpsc::Sender<SendMessage>,
         ArcRefCellWrapper<ClientInner>,
+        [AbortHandle; 2],
     )>
     where
         T: tokio::net::ToSocketAddrs,
@@ -181,15 +202,17 @@ impl ClientInner {
         };
         let client = ArcRefCellWrapper::new(client);
 
-        tokio::spawn(run_recv(client.clone(), processor));
-        tokio::spawn(run_send(client.clone(), rx));
+        let io_tasks = [
+            tokio::spawn(run_recv(client.clone(), processor)).abort_handle(),
+            tokio::spawn(run_send(client.clone(), rx)).abort_handle(),
+        ];
         if let Some(tx) = tx {
             let _ = tx.send(ConnectionNetEvent::CONNECTED(
                 client.channel.remote_address(),
                 //client.channel.clone(),
             ));
         }
-        Ok((tx_, client))
+        Ok((tx_, client, io_tasks))
     }
 
     pub async fn send(
@@ -248,14 +271,23 @@ impl Client {
         Ok(Client {
             connection: Connection::new(tcp_stream?),
         })*/
-        let (tx, inner) = ClientInner::connect(addr, processor, tx).await?;
+        let (tx, inner, io_tasks) = ClientInner::connect(addr, processor, tx).await?;
         Ok(Client {
             //connection: inner.connection.clone(),
             inner,
             tx,
+            io_tasks: Arc::new(io_tasks),
         })
     }
 
+    /// Stops reading from and writing to the connection, which is closed once the last clone
+    /// of this client is dropped. Pending requests fail.
+    pub fn close(&self) {
+        for io_task in self.io_tasks.iter() {
+            io_task.abort();
+        }
+    }
+
     /// Invokes a remote operation with the given `RemotingCommand`.
     ///
     /// # Arguments
@@ -356,6 +388,16 @@ impl Client {
         }
     }
 
+    pub fn remote_address(&self) -> SocketAddr {
+        self.inner.ctx.channel.remote_address()
+    }
+
+    /// Outbound queue of the client: requests waiting to be written and how they were
+    /// batched.
+    pub fn outbound_queue_stats(&self) -> OutboundQueueStats {
+        self.inner.channel.outbound_stats.snapshot(&self.tx)
+    }
+
     pub fn connection(&self) -> &Connection {
         self.inner.ctx.channel.connection_ref()
     }
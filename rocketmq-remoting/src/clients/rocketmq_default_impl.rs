@@ -16,14 +16,19 @@
  */
 use std::collections::HashMap;
 use std::collections::HashSet;
+use std::net::IpAddr;
+use std::net::SocketAddr;
 use std::sync::atomic::AtomicI32;
 use std::sync::Arc;
 use std::time::Duration;
 
+use parking_lot::RwLock;
 use rand::Rng;
+use rocketmq_common::utils::network_util::NetworkUtil;
 use rocketmq_common::ArcRefCellWrapper;
 use rocketmq_runtime::RocketMQRuntime;
 use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
 use tokio::time;
 use tracing::debug;
 use tracing::error;
@@ -51,13 +56,16 @@ pub struct RocketmqDefaultClient<PR = DefaultRemotingRequestProcessor> {
     tokio_client_config: Arc<TokioClientConfig>,
     //cache connection
     connection_tables: Arc<Mutex<HashMap<String /* ip:port */, Client>>>,
-    namesrv_addr_list: ArcRefCellWrapper<Vec<String>>,
+    namesrv_addr_list: Arc<RwLock<Vec<String>>>,
     namesrv_addr_choosed: ArcRefCellWrapper<Option<String>>,
     available_namesrv_addr_set: ArcRefCellWrapper<HashSet<String>>,
     namesrv_index: Arc<AtomicI32>,
     client_runtime: Arc<RocketMQRuntime>,
     processor: PR,
     tx: Option<tokio::sync::broadcast::Sender<ConnectionNetEvent>>,
+    rpc_hooks: Vec<Arc<Box<dyn RPCHook>>>,
+    /// Name server scan and DNS refresh tasks started by `start`.
+    background_tasks: Arc<parking_lot::Mutex<Vec<JoinHandle<()>>>>,
 }
 impl<PR: RequestProcessor + Sync + Clone + 'static> RocketmqDefaultClient<PR> {
     pub fn new(tokio_client_config: Arc<TokioClientConfig>, processor: PR) -> Self {
@@ -72,13 +80,15 @@ impl<PR: RequestProcessor + Sync + Clone + 'static> RocketmqDefaultClient<PR> {
         Self {
             tokio_client_config,
             connection_tables: Arc::new(Mutex::new(Default::default())),
-            namesrv_addr_list: ArcRefCellWrapper::new(Default::default()),
+            namesrv_addr_list: Arc::new(RwLock::new(Vec::new())),
             namesrv_addr_choosed: ArcRefCellWrapper::new(Default::default()),
             available_namesrv_addr_set: ArcRefCellWrapper::new(Default::default()),
             namesrv_index: Arc::new(AtomicI32::new(init_value_index())),
             client_runtime: Arc::new(RocketMQRuntime::new_multi(10, "client-thread")),
             processor,
             tx,
+            rpc_hooks: Vec::new(),
+            background_tasks: Arc::new(Default::default()),
         }
     }
 }
@@ -110,7 +120,7 @@ impl<PR: RequestProcessor + Sync + Clone + 'static> RocketmqDefaultClient<PR> {
                 }
             }
         }
-        let addr_list = self.namesrv_addr_list.as_ref();
+        let addr_list = self.namesrv_addr_list.read().clone();
         if !addr_list.is_empty() {
             let index = self
                 .namesrv_index
@@ -178,7 +188,11 @@ impl<PR: RequestProcessor + Sync + Clone + 'static> RocketmqDefaultClient<PR> {
             let _ = connection_tables.remove(addr);
         }
 
-        let addr_inner = addr.to_string();
+        // Bare IPv6 literals such as `fd00::1:9876` only resolve with brackets.
+        let addr_inner = NetworkUtil::split_host_port(addr).map_or_else(
+            || addr.to_string(),
+            |(host, port)| NetworkUtil::host_port(host, port),
+        );
 
         match time::timeout(duration, async {
             Client::connect(addr_inner, self.processor.clone(), self.tx.as_ref()).await
@@ -204,20 +218,55 @@ impl<PR: RequestProcessor + Sync + Clone + 'static> RocketmqDefaultClient<PR> {
         }
     }
 
+    /// Runs the registered hooks on `request` before it is sent to `remote_addr`. The custom
+    /// header is encoded into the ext fields first so hooks see every field that goes on the
+    /// wire, e.g. to sign them.
+    fn do_before_rpc_hooks(
+        &self,
+        remote_addr: SocketAddr,
+        request: &mut RemotingCommand,
+    ) -> Result<()> {
+        if self.rpc_hooks.is_empty() {
+            return Ok(());
+        }
+        request.make_custom_header_to_net();
+        for hook in self.rpc_hooks.iter() {
+            hook.do_before_request(remote_addr, request)?;
+        }
+        Ok(())
+    }
+
+    fn do_after_rpc_hooks(
+        &self,
+        remote_addr: SocketAddr,
+        response: &mut RemotingCommand,
+    ) -> Result<()> {
+        for hook in self.rpc_hooks.iter() {
+            hook.do_after_response(remote_addr, response)?;
+        }
+        Ok(())
+    }
+
     async fn scan_available_name_srv(&self) {
-        if self.namesrv_addr_list.as_ref().is_empty() {
+        let namesrv_addr_list = self.namesrv_addr_list.read().clone();
+        if namesrv_addr_list.is_empty() {
             debug!("scanAvailableNameSrv addresses of name remoting_server is null!");
             return;
         }
-        for address in self.available_namesrv_addr_set.as_ref().iter() {
-            if !self.namesrv_addr_list.as_ref().contains(address) {
-                warn!("scanAvailableNameSrv remove invalid address {}", address);
-                self.available_namesrv_addr_set
-                    .mut_from_ref()
-                    .remove(address);
-            }
+        let invalid_addresses: Vec<String> = self
+            .available_namesrv_addr_set
+            .as_ref()
+            .iter()
+            .filter(|address| !namesrv_addr_list.contains(address))
+            .cloned()
+            .collect();
+        for address in invalid_addresses.iter() {
+            warn!("scanAvailableNameSrv remove invalid address {}", address);
+            self.available_namesrv_addr_set
+                .mut_from_ref()
+                .remove(address);
         }
-        for namesrv_addr in self.namesrv_addr_list.as_ref().iter() {
+        for namesrv_addr in namesrv_addr_list.iter() {
             let client = self
                 .get_and_create_client(Some(namesrv_addr.as_str()))
                 .await;
@@ -235,6 +284,37 @@ impl<PR: RequestProcessor + Sync + Clone + 'static> RocketmqDefaultClient<PR> {
             }
         }
     }
+
+    /// Re-resolves the name servers given as hostnames and drops the cached connection of any
+    /// whose peer is no longer among the resolved addresses, so the next request reconnects to
+    /// where the name now points instead of sticking to the IP resolved at first connect.
+    async fn refresh_name_srv_dns(&self) {
+        let namesrv_addr_list = self.namesrv_addr_list.read().clone();
+        for namesrv_addr in namesrv_addr_list
+            .iter()
+            .filter(|addr| is_hostname_addr(addr))
+        {
+            let resolved: HashSet<SocketAddr> = match tokio::net::lookup_host(namesrv_addr).await {
+                Ok(addrs) => addrs.collect(),
+                Err(e) => {
+                    warn!("resolve name server address {} failed: {}", namesrv_addr, e);
+                    continue;
+                }
+            };
+            let mut connection_tables = self.connection_tables.lock().await;
+            let Some(client) = connection_tables.get(namesrv_addr) else {
+                continue;
+            };
+            let remote_address = client.remote_address();
+            if !resolved.contains(&remote_address) {
+                info!(
+                    "name server {} now resolves to {:?}, close the connection to {}",
+                    namesrv_addr, resolved, remote_address
+                );
+                connection_tables.remove(namesrv_addr);
+            }
+        }
+    }
 }
 
 #[allow(unused_variables)]
@@ -249,77 +329,90 @@ impl<PR: RequestProcessor + Sync + Clone + 'static> RemotingService for Rocketmq
                 client.scan_available_name_srv().await;
             }
         });*/
-        self.client_runtime.get_handle().spawn(async move {
-            loop {
-                time::sleep(Duration::from_millis(1)).await;
-                client.scan_available_name_srv().await;
-            }
-        });
+        let scan_task = self
+            .client_runtime
+            .spawn_named("scanAvailableNameSrv", async move {
+                loop {
+                    time::sleep(Duration::from_millis(1)).await;
+                    client.scan_available_name_srv().await;
+                }
+            });
+
+        let client = self.clone();
+        let dns_refresh_interval =
+            Duration::from_millis(self.tokio_client_config.namesrv_dns_refresh_interval_millis);
+        let refresh_task = self
+            .client_runtime
+            .spawn_named("refreshNameSrvDns", async move {
+                loop {
+                    time::sleep(dns_refresh_interval).await;
+                    client.refresh_name_srv_dns().await;
+                }
+            });
+        self.background_tasks
+            .lock()
+            .extend([scan_task, refresh_task]);
     }
 
     fn shutdown(&mut self) {
-        todo!()
+        for task in self.background_tasks.lock().drain(..) {
+            task.abort();
+        }
+        // Do not wait for a request holding the table, close the connections once it is released
+        match self.connection_tables.try_lock() {
+            Ok(mut connection_tables) => close_connections(&mut connection_tables),
+            Err(_) => {
+                let connection_tables = self.connection_tables.clone();
+                self.client_runtime.get_handle().spawn(async move {
+                    close_connections(&mut *connection_tables.lock().await);
+                });
+            }
+        }
     }
 
     fn register_rpc_hook(&mut self, hook: Arc<Box<dyn RPCHook>>) {
-        todo!()
+        if !self.rpc_hooks.iter().any(|value| Arc::ptr_eq(value, &hook)) {
+            self.rpc_hooks.push(hook);
+        }
     }
 
     fn clear_rpc_hook(&mut self) {
-        todo!()
+        self.rpc_hooks.clear();
     }
 }
 
 #[allow(unused_variables)]
 impl<PR: RequestProcessor + Sync + Clone + 'static> RemotingClient for RocketmqDefaultClient<PR> {
     async fn update_name_server_address_list(&self, addrs: Vec<String>) {
-        let old = self.namesrv_addr_list.mut_from_ref();
-        let mut update = false;
-
-        if !addrs.is_empty() {
-            if old.is_empty() || addrs.len() != old.len() {
-                update = true;
-            } else {
-                for addr in &addrs {
-                    if !old.contains(addr) {
-                        update = true;
-                        break;
-                    }
-                }
+        if addrs.is_empty() {
+            return;
+        }
+        let old = {
+            let mut namesrv_addr_list = self.namesrv_addr_list.write();
+            let unchanged = namesrv_addr_list.len() == addrs.len()
+                && addrs.iter().all(|addr| namesrv_addr_list.contains(addr));
+            if unchanged {
+                return;
             }
+            std::mem::replace(&mut *namesrv_addr_list, addrs.clone())
+        };
+        info!(
+            "name remoting_server address updated. NEW : {:?} , OLD: {:?}",
+            addrs, old
+        );
 
-            if update {
-                // Shuffle the addresses
-                // Shuffle logic is not implemented here as it is not available in standard library
-                // You can implement it using various algorithms like Fisher-Yates shuffle
-
-                info!(
-                    "name remoting_server address updated. NEW : {:?} , OLD: {:?}",
-                    addrs, old
-                );
-                old.clone_from(&addrs);
-
-                // should close the channel if choosed addr is not exist.
-                if let Some(namesrv_addr) = self.namesrv_addr_choosed.as_ref() {
-                    if !addrs.contains(namesrv_addr) {
-                        let mut remove_vec = Vec::new();
-                        let mut result = self.connection_tables.lock().await;
-                        for (addr, client) in result.iter() {
-                            if addr.contains(namesrv_addr) {
-                                remove_vec.push(addr.clone());
-                            }
-                        }
-                        for addr in &remove_vec {
-                            result.remove(addr);
-                        }
-                    }
-                }
+        // should close the channel if choosed addr is not exist.
+        let choosed = self.namesrv_addr_choosed.as_ref().clone();
+        if let Some(namesrv_addr) = choosed {
+            if !addrs.contains(&namesrv_addr) {
+                self.namesrv_addr_choosed.mut_from_ref().take();
+                self.connection_tables.lock().await.remove(&namesrv_addr);
             }
         }
     }
 
-    fn get_name_server_address_list(&self) -> &[String] {
-        self.namesrv_addr_list.as_ref()
+    fn get_name_server_address_list(&self) -> Vec<String> {
+        self.namesrv_addr_list.read().clone()
     }
 
     fn get_available_name_srv_list(&self) -> Vec<String> {
@@ -340,6 +433,9 @@ impl<PR: RequestProcessor + Sync + Clone + 'static> RemotingClient for RocketmqD
         match client {
             None => Err(Error::RemoteException("get client failed".to_string())),
             Some(mut client) => {
+                let remote_addr = client.remote_address();
+                let mut request = request;
+                self.do_before_rpc_hooks(remote_addr, &mut request)?;
                 match self
                     .client_runtime
                     .get_handle()
@@ -353,7 +449,10 @@ impl<PR: RequestProcessor + Sync + Clone + 'static> RemotingClient for RocketmqD
                 {
                     Ok(result) => match result {
                         Ok(response) => match response {
-                            Ok(value) => Ok(value),
+                            Ok(mut value) => {
+                                self.do_after_rpc_hooks(remote_addr, &mut value)?;
+                                Ok(value)
+                            }
                             Err(e) => Err(Error::RemoteException(e.to_string())),
                         },
                         Err(err) => Err(Error::RemoteException(err.to_string())),
@@ -371,6 +470,11 @@ impl<PR: RequestProcessor + Sync + Clone + 'static> RemotingClient for RocketmqD
                 error!("get client failed");
             }
             Some(mut client) => {
+                let mut request = request;
+                if let Err(e) = self.do_before_rpc_hooks(client.remote_address(), &mut request) {
+                    error!("invoke oneway to {} rejected by rpc hook: {}", addr, e);
+                    return;
+                }
                 self.client_runtime.get_handle().spawn(async move {
                     match time::timeout(Duration::from_millis(timeout_millis), async move {
                         //client.lock().await.send(request).await
@@ -399,7 +503,71 @@ impl<PR: RequestProcessor + Sync + Clone + 'static> RemotingClient for RocketmqD
     }
 }
 
+fn close_connections(connection_tables: &mut HashMap<String /* ip:port */, Client>) {
+    for (addr, client) in connection_tables.drain() {
+        client.close();
+        info!("shutdown client, close the connection to {}", addr);
+    }
+}
+
+/// Whether the host part of `addr` is a name to resolve rather than an IP literal.
+fn is_hostname_addr(addr: &str) -> bool {
+    let host = NetworkUtil::split_host_port(addr).map_or(addr, |(host, _)| host);
+    !host.is_empty() && host.parse::<IpAddr>().is_err()
+}
+
 fn init_value_index() -> i32 {
     let mut rng = rand::thread_rng();
     rng.gen_range(0..999)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hostname_addr_detection() {
+        assert!(is_hostname_addr("rocketmq-namesrv.default.svc:9876"));
+        assert!(is_hostname_addr("localhost:9876"));
+        assert!(!is_hostname_addr("127.0.0.1:9876"));
+        assert!(!is_hostname_addr("[::1]:9876"));
+    }
+
+    #[test]
+    fn shutdown_closes_the_cached_connections() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let client = runtime.block_on(async {
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = listener.local_addr().unwrap().to_string();
+            let peer = tokio::spawn(async move {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut buf = Vec::new();
+                tokio::io::AsyncReadExt::read_to_end(&mut stream, &mut buf).await
+            });
+            let mut client = RocketmqDefaultClient::new(
+                Arc::new(TokioClientConfig::default()),
+                DefaultRemotingRequestProcessor,
+            );
+            let connection = Client::connect(addr.as_str(), DefaultRemotingRequestProcessor, None)
+                .await
+                .unwrap();
+            client
+                .connection_tables
+                .lock()
+                .await
+                .insert(addr, connection);
+
+            client.shutdown();
+            assert!(client.connection_tables.lock().await.is_empty());
+            // The peer reads the end of the stream once the connection is closed
+            let read = tokio::time::timeout(Duration::from_secs(5), peer)
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(read.unwrap(), 0);
+            client
+        });
+        drop(runtime);
+        drop(client);
+    }
+}
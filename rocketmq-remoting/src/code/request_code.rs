@@ -14,6 +14,7 @@
  * See the License for the specific language governing permissions and
  * limitations under the License.
  */
+use rocketmq_common::common::mq_version::RocketMqVersion;
 
 #[derive(Debug, PartialEq, Clone, Copy, Hash)]
 pub enum RequestCode {
@@ -129,6 +130,13 @@ pub enum RequestCode {
     GetSubscriptionGroupConfig = 352,
     UpdateAndGetGroupForbidden = 353,
     LitePullMessage = 361,
+    GetConsumerOfflineHistory = 370,
+    ResendCommitLogMessages = 371,
+    QueryDlqMessages = 372,
+    ResendDlqMessages = 373,
+    GetCommercialStats = 374,
+    QueryMessageTrace = 375,
+    GetPullSourceStats = 376,
     QueryAssignment = 400,
     SetMessageRequestMode = 401,
     GetAllMessageRequestMode = 402,
@@ -272,6 +280,13 @@ impl From<i32> for RequestCode {
             352 => RequestCode::GetSubscriptionGroupConfig,
             353 => RequestCode::UpdateAndGetGroupForbidden,
             361 => RequestCode::LitePullMessage,
+            370 => RequestCode::GetConsumerOfflineHistory,
+            371 => RequestCode::ResendCommitLogMessages,
+            372 => RequestCode::QueryDlqMessages,
+            373 => RequestCode::ResendDlqMessages,
+            374 => RequestCode::GetCommercialStats,
+            375 => RequestCode::QueryMessageTrace,
+            376 => RequestCode::GetPullSourceStats,
             400 => RequestCode::QueryAssignment,
             401 => RequestCode::SetMessageRequestMode,
             402 => RequestCode::GetAllMessageRequestMode,
@@ -303,6 +318,20 @@ impl RequestCode {
     pub fn value_of(code: i32) -> Option<Self> {
         Some(code.into())
     }
+
+    /// The oldest broker version serving this request, `None` when every broker does.
+    pub fn min_broker_version(self) -> Option<RocketMqVersion> {
+        match self {
+            RequestCode::PopMessage
+            | RequestCode::AckMessage
+            | RequestCode::BatchAckMessage
+            | RequestCode::PeekMessage
+            | RequestCode::ChangeMessageInvisibleTime
+            | RequestCode::Notification
+            | RequestCode::PollingInfo => Some(RocketMqVersion::V500),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Clone, Copy)]
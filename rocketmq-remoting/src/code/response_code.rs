@@ -86,6 +86,7 @@ pub enum ResponseCode {
     BrokerDispatchNotComplete = 212,
     BroadcastConsumption = 213,
     FlowControl = 215,
+    TopicRouteNotModified = 216,
     NotLeaderForQueue = 501,
     IllegalOperation = 604,
     RpcUnknown = -1000,
@@ -158,6 +159,7 @@ impl From<i32> for ResponseCode {
             212 => ResponseCode::BrokerDispatchNotComplete,
             213 => ResponseCode::BroadcastConsumption,
             215 => ResponseCode::FlowControl,
+            216 => ResponseCode::TopicRouteNotModified,
             501 => ResponseCode::NotLeaderForQueue,
             604 => ResponseCode::IllegalOperation,
             -1000 => ResponseCode::RpcUnknown,
@@ -279,6 +281,7 @@ mod tests {
         );
         assert_eq!(ResponseCode::from(213), ResponseCode::BroadcastConsumption);
         assert_eq!(ResponseCode::from(215), ResponseCode::FlowControl);
+        assert_eq!(ResponseCode::from(216), ResponseCode::TopicRouteNotModified);
         assert_eq!(ResponseCode::from(501), ResponseCode::NotLeaderForQueue);
         assert_eq!(ResponseCode::from(604), ResponseCode::IllegalOperation);
         assert_eq!(ResponseCode::from(-1000), ResponseCode::RpcUnknown);
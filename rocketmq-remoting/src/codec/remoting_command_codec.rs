@@ -165,6 +165,9 @@ impl Encoder<RemotingCommand> for RemotingCommandCodec {
         if let Some(body_inner) = item.get_body() {
             dst.put(body_inner.as_ref());
         }
+        if let Some(body_region) = item.body_region() {
+            body_region.transfer_to(dst);
+        }
         Ok(())
     }
 }
@@ -174,6 +177,7 @@ mod tests {
     use bytes::Bytes;
 
     use super::*;
+    use crate::protocol::file_region::FileRegion;
     use crate::protocol::header::client_request_header::GetRouteInfoRequestHeader;
     use crate::protocol::LanguageCode;
 
@@ -205,6 +209,36 @@ mod tests {
         assert!(encoder.encode(command, &mut dst).is_ok());
     }
 
+    #[tokio::test]
+    async fn encode_appends_body_region_after_body() {
+        struct StaticRegion(&'static [u8]);
+        impl FileRegion for StaticRegion {
+            fn count(&self) -> usize {
+                self.0.len()
+            }
+
+            fn transfer_to(&self, dst: &mut BytesMut) {
+                dst.put(self.0);
+            }
+        }
+
+        let mut encoder = RemotingCommandCodec::new();
+        let mut dst = BytesMut::new();
+        let command = RemotingCommand::create_response_command()
+            .set_body(Some(Bytes::from_static(b"head-")))
+            .set_body_region(Some(std::sync::Arc::new(StaticRegion(b"region"))));
+        assert_eq!(command.body_length(), 11);
+        encoder.encode(command, &mut dst).unwrap();
+        assert!(dst.ends_with(b"head-region"));
+
+        let decoded = encoder.decode(&mut dst).unwrap().unwrap();
+        assert_eq!(
+            decoded.get_body().map(|body| body.as_ref()),
+            Some(b"head-region".as_ref())
+        );
+        assert!(decoded.body_region().is_none());
+    }
+
     #[tokio::test]
     async fn encode_handles_non_empty_body() {
         let mut encoder = RemotingCommandCodec::new();
@@ -20,6 +20,8 @@ use std::fmt::Display;
 use std::hash::Hash;
 use std::hash::Hasher;
 use std::net::SocketAddr;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -27,6 +29,8 @@ use futures_util::SinkExt;
 use rocketmq_common::ArcRefCellWrapper;
 use tokio::sync::mpsc::Receiver;
 use tokio::time::timeout;
+use tokio::time::timeout_at;
+use tokio::time::Instant;
 use tracing::error;
 use uuid::Uuid;
 
@@ -46,20 +50,113 @@ pub struct Channel {
     tx: tokio::sync::mpsc::Sender<ChannelMessage>,
     pub(crate) connection: ArcRefCellWrapper<Connection>,
     pub(crate) response_table: ArcRefCellWrapper<HashMap<i32, ResponseFuture>>,
+    pub(crate) outbound_stats: Arc<OutboundStats>,
 }
 
-type ChannelMessage = (
+pub(crate) type ChannelMessage = (
     RemotingCommand,
     Option<tokio::sync::oneshot::Sender<Result<RemotingCommand>>>,
     Option<u64>,
 );
 
-pub(crate) async fn run_send(
-    mut connection: ArcRefCellWrapper<Connection>,
-    mut rx: Receiver<ChannelMessage>,
-    mut response_table: ArcRefCellWrapper<HashMap<i32, ResponseFuture>>,
-) {
-    while let Some((request, tx, timeout_millis)) = rx.recv().await {
+/// How the writer of a channel coalesces queued commands into one flush. Commands already
+/// queued behind the one being written go out with it, so a busy channel makes one write for
+/// many small commands while an idle one still writes each command at once unless
+/// `max_linger` asks to wait for more.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WriteBatchConfig {
+    /// Most commands flushed together.
+    pub max_batch_commands: usize,
+    /// Body bytes after which the batch is flushed.
+    pub max_batch_bytes: usize,
+    /// How long to wait for more commands once the queue is empty, zero flushes at once.
+    pub max_linger: Duration,
+}
+
+impl Default for WriteBatchConfig {
+    fn default() -> Self {
+        Self {
+            max_batch_commands: 32,
+            max_batch_bytes: 64 * 1024,
+            max_linger: Duration::ZERO,
+        }
+    }
+}
+
+/// Counters of the commands written by the writer of a channel.
+#[derive(Debug, Default)]
+pub struct OutboundStats {
+    written: AtomicU64,
+    failed: AtomicU64,
+    flushes: AtomicU64,
+    max_batch: AtomicU64,
+}
+
+impl OutboundStats {
+    fn record_flush(&self, commands: u64) {
+        self.written.fetch_add(commands, Ordering::Relaxed);
+        self.flushes.fetch_add(1, Ordering::Relaxed);
+        self.max_batch.fetch_max(commands, Ordering::Relaxed);
+    }
+
+    fn record_failed(&self, commands: u64) {
+        self.failed.fetch_add(commands, Ordering::Relaxed);
+    }
+
+    pub(crate) fn snapshot<T>(&self, tx: &tokio::sync::mpsc::Sender<T>) -> OutboundQueueStats {
+        OutboundQueueStats {
+            pending: (tx.max_capacity() - tx.capacity()) as u64,
+            written: self.written.load(Ordering::Relaxed),
+            failed: self.failed.load(Ordering::Relaxed),
+            flushes: self.flushes.load(Ordering::Relaxed),
+            max_batch: self.max_batch.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Outbound queue of a channel at a point in time.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OutboundQueueStats {
+    /// Commands queued and not written yet.
+    pub pending: u64,
+    /// Commands written to the socket.
+    pub written: u64,
+    /// Commands that could not be written.
+    pub failed: u64,
+    /// Flushes of the socket, each of them wrote one batch.
+    pub flushes: u64,
+    /// Largest batch written in one flush.
+    pub max_batch: u64,
+}
+
+impl OutboundQueueStats {
+    /// Commands written per flush on average.
+    pub fn avg_batch_size(&self) -> f64 {
+        if self.flushes == 0 {
+            return 0.0;
+        }
+        self.written as f64 / self.flushes as f64
+    }
+}
+
+/// Writes `first` along with the commands queued behind it in `rx`, within the limits of
+/// `config`, and flushes them once. Commands expecting a response are registered in
+/// `response_table` before they are written and removed again when they cannot be. Returns
+/// the I/O error that broke the connection, if any.
+pub(crate) async fn write_batch(
+    connection: &mut Connection,
+    rx: &mut Receiver<ChannelMessage>,
+    first: ChannelMessage,
+    response_table: &mut ArcRefCellWrapper<HashMap<i32, ResponseFuture>>,
+    config: &WriteBatchConfig,
+    stats: &OutboundStats,
+) -> std::result::Result<(), std::io::Error> {
+    let deadline = Instant::now() + config.max_linger;
+    let mut opaques = Vec::with_capacity(config.max_batch_commands);
+    let mut batch_bytes = 0;
+    let mut next = Some(first);
+    let mut io_error = None;
+    while let Some((request, tx, timeout_millis)) = next.take() {
         let opaque = request.opaque();
         if let Some(tx) = tx {
             response_table.insert(
@@ -67,21 +164,71 @@ pub(crate) async fn run_send(
                 ResponseFuture::new(opaque, timeout_millis.unwrap_or(0), true, tx),
             );
         }
-        match connection.writer.send(request).await {
-            Ok(_) => {}
-            Err(error) => match error {
-                Io(error) => {
-                    error!("send request failed: {}", error);
-                    response_table.remove(&opaque);
-                    connection.ok = false;
-                    return;
-                }
-                _ => {
-                    response_table.remove(&opaque);
-                }
-            },
+        batch_bytes += request.body().as_ref().map_or(0, |body| body.len());
+        match connection.writer.feed(request).await {
+            Ok(_) => opaques.push(opaque),
+            Err(Io(error)) => {
+                response_table.remove(&opaque);
+                stats.record_failed(1);
+                io_error = Some(error);
+                break;
+            }
+            Err(_) => {
+                response_table.remove(&opaque);
+                stats.record_failed(1);
+            }
+        }
+        if opaques.len() >= config.max_batch_commands || batch_bytes >= config.max_batch_bytes {
+            break;
+        }
+        next = match rx.try_recv() {
+            Ok(message) => Some(message),
+            Err(_) if !config.max_linger.is_zero() => {
+                timeout_at(deadline, rx.recv()).await.ok().flatten()
+            }
+            Err(_) => None,
         };
     }
+    if io_error.is_none() {
+        match connection.writer.flush().await {
+            Ok(_) => {
+                stats.record_flush(opaques.len() as u64);
+                return Ok(());
+            }
+            Err(Io(error)) => io_error = Some(error),
+            Err(error) => io_error = Some(std::io::Error::other(error.to_string())),
+        }
+    }
+    for opaque in opaques.iter() {
+        response_table.remove(opaque);
+    }
+    stats.record_failed(opaques.len() as u64);
+    connection.ok = false;
+    Err(io_error.unwrap())
+}
+
+pub(crate) async fn run_send(
+    mut connection: ArcRefCellWrapper<Connection>,
+    mut rx: Receiver<ChannelMessage>,
+    mut response_table: ArcRefCellWrapper<HashMap<i32, ResponseFuture>>,
+    stats: Arc<OutboundStats>,
+) {
+    let config = WriteBatchConfig::default();
+    while let Some(first) = rx.recv().await {
+        if let Err(error) = write_batch(
+            connection.as_mut(),
+            &mut rx,
+            first,
+            &mut response_table,
+            &config,
+            &stats,
+        )
+        .await
+        {
+            error!("send request failed: {}", error);
+            return;
+        }
+    }
 }
 
 impl PartialEq for Channel {
@@ -140,7 +287,13 @@ impl Channel {
         let (tx, rx) = tokio::sync::mpsc::channel(1024);
         //let response_table = ArcRefCellWrapper::new(HashMap::with_capacity(32));
         let connection = ArcRefCellWrapper::new(connection);
-        tokio::spawn(run_send(connection.clone(), rx, response_table.clone()));
+        let outbound_stats = Arc::new(OutboundStats::default());
+        tokio::spawn(run_send(
+            connection.clone(),
+            rx,
+            response_table.clone(),
+            outbound_stats.clone(),
+        ));
         Self {
             local_address,
             remote_address,
@@ -148,6 +301,7 @@ impl Channel {
             tx,
             connection,
             response_table,
+            outbound_stats,
         }
     }
 }
@@ -173,6 +327,12 @@ impl Channel {
         self.channel_id.as_str()
     }
 
+    /// Outbound queue of the channel: commands waiting to be written and how they were
+    /// batched.
+    pub fn outbound_queue_stats(&self) -> OutboundQueueStats {
+        self.outbound_stats.snapshot(&self.tx)
+    }
+
     pub fn connection(&self) -> ArcRefCellWrapper<Connection> {
         self.connection.clone()
     }
@@ -217,6 +377,14 @@ impl Channel {
             }
         }
     }
+
+    pub async fn send_one_way(&mut self, request: RemotingCommand) -> Result<()> {
+        let request = request.mark_oneway_rpc();
+        if let Err(err) = self.tx.send((request, None, None)).await {
+            return Err(ChannelSendRequestFailed(err.to_string()));
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -225,6 +393,8 @@ mod tests {
     use std::net::Ipv4Addr;
     use std::net::SocketAddr;
 
+    use futures::StreamExt;
+
     use super::*;
 
     #[test]
@@ -255,4 +425,58 @@ mod tests {
         assert_eq!(channel.remote_address(), new_remote_address);
         assert_eq!(channel.channel_id(), new_channel_id);*/
     }
+
+    #[tokio::test]
+    async fn write_batch_flushes_queued_commands_together() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let (stream, accepted) = tokio::join!(tokio::net::TcpStream::connect(address), async {
+            listener.accept().await.unwrap().0
+        });
+        let mut connection = Connection::new(stream.unwrap());
+        let mut peer = Connection::new(accepted);
+        let (tx, mut rx) = tokio::sync::mpsc::channel(16);
+        for opaque in 1..=4 {
+            tx.send((
+                RemotingCommand::create_remoting_command(10).set_opaque(opaque),
+                None,
+                None,
+            ))
+            .await
+            .unwrap();
+        }
+        let (response_tx, _response_rx) = tokio::sync::oneshot::channel();
+        let first = (
+            RemotingCommand::create_remoting_command(10).set_opaque(0),
+            Some(response_tx),
+            Some(3000),
+        );
+        let mut response_table = ArcRefCellWrapper::new(HashMap::new());
+        let config = WriteBatchConfig {
+            max_batch_commands: 3,
+            ..WriteBatchConfig::default()
+        };
+        let stats = OutboundStats::default();
+
+        write_batch(
+            &mut connection,
+            &mut rx,
+            first,
+            &mut response_table,
+            &config,
+            &stats,
+        )
+        .await
+        .unwrap();
+        assert!(response_table.contains_key(&0));
+        let snapshot = stats.snapshot(&tx);
+        assert_eq!(snapshot.pending, 2);
+        assert_eq!(snapshot.written, 3);
+        assert_eq!(snapshot.flushes, 1);
+        assert_eq!(snapshot.avg_batch_size(), 3.0);
+        for opaque in 0..3 {
+            let command = peer.reader.next().await.unwrap().unwrap();
+            assert_eq!(command.opaque(), opaque);
+        }
+    }
 }
@@ -36,6 +36,7 @@ use crate::rocketmq_serializable::RocketMQSerializable;
 pub mod admin;
 pub mod body;
 pub mod command_custom_header;
+pub mod file_region;
 pub mod filter;
 pub mod forbidden_type;
 pub mod header;
@@ -408,6 +409,17 @@ pub trait RemotingDeserializable {
     /// A `Result` containing either the deserialized object of type `Output` or an `Error` if
     /// deserialization fails.
     fn decode(bytes: &[u8]) -> Result<Self::Output, Error>;
+
+    /// Deserializes an object from a slice of bytes, tolerating the JSON quirks of Java
+    /// brokers and clients (empty body, single quoted strings, enum case differences).
+    ///
+    /// # Arguments
+    /// * `bytes` - A slice of bytes representing the serialized object.
+    ///
+    /// # Returns
+    /// A `Result` containing either the deserialized object of type `Output` or an `Error`
+    /// describing the failing field and the raw JSON around it.
+    fn decode_lenient(bytes: &[u8]) -> Result<Self::Output, Error>;
 }
 
 pub trait JsonSerializable: Serialize + RemotingSerializable {}
@@ -431,6 +443,10 @@ impl<T: serde::de::DeserializeOwned> RemotingDeserializable for T {
     fn decode(bytes: &[u8]) -> Result<Self::Output, Error> {
         SerdeJsonUtils::decode(bytes)
     }
+
+    fn decode_lenient(bytes: &[u8]) -> Result<Self::Output, Error> {
+        SerdeJsonUtils::decode_lenient(bytes)
+    }
 }
 
 /// Trait for handling fast encoding and decoding headers in a RocketMQ message.
@@ -19,11 +19,19 @@ pub mod broker_body;
 pub mod consumer_running_info;
 pub mod create_topic_list_request_body;
 pub mod get_consumer_listby_group_response_body;
+pub mod get_consumer_status_body;
 
 pub mod consumer_connection;
 
+pub mod commercial_stats_body;
 pub mod connection;
+pub mod dlq_messages_body;
 pub mod group_list;
 pub mod kv_table;
+pub mod pull_source_stats_body;
+pub mod query_consume_time_span_body;
+pub mod query_message_trace_body;
+pub mod resend_commit_log_messages_response_body;
+pub mod resend_dlq_messages_response_body;
 pub mod topic;
 pub mod topic_info_wrapper;
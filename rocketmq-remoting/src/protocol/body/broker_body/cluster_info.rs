@@ -18,6 +18,7 @@
 use std::collections::HashMap;
 use std::collections::HashSet;
 
+use rocketmq_common::common::mix_all::MASTER_BROKER_ADDR_ID;
 use serde::Deserialize;
 use serde::Serialize;
 
@@ -43,3 +44,93 @@ impl ClusterInfo {
         }
     }
 }
+
+impl ClusterInfo {
+    /// Names of the brokers in `cluster_name`, `None` when the cluster is unknown.
+    pub fn broker_names_of_cluster(&self, cluster_name: &str) -> Option<&HashSet<String>> {
+        self.cluster_addr_table.as_ref()?.get(cluster_name)
+    }
+
+    /// Master addresses of the brokers in `cluster_name`, sorted. Brokers without a master
+    /// online are skipped.
+    pub fn master_addrs_of_cluster(&self, cluster_name: &str) -> Vec<String> {
+        let (Some(broker_names), Some(broker_addr_table)) = (
+            self.broker_names_of_cluster(cluster_name),
+            self.broker_addr_table.as_ref(),
+        ) else {
+            return Vec::new();
+        };
+        let mut master_addrs = broker_names
+            .iter()
+            .filter_map(|broker_name| broker_addr_table.get(broker_name))
+            .filter_map(|broker_data| broker_data.broker_addrs().get(&MASTER_BROKER_ADDR_ID))
+            .cloned()
+            .collect::<Vec<_>>();
+        master_addrs.sort();
+        master_addrs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn broker_data(cluster: &str, broker_name: &str, addrs: &[(i64, &str)]) -> BrokerData {
+        BrokerData::new(
+            cluster.to_string(),
+            broker_name.to_string(),
+            addrs
+                .iter()
+                .map(|(id, addr)| (*id, addr.to_string()))
+                .collect(),
+            None,
+        )
+    }
+
+    #[test]
+    fn master_addrs_of_cluster_skips_brokers_without_master() {
+        let broker_addr_table = HashMap::from([
+            (
+                "broker-a".to_string(),
+                broker_data(
+                    "c1",
+                    "broker-a",
+                    &[(0, "10.0.0.2:10911"), (1, "10.0.0.3:10911")],
+                ),
+            ),
+            (
+                "broker-b".to_string(),
+                broker_data("c1", "broker-b", &[(0, "10.0.0.1:10911")]),
+            ),
+            (
+                "broker-c".to_string(),
+                broker_data("c1", "broker-c", &[(1, "10.0.0.4:10911")]),
+            ),
+            (
+                "broker-d".to_string(),
+                broker_data("c2", "broker-d", &[(0, "10.0.0.5:10911")]),
+            ),
+        ]);
+        let cluster_addr_table = HashMap::from([
+            (
+                "c1".to_string(),
+                HashSet::from([
+                    "broker-a".to_string(),
+                    "broker-b".to_string(),
+                    "broker-c".to_string(),
+                ]),
+            ),
+            ("c2".to_string(), HashSet::from(["broker-d".to_string()])),
+        ]);
+        let cluster_info = ClusterInfo::new(Some(broker_addr_table), Some(cluster_addr_table));
+
+        assert_eq!(
+            cluster_info.master_addrs_of_cluster("c1"),
+            vec!["10.0.0.1:10911", "10.0.0.2:10911"]
+        );
+        assert!(cluster_info.master_addrs_of_cluster("c3").is_empty());
+        assert!(ClusterInfo::default()
+            .master_addrs_of_cluster("c1")
+            .is_empty());
+    }
+}
@@ -16,6 +16,7 @@
  */
 
 use bytes::Bytes;
+use rocketmq_common::common::compression::compression_type::CompressionType;
 use rocketmq_common::common::mq_version::RocketMqVersion;
 use rocketmq_common::utils::serde_json_utils::SerdeJsonUtils;
 use serde::Deserialize;
@@ -51,12 +52,16 @@ impl RegisterBrokerBody {
         &self.filter_server_list
     }
 
+    /// Encodes the body, zlib compressed when `compress` is set, which shrinks the payload of
+    /// brokers hosting many topics by an order of magnitude.
     pub fn encode(&self, compress: bool) -> Vec<u8> {
+        let body = <Self as RemotingSerializable>::encode(self);
         if !compress {
-            return <Self as RemotingSerializable>::encode(self);
+            return body;
         }
-
-        unimplemented!()
+        CompressionType::Zlib
+            .compression(&Bytes::from(body))
+            .to_vec()
     }
 }
 
@@ -69,6 +74,46 @@ impl RegisterBrokerBody {
         if !compressed {
             return SerdeJsonUtils::decode::<RegisterBrokerBody>(bytes.iter().as_slice()).unwrap();
         }
-        todo!()
+        let body = CompressionType::Zlib.try_decompression(bytes).unwrap();
+        SerdeJsonUtils::decode::<RegisterBrokerBody>(body.as_ref()).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rocketmq_common::common::config::TopicConfig;
+
+    use super::*;
+
+    #[test]
+    fn compressed_body_round_trip() {
+        let mut body = RegisterBrokerBody::default();
+        for i in 0..100 {
+            let topic = format!("TopicTest{}", i);
+            body.topic_config_serialize_wrapper
+                .topic_config_serialize_wrapper
+                .topic_config_table
+                .insert(topic.clone(), TopicConfig::new(topic));
+        }
+        body.filter_server_list.push("127.0.0.1:9876".to_string());
+
+        let plain = body.encode(false);
+        let compressed = body.encode(true);
+        assert!(compressed.len() < plain.len());
+
+        let decoded = RegisterBrokerBody::decode(
+            &Bytes::from(compressed),
+            true,
+            RocketMqVersion::CURRENT_VERSION,
+        );
+        assert_eq!(
+            decoded
+                .topic_config_serialize_wrapper()
+                .topic_config_serialize_wrapper
+                .topic_config_table
+                .len(),
+            100
+        );
+        assert_eq!(decoded.filter_server_list(), &body.filter_server_list);
     }
 }
@@ -0,0 +1,41 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use serde::Deserialize;
+use serde::Serialize;
+
+/// Billed message counts and bytes of a broker, per minute, owner, topic and group.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct CommercialStatsBody {
+    pub items: Vec<CommercialStatsItem>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct CommercialStatsItem {
+    /// Start of the minute, in milliseconds.
+    pub minute_timestamp: u64,
+    pub owner: String,
+    pub topic: String,
+    /// Producer group of sends, consumer group of pulls.
+    pub group: String,
+    /// e.g. `SEND_SUCCESS` or `RCV_SUCCESS`.
+    pub stats_type: String,
+    /// Billed message count, in units of `commercialSizePerMsg` times `commercialBaseCount`.
+    pub times: i64,
+    pub size: i64,
+}
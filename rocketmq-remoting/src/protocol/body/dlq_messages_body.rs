@@ -0,0 +1,53 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use serde::Deserialize;
+use serde::Serialize;
+
+/// A page of the messages in a dead letter queue.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct DlqMessagesBody {
+    pub messages: Vec<DlqMessage>,
+    /// Consume queue offset the next page starts at.
+    pub next_offset: i64,
+    pub min_offset: i64,
+    pub max_offset: i64,
+}
+
+impl DlqMessagesBody {
+    pub fn has_more(&self) -> bool {
+        self.next_offset < self.max_offset
+    }
+}
+
+/// A dead letter queue message along with where it came from and how often it was consumed.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct DlqMessage {
+    pub queue_offset: i64,
+    pub msg_id: String,
+    /// Id of the message first sent by the producer.
+    pub origin_msg_id: Option<String>,
+    /// Topic the message was sent to before it was retried, the target of a resend.
+    pub original_topic: Option<String>,
+    pub reconsume_times: i32,
+    pub store_timestamp: i64,
+    pub born_timestamp: i64,
+    pub tags: Option<String>,
+    pub keys: Option<String>,
+    pub body_size: i32,
+}
@@ -21,4 +21,65 @@ use serde::Serialize;
 #[serde(rename_all = "camelCase")]
 pub struct GetConsumerListByGroupResponseBody {
     pub consumer_id_list: Vec<String>,
+    /// Consumers of the group that went offline recently, newest first. Only filled when the
+    /// group has no online consumer, to tell why a rebalance came out empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub offline_consumer_list: Vec<OfflineConsumerInfo>,
+}
+
+/// A consumer the broker saw leave its group.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct OfflineConsumerInfo {
+    pub client_id: String,
+    pub client_addr: String,
+    pub version: i32,
+    /// Last heartbeat of the consumer, in milliseconds.
+    pub last_update_timestamp: u64,
+    /// When the broker dropped the consumer, in milliseconds.
+    pub offline_timestamp: u64,
+    pub offline_reason: OfflineReason,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum OfflineReason {
+    /// The client unregistered itself on shutdown.
+    #[default]
+    Unregister,
+    /// No heartbeat came in within the channel expired timeout.
+    Expired,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::RemotingDeserializable;
+    use crate::protocol::RemotingSerializable;
+
+    #[test]
+    fn offline_consumer_list_is_omitted_when_empty() {
+        let body = GetConsumerListByGroupResponseBody {
+            consumer_id_list: vec!["127.0.0.1@1".to_string()],
+            offline_consumer_list: vec![],
+        };
+        assert_eq!(
+            String::from_utf8(body.encode()).unwrap(),
+            r#"{"consumerIdList":["127.0.0.1@1"]}"#
+        );
+
+        let body = GetConsumerListByGroupResponseBody {
+            consumer_id_list: vec![],
+            offline_consumer_list: vec![OfflineConsumerInfo {
+                client_id: "127.0.0.1@1".to_string(),
+                client_addr: "127.0.0.1:52110".to_string(),
+                version: 1,
+                last_update_timestamp: 100,
+                offline_timestamp: 200,
+                offline_reason: OfflineReason::Expired,
+            }],
+        };
+        let decoded = GetConsumerListByGroupResponseBody::decode(&body.encode()).unwrap();
+        assert_eq!(decoded.offline_consumer_list, body.offline_consumer_list);
+    }
 }
@@ -0,0 +1,105 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::collections::HashMap;
+
+use rocketmq_common::common::message::message_queue::MessageQueue;
+use serde::Deserialize;
+use serde::Serialize;
+use serde_json_any_key::*;
+
+/// Consume progress reported by clients for `GET_CONSUMER_STATUS_FROM_CLIENT`.
+///
+/// A single client fills `message_queue_table`; the broker aggregates the answers of every
+/// client of the group into `consumer_table`, keyed by client id.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct GetConsumerStatusBody {
+    #[serde(with = "any_key_map")]
+    pub message_queue_table: HashMap<MessageQueue, i64>,
+
+    #[serde(with = "consumer_table")]
+    pub consumer_table: HashMap<String, HashMap<MessageQueue, i64>>,
+}
+
+mod consumer_table {
+    use std::collections::HashMap;
+
+    use rocketmq_common::common::message::message_queue::MessageQueue;
+    use serde::Deserialize;
+    use serde::Deserializer;
+    use serde::Serialize;
+    use serde::Serializer;
+    use serde_json_any_key::any_key_map;
+
+    struct OffsetTableRef<'a>(&'a HashMap<MessageQueue, i64>);
+
+    impl Serialize for OffsetTableRef<'_> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            any_key_map::serialize(self.0, serializer)
+        }
+    }
+
+    #[derive(Deserialize)]
+    struct OffsetTable(#[serde(with = "any_key_map")] HashMap<MessageQueue, i64>);
+
+    pub fn serialize<S: Serializer>(
+        table: &HashMap<String, HashMap<MessageQueue, i64>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(
+            table
+                .iter()
+                .map(|(client_id, offsets)| (client_id, OffsetTableRef(offsets))),
+        )
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<HashMap<String, HashMap<MessageQueue, i64>>, D::Error> {
+        let table = HashMap::<String, OffsetTable>::deserialize(deserializer)?;
+        Ok(table
+            .into_iter()
+            .map(|(client_id, offsets)| (client_id, offsets.0))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::RemotingDeserializable;
+    use crate::protocol::RemotingSerializable;
+
+    #[test]
+    fn get_consumer_status_body_round_trip() {
+        let mq = MessageQueue::from_parts("topic", "broker-a", 1);
+        let mut body = GetConsumerStatusBody::default();
+        body.message_queue_table.insert(mq.clone(), 10);
+        body.consumer_table
+            .insert("client-1".to_string(), HashMap::from([(mq.clone(), 20)]));
+
+        let decoded = GetConsumerStatusBody::decode(body.encode().as_slice()).unwrap();
+        assert_eq!(decoded.message_queue_table.get(&mq), Some(&10));
+        assert_eq!(
+            decoded
+                .consumer_table
+                .get("client-1")
+                .and_then(|offsets| offsets.get(&mq)),
+            Some(&20)
+        );
+    }
+}
@@ -0,0 +1,39 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use serde::Deserialize;
+use serde::Serialize;
+
+/// Trace records of the trace topic matching a message id or key.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryMessageTraceBody {
+    pub records: Vec<MessageTraceRecord>,
+    /// Whether the broker stopped scanning before the end of the time window, because of the
+    /// record or scan limits.
+    pub truncated: bool,
+}
+
+/// A trace message, holding the encoded trace contexts one client sent in a batch.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageTraceRecord {
+    pub msg_id: String,
+    pub store_timestamp: i64,
+    /// Address of the client that sent the trace message, the client the traces are from.
+    pub born_host: String,
+    pub trace_data: String,
+}
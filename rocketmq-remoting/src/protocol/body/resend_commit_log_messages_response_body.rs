@@ -0,0 +1,41 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use serde::Deserialize;
+use serde::Serialize;
+
+/// Outcome of one resend request. A scan stops early at the resend or scan size limit, in
+/// which case `next_offset` is below the requested end and the caller continues from there.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ResendCommitLogMessagesResponseBody {
+    /// Messages of any topic read from the commit log.
+    pub scanned: u64,
+    /// Messages of the requested topic inside the time range.
+    pub matched: u64,
+    pub resent: u64,
+    pub failed: u64,
+    /// Physical offset the scan stopped at.
+    pub next_offset: i64,
+    /// Physical offset the scan was asked to end at.
+    pub end_offset: i64,
+}
+
+impl ResendCommitLogMessagesResponseBody {
+    pub fn is_finished(&self) -> bool {
+        self.next_offset >= self.end_offset
+    }
+}
@@ -14,9 +14,14 @@
  * See the License for the specific language governing permissions and
  * limitations under the License.
  */
+use bytes::Bytes;
+use rocketmq_common::common::compression::compression_type::CompressionType;
 use serde::Deserialize;
 use serde::Serialize;
 
+use crate::protocol::RemotingDeserializable;
+use crate::protocol::RemotingSerializable;
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct TopicList {
@@ -24,6 +29,29 @@ pub struct TopicList {
     pub broker_addr: Option<String>,
 }
 
+impl TopicList {
+    /// Response body carrying the list, zlib compressed when `compressed` is set. Lists of all
+    /// the topics of a large cluster get big, and compress well.
+    pub fn encode_body(&self, compressed: bool) -> Bytes {
+        let body = Bytes::from(self.encode());
+        if compressed {
+            CompressionType::Zlib.compression(&body)
+        } else {
+            body
+        }
+    }
+
+    /// Reads a body written by [`encode_body`](Self::encode_body), `None` if it is corrupt.
+    pub fn decode_body(body: &Bytes, compressed: bool) -> Option<TopicList> {
+        if compressed {
+            let body = CompressionType::Zlib.try_decompression(body).ok()?;
+            TopicList::decode(body.as_ref()).ok()
+        } else {
+            TopicList::decode(body.as_ref()).ok()
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -47,4 +75,26 @@ mod tests {
         );
         assert_eq!(topic_list.broker_addr, Some("broker1".to_string()));
     }
+
+    #[test]
+    fn topic_list_body_round_trip() {
+        let topic_list = TopicList {
+            topic_list: (0..100).map(|i| format!("topic{}", i)).collect(),
+            broker_addr: None,
+        };
+        let plain = topic_list.encode_body(false);
+        let compressed = topic_list.encode_body(true);
+        assert!(compressed.len() < plain.len());
+        assert_eq!(
+            TopicList::decode_body(&compressed, true)
+                .unwrap()
+                .topic_list,
+            topic_list.topic_list
+        );
+        assert_eq!(
+            TopicList::decode_body(&plain, false).unwrap().topic_list,
+            topic_list.topic_list
+        );
+        assert!(TopicList::decode_body(&plain, true).is_none());
+    }
 }
@@ -0,0 +1,28 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use bytes::BytesMut;
+
+/// Body of a command which is not held in a heap buffer, e.g. messages still in the page cache
+/// of the commit log. The codec copies it straight into the frame of the command, sparing the
+/// heap copy a body in [`Bytes`](bytes::Bytes) would need.
+pub trait FileRegion: Send + Sync + 'static {
+    /// Length in bytes of the region.
+    fn count(&self) -> usize;
+
+    /// Appends the whole region to `dst`.
+    fn transfer_to(&self, dst: &mut BytesMut);
+}
@@ -17,11 +17,16 @@
 pub mod broker;
 pub mod check_transaction_state_request_header;
 pub mod client_request_header;
+pub mod clone_group_offset_request_header;
+pub mod consume_message_directly_result_request_header;
+pub mod consumer_send_msg_back_request_header;
 pub mod create_topic_request_header;
 pub mod delete_topic_request_header;
+pub mod end_transaction_request_header;
 pub mod get_all_topic_config_response_header;
 pub mod get_consumer_listby_group_request_header;
 pub mod get_consumer_listby_group_response_header;
+pub mod get_earliest_msg_storetime_request_header;
 pub mod get_earliest_msg_storetime_response_header;
 pub mod get_max_offset_response_header;
 pub mod get_min_offset_response_header;
@@ -37,13 +42,23 @@ pub mod query_consumer_offset_response_header;
 pub mod query_message_request_header;
 pub mod query_message_response_header;
 
+pub mod get_commercial_stats_request_header;
 pub mod get_consumer_connection_list_request_header;
+pub mod get_consumer_running_info_request_header;
+pub mod get_consumer_status_request_header;
 pub mod get_max_offset_request_header;
 pub mod get_min_offset_request_header;
 pub mod heartbeat_request_header;
+pub mod notify_consumer_ids_changed_request_header;
+pub mod query_consume_time_span_request_header;
+pub mod query_dlq_messages_request_header;
+pub mod query_message_trace_request_header;
 pub mod query_topic_consume_by_who_request_header;
 pub mod query_topics_by_consumer_request_header;
 pub mod reply_message_request_header;
+pub mod resend_commit_log_messages_request_header;
+pub mod resend_dlq_messages_request_header;
+pub mod search_offset_request_header;
 pub mod search_offset_response_header;
 pub mod unregister_client_request_header;
 pub mod update_consumer_offset_header;
@@ -31,6 +31,11 @@ pub struct GetRouteInfoRequestHeader {
     #[serde(rename = "acceptStandardJsonOnly")]
     pub accept_standard_json_only: Option<bool>,
 
+    /// Version of the route the client already holds, the name server answers
+    /// `TopicRouteNotModified` without a body when it is still current.
+    #[serde(rename = "topicRouteVersion")]
+    pub topic_route_version: Option<String>,
+
     #[serde(flatten)]
     pub topic_request_header: Option<TopicRequestHeader>,
 }
@@ -38,6 +43,8 @@ pub struct GetRouteInfoRequestHeader {
 impl GetRouteInfoRequestHeader {
     const ACCEPT_STANDARD_JSON_ONLY: &'static str = "acceptStandardJsonOnly";
     const TOPIC: &'static str = "topic";
+    /// Request field and response ext field carrying the topic route version.
+    pub const TOPIC_ROUTE_VERSION: &'static str = "topicRouteVersion";
 
     pub fn new(topic: impl Into<String>, accept_standard_json_only: Option<bool>) -> Self {
         GetRouteInfoRequestHeader {
@@ -60,6 +67,9 @@ impl FromMap for GetRouteInfoRequestHeader {
             accept_standard_json_only: map
                 .get(GetRouteInfoRequestHeader::ACCEPT_STANDARD_JSON_ONLY)
                 .and_then(|s| s.parse::<bool>().ok()),
+            topic_route_version: map
+                .get(GetRouteInfoRequestHeader::TOPIC_ROUTE_VERSION)
+                .cloned(),
             topic_request_header: <TopicRequestHeader as FromMap>::from(map),
         })
     }
@@ -83,6 +93,12 @@ impl CommandCustomHeader for GetRouteInfoRequestHeader {
                 );
             }
         }
+        if let Some(topic_route_version) = &self.topic_route_version {
+            map.insert(
+                String::from(GetRouteInfoRequestHeader::TOPIC_ROUTE_VERSION),
+                topic_route_version.clone(),
+            );
+        }
         if let Some(topic_request_header) = &self.topic_request_header {
             if let Some(topic_request_header_map) = topic_request_header.to_map() {
                 map.extend(topic_request_header_map);
@@ -98,12 +114,14 @@ mod tests {
 
     use super::GetRouteInfoRequestHeader;
     use crate::protocol::command_custom_header::CommandCustomHeader;
+    use crate::protocol::command_custom_header::FromMap;
 
     #[test]
     fn test_to_map_no_accept_standard_json_only() {
         let request_header = GetRouteInfoRequestHeader {
             topic: "test".into(),
             accept_standard_json_only: None,
+            topic_route_version: None,
             topic_request_header: None,
         };
 
@@ -119,4 +137,18 @@ mod tests {
             ]))
         );
     }
+
+    #[test]
+    fn test_topic_route_version_round_trip() {
+        let request_header = GetRouteInfoRequestHeader {
+            topic: "test".into(),
+            topic_route_version: Some("1a2b".into()),
+            ..Default::default()
+        };
+
+        let map = request_header.to_map().unwrap();
+        assert_eq!(map.get("topicRouteVersion").unwrap(), "1a2b");
+        let decoded = <GetRouteInfoRequestHeader as FromMap>::from(&map).unwrap();
+        assert_eq!(decoded.topic_route_version.as_deref(), Some("1a2b"));
+    }
 }
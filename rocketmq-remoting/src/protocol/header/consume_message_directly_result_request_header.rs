@@ -0,0 +1,61 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use rocketmq_macros::RequestHeaderCodec;
+use serde::Deserialize;
+use serde::Serialize;
+
+#[derive(Debug, Clone, Serialize, Deserialize, RequestHeaderCodec, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConsumeMessageDirectlyResultRequestHeader {
+    pub consumer_group: String,
+    pub client_id: Option<String>,
+    pub msg_id: Option<String>,
+    pub broker_name: Option<String>,
+    pub topic: Option<String>,
+    pub topic_sys_flag: Option<i32>,
+    pub group_sys_flag: Option<i32>,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::protocol::command_custom_header::FromMap;
+
+    #[test]
+    fn consume_message_directly_result_request_header_from_map() {
+        let mut map = HashMap::new();
+        map.insert("consumerGroup".to_string(), "group".to_string());
+        map.insert("clientId".to_string(), "client".to_string());
+        map.insert(
+            "msgId".to_string(),
+            "7F0000010000000C0000000000000001".to_string(),
+        );
+        map.insert("topicSysFlag".to_string(), "1".to_string());
+        let header = <ConsumeMessageDirectlyResultRequestHeader as FromMap>::from(&map).unwrap();
+        assert_eq!(header.consumer_group, "group");
+        assert_eq!(header.client_id.as_deref(), Some("client"));
+        assert_eq!(
+            header.msg_id.as_deref(),
+            Some("7F0000010000000C0000000000000001")
+        );
+        assert_eq!(header.topic_sys_flag, Some(1));
+        assert_eq!(header.group_sys_flag, None);
+        assert_eq!(header.broker_name, None);
+    }
+}
@@ -0,0 +1,73 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use rocketmq_macros::RequestHeaderCodec;
+use serde::Deserialize;
+use serde::Serialize;
+
+#[derive(Debug, Clone, Serialize, Deserialize, RequestHeaderCodec, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConsumerSendMsgBackRequestHeader {
+    /// Commit log offset of the message being sent back.
+    pub offset: i64,
+    pub group: String,
+    /// `0` lets the broker escalate the level from the reconsume times, a negative level sends
+    /// the message to the DLQ directly.
+    pub delay_level: i32,
+    pub origin_msg_id: Option<String>,
+    pub origin_topic: Option<String>,
+    pub unit_mode: bool,
+    pub max_reconsume_times: Option<i32>,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::protocol::command_custom_header::CommandCustomHeader;
+    use crate::protocol::command_custom_header::FromMap;
+
+    #[test]
+    fn consumer_send_msg_back_request_header_round_trip() {
+        let header = ConsumerSendMsgBackRequestHeader {
+            offset: 1024,
+            group: "test_group".to_string(),
+            delay_level: 3,
+            origin_msg_id: Some("msg_id".to_string()),
+            origin_topic: None,
+            unit_mode: false,
+            max_reconsume_times: Some(16),
+        };
+        let map = header.to_map().unwrap();
+        assert_eq!(map.get("offset").unwrap(), "1024");
+        assert_eq!(map.get("delayLevel").unwrap(), "3");
+        assert_eq!(map.get("maxReconsumeTimes").unwrap(), "16");
+        assert!(!map.contains_key("originTopic"));
+
+        let mut map = HashMap::new();
+        map.insert("offset".to_string(), "7".to_string());
+        map.insert("group".to_string(), "g".to_string());
+        map.insert("delayLevel".to_string(), "-1".to_string());
+        map.insert("unitMode".to_string(), "true".to_string());
+        let decoded = <ConsumerSendMsgBackRequestHeader as FromMap>::from(&map).unwrap();
+        assert_eq!(decoded.offset, 7);
+        assert_eq!(decoded.group, "g");
+        assert_eq!(decoded.delay_level, -1);
+        assert!(decoded.unit_mode);
+        assert_eq!(decoded.max_reconsume_times, None);
+    }
+}
@@ -0,0 +1,154 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::collections::HashMap;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::protocol::command_custom_header::CommandCustomHeader;
+use crate::protocol::command_custom_header::FromMap;
+use crate::rpc::rpc_request_header::RpcRequestHeader;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct EndTransactionRequestHeader {
+    pub topic: Option<String>,
+    pub producer_group: String,
+    pub tran_state_table_offset: i64,
+    pub commit_log_offset: i64,
+    /// One of `MessageSysFlag::TRANSACTION_NOT_TYPE`, `TRANSACTION_COMMIT_TYPE` or
+    /// `TRANSACTION_ROLLBACK_TYPE`.
+    pub commit_or_rollback: i32,
+    pub from_transaction_check: bool,
+    pub msg_id: String,
+    pub transaction_id: Option<String>,
+    #[serde(flatten)]
+    pub rpc_request_header: Option<RpcRequestHeader>,
+}
+
+impl EndTransactionRequestHeader {
+    pub const TOPIC: &'static str = "topic";
+    pub const PRODUCER_GROUP: &'static str = "producerGroup";
+    pub const TRAN_STATE_TABLE_OFFSET: &'static str = "tranStateTableOffset";
+    pub const COMMIT_LOG_OFFSET: &'static str = "commitLogOffset";
+    pub const COMMIT_OR_ROLLBACK: &'static str = "commitOrRollback";
+    pub const FROM_TRANSACTION_CHECK: &'static str = "fromTransactionCheck";
+    pub const MSG_ID: &'static str = "msgId";
+    pub const TRANSACTION_ID: &'static str = "transactionId";
+}
+
+impl CommandCustomHeader for EndTransactionRequestHeader {
+    fn to_map(&self) -> Option<HashMap<String, String>> {
+        let mut map = HashMap::new();
+        if let Some(value) = self.topic.as_ref() {
+            map.insert(Self::TOPIC.to_string(), value.clone());
+        }
+        map.insert(
+            Self::PRODUCER_GROUP.to_string(),
+            self.producer_group.clone(),
+        );
+        map.insert(
+            Self::TRAN_STATE_TABLE_OFFSET.to_string(),
+            self.tran_state_table_offset.to_string(),
+        );
+        map.insert(
+            Self::COMMIT_LOG_OFFSET.to_string(),
+            self.commit_log_offset.to_string(),
+        );
+        map.insert(
+            Self::COMMIT_OR_ROLLBACK.to_string(),
+            self.commit_or_rollback.to_string(),
+        );
+        map.insert(
+            Self::FROM_TRANSACTION_CHECK.to_string(),
+            self.from_transaction_check.to_string(),
+        );
+        map.insert(Self::MSG_ID.to_string(), self.msg_id.clone());
+        if let Some(value) = self.transaction_id.as_ref() {
+            map.insert(Self::TRANSACTION_ID.to_string(), value.clone());
+        }
+        if let Some(value) = self.rpc_request_header.as_ref() {
+            if let Some(value) = value.to_map() {
+                map.extend(value);
+            }
+        }
+        Some(map)
+    }
+}
+
+impl FromMap for EndTransactionRequestHeader {
+    type Target = Self;
+
+    fn from(map: &HashMap<String, String>) -> Option<Self::Target> {
+        Some(EndTransactionRequestHeader {
+            topic: map.get(Self::TOPIC).cloned(),
+            producer_group: map.get(Self::PRODUCER_GROUP).cloned().unwrap_or_default(),
+            tran_state_table_offset: map
+                .get(Self::TRAN_STATE_TABLE_OFFSET)
+                .and_then(|v| v.parse().ok())
+                .unwrap_or_default(),
+            commit_log_offset: map
+                .get(Self::COMMIT_LOG_OFFSET)
+                .and_then(|v| v.parse().ok())
+                .unwrap_or_default(),
+            commit_or_rollback: map
+                .get(Self::COMMIT_OR_ROLLBACK)
+                .and_then(|v| v.parse().ok())
+                .unwrap_or_default(),
+            from_transaction_check: map
+                .get(Self::FROM_TRANSACTION_CHECK)
+                .and_then(|v| v.parse().ok())
+                .unwrap_or_default(),
+            msg_id: map.get(Self::MSG_ID).cloned().unwrap_or_default(),
+            transaction_id: map.get(Self::TRANSACTION_ID).cloned(),
+            rpc_request_header: <RpcRequestHeader as FromMap>::from(map),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn end_transaction_request_header_to_map_and_from_map() {
+        let header = EndTransactionRequestHeader {
+            topic: Some("test_topic".to_string()),
+            producer_group: "test_group".to_string(),
+            tran_state_table_offset: 10,
+            commit_log_offset: 1024,
+            commit_or_rollback: 8,
+            from_transaction_check: true,
+            msg_id: "msg_id".to_string(),
+            transaction_id: Some("transaction_id".to_string()),
+            rpc_request_header: None,
+        };
+        let map = header.to_map().unwrap();
+        assert_eq!(
+            map.get(EndTransactionRequestHeader::FROM_TRANSACTION_CHECK),
+            Some(&"true".to_string())
+        );
+
+        let decoded = <EndTransactionRequestHeader as FromMap>::from(&map).unwrap();
+        assert_eq!(decoded.topic, header.topic);
+        assert_eq!(decoded.producer_group, "test_group");
+        assert_eq!(decoded.commit_log_offset, 1024);
+        assert_eq!(decoded.commit_or_rollback, 8);
+        assert!(decoded.from_transaction_check);
+        assert_eq!(decoded.transaction_id, header.transaction_id);
+    }
+}
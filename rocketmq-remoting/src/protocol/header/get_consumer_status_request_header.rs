@@ -0,0 +1,60 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use rocketmq_macros::RequestHeaderCodec;
+use serde::Deserialize;
+use serde::Serialize;
+
+/// Header shared by `INVOKE_BROKER_TO_GET_CONSUMER_STATUS` (admin -> broker) and
+/// `GET_CONSUMER_STATUS_FROM_CLIENT` (broker -> client).
+#[derive(Debug, Clone, Serialize, Deserialize, RequestHeaderCodec, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct GetConsumerStatusRequestHeader {
+    pub topic: String,
+    pub group: String,
+    pub client_addr: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::protocol::command_custom_header::CommandCustomHeader;
+    use crate::protocol::command_custom_header::FromMap;
+
+    #[test]
+    fn get_consumer_status_request_header_round_trip() {
+        let header = GetConsumerStatusRequestHeader {
+            topic: "test_topic".to_string(),
+            group: "test_group".to_string(),
+            client_addr: None,
+        };
+        let map = header.to_map().unwrap();
+        assert_eq!(map.get("topic").unwrap(), "test_topic");
+        assert_eq!(map.get("group").unwrap(), "test_group");
+        assert!(!map.contains_key("clientAddr"));
+
+        let mut map = HashMap::new();
+        map.insert("topic".to_string(), "t".to_string());
+        map.insert("group".to_string(), "g".to_string());
+        map.insert("clientAddr".to_string(), "client-1".to_string());
+        let decoded = <GetConsumerStatusRequestHeader as FromMap>::from(&map).unwrap();
+        assert_eq!(decoded.topic, "t");
+        assert_eq!(decoded.group, "g");
+        assert_eq!(decoded.client_addr.as_deref(), Some("client-1"));
+    }
+}
@@ -61,6 +61,11 @@ pub struct RegisterBrokerRequestHeader {
     /// The CRC32 checksum for the message body.
     #[serde(rename = "bodyCrc32")]
     pub body_crc32: u32,
+
+    /// Indicates whether the body only carries the topic configs changed since the last
+    /// registration, instead of all of them.
+    #[serde(default)]
+    pub incremental: bool,
 }
 
 impl RegisterBrokerRequestHeader {
@@ -73,6 +78,7 @@ impl RegisterBrokerRequestHeader {
     const ENABLE_ACTING_MASTER: &'static str = "enableActingMaster";
     const HA_SERVER_ADDR: &'static str = "haServerAddr";
     const HEARTBEAT_TIMEOUT_MILLIS: &'static str = "heartbeatTimeoutMillis";
+    const INCREMENTAL: &'static str = "incremental";
 
     /// Creates a new instance of `RegisterBrokerRequestHeader`.
     ///
@@ -88,6 +94,7 @@ impl RegisterBrokerRequestHeader {
     ///   enabled.
     /// * `compressed` - Indicates whether the data is compressed.
     /// * `body_crc32` - The CRC32 checksum for the message body.
+    /// * `incremental` - Indicates whether only the changed topic configs are registered.
     ///
     /// # Returns
     ///
@@ -102,6 +109,7 @@ impl RegisterBrokerRequestHeader {
         enable_acting_master: Option<bool>,
         compressed: bool,
         body_crc32: u32,
+        incremental: bool,
     ) -> Self {
         RegisterBrokerRequestHeader {
             broker_name,
@@ -113,6 +121,7 @@ impl RegisterBrokerRequestHeader {
             enable_acting_master,
             compressed,
             body_crc32,
+            incremental,
         }
     }
 }
@@ -156,6 +165,10 @@ impl FromMap for RegisterBrokerRequestHeader {
                 .get(RegisterBrokerRequestHeader::BODY_CRC32)
                 .and_then(|s| s.parse::<u32>().ok())
                 .unwrap_or(0),
+            incremental: map
+                .get(RegisterBrokerRequestHeader::INCREMENTAL)
+                .and_then(|s| s.parse::<bool>().ok())
+                .unwrap_or(false),
         })
     }
 }
@@ -211,6 +224,12 @@ impl CommandCustomHeader for RegisterBrokerRequestHeader {
             RegisterBrokerRequestHeader::BODY_CRC32.to_string(),
             self.body_crc32.to_string(),
         );
+        if self.incremental {
+            map.insert(
+                RegisterBrokerRequestHeader::INCREMENTAL.to_string(),
+                self.incremental.to_string(),
+            );
+        }
 
         Some(map)
     }
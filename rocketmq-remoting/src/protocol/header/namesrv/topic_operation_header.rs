@@ -108,6 +108,8 @@ impl FromMap for RegisterTopicRequestHeader {
 #[derive(Debug, Clone, Deserialize, Serialize, Default)]
 pub struct GetTopicsByClusterRequestHeader {
     pub cluster: String,
+    /// Asks for a compressed body, see [`GetTopicListRequestHeader::compressed`].
+    pub compressed: Option<bool>,
 }
 
 impl GetTopicsByClusterRequestHeader {
@@ -116,13 +118,20 @@ impl GetTopicsByClusterRequestHeader {
     pub fn new(cluster: impl Into<String>) -> Self {
         Self {
             cluster: cluster.into(),
+            compressed: None,
         }
     }
 }
 
 impl CommandCustomHeader for GetTopicsByClusterRequestHeader {
     fn to_map(&self) -> Option<HashMap<String, String>> {
-        let map = HashMap::from([(Self::CLUSTER.to_string(), self.cluster.clone())]);
+        let mut map = HashMap::from([(Self::CLUSTER.to_string(), self.cluster.clone())]);
+        if let Some(compressed) = self.compressed {
+            map.insert(
+                GetTopicListRequestHeader::COMPRESSED.to_string(),
+                compressed.to_string(),
+            );
+        }
         Some(map)
     }
 }
@@ -133,6 +142,52 @@ impl FromMap for GetTopicsByClusterRequestHeader {
     fn from(map: &HashMap<String, String>) -> Option<Self::Target> {
         Some(GetTopicsByClusterRequestHeader {
             cluster: map.get(Self::CLUSTER).cloned().unwrap_or_default(),
+            compressed: map
+                .get(GetTopicListRequestHeader::COMPRESSED)
+                .and_then(|s| s.parse::<bool>().ok()),
+        })
+    }
+}
+
+/// Optional header of the requests for a topic list that take no argument, e.g.
+/// `GET_ALL_TOPIC_LIST_FROM_NAMESERVER`.
+#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+pub struct GetTopicListRequestHeader {
+    /// Asks for a zlib compressed body. A name server which compressed the body marks the
+    /// response with the same ext field, older ones ignore it and answer plain JSON.
+    pub compressed: Option<bool>,
+}
+
+impl GetTopicListRequestHeader {
+    /// Request field and response ext field telling the body is compressed.
+    pub const COMPRESSED: &'static str = "compressed";
+
+    pub fn new(compressed: bool) -> Self {
+        Self {
+            compressed: Some(compressed),
+        }
+    }
+}
+
+impl CommandCustomHeader for GetTopicListRequestHeader {
+    fn to_map(&self) -> Option<HashMap<String, String>> {
+        let map = self
+            .compressed
+            .map(|compressed| (Self::COMPRESSED.to_string(), compressed.to_string()))
+            .into_iter()
+            .collect();
+        Some(map)
+    }
+}
+
+impl FromMap for GetTopicListRequestHeader {
+    type Target = Self;
+
+    fn from(map: &HashMap<String, String>) -> Option<Self::Target> {
+        Some(GetTopicListRequestHeader {
+            compressed: map
+                .get(Self::COMPRESSED)
+                .and_then(|s| s.parse::<bool>().ok()),
         })
     }
 }
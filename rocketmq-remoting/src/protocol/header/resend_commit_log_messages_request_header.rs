@@ -0,0 +1,36 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use rocketmq_macros::RequestHeaderCodec;
+use serde::Deserialize;
+use serde::Serialize;
+
+/// Asks the broker to republish the messages of `topic` stored in a commit log range.
+///
+/// The range is given by physical offsets, store timestamps or both; bounds which are absent
+/// default to the whole commit log. Messages go to `target_topic`, or back to `topic` when it
+/// is absent, and at most `max_count` of them are resent per request.
+#[derive(Debug, Clone, Serialize, Deserialize, RequestHeaderCodec, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ResendCommitLogMessagesRequestHeader {
+    pub topic: String,
+    pub target_topic: Option<String>,
+    pub begin_offset: Option<i64>,
+    pub end_offset: Option<i64>,
+    pub begin_timestamp: Option<i64>,
+    pub end_timestamp: Option<i64>,
+    pub max_count: Option<i32>,
+}
@@ -0,0 +1,54 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use rocketmq_macros::RequestHeaderCodec;
+use serde::Deserialize;
+use serde::Serialize;
+
+/// Asks the broker to send the dead letter queue messages of `consumer_group` at the given
+/// consume queue offsets back to the topics they were originally sent to.
+#[derive(Debug, Clone, Serialize, Deserialize, RequestHeaderCodec, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ResendDlqMessagesRequestHeader {
+    pub consumer_group: String,
+    pub queue_id: Option<i32>,
+    /// Comma separated consume queue offsets of the messages to resend.
+    pub offsets: String,
+}
+
+impl ResendDlqMessagesRequestHeader {
+    /// The requested offsets, entries which are not numbers are ignored.
+    pub fn offsets(&self) -> Vec<i64> {
+        self.offsets
+            .split(',')
+            .filter_map(|offset| offset.trim().parse().ok())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn offsets_skip_blank_and_invalid_entries() {
+        let header = ResendDlqMessagesRequestHeader {
+            offsets: "3, 5,,x,8".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(header.offsets(), vec![3, 5, 8]);
+    }
+}
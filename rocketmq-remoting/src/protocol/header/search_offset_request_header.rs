@@ -0,0 +1,230 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::collections::HashMap;
+
+use rocketmq_common::common::boundary_type::BoundaryType;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::protocol::command_custom_header::CommandCustomHeader;
+use crate::protocol::command_custom_header::FromMap;
+use crate::protocol::header::message_operation_header::TopicRequestHeaderTrait;
+use crate::rpc::topic_request_header::TopicRequestHeader;
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchOffsetRequestHeader {
+    pub topic: String,
+
+    pub queue_id: i32,
+
+    pub timestamp: i64,
+
+    pub boundary_type: Option<BoundaryType>,
+
+    #[serde(flatten)]
+    pub topic_request_header: Option<TopicRequestHeader>,
+}
+
+impl SearchOffsetRequestHeader {
+    pub const TOPIC: &'static str = "topic";
+    pub const QUEUE_ID: &'static str = "queueId";
+    pub const TIMESTAMP: &'static str = "timestamp";
+    pub const BOUNDARY_TYPE: &'static str = "boundaryType";
+}
+
+impl CommandCustomHeader for SearchOffsetRequestHeader {
+    fn to_map(&self) -> Option<HashMap<String, String>> {
+        let mut map = HashMap::new();
+        map.insert(Self::TOPIC.to_string(), self.topic.clone());
+        map.insert(Self::QUEUE_ID.to_string(), self.queue_id.to_string());
+        map.insert(Self::TIMESTAMP.to_string(), self.timestamp.to_string());
+        if let Some(boundary_type) = &self.boundary_type {
+            map.insert(
+                Self::BOUNDARY_TYPE.to_string(),
+                boundary_type.get_name().to_string(),
+            );
+        }
+        if let Some(topic_request_header) = &self.topic_request_header {
+            if let Some(topic_request_header_map) = topic_request_header.to_map() {
+                map.extend(topic_request_header_map);
+            }
+        }
+        Some(map)
+    }
+}
+
+impl FromMap for SearchOffsetRequestHeader {
+    type Target = Self;
+
+    fn from(map: &HashMap<String, String>) -> Option<Self::Target> {
+        Some(SearchOffsetRequestHeader {
+            topic: map.get(Self::TOPIC).cloned().unwrap_or_default(),
+            queue_id: map
+                .get(Self::QUEUE_ID)
+                .and_then(|s| s.parse().ok())
+                .unwrap_or_default(),
+            timestamp: map
+                .get(Self::TIMESTAMP)
+                .and_then(|s| s.parse().ok())
+                .unwrap_or_default(),
+            boundary_type: map
+                .get(Self::BOUNDARY_TYPE)
+                .and_then(|s| BoundaryType::get_type(s)),
+            topic_request_header: <TopicRequestHeader as FromMap>::from(map),
+        })
+    }
+}
+
+impl TopicRequestHeaderTrait for SearchOffsetRequestHeader {
+    fn set_lo(&mut self, lo: Option<bool>) {
+        self.topic_request_header.as_mut().unwrap().lo = lo;
+    }
+
+    fn lo(&self) -> Option<bool> {
+        self.topic_request_header.as_ref().unwrap().lo
+    }
+
+    fn set_topic(&mut self, topic: String) {
+        self.topic = topic;
+    }
+
+    fn topic(&self) -> &str {
+        self.topic.as_str()
+    }
+
+    fn broker_name(&self) -> Option<&str> {
+        self.topic_request_header
+            .as_ref()
+            .unwrap()
+            .rpc_request_header
+            .as_ref()
+            .unwrap()
+            .broker_name
+            .as_deref()
+    }
+
+    fn set_broker_name(&mut self, broker_name: String) {
+        self.topic_request_header
+            .as_mut()
+            .unwrap()
+            .rpc_request_header
+            .as_mut()
+            .unwrap()
+            .broker_name = Some(broker_name);
+    }
+
+    fn namespace(&self) -> Option<&str> {
+        self.topic_request_header
+            .as_ref()
+            .unwrap()
+            .rpc_request_header
+            .as_ref()
+            .unwrap()
+            .namespace
+            .as_deref()
+    }
+
+    fn set_namespace(&mut self, namespace: String) {
+        self.topic_request_header
+            .as_mut()
+            .unwrap()
+            .rpc_request_header
+            .as_mut()
+            .unwrap()
+            .namespace = Some(namespace);
+    }
+
+    fn namespaced(&self) -> Option<bool> {
+        self.topic_request_header
+            .as_ref()
+            .unwrap()
+            .rpc_request_header
+            .as_ref()
+            .unwrap()
+            .namespaced
+    }
+
+    fn set_namespaced(&mut self, namespaced: bool) {
+        self.topic_request_header
+            .as_mut()
+            .unwrap()
+            .rpc_request_header
+            .as_mut()
+            .unwrap()
+            .namespaced = Some(namespaced);
+    }
+
+    fn oneway(&self) -> Option<bool> {
+        self.topic_request_header
+            .as_ref()
+            .unwrap()
+            .rpc_request_header
+            .as_ref()
+            .unwrap()
+            .oneway
+    }
+
+    fn set_oneway(&mut self, oneway: bool) {
+        self.topic_request_header
+            .as_mut()
+            .unwrap()
+            .rpc_request_header
+            .as_mut()
+            .unwrap()
+            .oneway = Some(oneway);
+    }
+
+    fn queue_id(&self) -> Option<i32> {
+        Some(self.queue_id)
+    }
+
+    fn set_queue_id(&mut self, queue_id: Option<i32>) {
+        self.queue_id = queue_id.unwrap_or_default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn search_offset_request_header_to_map_and_from_map() {
+        let header = SearchOffsetRequestHeader {
+            topic: "test_topic".to_string(),
+            queue_id: 2,
+            timestamp: 1700000000000,
+            boundary_type: Some(BoundaryType::Upper),
+            topic_request_header: None,
+        };
+        let map = header.to_map().unwrap();
+        assert_eq!(
+            map.get(SearchOffsetRequestHeader::TIMESTAMP),
+            Some(&"1700000000000".to_string())
+        );
+        assert_eq!(
+            map.get(SearchOffsetRequestHeader::BOUNDARY_TYPE),
+            Some(&"upper".to_string())
+        );
+
+        let decoded = <SearchOffsetRequestHeader as FromMap>::from(&map).unwrap();
+        assert_eq!(decoded.topic, "test_topic");
+        assert_eq!(decoded.queue_id, 2);
+        assert_eq!(decoded.timestamp, 1700000000000);
+        assert_eq!(decoded.boundary_type, Some(BoundaryType::Upper));
+    }
+}
@@ -42,6 +42,7 @@ use crate::code::response_code::RemotingSysResponseCode;
 use crate::error::Error;
 use crate::protocol::command_custom_header::CommandCustomHeader;
 use crate::protocol::command_custom_header::FromMap;
+use crate::protocol::file_region::FileRegion;
 use crate::protocol::LanguageCode;
 use crate::rocketmq_serializable::RocketMQSerializable;
 
@@ -67,7 +68,7 @@ lazy_static! {
 
 fn set_cmd_version(cmd: &mut RemotingCommand) {
     INIT.call_once(|| {
-        let v = match std::env::var("REMOTING_VERSION_KEY") {
+        let v = match std::env::var(REMOTING_VERSION_KEY) {
             Ok(value) => value
                 .parse::<i32>()
                 .unwrap_or(i32::from(RocketMqVersion::V500)),
@@ -80,7 +81,7 @@ fn set_cmd_version(cmd: &mut RemotingCommand) {
 
     if config_version >= 0 {
         cmd.set_version_ref(config_version);
-    } else if let Ok(v) = std::env::var("rocketmq.remoting.version") {
+    } else if let Ok(v) = std::env::var(REMOTING_VERSION_KEY) {
         if let Ok(value) = v.parse::<i32>() {
             cmd.set_version_ref(value);
             *CONFIG_VERSION.write().unwrap() = value;
@@ -108,6 +109,9 @@ pub struct RemotingCommand {
 
     #[serde(skip)]
     body: Option<Bytes>,
+    /// Body written after `body` straight from its source when the command is encoded.
+    #[serde(skip)]
+    body_region: Option<Arc<dyn FileRegion>>,
     #[serde(skip)]
     suspended: bool,
     #[serde(skip)]
@@ -128,6 +132,7 @@ impl Clone for RemotingCommand {
             remark: self.remark.clone(),
             ext_fields: self.ext_fields.clone(),
             body: self.body.clone(),
+            body_region: self.body_region.clone(),
             suspended: self.suspended,
             command_custom_header: self.command_custom_header.clone(),
             serialize_type: self.serialize_type,
@@ -165,6 +170,7 @@ impl Default for RemotingCommand {
             remark: None,
             ext_fields: None,
             body: None,
+            body_region: None,
             suspended: false,
             command_custom_header: None,
             serialize_type: *SERIALIZE_TYPE_CONFIG_IN_THIS_SERVER,
@@ -400,7 +406,7 @@ impl RemotingCommand {
                     }
                 };
                 let header_length = header.as_ref().map_or(0, |h| h.len()) as i32;
-                let body_length = self.body.as_ref().map_or(0, |b| b.len()) as i32;
+                let body_length = self.body_length() as i32;
                 let total_length = 4 + header_length + body_length;
 
                 dst.reserve((total_length + 4) as usize);
@@ -422,7 +428,7 @@ impl RemotingCommand {
                     }
                 }
                 let header_size = RocketMQSerializable::rocketmq_protocol_encode(self, dst);
-                let body_length = self.body.as_ref().map_or(0, |b| b.len()) as i32;
+                let body_length = self.body_length() as i32;
                 let serialize_type = RemotingCommand::mark_serialize_type(
                     header_size as i32,
                     SerializeType::ROCKETMQ,
@@ -506,6 +512,25 @@ impl RemotingCommand {
         }
     }
 
+    /// Sends `body_region` as the body, after `body` if both are set.
+    pub fn set_body_region(mut self, body_region: Option<Arc<dyn FileRegion>>) -> Self {
+        self.body_region = body_region;
+        self
+    }
+
+    pub fn body_region(&self) -> Option<&Arc<dyn FileRegion>> {
+        self.body_region.as_ref()
+    }
+
+    /// Length of the encoded body, `body` plus `body_region`.
+    pub fn body_length(&self) -> usize {
+        self.body.as_ref().map_or(0, |body| body.len())
+            + self
+                .body_region
+                .as_ref()
+                .map_or(0, |body_region| body_region.count())
+    }
+
     pub fn get_body(&self) -> Option<&Bytes> {
         self.body.as_ref()
     }
@@ -609,9 +634,9 @@ impl RemotingCommand {
     }
 
     pub fn add_ext_field(&mut self, key: impl Into<String>, value: impl Into<String>) -> &mut Self {
-        if let Some(ref mut ext) = self.ext_fields {
-            ext.insert(key.into(), value.into());
-        }
+        self.ext_fields
+            .get_or_insert_with(HashMap::new)
+            .insert(key.into(), value.into());
         self
     }
 
@@ -57,12 +57,60 @@ impl TopicQueueMappingDetail {
     pub fn clone_as_mapping_info(
         mapping_detail: &TopicQueueMappingDetail,
     ) -> TopicQueueMappingInfo {
+        let info = &mapping_detail.topic_queue_mapping_info;
         TopicQueueMappingInfo {
-            topic: mapping_detail.topic_queue_mapping_info.topic.clone(),
-            total_queues: mapping_detail.topic_queue_mapping_info.total_queues,
-            bname: mapping_detail.topic_queue_mapping_info.bname.clone(),
-            epoch: mapping_detail.topic_queue_mapping_info.epoch,
-            ..TopicQueueMappingInfo::default()
+            topic: info.topic.clone(),
+            scope: info.scope.clone(),
+            total_queues: info.total_queues,
+            bname: info.bname.clone(),
+            epoch: info.epoch,
+            dirty: info.dirty,
+            curr_id_map: Some(Self::build_leader_id_map(mapping_detail)),
         }
     }
+
+    /// Maps the global id of every logical queue whose latest item is on this broker to the
+    /// physical queue id it is written to.
+    pub fn build_leader_id_map(mapping_detail: &TopicQueueMappingDetail) -> HashMap<i32, i32> {
+        let bname = mapping_detail.topic_queue_mapping_info.bname.as_ref();
+        mapping_detail
+            .hosted_queues
+            .iter()
+            .flatten()
+            .filter_map(|(global_id, items)| {
+                let leader_item = items.last()?;
+                (leader_item.bname.as_ref() == bname).then_some((*global_id, leader_item.queue_id))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mapping_info_only_maps_the_queues_led_by_the_broker() {
+        let item = |bname: &str, queue_id| LogicQueueMappingItem {
+            bname: Some(bname.to_string()),
+            queue_id,
+            ..Default::default()
+        };
+        let mapping_detail = TopicQueueMappingDetail {
+            topic_queue_mapping_info: TopicQueueMappingInfo::new(
+                "TopicTest".to_string(),
+                3,
+                "broker-a".to_string(),
+                7,
+            ),
+            hosted_queues: Some(HashMap::from([
+                (0, vec![item("broker-a", 0)]),
+                (1, vec![item("broker-b", 0), item("broker-a", 1)]),
+                (2, vec![item("broker-a", 2), item("broker-b", 1)]),
+            ])),
+        };
+        let info = TopicQueueMappingDetail::clone_as_mapping_info(&mapping_detail);
+        assert_eq!(info.epoch, 7);
+        assert_eq!(info.curr_id_map, Some(HashMap::from([(0, 0), (1, 1)])));
+    }
 }
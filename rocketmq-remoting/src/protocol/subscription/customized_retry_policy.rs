@@ -25,6 +25,12 @@ pub struct CustomizedRetryPolicy {
 }
 
 impl CustomizedRetryPolicy {
+    /// Policy retrying after `next[reconsume_times + 2]` milliseconds, the last delay repeating
+    /// once the ladder is exhausted, the same as the delay levels starting at level 3.
+    pub fn new(next: Vec<i64>) -> Self {
+        CustomizedRetryPolicy { next }
+    }
+
     pub fn next(&self) -> &[i64] {
         &self.next
     }
@@ -28,6 +28,7 @@ pub struct GroupRetryPolicy {
     type_: GroupRetryPolicyType,
     exponential_retry_policy: Option<ExponentialRetryPolicy>,
     customized_retry_policy: Option<CustomizedRetryPolicy>,
+    #[serde(skip)]
     default_retry_policy: CustomizedRetryPolicy,
 }
 
@@ -73,6 +74,44 @@ impl GroupRetryPolicy {
         self.customized_retry_policy = customized_retry_policy;
     }
 
+    /// Policy set for the type of the group, `None` when the group did not set one and keeps
+    /// retrying along the delay levels of the broker.
+    pub fn configured_retry_policy(&self) -> Option<&dyn RetryPolicy> {
+        match self.type_ {
+            GroupRetryPolicyType::Exponential => self
+                .exponential_retry_policy
+                .as_ref()
+                .map(|p| p as &dyn RetryPolicy),
+            GroupRetryPolicyType::Customized => self
+                .customized_retry_policy
+                .as_ref()
+                .map(|p| p as &dyn RetryPolicy),
+        }
+    }
+
+    /// Rejects policies which cannot give a delay, e.g. an empty ladder or a multiplier of 0.
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(policy) = &self.customized_retry_policy {
+            if policy.next().is_empty() || policy.next().iter().any(|delay| *delay <= 0) {
+                return Err(format!(
+                    "the delays of the customized retry policy must be positive, {:?}",
+                    policy.next()
+                ));
+            }
+        }
+        if let Some(policy) = &self.exponential_retry_policy {
+            if policy.initial() == 0 || policy.multiplier() == 0 || policy.max() < policy.initial()
+            {
+                return Err(format!(
+                    "the exponential retry policy needs initial > 0, multiplier > 0 and max >= \
+                     initial, {:?}",
+                    policy
+                ));
+            }
+        }
+        Ok(())
+    }
+
     pub fn get_retry_policy(&self) -> &dyn RetryPolicy {
         match self.type_ {
             GroupRetryPolicyType::Exponential => self
@@ -88,3 +127,45 @@ impl GroupRetryPolicy {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn configured_retry_policy_follows_the_type() {
+        let mut policy = GroupRetryPolicy::default();
+        assert!(policy.configured_retry_policy().is_none());
+        assert!(policy.validate().is_ok());
+
+        policy.set_customized_retry_policy(Some(CustomizedRetryPolicy::new(vec![
+            1_000, 2_000, 3_000,
+        ])));
+        assert_eq!(
+            policy
+                .configured_retry_policy()
+                .unwrap()
+                .next_delay_duration(0),
+            3_000
+        );
+        policy.set_type_(GroupRetryPolicyType::Exponential);
+        assert!(policy.configured_retry_policy().is_none());
+
+        policy.set_exponential_retry_policy(Some(ExponentialRetryPolicy::new(0, 1_000, 2)));
+        assert!(policy.validate().is_err());
+        policy.set_exponential_retry_policy(Some(ExponentialRetryPolicy::new(500, 1_000, 2)));
+        assert!(policy.validate().is_ok());
+        assert_eq!(
+            policy
+                .configured_retry_policy()
+                .unwrap()
+                .next_delay_duration(1),
+            1_000
+        );
+
+        let json = serde_json::to_string(&policy).unwrap();
+        assert!(json.contains("\"type\":\"EXPONENTIAL\""));
+        let decoded: GroupRetryPolicy = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.type_(), GroupRetryPolicyType::Exponential);
+    }
+}
@@ -17,6 +17,7 @@
 
 use crate::remoting::RemotingService;
 
+pub mod flow_control;
 pub mod server;
 
 pub trait RemotingServer: RemotingService {
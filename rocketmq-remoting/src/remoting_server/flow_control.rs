@@ -0,0 +1,213 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::collections::HashMap;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Instant;
+
+use rocketmq_common::common::server::config::RequestCodeLimit;
+use tokio::sync::OwnedSemaphorePermit;
+use tokio::sync::Semaphore;
+
+/// Requests of one request code accepted and rejected by a [`RequestFlowController`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RequestFlowStats {
+    pub accepted: u64,
+    pub rejected_by_qps: u64,
+    pub rejected_by_concurrency: u64,
+}
+
+/// Why a request was rejected by a [`RequestFlowController`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlowControlRejection {
+    Qps(u32),
+    Concurrency(u32),
+}
+
+/// Admission of a request, holds the concurrency slot of its request code until dropped.
+#[derive(Debug)]
+pub struct RequestPermit {
+    _permit: Option<OwnedSemaphorePermit>,
+}
+
+/// Caps the QPS and concurrency of the requests with some request codes, e.g.
+/// `GET_ROUTEINFO_BY_TOPIC` on a name server, so that a storm of clients cannot take the
+/// control plane down. Requests with other codes are always accepted.
+#[derive(Debug, Default)]
+pub struct RequestFlowController {
+    limiters: HashMap<i32, RequestCodeLimiter>,
+}
+
+#[derive(Debug)]
+struct RequestCodeLimiter {
+    limit: RequestCodeLimit,
+    /// Tokens left and when they were last refilled.
+    bucket: parking_lot::Mutex<(f64, Instant)>,
+    concurrency: Arc<Semaphore>,
+    accepted: AtomicU64,
+    rejected_by_qps: AtomicU64,
+    rejected_by_concurrency: AtomicU64,
+}
+
+impl RequestFlowController {
+    pub fn new(limits: &[RequestCodeLimit]) -> Self {
+        let now = Instant::now();
+        let limiters = limits
+            .iter()
+            .filter(|limit| limit.max_qps > 0 || limit.max_concurrency > 0)
+            .map(|limit| {
+                let limiter = RequestCodeLimiter {
+                    limit: limit.clone(),
+                    bucket: parking_lot::Mutex::new((limit.max_qps as f64, now)),
+                    concurrency: Arc::new(Semaphore::new(limit.max_concurrency as usize)),
+                    accepted: AtomicU64::new(0),
+                    rejected_by_qps: AtomicU64::new(0),
+                    rejected_by_concurrency: AtomicU64::new(0),
+                };
+                (limit.code, limiter)
+            })
+            .collect();
+        Self { limiters }
+    }
+
+    /// Admits a request with request code `code`, or tells which limit it is over.
+    pub fn try_acquire(&self, code: i32) -> Result<RequestPermit, FlowControlRejection> {
+        let Some(limiter) = self.limiters.get(&code) else {
+            return Ok(RequestPermit { _permit: None });
+        };
+        let permit = if limiter.limit.max_concurrency > 0 {
+            match limiter.concurrency.clone().try_acquire_owned() {
+                Ok(permit) => Some(permit),
+                Err(_) => {
+                    limiter
+                        .rejected_by_concurrency
+                        .fetch_add(1, Ordering::Relaxed);
+                    return Err(FlowControlRejection::Concurrency(
+                        limiter.limit.max_concurrency,
+                    ));
+                }
+            }
+        } else {
+            None
+        };
+        if limiter.limit.max_qps > 0 && !limiter.take_token(Instant::now()) {
+            limiter.rejected_by_qps.fetch_add(1, Ordering::Relaxed);
+            return Err(FlowControlRejection::Qps(limiter.limit.max_qps));
+        }
+        limiter.accepted.fetch_add(1, Ordering::Relaxed);
+        Ok(RequestPermit { _permit: permit })
+    }
+
+    /// Requests accepted and rejected so far, by request code.
+    pub fn stats(&self) -> HashMap<i32, RequestFlowStats> {
+        self.limiters
+            .iter()
+            .map(|(code, limiter)| {
+                let stats = RequestFlowStats {
+                    accepted: limiter.accepted.load(Ordering::Relaxed),
+                    rejected_by_qps: limiter.rejected_by_qps.load(Ordering::Relaxed),
+                    rejected_by_concurrency: limiter
+                        .rejected_by_concurrency
+                        .load(Ordering::Relaxed),
+                };
+                (*code, stats)
+            })
+            .collect()
+    }
+}
+
+impl RequestCodeLimiter {
+    fn take_token(&self, now: Instant) -> bool {
+        let max_qps = self.limit.max_qps as f64;
+        let mut bucket = self.bucket.lock();
+        let (tokens, last_refill) = *bucket;
+        let tokens = (tokens + now.saturating_duration_since(last_refill).as_secs_f64() * max_qps)
+            .min(max_qps);
+        if tokens < 1.0 {
+            *bucket = (tokens, now);
+            return false;
+        }
+        *bucket = (tokens - 1.0, now);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn requests_over_the_limits_of_their_code_are_rejected() {
+        let controller = RequestFlowController::new(&[
+            RequestCodeLimit {
+                code: 105,
+                max_qps: 2,
+                max_concurrency: 0,
+            },
+            RequestCodeLimit {
+                code: 12,
+                max_qps: 0,
+                max_concurrency: 1,
+            },
+        ]);
+
+        assert!(controller.try_acquire(105).is_ok());
+        assert!(controller.try_acquire(105).is_ok());
+        assert_eq!(
+            controller.try_acquire(105).unwrap_err(),
+            FlowControlRejection::Qps(2)
+        );
+
+        let permit = controller.try_acquire(12).unwrap();
+        assert_eq!(
+            controller.try_acquire(12).unwrap_err(),
+            FlowControlRejection::Concurrency(1)
+        );
+        drop(permit);
+        assert!(controller.try_acquire(12).is_ok());
+
+        for _ in 0..10 {
+            assert!(controller.try_acquire(10).is_ok());
+        }
+
+        let stats = controller.stats();
+        assert_eq!(stats[&105].accepted, 2);
+        assert_eq!(stats[&105].rejected_by_qps, 1);
+        assert_eq!(stats[&12].rejected_by_concurrency, 1);
+        assert!(!stats.contains_key(&10));
+    }
+
+    #[test]
+    fn tokens_refill_at_the_qps() {
+        let controller = RequestFlowController::new(&[RequestCodeLimit {
+            code: 105,
+            max_qps: 10,
+            max_concurrency: 0,
+        }]);
+        let limiter = &controller.limiters[&105];
+        let start = Instant::now();
+        for _ in 0..10 {
+            assert!(limiter.take_token(start));
+        }
+        assert!(!limiter.take_token(start));
+        assert!(limiter.take_token(start + Duration::from_millis(100)));
+        assert!(!limiter.take_token(start + Duration::from_millis(100)));
+    }
+}
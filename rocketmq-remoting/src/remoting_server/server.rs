@@ -22,6 +22,7 @@ use std::time::Duration;
 
 use futures::SinkExt;
 use rocketmq_common::common::server::config::ServerConfig;
+use rocketmq_common::utils::network_util::NetworkUtil;
 use rocketmq_common::ArcRefCellWrapper;
 use tokio::net::TcpListener;
 use tokio::net::TcpStream;
@@ -41,6 +42,7 @@ use crate::error::Error;
 use crate::net::channel::Channel;
 use crate::protocol::remoting_command::RemotingCommand;
 use crate::protocol::RemotingCommandType;
+use crate::remoting_server::flow_control::RequestFlowController;
 use crate::runtime::connection_handler_context::ConnectionHandlerContextWrapper;
 use crate::runtime::processor::RequestProcessor;
 use crate::runtime::RPCHook;
@@ -64,6 +66,7 @@ pub struct ConnectionHandler<RP> {
     conn_disconnect_notify: Option<broadcast::Sender<SocketAddr>>,
     rpc_hooks: Arc<Vec<Box<dyn RPCHook>>>,
     response_table: ArcRefCellWrapper<HashMap<i32, ResponseFuture>>,
+    flow_controller: Arc<RequestFlowController>,
 }
 
 impl<RP> Drop for ConnectionHandler<RP> {
@@ -136,6 +139,46 @@ impl<RP: RequestProcessor + Sync + 'static> ConnectionHandler<RP> {
                 continue;
             }
 
+            //flow control, the permit is held until the response is sent
+            let _permit = match self.flow_controller.try_acquire(cmd.code()) {
+                Ok(permit) => permit,
+                Err(rejection) => {
+                    warn!(
+                        "request code {} from {} rejected by flow control: {:?}",
+                        cmd.code(),
+                        self.channel.remote_address(),
+                        rejection
+                    );
+                    if cmd.is_oneway_rpc() {
+                        continue;
+                    }
+                    let response = RemotingCommand::create_response_command_with_code_remark(
+                        ResponseCode::FlowControl,
+                        format!(
+                            "[FLOW_CONTROL] request code {} is over its limit {:?}, try later",
+                            cmd.code(),
+                            rejection
+                        ),
+                    );
+                    match self
+                        .connection_handler_context
+                        .channel
+                        .connection
+                        .writer
+                        .send(response.set_opaque(cmd.opaque()))
+                        .await
+                    {
+                        Ok(_) => {}
+                        Err(Error::Io(io_error)) => {
+                            error!("send response failed: {}", io_error);
+                            return Ok(());
+                        }
+                        Err(err) => error!("send response failed: {}", err),
+                    }
+                    continue;
+                }
+            };
+
             //handle request
             let mut exception = match self.do_before_rpc_hooks(&self.channel, &mut cmd) {
                 Ok(_) => None,
@@ -255,6 +298,8 @@ struct ConnectionListener<RP> {
     request_processor: RP,
 
     rpc_hooks: Arc<Vec<Box<dyn RPCHook>>>,
+
+    flow_controller: Arc<RequestFlowController>,
 }
 
 impl<RP: RequestProcessor + Sync + 'static + Clone> ConnectionListener<RP> {
@@ -298,6 +343,7 @@ impl<RP: RequestProcessor + Sync + 'static + Clone> ConnectionListener<RP> {
                 conn_disconnect_notify: self.conn_disconnect_notify.clone(),
                 rpc_hooks: self.rpc_hooks.clone(),
                 response_table,
+                flow_controller: self.flow_controller.clone(),
             };
 
             tokio::spawn(async move {
@@ -345,30 +391,31 @@ impl<RP: RequestProcessor + Sync + 'static + Clone> ConnectionListener<RP> {
 
 pub struct RocketMQServer<RP> {
     config: Arc<ServerConfig>,
+    flow_controller: Arc<RequestFlowController>,
     _phantom_data: std::marker::PhantomData<RP>,
 }
 
 impl<RP> RocketMQServer<RP> {
     pub fn new(config: Arc<ServerConfig>) -> Self {
         Self {
+            flow_controller: Arc::new(RequestFlowController::new(&config.request_code_limits)),
             config,
             _phantom_data: std::marker::PhantomData,
         }
     }
+
+    /// Flow control of the requests of this server, e.g. to export how many were rejected.
+    pub fn flow_controller(&self) -> &Arc<RequestFlowController> {
+        &self.flow_controller
+    }
 }
 
 impl<RP: RequestProcessor + Sync + 'static + Clone> RocketMQServer<RP> {
     pub async fn run(&self, request_processor: RP) {
-        let listener = TcpListener::bind(&format!(
-            "{}:{}",
-            self.config.bind_address, self.config.listen_port
-        ))
-        .await
-        .unwrap();
-        info!(
-            "Bind local address: {}",
-            format!("{}:{}", self.config.bind_address, self.config.listen_port)
-        );
+        let bind_address =
+            NetworkUtil::host_port(&self.config.bind_address, self.config.listen_port);
+        let listener = TcpListener::bind(&bind_address).await.unwrap();
+        info!("Bind local address: {}", bind_address);
         let (notify_conn_disconnect, _) = broadcast::channel::<SocketAddr>(100);
         run(
             listener,
@@ -376,6 +423,7 @@ impl<RP: RequestProcessor + Sync + 'static + Clone> RocketMQServer<RP> {
             request_processor,
             Some(notify_conn_disconnect),
             vec![],
+            self.flow_controller.clone(),
         )
         .await;
     }
@@ -387,6 +435,7 @@ pub async fn run<RP: RequestProcessor + Sync + 'static + Clone>(
     request_processor: RP,
     conn_disconnect_notify: Option<broadcast::Sender<SocketAddr>>,
     rpc_hooks: Vec<Box<dyn RPCHook>>,
+    flow_controller: Arc<RequestFlowController>,
 ) {
     let (notify_shutdown, _) = broadcast::channel(1);
     let (shutdown_complete_tx, mut shutdown_complete_rx) = mpsc::channel(1);
@@ -399,6 +448,7 @@ pub async fn run<RP: RequestProcessor + Sync + 'static + Clone>(
         limit_connections: Arc::new(Semaphore::new(DEFAULT_MAX_CONNECTIONS)),
         request_processor,
         rpc_hooks: Arc::new(rpc_hooks),
+        flow_controller,
     };
 
     tokio::select! {
@@ -20,7 +20,7 @@ use std::sync::Arc;
 use parking_lot::RwLock;
 use rocketmq_common::common::message::message_queue::MessageQueue;
 use rocketmq_common::common::mix_all;
-use rocketmq_common::common::mix_all::MASTER_ID;
+use rocketmq_common::common::mix_all::MASTER_BROKER_ADDR_ID;
 
 use crate::protocol::body::broker_body::cluster_info::ClusterInfo;
 use crate::protocol::route::topic_route_data::TopicRouteData;
@@ -123,7 +123,7 @@ impl ClientMetadata {
         let broker_addr = read_guard
             .get(broker_name)
             .unwrap()
-            .get(&(MASTER_ID as i64));
+            .get(&MASTER_BROKER_ADDR_ID);
         broker_addr.cloned()
     }
 
@@ -131,24 +131,27 @@ impl ClientMetadata {
         topic: &str,
         topic_route_data: &TopicRouteData,
     ) -> Option<HashMap<MessageQueue, String>> {
-        if topic_route_data.topic_queue_mapping_by_broker.is_none()
-            || topic_route_data
-                .topic_queue_mapping_by_broker
-                .as_ref()
-                .unwrap()
-                .is_empty()
-        {
-            return Some(HashMap::new());
+        match topic_route_data.topic_queue_mapping_by_broker.as_ref() {
+            Some(mapping_by_broker) => Some(ClientMetadata::mapping_infos2endpoints(
+                topic,
+                mapping_by_broker,
+            )),
+            None => Some(HashMap::new()),
         }
+    }
 
+    /// Broker each logical queue of a static topic is currently written to, the newest epoch
+    /// wins when several brokers claim the same queue.
+    pub fn mapping_infos2endpoints(
+        topic: &str,
+        mapping_by_broker: &HashMap<String, TopicQueueMappingInfo>,
+    ) -> HashMap<MessageQueue, String> {
         let mut mq_end_points_of_broker = HashMap::new();
         let mut mapping_infos_by_scope = HashMap::new();
-        for (broker_name, info) in topic_route_data
-            .topic_queue_mapping_by_broker
-            .as_ref()
-            .unwrap()
-            .iter()
-        {
+        for (broker_name, info) in mapping_by_broker.iter() {
+            if info.curr_id_map.is_none() {
+                continue;
+            }
             let scope = info.scope.as_ref();
             if let Some(scope_inner) = scope {
                 if !mapping_infos_by_scope.contains_key(scope_inner) {
@@ -209,7 +212,7 @@ impl ClientMetadata {
             }
         }
 
-        Some(mq_end_points_of_broker)
+        mq_end_points_of_broker
     }
 
     pub fn broker_addr_table(&self) -> Arc<RwLock<HashMap<String, HashMap<i64, String>>>> {
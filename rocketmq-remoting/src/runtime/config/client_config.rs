@@ -45,6 +45,8 @@ pub struct TokioClientConfig {
     pub max_reconnect_interval_time_seconds: i64,
     pub enable_reconnect_for_go_away: bool,
     pub enable_transparent_retry: bool,
+    /// How often name servers given as hostnames are re-resolved.
+    pub namesrv_dns_refresh_interval_millis: u64,
 }
 
 impl Default for TokioClientConfig {
@@ -70,6 +72,7 @@ impl Default for TokioClientConfig {
             max_reconnect_interval_time_seconds: 60,
             enable_reconnect_for_go_away: true,
             enable_transparent_retry: true,
+            namesrv_dns_refresh_interval_millis: 30_000,
         }
     }
 }
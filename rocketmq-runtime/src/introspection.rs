@@ -0,0 +1,184 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::task::Context;
+use std::task::Poll;
+use std::time::Duration;
+use std::time::Instant;
+
+/// Snapshot of a runtime, see [`RocketMQRuntime::runtime_stats`](crate::RocketMQRuntime).
+#[derive(Debug, Clone)]
+pub struct RuntimeStats {
+    pub name: String,
+    pub workers: usize,
+    pub alive_tasks: usize,
+    /// Tasks waiting in the global injection queue, only known with `--cfg tokio_unstable`.
+    pub global_queue_depth: Option<usize>,
+    /// Live tasks spawned with `spawn_named`, empty without the `introspection` feature.
+    pub tasks: Vec<TaskStats>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TaskStats {
+    pub name: String,
+    pub polls: u64,
+    pub total_poll_duration: Duration,
+    pub max_poll_duration: Duration,
+    /// Time since the task was last polled. A scheduled loop idle for much longer than its
+    /// period is stuck in one of its awaits.
+    pub idle: Duration,
+}
+
+/// Named tasks of a runtime.
+pub struct TaskRegistry {
+    name: String,
+    tasks: Mutex<Vec<Arc<TaskCounters>>>,
+}
+
+impl TaskRegistry {
+    pub(crate) fn new(name: &str) -> Self {
+        TaskRegistry {
+            name: name.to_string(),
+            tasks: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    #[cfg_attr(not(feature = "introspection"), allow(dead_code))]
+    pub(crate) fn track<F>(&self, name: &str, future: F) -> Tracked<F>
+    where
+        F: Future,
+    {
+        let counters = Arc::new(TaskCounters {
+            name: name.to_string(),
+            polls: AtomicU64::new(0),
+            total_poll_nanos: AtomicU64::new(0),
+            max_poll_nanos: AtomicU64::new(0),
+            last_poll: Mutex::new(Instant::now()),
+            completed: AtomicBool::new(false),
+        });
+        let mut tasks = self.tasks.lock().unwrap();
+        tasks.retain(|task| !task.completed.load(Ordering::Relaxed));
+        tasks.push(counters.clone());
+        Tracked {
+            inner: Box::pin(future),
+            counters,
+        }
+    }
+
+    pub(crate) fn task_stats(&self) -> Vec<TaskStats> {
+        let now = Instant::now();
+        self.tasks
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|task| !task.completed.load(Ordering::Relaxed))
+            .map(|task| TaskStats {
+                name: task.name.clone(),
+                polls: task.polls.load(Ordering::Relaxed),
+                total_poll_duration: Duration::from_nanos(
+                    task.total_poll_nanos.load(Ordering::Relaxed),
+                ),
+                max_poll_duration: Duration::from_nanos(
+                    task.max_poll_nanos.load(Ordering::Relaxed),
+                ),
+                idle: now.saturating_duration_since(*task.last_poll.lock().unwrap()),
+            })
+            .collect()
+    }
+}
+
+struct TaskCounters {
+    name: String,
+    polls: AtomicU64,
+    total_poll_nanos: AtomicU64,
+    max_poll_nanos: AtomicU64,
+    last_poll: Mutex<Instant>,
+    completed: AtomicBool,
+}
+
+/// Future wrapper recording every poll of the inner future.
+pub(crate) struct Tracked<F> {
+    inner: Pin<Box<F>>,
+    counters: Arc<TaskCounters>,
+}
+
+impl<F: Future> Future for Tracked<F> {
+    type Output = F::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let start = Instant::now();
+        let result = self.inner.as_mut().poll(cx);
+        let end = Instant::now();
+        let nanos = (end - start).as_nanos() as u64;
+        let counters = &self.counters;
+        counters.polls.fetch_add(1, Ordering::Relaxed);
+        counters
+            .total_poll_nanos
+            .fetch_add(nanos, Ordering::Relaxed);
+        counters.max_poll_nanos.fetch_max(nanos, Ordering::Relaxed);
+        *counters.last_poll.lock().unwrap() = end;
+        if result.is_ready() {
+            counters.completed.store(true, Ordering::Relaxed);
+        }
+        result
+    }
+}
+
+impl<F> Drop for Tracked<F> {
+    fn drop(&mut self) {
+        // aborted tasks are dropped without completing
+        self.counters.completed.store(true, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tracked_future_records_polls_until_completed() {
+        let registry = TaskRegistry::new("test");
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let tracked = registry.track("sleeper", async {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+            tokio::task::yield_now().await;
+        });
+        let handle = runtime.spawn(tracked);
+        let stats = registry.task_stats();
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0].name, "sleeper");
+
+        runtime.block_on(handle).unwrap();
+        assert!(registry.task_stats().is_empty());
+        let counters = registry.tasks.lock().unwrap()[0].clone();
+        assert!(counters.polls.load(Ordering::Relaxed) >= 2);
+    }
+}
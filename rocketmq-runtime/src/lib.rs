@@ -15,10 +15,20 @@
  * limitations under the License.
  */
 
+use std::future::Future;
+use std::sync::Arc;
 use std::time::Duration;
 
+use tokio::task::JoinHandle;
+
+pub use crate::introspection::RuntimeStats;
+pub use crate::introspection::TaskRegistry;
+pub use crate::introspection::TaskStats;
+
+mod introspection;
+
 pub enum RocketMQRuntime {
-    Multi(tokio::runtime::Runtime),
+    Multi(tokio::runtime::Runtime, Arc<TaskRegistry>),
 }
 
 impl RocketMQRuntime {
@@ -30,26 +40,79 @@ impl RocketMQRuntime {
                 .enable_all()
                 .build()
                 .unwrap(),
+            Arc::new(TaskRegistry::new(name)),
         )
     }
 }
 
+/// Installs the global tracing subscriber with the tokio-console layer, in place of the usual
+/// `tracing_subscriber` initialization.
+#[cfg(feature = "tokio-console")]
+pub fn init_console() {
+    console_subscriber::init();
+}
+
 impl RocketMQRuntime {
     pub fn get_handle(&self) -> &tokio::runtime::Handle {
         match self {
-            Self::Multi(runtime) => runtime.handle(),
+            Self::Multi(runtime, _) => runtime.handle(),
         }
     }
 
     pub fn get_runtime(&self) -> &tokio::runtime::Runtime {
         match self {
-            Self::Multi(runtime) => runtime,
+            Self::Multi(runtime, _) => runtime,
+        }
+    }
+
+    pub fn task_registry(&self) -> &Arc<TaskRegistry> {
+        match self {
+            Self::Multi(_, task_registry) => task_registry,
         }
     }
 
     pub fn shutdown(self) {
         match self {
-            Self::Multi(runtime) => runtime.shutdown_background(),
+            Self::Multi(runtime, _) => runtime.shutdown_background(),
+        }
+    }
+
+    /// Spawns `future` as a task called `name`. The task is tracked for
+    /// [`runtime_stats`](Self::runtime_stats) with the `introspection` feature, and shows up
+    /// under its name in tokio-console with the `tokio-console` feature.
+    pub fn spawn_named<F>(&self, name: &str, future: F) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        #[cfg(feature = "introspection")]
+        let future = self.task_registry().track(name, future);
+        #[cfg(all(feature = "tokio-console", tokio_unstable))]
+        {
+            tokio::task::Builder::new()
+                .name(name)
+                .spawn_on(future, self.get_handle())
+                .expect("spawn named task")
+        }
+        #[cfg(not(all(feature = "tokio-console", tokio_unstable)))]
+        {
+            let _ = name;
+            self.get_handle().spawn(future)
+        }
+    }
+
+    pub fn runtime_stats(&self) -> RuntimeStats {
+        let metrics = self.get_handle().metrics();
+        #[cfg(tokio_unstable)]
+        let global_queue_depth = Some(metrics.global_queue_depth());
+        #[cfg(not(tokio_unstable))]
+        let global_queue_depth = None;
+        RuntimeStats {
+            name: self.task_registry().name().to_string(),
+            workers: metrics.num_workers(),
+            alive_tasks: metrics.num_alive_tasks(),
+            global_queue_depth,
+            tasks: self.task_registry().task_stats(),
         }
     }
 
@@ -61,29 +124,26 @@ impl RocketMQRuntime {
     ) where
         F: Fn() + Send + 'static,
     {
-        match self {
-            RocketMQRuntime::Multi(runtime) => {
-                runtime.handle().spawn(async move {
-                    // initial delay
-                    if let Some(initial_delay_inner) = initial_delay {
-                        tokio::time::sleep(initial_delay_inner).await;
-                    }
-
-                    loop {
-                        // record current execution time
-                        let current_execution_time = tokio::time::Instant::now();
-                        // execute task
-                        task();
-                        // Calculate the time of the next execution
-                        let next_execution_time = current_execution_time + period;
-
-                        // Wait until the next execution
-                        let delay = next_execution_time
-                            .saturating_duration_since(tokio::time::Instant::now());
-                        tokio::time::sleep(delay).await;
-                    }
-                });
+        let name = format!("{}-fixed-rate", self.task_registry().name());
+        self.spawn_named(name.as_str(), async move {
+            // initial delay
+            if let Some(initial_delay_inner) = initial_delay {
+                tokio::time::sleep(initial_delay_inner).await;
             }
-        }
+
+            loop {
+                // record current execution time
+                let current_execution_time = tokio::time::Instant::now();
+                // execute task
+                task();
+                // Calculate the time of the next execution
+                let next_execution_time = current_execution_time + period;
+
+                // Wait until the next execution
+                let delay =
+                    next_execution_time.saturating_duration_since(tokio::time::Instant::now());
+                tokio::time::sleep(delay).await;
+            }
+        });
     }
 }
@@ -17,13 +17,15 @@
 
 use memmap2::MmapMut;
 
-pub mod allocate_mapped_file_service;
 pub mod append_message_callback;
 pub mod commit_log_dispatcher;
 pub mod compaction_append_msg_callback;
-pub(crate) mod dispatch_request;
+pub mod concurrent_dispatcher;
+pub mod corrupted_message_report;
+pub mod dispatch_request;
 pub mod flush_manager;
 pub mod get_message_result;
+pub mod ha_service;
 pub mod message_arriving_listener;
 pub mod message_result;
 pub mod message_status_enum;
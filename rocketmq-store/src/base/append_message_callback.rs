@@ -135,6 +135,8 @@ impl AppendMessageCallback for DefaultAppendMessageCallback {
         }
 
         if (msg_len + END_FILE_MIN_BLANK_LENGTH) > max_blank {
+            // Keep the encoded message for the retry in the next file.
+            msg_inner.encoded_buff = Some(pre_encode_buffer);
             let bytes = self.msg_store_item_memory.mut_from_ref();
             bytes.clear();
             bytes.put_i32(max_blank);
@@ -17,6 +17,15 @@
 
 use crate::base::dispatch_request::DispatchRequest;
 
+/// Consumer of the commit log replay stream.
+///
+/// The message store calls every registered dispatcher, in order, for each message appended to
+/// the commit log, e.g. to build the consume queue and the index. Plugins can register their
+/// own dispatchers through `MessageStore::add_dispatcher`. A dispatcher may complete the request
+/// for the ones after it, e.g. with the filter bit map the consume queue stores in its ext.
 pub trait CommitLogDispatcher: Send + Sync + 'static {
-    fn dispatch(&self, dispatch_request: &DispatchRequest);
+    fn dispatch(&self, dispatch_request: &mut DispatchRequest);
 }
+
+/// Alias for `Box<dyn CommitLogDispatcher>`.
+pub type BoxedCommitLogDispatcher = Box<dyn CommitLogDispatcher>;
@@ -0,0 +1,204 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::collections::hash_map::DefaultHasher;
+use std::hash::Hash;
+use std::hash::Hasher;
+use std::sync::atomic::AtomicI64;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+use std::time::Instant;
+
+use tokio::sync::mpsc;
+use tracing::error;
+use tracing::info;
+
+use crate::base::dispatch_request::DispatchRequest;
+
+/// What a worker does with each dispatch request, e.g. run the dispatchers and notify the
+/// long polling pulls.
+pub type DispatchHandler = Arc<dyn Fn(&mut DispatchRequest) + Send + Sync>;
+
+/// Runs the commit log dispatch on a pool of worker threads. Every queue belongs to one
+/// worker, so the requests of a queue are dispatched in commit log order while different
+/// queues are built in parallel. `max_buffer` bounds the requests waiting for the workers,
+/// [`submit`](Self::submit) waits once a worker is that far behind.
+pub struct ConcurrentDispatcher {
+    senders: parking_lot::RwLock<Vec<mpsc::Sender<DispatchRequest>>>,
+    workers: parking_lot::Mutex<Vec<JoinHandle<()>>>,
+    pending: Arc<AtomicUsize>,
+    pending_bytes: Arc<AtomicI64>,
+}
+
+impl ConcurrentDispatcher {
+    pub fn new(concurrency: usize, max_buffer: usize, handler: DispatchHandler) -> Self {
+        let concurrency = concurrency.max(1);
+        let capacity = (max_buffer / concurrency).max(1);
+        let pending = Arc::new(AtomicUsize::new(0));
+        let pending_bytes = Arc::new(AtomicI64::new(0));
+        let mut senders = Vec::with_capacity(concurrency);
+        let mut workers = Vec::with_capacity(concurrency);
+        for index in 0..concurrency {
+            let (tx, mut rx) = mpsc::channel::<DispatchRequest>(capacity);
+            let handler = handler.clone();
+            let pending = pending.clone();
+            let pending_bytes = pending_bytes.clone();
+            let worker = std::thread::Builder::new()
+                .name(format!("DispatchWorker-{}", index))
+                .spawn(move || {
+                    while let Some(mut dispatch_request) = rx.blocking_recv() {
+                        handler(&mut dispatch_request);
+                        pending_bytes.fetch_sub(dispatch_request.msg_size as i64, Ordering::AcqRel);
+                        pending.fetch_sub(1, Ordering::AcqRel);
+                    }
+                })
+                .expect("spawn dispatch worker failed");
+            senders.push(tx);
+            workers.push(worker);
+        }
+        info!(
+            "start {} dispatch workers, buffer {} requests each",
+            concurrency, capacity
+        );
+        Self {
+            senders: parking_lot::RwLock::new(senders),
+            workers: parking_lot::Mutex::new(workers),
+            pending,
+            pending_bytes,
+        }
+    }
+
+    /// Worker of the queue `queue_id` of `topic` among `concurrency` ones.
+    pub fn shard(topic: &str, queue_id: i32, concurrency: usize) -> usize {
+        let mut hasher = DefaultHasher::new();
+        topic.hash(&mut hasher);
+        queue_id.hash(&mut hasher);
+        (hasher.finish() % concurrency.max(1) as u64) as usize
+    }
+
+    /// Hands `dispatch_request` to the worker of its queue, waiting while its buffer is full.
+    /// Returns `false` once the dispatcher is shut down.
+    pub async fn submit(&self, dispatch_request: DispatchRequest) -> bool {
+        let sender = {
+            let senders = self.senders.read();
+            if senders.is_empty() {
+                return false;
+            }
+            let shard = Self::shard(
+                dispatch_request.topic.as_str(),
+                dispatch_request.queue_id,
+                senders.len(),
+            );
+            senders[shard].clone()
+        };
+        let msg_size = dispatch_request.msg_size as i64;
+        self.pending.fetch_add(1, Ordering::AcqRel);
+        self.pending_bytes.fetch_add(msg_size, Ordering::AcqRel);
+        if sender.send(dispatch_request).await.is_err() {
+            self.pending.fetch_sub(1, Ordering::AcqRel);
+            self.pending_bytes.fetch_sub(msg_size, Ordering::AcqRel);
+            error!("dispatch worker stopped, request dropped");
+            return false;
+        }
+        true
+    }
+
+    /// Requests submitted and not dispatched yet.
+    pub fn pending(&self) -> usize {
+        self.pending.load(Ordering::Acquire)
+    }
+
+    /// Commit log bytes submitted and not dispatched yet.
+    pub fn pending_bytes(&self) -> i64 {
+        self.pending_bytes.load(Ordering::Acquire)
+    }
+
+    /// Waits up to `timeout` for the workers to dispatch everything submitted so far.
+    pub fn wait_idle(&self, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        while self.pending() > 0 {
+            if Instant::now() >= deadline {
+                return false;
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        true
+    }
+
+    /// Stops taking requests and waits for the workers to dispatch the ones they have.
+    pub fn shutdown(&self) {
+        self.senders.write().clear();
+        for worker in self.workers.lock().drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn dispatches_each_queue_in_order() {
+        let seen = Arc::new(parking_lot::Mutex::new(
+            HashMap::<(String, i32), Vec<i64>>::new(),
+        ));
+        let seen_by_workers = seen.clone();
+        let dispatcher = ConcurrentDispatcher::new(
+            4,
+            8,
+            Arc::new(move |dispatch_request: &mut DispatchRequest| {
+                seen_by_workers
+                    .lock()
+                    .entry((dispatch_request.topic.clone(), dispatch_request.queue_id))
+                    .or_default()
+                    .push(dispatch_request.consume_queue_offset);
+            }),
+        );
+        for offset in 0..100 {
+            for topic in ["TopicA", "TopicB"] {
+                for queue_id in 0..4 {
+                    assert!(
+                        dispatcher
+                            .submit(DispatchRequest {
+                                topic: topic.to_string(),
+                                queue_id,
+                                consume_queue_offset: offset,
+                                msg_size: 10,
+                                ..Default::default()
+                            })
+                            .await
+                    );
+                }
+            }
+        }
+        assert!(dispatcher.wait_idle(Duration::from_secs(10)));
+        assert_eq!(dispatcher.pending_bytes(), 0);
+        dispatcher.shutdown();
+        assert!(!dispatcher.submit(DispatchRequest::default()).await);
+
+        let seen = seen.lock();
+        assert_eq!(seen.len(), 8);
+        for offsets in seen.values() {
+            assert_eq!(*offsets, (0..100).collect::<Vec<i64>>());
+        }
+    }
+}
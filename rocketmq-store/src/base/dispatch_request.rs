@@ -19,6 +19,8 @@ use std::collections::HashMap;
 use std::fmt::Display;
 use std::fmt::Formatter;
 
+use bytes::Bytes;
+
 #[derive(Debug)]
 pub struct DispatchRequest {
     pub topic: String,
@@ -34,7 +36,7 @@ pub struct DispatchRequest {
     pub sys_flag: i32,
     pub prepared_transaction_offset: i64,
     pub properties_map: Option<HashMap<String, String>>,
-    pub bit_map: Option<Vec<u8>>,
+    pub bit_map: Option<Bytes>,
     pub buffer_size: i32,
     pub msg_base_offset: i64,
     pub batch_size: i16,
@@ -16,6 +16,8 @@
  */
 use std::fmt;
 
+use bytes::Bytes;
+
 use crate::base::message_status_enum::GetMessageStatus;
 use crate::base::select_result::SelectMappedBufferResult;
 
@@ -177,6 +179,18 @@ impl GetMessageResult {
     pub fn message_mapped_list(&self) -> &[SelectMappedBufferResult] {
         self.message_mapped_list.as_slice()
     }
+
+    /// Replaces the content of the `index`th message with `bytes`, which must be a whole
+    /// encoded message. Returns `false` when there is no such message.
+    pub fn replace_message(&mut self, index: usize, bytes: Bytes) -> bool {
+        let Some(mapped_buffer) = self.message_mapped_list.get_mut(index) else {
+            return false;
+        };
+        self.buffer_total_size += bytes.len() as i32 - mapped_buffer.size;
+        mapped_buffer.size = bytes.len() as i32;
+        mapped_buffer.bytes = Some(bytes);
+        true
+    }
 }
 
 #[cfg(test)]
@@ -247,4 +261,25 @@ mod tests {
         assert_eq!(result.commercial_size_per_msg, commercial_size_per_msg);
         assert_eq!(result.cold_data_sum, cold_data_sum);
     }
+
+    #[test]
+    fn replace_message_swaps_content_and_total_size() {
+        let mut result = GetMessageResult::new();
+        result.add_message(
+            SelectMappedBufferResult {
+                start_offset: 0,
+                size: 4,
+                mapped_file: None,
+                is_in_cache: true,
+                bytes: Some(Bytes::from_static(b"abcd")),
+            },
+            0,
+            1,
+        );
+
+        assert!(result.replace_message(0, Bytes::from_static(b"plaintext")));
+        assert!(!result.replace_message(1, Bytes::new()));
+        assert_eq!(result.buffer_total_size(), 9);
+        assert_eq!(result.message_mapped_list()[0].get_buffer(), b"plaintext");
+    }
 }
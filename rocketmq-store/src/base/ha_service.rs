@@ -0,0 +1,29 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use tokio::sync::oneshot;
+
+/// Replication side of a master, the commit log waits on it before acknowledging messages that
+/// must reach the slaves (a `SyncMaster` with more than one in-sync replica).
+pub trait HAService: Send + Sync + 'static {
+    /// Whether a slave is connected and close enough to `master_put_where` to catch up in time.
+    fn is_slave_ok(&self, master_put_where: i64) -> bool;
+
+    /// Registers a wait for `need_ack_nums` replicas, the master included, to have everything
+    /// before `next_offset`. The receiver resolves with `true` once they acknowledged it, the
+    /// caller bounds the wait.
+    fn wait_for_replicas(&self, next_offset: i64, need_ack_nums: u32) -> oneshot::Receiver<bool>;
+}
@@ -16,6 +16,8 @@
  */
 use std::collections::HashMap;
 
+use bytes::Bytes;
+
 pub trait MessageArrivingListener {
     /// This method is called when a new message arrives.
     ///
@@ -26,7 +28,7 @@ pub trait MessageArrivingListener {
     /// * `logic_offset` - An i64 that represents the logical offset of the message in the queue.
     /// * `tags_code` - An i64 that represents the tags associated with the message.
     /// * `msg_store_time` - An i64 that represents the time when the message was stored.
-    /// * `filter_bit_map` - A Vec<u8> that represents the filter bit map for the message.
+    /// * `filter_bit_map` - A slice of the filter bit map computed at dispatch for the message.
     /// * `properties` - An Option containing a reference to a HashMap<String, String> that holds
     ///   the properties of the message.
     fn arriving(
@@ -36,7 +38,7 @@ pub trait MessageArrivingListener {
         logic_offset: i64,
         tags_code: Option<i64>,
         msg_store_time: i64,
-        filter_bit_map: Option<Vec<u8>>,
+        filter_bit_map: Option<Bytes>,
         properties: Option<&HashMap<String, String>>,
     );
 }
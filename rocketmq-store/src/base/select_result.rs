@@ -33,11 +33,17 @@ pub struct SelectMappedBufferResult {
     pub mapped_file: Option<Arc<DefaultMappedFile>>,
     /// Whether the buffer is in cache.
     pub is_in_cache: bool,
+    /// Content replacing the mapped region, set when a store plugin rewrites the message on
+    /// read.
+    pub bytes: Option<Bytes>,
 }
 
 impl SelectMappedBufferResult {
     /// Returns the buffer.
     pub fn get_buffer(&self) -> &[u8] {
+        if let Some(bytes) = self.bytes.as_ref() {
+            return bytes.as_ref();
+        }
         self.mapped_file.as_ref().unwrap().get_mapped_file()
             [self.start_offset as usize..(self.start_offset + self.size as u64) as usize]
             .as_ref()
@@ -50,6 +56,9 @@ impl SelectMappedBufferResult {
     }
 
     pub fn get_bytes(&self) -> Option<Bytes> {
+        if let Some(bytes) = self.bytes.as_ref() {
+            return Some(bytes.clone());
+        }
         if self.size <= 0 || self.mapped_file.is_none() {
             return None;
         }
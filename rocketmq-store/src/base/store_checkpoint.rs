@@ -88,7 +88,8 @@ impl StoreCheckpoint {
     }
 
     pub fn flush(&self) -> std::io::Result<()> {
-        let mut buffer = &mut self.mmap.lock()[..8];
+        let mut mmap = self.mmap.lock();
+        let mut buffer = &mut mmap[..40];
         buffer.write_all(
             self.physic_msg_timestamp
                 .load(Ordering::Relaxed)
@@ -119,7 +120,7 @@ impl StoreCheckpoint {
                 .to_be_bytes()
                 .as_ref(),
         )?;
-        self.mmap.lock().flush()?;
+        mmap.flush()?;
         Ok(())
     }
 
@@ -186,3 +187,33 @@ impl StoreCheckpoint {
             .min(self.index_msg_timestamp.load(Ordering::Relaxed))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::*;
+
+    #[test]
+    fn flushed_timestamps_survive_a_reopen() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("checkpoint");
+        let checkpoint = StoreCheckpoint::new(&path).unwrap();
+        checkpoint.set_physic_msg_timestamp(3_000_000);
+        checkpoint.set_logics_msg_timestamp(2_000_000);
+        checkpoint.set_index_msg_timestamp(1_000_000);
+        checkpoint.set_master_flushed_offset(4096);
+        checkpoint.set_confirm_phy_offset(8192);
+        checkpoint.flush().unwrap();
+        drop(checkpoint);
+
+        let checkpoint = StoreCheckpoint::new(&path).unwrap();
+        assert_eq!(checkpoint.physic_msg_timestamp(), 3_000_000);
+        assert_eq!(checkpoint.logics_msg_timestamp(), 2_000_000);
+        assert_eq!(checkpoint.index_msg_timestamp(), 1_000_000);
+        assert_eq!(checkpoint.master_flushed_offset(), 4096);
+        assert_eq!(checkpoint.confirm_phy_offset(), 8192);
+        assert_eq!(checkpoint.get_min_timestamp(), 2_000_000 - 3000);
+        assert_eq!(checkpoint.get_min_timestamp_index(), 1_000_000);
+    }
+}
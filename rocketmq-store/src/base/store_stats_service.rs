@@ -17,9 +17,11 @@
 use std::collections::BTreeMap;
 use std::collections::HashMap;
 use std::collections::LinkedList;
+use std::sync::atomic::AtomicBool;
 use std::sync::atomic::AtomicUsize;
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
+use std::time::Duration;
 use std::time::Instant;
 
 use parking_lot::Mutex;
@@ -27,9 +29,11 @@ use parking_lot::RwLock;
 use rocketmq_common::common::broker::broker_config::BrokerIdentity;
 use rocketmq_common::common::system_clock::SystemClock;
 use rocketmq_common::TimeUtils::get_current_millis;
+use tracing::info;
 
 const FREQUENCY_OF_SAMPLING: u64 = 1000;
 const MAX_RECORDS_OF_SAMPLING: usize = 60 * 10;
+const PUT_MESSAGE_DISTRIBUTE_TIME_PERIOD: u64 = 60 * 1000;
 const PUT_MESSAGE_ENTIRE_TIME_MAX_DESC: [&str; 13] = [
     "[<=0ms]",
     "[0~10ms]",
@@ -84,6 +88,7 @@ pub struct StoreStatsService {
     sampling_lock: Mutex<()>,
     last_print_timestamp: u64,
     broker_identity: Option<BrokerIdentity>,
+    stopped: AtomicBool,
 }
 
 impl StoreStatsService {
@@ -112,8 +117,32 @@ impl StoreStatsService {
             sampling_lock: Mutex::new(()),
             last_print_timestamp: get_current_millis(),
             broker_identity,
+            stopped: AtomicBool::new(false),
         }
     }
+
+    /// Samples the counters every second for the tps windows, and publishes the put latency
+    /// distribution every minute.
+    pub fn start(self: Arc<Self>) {
+        tokio::spawn(async move {
+            info!("StoreStatsService service started");
+            let mut last_distribute_time_period = get_current_millis();
+            while !self.stopped.load(Ordering::Acquire) {
+                tokio::time::sleep(Duration::from_millis(FREQUENCY_OF_SAMPLING)).await;
+                self.sampling();
+                let now = get_current_millis();
+                if now >= last_distribute_time_period + PUT_MESSAGE_DISTRIBUTE_TIME_PERIOD {
+                    last_distribute_time_period = now;
+                    self.rotate_put_message_distribute_time();
+                }
+            }
+            info!("StoreStatsService service end");
+        });
+    }
+
+    pub fn shutdown(&self) {
+        self.stopped.store(true, Ordering::Release);
+    }
 }
 
 impl StoreStatsService {
@@ -161,9 +190,90 @@ impl StoreStatsService {
         }
     }
 
-    pub fn set_put_message_entire_time_max(&self, _value: u64) {}
+    pub fn set_put_message_entire_time_max(&self, value: u64) {
+        let index = match value {
+            0 => 0,
+            1..10 => 1,
+            10..50 => 2,
+            50..100 => 3,
+            100..200 => 4,
+            200..500 => 5,
+            500..1000 => 6,
+            1000..2000 => 7,
+            2000..3000 => 8,
+            3000..4000 => 9,
+            4000..5000 => 10,
+            5000..10000 => 11,
+            _ => 12,
+        };
+        self.put_message_distribute_time[index].fetch_add(1, Ordering::Relaxed);
+        self.put_message_entire_time_max
+            .fetch_max(value as usize, Ordering::Relaxed);
+    }
+
+    /// Counts `msg_num` messages of `size` bytes put to `topic`.
+    pub fn add_put_message_topic(&self, topic: &str, msg_num: i32, size: i32) {
+        Self::add_topic_total(&self.put_message_topic_times_total, topic, msg_num.max(0));
+        Self::add_topic_total(&self.put_message_topic_size_total, topic, size.max(0));
+    }
+
+    fn add_topic_total(table: &RwLock<HashMap<String, AtomicUsize>>, topic: &str, delta: i32) {
+        if let Some(total) = table.read().get(topic) {
+            total.fetch_add(delta as usize, Ordering::Relaxed);
+            return;
+        }
+        table
+            .write()
+            .entry(topic.to_string())
+            .or_default()
+            .fetch_add(delta as usize, Ordering::Relaxed);
+    }
+
+    /// Takes the snapshots the tps windows of the runtime info are computed from.
+    pub fn sampling(&self) {
+        let _guard = self.sampling_lock.lock();
+        let now = get_current_millis();
+        Self::push_snapshot(
+            &self.put_times_list,
+            now,
+            self.get_put_message_times_total(),
+        );
+        Self::push_snapshot(
+            &self.get_times_found_list,
+            now,
+            self.get_message_times_total_found.load(Ordering::Relaxed) as u64,
+        );
+        Self::push_snapshot(
+            &self.get_times_miss_list,
+            now,
+            self.get_message_times_total_miss.load(Ordering::Relaxed) as u64,
+        );
+        Self::push_snapshot(
+            &self.transferred_msg_count_list,
+            now,
+            self.get_message_transferred_msg_count
+                .load(Ordering::Relaxed) as u64,
+        );
+    }
 
-    // Add more methods as needed for functionality
+    fn push_snapshot(list: &Mutex<LinkedList<CallSnapshot>>, timestamp: u64, total: u64) {
+        let mut list = list.lock();
+        list.push_back(CallSnapshot::new(timestamp, total));
+        if list.len() > MAX_RECORDS_OF_SAMPLING + 1 {
+            list.pop_front();
+        }
+    }
+
+    /// Publishes the put latency distribution of the period just ended and starts a new one.
+    pub fn rotate_put_message_distribute_time(&self) {
+        for (current, last) in self
+            .put_message_distribute_time
+            .iter()
+            .zip(self.last_put_message_distribute_time.iter())
+        {
+            last.store(current.swap(0, Ordering::Relaxed), Ordering::Relaxed);
+        }
+    }
 
     pub fn get_runtime_info(&self) -> HashMap<String, String> {
         let mut result = HashMap::new();
@@ -217,6 +327,11 @@ impl StoreStatsService {
             "getTransferredTps".to_string(),
             self.get_get_transferred_tps(),
         );
+        // Spelling read by mqadmin brokerStatus and the dashboard
+        result.insert(
+            "getTransferedTps".to_string(),
+            self.get_get_transferred_tps(),
+        );
         result.insert(
             "putLatency99".to_string(),
             format!("{:.2}", self.find_put_message_entire_time_px(0.99)),
@@ -538,3 +653,48 @@ mod call_snapshot_tests {
         assert!(tps < 0.0);
     }
 }
+
+#[cfg(test)]
+mod store_stats_service_tests {
+    use super::*;
+
+    #[test]
+    fn put_message_entire_time_is_distributed_in_buckets() {
+        let service = StoreStatsService::new(None);
+        service.set_put_message_entire_time_max(0);
+        service.set_put_message_entire_time_max(5);
+        service.set_put_message_entire_time_max(5);
+        service.set_put_message_entire_time_max(20_000);
+
+        assert_eq!(
+            service.put_message_entire_time_max.load(Ordering::Relaxed),
+            20_000
+        );
+        service.rotate_put_message_distribute_time();
+        let distribute_time = service.put_message_distribute_time_to_string();
+        assert!(distribute_time.starts_with("[<=0ms]:1, [0~10ms]:2, "));
+        assert!(distribute_time.ends_with("[10s~]:1, "));
+        assert_eq!(
+            service.put_message_distribute_time[1].load(Ordering::Relaxed),
+            0
+        );
+    }
+
+    #[test]
+    fn sampling_feeds_put_tps_windows() {
+        let service = StoreStatsService::new(None);
+        service.add_put_message_topic("TopicTest", 2, 128);
+        service.add_put_message_topic("TopicTest", 1, 64);
+
+        assert_eq!(service.get_put_message_times_total(), 3);
+        assert_eq!(service.get_put_message_size_total(), 192);
+        for _ in 0..=MAX_RECORDS_OF_SAMPLING + 1 {
+            service.sampling();
+        }
+        assert_eq!(
+            service.put_times_list.lock().len(),
+            MAX_RECORDS_OF_SAMPLING + 1
+        );
+        assert!(!service.get_put_tps_time(10).is_empty());
+    }
+}
@@ -19,6 +19,7 @@ use std::collections::HashMap;
 use std::path::PathBuf;
 
 use lazy_static::lazy_static;
+use rocketmq_common::common::mix_all::MULTI_PATH_SPLITTER;
 use serde::Deserialize;
 
 use crate::base::store_enum::StoreType;
@@ -34,6 +35,8 @@ lazy_static! {
 #[serde(rename_all = "camelCase")]
 pub struct MessageStoreConfig {
     pub store_path_root_dir: String,
+    /// Directories of the commit log, separated by `MULTI_PATH_SPLITTER`, to spread it over
+    /// several data disks.
     pub store_path_commit_log: Option<String>,
     pub store_path_dledger_commit_log: Option<String>,
     pub store_path_epoch_file: Option<String>,
@@ -92,6 +95,12 @@ pub struct MessageStoreConfig {
     pub put_msg_index_hight_water: usize,
     pub max_message_size: i32,
     pub check_crc_on_recover: bool,
+    pub check_crc_on_read: bool,
+    /// Workers building the consume queues and index from the commit log, each owning a shard
+    /// of the queues. `1` dispatches on the reput service itself.
+    pub dispatch_concurrency: usize,
+    /// Messages waiting for a dispatch worker, above which the reput service waits.
+    pub dispatch_max_buffer: usize,
     pub flush_commit_log_least_pages: i32,
     pub commit_commit_log_least_pages: i32,
     pub flush_least_pages_when_warm_mapped_file: usize,
@@ -103,6 +112,9 @@ pub struct MessageStoreConfig {
     pub max_transfer_count_on_message_in_memory: u64,
     pub max_transfer_bytes_on_message_in_disk: u64,
     pub max_transfer_count_on_message_in_disk: u64,
+    /// Share of the physical memory, in percent, the page cache is assumed to hold: messages
+    /// that far behind the commit log end count as on disk, get the smaller
+    /// `max_transfer_*_on_message_in_disk` limits and suggest pulling from a slave.
     pub access_message_in_memory_max_ratio: usize,
     pub message_index_enable: bool,
     pub max_hash_slot_num: u32,
@@ -124,6 +136,12 @@ pub struct MessageStoreConfig {
     pub flush_delay_offset_interval: usize,
     pub clean_file_forcibly_enable: bool,
     pub warm_mapped_file_enable: bool,
+    /// Allocates the next commit log and consume queue files on a background thread ahead of
+    /// the file roll. The commit log always pre-allocates with `warm_mapped_file_enable`.
+    pub pre_allocate_mapped_file_enable: bool,
+    /// With several commit log paths, rolls new files into the path on the disk with the most
+    /// free space instead of round-robin over the paths.
+    pub commit_log_path_select_by_free_space: bool,
     pub offset_check_in_slave: bool,
     pub debug_lock_enable: bool,
     pub duplication_enable: bool,
@@ -278,6 +296,9 @@ impl Default for MessageStoreConfig {
             put_msg_index_hight_water: 0,
             max_message_size: 1024 * 1024 * 4,
             check_crc_on_recover: false,
+            check_crc_on_read: false,
+            dispatch_concurrency: 1,
+            dispatch_max_buffer: 16 * 1024,
             flush_commit_log_least_pages: 0,
             commit_commit_log_least_pages: 4,
             flush_least_pages_when_warm_mapped_file: 0,
@@ -310,6 +331,8 @@ impl Default for MessageStoreConfig {
             flush_delay_offset_interval: 0,
             clean_file_forcibly_enable: false,
             warm_mapped_file_enable: false,
+            pre_allocate_mapped_file_enable: false,
+            commit_log_path_select_by_free_space: true,
             offset_check_in_slave: false,
             debug_lock_enable: false,
             duplication_enable: false,
@@ -406,6 +429,18 @@ impl MessageStoreConfig {
         self.store_path_commit_log.clone().unwrap()
     }
 
+    /// Commit log directories, more than one when the commit log spans several disks.
+    pub fn get_store_paths_commit_log(&self) -> Vec<String> {
+        split_store_paths(&self.get_store_path_commit_log())
+    }
+
+    pub fn get_read_only_commit_log_store_paths(&self) -> Vec<String> {
+        self.read_only_commit_log_store_paths
+            .as_deref()
+            .map(split_store_paths)
+            .unwrap_or_default()
+    }
+
     pub fn is_enable_rocksdb_store(&self) -> bool {
         self.store_type == StoreType::RocksDB
     }
@@ -658,6 +693,18 @@ impl MessageStoreConfig {
             "checkCrcOnRecover".to_string(),
             self.check_crc_on_recover.to_string(),
         );
+        properties.insert(
+            "checkCrcOnRead".to_string(),
+            self.check_crc_on_read.to_string(),
+        );
+        properties.insert(
+            "dispatchConcurrency".to_string(),
+            self.dispatch_concurrency.to_string(),
+        );
+        properties.insert(
+            "dispatchMaxBuffer".to_string(),
+            self.dispatch_max_buffer.to_string(),
+        );
         properties.insert(
             "flushCommitLogLeastPages".to_string(),
             self.flush_commit_log_least_pages.to_string(),
@@ -777,6 +824,14 @@ impl MessageStoreConfig {
             "warmMappedFileEnable".to_string(),
             self.warm_mapped_file_enable.to_string(),
         );
+        properties.insert(
+            "preAllocateMappedFileEnable".to_string(),
+            self.pre_allocate_mapped_file_enable.to_string(),
+        );
+        properties.insert(
+            "commitLogPathSelectByFreeSpace".to_string(),
+            self.commit_log_path_select_by_free_space.to_string(),
+        );
         properties.insert(
             "offsetCheckInSlave".to_string(),
             self.offset_check_in_slave.to_string(),
@@ -1093,3 +1148,12 @@ impl MessageStoreConfig {
         properties
     }
 }
+
+fn split_store_paths(store_paths: &str) -> Vec<String> {
+    store_paths
+        .split(MULTI_PATH_SPLITTER.as_str())
+        .map(str::trim)
+        .filter(|path| !path.is_empty())
+        .map(str::to_string)
+        .collect()
+}
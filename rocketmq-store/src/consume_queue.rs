@@ -17,3 +17,4 @@
 
 pub mod consume_queue_ext;
 pub mod mapped_file_queue;
+pub mod store_path_selector;
@@ -14,11 +14,15 @@
  * See the License for the specific language governing permissions and
  * limitations under the License.
  */
+use bytes::Buf;
+use bytes::BufMut;
+use bytes::Bytes;
+use bytes::BytesMut;
 
-const MIN_EXT_UNIT_SIZE: i16 = 2  // size, 32k max
+pub(crate) const MIN_EXT_UNIT_SIZE: i16 = 2  // size, 32k max
  + 8 * 2 // msg time + tagCode
   + 2; // bitMapSize
-const MAX_EXT_UNIT_SIZE: i16 = i16::MAX;
+pub(crate) const MAX_EXT_UNIT_SIZE: i16 = i16::MAX;
 
 #[derive(Clone, Default)]
 pub struct CqExtUnit {
@@ -26,11 +30,11 @@ pub struct CqExtUnit {
     tags_code: i64,
     msg_store_time: i64,
     bit_map_size: i16,
-    filter_bit_map: Option<Vec<u8>>,
+    filter_bit_map: Option<Bytes>,
 }
 
 impl CqExtUnit {
-    pub fn new(tags_code: i64, msg_store_time: i64, filter_bit_map: Option<Vec<u8>>) -> Self {
+    pub fn new(tags_code: i64, msg_store_time: i64, filter_bit_map: Option<Bytes>) -> Self {
         let bit_map_size = if let Some(val) = filter_bit_map.as_ref() {
             val.len() as i16
         } else {
@@ -58,7 +62,57 @@ impl CqExtUnit {
     pub fn bit_map_size(&self) -> i16 {
         self.bit_map_size
     }
-    pub fn filter_bit_map(&self) -> &Option<Vec<u8>> {
+    pub fn filter_bit_map(&self) -> &Option<Bytes> {
         &self.filter_bit_map
     }
+
+    /// Unit size computed from the bit map, the stored size of a unit built by [`new`](Self::new)
+    /// as well.
+    pub fn calc_unit_size(&self) -> usize {
+        MIN_EXT_UNIT_SIZE as usize + self.filter_bit_map.as_ref().map_or(0, Bytes::len)
+    }
+
+    /// Encodes the unit as stored in the consume queue ext files: size, tags code, store time,
+    /// bit map size and the bit map itself.
+    pub fn write(&self) -> Bytes {
+        let size = self.calc_unit_size();
+        let mut bytes = BytesMut::with_capacity(size);
+        bytes.put_i16(size as i16);
+        bytes.put_i64(self.tags_code);
+        bytes.put_i64(self.msg_store_time);
+        bytes.put_i16((size - MIN_EXT_UNIT_SIZE as usize) as i16);
+        if let Some(filter_bit_map) = self.filter_bit_map.as_ref() {
+            bytes.put_slice(filter_bit_map);
+        }
+        bytes.freeze()
+    }
+
+    /// Decodes the unit at the start of `buffer`, `None` at the end of the written data of a
+    /// file, where the size is zero or the `-1` end marker.
+    pub fn read(mut buffer: &[u8]) -> Option<Self> {
+        if buffer.len() < MIN_EXT_UNIT_SIZE as usize {
+            return None;
+        }
+        let size = buffer.get_i16();
+        if size < MIN_EXT_UNIT_SIZE || size as usize > buffer.len() + 2 {
+            return None;
+        }
+        let tags_code = buffer.get_i64();
+        let msg_store_time = buffer.get_i64();
+        let bit_map_size = buffer.get_i16();
+        let filter_bit_map = if bit_map_size < 1 {
+            None
+        } else if (bit_map_size as usize) > buffer.len() {
+            return None;
+        } else {
+            Some(Bytes::copy_from_slice(&buffer[..bit_map_size as usize]))
+        };
+        Some(Self {
+            size,
+            tags_code,
+            msg_store_time,
+            bit_map_size: bit_map_size.max(0),
+            filter_bit_map,
+        })
+    }
 }
@@ -27,6 +27,7 @@ use parking_lot::RwLock;
 use rocketmq_common::UtilAll::offset_to_file_name;
 use tracing::info;
 
+use crate::consume_queue::store_path_selector::StorePathSelector;
 use crate::log_file::mapped_file::default_mapped_file_impl::DefaultMappedFile;
 use crate::log_file::mapped_file::MappedFile;
 use crate::services::allocate_mapped_file_service::AllocateMappedFileService;
@@ -43,6 +44,8 @@ pub struct MappedFileQueue {
     pub(crate) mapped_files: Arc<RwLock<Vec<Arc<DefaultMappedFile>>>>,
     //  pub(crate) mapped_files: Vec<LocalMappedFile>,
     pub(crate) allocate_mapped_file_service: Option<AllocateMappedFileService>,
+    /// Set when the files are spread over several store paths, `store_path` is the first one.
+    pub(crate) store_path_selector: Option<StorePathSelector>,
 
     pub(crate) flushed_where: Arc<AtomicU64>,
 
@@ -62,25 +65,48 @@ impl MappedFileQueue {
             mapped_file_size,
             mapped_files: Arc::new(RwLock::new(Vec::new())),
             allocate_mapped_file_service,
+            store_path_selector: None,
             flushed_where: Arc::new(AtomicU64::new(0)),
             committed_where: Arc::new(AtomicU64::new(0)),
             store_timestamp: Arc::new(AtomicU64::new(0)),
         }
     }
+
+    /// A queue whose files are spread over the paths of `store_path_selector`.
+    pub fn new_multi_path(
+        store_path_selector: StorePathSelector,
+        mapped_file_size: u64,
+        allocate_mapped_file_service: Option<AllocateMappedFileService>,
+    ) -> MappedFileQueue {
+        MappedFileQueue {
+            store_path_selector: Some(store_path_selector.clone()),
+            ..MappedFileQueue::new(
+                store_path_selector
+                    .store_paths()
+                    .first()
+                    .cloned()
+                    .unwrap_or_default(),
+                mapped_file_size,
+                allocate_mapped_file_service,
+            )
+        }
+    }
 }
 
 impl MappedFileQueue {
     pub fn load(&mut self) -> bool {
         //list dir files
-        let dir = Path::new(&self.store_path);
-        if let Ok(ls) = fs::read_dir(dir) {
-            let files: Vec<_> = ls
-                .filter_map(Result::ok)
-                .map(|entry| entry.path())
-                .collect();
-            return self.do_load(files);
-        }
-        true
+        let dirs: Vec<String> = match self.store_path_selector {
+            // The files of all paths make up one queue, ordered by their names
+            Some(ref selector) => selector.load_paths().cloned().collect(),
+            None => vec![self.store_path.clone()],
+        };
+        let files: Vec<_> = dirs
+            .iter()
+            .filter_map(|dir| fs::read_dir(Path::new(dir)).ok())
+            .flat_map(|ls| ls.filter_map(Result::ok).map(|entry| entry.path()))
+            .collect();
+        self.do_load(files)
     }
 
     pub fn commit(&self, commit_least_pages: i32) -> bool {
@@ -196,6 +222,11 @@ impl MappedFileQueue {
     }
 
     pub fn try_create_mapped_file(&mut self, create_offset: u64) -> Option<Arc<DefaultMappedFile>> {
+        if let Some(ref selector) = self.store_path_selector {
+            let (next_file_path, next_next_file_path) =
+                selector.select(create_offset, self.mapped_file_size);
+            return self.do_create_mapped_file(next_file_path, next_next_file_path);
+        }
         let next_file_path =
             PathBuf::from(self.store_path.clone()).join(offset_to_file_name(create_offset));
         let next_next_file_path = PathBuf::from(self.store_path.clone())
@@ -206,16 +237,18 @@ impl MappedFileQueue {
     fn do_create_mapped_file(
         &mut self,
         next_file_path: PathBuf,
-        _next_next_file_path: PathBuf,
+        next_next_file_path: PathBuf,
     ) -> Option<Arc<DefaultMappedFile>> {
         let mut mapped_file = match self.allocate_mapped_file_service {
             None => DefaultMappedFile::new(
                 next_file_path.to_string_lossy().to_string(),
                 self.mapped_file_size,
             ),
-            Some(ref _value) => {
-                unimplemented!()
-            }
+            Some(ref service) => service.put_request_and_return_mapped_file(
+                next_file_path.to_string_lossy().to_string(),
+                next_next_file_path.to_string_lossy().to_string(),
+                self.mapped_file_size,
+            )?,
         };
 
         if self.mapped_files.read().is_empty() {
@@ -261,6 +294,7 @@ impl MappedFileQueue {
                 }
             }
         }
+        self.delete_expired_file(will_remove_files);
     }
 
     pub fn get_max_offset(&self) -> i64 {
@@ -284,10 +318,7 @@ impl MappedFileQueue {
     }
 
     pub(crate) fn delete_expired_file(&mut self, files: Vec<Arc<DefaultMappedFile>>) {
-        let mut files = files;
-        let read_guard = self.mapped_files.read();
         if !files.is_empty() {
-            files.retain(|mf| read_guard.contains(mf));
             self.mapped_files.write().retain(|mf| !files.contains(mf));
         }
     }
@@ -298,9 +329,15 @@ impl MappedFileQueue {
         }
         self.mapped_files.write().clear();
         self.set_flushed_where(0);
-        let path = PathBuf::from(&self.store_path);
-        if path.is_dir() {
-            let _ = fs::remove_dir_all(path);
+        let store_paths = match self.store_path_selector {
+            Some(ref selector) => selector.store_paths().to_vec(),
+            None => vec![self.store_path.clone()],
+        };
+        for store_path in store_paths {
+            let path = PathBuf::from(store_path);
+            if path.is_dir() {
+                let _ = fs::remove_dir_all(path);
+            }
         }
     }
 
@@ -472,4 +509,36 @@ mod tests {
         assert!(queue.load());
         assert_eq!(queue.mapped_files.read().len(), 1);
     }
+
+    #[test]
+    fn test_multi_path_load_and_create() {
+        let dirs = [tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap()];
+        let paths: Vec<String> = dirs
+            .iter()
+            .map(|dir| dir.path().to_string_lossy().into_owned())
+            .collect();
+        fs::write(
+            dirs[1].path().join(offset_to_file_name(1024)),
+            vec![0u8; 1024],
+        )
+        .unwrap();
+        fs::write(dirs[0].path().join(offset_to_file_name(0)), vec![0u8; 1024]).unwrap();
+
+        let mut queue = MappedFileQueue::new_multi_path(
+            StorePathSelector::new(paths.clone(), vec![], false),
+            1024,
+            None,
+        );
+        assert!(queue.load());
+        let loaded: Vec<u64> = queue
+            .mapped_files
+            .read()
+            .iter()
+            .map(|file| file.get_file_from_offset())
+            .collect();
+        assert_eq!(loaded, vec![0, 1024]);
+
+        let created = queue.try_create_mapped_file(2048).unwrap();
+        assert!(created.get_file_name().starts_with(paths[0].as_str()));
+    }
 }
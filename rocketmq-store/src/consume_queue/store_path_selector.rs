@@ -0,0 +1,180 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use rocketmq_common::UtilAll::offset_to_file_name;
+
+use crate::utils::store_util::StoreUtil;
+
+/// Spreads the mapped files of a queue over several store paths, e.g. one per data disk.
+///
+/// New files go to the path on the disk with the most free space, or round-robin over the
+/// paths. Files are also loaded from read-only paths, which never receive new files.
+#[derive(Clone, Default)]
+pub struct StorePathSelector {
+    store_paths: Vec<String>,
+    read_only_store_paths: Vec<String>,
+    select_by_free_space: bool,
+    /// Path picked for the file pre-allocated ahead of the file roll, with its start offset,
+    /// so the file is created where it was pre-allocated.
+    planned: Arc<Mutex<Option<(u64, String)>>>,
+}
+
+impl StorePathSelector {
+    pub fn new(
+        store_paths: Vec<String>,
+        read_only_store_paths: Vec<String>,
+        select_by_free_space: bool,
+    ) -> Self {
+        Self {
+            store_paths,
+            read_only_store_paths,
+            select_by_free_space,
+            planned: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Paths the existing files are loaded from.
+    pub fn load_paths(&self) -> impl Iterator<Item = &String> {
+        self.store_paths
+            .iter()
+            .chain(self.read_only_store_paths.iter())
+    }
+
+    pub fn store_paths(&self) -> &[String] {
+        &self.store_paths
+    }
+
+    /// Paths of the file starting at `create_offset` and of the file after it.
+    pub fn select(&self, create_offset: u64, mapped_file_size: u64) -> (PathBuf, PathBuf) {
+        self.select_with(
+            create_offset,
+            mapped_file_size,
+            StoreUtil::get_disk_free_space,
+        )
+    }
+
+    fn select_with(
+        &self,
+        create_offset: u64,
+        mapped_file_size: u64,
+        free_space: impl Fn(&str) -> Option<u64>,
+    ) -> (PathBuf, PathBuf) {
+        let next_next_offset = create_offset + mapped_file_size;
+        let mut planned = self.planned.lock();
+        let dir = match planned.take() {
+            Some((offset, dir)) if offset == create_offset => dir,
+            _ => self.pick(create_offset, mapped_file_size, &free_space),
+        };
+        let next_next_dir = self.pick(next_next_offset, mapped_file_size, &free_space);
+        let paths = (
+            PathBuf::from(&dir).join(offset_to_file_name(create_offset)),
+            PathBuf::from(&next_next_dir).join(offset_to_file_name(next_next_offset)),
+        );
+        *planned = Some((next_next_offset, next_next_dir));
+        paths
+    }
+
+    fn pick(
+        &self,
+        create_offset: u64,
+        mapped_file_size: u64,
+        free_space: &impl Fn(&str) -> Option<u64>,
+    ) -> String {
+        let round_robin =
+            (create_offset / mapped_file_size.max(1)) as usize % self.store_paths.len();
+        if self.select_by_free_space {
+            // Ties, e.g. paths on the same disk, fall back to round-robin
+            let most_free = self
+                .store_paths
+                .iter()
+                .enumerate()
+                .map(|(index, path)| {
+                    let distance =
+                        (index + self.store_paths.len() - round_robin) % self.store_paths.len();
+                    (
+                        free_space(path).unwrap_or(0),
+                        std::cmp::Reverse(distance),
+                        path,
+                    )
+                })
+                .max();
+            if let Some((_, _, path)) = most_free {
+                return path.clone();
+            }
+        }
+        self.store_paths[round_robin].clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn data_paths_selector(select_by_free_space: bool) -> StorePathSelector {
+        StorePathSelector::new(
+            vec![
+                "/data1".to_string(),
+                "/data2".to_string(),
+                "/data3".to_string(),
+            ],
+            vec![],
+            select_by_free_space,
+        )
+    }
+
+    #[test]
+    fn round_robin_over_the_paths() {
+        let selector = data_paths_selector(false);
+        let (next, next_next) = selector.select_with(1024, 1024, |_| None);
+        assert_eq!(
+            next,
+            PathBuf::from("/data2").join(offset_to_file_name(1024))
+        );
+        assert_eq!(
+            next_next,
+            PathBuf::from("/data3").join(offset_to_file_name(2048))
+        );
+        let (next, _) = selector.select_with(3072, 1024, |_| None);
+        assert_eq!(
+            next,
+            PathBuf::from("/data1").join(offset_to_file_name(3072))
+        );
+    }
+
+    #[test]
+    fn picks_the_most_free_path_and_keeps_the_pre_allocated_one() {
+        let selector = data_paths_selector(true);
+        let free = |path: &str| Some(if path == "/data3" { 500 } else { 100 });
+        let (next, next_next) = selector.select_with(0, 1024, free);
+        assert_eq!(next.parent().unwrap(), PathBuf::from("/data3"));
+        assert_eq!(next_next.parent().unwrap(), PathBuf::from("/data3"));
+
+        // The file after was pre-allocated on /data3, it stays there even though /data1 has
+        // more free space now
+        let free = |path: &str| Some(if path == "/data1" { 900 } else { 100 });
+        let (next, next_next) = selector.select_with(1024, 1024, free);
+        assert_eq!(next.parent().unwrap(), PathBuf::from("/data3"));
+        assert_eq!(next_next.parent().unwrap(), PathBuf::from("/data1"));
+
+        // Equal free space is spread round-robin
+        let (next, _) = data_paths_selector(true).select_with(1024, 1024, |_| Some(100));
+        assert_eq!(next.parent().unwrap(), PathBuf::from("/data2"));
+    }
+}
@@ -14,7 +14,7 @@
  * See the License for the specific language governing permissions and
  * limitations under the License.
  */
-use rocketmq_common::common::message::message_ext::MessageExt;
+use rocketmq_common::common::message::message_ext_broker_inner::MessageExtBrokerInner;
 
 use crate::base::message_result::PutMessageResult;
 
@@ -24,16 +24,21 @@ pub trait PutMessageHook {
     fn hook_name(&self) -> String;
 
     /// Execute before putting a message.
-    /// For example, message verification or special message transformation.
+    /// For example, message verification or special message transformation, such as
+    /// rewriting the topic of a delay message. Hooks run in registration order and the
+    /// first one returning a result rejects the message.
     ///
     /// # Arguments
     ///
-    /// * `msg` - The message to be put
+    /// * `msg` - The message to be put, hooks may modify it
     ///
     /// # Returns
     ///
-    /// The result of putting the message
-    fn execute_before_put_message(&self, msg: &MessageExt) -> Option<PutMessageResult>;
+    /// The result of putting the message, `None` to continue with the next hook
+    fn execute_before_put_message(
+        &self,
+        msg: &mut MessageExtBrokerInner,
+    ) -> Option<PutMessageResult>;
 }
 
 /// Alias for `Arc<dyn PutMessageHook>`.
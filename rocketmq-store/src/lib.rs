@@ -28,6 +28,7 @@ mod kv;
 pub mod log_file;
 pub(crate) mod message_encoder;
 pub mod message_store;
+pub mod plugin;
 mod queue;
 pub(crate) mod services;
 pub mod stats;
@@ -19,11 +19,15 @@ use std::error::Error;
 use std::sync::Arc;
 
 use parking_lot::RwLock;
+use rocketmq_common::common::boundary_type::BoundaryType;
 use rocketmq_common::common::message::message_batch::MessageExtBatch;
 use rocketmq_common::common::message::message_ext::MessageExt;
 use rocketmq_common::common::message::message_ext_broker_inner::MessageExtBrokerInner;
+use rocketmq_common::common::offset::CommitLogOffset;
+use rocketmq_common::common::offset::QueueOffset;
 use rocketmq_common::TimeUtils::get_current_millis;
 
+use crate::base::commit_log_dispatcher::BoxedCommitLogDispatcher;
 use crate::base::dispatch_request::DispatchRequest;
 use crate::base::get_message_result::GetMessageResult;
 use crate::base::message_result::PutMessageResult;
@@ -74,14 +78,21 @@ pub trait RocketMQMessageStore: Clone + 'static {
     /// # Returns
     ///
     /// The maximum physical offset.
-    fn get_max_phy_offset(&self) -> i64;
+    fn get_max_phy_offset(&self) -> CommitLogOffset;
+
+    /// Get the minimum physical offset.
+    ///
+    /// # Returns
+    ///
+    /// The minimum physical offset.
+    fn get_min_phy_offset(&self) -> CommitLogOffset;
 
     /// Set the broker initial maximum offset.
     ///
     /// # Arguments
     ///
     /// * `broker_init_max_offset` - The initial maximum offset of the broker.
-    fn set_broker_init_max_offset(&mut self, broker_init_max_offset: i64);
+    fn set_broker_init_max_offset(&mut self, broker_init_max_offset: CommitLogOffset);
 
     /// Get the current time in milliseconds.
     ///
@@ -170,6 +181,27 @@ pub trait RocketMQMessageStore: Clone + 'static {
     /// * `put_message_hook` - The hook to set.
     fn set_put_message_hook(&self, put_message_hook: BoxedPutMessageHook);
 
+    /// Get the list of commit log dispatchers, in dispatch order.
+    ///
+    /// # Returns
+    ///
+    /// An `Arc` containing a read-write lock around a vector of boxed commit log dispatchers.
+    fn get_dispatcher_list(&self) -> Arc<RwLock<Vec<BoxedCommitLogDispatcher>>>;
+
+    /// Register a commit log dispatcher, called after the existing dispatchers.
+    ///
+    /// # Arguments
+    ///
+    /// * `dispatcher` - The dispatcher to register.
+    fn add_dispatcher(&self, dispatcher: BoxedCommitLogDispatcher);
+
+    /// Register a commit log dispatcher, called before the existing dispatchers.
+    ///
+    /// # Arguments
+    ///
+    /// * `dispatcher` - The dispatcher to register.
+    fn add_first_dispatcher(&self, dispatcher: BoxedCommitLogDispatcher);
+
     /// Get the broker statistics manager.
     ///
     /// # Returns
@@ -180,6 +212,10 @@ pub trait RocketMQMessageStore: Clone + 'static {
     /// Dispatch bytes that are behind.
     fn dispatch_behind_bytes(&self) -> i64;
 
+    /// Bytes written to the commit log but not flushed to disk yet, including the data still to
+    /// be committed from the transient store pool.
+    fn flush_behind_bytes(&self) -> i64;
+
     /// Get the minimum offset in the queue.
     ///
     /// # Arguments
@@ -190,7 +226,7 @@ pub trait RocketMQMessageStore: Clone + 'static {
     /// # Returns
     ///
     /// The minimum offset in the queue.
-    fn get_min_offset_in_queue(&self, topic: &str, queue_id: i32) -> i64;
+    fn get_min_offset_in_queue(&self, topic: &str, queue_id: i32) -> QueueOffset;
 
     /// Get the maximum offset in the queue.
     ///
@@ -202,7 +238,7 @@ pub trait RocketMQMessageStore: Clone + 'static {
     /// # Returns
     ///
     /// The maximum offset in the queue.
-    fn get_max_offset_in_queue(&self, topic: &str, queue_id: i32) -> i64;
+    fn get_max_offset_in_queue(&self, topic: &str, queue_id: i32) -> QueueOffset;
 
     /// Get the maximum committed offset in the queue.
     ///
@@ -215,8 +251,47 @@ pub trait RocketMQMessageStore: Clone + 'static {
     /// # Returns
     ///
     /// The maximum committed offset in the queue.
-    fn get_max_offset_in_queue_committed(&self, topic: &str, queue_id: i32, committed: bool)
-        -> i64;
+    fn get_max_offset_in_queue_committed(
+        &self,
+        topic: &str,
+        queue_id: i32,
+        committed: bool,
+    ) -> QueueOffset;
+
+    /// Look up the offset of a message in the queue by its store timestamp.
+    ///
+    /// # Arguments
+    ///
+    /// * `topic` - The topic name.
+    /// * `queue_id` - The queue identifier.
+    /// * `timestamp` - The store timestamp to search, in milliseconds.
+    /// * `boundary_type` - `Lower` for the first message stored at or after `timestamp`, `Upper`
+    ///   for the last message stored at or before it.
+    ///
+    /// # Returns
+    ///
+    /// The queue offset. Messages whose commit log has been deleted are skipped; if no message
+    /// is on the requested side of `timestamp`, the max offset (`Lower`) or the earliest
+    /// readable offset (`Upper`) is returned.
+    fn get_offset_in_queue_by_time(
+        &self,
+        topic: &str,
+        queue_id: i32,
+        timestamp: i64,
+        boundary_type: BoundaryType,
+    ) -> QueueOffset;
+
+    /// Get the store time of the earliest message in the queue.
+    ///
+    /// # Arguments
+    ///
+    /// * `topic` - The topic name.
+    /// * `queue_id` - The queue identifier.
+    ///
+    /// # Returns
+    ///
+    /// The store timestamp of the earliest message still readable, -1 if there is none.
+    fn get_earliest_message_time_in_queue(&self, topic: &str, queue_id: i32) -> i64;
 
     /// Get a message asynchronously.
     ///
@@ -424,6 +499,15 @@ pub trait RocketMQMessageStore: Clone + 'static {
     /// * `i32` - The number of remaining transient store buffers.
     fn remain_transient_store_buffer_nums(&self) -> i32;
 
+    /// Check if the transient store pool has run out of buffers.
+    ///
+    /// # Returns
+    ///
+    /// `true` if writes have no buffer left to go to; `false` otherwise.
+    fn is_transient_store_pool_deficient(&self) -> bool {
+        self.remain_transient_store_buffer_nums() == 0
+    }
+
     ///  Get remain how many data to commit
     /// @return
     /// * `i64` - remain how many data to commit.
@@ -40,6 +40,7 @@ use rocketmq_common::utils::queue_type_utils::QueueTypeUtils;
 use rocketmq_common::utils::time_utils;
 use rocketmq_common::CRC32Utils::crc32;
 use rocketmq_common::MessageDecoder::string_to_message_properties;
+use rocketmq_common::MessageDecoder::BORN_TIMESTAMP_POSITION;
 use rocketmq_common::MessageDecoder::MESSAGE_MAGIC_CODE_POSITION;
 use rocketmq_common::MessageDecoder::MESSAGE_MAGIC_CODE_V2;
 use rocketmq_common::MessageDecoder::SYSFLAG_POSITION;
@@ -54,6 +55,7 @@ use crate::base::append_message_callback::DefaultAppendMessageCallback;
 use crate::base::commit_log_dispatcher::CommitLogDispatcher;
 use crate::base::dispatch_request::DispatchRequest;
 use crate::base::flush_manager::FlushManager;
+use crate::base::ha_service::HAService;
 use crate::base::message_result::AppendMessageResult;
 use crate::base::message_result::PutMessageResult;
 use crate::base::message_status_enum::AppendMessageStatus;
@@ -66,6 +68,7 @@ use crate::base::topic_queue_lock::TopicQueueLock;
 use crate::config::broker_role::BrokerRole;
 use crate::config::message_store_config::MessageStoreConfig;
 use crate::consume_queue::mapped_file_queue::MappedFileQueue;
+use crate::consume_queue::store_path_selector::StorePathSelector;
 use crate::log_file::cold_data_check_service::ColdDataCheckService;
 use crate::log_file::flush_manager_impl::defalut_flush_manager::DefaultFlushManager;
 use crate::log_file::mapped_file::default_mapped_file_impl::DefaultMappedFile;
@@ -75,6 +78,7 @@ use crate::message_store::default_message_store::CommitLogDispatcherDefault;
 use crate::message_store::default_message_store::DefaultMessageStore;
 use crate::queue::local_file_consume_queue_store::ConsumeQueueStore;
 use crate::queue::ConsumeQueueStoreTrait;
+use crate::services::allocate_mapped_file_service::AllocateMappedFileService;
 
 // Message's MAGIC CODE daa320a7
 pub const MESSAGE_MAGIC_CODE: i32 = -626843481;
@@ -86,6 +90,13 @@ pub const BLANK_MAGIC_CODE: i32 = -875286124;
 // PROPERTY_SEPARATOR]
 pub const CRC32_RESERVED_LEN: i32 = (MessageConst::PROPERTY_CRC32.len() + 1 + 10 + 1) as i32;
 
+/// A message waiting to be appended by the writer that holds `put_message_lock`.
+struct GroupAppendRequest {
+    msg: MessageExtBrokerInner,
+    put_message_context: PutMessageContext,
+    tx: tokio::sync::oneshot::Sender<(MessageExtBrokerInner, PutMessageResult)>,
+}
+
 struct PutMessageThreadLocal {
     encoder: RefCell<Option<MessageExtEncoder>>,
     key: RefCell<String>,
@@ -155,6 +166,19 @@ pub fn get_cq_type(
     QueueTypeUtils::get_cq_type(&option)
 }
 
+/// Status of a put once its flush and its replication settled: a replication failure wins over a
+/// flush failure as the client must hear about the slaves first, like the Java broker does.
+pub fn compose_put_message_status(
+    flush_status: PutMessageStatus,
+    replica_status: PutMessageStatus,
+) -> PutMessageStatus {
+    if replica_status != PutMessageStatus::PutOk {
+        replica_status
+    } else {
+        flush_status
+    }
+}
+
 pub fn get_message_num(
     topic_config_table: &Arc<parking_lot::Mutex<HashMap<String, TopicConfig>>>,
     msg_inner: &MessageExtBrokerInner,
@@ -188,6 +212,7 @@ pub struct CommitLog {
     store_checkpoint: Arc<StoreCheckpoint>,
     append_message_callback: Arc<DefaultAppendMessageCallback>,
     put_message_lock: Arc<tokio::sync::Mutex<()>>,
+    group_append_requests: Arc<parking_lot::Mutex<Vec<GroupAppendRequest>>>,
     topic_queue_lock: Arc<TopicQueueLock>,
     topic_config_table: Arc<parking_lot::Mutex<HashMap<String, TopicConfig>>>,
     consume_queue_store: ConsumeQueueStore,
@@ -195,6 +220,7 @@ pub struct CommitLog {
     //flush_manager: Arc<parking_lot::Mutex<DefaultFlushManager>>,
     begin_time_in_lock: Arc<AtomicU64>,
     cold_data_check_service: Arc<ColdDataCheckService>,
+    ha_service: Arc<parking_lot::RwLock<Option<Arc<dyn HAService>>>>,
 }
 
 impl CommitLog {
@@ -205,11 +231,29 @@ impl CommitLog {
         store_checkpoint: Arc<StoreCheckpoint>,
         topic_config_table: Arc<parking_lot::Mutex<HashMap<String, TopicConfig>>>,
         consume_queue_store: ConsumeQueueStore,
+        allocate_mapped_file_service: Option<AllocateMappedFileService>,
     ) -> Self {
         let enabled_append_prop_crc = message_store_config.enabled_append_prop_crc;
-        let store_path = message_store_config.get_store_path_commit_log();
+        let store_paths = message_store_config.get_store_paths_commit_log();
         let mapped_file_size = message_store_config.mapped_file_size_commit_log;
-        let mapped_file_queue = MappedFileQueue::new(store_path, mapped_file_size as u64, None);
+        let read_only_store_paths = message_store_config.get_read_only_commit_log_store_paths();
+        let mapped_file_queue = if store_paths.len() > 1 || !read_only_store_paths.is_empty() {
+            MappedFileQueue::new_multi_path(
+                StorePathSelector::new(
+                    store_paths,
+                    read_only_store_paths,
+                    message_store_config.commit_log_path_select_by_free_space,
+                ),
+                mapped_file_size as u64,
+                allocate_mapped_file_service,
+            )
+        } else {
+            MappedFileQueue::new(
+                message_store_config.get_store_path_commit_log(),
+                mapped_file_size as u64,
+                allocate_mapped_file_service,
+            )
+        };
         Self {
             mapped_file_queue: mapped_file_queue.clone(),
             message_store_config: message_store_config.clone(),
@@ -224,6 +268,7 @@ impl CommitLog {
                 topic_config_table.clone(),
             )),
             put_message_lock: Arc::new(Default::default()),
+            group_append_requests: Arc::new(Default::default()),
             topic_queue_lock: Arc::new(TopicQueueLock::new(
                 message_store_config.topic_queue_lock_num,
             )),
@@ -236,6 +281,7 @@ impl CommitLog {
             ))),
             begin_time_in_lock: Arc::new(AtomicU64::new(0)),
             cold_data_check_service: Arc::new(Default::default()),
+            ha_service: Arc::new(Default::default()),
         }
     }
 }
@@ -261,7 +307,11 @@ impl CommitLog {
         });
     }
 
-    pub fn shutdown(&mut self) {}
+    pub fn shutdown(&mut self) {
+        if let Some(service) = self.mapped_file_queue.allocate_mapped_file_service.as_ref() {
+            service.shutdown();
+        }
+    }
 
     pub fn destroy(&mut self) {}
 
@@ -284,6 +334,33 @@ impl CommitLog {
         }
     }
 
+    /// Store timestamp of the message at `offset`, -1 if its file has been deleted or the
+    /// offset is past the end of the commit log.
+    pub fn pickup_store_timestamp(&self, offset: i64, size: i32) -> i64 {
+        if offset < self.get_min_offset() || offset + size as i64 > self.get_max_offset() {
+            return -1;
+        }
+        let Some(result) = self.get_message(offset, size) else {
+            return -1;
+        };
+        let mut buffer = result.get_buffer();
+        if buffer.len() < SYSFLAG_POSITION + 4 {
+            return -1;
+        }
+        let sys_flag = (&buffer[SYSFLAG_POSITION..]).get_i32();
+        let born_host_length = if sys_flag & MessageSysFlag::BORNHOST_V6_FLAG == 0 {
+            8
+        } else {
+            20
+        };
+        let store_timestamp_position = BORN_TIMESTAMP_POSITION + 8 + born_host_length;
+        if buffer.len() < store_timestamp_position + 8 {
+            return -1;
+        }
+        buffer.advance(store_timestamp_position);
+        buffer.get_i64()
+    }
+
     pub fn set_confirm_offset(&mut self, phy_offset: i64) {
         self.confirm_offset = phy_offset;
         self.store_checkpoint
@@ -519,14 +596,6 @@ impl CommitLog {
 
         let topic_queue_key = generate_key(&msg);
 
-        let mut _unlock_mapped_file = None;
-        let mut mapped_file = self.mapped_file_queue.get_last_mapped_file();
-        // current offset is physical offset
-        let curr_offset = if let Some(ref mapped_file_inner) = mapped_file {
-            mapped_file_inner.get_wrote_position() as u64 + mapped_file_inner.get_file_from_offset()
-        } else {
-            0
-        };
         let need_ack_nums = self.message_store_config.in_sync_replicas;
         let need_handle_ha = self.need_handle_ha(&msg);
         if need_handle_ha && self.broker_config.enable_controller_mode {
@@ -538,8 +607,8 @@ impl CommitLog {
         let need_assign_offset = !(self.message_store_config.duplication_enable
             && self.message_store_config.broker_role != BrokerRole::Slave);
 
-        let topic_queue_lock = self
-            .topic_queue_lock
+        let topic_queue_locks = self.topic_queue_lock.clone();
+        let topic_queue_lock = topic_queue_locks
             .lock(topic_queue_key.as_str())
             .lock()
             .await;
@@ -554,54 +623,110 @@ impl CommitLog {
         }
         msg.encoded_buff = Some(encoded_buff);
         let put_message_context = PutMessageContext::new(topic_queue_key);
-        let lock = self.put_message_lock.lock().await;
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        self.group_append_requests.lock().push(GroupAppendRequest {
+            msg,
+            put_message_context,
+            tx,
+        });
+        // The first writer to get the lock appends every pending request, the others find their
+        // result already sent when they get it.
+        let put_message_lock = self.put_message_lock.clone();
+        let lock = put_message_lock.lock().await;
+        let requests = mem::take(&mut *self.group_append_requests.lock());
+        if !requests.is_empty() {
+            self.group_append(requests);
+        }
+        drop(lock);
+        let Ok((msg, put_message_result)) = rx.await else {
+            return PutMessageResult::new_default(PutMessageStatus::UnknownError);
+        };
+
+        if put_message_result.put_message_status() == PutMessageStatus::PutOk {
+            let message_num = get_message_num(&self.topic_config_table, &msg);
+            self.increase_offset(&msg, message_num);
+            drop(topic_queue_lock);
+            self.handle_disk_flush_and_ha(put_message_result, msg, need_ack_nums, need_handle_ha)
+                .await
+        } else {
+            put_message_result
+        }
+    }
+
+    /// Appends a group of encoded messages in one locked region, must be called with
+    /// `put_message_lock` held.
+    fn group_append(&mut self, requests: Vec<GroupAppendRequest>) {
         let begin_lock_timestamp = time_utils::get_current_millis();
         self.begin_time_in_lock
             .store(begin_lock_timestamp, std::sync::atomic::Ordering::Release);
         let start_time = Instant::now();
-        // Here settings are stored timestamp, in order to ensure an orderly global
-        if !self.message_store_config.duplication_enable {
-            msg.message_ext_inner.store_timestamp = begin_lock_timestamp as i64;
+        let request_num = requests.len();
+        let mut wrote_bytes = 0;
+        let mut mapped_file = self.mapped_file_queue.get_last_mapped_file();
+        for request in requests {
+            let GroupAppendRequest {
+                mut msg,
+                put_message_context,
+                tx,
+            } = request;
+            // Here settings are stored timestamp, in order to ensure an orderly global
+            if !self.message_store_config.duplication_enable {
+                msg.message_ext_inner.store_timestamp = begin_lock_timestamp as i64;
+            }
+            let put_message_result =
+                self.append_in_lock(&mut mapped_file, &mut msg, &put_message_context);
+            if let Some(result) = put_message_result.append_message_result() {
+                wrote_bytes += result.wrote_bytes as i64;
+            }
+            let _ = tx.send((msg, put_message_result));
+        }
+        let elapsed_time_in_lock = start_time.elapsed().as_millis() as u64;
+        self.begin_time_in_lock
+            .store(0, std::sync::atomic::Ordering::Release);
+        if elapsed_time_in_lock > 500 {
+            warn!(
+                "[NOTIFYME]putMessage in lock cost time(ms)={}, messageNum={}, wroteBytes={}",
+                elapsed_time_in_lock, request_num, wrote_bytes,
+            );
         }
+    }
 
+    fn append_in_lock(
+        &mut self,
+        mapped_file: &mut Option<Arc<DefaultMappedFile>>,
+        msg: &mut MessageExtBrokerInner,
+        put_message_context: &PutMessageContext,
+    ) -> PutMessageResult {
         if mapped_file.is_none() || mapped_file.as_ref().unwrap().is_full() {
-            mapped_file = self
+            *mapped_file = self
                 .mapped_file_queue
                 .get_last_mapped_file_mut_start_offset(0, true);
         }
-
-        if mapped_file.is_none() {
-            drop(lock);
-            drop(topic_queue_lock);
+        let Some(file) = mapped_file.as_ref() else {
             error!(
                 "create mapped file error, topic: {}  clientAddr: {}",
                 msg.topic(),
                 msg.born_host()
             );
-            self.begin_time_in_lock
-                .store(0, std::sync::atomic::Ordering::Release);
             return PutMessageResult::new_default(PutMessageStatus::CreateMappedFileFailed);
-        }
+        };
 
-        let result = mapped_file.as_ref().unwrap().append_message(
-            &mut msg,
+        let result = file.append_message(
+            msg,
             self.append_message_callback.as_ref(),
-            &put_message_context,
+            put_message_context,
         );
-        let put_message_result = match result.status {
+        match result.status {
             AppendMessageStatus::PutOk => {
                 //onCommitLogAppend(msg, result, mappedFile); in java not support this version
                 PutMessageResult::new_append_result(PutMessageStatus::PutOk, Some(result))
             }
             AppendMessageStatus::EndOfFile => {
                 //onCommitLogAppend(msg, result, mappedFile); in java not support this version
-                _unlock_mapped_file = mapped_file;
-                mapped_file = self
+                *mapped_file = self
                     .mapped_file_queue
                     .get_last_mapped_file_mut_start_offset(0, true);
-                if mapped_file.is_none() {
-                    self.begin_time_in_lock
-                        .store(0, std::sync::atomic::Ordering::Release);
+                let Some(file) = mapped_file.as_ref() else {
                     error!(
                         "create mapped file error, topic: {}  clientAddr: {}",
                         msg.topic(),
@@ -611,11 +736,11 @@ impl CommitLog {
                         PutMessageStatus::CreateMappedFileFailed,
                         Some(result),
                     );
-                }
-                let result = mapped_file.as_ref().unwrap().append_message(
-                    &mut msg,
+                };
+                let result = file.append_message(
+                    msg,
                     self.append_message_callback.as_ref(),
-                    &put_message_context,
+                    put_message_context,
                 );
                 if AppendMessageStatus::PutOk == result.status {
                     PutMessageResult::new_append_result(PutMessageStatus::PutOk, Some(result))
@@ -628,38 +753,11 @@ impl CommitLog {
             }
             AppendMessageStatus::MessageSizeExceeded
             | AppendMessageStatus::PropertiesSizeExceeded => {
-                self.begin_time_in_lock
-                    .store(0, std::sync::atomic::Ordering::Release);
                 PutMessageResult::new_append_result(PutMessageStatus::MessageIllegal, Some(result))
             }
             AppendMessageStatus::UnknownError => {
-                self.begin_time_in_lock
-                    .store(0, std::sync::atomic::Ordering::Release);
                 PutMessageResult::new_append_result(PutMessageStatus::UnknownError, Some(result))
             }
-        };
-        let elapsed_time_in_lock = start_time.elapsed().as_millis() as u64;
-        drop(lock);
-        self.begin_time_in_lock
-            .store(0, std::sync::atomic::Ordering::Release);
-        if elapsed_time_in_lock > 500 {
-            warn!(
-                "[NOTIFYME]putMessage in lock cost time(ms)={}, bodyLength={} \
-                 AppendMessageResult={}",
-                elapsed_time_in_lock,
-                msg.body_len(),
-                put_message_result.append_message_result().as_ref().unwrap(),
-            );
-        }
-
-        if put_message_result.put_message_status() == PutMessageStatus::PutOk {
-            let message_num = get_message_num(&self.topic_config_table, &msg);
-            self.increase_offset(&msg, message_num);
-            drop(topic_queue_lock);
-            self.handle_disk_flush_and_ha(put_message_result, msg, need_ack_nums, need_handle_ha)
-                .await
-        } else {
-            put_message_result
         }
     }
 
@@ -683,6 +781,14 @@ impl CommitLog {
         }
     }
 
+    /// Replication service the messages of a `SyncMaster` wait on, without one they are
+    /// answered with `SlaveNotAvailable` when more than one in-sync replica is required.
+    pub fn set_ha_service(&self, ha_service: Option<Arc<dyn HAService>>) {
+        *self.ha_service.write() = ha_service;
+    }
+
+    /// Resolves once both the flush and the replication of the appended message settled, the
+    /// status of the result tells the first of them that failed.
     async fn handle_disk_flush_and_ha(
         &mut self,
         mut put_message_result: PutMessageResult,
@@ -690,41 +796,19 @@ impl CommitLog {
         need_ack_nums: u32,
         need_handle_ha: bool,
     ) -> PutMessageResult {
-        let commit_log = Arc::new(self.clone());
-        let commit_log_cloned = commit_log.clone();
-        let put_message_result_clone =
-            Arc::new(put_message_result.append_message_result().unwrap().clone());
-        let put_message_result_cloned = put_message_result_clone.clone();
-        let disk_flush_handle = tokio::spawn(async move {
-            commit_log
-                .handle_disk_flush(put_message_result_clone.as_ref(), &msg)
-                .await
-        });
-
-        let replica_result_handle = tokio::spawn(async move {
-            if need_handle_ha {
-                commit_log_cloned
-                    .handle_ha(put_message_result_cloned.as_ref(), need_ack_nums)
-                    .await
-            } else {
-                PutMessageStatus::PutOk
-            }
-        });
-
-        match disk_flush_handle.await {
-            Ok(status) => {
-                put_message_result.set_put_message_status(status);
-                if status == PutMessageStatus::PutOk {
-                    if let Ok(replica_status) = replica_result_handle.await {
-                        put_message_result.set_put_message_status(replica_status);
-                    }
+        let Some(append_result) = put_message_result.append_message_result().cloned() else {
+            return put_message_result;
+        };
+        let (flush_status, replica_status) =
+            tokio::join!(self.handle_disk_flush(&append_result, &msg), async {
+                if need_handle_ha {
+                    self.handle_ha(&append_result, need_ack_nums).await
+                } else {
+                    PutMessageStatus::PutOk
                 }
-            }
-            Err(error) => {
-                put_message_result.set_put_message_status(PutMessageStatus::FlushDiskTimeout);
-            }
-        }
-
+            });
+        put_message_result
+            .set_put_message_status(compose_put_message_status(flush_status, replica_status));
         put_message_result
     }
 
@@ -736,10 +820,23 @@ impl CommitLog {
         if need_ack_nums <= 1 {
             return PutMessageStatus::PutOk;
         }
-
-        //HA service to do unimplemented
-
-        PutMessageStatus::PutOk
+        let Some(ha_service) = self.ha_service.read().clone() else {
+            return PutMessageStatus::SlaveNotAvailable;
+        };
+        let next_offset = put_message_result.wrote_offset + put_message_result.wrote_bytes as i64;
+        if !ha_service.is_slave_ok(next_offset) {
+            return PutMessageStatus::SlaveNotAvailable;
+        }
+        // Java waits for the slaves as long as for a sync flush, the slave timeout is unused
+        let acked = tokio::time::timeout(
+            std::time::Duration::from_millis(self.message_store_config.sync_flush_timeout),
+            ha_service.wait_for_replicas(next_offset, need_ack_nums),
+        )
+        .await;
+        match acked {
+            Ok(Ok(true)) => PutMessageStatus::PutOk,
+            _ => PutMessageStatus::FlushSlaveTimeout,
+        }
     }
 
     async fn handle_disk_flush(
@@ -775,7 +872,7 @@ impl CommitLog {
 
     fn on_commit_log_dispatch(
         &mut self,
-        request: &DispatchRequest,
+        request: &mut DispatchRequest,
         do_dispatch: bool,
         is_recover: bool,
         is_file_end: bool,
@@ -829,7 +926,7 @@ impl CommitLog {
                     break;
                 }
                 let mut msg_bytes = msg.unwrap();
-                let dispatch_request = check_message_and_return_size(
+                let mut dispatch_request = check_message_and_return_size(
                     &mut msg_bytes,
                     check_crc_on_recover,
                     check_dup_info,
@@ -840,12 +937,12 @@ impl CommitLog {
                 if dispatch_request.success && dispatch_request.msg_size > 0 {
                     last_valid_msg_phy_offset = process_offset + mapped_file_offset;
                     mapped_file_offset += dispatch_request.msg_size as u64;
-                    self.on_commit_log_dispatch(&dispatch_request, do_dispatch, true, false);
+                    self.on_commit_log_dispatch(&mut dispatch_request, do_dispatch, true, false);
                 } else if dispatch_request.success && dispatch_request.msg_size == 0 {
                     // Come the end of the file, switch to the next file Since the
                     // return 0 representatives met last hole,
                     // this can not be included in truncate offset
-                    self.on_commit_log_dispatch(&dispatch_request, do_dispatch, true, true);
+                    self.on_commit_log_dispatch(&mut dispatch_request, do_dispatch, true, true);
                     index += 1;
                     if index >= mapped_files_inner.len() {
                         info!(
@@ -983,7 +1080,7 @@ impl CommitLog {
                     break;
                 }
                 let mut msg_bytes = msg.unwrap();
-                let dispatch_request = check_message_and_return_size(
+                let mut dispatch_request = check_message_and_return_size(
                     &mut msg_bytes,
                     check_crc_on_recover,
                     check_dup_info,
@@ -1002,7 +1099,7 @@ impl CommitLog {
                             <= self.get_confirm_offset()
                         {
                             self.on_commit_log_dispatch(
-                                &dispatch_request,
+                                &mut dispatch_request,
                                 do_dispatch,
                                 true,
                                 false,
@@ -1011,13 +1108,18 @@ impl CommitLog {
                                 dispatch_request.commit_log_offset as u64 + size as u64;
                         }
                     } else {
-                        self.on_commit_log_dispatch(&dispatch_request, do_dispatch, true, false);
+                        self.on_commit_log_dispatch(
+                            &mut dispatch_request,
+                            do_dispatch,
+                            true,
+                            false,
+                        );
                     }
                 } else if dispatch_request.success && dispatch_request.msg_size == 0 {
                     // Come the end of the file, switch to the next file Since the
                     // return 0 representatives met last hole,
                     // this can not be included in truncate offset
-                    self.on_commit_log_dispatch(&dispatch_request, do_dispatch, true, true);
+                    self.on_commit_log_dispatch(&mut dispatch_request, do_dispatch, true, true);
                     index += 1;
                     if index >= mapped_files_inner.len() {
                         info!(
@@ -1320,6 +1422,55 @@ pub fn check_message_and_return_size(
     dispatch_request
 }
 
+/// Recomputes the body CRC of the message at the head of `buffer` and returns the stored and
+/// the recomputed value when they differ. Blank entries, illegal magic codes and truncated
+/// buffers yield `None`, those are left to [`check_message_and_return_size`].
+pub fn body_crc_mismatch(buffer: &[u8]) -> Option<(u32, u32)> {
+    let mut bytes = buffer;
+    if bytes.remaining() < 12 {
+        return None;
+    }
+    let _total_size = bytes.get_i32();
+    let magic_code = bytes.get_i32();
+    if magic_code != MESSAGE_MAGIC_CODE && magic_code != MESSAGE_MAGIC_CODE_V2 {
+        return None;
+    }
+    let body_crc = bytes.get_u32();
+    // queueId, flag, queueOffset, physicalOffset
+    let header_len = 4 + 4 + 8 + 8;
+    if bytes.remaining() < header_len + 4 {
+        return None;
+    }
+    bytes.advance(header_len);
+    let sys_flag = bytes.get_i32();
+    let born_host_len = if sys_flag & MessageSysFlag::BORNHOST_V6_FLAG == 0 {
+        8
+    } else {
+        20
+    };
+    let store_host_len = if sys_flag & MessageSysFlag::STOREHOSTADDRESS_V6_FLAG == 0 {
+        8
+    } else {
+        20
+    };
+    // bornTimestamp, bornHost, storeTimestamp, storeHost, reconsumeTimes, preparedOffset
+    let header_len = 8 + born_host_len + 8 + store_host_len + 4 + 8;
+    if bytes.remaining() < header_len + 4 {
+        return None;
+    }
+    bytes.advance(header_len);
+    let body_len = bytes.get_i32();
+    if body_len <= 0 || bytes.remaining() < body_len as usize {
+        return None;
+    }
+    let crc = crc32(&bytes[..body_len as usize]);
+    if crc != body_crc {
+        Some((body_crc, crc))
+    } else {
+        None
+    }
+}
+
 fn set_batch_size_if_needed(
     properties_map: &HashMap<String, String>,
     dispatch_request: &mut DispatchRequest,
@@ -1410,3 +1561,122 @@ impl Swappable for CommitLog {
         todo!()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use super::*;
+    use crate::config::flush_disk_type::FlushDiskType;
+    use crate::store::running_flags::RunningFlags;
+
+    fn new_commit_log(store_path_root_dir: &str, mapped_file_size: usize) -> CommitLog {
+        let message_store_config = Arc::new(MessageStoreConfig {
+            store_path_root_dir: store_path_root_dir.to_string(),
+            mapped_file_size_commit_log: mapped_file_size,
+            flush_disk_type: FlushDiskType::AsyncFlush,
+            ..MessageStoreConfig::default()
+        });
+        let broker_config = Arc::new(BrokerConfig::default());
+        let topic_config_table = Arc::new(parking_lot::Mutex::new(HashMap::new()));
+        let store_checkpoint =
+            Arc::new(StoreCheckpoint::new(format!("{}/checkpoint", store_path_root_dir)).unwrap());
+        let consume_queue_store = ConsumeQueueStore::new(
+            message_store_config.clone(),
+            broker_config.clone(),
+            topic_config_table.clone(),
+            Arc::new(RunningFlags::new()),
+            store_checkpoint.clone(),
+            None,
+        );
+        CommitLog::new(
+            message_store_config,
+            broker_config,
+            &CommitLogDispatcherDefault::new(vec![]),
+            store_checkpoint,
+            topic_config_table,
+            consume_queue_store,
+            None,
+        )
+    }
+
+    fn message(queue_id: i32) -> MessageExtBrokerInner {
+        let mut msg = MessageExtBrokerInner::default();
+        msg.message_ext_inner.message.topic = "TopicTest".to_string();
+        msg.message_ext_inner.message.body = Some(Bytes::from(vec![b'a'; 900]));
+        msg.message_ext_inner.queue_id = queue_id;
+        msg
+    }
+
+    #[tokio::test]
+    async fn concurrent_puts_share_a_mapped_file_and_roll_over_to_the_next() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let commit_log = new_commit_log(temp_dir.path().to_str().unwrap(), 4096);
+
+        // hold the lock until all six messages wait, the writer getting it appends all of them
+        let put_message_lock = commit_log.put_message_lock.clone();
+        let lock = put_message_lock.lock().await;
+        let puts = (0..6).map(|queue_id| {
+            let mut commit_log = commit_log.clone();
+            tokio::spawn(async move { commit_log.put_message(message(queue_id)).await })
+        });
+        let puts = tokio::spawn(futures::future::join_all(puts));
+        while commit_log.group_append_requests.lock().len() < 6 {
+            tokio::task::yield_now().await;
+        }
+        drop(lock);
+        let results = puts
+            .await
+            .unwrap()
+            .into_iter()
+            .map(Result::unwrap)
+            .collect::<Vec<_>>();
+
+        let mut wrote_offsets = results
+            .iter()
+            .map(|result| {
+                assert_eq!(result.put_message_status(), PutMessageStatus::PutOk);
+                let append_result = result.append_message_result().unwrap();
+                (append_result.wrote_offset, append_result.wrote_bytes as i64)
+            })
+            .collect::<Vec<_>>();
+        wrote_offsets.sort();
+        let (first_file, second_file): (Vec<_>, Vec<_>) = wrote_offsets
+            .into_iter()
+            .partition(|(wrote_offset, _)| *wrote_offset < 4096);
+        // four messages fill the first file, the rest go to the second one
+        assert_eq!(first_file.len(), 4);
+        assert_eq!(second_file.len(), 2);
+        for pair in first_file.windows(2).chain(second_file.windows(2)) {
+            let [(offset, bytes), (next_offset, _)] = pair else {
+                unreachable!()
+            };
+            assert_eq!(offset + bytes, *next_offset);
+        }
+        assert_eq!(second_file[0].0, 4096);
+        assert_eq!(commit_log.mapped_file_queue.get_mapped_files_size(), 2);
+        let (last_offset, last_bytes) = second_file.last().unwrap();
+        assert_eq!(commit_log.get_max_offset(), last_offset + last_bytes);
+    }
+
+    #[test]
+    fn compose_put_message_status_reports_replication_failures_first() {
+        let ok = PutMessageStatus::PutOk;
+        assert_eq!(compose_put_message_status(ok, ok), ok);
+        assert_eq!(
+            compose_put_message_status(PutMessageStatus::FlushDiskTimeout, ok),
+            PutMessageStatus::FlushDiskTimeout
+        );
+        assert_eq!(
+            compose_put_message_status(ok, PutMessageStatus::SlaveNotAvailable),
+            PutMessageStatus::SlaveNotAvailable
+        );
+        assert_eq!(
+            compose_put_message_status(
+                PutMessageStatus::FlushDiskTimeout,
+                PutMessageStatus::FlushSlaveTimeout
+            ),
+            PutMessageStatus::FlushSlaveTimeout
+        );
+    }
+}
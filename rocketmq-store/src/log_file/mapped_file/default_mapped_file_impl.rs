@@ -25,6 +25,7 @@ use std::sync::atomic::AtomicI32;
 use std::sync::atomic::AtomicI64;
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
+use std::time::Instant;
 
 use bytes::Bytes;
 use bytes::BytesMut;
@@ -470,6 +471,7 @@ impl MappedFile for DefaultMappedFile {
                     size,
                     mapped_file: Some(self),
                     is_in_cache: true,
+                    bytes: None,
                 })
             } else {
                 None
@@ -492,6 +494,7 @@ impl MappedFile for DefaultMappedFile {
                 size: read_position - pos,
                 mapped_file: Some(self),
                 is_in_cache: true,
+                bytes: None,
             })
         } else {
             None
@@ -638,15 +641,49 @@ impl MappedFile for DefaultMappedFile {
     }
 
     fn mlock(&self) {
-        todo!()
+        #[cfg(unix)]
+        if let Err(err) = self.get_mapped_file().lock() {
+            warn!("mlock {} failed: {}", self.file_name, err);
+        }
     }
 
     fn munlock(&self) {
-        todo!()
+        #[cfg(unix)]
+        if let Err(err) = self.get_mapped_file().unlock() {
+            warn!("munlock {} failed: {}", self.file_name, err);
+        }
     }
 
     fn warm_mapped_file(&self, flush_disk_type: FlushDiskType, pages: usize) {
-        todo!()
+        let begin_time = Instant::now();
+        let mmap = self.get_mapped_file_mut();
+        let page_size = OS_PAGE_SIZE as usize;
+        let mut flushed_page = 0;
+        // Touch one byte of every page so the kernel backs the whole mapping before the first
+        // append reaches it.
+        for (page, index) in (0..self.file_size as usize).step_by(page_size).enumerate() {
+            mmap[index] = 0;
+            if flush_disk_type == FlushDiskType::SyncFlush
+                && pages > 0
+                && page - flushed_page >= pages
+            {
+                flushed_page = page;
+                if let Err(err) = mmap.flush() {
+                    warn!("flush {} when warming failed: {}", self.file_name, err);
+                }
+            }
+        }
+        if flush_disk_type == FlushDiskType::SyncFlush {
+            if let Err(err) = mmap.flush() {
+                warn!("flush {} when warming failed: {}", self.file_name, err);
+            }
+        }
+        info!(
+            "mapped file warm-up done, mappedFile={}, costTime={}ms",
+            self.file_name,
+            begin_time.elapsed().as_millis()
+        );
+        self.mlock();
     }
 
     fn swap_map(&self) -> bool {
@@ -22,6 +22,7 @@ use std::fs;
 use std::path::PathBuf;
 use std::sync::atomic::AtomicBool;
 use std::sync::atomic::AtomicI64;
+use std::sync::atomic::AtomicU64;
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
 use std::thread;
@@ -30,12 +31,15 @@ use std::time::Instant;
 
 use bytes::Buf;
 use rocketmq_common::common::attribute::cleanup_policy::CleanupPolicy;
+use rocketmq_common::common::boundary_type::BoundaryType;
 use rocketmq_common::common::message::message_batch::MessageExtBatch;
 use rocketmq_common::common::message::message_ext::MessageExt;
 use rocketmq_common::common::mix_all::is_lmq;
 use rocketmq_common::common::mix_all::is_sys_consumer_group_for_no_cold_read_limit;
 use rocketmq_common::common::mix_all::MULTI_DISPATCH_QUEUE_SPLITTER;
 use rocketmq_common::common::mix_all::RETRY_GROUP_TOPIC_PREFIX;
+use rocketmq_common::common::offset::CommitLogOffset;
+use rocketmq_common::common::offset::QueueOffset;
 use rocketmq_common::utils::util_all;
 use rocketmq_common::CleanupPolicyUtils::get_delete_policy;
 use rocketmq_common::TimeUtils::get_current_millis;
@@ -58,10 +62,14 @@ use tracing::error;
 use tracing::info;
 use tracing::warn;
 
-use crate::base::allocate_mapped_file_service::AllocateMappedFileService;
+use crate::base::commit_log_dispatcher::BoxedCommitLogDispatcher;
 use crate::base::commit_log_dispatcher::CommitLogDispatcher;
+use crate::base::concurrent_dispatcher::ConcurrentDispatcher;
+use crate::base::corrupted_message_report::CorruptedMessageReport;
+use crate::base::corrupted_message_report::CorruptionSource;
 use crate::base::dispatch_request::DispatchRequest;
 use crate::base::get_message_result::GetMessageResult;
+use crate::base::ha_service::HAService;
 use crate::base::message_arriving_listener::MessageArrivingListener;
 use crate::base::message_result::PutMessageResult;
 use crate::base::message_status_enum::GetMessageStatus;
@@ -86,13 +94,16 @@ use crate::log_file::commit_log::CommitLog;
 use crate::log_file::mapped_file::MappedFile;
 use crate::log_file::MessageStore;
 use crate::log_file::MAX_PULL_MSG_SIZE;
+use crate::plugin::message_store_plugin::MessageStorePlugin;
 use crate::queue::build_consume_queue::CommitLogDispatcherBuildConsumeQueue;
 use crate::queue::local_file_consume_queue_store::ConsumeQueueStore;
 use crate::queue::ArcConsumeQueue;
 use crate::queue::ConsumeQueueStoreTrait;
+use crate::services::allocate_mapped_file_service::AllocateMappedFileService;
 use crate::stats::broker_stats_manager::BrokerStatsManager;
 use crate::store::running_flags::RunningFlags;
 use crate::store_path_config_helper::get_abort_file;
+use crate::store_path_config_helper::get_corrupted_message_report;
 use crate::store_path_config_helper::get_store_checkpoint;
 use crate::store_path_config_helper::get_store_path_consume_queue;
 use crate::timer::timer_message_store::TimerMessageStore;
@@ -103,6 +114,7 @@ pub struct DefaultMessageStore {
     message_store_config: Arc<MessageStoreConfig>,
     broker_config: Arc<BrokerConfig>,
     put_message_hook_list: Arc<parking_lot::RwLock<Vec<BoxedPutMessageHook>>>,
+    plugins: Arc<parking_lot::RwLock<Vec<Arc<dyn MessageStorePlugin>>>>,
     topic_config_table: Arc<parking_lot::Mutex<HashMap<String, TopicConfig>>>,
     //message_store_runtime: Option<RocketMQRuntime>,
     commit_log: CommitLog,
@@ -110,7 +122,7 @@ pub struct DefaultMessageStore {
     store_checkpoint: Option<Arc<StoreCheckpoint>>,
     master_flushed_offset: Arc<AtomicI64>,
     index_service: IndexService,
-    allocate_mapped_file_service: Arc<AllocateMappedFileService>,
+    allocate_mapped_file_service: Option<AllocateMappedFileService>,
     consume_queue_store: ConsumeQueueStore,
     dispatcher: CommitLogDispatcherDefault,
     broker_init_max_offset: Arc<AtomicI64>,
@@ -122,6 +134,7 @@ pub struct DefaultMessageStore {
     clean_commit_log_service: Arc<CleanCommitLogService>,
     correct_logic_offset_service: Arc<CorrectLogicOffsetService>,
     clean_consume_queue_service: Arc<CleanConsumeQueueService>,
+    flush_consume_queue_service: FlushConsumeQueueService,
     broker_stats_manager: Option<Arc<BrokerStatsManager>>,
     message_arriving_listener:
         Option<Arc<Box<dyn MessageArrivingListener + Sync + Send + 'static>>>,
@@ -130,6 +143,7 @@ pub struct DefaultMessageStore {
     compaction_store: Arc<CompactionStore>,
     timer_message_store: Arc<TimerMessageStore>,
     transient_store_pool: TransientStorePool,
+    corrupted_message_report: Arc<CorruptedMessageReport>,
 }
 
 impl Clone for DefaultMessageStore {
@@ -138,6 +152,7 @@ impl Clone for DefaultMessageStore {
             message_store_config: self.message_store_config.clone(),
             broker_config: self.broker_config.clone(),
             put_message_hook_list: self.put_message_hook_list.clone(),
+            plugins: self.plugins.clone(),
             topic_config_table: self.topic_config_table.clone(),
             commit_log: self.commit_log.clone(),
             compaction_service: self.compaction_service.clone(),
@@ -155,6 +170,7 @@ impl Clone for DefaultMessageStore {
             clean_commit_log_service: self.clean_commit_log_service.clone(),
             correct_logic_offset_service: self.correct_logic_offset_service.clone(),
             clean_consume_queue_service: self.clean_consume_queue_service.clone(),
+            flush_consume_queue_service: self.flush_consume_queue_service.clone(),
             broker_stats_manager: self.broker_stats_manager.clone(),
             message_arriving_listener: self.message_arriving_listener.clone(),
             notify_message_arrive_in_batch: self.notify_message_arrive_in_batch,
@@ -162,6 +178,7 @@ impl Clone for DefaultMessageStore {
             compaction_store: self.compaction_store.clone(),
             timer_message_store: self.timer_message_store.clone(),
             transient_store_pool: self.transient_store_pool.clone(),
+            corrupted_message_report: self.corrupted_message_report.clone(),
         }
     }
 }
@@ -186,19 +203,31 @@ impl DefaultMessageStore {
         let build_index =
             CommitLogDispatcherBuildIndex::new(index_service.clone(), message_store_config.clone());
         // let topic_config_table = Arc::new(parking_lot::Mutex::new(HashMap::new()));
+        let allocate_mapped_file_service = (message_store_config.warm_mapped_file_enable
+            || message_store_config.pre_allocate_mapped_file_enable)
+            .then(|| AllocateMappedFileService::new(message_store_config.clone()));
         let consume_queue_store = ConsumeQueueStore::new(
             message_store_config.clone(),
             broker_config.clone(),
             topic_config_table.clone(),
             running_flags.clone(),
             store_checkpoint.clone(),
+            allocate_mapped_file_service
+                .clone()
+                .filter(|_| message_store_config.pre_allocate_mapped_file_enable),
         );
         let build_consume_queue =
             CommitLogDispatcherBuildConsumeQueue::new(consume_queue_store.clone());
+        let flush_consume_queue_service = FlushConsumeQueueService::new(
+            message_store_config.clone(),
+            consume_queue_store.clone(),
+            store_checkpoint.clone(),
+        );
 
-        let dispatcher = CommitLogDispatcherDefault {
-            dispatcher_vec: Arc::new(vec![Box::new(build_consume_queue), Box::new(build_index)]),
-        };
+        let dispatcher = CommitLogDispatcherDefault::new(vec![
+            Box::new(build_consume_queue),
+            Box::new(build_index),
+        ]);
 
         let commit_log = CommitLog::new(
             message_store_config.clone(),
@@ -207,6 +236,7 @@ impl DefaultMessageStore {
             store_checkpoint.clone(),
             topic_config_table.clone(),
             consume_queue_store.clone(),
+            allocate_mapped_file_service.clone(),
         );
 
         ensure_dir_ok(message_store_config.store_path_root_dir.as_str());
@@ -218,10 +248,14 @@ impl DefaultMessageStore {
             message_store_config.transient_store_pool_size,
             message_store_config.mapped_file_size_commit_log,
         );
+        let corrupted_message_report = Arc::new(CorruptedMessageReport::new(
+            get_corrupted_message_report(message_store_config.store_path_root_dir.as_str()),
+        ));
         Self {
             message_store_config: message_store_config.clone(),
             broker_config,
             put_message_hook_list: Arc::new(parking_lot::RwLock::new(vec![])),
+            plugins: Arc::new(parking_lot::RwLock::new(vec![])),
             topic_config_table,
             // message_store_runtime: Some(RocketMQRuntime::new_multi(10, "message-store-thread")),
             commit_log,
@@ -229,7 +263,7 @@ impl DefaultMessageStore {
             store_checkpoint: Some(store_checkpoint),
             master_flushed_offset: Arc::new(AtomicI64::new(-1)),
             index_service,
-            allocate_mapped_file_service: Arc::new(AllocateMappedFileService::new()),
+            allocate_mapped_file_service,
             consume_queue_store,
             dispatcher,
             broker_init_max_offset: Arc::new(AtomicI64::new(-1)),
@@ -238,13 +272,15 @@ impl DefaultMessageStore {
             running_flags,
             reput_message_service: ReputMessageService {
                 tx: None,
-                reput_from_offset: None,
+                reput_from_offset: Some(Arc::new(AtomicI64::new(0))),
                 message_store_config,
                 inner: None,
+                concurrent_dispatcher: None,
             },
             clean_commit_log_service: Arc::new(CleanCommitLogService {}),
             correct_logic_offset_service: Arc::new(CorrectLogicOffsetService {}),
             clean_consume_queue_service: Arc::new(CleanConsumeQueueService {}),
+            flush_consume_queue_service,
             broker_stats_manager,
             message_arriving_listener: None,
             notify_message_arrive_in_batch,
@@ -252,6 +288,7 @@ impl DefaultMessageStore {
             compaction_store: Arc::new(CompactionStore),
             timer_message_store: Arc::new(TimerMessageStore::new_empty()),
             transient_store_pool,
+            corrupted_message_report,
         }
     }
 
@@ -296,6 +333,106 @@ impl DefaultMessageStore {
         self.topic_config_table.lock().get(topic).cloned()
     }
 
+    /// Replaces the plugin chain wrapping the reads and writes of the store, see
+    /// [`MessageStorePlugin`].
+    pub fn set_plugins(&self, plugins: Vec<Arc<dyn MessageStorePlugin>>) {
+        *self.plugins.write() = plugins;
+    }
+
+    /// Replication service puts on a `SyncMaster` wait on, see [`HAService`].
+    pub fn set_ha_service(&self, ha_service: Option<Arc<dyn HAService>>) {
+        self.commit_log.set_ha_service(ha_service);
+    }
+
+    pub fn plugins(&self) -> Vec<Arc<dyn MessageStorePlugin>> {
+        self.plugins.read().clone()
+    }
+
+    async fn put_message_inner(&mut self, mut msg: MessageExtBrokerInner) -> PutMessageResult {
+        for hook in self.put_message_hook_list.read().iter() {
+            if let Some(result) = hook.execute_before_put_message(&mut msg) {
+                return result;
+            }
+        }
+
+        if msg
+            .message_ext_inner
+            .properties()
+            .contains_key(MessageConst::PROPERTY_INNER_NUM)
+            && !MessageSysFlag::check(msg.sys_flag(), MessageSysFlag::INNER_BATCH_FLAG)
+        {
+            warn!(
+                "[BUG]The message had property {} but is not an inner batch",
+                MessageConst::PROPERTY_INNER_NUM
+            );
+            return PutMessageResult::new_default(PutMessageStatus::MessageIllegal);
+        }
+
+        if MessageSysFlag::check(msg.sys_flag(), MessageSysFlag::INNER_BATCH_FLAG) {
+            let topic_config = self.get_topic_config(msg.topic());
+            if !QueueTypeUtils::is_batch_cq(&topic_config) {
+                error!("[BUG]The message is an inner batch but cq type is not batch cq");
+                return PutMessageResult::new_default(PutMessageStatus::MessageIllegal);
+            }
+        }
+        let topic = msg.topic().to_string();
+        let begin_time = Instant::now();
+        //put message to commit log
+        let result = self.commit_log.put_message(msg).await;
+        let elapsed_time = begin_time.elapsed().as_millis();
+        if elapsed_time > 500 {
+            warn!(
+                "DefaultMessageStore#putMessage: CommitLog#putMessage cost {}ms",
+                elapsed_time,
+            );
+        }
+        self.store_stats_service
+            .set_put_message_entire_time_max(elapsed_time as u64);
+        match result.append_message_result() {
+            Some(append_result) if result.is_ok() => self
+                .store_stats_service
+                .add_put_message_topic(&topic, append_result.msg_num, append_result.wrote_bytes),
+            _ => {
+                self.store_stats_service
+                    .get_put_message_failed_times()
+                    .fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        result
+    }
+
+    async fn put_messages_inner(&mut self, mut msg_batch: MessageExtBatch) -> PutMessageResult {
+        for hook in self.put_message_hook_list.read().iter() {
+            if let Some(result) =
+                hook.execute_before_put_message(&mut msg_batch.message_ext_broker_inner)
+            {
+                return result;
+            }
+        }
+
+        let topic = msg_batch.message_ext_broker_inner.topic().to_string();
+        let begin_time = Instant::now();
+        //put message to commit log
+        let result = self.commit_log.put_messages(msg_batch).await;
+        let elapsed_time = begin_time.elapsed().as_millis();
+        if elapsed_time > 500 {
+            warn!("not in lock eclipse time(ms) {}ms", elapsed_time,);
+        }
+        self.store_stats_service
+            .set_put_message_entire_time_max(elapsed_time as u64);
+        match result.append_message_result() {
+            Some(append_result) if result.is_ok() => self
+                .store_stats_service
+                .add_put_message_topic(&topic, append_result.msg_num, append_result.wrote_bytes),
+            _ => {
+                self.store_stats_service
+                    .get_put_message_failed_times()
+                    .fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        result
+    }
+
     fn is_temp_file_exist(&self) -> bool {
         let file_name = get_abort_file(self.message_store_config.store_path_root_dir.as_str());
         fs::metadata(file_name).is_ok()
@@ -391,7 +528,7 @@ impl DefaultMessageStore {
 
     pub fn on_commit_log_dispatch(
         &mut self,
-        dispatch_request: &DispatchRequest,
+        dispatch_request: &mut DispatchRequest,
         do_dispatch: bool,
         is_recover: bool,
         _is_file_end: bool,
@@ -401,7 +538,7 @@ impl DefaultMessageStore {
         }
     }
 
-    pub fn do_dispatch(&mut self, dispatch_request: &DispatchRequest) {
+    pub fn do_dispatch(&mut self, dispatch_request: &mut DispatchRequest) {
         self.dispatcher.dispatch(dispatch_request)
     }
 
@@ -492,6 +629,30 @@ impl DefaultMessageStore {
         next_offset
     }
 
+    /// Whether the store accepts writes right now, `PutOk` if it does.
+    fn check_store_status(&self) -> PutMessageStatus {
+        if self.is_shutdown() {
+            warn!("message store has shutdown, so putMessage is forbidden");
+            return PutMessageStatus::ServiceNotAvailable;
+        }
+        if self.message_store_config.broker_role == BrokerRole::Slave {
+            warn!("broke role is slave, so putMessage is forbidden");
+            return PutMessageStatus::ServiceNotAvailable;
+        }
+        if !self.running_flags.is_writeable() {
+            warn!(
+                "the message store is not writable. It may be caused by one of the following \
+                 reasons: the broker's disk is full, write to logic queue error, write to index \
+                 file error, etc"
+            );
+            return PutMessageStatus::ServiceNotAvailable;
+        }
+        if self.is_os_page_cache_busy() {
+            return PutMessageStatus::OsPageCacheBusy;
+        }
+        PutMessageStatus::PutOk
+    }
+
     fn check_in_mem_by_commit_offset(&self, offset_py: i64, size: i32) -> bool {
         let message = self.commit_log.get_message(offset_py, size);
         match message {
@@ -610,7 +771,9 @@ impl MessageStore for DefaultMessageStore {
         info!("load over, and the max phy offset = {}", max_offset);
 
         if !result {
-            // self.allocate_mapped_file_service.shutdown();
+            if let Some(service) = self.allocate_mapped_file_service.as_ref() {
+                service.shutdown();
+            }
         }
         result
     }
@@ -627,8 +790,10 @@ impl MessageStore for DefaultMessageStore {
             self.notify_message_arrive_in_batch,
             self.clone(),
         );
+        self.flush_consume_queue_service.start();
 
         self.commit_log.start();
+        self.store_stats_service.clone().start();
 
         //self.add_schedule_task();
 
@@ -639,13 +804,24 @@ impl MessageStore for DefaultMessageStore {
         if !self.shutdown.load(Ordering::Acquire) {
             self.shutdown.store(true, Ordering::SeqCst);
             self.reput_message_service.shutdown();
+            self.store_stats_service.shutdown();
             self.commit_log.shutdown();
+            self.flush_consume_queue_service.shutdown();
+            if let Some(store_checkpoint) = self.store_checkpoint.as_ref() {
+                if let Err(e) = store_checkpoint.shutdown() {
+                    error!("flush store checkpoint error: {}", e);
+                }
+            }
 
-            if self.running_flags.is_writeable() {
+            // Keep the abort file when messages are not dispatched yet, the next start
+            // recovers abnormally and dispatches them from the commit log
+            if self.running_flags.is_writeable() && self.dispatch_behind_bytes() == 0 {
                 //delete abort file
                 self.delete_file(get_abort_file(
                     self.message_store_config.store_path_root_dir.as_str(),
                 ))
+            } else {
+                warn!("the store may be wrong, so shutdown abnormally, and keep abort file.");
             }
         }
     }
@@ -654,88 +830,57 @@ impl MessageStore for DefaultMessageStore {
         self.commit_log.set_confirm_offset(phy_offset);
     }
 
-    fn get_max_phy_offset(&self) -> i64 {
-        self.commit_log.get_max_offset()
+    fn get_max_phy_offset(&self) -> CommitLogOffset {
+        CommitLogOffset::new(self.commit_log.get_max_offset())
     }
 
-    fn set_broker_init_max_offset(&mut self, broker_init_max_offset: i64) {
+    fn get_min_phy_offset(&self) -> CommitLogOffset {
+        CommitLogOffset::new(self.commit_log.get_min_offset())
+    }
+
+    fn set_broker_init_max_offset(&mut self, broker_init_max_offset: CommitLogOffset) {
         self.broker_init_max_offset
-            .store(broker_init_max_offset, Ordering::SeqCst);
+            .store(broker_init_max_offset.value(), Ordering::SeqCst);
     }
 
     fn get_state_machine_version(&self) -> i64 {
         self.state_machine_version.load(Ordering::Relaxed)
     }
 
-    async fn put_message(&mut self, msg: MessageExtBrokerInner) -> PutMessageResult {
-        for hook in self.put_message_hook_list.read().iter() {
-            if let Some(result) = hook.execute_before_put_message(&msg.message_ext_inner) {
+    async fn put_message(&mut self, mut msg: MessageExtBrokerInner) -> PutMessageResult {
+        let store_status = self.check_store_status();
+        if store_status != PutMessageStatus::PutOk {
+            return PutMessageResult::new_default(store_status);
+        }
+        let plugins = self.plugins.read().clone();
+        for plugin in plugins.iter() {
+            if let Some(result) = plugin.before_put_message(&mut msg) {
                 return result;
             }
         }
-
-        if msg
-            .message_ext_inner
-            .properties()
-            .contains_key(MessageConst::PROPERTY_INNER_NUM)
-            && !MessageSysFlag::check(msg.sys_flag(), MessageSysFlag::INNER_BATCH_FLAG)
-        {
-            warn!(
-                "[BUG]The message had property {} but is not an inner batch",
-                MessageConst::PROPERTY_INNER_NUM
-            );
-            return PutMessageResult::new_default(PutMessageStatus::MessageIllegal);
-        }
-
-        if MessageSysFlag::check(msg.sys_flag(), MessageSysFlag::INNER_BATCH_FLAG) {
-            let topic_config = self.get_topic_config(msg.topic());
-            if !QueueTypeUtils::is_batch_cq(&topic_config) {
-                error!("[BUG]The message is an inner batch but cq type is not batch cq");
-                return PutMessageResult::new_default(PutMessageStatus::MessageIllegal);
-            }
-        }
-        let begin_time = Instant::now();
-        //put message to commit log
-        let result = self.commit_log.put_message(msg).await;
-        let elapsed_time = begin_time.elapsed().as_millis();
-        if elapsed_time > 500 {
-            warn!(
-                "DefaultMessageStore#putMessage: CommitLog#putMessage cost {}ms",
-                elapsed_time,
-            );
-        }
-        self.store_stats_service
-            .set_put_message_entire_time_max(elapsed_time as u64);
-        if !result.is_ok() {
-            self.store_stats_service
-                .get_message_times_total_found()
-                .fetch_add(1, Ordering::Relaxed);
+        let topic = msg.topic().to_string();
+        let result = self.put_message_inner(msg).await;
+        for plugin in plugins.iter() {
+            plugin.after_put_message(topic.as_str(), &result);
         }
         result
     }
 
-    async fn put_messages(&mut self, msg_batch: MessageExtBatch) -> PutMessageResult {
-        for hook in self.put_message_hook_list.read().iter() {
-            if let Some(result) = hook
-                .execute_before_put_message(&msg_batch.message_ext_broker_inner.message_ext_inner)
-            {
+    async fn put_messages(&mut self, mut msg_batch: MessageExtBatch) -> PutMessageResult {
+        let store_status = self.check_store_status();
+        if store_status != PutMessageStatus::PutOk {
+            return PutMessageResult::new_default(store_status);
+        }
+        let plugins = self.plugins.read().clone();
+        for plugin in plugins.iter() {
+            if let Some(result) = plugin.before_put_messages(&mut msg_batch) {
                 return result;
             }
         }
-
-        let begin_time = Instant::now();
-        //put message to commit log
-        let result = self.commit_log.put_messages(msg_batch).await;
-        let elapsed_time = begin_time.elapsed().as_millis();
-        if elapsed_time > 500 {
-            warn!("not in lock eclipse time(ms) {}ms", elapsed_time,);
-        }
-        self.store_stats_service
-            .set_put_message_entire_time_max(elapsed_time as u64);
-        if !result.is_ok() {
-            self.store_stats_service
-                .get_message_times_total_found()
-                .fetch_add(1, Ordering::Relaxed);
+        let topic = msg_batch.message_ext_broker_inner.topic().to_string();
+        let result = self.put_messages_inner(msg_batch).await;
+        for plugin in plugins.iter() {
+            plugin.after_put_message(topic.as_str(), &result);
         }
         result
     }
@@ -755,8 +900,7 @@ impl MessageStore for DefaultMessageStore {
     }
 
     fn is_shutdown(&self) -> bool {
-        // todo!()
-        false
+        self.shutdown.load(Ordering::Acquire)
     }
 
     fn get_put_message_hook_list(&self) -> Arc<parking_lot::RwLock<Vec<BoxedPutMessageHook>>> {
@@ -767,20 +911,44 @@ impl MessageStore for DefaultMessageStore {
         self.put_message_hook_list.write().push(put_message_hook);
     }
 
+    fn get_dispatcher_list(&self) -> Arc<parking_lot::RwLock<Vec<BoxedCommitLogDispatcher>>> {
+        self.dispatcher.dispatcher_list()
+    }
+
+    fn add_dispatcher(&self, dispatcher: BoxedCommitLogDispatcher) {
+        self.dispatcher.add_dispatcher(dispatcher);
+    }
+
+    fn add_first_dispatcher(&self, dispatcher: BoxedCommitLogDispatcher) {
+        self.dispatcher.add_first_dispatcher(dispatcher);
+    }
+
     fn get_broker_stats_manager(&self) -> Option<Arc<BrokerStatsManager>> {
         self.broker_stats_manager.clone()
     }
 
     fn dispatch_behind_bytes(&self) -> i64 {
-        0
+        self.reput_message_service
+            .behind(self.commit_log.get_confirm_offset())
     }
 
-    fn get_min_offset_in_queue(&self, topic: &str, queue_id: i32) -> i64 {
-        self.consume_queue_store
-            .get_min_offset_in_queue(topic, queue_id)
+    fn flush_behind_bytes(&self) -> i64 {
+        if self.is_transient_store_pool_enable() {
+            self.commit_log.remain_how_many_data_to_commit()
+                + self.commit_log.remain_how_many_data_to_flush()
+        } else {
+            self.commit_log.remain_how_many_data_to_flush()
+        }
+    }
+
+    fn get_min_offset_in_queue(&self, topic: &str, queue_id: i32) -> QueueOffset {
+        QueueOffset::new(
+            self.consume_queue_store
+                .get_min_offset_in_queue(topic, queue_id),
+        )
     }
 
-    fn get_max_offset_in_queue(&self, topic: &str, queue_id: i32) -> i64 {
+    fn get_max_offset_in_queue(&self, topic: &str, queue_id: i32) -> QueueOffset {
         self.get_max_offset_in_queue_committed(topic, queue_id, true)
     }
 
@@ -789,8 +957,8 @@ impl MessageStore for DefaultMessageStore {
         topic: &str,
         queue_id: i32,
         committed: bool,
-    ) -> i64 {
-        if committed {
+    ) -> QueueOffset {
+        let offset = if committed {
             let queue = self
                 .consume_queue_store
                 .find_or_create_consume_queue(topic, queue_id);
@@ -800,7 +968,56 @@ impl MessageStore for DefaultMessageStore {
             self.consume_queue_store
                 .get_max_offset(topic, queue_id)
                 .unwrap_or_default()
+        };
+        QueueOffset::new(offset)
+    }
+
+    fn get_offset_in_queue_by_time(
+        &self,
+        topic: &str,
+        queue_id: i32,
+        timestamp: i64,
+        boundary_type: BoundaryType,
+    ) -> QueueOffset {
+        let Some(consume_queue) = self.find_consume_queue(topic, queue_id) else {
+            return QueueOffset::default();
+        };
+        QueueOffset::new(search_offset_by_time(
+            consume_queue.get_min_offset_in_queue(),
+            consume_queue.get_max_offset_in_queue(),
+            timestamp,
+            boundary_type,
+            |offset| match consume_queue.get(offset) {
+                Some(cq_unit) => self
+                    .commit_log
+                    .pickup_store_timestamp(cq_unit.pos, cq_unit.size),
+                None => -1,
+            },
+        ))
+    }
+
+    fn get_earliest_message_time_in_queue(&self, topic: &str, queue_id: i32) -> i64 {
+        let Some(consume_queue) = self.find_consume_queue(topic, queue_id) else {
+            return -1;
+        };
+        let store_timestamp = |offset| match consume_queue.get(offset) {
+            Some(cq_unit) => self
+                .commit_log
+                .pickup_store_timestamp(cq_unit.pos, cq_unit.size),
+            None => -1,
+        };
+        let max_offset = consume_queue.get_max_offset_in_queue();
+        let first_readable = search_offset_by_time(
+            consume_queue.get_min_offset_in_queue(),
+            max_offset,
+            0,
+            BoundaryType::Lower,
+            store_timestamp,
+        );
+        if first_readable >= max_offset {
+            return -1;
         }
+        store_timestamp(first_readable)
     }
 
     async fn get_message(
@@ -969,9 +1186,30 @@ impl MessageStore for DefaultMessageStore {
                                     self.commit_log.roll_next_file(offset_py);
                                 continue;
                             }
+                            if self.message_store_config.check_crc_on_read {
+                                if let Some((stored_crc, actual_crc)) =
+                                    commit_log::body_crc_mismatch(
+                                        select_result.as_ref().unwrap().get_buffer(),
+                                    )
+                                {
+                                    self.corrupted_message_report.record(
+                                        CorruptionSource::Pull,
+                                        offset_py,
+                                        size_py,
+                                        stored_crc,
+                                        actual_crc,
+                                    );
+                                    if get_result_ref.buffer_total_size() == 0 {
+                                        status = GetMessageStatus::NoMatchedMessage;
+                                    }
+                                    continue;
+                                }
+                            }
+                            // Data far behind the commit log head is unlikely to still be
+                            // in the page cache, count it as cold as well.
                             if self.message_store_config.cold_data_flow_control_enable
                                 && !is_sys_consumer_group_for_no_cold_read_limit(group)
-                                && !select_result.as_ref().unwrap().is_in_cache
+                                && (!is_in_mem || !select_result.as_ref().unwrap().is_in_cache)
                             {
                                 get_result_ref.set_cold_data_sum(
                                     get_result_ref.cold_data_sum() + size_py as i64,
@@ -1000,6 +1238,9 @@ impl MessageStore for DefaultMessageStore {
                             );
                             status = GetMessageStatus::Found;
                             next_phy_file_start_offset = i64::MIN;
+                        } else {
+                            // end of the file, go on from the next one
+                            break;
                         }
                     }
                 }
@@ -1042,6 +1283,11 @@ impl MessageStore for DefaultMessageStore {
         result.set_next_begin_offset(next_begin_offset);
         result.set_max_offset(max_offset);
         result.set_min_offset(min_offset);
+        if GetMessageStatus::Found == status {
+            for plugin in self.plugins.read().iter().rev() {
+                plugin.after_get_message(group, topic, queue_id, result);
+            }
+        }
         /*        println!(
             "------------------------------------------------{} {} {} {} {}",
             result, next_begin_offset, max_offset, min_offset, elapsed_time
@@ -1265,7 +1511,37 @@ impl MessageStore for DefaultMessageStore {
         }
     }
     fn get_runtime_info(&self) -> HashMap<String, String> {
-        self.store_stats_service.get_runtime_info()
+        let mut result = self.store_stats_service.get_runtime_info();
+        result.insert(
+            "commitLogMinOffset".to_string(),
+            self.commit_log.get_min_offset().to_string(),
+        );
+        result.insert(
+            "commitLogMaxOffset".to_string(),
+            self.get_max_phy_offset().to_string(),
+        );
+        self.corrupted_message_report
+            .build_runtime_info(&mut result);
+        if let Some(service) = self.allocate_mapped_file_service.as_ref() {
+            let snapshot = service.snapshot();
+            result.insert(
+                "allocateMappedFileRequests".to_string(),
+                snapshot.requests.to_string(),
+            );
+            result.insert(
+                "allocateMappedFilePreAllocated".to_string(),
+                snapshot.pre_allocated.to_string(),
+            );
+            result.insert(
+                "allocateMappedFileAvgWaitMicros".to_string(),
+                (snapshot.avg_wait_nanos() / 1000).to_string(),
+            );
+            result.insert(
+                "allocateMappedFileMaxWaitMicros".to_string(),
+                (snapshot.max_wait_nanos / 1000).to_string(),
+            );
+        }
+        result
     }
 
     fn lock_time_mills(&self) -> i64 {
@@ -1304,14 +1580,34 @@ impl MessageStore for DefaultMessageStore {
 pub struct CommitLogDispatcherDefault {
     /*build_index: CommitLogDispatcherBuildIndex,
     build_consume_queue: CommitLogDispatcherBuildConsumeQueue,*/
-    dispatcher_vec: Arc<Vec<Box<dyn CommitLogDispatcher>>>,
+    dispatcher_vec: Arc<parking_lot::RwLock<Vec<BoxedCommitLogDispatcher>>>,
+}
+
+impl CommitLogDispatcherDefault {
+    pub fn new(dispatcher_vec: Vec<BoxedCommitLogDispatcher>) -> Self {
+        CommitLogDispatcherDefault {
+            dispatcher_vec: Arc::new(parking_lot::RwLock::new(dispatcher_vec)),
+        }
+    }
+
+    pub fn dispatcher_list(&self) -> Arc<parking_lot::RwLock<Vec<BoxedCommitLogDispatcher>>> {
+        self.dispatcher_vec.clone()
+    }
+
+    pub fn add_dispatcher(&self, dispatcher: BoxedCommitLogDispatcher) {
+        self.dispatcher_vec.write().push(dispatcher);
+    }
+
+    pub fn add_first_dispatcher(&self, dispatcher: BoxedCommitLogDispatcher) {
+        self.dispatcher_vec.write().insert(0, dispatcher);
+    }
 }
 
 impl CommitLogDispatcher for CommitLogDispatcherDefault {
-    fn dispatch(&self, dispatch_request: &DispatchRequest) {
+    fn dispatch(&self, dispatch_request: &mut DispatchRequest) {
         /*self.build_index.dispatch(dispatch_request);
         self.build_consume_queue.dispatch(dispatch_request);*/
-        for dispatcher in self.dispatcher_vec.iter() {
+        for dispatcher in self.dispatcher_vec.read().iter() {
             dispatcher.dispatch(dispatch_request);
         }
     }
@@ -1322,6 +1618,7 @@ struct ReputMessageService {
     reput_from_offset: Option<Arc<AtomicI64>>,
     message_store_config: Arc<MessageStoreConfig>,
     inner: Option<ReputMessageServiceInner>,
+    concurrent_dispatcher: Option<Arc<ConcurrentDispatcher>>,
 }
 
 impl ReputMessageService {
@@ -1381,7 +1678,27 @@ impl ReputMessageService {
     }
 
     pub fn set_reput_from_offset(&mut self, reput_from_offset: i64) {
-        self.reput_from_offset = Some(Arc::new(AtomicI64::new(reput_from_offset)));
+        // Store into the shared offset so clones of the message store see the dispatch progress.
+        match self.reput_from_offset.as_ref() {
+            Some(offset) => offset.store(reput_from_offset, Ordering::SeqCst),
+            None => self.reput_from_offset = Some(Arc::new(AtomicI64::new(reput_from_offset))),
+        }
+    }
+
+    /// Bytes of the commit log, up to `reput_end_offset`, not dispatched to the consume queues
+    /// and index yet.
+    pub fn behind(&self, reput_end_offset: i64) -> i64 {
+        let not_read = match self.reput_from_offset.as_ref() {
+            Some(offset) => CommitLogOffset::new(offset.load(Ordering::Relaxed))
+                .distance_to(CommitLogOffset::new(reput_end_offset)),
+            None => 0,
+        };
+        // Messages read from the commit log but still queued for a dispatch worker.
+        let not_dispatched = self
+            .concurrent_dispatcher
+            .as_ref()
+            .map_or(0, |dispatcher| dispatcher.pending_bytes());
+        not_read + not_dispatched
     }
 
     pub fn start(
@@ -1392,15 +1709,39 @@ impl ReputMessageService {
         notify_message_arrive_in_batch: bool,
         message_store: DefaultMessageStore,
     ) {
+        let message_store = Arc::new(message_store);
+        // Multi dispatch writes a message to the queues of other topics as well, which the
+        // workers of those queues could be building concurrently.
+        let concurrent_dispatcher = if message_store_config.dispatch_concurrency > 1
+            && !message_store_config.enable_lmq
+            && !message_store_config.enable_multi_dispatch
+        {
+            let worker_dispatcher = dispatcher.clone();
+            let worker_store = message_store.clone();
+            Some(Arc::new(ConcurrentDispatcher::new(
+                message_store_config.dispatch_concurrency,
+                message_store_config.dispatch_max_buffer,
+                Arc::new(move |dispatch_request: &mut DispatchRequest| {
+                    worker_dispatcher.dispatch(dispatch_request);
+                    if !notify_message_arrive_in_batch {
+                        worker_store.notify_message_arrive_if_necessary(dispatch_request);
+                    }
+                }),
+            )))
+        } else {
+            None
+        };
         let mut inner = ReputMessageServiceInner {
             reput_from_offset: self.reput_from_offset.clone().unwrap(),
             commit_log,
             message_store_config,
             dispatcher,
             notify_message_arrive_in_batch,
-            message_store: Arc::new(message_store),
+            message_store,
+            concurrent_dispatcher: concurrent_dispatcher.clone(),
         };
         self.inner = Some(inner.clone());
+        self.concurrent_dispatcher = concurrent_dispatcher;
         let (tx, mut rx) = tokio::sync::mpsc::channel(1);
         self.tx = Some(Arc::new(tx));
         let handle = tokio::spawn(async move {
@@ -1432,6 +1773,9 @@ impl ReputMessageService {
                 }
                 interval.tick().await;
             }
+            if let Some(concurrent_dispatcher) = inner.concurrent_dispatcher.take() {
+                let _ = tokio::task::spawn_blocking(move || concurrent_dispatcher.shutdown()).await;
+            }
         });
     }
 
@@ -1462,6 +1806,7 @@ struct ReputMessageServiceInner {
     dispatcher: CommitLogDispatcherDefault,
     notify_message_arrive_in_batch: bool,
     message_store: Arc<DefaultMessageStore>,
+    concurrent_dispatcher: Option<Arc<ConcurrentDispatcher>>,
 }
 
 impl ReputMessageServiceInner {
@@ -1546,15 +1891,44 @@ impl ReputMessageServiceInner {
                     do_next = false;
                     break;
                 }
-                let mut bytes = mapped_file.get_data(
-                    (start_pos + read_size) as usize,
-                    size.unwrap().get_i32() as usize,
-                );
+                let msg_size = size.unwrap().get_i32();
+                let mut bytes =
+                    mapped_file.get_data((start_pos + read_size) as usize, msg_size as usize);
                 if bytes.is_none() {
                     do_next = false;
                     break;
                 }
 
+                let corrupted = if self.message_store_config.check_crc_on_read {
+                    commit_log::body_crc_mismatch(bytes.as_ref().unwrap())
+                } else {
+                    None
+                };
+                if let Some((stored_crc, actual_crc)) = corrupted {
+                    let phy_offset = self.reput_from_offset.load(Ordering::Acquire);
+                    if phy_offset + msg_size as i64 > self.commit_log.get_confirm_offset() {
+                        do_next = false;
+                        break;
+                    }
+                    self.message_store.corrupted_message_report.record(
+                        CorruptionSource::Dispatch,
+                        phy_offset,
+                        msg_size,
+                        stored_crc,
+                        actual_crc,
+                    );
+                    self.reput_from_offset
+                        .fetch_add(msg_size as i64, Ordering::SeqCst);
+                    read_size += msg_size;
+                    if !(read_size < result.size
+                        && self.reput_from_offset.load(Ordering::Acquire)
+                            < self.commit_log.get_confirm_offset())
+                    {
+                        break;
+                    }
+                    continue;
+                }
+
                 let mut dispatch_request = commit_log::check_message_and_return_size(
                     bytes.as_mut().unwrap(),
                     false,
@@ -1571,14 +1945,28 @@ impl ReputMessageServiceInner {
                 if dispatch_request.success {
                     match dispatch_request.msg_size.cmp(&0) {
                         std::cmp::Ordering::Greater => {
-                            self.dispatcher.dispatch(&dispatch_request);
-                            if !self.notify_message_arrive_in_batch {
-                                self.message_store
-                                    .notify_message_arrive_if_necessary(&mut dispatch_request);
+                            let msg_size = dispatch_request.msg_size;
+                            if let Some(concurrent_dispatcher) = self.concurrent_dispatcher.as_ref()
+                            {
+                                if !concurrent_dispatcher.submit(dispatch_request).await {
+                                    // shut down, the message is dispatched again after restart
+                                    warn!(
+                                        "concurrent dispatcher stopped, stop reput at offset {}",
+                                        self.reput_from_offset.load(Ordering::Acquire)
+                                    );
+                                    do_next = false;
+                                    break;
+                                }
+                            } else {
+                                self.dispatcher.dispatch(&mut dispatch_request);
+                                if !self.notify_message_arrive_in_batch {
+                                    self.message_store
+                                        .notify_message_arrive_if_necessary(&mut dispatch_request);
+                                }
                             }
                             self.reput_from_offset
-                                .fetch_add(dispatch_request.msg_size as i64, Ordering::AcqRel);
-                            read_size += dispatch_request.msg_size;
+                                .fetch_add(msg_size as i64, Ordering::AcqRel);
+                            read_size += msg_size;
                             if !self.message_store_config.duplication_enable
                                 && self.message_store_config.broker_role == BrokerRole::Slave
                             {
@@ -1634,6 +2022,61 @@ impl ReputMessageServiceInner {
     }
 }
 
+/// Binary search over queue offsets `[min_offset, max_offset)` by store timestamp.
+///
+/// `store_timestamp` returns -1 for entries whose commit log is gone, those only form a prefix
+/// of the queue since commit log files are deleted oldest first, and are skipped.
+fn search_offset_by_time(
+    min_offset: i64,
+    max_offset: i64,
+    timestamp: i64,
+    boundary_type: BoundaryType,
+    store_timestamp: impl Fn(i64) -> i64,
+) -> i64 {
+    // first offset whose message is still readable
+    let (mut low, mut high) = (min_offset, max_offset);
+    while low < high {
+        let mid = low + (high - low) / 2;
+        if store_timestamp(mid) < 0 {
+            low = mid + 1;
+        } else {
+            high = mid;
+        }
+    }
+    let first_readable = low;
+    if first_readable >= max_offset {
+        return max_offset;
+    }
+    match boundary_type {
+        BoundaryType::Lower => {
+            // first offset stored at or after timestamp
+            let (mut low, mut high) = (first_readable, max_offset);
+            while low < high {
+                let mid = low + (high - low) / 2;
+                if store_timestamp(mid) < timestamp {
+                    low = mid + 1;
+                } else {
+                    high = mid;
+                }
+            }
+            low
+        }
+        BoundaryType::Upper => {
+            // last offset stored at or before timestamp
+            let (mut low, mut high) = (first_readable, max_offset);
+            while low < high {
+                let mid = low + (high - low) / 2;
+                if store_timestamp(mid) <= timestamp {
+                    low = mid + 1;
+                } else {
+                    high = mid;
+                }
+            }
+            (low - 1).max(first_readable)
+        }
+    }
+}
+
 struct CleanCommitLogService {}
 
 impl CleanCommitLogService {
@@ -1657,3 +2100,234 @@ impl CorrectLogicOffsetService {
         println!("correct logic offset service run unimplemented!")
     }
 }
+
+/// Flushes the consume queues every `flush_interval_consume_queue`, then the store checkpoint
+/// whose logics timestamp abnormal recovery replays the commit log from.
+#[derive(Clone)]
+struct FlushConsumeQueueService {
+    message_store_config: Arc<MessageStoreConfig>,
+    consume_queue_store: ConsumeQueueStore,
+    store_checkpoint: Arc<StoreCheckpoint>,
+    last_flush_timestamp: Arc<AtomicU64>,
+    stopped: Arc<AtomicBool>,
+}
+
+impl FlushConsumeQueueService {
+    const RETRY_TIMES_OVER: usize = 3;
+
+    fn new(
+        message_store_config: Arc<MessageStoreConfig>,
+        consume_queue_store: ConsumeQueueStore,
+        store_checkpoint: Arc<StoreCheckpoint>,
+    ) -> Self {
+        Self {
+            message_store_config,
+            consume_queue_store,
+            store_checkpoint,
+            last_flush_timestamp: Arc::new(AtomicU64::new(0)),
+            stopped: Arc::new(AtomicBool::new(true)),
+        }
+    }
+
+    fn start(&self) {
+        self.stopped.store(false, Ordering::Release);
+        let service = self.clone();
+        tokio::spawn(async move {
+            info!("FlushConsumeQueueService service started");
+            let interval = Duration::from_millis(
+                service.message_store_config.flush_interval_consume_queue as u64,
+            );
+            loop {
+                tokio::time::sleep(interval).await;
+                if service.stopped.load(Ordering::Acquire) {
+                    break;
+                }
+                service.do_flush(1);
+            }
+            info!("FlushConsumeQueueService service end");
+        });
+    }
+
+    /// Stops the periodic flush and flushes everything one last time.
+    fn shutdown(&self) {
+        self.stopped.store(true, Ordering::Release);
+        self.do_flush(Self::RETRY_TIMES_OVER);
+    }
+
+    fn do_flush(&self, retry_times: usize) {
+        let mut flush_least_pages = if retry_times == Self::RETRY_TIMES_OVER {
+            0
+        } else {
+            self.message_store_config.flush_consume_queue_least_pages as i32
+        };
+        // Only a thorough flush covers every message dispatched before it, take the logics
+        // timestamp before flushing so the checkpoint never runs ahead of the consume queues
+        let mut logics_msg_timestamp = 0;
+        let current_time_millis = get_current_millis();
+        if current_time_millis
+            >= self.last_flush_timestamp.load(Ordering::Relaxed)
+                + self
+                    .message_store_config
+                    .flush_consume_queue_thorough_interval as u64
+        {
+            self.last_flush_timestamp
+                .store(current_time_millis, Ordering::Relaxed);
+            flush_least_pages = 0;
+            logics_msg_timestamp = self.store_checkpoint.logics_msg_timestamp();
+        }
+
+        let consume_queues = self
+            .consume_queue_store
+            .get_consume_queue_table()
+            .lock()
+            .values()
+            .flat_map(|queue_table| queue_table.values().cloned())
+            .collect::<Vec<_>>();
+        for consume_queue in consume_queues {
+            // Retry until a flush reaches the end of what was written
+            let _ = (0..retry_times).any(|_| {
+                self.consume_queue_store
+                    .flush(consume_queue.as_ref().as_ref(), flush_least_pages)
+            });
+        }
+
+        if flush_least_pages == 0 {
+            if logics_msg_timestamp > 0 {
+                self.store_checkpoint
+                    .set_logics_msg_timestamp(logics_msg_timestamp);
+            }
+            if let Err(e) = self.store_checkpoint.flush() {
+                error!("flush store checkpoint error: {}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct RecordDispatcher {
+        name: &'static str,
+        records: Arc<parking_lot::Mutex<Vec<&'static str>>>,
+    }
+
+    impl CommitLogDispatcher for RecordDispatcher {
+        fn dispatch(&self, _dispatch_request: &mut DispatchRequest) {
+            self.records.lock().push(self.name);
+        }
+    }
+
+    #[test]
+    fn commit_log_dispatcher_default_dispatches_in_order() {
+        let records = Arc::new(parking_lot::Mutex::new(vec![]));
+        let dispatcher = CommitLogDispatcherDefault::new(vec![Box::new(RecordDispatcher {
+            name: "build_consume_queue",
+            records: records.clone(),
+        })]);
+        let cloned = dispatcher.clone();
+        cloned.add_dispatcher(Box::new(RecordDispatcher {
+            name: "plugin",
+            records: records.clone(),
+        }));
+        cloned.add_first_dispatcher(Box::new(RecordDispatcher {
+            name: "first",
+            records: records.clone(),
+        }));
+
+        dispatcher.dispatch(&mut DispatchRequest::default());
+
+        assert_eq!(
+            *records.lock(),
+            vec!["first", "build_consume_queue", "plugin"]
+        );
+        assert_eq!(dispatcher.dispatcher_list().read().len(), 3);
+    }
+
+    #[test]
+    fn batch_limits_depend_on_whether_messages_are_in_memory() {
+        let config = Arc::new(MessageStoreConfig {
+            max_transfer_bytes_on_message_in_memory: 1000,
+            max_transfer_count_on_message_in_memory: 4,
+            max_transfer_bytes_on_message_in_disk: 300,
+            max_transfer_count_on_message_in_disk: 2,
+            access_message_in_memory_max_ratio: 40,
+            ..MessageStoreConfig::default()
+        });
+        let batch_full = |buffer_total, message_total, is_in_mem| {
+            is_the_batch_full(
+                100,
+                1,
+                32,
+                i64::MAX,
+                buffer_total,
+                message_total,
+                is_in_mem,
+                &config,
+            )
+        };
+
+        assert!(!batch_full(0, 0, false));
+        assert!(!batch_full(200, 2, true));
+        assert!(batch_full(200, 2, false));
+        assert!(batch_full(300, 4, true));
+        assert!(batch_full(950, 3, true));
+
+        let in_memory_bytes = (*TOTAL_PHYSICAL_MEMORY_SIZE as f64 * 0.4) as i64;
+        assert!(estimate_in_mem_by_commit_offset(
+            0,
+            in_memory_bytes,
+            &config
+        ));
+        assert!(!estimate_in_mem_by_commit_offset(
+            0,
+            in_memory_bytes + 1,
+            &config
+        ));
+    }
+
+    #[test]
+    fn search_offset_by_time_respects_boundary_and_skips_deleted_entries() {
+        // offsets 0..2 are in deleted commit log files, 2..8 stored at 100, 200, 200, 200, 300,
+        // 400
+        let store_timestamps = [-1, -1, 100, 200, 200, 200, 300, 400];
+        let lookup = |offset: i64| store_timestamps[offset as usize];
+        let search = |timestamp, boundary_type| {
+            search_offset_by_time(0, 8, timestamp, boundary_type, lookup)
+        };
+
+        assert_eq!(search(200, BoundaryType::Lower), 3);
+        assert_eq!(search(200, BoundaryType::Upper), 5);
+        assert_eq!(search(250, BoundaryType::Lower), 6);
+        assert_eq!(search(250, BoundaryType::Upper), 5);
+        assert_eq!(search(50, BoundaryType::Lower), 2);
+        assert_eq!(search(50, BoundaryType::Upper), 2);
+        assert_eq!(search(500, BoundaryType::Lower), 8);
+        assert_eq!(search(500, BoundaryType::Upper), 7);
+
+        assert_eq!(
+            search_offset_by_time(0, 2, 100, BoundaryType::Lower, lookup),
+            2
+        );
+        assert_eq!(
+            search_offset_by_time(5, 5, 100, BoundaryType::Upper, lookup),
+            5
+        );
+    }
+
+    #[test]
+    fn reput_message_service_behind_is_shared_with_clones() {
+        let mut service = ReputMessageService {
+            tx: None,
+            reput_from_offset: Some(Arc::new(AtomicI64::new(0))),
+            message_store_config: Arc::new(MessageStoreConfig::default()),
+            inner: None,
+            concurrent_dispatcher: None,
+        };
+        let cloned = service.clone();
+        service.set_reput_from_offset(100);
+
+        assert_eq!(cloned.behind(150), 50);
+        assert_eq!(cloned.behind(80), 0);
+    }
+}
@@ -0,0 +1,152 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use rocketmq_common::common::broker::broker_config::BrokerConfig;
+use rocketmq_common::common::message::message_batch::MessageExtBatch;
+use rocketmq_common::common::message::message_ext_broker_inner::MessageExtBrokerInner;
+
+use crate::base::get_message_result::GetMessageResult;
+use crate::base::message_result::PutMessageResult;
+use crate::config::message_store_config::MessageStoreConfig;
+
+/// Decorator around the reads and writes of the message store, e.g. at-rest encryption, audit or
+/// shadow writes to another cluster.
+///
+/// Plugins are chained in the order of `messageStorePlugIn`: on put the first plugin sees the
+/// message first, on get it sees the result last, so a plugin encrypting bodies on put gets
+/// them back encrypted on get whatever the plugins behind it do.
+pub trait MessageStorePlugin: Send + Sync + 'static {
+    /// Returns the name of the plugin.
+    fn plugin_name(&self) -> &str;
+
+    /// Runs before the message is handed to the put message hooks and the commit log, plugins
+    /// may modify it. Returning a result rejects the message and skips the rest of the chain.
+    fn before_put_message(&self, _msg: &mut MessageExtBrokerInner) -> Option<PutMessageResult> {
+        None
+    }
+
+    /// Same as [`MessageStorePlugin::before_put_message`] for a batch.
+    fn before_put_messages(&self, _msg_batch: &mut MessageExtBatch) -> Option<PutMessageResult> {
+        None
+    }
+
+    /// Runs with the result of every put which went past the plugins, including the ones
+    /// rejected by the put message hooks or the commit log.
+    fn after_put_message(&self, _topic: &str, _result: &PutMessageResult) {}
+
+    /// Runs on every result of `get_message` which found messages, plugins may replace the
+    /// content of a message with [`GetMessageResult::replace_message`].
+    fn after_get_message(
+        &self,
+        _group: &str,
+        _topic: &str,
+        _queue_id: i32,
+        _result: &mut GetMessageResult,
+    ) {
+    }
+}
+
+/// Everything a plugin factory may need to build its plugin.
+pub struct MessageStorePluginContext {
+    pub broker_config: Arc<BrokerConfig>,
+    pub message_store_config: Arc<MessageStoreConfig>,
+}
+
+pub type MessageStorePluginFactory =
+    Arc<dyn Fn(&MessageStorePluginContext) -> Arc<dyn MessageStorePlugin> + Send + Sync>;
+
+/// Plugin factories by name, the broker builds the chain configured by `messageStorePlugIn`
+/// from it.
+#[derive(Clone, Default)]
+pub struct MessageStorePluginRegistry {
+    factories: HashMap<String, MessageStorePluginFactory>,
+}
+
+impl MessageStorePluginRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register<F>(&mut self, name: impl Into<String>, factory: F)
+    where
+        F: Fn(&MessageStorePluginContext) -> Arc<dyn MessageStorePlugin> + Send + Sync + 'static,
+    {
+        self.factories.insert(name.into(), Arc::new(factory));
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.factories.contains_key(name)
+    }
+
+    /// Builds the plugins named in `plugin_names`, separated by commas, in that order. Fails on
+    /// the first name without a registered factory.
+    pub fn build_chain(
+        &self,
+        plugin_names: &str,
+        context: &MessageStorePluginContext,
+    ) -> Result<Vec<Arc<dyn MessageStorePlugin>>, String> {
+        plugin_names
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(|name| match self.factories.get(name) {
+                Some(factory) => Ok(factory(context)),
+                None => Err(format!("message store plugin {} is not registered", name)),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct NamedPlugin(String);
+
+    impl MessageStorePlugin for NamedPlugin {
+        fn plugin_name(&self) -> &str {
+            self.0.as_str()
+        }
+    }
+
+    fn context() -> MessageStorePluginContext {
+        MessageStorePluginContext {
+            broker_config: Arc::new(BrokerConfig::default()),
+            message_store_config: Arc::new(MessageStoreConfig::default()),
+        }
+    }
+
+    #[test]
+    fn build_chain_keeps_configured_order() {
+        let mut registry = MessageStorePluginRegistry::new();
+        for name in ["audit", "encryption"] {
+            registry.register(name, move |_| {
+                Arc::new(NamedPlugin(name.to_string())) as Arc<dyn MessageStorePlugin>
+            });
+        }
+
+        let chain = registry
+            .build_chain(" encryption, audit ,", &context())
+            .unwrap();
+        let names: Vec<&str> = chain.iter().map(|plugin| plugin.plugin_name()).collect();
+        assert_eq!(names, vec!["encryption", "audit"]);
+        assert!(registry.build_chain("", &context()).unwrap().is_empty());
+        assert!(registry.build_chain("audit,shadow", &context()).is_err());
+    }
+}
@@ -16,14 +16,27 @@
  */
 
 use std::collections::BTreeMap;
+use std::ops::Bound;
+use std::path::Path;
 use std::path::PathBuf;
 use std::sync::atomic::AtomicI64;
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
 
+use bytes::Buf;
+use bytes::BufMut;
+use bytes::Bytes;
+use bytes::BytesMut;
 use rocketmq_common::common::attribute::cq_type::CQType;
 use rocketmq_common::common::boundary_type::BoundaryType;
 use rocketmq_common::common::message::message_ext_broker_inner::MessageExtBrokerInner;
+use rocketmq_common::common::message::MessageConst;
+use rocketmq_common::common::message::MessageTrait;
+use rocketmq_common::common::sys_flag::message_sys_flag::MessageSysFlag;
+use rocketmq_common::MessageDecoder::message_properties_to_string;
+use tracing::error;
 use tracing::info;
+use tracing::warn;
 
 use crate::base::dispatch_request::DispatchRequest;
 use crate::base::swappable::Swappable;
@@ -31,6 +44,7 @@ use crate::config::message_store_config::MessageStoreConfig;
 use crate::consume_queue::mapped_file_queue::MappedFileQueue;
 use crate::filter::MessageFilter;
 use crate::log_file::mapped_file::default_mapped_file_impl::DefaultMappedFile;
+use crate::log_file::mapped_file::MappedFile;
 use crate::queue::queue_offset_operator::QueueOffsetOperator;
 use crate::queue::ConsumeQueueTrait;
 use crate::queue::CqUnit;
@@ -58,7 +72,11 @@ const INVALID_POS: i32 = -1;
 /// BatchConsumeQueue's store unit. Size:
 /// CommitLog Physical Offset(8) + Body Size(4) + Tag HashCode(8) + Store time(8) +
 /// msgBaseOffset(8) + batchSize(2) + compactedOffset(4) + reserved(4)= 46 Bytes
-
+///
+/// Consume queue of the batch topics, each unit addresses a whole batch stored as one entry of
+/// the commit log with the queue offset of its first message and the number of messages it
+/// holds. Queue offsets keep counting the messages, so the unit of an offset is found with a
+/// binary search over the base offsets instead of from the position of the unit.
 pub struct BatchConsumeQueue {
     message_store_config: Arc<MessageStoreConfig>,
     mapped_file_queue: MappedFileQueue,
@@ -73,7 +91,8 @@ pub struct BatchConsumeQueue {
     max_offset_in_queue: Arc<AtomicI64>,
     min_offset_in_queue: Arc<AtomicI64>,
     commit_log_size: i32,
-    offset_cache: Arc<parking_lot::RwLock<BTreeMap<i64, DefaultMappedFile>>>,
+    /// Files by the base offset of their first unit.
+    offset_cache: Arc<parking_lot::RwLock<BTreeMap<i64, Arc<DefaultMappedFile>>>>,
     time_cache: Arc<parking_lot::RwLock<BTreeMap<i64, DefaultMappedFile>>>,
 }
 
@@ -130,6 +149,263 @@ impl BatchConsumeQueue {
     }
 }
 
+impl BatchConsumeQueue {
+    /// Appends the unit of a batch of `batch_size` messages starting at queue offset
+    /// `msg_base_offset`, stored at `offset` of the commit log.
+    pub fn put_batch_message_position_info(
+        &mut self,
+        offset: i64,
+        size: i32,
+        tags_code: i64,
+        store_time: i64,
+        msg_base_offset: i64,
+        batch_size: i16,
+    ) -> bool {
+        if offset + size as i64 <= self.get_max_physic_offset() {
+            warn!(
+                "Maybe try to build batch consume queue repeatedly maxMsgPhyOffsetInCommitLog={} \
+                 phyOffset={}",
+                self.get_max_physic_offset(),
+                offset
+            );
+            return true;
+        }
+        let max_offset_in_queue = self.max_offset_in_queue.load(Ordering::Acquire);
+        if self.min_offset_in_queue.load(Ordering::Acquire) >= 0
+            && msg_base_offset != max_offset_in_queue
+        {
+            warn!(
+                "[BUG]batch consume queue order maybe wrong, msgBaseOffset: {} maxOffsetInQueue: \
+                 {} Topic: {} QID: {}",
+                msg_base_offset, max_offset_in_queue, self.topic, self.queue_id
+            );
+            if msg_base_offset < max_offset_in_queue {
+                return true;
+            }
+        }
+
+        let mut bytes = BytesMut::with_capacity(CQ_STORE_UNIT_SIZE as usize);
+        bytes.put_i64(offset);
+        bytes.put_i32(size);
+        bytes.put_i64(tags_code);
+        bytes.put_i64(store_time);
+        bytes.put_i64(msg_base_offset);
+        bytes.put_i16(batch_size);
+        bytes.put_i32(INVALID_POS);
+        bytes.put_i32(0);
+
+        let logic_offset = self.mapped_file_queue.get_max_offset();
+        let Some(mut mapped_file) = self
+            .mapped_file_queue
+            .get_last_mapped_file_mut_start_offset(logic_offset as u64, true)
+        else {
+            return false;
+        };
+        // the file size needs not be a multiple of the unit size, the unit goes to the next
+        // file when it does not fit
+        if mapped_file.get_wrote_position() + CQ_STORE_UNIT_SIZE
+            > mapped_file.get_file_size() as i32
+        {
+            let next_offset = mapped_file.get_file_from_offset() + mapped_file.get_file_size();
+            let Some(next) = self.mapped_file_queue.try_create_mapped_file(next_offset) else {
+                return false;
+            };
+            mapped_file = next;
+        }
+        let logic_offset =
+            mapped_file.get_file_from_offset() as i64 + mapped_file.get_wrote_position() as i64;
+        if mapped_file.get_wrote_position() == 0 {
+            self.offset_cache
+                .write()
+                .insert(msg_base_offset, mapped_file.clone());
+        }
+        if !mapped_file.append_message_bytes(&bytes.freeze()) {
+            return false;
+        }
+        self.max_msg_phy_offset_in_commit_log
+            .store(offset + size as i64, Ordering::SeqCst);
+        self.max_offset_in_queue
+            .store(msg_base_offset + batch_size as i64, Ordering::SeqCst);
+        if self.min_offset_in_queue.load(Ordering::Acquire) < 0 {
+            self.min_offset_in_queue
+                .store(msg_base_offset, Ordering::SeqCst);
+            self.min_logic_offset.store(logic_offset, Ordering::SeqCst);
+        }
+        true
+    }
+
+    fn decode_unit(mut bytes: Bytes) -> (CqUnit, i64) {
+        let pos = bytes.get_i64();
+        let size = bytes.get_i32();
+        let tags_code = bytes.get_i64();
+        let store_time = bytes.get_i64();
+        let msg_base_offset = bytes.get_i64();
+        let batch_num = bytes.get_i16();
+        let compacted_offset = bytes.get_i32();
+        let cq_unit = CqUnit {
+            queue_offset: msg_base_offset,
+            size,
+            pos,
+            batch_num,
+            tags_code,
+            compacted_offset,
+            ..CqUnit::default()
+        };
+        (cq_unit, store_time)
+    }
+
+    /// Unit at `position` of `mapped_file` with its store time, `None` past the written units.
+    fn unit_at(mapped_file: &DefaultMappedFile, position: i32) -> Option<(CqUnit, i64)> {
+        if position < 0 || position + CQ_STORE_UNIT_SIZE > mapped_file.get_read_position() {
+            return None;
+        }
+        mapped_file
+            .get_bytes(position as usize, CQ_STORE_UNIT_SIZE as usize)
+            .map(Self::decode_unit)
+    }
+
+    /// Position in `mapped_file` of its first unit still in the queue.
+    fn first_position_in(&self, mapped_file: &DefaultMappedFile) -> i32 {
+        let start = self.min_logic_offset.load(Ordering::Acquire)
+            - mapped_file.get_file_from_offset() as i64;
+        start.clamp(0, mapped_file.get_file_size() as i64) as i32
+    }
+
+    /// Position in `mapped_file` of the unit of the batch holding the message at queue offset
+    /// `index`.
+    fn find_unit_position(&self, mapped_file: &DefaultMappedFile, index: i64) -> Option<i32> {
+        let mut low = self.first_position_in(mapped_file) / CQ_STORE_UNIT_SIZE;
+        let mut high = mapped_file.get_read_position() / CQ_STORE_UNIT_SIZE - 1;
+        while low <= high {
+            let mid = (low + high) / 2;
+            let (cq_unit, _) = Self::unit_at(mapped_file, mid * CQ_STORE_UNIT_SIZE)?;
+            if index < cq_unit.queue_offset {
+                high = mid - 1;
+            } else if index >= cq_unit.queue_offset + cq_unit.batch_num as i64 {
+                low = mid + 1;
+            } else {
+                return Some(mid * CQ_STORE_UNIT_SIZE);
+            }
+        }
+        None
+    }
+
+    /// File and position of the unit of the batch holding the message at queue offset `index`.
+    fn locate(&self, index: i64) -> Option<(Arc<DefaultMappedFile>, i32)> {
+        if index < self.get_min_offset_in_queue() || index >= self.get_max_offset_in_queue() {
+            return None;
+        }
+        let mapped_file = self
+            .offset_cache
+            .read()
+            .range(..=index)
+            .next_back()
+            .map(|(_, mapped_file)| mapped_file.clone())?;
+        let position = self.find_unit_position(&mapped_file, index)?;
+        Some((mapped_file, position))
+    }
+
+    /// First unit in the queue for which `predicate` holds, with its logic offset. The
+    /// predicate must not hold for a unit before one it holds for, like the commit log offsets
+    /// and store times only grow along the queue.
+    fn find_first_unit<F>(&self, predicate: F) -> Option<(CqUnit, i64, i64)>
+    where
+        F: Fn(&CqUnit, i64) -> bool,
+    {
+        let mapped_files = self.mapped_file_queue.get_mapped_files().read().clone();
+        for mapped_file in mapped_files.iter() {
+            let units = mapped_file.get_read_position() / CQ_STORE_UNIT_SIZE;
+            let Some((last_unit, last_store_time)) =
+                Self::unit_at(mapped_file, (units - 1) * CQ_STORE_UNIT_SIZE)
+            else {
+                continue;
+            };
+            if !predicate(&last_unit, last_store_time) {
+                continue;
+            }
+            let mut low = self.first_position_in(mapped_file) / CQ_STORE_UNIT_SIZE;
+            let mut high = units - 1;
+            while low < high {
+                let mid = (low + high) / 2;
+                let (cq_unit, store_time) = Self::unit_at(mapped_file, mid * CQ_STORE_UNIT_SIZE)?;
+                if predicate(&cq_unit, store_time) {
+                    high = mid;
+                } else {
+                    low = mid + 1;
+                }
+            }
+            let (cq_unit, store_time) = Self::unit_at(mapped_file, low * CQ_STORE_UNIT_SIZE)?;
+            let logic_offset =
+                mapped_file.get_file_from_offset() as i64 + (low * CQ_STORE_UNIT_SIZE) as i64;
+            return Some((cq_unit, store_time, logic_offset));
+        }
+        None
+    }
+
+    /// Drops the cached files that are no longer in the queue.
+    fn retain_cached_files(&self) {
+        let mapped_files = self.mapped_file_queue.get_mapped_files();
+        let mapped_files = mapped_files.read();
+        self.offset_cache
+            .write()
+            .retain(|_, mapped_file| mapped_files.contains(mapped_file));
+    }
+
+    /// Resets the queue offsets and the max physical offset from the last unit left.
+    fn refresh_max_offsets(&self) {
+        let latest = self
+            .mapped_file_queue
+            .get_last_mapped_file()
+            .and_then(|mapped_file| {
+                Self::unit_at(
+                    &mapped_file,
+                    mapped_file.get_read_position() - CQ_STORE_UNIT_SIZE,
+                )
+            });
+        match latest {
+            Some((cq_unit, _)) => {
+                self.max_msg_phy_offset_in_commit_log
+                    .store(cq_unit.pos + cq_unit.size as i64, Ordering::SeqCst);
+                self.max_offset_in_queue.store(
+                    cq_unit.queue_offset + cq_unit.batch_num as i64,
+                    Ordering::SeqCst,
+                );
+            }
+            None => {
+                self.max_msg_phy_offset_in_commit_log
+                    .store(-1, Ordering::SeqCst);
+                self.max_offset_in_queue.store(
+                    self.min_offset_in_queue.load(Ordering::Acquire).max(0),
+                    Ordering::SeqCst,
+                );
+            }
+        }
+    }
+
+    /// Sets the min offsets to the first unit of the first file.
+    fn refresh_min_offsets(&self) {
+        let earliest = self
+            .mapped_file_queue
+            .get_first_mapped_file()
+            .and_then(|mapped_file| {
+                Self::unit_at(&mapped_file, 0)
+                    .map(|unit| (unit, mapped_file.get_file_from_offset()))
+            });
+        match earliest {
+            Some(((cq_unit, _), file_from_offset)) => {
+                self.min_offset_in_queue
+                    .store(cq_unit.queue_offset, Ordering::SeqCst);
+                self.min_logic_offset
+                    .store(file_from_offset as i64, Ordering::SeqCst);
+            }
+            None => {
+                self.min_offset_in_queue.store(-1, Ordering::SeqCst);
+                self.min_logic_offset.store(0, Ordering::SeqCst);
+            }
+        }
+    }
+}
+
 #[allow(unused_variables)]
 impl FileQueueLifeCycle for BatchConsumeQueue {
     fn load(&mut self) -> bool {
@@ -145,42 +421,155 @@ impl FileQueueLifeCycle for BatchConsumeQueue {
     }
 
     fn recover(&mut self) {
-        todo!()
+        let mapped_files = self.mapped_file_queue.get_mapped_files().read().clone();
+        if mapped_files.is_empty() {
+            return;
+        }
+        self.offset_cache.write().clear();
+        let scan_from = mapped_files.len().saturating_sub(3);
+        let mut process_offset = mapped_files[0].get_file_from_offset() as i64;
+        for (index, mapped_file) in mapped_files.iter().enumerate() {
+            let file_size = mapped_file.get_file_size() as i32;
+            if index < scan_from {
+                if let Some((cq_unit, _)) = Self::unit_at(mapped_file, 0) {
+                    self.offset_cache
+                        .write()
+                        .insert(cq_unit.queue_offset, mapped_file.clone());
+                }
+                process_offset = mapped_file.get_file_from_offset() as i64 + file_size as i64;
+                continue;
+            }
+            let mut position = 0;
+            while position + CQ_STORE_UNIT_SIZE <= file_size {
+                let Some((cq_unit, _)) = Self::unit_at(mapped_file, position) else {
+                    break;
+                };
+                if cq_unit.pos < 0 || cq_unit.size <= 0 || cq_unit.batch_num <= 0 {
+                    break;
+                }
+                if position == 0 {
+                    self.offset_cache
+                        .write()
+                        .insert(cq_unit.queue_offset, mapped_file.clone());
+                }
+                position += CQ_STORE_UNIT_SIZE;
+            }
+            process_offset = mapped_file.get_file_from_offset() as i64 + position as i64;
+            if position + CQ_STORE_UNIT_SIZE <= file_size {
+                info!(
+                    "recover current batch consume queue file over, {} {}",
+                    mapped_file.get_file_name(),
+                    process_offset
+                );
+                break;
+            }
+        }
+        self.mapped_file_queue.set_flushed_where(process_offset);
+        self.mapped_file_queue.set_committed_where(process_offset);
+        self.mapped_file_queue.truncate_dirty_files(process_offset);
+        self.retain_cached_files();
+        self.refresh_min_offsets();
+        self.refresh_max_offsets();
     }
 
     fn check_self(&self) {
-        todo!()
+        self.mapped_file_queue.check_self();
     }
 
     fn flush(&self, flush_least_pages: i32) -> bool {
-        todo!()
+        self.mapped_file_queue.flush(flush_least_pages)
     }
 
     fn destroy(&mut self) {
-        todo!()
+        self.max_msg_phy_offset_in_commit_log
+            .store(-1, Ordering::SeqCst);
+        self.min_logic_offset.store(0, Ordering::SeqCst);
+        self.max_offset_in_queue.store(0, Ordering::SeqCst);
+        self.min_offset_in_queue.store(-1, Ordering::SeqCst);
+        self.offset_cache.write().clear();
+        self.mapped_file_queue.destroy();
     }
 
     fn truncate_dirty_logic_files(&mut self, max_commit_log_pos: i64) {
-        todo!()
+        while let Some(mapped_file) = self.mapped_file_queue.get_last_mapped_file() {
+            let units = mapped_file.get_read_position() / CQ_STORE_UNIT_SIZE;
+            let mut valid = units;
+            for index in 0..units {
+                match Self::unit_at(&mapped_file, index * CQ_STORE_UNIT_SIZE) {
+                    Some((cq_unit, _)) if cq_unit.pos < max_commit_log_pos => {}
+                    _ => {
+                        valid = index;
+                        break;
+                    }
+                }
+            }
+            if valid == 0 {
+                self.mapped_file_queue.delete_last_mapped_file();
+                continue;
+            }
+            let position = valid * CQ_STORE_UNIT_SIZE;
+            mapped_file.set_wrote_position(position);
+            mapped_file.set_committed_position(position);
+            mapped_file.set_flushed_position(position);
+            break;
+        }
+        self.retain_cached_files();
+        if self.mapped_file_queue.get_mapped_files_size() == 0 {
+            self.refresh_min_offsets();
+        }
+        self.refresh_max_offsets();
     }
 
     fn delete_expired_file(&self, min_commit_log_pos: i64) -> i32 {
-        todo!()
+        let mapped_files = self.mapped_file_queue.get_mapped_files().read().clone();
+        let mut expired = Vec::new();
+        // the last file is kept to go on from its offsets
+        for mapped_file in mapped_files
+            .iter()
+            .take(mapped_files.len().saturating_sub(1))
+        {
+            let Some((last_unit, _)) = Self::unit_at(
+                mapped_file,
+                mapped_file.get_read_position() - CQ_STORE_UNIT_SIZE,
+            ) else {
+                break;
+            };
+            if last_unit.pos + last_unit.size as i64 > min_commit_log_pos {
+                break;
+            }
+            mapped_file.destroy(1000);
+            expired.push(mapped_file.clone());
+        }
+        let deleted = expired.len() as i32;
+        self.mapped_file_queue.clone().delete_expired_file(expired);
+        self.retain_cached_files();
+        self.correct_min_offset(min_commit_log_pos);
+        deleted
     }
 
     fn roll_next_file(&self, next_begin_offset: i64) -> i64 {
-        todo!()
+        self.offset_cache
+            .read()
+            .range((Bound::Excluded(next_begin_offset), Bound::Unbounded))
+            .next()
+            .map(|(msg_base_offset, _)| *msg_base_offset)
+            .unwrap_or_else(|| self.get_max_offset_in_queue())
     }
 
     fn is_first_file_available(&self) -> bool {
-        todo!()
+        self.mapped_file_queue
+            .get_first_mapped_file()
+            .is_some_and(|mapped_file| mapped_file.is_available())
     }
 
     fn is_first_file_exist(&self) -> bool {
-        todo!()
+        self.mapped_file_queue
+            .get_first_mapped_file()
+            .is_some_and(|mapped_file| Path::new(&mapped_file.get_file_name()).exists())
     }
 }
 
+#[allow(unused_variables)]
 impl Swappable for BatchConsumeQueue {
     fn swap_map(
         &self,
@@ -198,51 +587,70 @@ impl Swappable for BatchConsumeQueue {
 
 impl ConsumeQueueTrait for BatchConsumeQueue {
     fn get_topic(&self) -> &str {
-        todo!()
+        self.topic.as_str()
     }
 
     fn get_queue_id(&self) -> i32 {
-        todo!()
+        self.queue_id
     }
 
     fn get(&self, index: i64) -> Option<CqUnit> {
-        todo!()
+        self.get_cq_unit_and_store_time(index)
+            .map(|(cq_unit, _)| cq_unit)
     }
 
     fn get_cq_unit_and_store_time(&self, index: i64) -> Option<(CqUnit, i64)> {
-        todo!()
+        let (mapped_file, position) = self.locate(index)?;
+        Self::unit_at(&mapped_file, position)
     }
 
     fn get_earliest_unit_and_store_time(&self) -> Option<(CqUnit, i64)> {
-        todo!()
+        self.get_cq_unit_and_store_time(self.get_min_offset_in_queue())
     }
 
     fn get_earliest_unit(&self) -> CqUnit {
-        todo!()
+        self.get(self.get_min_offset_in_queue()).unwrap_or_default()
     }
 
     fn get_latest_unit(&self) -> CqUnit {
-        todo!()
+        self.mapped_file_queue
+            .get_last_mapped_file()
+            .and_then(|mapped_file| {
+                Self::unit_at(
+                    &mapped_file,
+                    mapped_file.get_read_position() - CQ_STORE_UNIT_SIZE,
+                )
+            })
+            .map(|(cq_unit, _)| cq_unit)
+            .unwrap_or_default()
     }
 
     fn get_last_offset(&self) -> i64 {
-        todo!()
+        let latest = self.get_latest_unit();
+        if latest.size <= 0 {
+            return -1;
+        }
+        latest.pos + latest.size as i64
     }
 
     fn get_min_offset_in_queue(&self) -> i64 {
-        todo!()
+        let min_offset_in_queue = self.min_offset_in_queue.load(Ordering::Acquire);
+        if min_offset_in_queue < 0 {
+            return self.get_max_offset_in_queue();
+        }
+        min_offset_in_queue
     }
 
     fn get_max_offset_in_queue(&self) -> i64 {
-        todo!()
+        self.max_offset_in_queue.load(Ordering::Acquire)
     }
 
     fn get_message_total_in_queue(&self) -> i64 {
-        todo!()
+        self.get_max_offset_in_queue() - self.get_min_offset_in_queue()
     }
 
     fn get_offset_in_queue_by_time(&self, timestamp: i64) -> i64 {
-        todo!()
+        self.get_offset_in_queue_by_time_boundary(timestamp, BoundaryType::Lower)
     }
 
     fn get_offset_in_queue_by_time_boundary(
@@ -250,35 +658,81 @@ impl ConsumeQueueTrait for BatchConsumeQueue {
         timestamp: i64,
         boundary_type: BoundaryType,
     ) -> i64 {
-        todo!()
+        match boundary_type {
+            BoundaryType::Lower => self
+                .find_first_unit(|_, store_time| store_time >= timestamp)
+                .map(|(cq_unit, _, _)| cq_unit.queue_offset)
+                .unwrap_or_else(|| self.get_max_offset_in_queue()),
+            BoundaryType::Upper => {
+                let next = self
+                    .find_first_unit(|_, store_time| store_time > timestamp)
+                    .map(|(cq_unit, _, _)| cq_unit.queue_offset)
+                    .unwrap_or_else(|| self.get_max_offset_in_queue());
+                (next - 1).max(self.get_min_offset_in_queue())
+            }
+        }
     }
 
     fn get_max_physic_offset(&self) -> i64 {
-        todo!()
+        self.max_msg_phy_offset_in_commit_log.load(Ordering::SeqCst)
     }
 
     fn get_min_logic_offset(&self) -> i64 {
-        todo!()
+        self.min_logic_offset.load(Ordering::Relaxed)
     }
 
     fn get_cq_type(&self) -> CQType {
-        todo!()
+        CQType::BatchCQ
     }
 
     fn get_total_size(&self) -> i64 {
-        todo!()
+        self.mapped_file_queue.get_mapped_files_size() as i64 * self.mapped_file_size as i64
     }
 
     fn get_unit_size(&self) -> i32 {
-        todo!()
+        CQ_STORE_UNIT_SIZE
     }
 
     fn correct_min_offset(&self, min_commit_log_offset: i64) {
-        todo!()
+        match self.find_first_unit(|cq_unit, _| cq_unit.pos >= min_commit_log_offset) {
+            Some((cq_unit, _, logic_offset)) => {
+                self.min_offset_in_queue
+                    .store(cq_unit.queue_offset, Ordering::SeqCst);
+                self.min_logic_offset.store(logic_offset, Ordering::SeqCst);
+            }
+            None => {
+                info!(
+                    "BatchConsumeQueue[Topic={}, queue-id={}] contains no valid entries",
+                    self.topic, self.queue_id
+                );
+                self.min_offset_in_queue
+                    .store(self.get_max_offset_in_queue(), Ordering::SeqCst);
+                self.min_logic_offset
+                    .store(self.mapped_file_queue.get_max_offset(), Ordering::SeqCst);
+            }
+        }
     }
 
     fn put_message_position_info_wrapper(&mut self, request: &DispatchRequest) {
-        todo!()
+        // a message sent alone to a batch topic is a batch of one at its queue offset
+        let msg_base_offset = if request.msg_base_offset >= 0 {
+            request.msg_base_offset
+        } else {
+            request.consume_queue_offset
+        };
+        if !self.put_batch_message_position_info(
+            request.commit_log_offset,
+            request.msg_size,
+            request.tags_code,
+            request.store_timestamp,
+            msg_base_offset,
+            request.batch_size,
+        ) {
+            error!(
+                "[BUG]batch consume queue can not write, {} {}",
+                self.topic, self.queue_id
+            );
+        }
     }
 
     fn increase_queue_offset(
@@ -287,7 +741,10 @@ impl ConsumeQueueTrait for BatchConsumeQueue {
         msg: &MessageExtBrokerInner,
         message_num: i16,
     ) {
-        todo!()
+        queue_offset_assigner.increase_batch_queue_offset(
+            format!("{}-{}", msg.topic(), msg.queue_id()).as_str(),
+            message_num,
+        );
     }
 
     fn assign_queue_offset(
@@ -295,22 +752,143 @@ impl ConsumeQueueTrait for BatchConsumeQueue {
         queue_offset_operator: &QueueOffsetOperator,
         msg: &mut MessageExtBrokerInner,
     ) {
-        todo!()
+        let queue_offset = queue_offset_operator
+            .get_batch_queue_offset(format!("{}-{}", msg.topic(), msg.queue_id()).as_str());
+        if MessageSysFlag::check(msg.sys_flag(), MessageSysFlag::INNER_BATCH_FLAG) {
+            msg.message_ext_inner.message.put_property(
+                MessageConst::PROPERTY_INNER_BASE,
+                queue_offset.to_string().as_str(),
+            );
+            msg.properties_string =
+                message_properties_to_string(msg.message_ext_inner.message.properties());
+        }
+        msg.message_ext_inner.queue_offset = queue_offset;
     }
 
     fn estimate_message_count(&self, from: i64, to: i64, filter: &dyn MessageFilter) -> i64 {
-        todo!()
+        let mut count = 0i64;
+        let mut next = from.max(self.get_min_offset_in_queue());
+        while next <= to {
+            let Some(mut units) = self.iterate_from(next) else {
+                break;
+            };
+            let last = next;
+            for cq_unit in units.by_ref() {
+                if cq_unit.queue_offset > to {
+                    return count;
+                }
+                next = cq_unit.queue_offset + cq_unit.batch_num as i64;
+                if filter.is_matched_by_consume_queue(
+                    cq_unit.get_valid_tags_code_as_long(),
+                    cq_unit.cq_ext_unit.as_ref(),
+                ) {
+                    count += cq_unit.batch_num as i64;
+                }
+            }
+            if next == last {
+                break;
+            }
+        }
+        count
     }
 
     fn iterate_from(&self, start_index: i64) -> Option<Box<dyn Iterator<Item = CqUnit>>> {
-        todo!()
+        let (mapped_file, position) = self.locate(start_index)?;
+        let limit = mapped_file.get_read_position();
+        Some(Box::new(BatchConsumeQueueIterator {
+            mapped_file,
+            position,
+            limit,
+        }))
     }
 
     fn iterate_from_inner(
         &self,
         start_index: i64,
-        count: i32,
+        _count: i32,
     ) -> Option<Box<dyn Iterator<Item = CqUnit>>> {
-        todo!()
+        self.iterate_from(start_index)
+    }
+}
+
+/// Units of one file from the batch holding the requested queue offset on, the first unit may
+/// start before that offset.
+struct BatchConsumeQueueIterator {
+    mapped_file: Arc<DefaultMappedFile>,
+    position: i32,
+    limit: i32,
+}
+
+impl Iterator for BatchConsumeQueueIterator {
+    type Item = CqUnit;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.position + CQ_STORE_UNIT_SIZE > self.limit {
+            return None;
+        }
+        let (cq_unit, _) = BatchConsumeQueue::unit_at(&self.mapped_file, self.position)?;
+        self.position += CQ_STORE_UNIT_SIZE;
+        Some(cq_unit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::*;
+
+    fn new_queue(store_path: &str) -> BatchConsumeQueue {
+        BatchConsumeQueue::new(
+            "TopicTest".to_string(),
+            0,
+            store_path.to_string(),
+            (CQ_STORE_UNIT_SIZE * 2) as usize,
+            None,
+            Arc::new(MessageStoreConfig::default()),
+        )
+    }
+
+    #[test]
+    fn inner_offsets_resolve_to_their_batch() {
+        let dir = tempdir().unwrap();
+        let store_path = dir.path().to_string_lossy().to_string();
+        let mut queue = new_queue(&store_path);
+        assert!(queue.put_batch_message_position_info(0, 100, 1, 1000, 0, 3));
+        assert!(queue.put_batch_message_position_info(100, 50, 2, 2000, 3, 1));
+        // the third batch starts the next file
+        assert!(queue.put_batch_message_position_info(150, 80, 3, 3000, 4, 5));
+        assert_eq!(queue.get_min_offset_in_queue(), 0);
+        assert_eq!(queue.get_max_offset_in_queue(), 9);
+
+        let cq_unit = queue.get(1).unwrap();
+        assert_eq!((cq_unit.queue_offset, cq_unit.batch_num), (0, 3));
+        assert_eq!(queue.get(3).unwrap().pos, 100);
+        assert_eq!(queue.get(6).unwrap().pos, 150);
+        assert!(queue.get(9).is_none());
+        assert_eq!(queue.iterate_from(2).unwrap().count(), 2);
+        assert_eq!(queue.roll_next_file(2), 4);
+        assert_eq!(
+            queue.get_offset_in_queue_by_time_boundary(1500, BoundaryType::Lower),
+            3
+        );
+        assert_eq!(
+            queue.get_offset_in_queue_by_time_boundary(2500, BoundaryType::Upper),
+            3
+        );
+
+        let mut recovered = new_queue(&store_path);
+        assert!(recovered.load());
+        recovered.recover();
+        assert_eq!(recovered.get_max_offset_in_queue(), 9);
+        assert_eq!(recovered.get_max_physic_offset(), 230);
+        assert_eq!(recovered.get(7).unwrap().pos, 150);
+
+        recovered.truncate_dirty_logic_files(150);
+        assert_eq!(recovered.get_max_offset_in_queue(), 4);
+        assert!(recovered.get(6).is_none());
+        recovered.correct_min_offset(100);
+        assert_eq!(recovered.get_min_offset_in_queue(), 3);
+        assert!(recovered.get(1).is_none());
     }
 }
@@ -16,10 +16,14 @@
  */
 use std::path::PathBuf;
 
+use tracing::error;
 use tracing::info;
+use tracing::warn;
 
 use crate::consume_queue::consume_queue_ext::CqExtUnit;
+use crate::consume_queue::consume_queue_ext::MAX_EXT_UNIT_SIZE;
 use crate::consume_queue::mapped_file_queue::MappedFileQueue;
+use crate::log_file::mapped_file::MappedFile;
 
 const END_BLANK_DATA_LENGTH: usize = 4;
 
@@ -27,6 +31,10 @@ const END_BLANK_DATA_LENGTH: usize = 4;
 const MAX_ADDR: i64 = i32::MIN as i64 - 1;
 const MAX_REAL_OFFSET: i64 = MAX_ADDR - i64::MIN;
 
+/// Extension of a consume queue, holding per message the data that does not fit its 20 bytes
+/// units: the store time and the filter bit map computed at dispatch. The consume queue unit
+/// then keeps the address of the ext unit in place of the tags code, addresses are below
+/// [`MAX_ADDR`] so that they can not be mistaken for a tags code.
 #[derive(Clone)]
 pub struct ConsumeQueueExt {
     mapped_file_queue: MappedFileQueue,
@@ -64,12 +72,76 @@ impl ConsumeQueueExt {
     pub fn is_ext_addr(address: i64) -> bool {
         address <= MAX_ADDR
     }
+
+    /// Address of the ext unit at `offset` in the ext files.
+    pub fn decorate(offset: i64) -> i64 {
+        if Self::is_ext_addr(offset) {
+            offset
+        } else {
+            offset + i64::MIN
+        }
+    }
+
+    /// Offset in the ext files of the ext unit at `address`.
+    pub fn un_decorate(address: i64) -> i64 {
+        if Self::is_ext_addr(address) {
+            address - i64::MIN
+        } else {
+            address
+        }
+    }
 }
 
 impl ConsumeQueueExt {
-    pub fn truncate_by_max_address(&self, max_address: i64) {}
+    /// Drops the units written after the one at `max_address`, the last unit still referenced
+    /// by the consume queue.
+    pub fn truncate_by_max_address(&self, max_address: i64) {
+        if !Self::is_ext_addr(max_address) {
+            return;
+        }
+        let Some(cq_ext_unit) = self.get(max_address) else {
+            return;
+        };
+        let real_offset = Self::un_decorate(max_address);
+        info!(
+            "Truncate consume queue ext by max, max address: {}, real offset: {}",
+            max_address, real_offset
+        );
+        // the mapped file queue shares its files between clones
+        self.mapped_file_queue
+            .clone()
+            .truncate_dirty_files(real_offset + cq_ext_unit.size() as i64);
+    }
 
-    pub fn truncate_by_min_address(&self, min_address: i64) {}
+    /// Deletes the files that only hold units before `min_address`, the first unit still
+    /// referenced by the consume queue.
+    pub fn truncate_by_min_address(&self, min_address: i64) {
+        if !Self::is_ext_addr(min_address) {
+            return;
+        }
+        let real_offset = Self::un_decorate(min_address);
+        let will_remove_files = self
+            .mapped_file_queue
+            .get_mapped_files()
+            .read()
+            .iter()
+            .filter(|file| {
+                (file.get_file_from_offset() + self.mapped_file_size as u64) < real_offset as u64
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        for file in &will_remove_files {
+            file.destroy(1000);
+        }
+        info!(
+            "Truncate consume queue ext by min, min address: {}, removed {} files",
+            min_address,
+            will_remove_files.len()
+        );
+        self.mapped_file_queue
+            .clone()
+            .delete_expired_file(will_remove_files);
+    }
 
     pub fn load(&mut self) -> bool {
         let result = self.mapped_file_queue.load();
@@ -83,17 +155,152 @@ impl ConsumeQueueExt {
         result
     }
 
-    pub fn recover(&mut self) {}
+    /// Moves the write position of the loaded files to the end of their last unit, the
+    /// consume queue truncates what it does not reference afterwards.
+    pub fn recover(&mut self) {
+        let mapped_files = self.mapped_file_queue.get_mapped_files();
+        let mut process_offset = None;
+        for mapped_file in mapped_files.read().iter() {
+            let data = mapped_file.get_mapped_file();
+            let mut mapped_file_offset = 0usize;
+            while let Some(cq_ext_unit) = CqExtUnit::read(&data[mapped_file_offset..]) {
+                mapped_file_offset += cq_ext_unit.size() as usize;
+            }
+            process_offset =
+                Some(mapped_file.get_file_from_offset() as i64 + mapped_file_offset as i64);
+        }
+        let Some(process_offset) = process_offset else {
+            return;
+        };
+        self.mapped_file_queue.set_flushed_where(process_offset);
+        self.mapped_file_queue.set_committed_where(process_offset);
+        self.mapped_file_queue.truncate_dirty_files(process_offset);
+    }
 
-    pub fn put(&self, cq_ext_unit: CqExtUnit) -> i64 {
-        unimplemented!()
+    /// Appends `cq_ext_unit` and returns its address, or `1` when it could not be written,
+    /// the caller then keeps the plain tags code in the consume queue.
+    pub fn put(&mut self, cq_ext_unit: CqExtUnit) -> i64 {
+        const RETRY_TIMES: usize = 3;
+        let size = cq_ext_unit.calc_unit_size();
+        if size > MAX_EXT_UNIT_SIZE as usize {
+            error!(
+                "Size of cq ext unit is greater than {}, {}",
+                MAX_EXT_UNIT_SIZE, size
+            );
+            return 1;
+        }
+        if self.mapped_file_queue.get_max_offset() + size as i64 > MAX_REAL_OFFSET {
+            warn!(
+                "Capacity of ext is maximum!{}, {}",
+                self.mapped_file_queue.get_max_offset(),
+                size
+            );
+            return 1;
+        }
+        let data = cq_ext_unit.write();
+        for _ in 0..RETRY_TIMES {
+            let Some(mapped_file) = self
+                .mapped_file_queue
+                .get_last_mapped_file_mut_start_offset(0, true)
+            else {
+                error!(
+                    "Create mapped file when save consume queue extend, {}-{}",
+                    self.topic, self.queue_id
+                );
+                continue;
+            };
+            let wrote_position = mapped_file.get_wrote_position();
+            let blank_size =
+                self.mapped_file_size as usize - wrote_position as usize - END_BLANK_DATA_LENGTH;
+            if size > blank_size {
+                // marks the end of the units of the file and rolls to the next one
+                mapped_file.put_slice(&(-1i16).to_be_bytes(), wrote_position as usize);
+                mapped_file.set_wrote_position(self.mapped_file_size);
+                info!(
+                    "No enough space(need:{}, has:{}) of file {}, so fill to end",
+                    size,
+                    blank_size,
+                    mapped_file.get_file_name()
+                );
+                continue;
+            }
+            if mapped_file.append_message_bytes(&data) {
+                return Self::decorate(
+                    wrote_position as i64 + mapped_file.get_file_from_offset() as i64,
+                );
+            }
+        }
+        1
     }
 
     pub fn destroy(&mut self) {
         self.mapped_file_queue.destroy();
     }
 
-    pub fn get(&self, address: i64, cq_ext_unit: &CqExtUnit) -> bool {
-        unimplemented!()
+    pub fn flush(&self, flush_least_pages: i32) -> bool {
+        self.mapped_file_queue.flush(flush_least_pages)
+    }
+
+    /// Ext unit at `address`, `None` when the address is not an ext address or the unit is
+    /// gone.
+    pub fn get(&self, address: i64) -> Option<CqExtUnit> {
+        if !Self::is_ext_addr(address) {
+            return None;
+        }
+        let real_offset = Self::un_decorate(address);
+        let mapped_file = self
+            .mapped_file_queue
+            .find_mapped_file_by_offset(real_offset, real_offset == 0)?;
+        let pos = (real_offset % self.mapped_file_size as i64) as usize;
+        if pos >= mapped_file.get_wrote_position() as usize {
+            return None;
+        }
+        CqExtUnit::read(&mapped_file.get_mapped_file()[pos..])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use tempfile::tempdir;
+
+    use super::*;
+
+    #[test]
+    fn put_returns_ext_addresses_readable_until_truncated() {
+        let dir = tempdir().unwrap();
+        let mut ext = ConsumeQueueExt::new(
+            "TopicTest".to_string(),
+            0,
+            dir.path().to_string_lossy().to_string(),
+            64,
+            64,
+        );
+        let first = ext.put(CqExtUnit::new(
+            7,
+            1000,
+            Some(Bytes::from_static(&[0b1010, 0xff])),
+        ));
+        let second = ext.put(CqExtUnit::new(8, 2000, None));
+        // the third unit does not fit behind the second one, it starts the next file
+        let third = ext.put(CqExtUnit::new(9, 3000, None));
+        assert!(ConsumeQueueExt::is_ext_addr(first));
+        assert_eq!(ConsumeQueueExt::un_decorate(first), 0);
+        assert_eq!(ConsumeQueueExt::un_decorate(third), 64);
+        assert!(!ConsumeQueueExt::is_ext_addr(7));
+
+        let unit = ext.get(first).unwrap();
+        assert_eq!(unit.tags_code(), 7);
+        assert_eq!(unit.msg_store_time(), 1000);
+        assert_eq!(
+            unit.filter_bit_map(),
+            &Some(Bytes::from_static(&[0b1010, 0xff]))
+        );
+        assert_eq!(ext.get(second).unwrap().tags_code(), 8);
+        assert!(ext.get(7).is_none());
+
+        ext.truncate_by_max_address(second);
+        assert!(ext.get(third).is_none());
+        assert_eq!(ext.get(second).unwrap().msg_store_time(), 2000);
     }
 }
@@ -41,6 +41,7 @@ use crate::queue::ConsumeQueueStoreTrait;
 use crate::queue::ConsumeQueueTable;
 use crate::queue::ConsumeQueueTrait;
 use crate::queue::CqUnit;
+use crate::services::allocate_mapped_file_service::AllocateMappedFileService;
 use crate::store::running_flags::RunningFlags;
 use crate::store_path_config_helper::get_store_path_batch_consume_queue;
 use crate::store_path_config_helper::get_store_path_consume_queue;
@@ -59,6 +60,7 @@ struct Inner {
     pub(crate) broker_config: Arc<BrokerConfig>,
     pub(crate) queue_offset_operator: QueueOffsetOperator,
     pub(crate) consume_queue_table: Arc<ConsumeQueueTable>,
+    pub(crate) allocate_mapped_file_service: Option<AllocateMappedFileService>,
 }
 
 impl Inner {
@@ -78,6 +80,7 @@ impl ConsumeQueueStore {
         topic_config_table: Arc<parking_lot::Mutex<HashMap<String, TopicConfig>>>,
         running_flags: Arc<RunningFlags>,
         store_checkpoint: Arc<StoreCheckpoint>,
+        allocate_mapped_file_service: Option<AllocateMappedFileService>,
     ) -> Self {
         Self {
             inner: Arc::new(Inner {
@@ -86,6 +89,7 @@ impl ConsumeQueueStore {
                 broker_config,
                 queue_offset_operator: QueueOffsetOperator::new(),
                 consume_queue_table: Arc::new(parking_lot::Mutex::new(HashMap::new())),
+                allocate_mapped_file_service,
             }),
             running_flags,
             store_checkpoint,
@@ -157,7 +161,7 @@ impl ConsumeQueueStoreTrait for ConsumeQueueStore {
     }
 
     fn flush(&self, consume_queue: &dyn ConsumeQueueTrait, flush_least_pages: i32) -> bool {
-        todo!()
+        consume_queue.flush(flush_least_pages)
     }
 
     fn clean_expired(&self, min_phy_offset: i64) {
@@ -344,6 +348,7 @@ impl ConsumeQueueStoreTrait for ConsumeQueueStore {
                     self.inner.message_store_config.clone(),
                     self.running_flags.clone(),
                     self.store_checkpoint.clone(),
+                    self.inner.allocate_mapped_file_service.clone(),
                 ))),
                 CQType::BatchCQ => ArcRefCellWrapper::new(Box::new(BatchConsumeQueue::new(
                     topic.to_string(),
@@ -510,6 +515,7 @@ impl ConsumeQueueStore {
                     self.inner.message_store_config.clone(),
                     self.running_flags.clone(),
                     self.store_checkpoint.clone(),
+                    self.inner.allocate_mapped_file_service.clone(),
                 );
                 Box::new(consume_queue)
             }
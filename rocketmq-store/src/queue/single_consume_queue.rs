@@ -26,6 +26,8 @@ use bytes::BytesMut;
 use rocketmq_common::common::attribute::cq_type::CQType;
 use rocketmq_common::common::boundary_type::BoundaryType;
 use rocketmq_common::common::message::message_ext_broker_inner::MessageExtBrokerInner;
+use rocketmq_common::common::offset::CommitLogOffset;
+use rocketmq_common::common::offset::QueueOffset;
 use tracing::debug;
 use tracing::error;
 use tracing::info;
@@ -47,6 +49,7 @@ use crate::queue::queue_offset_operator::QueueOffsetOperator;
 use crate::queue::ConsumeQueueTrait;
 use crate::queue::CqUnit;
 use crate::queue::FileQueueLifeCycle;
+use crate::services::allocate_mapped_file_service::AllocateMappedFileService;
 use crate::store::running_flags::RunningFlags;
 use crate::store_path_config_helper::get_store_path_consume_queue_ext;
 
@@ -90,6 +93,7 @@ impl ConsumeQueue {
         message_store_config: Arc<MessageStoreConfig>,
         running_flags: Arc<RunningFlags>,
         store_checkpoint: Arc<StoreCheckpoint>,
+        allocate_mapped_file_service: Option<AllocateMappedFileService>,
     ) -> Self {
         let queue_dir = PathBuf::from(store_path.clone())
             .join(topic.clone())
@@ -97,7 +101,7 @@ impl ConsumeQueue {
         let mapped_file_queue = MappedFileQueue::new(
             queue_dir.to_string_lossy().to_string(),
             mapped_file_size as u64,
-            None,
+            allocate_mapped_file_service,
         );
         let consume_queue_ext = if message_store_config.enable_consume_queue_ext {
             Some(ConsumeQueueExt::new(
@@ -230,7 +234,14 @@ impl ConsumeQueue {
         tags_code: i64,
         cq_offset: i64,
     ) -> bool {
-        if offset + size as i64 <= self.get_max_physic_offset() {
+        let Some(end_offset) = CommitLogOffset::new(offset).checked_add_size(size) else {
+            error!(
+                "commit log offset overflow, phyOffset={} size={}",
+                offset, size
+            );
+            return false;
+        };
+        if end_offset.value() <= self.get_max_physic_offset() {
             warn!(
                 "Maybe try to build consume queue repeatedly maxPhysicOffset={} phyOffset={}, \
                  size={}",
@@ -245,7 +256,12 @@ impl ConsumeQueue {
         bytes.put_i32(size);
         bytes.put_i64(tags_code);
 
-        let expect_logic_offset = cq_offset * CQ_STORE_UNIT_SIZE as i64;
+        let Some(expect_logic_offset) =
+            QueueOffset::new(cq_offset).to_byte_offset(CQ_STORE_UNIT_SIZE)
+        else {
+            error!("consume queue offset overflow, cqOffset={}", cq_offset);
+            return false;
+        };
         if let Some(mapped_file) = self
             .mapped_file_queue
             .get_last_mapped_file_mut_start_offset(expect_logic_offset as u64, true)
@@ -320,7 +336,7 @@ impl ConsumeQueue {
 
     pub fn get_index_buffer(&self, start_index: i64) -> Option<SelectMappedBufferResult> {
         let mapped_file_size = self.mapped_file_size;
-        let offset = start_index * CQ_STORE_UNIT_SIZE as i64;
+        let offset = QueueOffset::new(start_index).to_byte_offset(CQ_STORE_UNIT_SIZE)?;
         if offset >= self.get_min_logic_offset() {
             if let Some(mapped_file) = self
                 .mapped_file_queue
@@ -443,7 +459,15 @@ impl FileQueueLifeCycle for ConsumeQueue {
     }
 
     fn flush(&self, flush_least_pages: i32) -> bool {
-        todo!()
+        let mut result = self.mapped_file_queue.flush(flush_least_pages);
+        if self.is_ext_read_enable() {
+            result &= self
+                .consume_queue_ext
+                .as_ref()
+                .unwrap()
+                .flush(flush_least_pages);
+        }
+        result
     }
 
     fn destroy(&mut self) {
@@ -502,10 +526,7 @@ impl ConsumeQueueTrait for ConsumeQueue {
     }
 
     fn get(&self, index: i64) -> Option<CqUnit> {
-        match self.iterate_from(index) {
-            None => None,
-            Some(value) => None,
-        }
+        self.iterate_from(index)?.next()
     }
 
     fn get_cq_unit_and_store_time(&self, index: i64) -> Option<(CqUnit, i64)> {
@@ -529,11 +550,16 @@ impl ConsumeQueueTrait for ConsumeQueue {
     }
 
     fn get_min_offset_in_queue(&self) -> i64 {
-        self.min_logic_offset.load(Ordering::Acquire) / CQ_STORE_UNIT_SIZE as i64
+        QueueOffset::from_byte_offset(
+            self.min_logic_offset.load(Ordering::Acquire),
+            CQ_STORE_UNIT_SIZE,
+        )
+        .value()
     }
 
     fn get_max_offset_in_queue(&self) -> i64 {
-        self.mapped_file_queue.get_max_offset() / CQ_STORE_UNIT_SIZE as i64
+        QueueOffset::from_byte_offset(self.mapped_file_queue.get_max_offset(), CQ_STORE_UNIT_SIZE)
+            .value()
     }
 
     fn get_message_total_in_queue(&self) -> i64 {
@@ -720,7 +746,7 @@ impl ConsumeQueueTrait for ConsumeQueue {
         while i < max_retries && can_write {
             let mut tags_code = request.tags_code;
             if self.is_ext_write_enable() {
-                let ext_addr = self.consume_queue_ext.as_ref().unwrap().put(CqExtUnit::new(
+                let ext_addr = self.consume_queue_ext.as_mut().unwrap().put(CqExtUnit::new(
                     tags_code,
                     request.store_timestamp,
                     request.bit_map.clone(),
@@ -823,11 +849,8 @@ struct ConsumeQueueIterator {
 }
 
 impl ConsumeQueueIterator {
-    fn get_ext(&self, offset: i64, cq_ext_unit: &CqExtUnit) -> bool {
-        match self.consume_queue_ext.as_ref() {
-            None => false,
-            Some(value) => value.get(offset, cq_ext_unit),
-        }
+    fn get_ext(&self, offset: i64) -> Option<CqExtUnit> {
+        self.consume_queue_ext.as_ref()?.get(offset)
     }
 }
 
@@ -859,9 +882,7 @@ impl Iterator for ConsumeQueueIterator {
                 };
 
                 if ConsumeQueueExt::is_ext_addr(cq_unit.tags_code) {
-                    let cq_ext_unit = CqExtUnit::default();
-                    let ext_ret = self.get_ext(cq_unit.tags_code, &cq_ext_unit);
-                    if ext_ret {
+                    if let Some(cq_ext_unit) = self.get_ext(cq_unit.tags_code) {
                         cq_unit.tags_code = cq_ext_unit.tags_code();
                         cq_unit.cq_ext_unit = Some(cq_ext_unit);
                     } else {
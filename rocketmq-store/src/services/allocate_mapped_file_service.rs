@@ -1,2 +1,320 @@
-#[derive(Default, Clone)]
-pub struct AllocateMappedFileService;
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::collections::HashMap;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::mpsc;
+use std::sync::mpsc::Receiver;
+use std::sync::mpsc::Sender;
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
+
+use parking_lot::Condvar;
+use parking_lot::Mutex;
+use tracing::error;
+use tracing::warn;
+
+use crate::config::message_store_config::MessageStoreConfig;
+use crate::log_file::mapped_file::default_mapped_file_impl::DefaultMappedFile;
+use crate::log_file::mapped_file::MappedFile;
+
+/// How long a file roll waits for a file being allocated before giving up.
+const WAIT_TIMEOUT: Duration = Duration::from_secs(5);
+
+enum AllocateState {
+    Queued,
+    Allocating,
+    Allocated(Box<DefaultMappedFile>),
+    Cancelled,
+}
+
+struct AllocateRequest {
+    file_path: String,
+    file_size: u64,
+    state: Mutex<AllocateState>,
+    allocated: Condvar,
+}
+
+impl AllocateRequest {
+    /// Waits up to `timeout` for the file to be allocated and takes it.
+    fn take(&self, timeout: Duration) -> Option<DefaultMappedFile> {
+        let deadline = Instant::now() + timeout;
+        let mut state = self.state.lock();
+        while matches!(*state, AllocateState::Queued | AllocateState::Allocating) {
+            if self.allocated.wait_until(&mut state, deadline).timed_out() {
+                break;
+            }
+        }
+        match std::mem::replace(&mut *state, AllocateState::Cancelled) {
+            AllocateState::Allocated(mapped_file) => Some(*mapped_file),
+            _ => None,
+        }
+    }
+}
+
+/// Time file rolls spent waiting for their mapped file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AllocateMappedFileSnapshot {
+    pub requests: u64,
+    /// Requests served by a file allocated ahead, the others allocated it on the append path.
+    pub pre_allocated: u64,
+    pub total_wait_nanos: u64,
+    pub max_wait_nanos: u64,
+}
+
+impl AllocateMappedFileSnapshot {
+    pub fn avg_wait_nanos(&self) -> u64 {
+        self.total_wait_nanos
+            .checked_div(self.requests)
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Default)]
+struct AllocateMappedFileStats {
+    requests: AtomicU64,
+    pre_allocated: AtomicU64,
+    total_wait_nanos: AtomicU64,
+    max_wait_nanos: AtomicU64,
+}
+
+/// Creates commit log and consume queue files ahead of the file roll.
+///
+/// When a queue asks for its next file, the file after it is queued for allocation on the
+/// background `AllocateMappedFileService` thread and, for commit log files with
+/// `warm_mapped_file_enable` set, its pages are pre-touched and locked into memory, so the next
+/// roll finds its file ready instead of stalling the append path on file creation and page
+/// faults.
+#[derive(Clone)]
+pub struct AllocateMappedFileService {
+    request_table: Arc<Mutex<HashMap<String, Arc<AllocateRequest>>>>,
+    request_queue: Sender<Arc<AllocateRequest>>,
+    message_store_config: Arc<MessageStoreConfig>,
+    stats: Arc<AllocateMappedFileStats>,
+}
+
+impl Default for AllocateMappedFileService {
+    fn default() -> Self {
+        Self::new(Arc::new(MessageStoreConfig::default()))
+    }
+}
+
+impl AllocateMappedFileService {
+    pub fn new(message_store_config: Arc<MessageStoreConfig>) -> Self {
+        let (request_queue, requests) = mpsc::channel();
+        let config = message_store_config.clone();
+        if let Err(err) = std::thread::Builder::new()
+            .name("AllocateMappedFileService".to_string())
+            .spawn(move || Self::run(config, requests))
+        {
+            warn!("spawn allocate mapped file thread failed: {}", err);
+        }
+        Self {
+            request_table: Arc::new(Mutex::new(HashMap::new())),
+            request_queue,
+            message_store_config,
+            stats: Arc::new(AllocateMappedFileStats::default()),
+        }
+    }
+
+    /// Allocates the queued files until every handle to the service is dropped.
+    fn run(
+        message_store_config: Arc<MessageStoreConfig>,
+        requests: Receiver<Arc<AllocateRequest>>,
+    ) {
+        for request in requests {
+            {
+                let mut state = request.state.lock();
+                if !matches!(*state, AllocateState::Queued) {
+                    continue;
+                }
+                *state = AllocateState::Allocating;
+            }
+            let mapped_file = allocate(
+                &message_store_config,
+                request.file_path.clone(),
+                request.file_size,
+            );
+            let mut state = request.state.lock();
+            if matches!(*state, AllocateState::Allocating) {
+                *state = AllocateState::Allocated(Box::new(mapped_file));
+                request.allocated.notify_all();
+            }
+        }
+    }
+
+    /// Returns the mapped file for `next_file_path`, taking the pre-allocated one when it exists,
+    /// and queues the allocation of `next_next_file_path`. `None` when the pre-allocation of
+    /// the file did not finish in time.
+    pub fn put_request_and_return_mapped_file(
+        &self,
+        next_file_path: String,
+        next_next_file_path: String,
+        file_size: u64,
+    ) -> Option<DefaultMappedFile> {
+        let begin = Instant::now();
+        let pending = self.request_table.lock().remove(&next_file_path);
+        let pre_allocated = pending.is_some();
+        let mapped_file = match pending {
+            Some(request) => {
+                let mapped_file = request.take(WAIT_TIMEOUT);
+                if mapped_file.is_none() {
+                    error!(
+                        "allocate mapped file {} timed out after {:?}",
+                        next_file_path, WAIT_TIMEOUT
+                    );
+                }
+                mapped_file
+            }
+            None => Some(allocate(
+                &self.message_store_config,
+                next_file_path,
+                file_size,
+            )),
+        };
+        self.record_wait(begin.elapsed(), pre_allocated);
+        self.submit(next_next_file_path, file_size);
+        mapped_file
+    }
+
+    fn submit(&self, file_path: String, file_size: u64) {
+        let mut request_table = self.request_table.lock();
+        if request_table.contains_key(&file_path) {
+            return;
+        }
+        let request = Arc::new(AllocateRequest {
+            file_path: file_path.clone(),
+            file_size,
+            state: Mutex::new(AllocateState::Queued),
+            allocated: Condvar::new(),
+        });
+        if self.request_queue.send(request.clone()).is_ok() {
+            request_table.insert(file_path, request);
+        } else {
+            warn!(
+                "allocate mapped file service stopped, {} is not pre-allocated",
+                file_path
+            );
+        }
+    }
+
+    fn record_wait(&self, wait: Duration, pre_allocated: bool) {
+        let wait_nanos = wait.as_nanos() as u64;
+        self.stats.requests.fetch_add(1, Ordering::Relaxed);
+        if pre_allocated {
+            self.stats.pre_allocated.fetch_add(1, Ordering::Relaxed);
+        }
+        self.stats
+            .total_wait_nanos
+            .fetch_add(wait_nanos, Ordering::Relaxed);
+        self.stats
+            .max_wait_nanos
+            .fetch_max(wait_nanos, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> AllocateMappedFileSnapshot {
+        AllocateMappedFileSnapshot {
+            requests: self.stats.requests.load(Ordering::Relaxed),
+            pre_allocated: self.stats.pre_allocated.load(Ordering::Relaxed),
+            total_wait_nanos: self.stats.total_wait_nanos.load(Ordering::Relaxed),
+            max_wait_nanos: self.stats.max_wait_nanos.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Cancels the queued allocations, waits for the running one and removes the files
+    /// allocated ahead, they hold no data yet.
+    pub fn shutdown(&self) {
+        let pending = std::mem::take(&mut *self.request_table.lock());
+        for (file_path, request) in pending {
+            {
+                let mut state = request.state.lock();
+                if matches!(*state, AllocateState::Queued) {
+                    *state = AllocateState::Cancelled;
+                    continue;
+                }
+            }
+            if let Some(mapped_file) = request.take(WAIT_TIMEOUT) {
+                drop(mapped_file);
+                if let Err(err) = std::fs::remove_file(&file_path) {
+                    warn!("delete pre-allocated file {} failed: {}", file_path, err);
+                }
+            }
+        }
+    }
+}
+
+fn allocate(
+    message_store_config: &MessageStoreConfig,
+    file_path: String,
+    file_size: u64,
+) -> DefaultMappedFile {
+    let mapped_file = DefaultMappedFile::new(file_path, file_size);
+    if message_store_config.warm_mapped_file_enable
+        && file_size >= message_store_config.mapped_file_size_commit_log as u64
+    {
+        mapped_file.warm_mapped_file(
+            message_store_config.flush_disk_type,
+            message_store_config.flush_least_pages_when_warm_mapped_file,
+        );
+    }
+    mapped_file
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+
+    #[test]
+    fn next_next_file_is_pre_allocated() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = |name: &str| temp_dir.path().join(name).to_string_lossy().into_owned();
+        let service = AllocateMappedFileService::new(Arc::new(MessageStoreConfig {
+            warm_mapped_file_enable: true,
+            mapped_file_size_commit_log: 4096 * 4,
+            ..MessageStoreConfig::default()
+        }));
+
+        let first = service
+            .put_request_and_return_mapped_file(
+                path("00000000000000000000"),
+                path("00000000000000016384"),
+                4096 * 4,
+            )
+            .unwrap();
+        assert_eq!(first.get_file_from_offset(), 0);
+
+        let second = service
+            .put_request_and_return_mapped_file(
+                path("00000000000000016384"),
+                path("00000000000000032768"),
+                4096 * 4,
+            )
+            .unwrap();
+        assert_eq!(second.get_file_from_offset(), 16384);
+
+        let snapshot = service.snapshot();
+        assert_eq!(snapshot.requests, 2);
+        assert_eq!(snapshot.pre_allocated, 1);
+        assert!(snapshot.max_wait_nanos >= snapshot.avg_wait_nanos());
+
+        service.shutdown();
+        assert!(!Path::new(&path("00000000000000032768")).exists());
+    }
+}
@@ -17,4 +17,6 @@
 
 pub mod broker_stats;
 pub mod broker_stats_manager;
+pub mod commercial_stats;
+pub mod pull_source_stats;
 pub mod stats_type;
@@ -29,6 +29,11 @@ use rocketmq_common::common::statistics::statistics_manager::StatisticsManager;
 use rocketmq_common::common::stats::moment_stats_item_set::MomentStatsItemSet;
 use rocketmq_common::common::stats::stats_item_set::StatsItemSet;
 use rocketmq_common::common::stats::Stats;
+use rocketmq_common::TimeUtils::get_current_millis;
+
+use crate::stats::commercial_stats::CommercialStats;
+use crate::stats::pull_source_stats::PullSourceStats;
+use crate::stats::stats_type::StatsType;
 
 pub struct BrokerStatsManager {
     stats_table: Arc<parking_lot::RwLock<HashMap<String, StatsItemSet>>>,
@@ -40,6 +45,8 @@ pub struct BrokerStatsManager {
     producer_state_getter: Option<Arc<dyn StateGetter>>,
     consumer_state_getter: Option<Arc<dyn StateGetter>>,
     broker_config: Option<Arc<BrokerConfig>>,
+    commercial_stats: CommercialStats,
+    pull_source_stats: PullSourceStats,
 }
 
 impl BrokerStatsManager {
@@ -112,7 +119,9 @@ impl BrokerStatsManager {
             account_stat_manager: Default::default(),
             producer_state_getter: None,
             consumer_state_getter: None,
+            commercial_stats: CommercialStats::new(broker_config.commercial_stats_retained_minutes),
             broker_config: Some(broker_config),
+            pull_source_stats: PullSourceStats::default(),
         };
         broker_stats_manager.init();
         broker_stats_manager
@@ -137,7 +146,9 @@ impl BrokerStatsManager {
             account_stat_manager: Default::default(),
             producer_state_getter: None,
             consumer_state_getter: None,
+            commercial_stats: CommercialStats::new(broker_config.commercial_stats_retained_minutes),
             broker_config: Some(broker_config),
+            pull_source_stats: PullSourceStats::default(),
         };
         broker_stats_manager.init();
         broker_stats_manager
@@ -484,6 +495,7 @@ impl BrokerStatsManager {
     pub fn inc_group_ck_nums(&self, group: &str, topic: &str, inc_value: i32) {}
 
     pub fn inc_group_ack_nums(&self, group: &str, topic: &str, inc_value: i32) {}
+    pub fn inc_send_back_nums(&self, group: &str, topic: &str) {}
     pub fn inc_broker_get_nums(&self, group: &str, inc_value: i32) {}
     pub fn inc_broker_put_nums(&self, group: &str, inc_value: i32) {}
 
@@ -492,6 +504,42 @@ impl BrokerStatsManager {
     pub fn inc_queue_put_nums(&self, topic: &str, queue_id: i32, num: i32, times: i32) {}
     pub fn inc_queue_put_size(&self, topic: &str, queue_id: i32, size: i32) {}
     pub fn inc_topic_put_latency(&self, topic: &str, queue_id: i32, inc_value: i32) {}
+
+    /// Adds billed messages and bytes of `owner` to the current minute bucket of `topic` and
+    /// `group`.
+    pub fn inc_commercial_value(
+        &self,
+        owner: &str,
+        topic: &str,
+        group: &str,
+        stats_type: StatsType,
+        times: i32,
+        size: i32,
+    ) {
+        self.commercial_stats.record(
+            owner,
+            topic,
+            group,
+            stats_type,
+            times,
+            size,
+            get_current_millis(),
+        );
+    }
+
+    pub fn commercial_stats(&self) -> &CommercialStats {
+        &self.commercial_stats
+    }
+
+    /// Counts a pull of `group` served by this broker, `slave_suggested` when the response
+    /// sends the consumer to a slave for its next pull.
+    pub fn inc_group_pull_source(&self, group: &str, slave_suggested: bool) {
+        self.pull_source_stats.record(group, slave_suggested);
+    }
+
+    pub fn pull_source_stats(&self) -> &PullSourceStats {
+        &self.pull_source_stats
+    }
 }
 
 pub fn create_statistics_kind_meta(
@@ -0,0 +1,179 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::collections::BTreeMap;
+
+use parking_lot::Mutex;
+
+use crate::stats::stats_type::StatsType;
+
+const MINUTE_MILLIS: u64 = 60 * 1000;
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct CommercialStatsKey {
+    minute_timestamp: u64,
+    owner: String,
+    topic: String,
+    group: String,
+    stats_type: &'static str,
+}
+
+/// Billed message count and bytes of one owner, topic, group and stats type within the minute
+/// starting at `minute_timestamp`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CommercialStatsBucket {
+    pub minute_timestamp: u64,
+    pub owner: String,
+    pub topic: String,
+    pub group: String,
+    pub stats_type: String,
+    pub times: i64,
+    pub size: i64,
+}
+
+/// One minute buckets of the commercial send and receive values reported by the broker hooks,
+/// for operators to charge tenants back. Only the last `retained_minutes` are kept.
+pub struct CommercialStats {
+    retained_minutes: u64,
+    buckets: Mutex<BTreeMap<CommercialStatsKey, (i64, i64)>>,
+}
+
+impl CommercialStats {
+    pub fn new(retained_minutes: u64) -> Self {
+        Self {
+            retained_minutes: retained_minutes.max(1),
+            buckets: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    pub fn record(
+        &self,
+        owner: &str,
+        topic: &str,
+        group: &str,
+        stats_type: StatsType,
+        times: i32,
+        size: i32,
+        now_millis: u64,
+    ) {
+        let minute_timestamp = now_millis - now_millis % MINUTE_MILLIS;
+        let key = CommercialStatsKey {
+            minute_timestamp,
+            owner: owner.to_string(),
+            topic: topic.to_string(),
+            group: group.to_string(),
+            stats_type: stats_type.as_str(),
+        };
+        let mut buckets = self.buckets.lock();
+        let value = buckets.entry(key).or_default();
+        value.0 += times as i64;
+        value.1 += size as i64;
+
+        let oldest_retained =
+            minute_timestamp.saturating_sub((self.retained_minutes - 1) * MINUTE_MILLIS);
+        let expired = buckets
+            .first_key_value()
+            .is_some_and(|(key, _)| key.minute_timestamp < oldest_retained);
+        if expired {
+            *buckets = buckets.split_off(&CommercialStatsKey {
+                minute_timestamp: oldest_retained,
+                owner: String::new(),
+                topic: String::new(),
+                group: String::new(),
+                stats_type: "",
+            });
+        }
+    }
+
+    /// Buckets of the minute of `begin_timestamp` and of the later ones, oldest first.
+    pub fn export(&self, begin_timestamp: u64) -> Vec<CommercialStatsBucket> {
+        self.buckets
+            .lock()
+            .iter()
+            .filter(|(key, _)| key.minute_timestamp + MINUTE_MILLIS > begin_timestamp)
+            .map(|(key, (times, size))| CommercialStatsBucket {
+                minute_timestamp: key.minute_timestamp,
+                owner: key.owner.clone(),
+                topic: key.topic.clone(),
+                group: key.group.clone(),
+                stats_type: key.stats_type.to_string(),
+                times: *times,
+                size: *size,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_in_minute_buckets_and_drops_expired_ones() {
+        let stats = CommercialStats::new(2);
+        let minute = 1_700_000_040_000;
+        stats.record(
+            "tenant",
+            "TopicA",
+            "producer",
+            StatsType::SendSuccess,
+            1,
+            100,
+            minute,
+        );
+        stats.record(
+            "tenant",
+            "TopicA",
+            "producer",
+            StatsType::SendSuccess,
+            2,
+            50,
+            minute + 59_999,
+        );
+        stats.record(
+            "tenant",
+            "TopicA",
+            "consumer",
+            StatsType::RcvSuccess,
+            1,
+            100,
+            minute + 60_000,
+        );
+
+        let buckets = stats.export(0);
+        assert_eq!(buckets.len(), 2);
+        assert_eq!(buckets[0].minute_timestamp, minute);
+        assert_eq!((buckets[0].times, buckets[0].size), (3, 150));
+        assert_eq!(buckets[0].stats_type, "SEND_SUCCESS");
+        assert_eq!(buckets[1].group, "consumer");
+        assert_eq!(stats.export(minute + 60_000).len(), 1);
+
+        stats.record(
+            "tenant",
+            "TopicA",
+            "consumer",
+            StatsType::RcvEpolls,
+            1,
+            0,
+            minute + 120_000,
+        );
+        let buckets = stats.export(0);
+        assert_eq!(buckets.len(), 2);
+        assert!(buckets
+            .iter()
+            .all(|bucket| bucket.minute_timestamp > minute));
+    }
+}
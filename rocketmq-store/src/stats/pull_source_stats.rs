@@ -0,0 +1,94 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements.  See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License.  You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::collections::BTreeMap;
+
+use parking_lot::Mutex;
+
+/// Pulls of one consumer group served by this broker, and how many of them were answered with
+/// a suggestion to pull from a slave next because they read cold data.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PullSourceStatsItem {
+    pub group: String,
+    pub pulls: u64,
+    pub slave_suggested_pulls: u64,
+}
+
+/// Per consumer group counters of where pulls are sent, to see which groups read cold data off
+/// the master and whether the slave read fallback takes that load.
+#[derive(Default)]
+pub struct PullSourceStats {
+    groups: Mutex<BTreeMap<String, (u64, u64)>>,
+}
+
+impl PullSourceStats {
+    pub fn record(&self, group: &str, slave_suggested: bool) {
+        let mut groups = self.groups.lock();
+        let counters = match groups.get_mut(group) {
+            Some(counters) => counters,
+            None => groups.entry(group.to_string()).or_default(),
+        };
+        counters.0 += 1;
+        if slave_suggested {
+            counters.1 += 1;
+        }
+    }
+
+    /// Counters of every group pulling since the broker started, ordered by group.
+    pub fn export(&self) -> Vec<PullSourceStatsItem> {
+        self.groups
+            .lock()
+            .iter()
+            .map(
+                |(group, (pulls, slave_suggested_pulls))| PullSourceStatsItem {
+                    group: group.clone(),
+                    pulls: *pulls,
+                    slave_suggested_pulls: *slave_suggested_pulls,
+                },
+            )
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_pulls_per_group() {
+        let stats = PullSourceStats::default();
+        stats.record("group_b", false);
+        stats.record("group_a", true);
+        stats.record("group_a", false);
+        stats.record("group_a", true);
+
+        assert_eq!(
+            stats.export(),
+            vec![
+                PullSourceStatsItem {
+                    group: "group_a".to_string(),
+                    pulls: 3,
+                    slave_suggested_pulls: 2,
+                },
+                PullSourceStatsItem {
+                    group: "group_b".to_string(),
+                    pulls: 1,
+                    slave_suggested_pulls: 0,
+                },
+            ]
+        );
+    }
+}
@@ -30,6 +30,23 @@ pub enum StatsType {
     PermFailure,
 }
 
+impl StatsType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StatsType::SendSuccess => "SEND_SUCCESS",
+            StatsType::SendFailure => "SEND_FAILURE",
+            StatsType::RcvSuccess => "RCV_SUCCESS",
+            StatsType::RcvEpolls => "RCV_EPOLLS",
+            StatsType::SendBack => "SEND_BACK",
+            StatsType::SendBackToDlq => "SEND_BACK_TO_DLQ",
+            StatsType::SendOrder => "SEND_ORDER",
+            StatsType::SendTimer => "SEND_TIMER",
+            StatsType::SendTransaction => "SEND_TRANSACTION",
+            StatsType::PermFailure => "PERM_FAILURE",
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
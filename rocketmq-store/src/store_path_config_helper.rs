@@ -66,6 +66,13 @@ pub fn get_lock_file(root_dir: &str) -> String {
         .into_owned()
 }
 
+pub fn get_corrupted_message_report(root_dir: &str) -> String {
+    PathBuf::from(root_dir)
+        .join("corruptedMessages")
+        .to_string_lossy()
+        .into_owned()
+}
+
 pub fn get_delay_offset_store_path(root_dir: &str) -> String {
     PathBuf::from(root_dir)
         .join("config")
@@ -132,6 +139,13 @@ mod tests {
                 .to_string_lossy()
                 .into_owned()
         );
+        assert_eq!(
+            get_corrupted_message_report(root_dir),
+            PathBuf::from(root_dir)
+                .join("corruptedMessages")
+                .to_string_lossy()
+                .into_owned()
+        );
         assert_eq!(
             get_delay_offset_store_path(root_dir),
             PathBuf::from(root_dir)
@@ -14,7 +14,10 @@
  * See the License for the specific language governing permissions and
  * limitations under the License.
  */
+use std::fs;
+
 use once_cell::sync::Lazy;
+use sysinfo::Disks;
 use sysinfo::System;
 
 pub struct StoreUtil;
@@ -29,4 +32,15 @@ impl StoreUtil {
         let physical_total = sys.total_memory();
         physical_total * 1024 // Convert from kilobytes to bytes
     }
+
+    /// Bytes available on the disk `path` lives on, the disk mounted at the longest prefix of
+    /// the path. `None` when the path or its disk cannot be found.
+    pub fn get_disk_free_space(path: &str) -> Option<u64> {
+        let path = fs::canonicalize(path).ok()?;
+        Disks::new_with_refreshed_list()
+            .iter()
+            .filter(|disk| path.starts_with(disk.mount_point()))
+            .max_by_key(|disk| disk.mount_point().as_os_str().len())
+            .map(|disk| disk.available_space())
+    }
 }